@@ -1,24 +1,59 @@
 //! STREM application.
 //!
 
+use std::cell::OnceCell;
 use std::error::Error;
 use std::fmt;
-use std::fs::File;
-use std::io::{stdin, BufReader};
+use std::fs::{self, File};
+use std::io::{stdin, BufReader, Read};
 use std::path::PathBuf;
+use std::thread;
+use std::time::Duration;
 
 use clap::ArgMatches;
+use serde_json::de::SliceRead;
+use strem::compiler::ir::ops::Vacuity;
+use strem::compiler::Compiler;
+use strem::config::classmap::ClassMap;
+use strem::config::ontology::Ontology;
+use strem::config::predicate::Predicate;
 use strem::config::Configuration;
-use strem::controller::{Controller, Status};
+use strem::controller::deadline::Overrun;
+use strem::controller::metrics::Window;
+use strem::controller::{Controller, PrintCallback, Status};
+use strem::datastream::buffer::Overflow;
+use strem::datastream::gap::Policy;
+use strem::datastream::index::FrameIndex;
+use strem::datastream::io::Format;
+use strem::datastream::roi::Roi;
+use strem::datastream::transform::Transform;
 use strem::datastream::DataStream;
 
+use self::bench::Bencher;
+use self::cache::Cache;
+use self::daemon::Daemon;
+use self::foxglove::Foxglove;
+use self::generate::Generator;
+use self::migrate::Migrator;
 use self::printer::Printer;
+use self::server::{Server, Settings};
 
+mod bench;
+mod cache;
+mod copier;
+mod daemon;
+mod foxglove;
+mod generate;
+mod migrate;
 mod printer;
+mod server;
 
 pub struct App {
     matches: ArgMatches,
     paths: Option<Vec<PathBuf>>,
+
+    /// The pattern read from a file, once read.
+    pattern: OnceCell<String>,
 }
 
 impl App {
@@ -26,6 +61,7 @@ impl App {
         let mut app = Self {
             matches,
             paths: None,
+            pattern: OnceCell::new(),
         };
 
         // Set the positional arguments to search over.
@@ -37,6 +73,20 @@ impl App {
             .get_many("DATASTREAM")
             .map(|p| p.cloned().collect());
 
+        // Shift the pattern into the paths, if read from a file.
+        //
+        // As the pattern is then not given as an argument, the first
+        // positional argument is a data stream instead. The same holds for a
+        // set of queries.
+        if app.matches.contains_id("pattern-file") || app.matches.contains_id("queries") {
+            if let Some(first) = app.matches.get_one::<String>("PATTERN") {
+                let mut paths = vec![PathBuf::from(first)];
+                paths.extend(app.paths.take().unwrap_or_default());
+
+                app.paths = Some(paths);
+            }
+        }
+
         app
     }
 
@@ -45,7 +95,115 @@ impl App {
     /// This method is responsible for selecting what to run with what
     /// [`Configuration`] based on the arguments, options, and (most importantly)
     /// the subcommand(s).
-    pub fn run(&self) -> Result<Status, Box<dyn Error>> {
+    ///
+    /// If a subcommand is run, no [`Status`] is produced.
+    pub fn run(&self) -> Result<Option<Status>, Box<dyn Error>> {
+        match self.matches.subcommand() {
+            Some(("migrate", matches)) => {
+                let paths = matches
+                    .get_many::<PathBuf>("DATASTREAM")
+                    .map(|p| p.cloned().collect());
+
+                Migrator::new(paths).run()?;
+                Ok(None)
+            }
+            Some(("bench", matches)) => {
+                let paths = matches
+                    .get_many::<PathBuf>("DATASTREAM")
+                    .map(|p| p.cloned().collect());
+
+                Bencher::new(
+                    matches.get_one::<String>("PATTERN").unwrap().clone(),
+                    paths,
+                    *matches.get_one::<usize>("iterations").unwrap(),
+                    self::compiler(matches)?,
+                )
+                .run()?;
+
+                Ok(None)
+            }
+            Some(("generate", matches)) => {
+                Generator::new(
+                    matches.get_one::<PathBuf>("SCENARIO").unwrap().clone(),
+                    matches.get_one::<u64>("seed").copied(),
+                )
+                .run()?;
+
+                Ok(None)
+            }
+            Some(("serve", matches)) => {
+                let ontology = match matches.get_one::<PathBuf>("ontology") {
+                    Some(path) => Some(Ontology::load(path)?),
+                    None => None,
+                };
+
+                // Listen for gRPC streams first, if requested.
+                //
+                // The address is bound before serving HTTP (if also requested),
+                // such that a failure to listen is reported rather than lost
+                // on another thread.
+                let grpc = match matches.get_one::<String>("grpc") {
+                    Some(address) => Some(self::grpc(address, matches, ontology.clone())?),
+                    None => None,
+                };
+
+                match matches.get_one::<String>("http") {
+                    Some(address) => {
+                        if let Some(grpc) = grpc {
+                            thread::spawn(move || {
+                                if let Err(e) = grpc() {
+                                    eprintln!("{}", e);
+                                }
+                            });
+                        }
+
+                        Server::new(
+                            address.clone(),
+                            Settings {
+                                root: matches.get_one::<PathBuf>("root").cloned(),
+                                ontology,
+                                vacuity: self::vacuity(matches, "vacuous-truth"),
+                                end: self::vacuity(matches, "vacuous-end"),
+                                strict: matches.get_flag("strict-bindings"),
+                                classmap: self::classmap(matches)?,
+                            },
+                        )
+                        .run()?;
+                    }
+                    None => {
+                        if let Some(grpc) = grpc {
+                            grpc()?;
+                        }
+                    }
+                }
+
+                Ok(None)
+            }
+            Some(("daemon", matches)) => {
+                let ontology = match matches.get_one::<PathBuf>("ontology") {
+                    Some(path) => Some(Ontology::load(path)?),
+                    None => None,
+                };
+
+                Daemon::new(
+                    matches.get_one::<PathBuf>("QUERIES").unwrap().clone(),
+                    matches
+                        .get_many::<String>("SOURCE")
+                        .map(|s| s.cloned().collect()),
+                    matches.get_one("window").copied(),
+                    ontology,
+                    self::compiler(matches)?,
+                )
+                .run()?;
+
+                Ok(None)
+            }
+            _ => self.search().map(Some),
+        }
+    }
+
+    /// Search the data stream(s) with the SpRE pattern.
+    fn search(&self) -> Result<Status, Box<dyn Error>> {
         // Set the default status for running the [`App`].
         //
         // By default, a match is not found. This should only be changed through
@@ -58,14 +216,56 @@ impl App {
         // [`Controller`] as well as the [`Printer`].
         let mut config = self.configure()?;
 
+        // Start the visualization sink, if requested.
+        //
+        // The sink runs alongside the search, and each match is published to
+        // it by the [`Printer`].
+        let sink = self
+            .matches
+            .get_one::<String>("foxglove")
+            .map(|address| Foxglove::start(address))
+            .transpose()?;
+
+        let printer = Printer::new(sink);
+
+        // 0. Read from an MQTT topic.
+        //
+        // If a topic is subscribed to, then each of its messages is searched as
+        // it arrives until the search is stopped.
+        if let Some(url) = self.matches.get_one::<String>("mqtt") {
+            let controller =
+                Controller::new(&config, Some(printer.callback())).reporter(printer.reporter());
+            return controller.run(self::subscribe(url)?);
+        }
+
         // 1. Read from file(s).
         //
         // If a file is supplied, then the input source will be from a file that
         // is loaded, accordingly.
         if let Some(paths) = &self.paths {
+            if let Some(tolerance) = config.align {
+                return self.align(&config, &printer, paths, tolerance);
+            }
+
+            // Set up the [`Cache`] of results, if requested.
+            //
+            // The results of a file depend on all options of the search other
+            // than the files searched (and the cache itself). A ROS bag is
+            // streamed rather than loaded; therefore, it cannot be keyed.
+            let cache = match self.matches.get_one::<PathBuf>("cache") {
+                Some(_) if config.format == Format::Rosbag => {
+                    return Err(Box::new(AppError::from(
+                        "cannot cache the results of a ROS bag",
+                    )))
+                }
+                Some(root) => Some(Cache::new(root.clone(), self.settings()?)),
+                None => None,
+            };
+
             for path in paths {
                 config.datastream = Some(path);
-                let controller = Controller::new(&config, Some(Printer::print));
+                let controller =
+                    Controller::new(&config, Some(printer.callback())).reporter(printer.reporter());
 
                 // Run the controller on the [`DataStream`].
                 //
@@ -76,7 +276,54 @@ impl App {
                     path.display()
                 )))))?;
 
-                let s = controller.run(DataStream::new(BufReader::new(f)))?;
+                let s = if config.format == Format::Rosbag {
+                    controller.run(self::rosbag(BufReader::new(f), config.topic.cloned())?)?
+                } else if let Some(capacity) = config.buffer {
+                    controller.run(DataStream::buffered(
+                        BufReader::new(f),
+                        capacity,
+                        config.overflow,
+                    ))?
+                } else if (config.online || config.chunk.is_some())
+                    && config.format == Format::Stremf
+                {
+                    controller.run(DataStream::new(BufReader::new(f)))?
+                } else {
+                    // Load the complete file into memory.
+                    //
+                    // Offline matching retains all frames regardless, so reading
+                    // the file up front lets strings be borrowed from the buffer
+                    // during import rather than allocated.
+                    let mut buffer = Vec::new();
+                    BufReader::new(f).read_to_end(&mut buffer).or(Err(Box::new(
+                        AppError::from(format!("{}: failed to read file", path.display())),
+                    )))?;
+
+                    // Seek to the starting frame.
+                    //
+                    // If an index is requested, the frames before the starting
+                    // frame are not parsed at all.
+                    let search = |callback: PrintCallback| {
+                        let controller =
+                            Controller::new(&config, Some(callback)).reporter(printer.reporter());
+
+                        if config.format == Format::Coco {
+                            controller.run(DataStream::from_coco(&buffer)?)
+                        } else if config.index {
+                            let index = FrameIndex::open(path, &buffer)?;
+                            let from = config.from.unwrap_or_default();
+
+                            controller.run(DataStream::from_index(&buffer, &index, from)?)
+                        } else {
+                            controller.run(DataStream::from_slice(&buffer))
+                        }
+                    };
+
+                    match &cache {
+                        Some(cache) => cache.run(&buffer, &config, &printer, search)?,
+                        None => search(printer.callback())?,
+                    }
+                };
 
                 // Set the status.
                 //
@@ -84,6 +331,14 @@ impl App {
                 // the [`Controller`] on any of the paths.
                 if matches!(s, Status::MatchFound) {
                     status = Status::MatchFound;
+
+                    // Stop at the first match, if nothing is printed.
+                    //
+                    // Only the existence of a match is needed, so the remaining
+                    // files need not be read.
+                    if config.quiet {
+                        break;
+                    }
                 }
             }
 
@@ -94,32 +349,346 @@ impl App {
         //
         // If no files are provided, then the input source will be from the
         // standard input ("stdin"), accordingly.
-        let controller = Controller::new(&config, Some(Printer::print));
+        let controller =
+            Controller::new(&config, Some(printer.callback())).reporter(printer.reporter());
 
         // Run the controller on the [`DataStream`].
         //
         // This creates a new [`DataStream`] with a source from the standard
         // input ("stdin"), accordingly.
-        status = controller.run(DataStream::new(BufReader::new(stdin().lock())))?;
+        //
+        // If a buffer is requested, the standard input is read ahead of the
+        // search on a separate thread, such that a fast producer (e.g., a
+        // socket) is not blocked on the search until the buffer is full.
+        //
+        // A COCO document cannot be read incrementally; therefore, it is read
+        // in full first.
+        status = match config.buffer {
+            None if config.format == Format::Rosbag => controller.run(self::rosbag(
+                BufReader::new(stdin()),
+                config.topic.cloned(),
+            )?)?,
+            Some(capacity) => controller.run(DataStream::buffered(
+                BufReader::new(stdin()),
+                capacity,
+                config.overflow,
+            ))?,
+            None if config.format == Format::Coco => {
+                let mut buffer = Vec::new();
+                stdin().lock().read_to_end(&mut buffer)?;
+
+                controller.run(DataStream::from_coco(&buffer)?)?
+            }
+            None => controller.run(DataStream::new(BufReader::new(stdin().lock())))?,
+        };
 
         Ok(status)
     }
 
+    /// Search the data stream(s) merged into one, aligned by timestamp.
+    ///
+    /// All files are loaded into memory and merged before searching. As the
+    /// matches span the files, they are reported without a path.
+    fn align(
+        &self,
+        config: &Configuration,
+        printer: &Printer,
+        paths: &[PathBuf],
+        tolerance: f64,
+    ) -> Result<Status, Box<dyn Error>> {
+        let mut buffers = Vec::new();
+
+        for path in paths {
+            let f = File::open(path).or(Err(Box::new(AppError::from(format!(
+                "{}: no such file found",
+                path.display()
+            )))))?;
+
+            let mut buffer = Vec::new();
+            BufReader::new(f)
+                .read_to_end(&mut buffer)
+                .or(Err(Box::new(AppError::from(format!(
+                    "{}: failed to read file",
+                    path.display()
+                )))))?;
+
+            buffers.push(buffer);
+        }
+
+        let sources = buffers.iter().map(|b| &b[..]).collect::<Vec<&[u8]>>();
+        let controller =
+            Controller::new(config, Some(printer.callback())).reporter(printer.reporter());
+
+        controller.run(DataStream::align(&sources, tolerance)?)
+    }
+
+    /// Describe the options of the search that may change its results.
+    ///
+    /// The contents of an ontology (and a class map) are included, such that a
+    /// change to its file is seen even if its path is not.
+    fn settings(&self) -> Result<String, Box<dyn Error>> {
+        let mut settings = Vec::new();
+
+        for id in self.matches.ids().map(|id| id.as_str()) {
+            if matches!(id, "PATTERN" | "DATASTREAM" | "cache") {
+                continue;
+            }
+
+            if let Ok(Some(values)) = self.matches.try_get_raw(id) {
+                settings.push(format!("{}={:?}", id, values.collect::<Vec<_>>()));
+            }
+        }
+
+        for id in ["ontology", "classmap"] {
+            if let Some(path) = self.matches.get_one::<PathBuf>(id) {
+                settings.push(fs::read_to_string(path)?);
+            }
+        }
+
+        Ok(settings.join("\n"))
+    }
+
     /// Create a [`Configuration`] from the CLI arguments.
-    fn configure(&self) -> Result<Configuration, Box<dyn Error>> {
+    fn configure(&self) -> Result<Configuration<'_>, Box<dyn Error>> {
+        // Load the [`Ontology`].
+        //
+        // The mapping is loaded once and shared across all data streams
+        // searched over, accordingly.
+        let ontology = match self.matches.get_one::<PathBuf>("ontology") {
+            Some(path) => Some(Ontology::load(path)?),
+            None => None,
+        };
+
+        let classmap = self::classmap(&self.matches)?;
+
+        // Load the queries, if requested.
+        //
+        // Each query names its own pattern; therefore, no pattern is given.
+        let queries = match self.matches.get_one::<PathBuf>("queries") {
+            Some(path) => Some(daemon::queries(path)?),
+            None => None,
+        };
+
+        // Read the pattern from a file, if requested.
+        //
+        // The pattern is read once and kept, such that it may be borrowed by
+        // the [`Configuration`].
+        let pattern = match self.matches.get_one::<PathBuf>("pattern-file") {
+            _ if queries.is_some() => self.pattern.get_or_init(String::new),
+            Some(path) => {
+                if self.pattern.get().is_none() {
+                    let pattern = fs::read_to_string(path).or(Err(Box::new(AppError::from(
+                        format!("{}: failed to read pattern file", path.display()),
+                    ))))?;
+
+                    let _ = self.pattern.set(pattern.trim().to_string());
+                }
+
+                self.pattern.get().unwrap()
+            }
+            None => self.matches.get_one("PATTERN").unwrap(),
+        };
+
         Ok(Configuration {
-            pattern: self.matches.get_one("PATTERN").unwrap(),
+            pattern,
             datastream: None,
+            format: self
+                .matches
+                .get_one::<String>("format")
+                .map(|f| f.parse::<Format>())
+                .transpose()?
+                .unwrap_or_default(),
+            topic: self.matches.get_one("topic"),
+            queries,
             online: self.matches.get_flag("online"),
+            reload: match self.matches.get_flag("reload") {
+                true => self.matches.get_one("pattern-file"),
+                false => None,
+            },
+            window: self.matches.get_one("window").copied(),
+            chunk: self.matches.get_one("chunk").copied(),
+            stats: self
+                .matches
+                .get_one::<String>("stats")
+                .map(|w| w.parse::<Window>())
+                .transpose()?,
+            deadline: match self.matches.get_one::<f64>("deadline") {
+                Some(ms) if *ms > 0.0 && ms.is_finite() => {
+                    Some(Duration::from_secs_f64(ms / 1000.0))
+                }
+                Some(ms) => {
+                    return Err(Box::new(AppError::from(format!(
+                        "`{}`: expected a positive deadline in milliseconds",
+                        ms
+                    ))))
+                }
+                None => None,
+            },
+            overrun: self
+                .matches
+                .get_one::<String>("overrun")
+                .map(|p| p.parse::<Overrun>())
+                .transpose()?
+                .unwrap_or_default(),
+            buffer: self.matches.get_one("buffer").copied(),
+            overflow: self
+                .matches
+                .get_one::<String>("overflow")
+                .map(|p| p.parse::<Overflow>())
+                .transpose()?
+                .unwrap_or_default(),
+            fuzzy: self.matches.get_one("fuzzy").copied().unwrap_or_default(),
+            vacuity: self::vacuity(&self.matches, "vacuous-truth"),
+            end: self::vacuity(&self.matches, "vacuous-end"),
+            strict: self.matches.get_flag("strict-bindings"),
             channels: self.matches.get_many("channel").map(|c| c.collect()),
+            merge: self.matches.get_flag("merge-channels"),
+            split: self.matches.get_flag("per-channel"),
+            align: self.matches.get_one("align").copied(),
+            nms: self.matches.get_one("nms").copied(),
+            roi: self
+                .matches
+                .get_one::<String>("roi")
+                .map(|r| r.parse::<Roi>())
+                .transpose()?,
             limit: self.matches.get_one("max-count").copied(),
             export: self.matches.get_flag("export"),
+            witness: self.matches.get_flag("witness"),
+            trajectory: self.matches.get_flag("trajectory"),
+            robustness: self.matches.get_flag("robustness"),
+            image_root: self.matches.get_one("image-root"),
+            images: self.matches.get_one("copy-images"),
+            publish: self.matches.get_one::<String>("foxglove").is_some(),
             quiet: self.matches.get_flag("quiet"),
+            trace: self.matches.get_flag("trace"),
+            explain: self.matches.get_flag("explain"),
+            partial: self.matches.get_one("partial").copied(),
+            force: self.matches.get_flag("force-version"),
+            fail_fast: self.matches.get_flag("strict"),
             skip: self.matches.get_one("skip").copied(),
+            from: self.matches.get_one("from").copied(),
+            index: self.matches.get_flag("index"),
+            ontology,
+            classmap,
+            predicates: self
+                .matches
+                .get_many::<String>("where")
+                .map(|p| p.map(|p| Predicate::parse(p)).collect())
+                .transpose()?,
+            transforms: self
+                .matches
+                .get_many::<String>("transform")
+                .map(|t| t.map(|t| Transform::parse(t)).collect())
+                .transpose()?,
+            gaps: self
+                .matches
+                .get_one::<String>("gaps")
+                .map(|p| p.parse::<Policy>())
+                .transpose()?
+                .unwrap_or_default(),
         })
     }
 }
 
+/// The [`Vacuity`] selected by the `flag` of the arguments.
+///
+/// The `--vacuous-truth` flag selects that of universal quantifiers, and
+/// `--vacuous-end` that of temporal operators.
+fn vacuity(matches: &ArgMatches, flag: &str) -> Vacuity {
+    match matches.get_flag(flag) {
+        true => Vacuity::Classical,
+        false => Vacuity::Strict,
+    }
+}
+
+/// Create the [`Compiler`] of patterns of a subcommand from its arguments.
+///
+/// This compiles patterns with the same settings as a search (see
+/// [`Configuration::compiler`]).
+fn compiler(matches: &ArgMatches) -> Result<Compiler, Box<dyn Error>> {
+    Ok(Compiler::new()
+        .vacuity(self::vacuity(matches, "vacuous-truth"))
+        .end(self::vacuity(matches, "vacuous-end"))
+        .strict(matches.get_flag("strict-bindings"))
+        .classmap(self::classmap(matches)?))
+}
+
+/// Load the [`ClassMap`] of the arguments, if any.
+fn classmap(matches: &ArgMatches) -> Result<Option<ClassMap>, Box<dyn Error>> {
+    match matches.get_one::<PathBuf>("classmap") {
+        Some(path) => Ok(Some(ClassMap::load(path)?)),
+        None => Ok(None),
+    }
+}
+
+/// A bound gRPC server, which is run (until stopped) once called.
+type Grpc = Box<dyn FnOnce() -> Result<(), Box<dyn Error>> + Send>;
+
+/// Bind a gRPC server to `address` (see [`strem::server::Server`]).
+#[cfg(feature = "grpc")]
+fn grpc(
+    address: &str,
+    matches: &ArgMatches,
+    ontology: Option<Ontology>,
+) -> Result<Grpc, Box<dyn Error>> {
+    let server = strem::server::Server::bind(
+        address,
+        matches.get_one("window").copied(),
+        ontology,
+        self::compiler(matches)?,
+    )?;
+
+    Ok(Box::new(move || server.run()))
+}
+
+/// Bind a gRPC server to `address`.
+///
+/// This build has no gRPC server (i.e., without the `grpc` feature).
+#[cfg(not(feature = "grpc"))]
+fn grpc(_: &str, _: &ArgMatches, _: Option<Ontology>) -> Result<Grpc, Box<dyn Error>> {
+    Err(Box::new(AppError::from(
+        "cannot serve gRPC (enable the `grpc` feature)",
+    )))
+}
+
+/// Subscribe to the MQTT topic of `url`.
+#[cfg(feature = "mqtt")]
+fn subscribe(url: &str) -> Result<DataStream<'static, SliceRead<'static>>, Box<dyn Error>> {
+    DataStream::subscribe(url)
+}
+
+/// Subscribe to the MQTT topic of `url`.
+///
+/// This build has no MQTT client (i.e., without the `mqtt` feature).
+#[cfg(not(feature = "mqtt"))]
+fn subscribe(_: &str) -> Result<DataStream<'static, SliceRead<'static>>, Box<dyn Error>> {
+    Err(Box::new(AppError::from(
+        "cannot subscribe to MQTT (enable the `mqtt` feature)",
+    )))
+}
+
+/// Read the detections of the `topic` of a ROS bag.
+#[cfg(feature = "rosbag")]
+fn rosbag<R: Read + Send + 'static>(
+    source: R,
+    topic: Option<String>,
+) -> Result<DataStream<'static, SliceRead<'static>>, Box<dyn Error>> {
+    DataStream::from_rosbag(source, topic)
+}
+
+/// Read the detections of the `topic` of a ROS bag.
+///
+/// This build cannot read ROS bags (i.e., without the `rosbag` feature).
+#[cfg(not(feature = "rosbag"))]
+fn rosbag<R: Read + Send + 'static>(
+    _: R,
+    _: Option<String>,
+) -> Result<DataStream<'static, SliceRead<'static>>, Box<dyn Error>> {
+    Err(Box::new(AppError::from(
+        "cannot read ROS bags (enable the `rosbag` feature)",
+    )))
+}
+
 #[derive(Debug, Clone)]
 struct AppError {
     msg: String,
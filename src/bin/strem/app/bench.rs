@@ -0,0 +1,237 @@
+//! Application benchmarker.
+//!
+
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{stdin, BufReader, Read};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use strem::compiler::Compiler;
+use strem::config::Configuration;
+use strem::datastream::frame::Frame;
+use strem::datastream::io::importer::Importer;
+use strem::datastream::DataStream;
+use strem::matcher::offline;
+use strem::monitor::Monitor;
+
+/// Measure the time spent in each stage of searching.
+///
+/// The pattern is searched over the input for a number of iterations, and the
+/// time of each stage is summarized across all iterations. The input is read
+/// into memory once beforehand, so reading from disk is not measured.
+pub struct Bencher {
+    pattern: String,
+    paths: Option<Vec<PathBuf>>,
+    iterations: usize,
+
+    /// The [`Compiler`] of the pattern.
+    compiler: Compiler,
+}
+
+impl Bencher {
+    /// Create a new [`Bencher`].
+    ///
+    /// If no paths are provided, standard input ("stdin") is assumed.
+    pub fn new(
+        pattern: String,
+        paths: Option<Vec<PathBuf>>,
+        iterations: usize,
+        compiler: Compiler,
+    ) -> Self {
+        Bencher {
+            pattern,
+            paths,
+            iterations,
+            compiler,
+        }
+    }
+
+    /// Run the [`Bencher`].
+    pub fn run(&self) -> Result<(), Box<dyn Error>> {
+        if self.iterations == 0 {
+            return Err(Box::new(BencherError::from(
+                "number of iterations must be positive",
+            )));
+        }
+
+        let buffers = self.load()?;
+        let config = Configuration::new(&self.pattern);
+
+        let mut compile = Vec::new();
+        let mut import = Vec::new();
+        let mut monitor = Vec::new();
+        let mut search = Vec::new();
+        let mut count = 0;
+
+        for _ in 0..self.iterations {
+            // 1. Compile the pattern.
+            let start = Instant::now();
+            let ast = self.compiler.compile(&self.pattern)?;
+            compile.push(start.elapsed());
+
+            // 2. Import all frames.
+            let start = Instant::now();
+            let mut frames = Vec::new();
+
+            for buffer in buffers.iter() {
+                let mut importer = Importer::new(&config);
+                let mut datastream = DataStream::from_slice(buffer);
+
+                while let Some(f) = datastream.request(&mut importer)? {
+                    frames.extend(f);
+                }
+            }
+
+            import.push(start.elapsed());
+
+            // 3. Evaluate each spatial formula against each frame.
+            //
+            // This is reported per frame, as it is the cost of a single step of
+            // the matcher.
+            let start = Instant::now();
+            let m = Monitor::new();
+
+            for frame in frames.iter() {
+                for formula in ast.fmap() {
+                    m.evaluate(frame, &formula.formula)?;
+                }
+            }
+
+            monitor.push(start.elapsed() / frames.len().max(1) as u32);
+
+            // 4. Search for all matches.
+            let start = Instant::now();
+            count = Self::search(&offline::Matcher::try_from(&ast)?, &frames)?;
+            search.push(start.elapsed());
+        }
+
+        println!("iterations: {}, matches: {}", self.iterations, count);
+        println!(
+            "{:<16}{:>14}{:>14}{:>14}{:>14}",
+            "stage", "mean", "stddev", "min", "max"
+        );
+
+        for (stage, samples) in [
+            ("compile", &compile),
+            ("import", &import),
+            ("monitor/frame", &monitor),
+            ("match", &search),
+        ] {
+            println!("{:<16}{}", stage, Summary::from(samples.as_slice()));
+        }
+
+        Ok(())
+    }
+
+    /// Read each input into memory.
+    fn load(&self) -> Result<Vec<Vec<u8>>, Box<dyn Error>> {
+        let mut buffers = Vec::new();
+
+        if let Some(paths) = &self.paths {
+            for path in paths {
+                let f = File::open(path).or(Err(Box::new(BencherError::from(format!(
+                    "{}: no such file found",
+                    path.display()
+                )))))?;
+
+                let mut buffer = Vec::new();
+                BufReader::new(f).read_to_end(&mut buffer)?;
+                buffers.push(buffer);
+            }
+
+            return Ok(buffers);
+        }
+
+        let mut buffer = Vec::new();
+        stdin().lock().read_to_end(&mut buffer)?;
+        buffers.push(buffer);
+
+        Ok(buffers)
+    }
+
+    /// Count all non-overlapping leftmost matches.
+    ///
+    /// This mirrors the search performed by the offline controller without
+    /// producing any output.
+    fn search(matcher: &offline::Matcher, frames: &[Frame]) -> Result<usize, Box<dyn Error>> {
+        let bitmap = matcher.evaluate(frames)?;
+
+        let mut count = 0;
+        let mut offset = 0;
+
+        while offset < frames.len() {
+            if let Some(m) = matcher.leftmost_at(&bitmap, offset)? {
+                count += 1;
+                offset += m.end;
+                continue;
+            }
+
+            offset += 1;
+        }
+
+        Ok(count)
+    }
+}
+
+/// Summary statistics of a set of timings.
+struct Summary {
+    mean: f64,
+    stddev: f64,
+    min: f64,
+    max: f64,
+}
+
+impl From<&[Duration]> for Summary {
+    fn from(samples: &[Duration]) -> Self {
+        let secs: Vec<f64> = samples.iter().map(|d| d.as_secs_f64()).collect();
+
+        let mean = secs.iter().sum::<f64>() / secs.len() as f64;
+        let variance = secs.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / secs.len() as f64;
+
+        Summary {
+            mean,
+            stddev: variance.sqrt(),
+            min: secs.iter().copied().fold(f64::INFINITY, f64::min),
+            max: secs.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for value in [self.mean, self.stddev, self.min, self.max] {
+            write!(f, "{:>14}", format!("{:.3}µs", value * 1e6))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct BencherError {
+    msg: String,
+}
+
+impl From<&str> for BencherError {
+    fn from(msg: &str) -> Self {
+        BencherError {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for BencherError {
+    fn from(msg: String) -> Self {
+        BencherError { msg }
+    }
+}
+
+impl fmt::Display for BencherError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "bencher: {}", self.msg)
+    }
+}
+
+impl Error for BencherError {}
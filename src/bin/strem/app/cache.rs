@@ -0,0 +1,294 @@
+//! Application cache.
+//!
+
+use std::error::Error;
+use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
+use std::io::{BufReader, BufWriter};
+use std::ops::ControlFlow;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use strem::config::Configuration;
+use strem::controller::{MatchContext, PrintCallback, Status};
+use strem::hash::Fnv;
+
+use super::printer::Printer;
+
+/// A match stored in the [`Cache`].
+#[derive(Serialize, Deserialize)]
+struct Entry {
+    start: usize,
+    end: usize,
+
+    /// The time of the match, if given (see [`MatchContext::time`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    time: Option<(f64, f64)>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    channel: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    query: Option<String>,
+
+    pattern: String,
+
+    /// The robustness of the match, if computed.
+    ///
+    /// This is stored as text, as JSON cannot represent an infinite score.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    score: Option<String>,
+}
+
+/// The results of a search stored in the [`Cache`].
+#[derive(Serialize, Deserialize)]
+struct Record {
+    /// The version of STREM that searched.
+    version: String,
+
+    /// Whether a match was found.
+    ///
+    /// A match may be found but not handled (e.g., with a limit of zero);
+    /// therefore, this is stored apart from the matches.
+    found: bool,
+
+    matches: Vec<Entry>,
+}
+
+/// A cache of the results of searching files.
+///
+/// The results of searching a file are stored in a directory, keyed by the
+/// contents of the file, the pattern, the options of the search, and the
+/// version of STREM. A repeated search of an unchanged file then replays the
+/// stored matches instead of importing and searching it again.
+pub struct Cache {
+    root: PathBuf,
+
+    /// The options of the search that may change its results.
+    settings: String,
+}
+
+impl Cache {
+    /// Create a new [`Cache`] stored in the directory at `root`.
+    pub fn new(root: PathBuf, settings: String) -> Self {
+        Cache { root, settings }
+    }
+
+    /// Search the contents of a file, unless its results are already stored.
+    ///
+    /// The `search` is run with the callback of each match, which prints it
+    /// with `printer` (as does replaying stored results). If the results are
+    /// not stored, they are stored once the search completes; a failure to
+    /// store them is only reported, as the search itself succeeded.
+    pub fn run<F>(
+        &self,
+        buffer: &[u8],
+        config: &Configuration,
+        printer: &Printer,
+        search: F,
+    ) -> Result<Status, Box<dyn Error>>
+    where
+        F: FnOnce(PrintCallback) -> Result<Status, Box<dyn Error>>,
+    {
+        let path = self.root.join(format!("{}.json", self.key(buffer, config)));
+
+        if let Some(record) = Self::load(&path) {
+            return Self::replay(record, config, printer);
+        }
+
+        let mut matches = Vec::new();
+
+        let status = search(Box::new(
+            |context: &MatchContext, config: &Configuration| {
+                Self::record(context, config, printer, &mut matches)
+            },
+        ))?;
+
+        let record = Record {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            found: matches!(status, Status::MatchFound),
+            matches,
+        };
+
+        if let Err(e) = self.store(&path, &record) {
+            eprintln!(
+                "cache: warning: {}: failed to store results ({})",
+                path.display(),
+                e
+            );
+        }
+
+        Ok(status)
+    }
+
+    /// The key of the results of searching `buffer`.
+    ///
+    /// The key names a file that outlives the process; therefore, it is hashed
+    /// with [`Fnv`], which (unlike the hasher of the standard library) is the
+    /// same across builds and platforms.
+    fn key(&self, buffer: &[u8], config: &Configuration) -> String {
+        let mut contents = Fnv::new();
+        contents.write(buffer);
+
+        let mut search = Fnv::new();
+        env!("CARGO_PKG_VERSION").hash(&mut search);
+        config.pattern.hash(&mut search);
+        config.queries.hash(&mut search);
+        self.settings.hash(&mut search);
+
+        format!("{:016x}{:016x}", contents.finish(), search.finish())
+    }
+
+    /// Load the stored results at `path`, if any.
+    ///
+    /// Results that cannot be read (e.g., of another version) are treated as
+    /// not stored, such that they are replaced.
+    fn load(path: &PathBuf) -> Option<Record> {
+        let f = File::open(path).ok()?;
+        let record: Record = serde_json::from_reader(BufReader::new(f)).ok()?;
+
+        (record.version == env!("CARGO_PKG_VERSION")).then_some(record)
+    }
+
+    /// Store the results of a search at `path`.
+    fn store(&self, path: &PathBuf, record: &Record) -> Result<(), Box<dyn Error>> {
+        fs::create_dir_all(&self.root)?;
+        serde_json::to_writer(BufWriter::new(File::create(path)?), record)?;
+
+        Ok(())
+    }
+
+    /// Print the stored matches of a search.
+    fn replay(
+        record: Record,
+        config: &Configuration,
+        printer: &Printer,
+    ) -> Result<Status, Box<dyn Error>> {
+        for entry in record.matches.iter() {
+            let context = MatchContext {
+                frames: &[],
+                range: entry.start..entry.end,
+                time: entry.time.map(|(start, end)| start..end),
+                path: config.datastream.map(|p| p.as_path()),
+                channel: entry.channel.as_deref(),
+                query: entry.query.as_deref(),
+                pattern: &entry.pattern,
+                witnesses: None,
+                score: entry.score.as_ref().and_then(|s| s.parse().ok()),
+            };
+
+            if printer.print(&context, config)?.is_break() {
+                break;
+            }
+        }
+
+        match record.found {
+            true => Ok(Status::MatchFound),
+            false => Ok(Status::MatchNotFound),
+        }
+    }
+
+    /// Print a match of a search and record it into `matches`.
+    fn record(
+        context: &MatchContext,
+        config: &Configuration,
+        printer: &Printer,
+        matches: &mut Vec<Entry>,
+    ) -> Result<ControlFlow<()>, Box<dyn Error>> {
+        matches.push(Entry {
+            start: context.range.start,
+            end: context.range.end,
+            time: context.time.as_ref().map(|time| (time.start, time.end)),
+            channel: context.channel.map(String::from),
+            query: context.query.map(String::from),
+            pattern: context.pattern.to_string(),
+            score: context.score.map(|s| s.to_string()),
+        });
+
+        printer.print(context, config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::ops::ControlFlow;
+
+    use strem::config::Configuration;
+    use strem::controller::{MatchContext, Status};
+
+    use super::super::printer::Printer;
+    use super::Cache;
+
+    #[test]
+    fn key() {
+        let pattern = String::from("[[:car:]]");
+        let config = Configuration::new(&pattern);
+        let cache = Cache::new(std::env::temp_dir(), String::from("--limit 1"));
+
+        // The key is the same for every run (and build) of the same search.
+        let key = cache.key(b"{}", &config);
+        assert_eq!(key, cache.key(b"{}", &config));
+        assert_eq!(&key[..16], "08f44b07b5901a25");
+
+        // Otherwise, it differs.
+        assert_ne!(key, cache.key(b"[]", &config));
+        assert_ne!(
+            key,
+            Cache::new(std::env::temp_dir(), String::new()).key(b"{}", &config)
+        );
+
+        let pattern = String::from("[[:pedestrian:]]");
+        assert_ne!(key, cache.key(b"{}", &Configuration::new(&pattern)));
+    }
+
+    #[test]
+    fn run() {
+        let root = std::env::temp_dir().join(format!("strem-cache-{}", std::process::id()));
+        let cache = Cache::new(root.clone(), String::new());
+
+        let pattern = String::from("[[:car:]]");
+        let config = Configuration::new(&pattern);
+        let printer = Printer::new(None);
+
+        // Search, recording a single match, and count the searches run.
+        let searches = Cell::new(0);
+        let run = |buffer: &[u8]| {
+            cache
+                .run(buffer, &config, &printer, |mut callback| {
+                    searches.set(searches.get() + 1);
+
+                    let context = MatchContext {
+                        frames: &[],
+                        range: 0..1,
+                        time: None,
+                        path: None,
+                        channel: None,
+                        query: None,
+                        pattern: &pattern,
+                        witnesses: None,
+                        score: None,
+                    };
+                    assert_eq!(
+                        callback(&context, &config).unwrap(),
+                        ControlFlow::Continue(())
+                    );
+
+                    Ok(Status::MatchFound)
+                })
+                .unwrap()
+        };
+
+        // A miss, a hit of the same contents, and a miss of changed contents.
+        assert!(matches!(run(b"first"), Status::MatchFound));
+        assert_eq!(searches.get(), 1);
+
+        assert!(matches!(run(b"first"), Status::MatchFound));
+        assert_eq!(searches.get(), 1);
+
+        assert!(matches!(run(b"second"), Status::MatchFound));
+        assert_eq!(searches.get(), 2);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
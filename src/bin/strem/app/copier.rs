@@ -0,0 +1,290 @@
+//! Application image copier.
+//!
+
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use serde_json::json;
+use strem::controller::MatchContext;
+use strem::datastream::frame::sample::detections::ImageSource;
+use strem::datastream::frame::sample::Sample;
+
+/// The name of the manifest of each match.
+const MANIFEST: &str = "index.json";
+
+/// Copy the images of each match to a directory.
+///
+/// For each match, a folder is created within the directory that holds the
+/// image of every sample of its frames, along with a manifest (i.e.,
+/// `index.json`) that describes where the match and each image came from.
+pub struct Copier {
+    /// The number of matches copied so far.
+    ///
+    /// Each match is copied into a folder named by its number, such that the
+    /// folders are listed in the order the matches were found.
+    count: AtomicUsize,
+}
+
+impl Copier {
+    /// Create a new [`Copier`] that has copied no matches.
+    pub fn new() -> Self {
+        Self {
+            count: AtomicUsize::new(0),
+        }
+    }
+
+    /// Copy the images of a [`Match`] into a new folder of `dir`.
+    ///
+    /// An image that cannot be copied (e.g., it does not exist) is skipped
+    /// with a warning, and is recorded as such in the manifest. An error is
+    /// only returned if the folder or its manifest cannot be written.
+    pub fn copy(&self, context: &MatchContext, dir: &Path) -> Result<(), Box<dyn Error>> {
+        let n = self.count.fetch_add(1, Ordering::Relaxed) + 1;
+        let folder = dir.join(format!("{:06}", n));
+
+        fs::create_dir_all(&folder).map_err(|e| {
+            CopierError::from(format!(
+                "{}: unable to create folder: {}",
+                folder.display(),
+                e
+            ))
+        })?;
+
+        let mut frames = Vec::new();
+
+        for frame in context.frames.iter() {
+            let mut images = Vec::new();
+
+            for (channel, image) in frame
+                .samples
+                .iter()
+                .filter_map(|s| match s {
+                    Sample::ObjectDetection(record) | Sample::Text(record) => Some(record),
+                    _ => None,
+                })
+                .flat_map(|record| record.images())
+            {
+                let stem = format!("{:06}_{}", frame.index, sanitize(channel));
+
+                // Copy (or write) the image into the folder.
+                //
+                // Embedded images have no file to copy from; therefore, the
+                // extension is taken from the contents of the image instead.
+                let (source, copied) = match &image.source {
+                    ImageSource::File(path) => {
+                        let name = match path.extension() {
+                            Some(ext) => format!("{}.{}", stem, ext.to_string_lossy()),
+                            None => stem,
+                        };
+
+                        let copied = fs::copy(path, folder.join(&name)).map(|_| name);
+                        (Some(path.display().to_string()), copied)
+                    }
+                    ImageSource::Embedded(bytes) => {
+                        let name = format!("{}.{}", stem, extension(bytes));
+
+                        let copied = fs::write(folder.join(&name), bytes).map(|_| name);
+                        (None, copied)
+                    }
+                };
+
+                let file = match copied {
+                    Ok(name) => Some(name),
+                    Err(e) => {
+                        eprintln!(
+                            "copier: warning: frame {}: {}: {} (skipped)",
+                            frame.index,
+                            source.as_deref().unwrap_or("embedded image"),
+                            e
+                        );
+
+                        None
+                    }
+                };
+
+                images.push(json!({
+                    "channel": channel,
+                    "source": source,
+                    "file": file,
+                }));
+            }
+
+            frames.push(json!({
+                "index": frame.index,
+                "timestamp": frame.timestamp,
+                "images": images,
+            }));
+        }
+
+        let manifest = json!({
+            "query": context.query,
+            "path": context.path.map(|p| p.display().to_string()),
+            "channel": context.channel,
+            "start": context.range.start,
+            "end": context.range.end,
+            "frames": frames,
+        });
+
+        fs::write(
+            folder.join(MANIFEST),
+            serde_json::to_string_pretty(&manifest)?,
+        )
+        .map_err(|e| {
+            CopierError::from(format!(
+                "{}: unable to write manifest: {}",
+                folder.display(),
+                e
+            ))
+        })?;
+
+        Ok(())
+    }
+}
+
+/// Replace the characters of a channel that are unsafe within a file name.
+fn sanitize(channel: &str) -> String {
+    channel
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '.' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+/// Find the extension of an encoded image from its contents.
+fn extension(bytes: &[u8]) -> &'static str {
+    match bytes {
+        [0x89, b'P', b'N', b'G', ..] => "png",
+        [0xFF, 0xD8, ..] => "jpg",
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => "webp",
+        _ => "bin",
+    }
+}
+
+#[derive(Debug, Clone)]
+struct CopierError {
+    msg: String,
+}
+
+impl From<&str> for CopierError {
+    fn from(msg: &str) -> Self {
+        CopierError {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for CopierError {
+    fn from(msg: String) -> Self {
+        CopierError { msg }
+    }
+}
+
+impl fmt::Display for CopierError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "copier: {}", self.msg)
+    }
+}
+
+impl Error for CopierError {}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use serde_json::Value;
+    use strem::controller::MatchContext;
+    use strem::datastream::frame::sample::detections::{DetectionRecord, Image, ImageSource};
+    use strem::datastream::frame::sample::Sample;
+    use strem::datastream::frame::Frame;
+
+    use super::{Copier, MANIFEST};
+
+    #[test]
+    fn copy() {
+        let dir = std::env::temp_dir().join(format!("strem-copier-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let png = dir.join("source.png");
+        fs::write(&png, [0x89, b'P', b'N', b'G']).unwrap();
+
+        let frames = [
+            (ImageSource::File(png.clone()), "cam/front"),
+            (ImageSource::Embedded(vec![0xFF, 0xD8]), "cam/back"),
+            (ImageSource::File(dir.join("missing.png")), "cam/front"),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, (source, channel))| {
+            let mut frame = Frame::new(i);
+            frame
+                .samples
+                .push(Sample::ObjectDetection(DetectionRecord::new(
+                    String::from(channel),
+                    Some(Image::new(source, 10, 10)),
+                )));
+
+            frame
+        })
+        .collect::<Vec<Frame>>();
+
+        let context = MatchContext {
+            frames: &frames,
+            range: 0..3,
+            time: None,
+            path: None,
+            channel: None,
+            query: None,
+            pattern: "[[:car:]]",
+            witnesses: None,
+            score: None,
+        };
+
+        let copier = Copier::new();
+        copier.copy(&context, &dir).unwrap();
+        copier.copy(&context, &dir).unwrap();
+
+        // Each match is copied into its own folder, numbered in order.
+        for folder in ["000001", "000002"] {
+            let folder = dir.join(folder);
+
+            assert!(folder.join("000000_cam_front.png").is_file());
+            assert!(folder.join("000001_cam_back.jpg").is_file());
+            assert!(!folder.join("000002_cam_front.png").exists());
+
+            let manifest: Value =
+                serde_json::from_str(&fs::read_to_string(folder.join(MANIFEST)).unwrap()).unwrap();
+
+            assert_eq!(manifest["start"], 0);
+            assert_eq!(manifest["end"], 3);
+
+            let files = manifest["frames"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|f| f["images"][0]["file"].clone())
+                .collect::<Vec<Value>>();
+
+            assert_eq!(
+                files,
+                [
+                    Value::from("000000_cam_front.png"),
+                    Value::from("000001_cam_back.jpg"),
+                    Value::Null,
+                ]
+            );
+            assert_eq!(
+                manifest["frames"][0]["images"][0]["source"],
+                Value::from(png.display().to_string())
+            );
+            assert_eq!(manifest["frames"][1]["images"][0]["source"], Value::Null);
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,238 @@
+//! Application daemon.
+//!
+
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{stdin, BufReader, Read};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+use std::thread;
+
+use serde::Deserialize;
+use serde_json::json;
+use strem::compiler::Compiler;
+use strem::config::ontology::Ontology;
+use strem::config::Configuration;
+use strem::controller::daemon::{Query, Session};
+use strem::datastream::io::importer::Importer;
+use strem::datastream::DataStream;
+
+/// A query of the queries file.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Spec {
+    id: String,
+    pattern: String,
+}
+
+/// Search several live sources with several persistent queries.
+///
+/// All queries are compiled once, and each source is searched on its own
+/// thread as its frames arrive. Each match is printed to standard output as a
+/// JSON object on its own line with the query and source it was found by.
+pub struct Daemon {
+    /// The file of the queries to search with.
+    queries: PathBuf,
+
+    /// The sources to search, where `-` is standard input ("stdin") and
+    /// `tcp://HOST:PORT` is a connection to a server.
+    sources: Vec<String>,
+
+    /// The maximum number of frames kept for an unbounded query.
+    window: Option<usize>,
+
+    /// The [`Ontology`] applied to each source, if any.
+    ontology: Option<Ontology>,
+
+    /// The [`Compiler`] of the queries.
+    compiler: Compiler,
+}
+
+impl Daemon {
+    /// Create a new [`Daemon`].
+    ///
+    /// If no sources are provided, standard input ("stdin") is assumed.
+    pub fn new(
+        queries: PathBuf,
+        sources: Option<Vec<String>>,
+        window: Option<usize>,
+        ontology: Option<Ontology>,
+        compiler: Compiler,
+    ) -> Self {
+        Daemon {
+            queries,
+            sources: sources.unwrap_or_else(|| vec![String::from("-")]),
+            window,
+            ontology,
+            compiler,
+        }
+    }
+
+    /// Run the [`Daemon`] until all sources end.
+    ///
+    /// A source that fails (e.g., is malformed) is reported and stops, while
+    /// the other sources continue.
+    pub fn run(&self) -> Result<(), Box<dyn Error>> {
+        let queries = self.load()?;
+
+        // Create the [`Configuration`] of each source.
+        //
+        // The importer does not depend on a pattern; the patterns of the
+        // queries are compiled separately.
+        let pattern = String::new();
+        let mut config = Configuration::new(&pattern);
+        config.online = true;
+        config.window = self.window;
+        config.ontology = self.ontology.clone();
+
+        let failed = thread::scope(|scope| {
+            let handles = self
+                .sources
+                .iter()
+                .map(|source| {
+                    let (queries, config) = (&queries, &config);
+
+                    scope.spawn(move || match Self::watch(source, queries, config) {
+                        Ok(()) => false,
+                        Err(e) => {
+                            eprintln!("daemon: warning: {}: {}", source, e);
+                            true
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .map(|h| h.join().unwrap_or(true))
+                .filter(|failed| *failed)
+                .count()
+        });
+
+        if failed == self.sources.len() {
+            return Err(Box::new(DaemonError::from("all sources failed")));
+        }
+
+        Ok(())
+    }
+
+    /// Load and compile the queries.
+    fn load(&self) -> Result<Vec<Query>, Box<dyn Error>> {
+        let mut queries = Vec::new();
+
+        for (id, pattern) in self::queries(&self.queries)? {
+            let query = Query::compile(&id, &pattern, &self.compiler, self.window)
+                .map_err(|e| DaemonError::from(format!("query `{}`: {}", id, e)))?;
+
+            if query.capacity().is_none() {
+                eprintln!(
+                    "daemon: warning: query `{}` is unbounded and keeps all frames (use `--window` to bound)",
+                    id
+                );
+            }
+
+            queries.push(query);
+        }
+
+        Ok(queries)
+    }
+
+    /// Search a single source with all queries.
+    fn watch(
+        source: &str,
+        queries: &[Query],
+        config: &Configuration,
+    ) -> Result<(), Box<dyn Error>> {
+        let reader: Box<dyn Read> = match source {
+            "-" => Box::new(stdin()),
+            _ => match source.strip_prefix("tcp://") {
+                Some(address) => Box::new(TcpStream::connect(address)?),
+                None => Box::new(File::open(source)?),
+            },
+        };
+
+        let mut datastream = DataStream::new(BufReader::new(reader));
+        let mut importer = Importer::new(config);
+        let mut session = Session::new(queries)?;
+
+        while let Some(frames) = datastream.request(&mut importer)? {
+            for frame in frames {
+                for event in session.push(frame)? {
+                    println!(
+                        "{}",
+                        json!({
+                            "query": event.query,
+                            "source": source,
+                            "start": event.range.start,
+                            "end": event.range.end,
+                        })
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Load the identifier and pattern of each query of the queries file.
+///
+/// The identifier of each query must be unique.
+pub fn queries(path: &Path) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+    let f = File::open(path).or(Err(Box::new(DaemonError::from(format!(
+        "{}: no such file found",
+        path.display()
+    )))))?;
+
+    let specs: Vec<Spec> = serde_json::from_reader(BufReader::new(f))
+        .map_err(|e| DaemonError::from(format!("{}: {}", path.display(), e)))?;
+
+    if specs.is_empty() {
+        return Err(Box::new(DaemonError::from(format!(
+            "{}: no queries found",
+            path.display()
+        ))));
+    }
+
+    let mut ids = HashSet::new();
+
+    for spec in specs.iter() {
+        if !ids.insert(&spec.id) {
+            return Err(Box::new(DaemonError::from(format!(
+                "duplicate query `{}`",
+                spec.id
+            ))));
+        }
+    }
+
+    Ok(specs.into_iter().map(|s| (s.id, s.pattern)).collect())
+}
+
+#[derive(Debug, Clone)]
+struct DaemonError {
+    msg: String,
+}
+
+impl From<&str> for DaemonError {
+    fn from(msg: &str) -> Self {
+        DaemonError {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for DaemonError {
+    fn from(msg: String) -> Self {
+        DaemonError { msg }
+    }
+}
+
+impl fmt::Display for DaemonError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "daemon: {}", self.msg)
+    }
+}
+
+impl Error for DaemonError {}
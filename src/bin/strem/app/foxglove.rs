@@ -0,0 +1,730 @@
+//! Application visualization sink.
+//!
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use strem::controller::MatchContext;
+use strem::datastream::frame::sample::detections::{DetectionRecord, Image, ImageSource};
+use strem::datastream::frame::sample::Sample;
+use strem::datastream::frame::Frame;
+use strem::datastream::io::base64;
+
+/// The subprotocol of the WebSocket connections.
+const SUBPROTOCOL: &str = "foxglove.websocket.v1";
+
+/// The GUID appended to the key of a WebSocket handshake (RFC 6455).
+const GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// The largest message accepted from a client (i.e., 1 MiB).
+const MAX_MESSAGE: u64 = 1024 * 1024;
+
+/// The largest handshake accepted from a client (i.e., 8 KiB).
+const MAX_HANDSHAKE: u64 = 8 * 1024;
+
+/// The longest time a client may take to send its handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The longest time a message may take to be sent to a client.
+///
+/// A client that cannot keep up is disconnected, rather than stalling the
+/// search.
+const TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The channels advertised to each client, as `(id, topic, schema)`.
+const CHANNELS: [(u32, &str, &str); 3] = [
+    (MATCHES, "/strem/matches", "strem.Match"),
+    (
+        ANNOTATIONS,
+        "/strem/annotations",
+        "foxglove.ImageAnnotations",
+    ),
+    (IMAGES, "/strem/image", "foxglove.CompressedImage"),
+];
+
+/// The channel of the intervals of the matches.
+const MATCHES: u32 = 0;
+
+/// The channel of the bounding boxes of the matched frames.
+const ANNOTATIONS: u32 = 1;
+
+/// The channel of the images of the matched frames.
+const IMAGES: u32 = 2;
+
+/// A client connected to the [`Foxglove`] sink.
+struct Client {
+    stream: TcpStream,
+
+    /// The channel of each subscription of the client, by its identifier.
+    subscriptions: HashMap<u32, u32>,
+}
+
+/// Stream the matches of a search to Foxglove Studio.
+///
+/// This runs a WebSocket server of the Foxglove WebSocket protocol, such that
+/// Foxglove Studio can connect to it live. For each match, the interval is
+/// published to `/strem/matches`, and the bounding boxes and images of its
+/// frames to `/strem/annotations` and `/strem/image`, respectively.
+pub struct Foxglove {
+    clients: Mutex<HashMap<usize, Client>>,
+
+    /// The identifier of the next client.
+    next: AtomicUsize,
+}
+
+impl Foxglove {
+    /// Start the [`Foxglove`] sink listening on `address`.
+    ///
+    /// Each client is served on its own thread until the process exits.
+    pub fn start(address: &str) -> Result<Arc<Self>, Box<dyn Error>> {
+        let listener = TcpListener::bind(address)
+            .map_err(|e| FoxgloveError::from(format!("{}: failed to listen ({})", address, e)))?;
+
+        eprintln!("foxglove: listening on ws://{}", listener.local_addr()?);
+
+        let sink = Arc::new(Foxglove {
+            clients: Mutex::new(HashMap::new()),
+            next: AtomicUsize::new(0),
+        });
+
+        let listening = Arc::clone(&sink);
+
+        thread::spawn(move || {
+            for stream in listener.incoming().filter_map(Result::ok) {
+                let sink = Arc::clone(&listening);
+
+                thread::spawn(move || {
+                    if let Err(e) = sink.serve(stream) {
+                        eprintln!("foxglove: warning: {}", e);
+                    }
+                });
+            }
+        });
+
+        Ok(sink)
+    }
+
+    /// Publish a match to the clients of the sink.
+    pub fn publish(&self, context: &MatchContext) {
+        let frames = context.witnesses.as_deref().unwrap_or(context.frames);
+
+        if let Some(last) = frames.last() {
+            self.send(
+                MATCHES,
+                timestamp(last),
+                json!({
+                    "timestamp": time(timestamp(last)),
+                    "pattern": context.pattern,
+                    "start": context.range.start,
+                    "end": context.range.end,
+                    "channel": context.channel,
+                    "path": context.path.map(|p| p.display().to_string()),
+                }),
+            );
+        }
+
+        for frame in frames {
+            let ns = timestamp(frame);
+
+            for record in frame.samples.iter().filter_map(|s| match s {
+                Sample::ObjectDetection(record) | Sample::Text(record) => Some(record),
+                _ => None,
+            }) {
+                if self.subscribed(ANNOTATIONS) {
+                    self.send(ANNOTATIONS, ns, annotations(record, ns));
+                }
+
+                // Load the image only if subscribed to.
+                //
+                // An image stored in a file would otherwise be read for each
+                // matched frame, even if it is never shown.
+                if self.subscribed(IMAGES) {
+                    for (channel, image) in record.images() {
+                        if let Some(image) = self::image(channel, image, ns) {
+                            self.send(IMAGES, ns, image);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Serve a single client until it disconnects.
+    fn serve(&self, stream: TcpStream) -> Result<(), Box<dyn Error>> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut writer = stream;
+
+        // Bound the time of the handshake.
+        //
+        // A connection that never completes its handshake would otherwise
+        // keep its thread forever. Once upgraded, a client may be idle for as
+        // long as it wants.
+        writer.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+        Self::handshake(&mut reader, &mut writer)?;
+        writer.set_read_timeout(None)?;
+        writer.set_write_timeout(Some(TIMEOUT))?;
+
+        let id = self.next.fetch_add(1, Ordering::Relaxed);
+
+        // Introduce the server and its channels.
+        //
+        // This is sent before the client is registered, such that no message
+        // of a channel can be sent before it is advertised.
+        let info = json!({
+            "op": "serverInfo",
+            "name": "strem",
+            "capabilities": [],
+            "supportedEncodings": [],
+            "metadata": {},
+        });
+
+        let advertise = json!({
+            "op": "advertise",
+            "channels": CHANNELS.iter().map(|(id, topic, schema)| json!({
+                "id": id,
+                "topic": topic,
+                "encoding": "json",
+                "schemaName": schema,
+                "schema": "{\"type\":\"object\"}",
+                "schemaEncoding": "jsonschema",
+            })).collect::<Vec<Value>>(),
+        });
+
+        write(&mut writer, 0x1, info.to_string().as_bytes())?;
+        write(&mut writer, 0x1, advertise.to_string().as_bytes())?;
+
+        self.clients.lock().unwrap().insert(
+            id,
+            Client {
+                stream: writer,
+                subscriptions: HashMap::new(),
+            },
+        );
+
+        let res = self.listen(id, &mut reader);
+        self.clients.lock().unwrap().remove(&id);
+
+        res
+    }
+
+    /// Accept the WebSocket handshake of a client.
+    ///
+    /// The handshake must be a `GET` request of HTTP/1.1 (or later) with a
+    /// WebSocket key, and at most [`MAX_HANDSHAKE`] bytes long.
+    fn handshake(reader: &mut impl BufRead, writer: &mut impl Write) -> Result<(), Box<dyn Error>> {
+        let mut reader = reader.take(MAX_HANDSHAKE);
+
+        let mut lines = Vec::new();
+
+        loop {
+            let mut line = String::new();
+
+            if reader.read_line(&mut line)? == 0 {
+                return Err(Box::new(FoxgloveError::from(match reader.limit() {
+                    0 => format!("handshake exceeds {} byte(s)", MAX_HANDSHAKE),
+                    _ => String::from("connection closed"),
+                })));
+            }
+
+            let line = line.trim_end().to_string();
+
+            if line.is_empty() {
+                break;
+            }
+
+            lines.push(line);
+        }
+
+        let mut lines = lines.iter();
+
+        // Check the request line.
+        //
+        // The upgrade of a connection is only defined for a `GET` request of
+        // HTTP/1.1 (RFC 6455).
+        let valid = lines.next().is_some_and(|line| {
+            let parts = line.split_whitespace().collect::<Vec<&str>>();
+
+            match parts[..] {
+                ["GET", _, version] => version
+                    .strip_prefix("HTTP/")
+                    .and_then(|v| v.split_once('.'))
+                    .and_then(|(major, minor)| Some((major.parse().ok()?, minor.parse().ok()?)))
+                    .is_some_and(|version: (u32, u32)| version >= (1, 1)),
+                _ => false,
+            }
+        });
+
+        let key = lines.find_map(|header| match header.split_once(':') {
+            Some((name, value)) if name.trim().eq_ignore_ascii_case("sec-websocket-key") => {
+                Some(value.trim().to_string())
+            }
+            _ => None,
+        });
+
+        let (true, Some(key)) = (valid, key) else {
+            write!(
+                writer,
+                "HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            )?;
+
+            return Err(Box::new(FoxgloveError::from(
+                "expected a WebSocket connection",
+            )));
+        };
+
+        write!(
+            writer,
+            "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\nSec-WebSocket-Protocol: {}\r\n\r\n",
+            self::accept(&key),
+            SUBPROTOCOL
+        )?;
+
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Handle the messages of a client until it disconnects.
+    fn listen(&self, id: usize, reader: &mut impl Read) -> Result<(), Box<dyn Error>> {
+        loop {
+            let (opcode, payload) = read(reader)?;
+
+            match opcode {
+                // Text.
+                0x1 => {
+                    let Ok(msg) = serde_json::from_slice::<Value>(&payload) else {
+                        continue;
+                    };
+
+                    let mut clients = self.clients.lock().unwrap();
+                    let Some(client) = clients.get_mut(&id) else {
+                        return Ok(());
+                    };
+
+                    match msg["op"].as_str() {
+                        Some("subscribe") => {
+                            for s in msg["subscriptions"].as_array().into_iter().flatten() {
+                                if let (Some(sub), Some(channel)) =
+                                    (s["id"].as_u64(), s["channelId"].as_u64())
+                                {
+                                    client.subscriptions.insert(sub as u32, channel as u32);
+                                }
+                            }
+                        }
+                        Some("unsubscribe") => {
+                            for sub in msg["subscriptionIds"].as_array().into_iter().flatten() {
+                                if let Some(sub) = sub.as_u64() {
+                                    client.subscriptions.remove(&(sub as u32));
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                // Close.
+                0x8 => {
+                    if let Some(client) = self.clients.lock().unwrap().get_mut(&id) {
+                        let _ = write(&mut client.stream, 0x8, &[]);
+                    }
+
+                    return Ok(());
+                }
+                // Ping.
+                0x9 => {
+                    if let Some(client) = self.clients.lock().unwrap().get_mut(&id) {
+                        write(&mut client.stream, 0xA, &payload)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Check if any client is subscribed to the `channel`.
+    fn subscribed(&self, channel: u32) -> bool {
+        self.clients
+            .lock()
+            .unwrap()
+            .values()
+            .any(|c| c.subscriptions.values().any(|ch| *ch == channel))
+    }
+
+    /// Send a message of the `channel` to each client subscribed to it.
+    ///
+    /// A client that fails to receive the message is disconnected.
+    fn send(&self, channel: u32, timestamp: u64, msg: Value) {
+        let msg = msg.to_string();
+        let mut clients = self.clients.lock().unwrap();
+
+        clients.retain(|_, client| {
+            client
+                .subscriptions
+                .iter()
+                .filter(|(_, ch)| **ch == channel)
+                .all(|(sub, _)| {
+                    let mut data = Vec::with_capacity(13 + msg.len());
+                    data.push(0x1);
+                    data.extend_from_slice(&sub.to_le_bytes());
+                    data.extend_from_slice(&timestamp.to_le_bytes());
+                    data.extend_from_slice(msg.as_bytes());
+
+                    write(&mut client.stream, 0x2, &data).is_ok()
+                })
+        });
+    }
+}
+
+/// The timestamp of a [`Frame`] in nanoseconds.
+///
+/// If the frame has no timestamp, its index is used as seconds instead.
+fn timestamp(frame: &Frame) -> u64 {
+    (frame.timestamp.unwrap_or(frame.index as f64).max(0.0) * 1e9) as u64
+}
+
+/// The Foxglove representation of a timestamp in nanoseconds.
+fn time(ns: u64) -> Value {
+    json!({ "sec": ns / 1_000_000_000, "nsec": ns % 1_000_000_000 })
+}
+
+/// The bounding boxes of a [`DetectionRecord`] as `foxglove.ImageAnnotations`.
+fn annotations(record: &DetectionRecord, ns: u64) -> Value {
+    let mut points = Vec::new();
+    let mut texts = Vec::new();
+
+    for annotation in record.annotations.values().flatten() {
+        let corners = annotation.bbox.corners();
+
+        points.push(json!({
+            "timestamp": time(ns),
+            "type": 2,
+            "points": corners.iter().map(|p| json!({ "x": p.x, "y": p.y })).collect::<Vec<Value>>(),
+            "outline_color": { "r": 0.0, "g": 1.0, "b": 0.0, "a": 1.0 },
+            "outline_colors": [],
+            "fill_color": { "r": 0.0, "g": 0.0, "b": 0.0, "a": 0.0 },
+            "thickness": 2.0,
+        }));
+
+        texts.push(json!({
+            "timestamp": time(ns),
+            "position": { "x": corners[0].x, "y": corners[0].y },
+            "text": format!("{} {:.2}", annotation.label.as_str(), annotation.score),
+            "font_size": 12.0,
+            "text_color": { "r": 1.0, "g": 1.0, "b": 1.0, "a": 1.0 },
+            "background_color": { "r": 0.0, "g": 0.0, "b": 0.0, "a": 0.7 },
+        }));
+    }
+
+    json!({
+        "circles": [],
+        "points": points,
+        "texts": texts,
+    })
+}
+
+/// An image of the `channel` as `foxglove.CompressedImage`, if supported.
+///
+/// Only PNG, JPEG, and WebP images are supported.
+fn image(channel: &str, image: &Image, ns: u64) -> Option<Value> {
+    let bytes = match &image.source {
+        ImageSource::File(path) => fs::read(path).ok()?,
+        ImageSource::Embedded(bytes) => bytes.clone(),
+    };
+
+    let format = match bytes.as_slice() {
+        [0x89, b'P', b'N', b'G', ..] => "png",
+        [0xFF, 0xD8, ..] => "jpeg",
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => "webp",
+        _ => return None,
+    };
+
+    Some(json!({
+        "timestamp": time(ns),
+        "frame_id": channel,
+        "data": base64::encode(&bytes),
+        "format": format,
+    }))
+}
+
+/// The accept key of the WebSocket handshake of a `key` (RFC 6455).
+fn accept(key: &str) -> String {
+    base64::encode(&sha1(format!("{}{}", key, GUID).as_bytes()))
+}
+
+/// Read a message of a client.
+///
+/// A client message is always masked (RFC 6455). Fragmented messages are not
+/// reassembled, as no message of the protocol needs to be fragmented.
+fn read(reader: &mut impl Read) -> io::Result<(u8, Vec<u8>)> {
+    let mut header = [0; 2];
+    reader.read_exact(&mut header)?;
+
+    let opcode = header[0] & 0x0F;
+
+    let length = match header[1] & 0x7F {
+        126 => {
+            let mut b = [0; 2];
+            reader.read_exact(&mut b)?;
+            u64::from(u16::from_be_bytes(b))
+        }
+        127 => {
+            let mut b = [0; 8];
+            reader.read_exact(&mut b)?;
+            u64::from_be_bytes(b)
+        }
+        n => u64::from(n),
+    };
+
+    if length > MAX_MESSAGE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("message exceeds {} byte(s)", MAX_MESSAGE),
+        ));
+    }
+
+    let mut mask = [0; 4];
+
+    if header[1] & 0x80 != 0 {
+        reader.read_exact(&mut mask)?;
+    }
+
+    let mut payload = vec![0; length as usize];
+    reader.read_exact(&mut payload)?;
+
+    for (i, b) in payload.iter_mut().enumerate() {
+        *b ^= mask[i % 4];
+    }
+
+    Ok((opcode, payload))
+}
+
+/// Write a message to a client.
+///
+/// A server message is never masked, and is sent as a single frame.
+fn write(writer: &mut impl Write, opcode: u8, payload: &[u8]) -> io::Result<()> {
+    let mut frame = vec![0x80 | opcode];
+
+    match payload.len() {
+        n if n < 126 => frame.push(n as u8),
+        n if n <= usize::from(u16::MAX) => {
+            frame.push(126);
+            frame.extend_from_slice(&(n as u16).to_be_bytes());
+        }
+        n => {
+            frame.push(127);
+            frame.extend_from_slice(&(n as u64).to_be_bytes());
+        }
+    }
+
+    frame.extend_from_slice(payload);
+    writer.write_all(&frame)
+}
+
+/// Compute the SHA-1 digest of the bytes (RFC 3174).
+///
+/// This is only used to accept the WebSocket handshake, which requires it.
+fn sha1(bytes: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+
+    // Pad the message to a multiple of 64 bytes.
+    //
+    // This appends a single set bit, then zeros, then the length of the
+    // message in bits.
+    let mut msg = bytes.to_vec();
+    msg.push(0x80);
+
+    while msg.len() % 64 != 56 {
+        msg.push(0);
+    }
+
+    msg.extend_from_slice(&((bytes.len() as u64) * 8).to_be_bytes());
+
+    for chunk in msg.chunks(64) {
+        let mut w = [0u32; 80];
+
+        for (i, word) in chunk.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+
+        for (i, word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+
+            let t = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(*word);
+
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = t;
+        }
+
+        for (h, x) in h.iter_mut().zip([a, b, c, d, e]) {
+            *h = h.wrapping_add(x);
+        }
+    }
+
+    let mut digest = [0; 20];
+
+    for (i, h) in h.iter().enumerate() {
+        digest[i * 4..(i + 1) * 4].copy_from_slice(&h.to_be_bytes());
+    }
+
+    digest
+}
+
+#[derive(Debug, Clone)]
+struct FoxgloveError {
+    msg: String,
+}
+
+impl From<&str> for FoxgloveError {
+    fn from(msg: &str) -> Self {
+        FoxgloveError {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for FoxgloveError {
+    fn from(msg: String) -> Self {
+        FoxgloveError { msg }
+    }
+}
+
+impl fmt::Display for FoxgloveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "foxglove: {}", self.msg)
+    }
+}
+
+impl Error for FoxgloveError {}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::{Foxglove, MAX_HANDSHAKE, MAX_MESSAGE};
+
+    #[test]
+    fn sha1() {
+        let hex = |digest: [u8; 20]| {
+            digest
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<String>()
+        };
+
+        assert_eq!(
+            hex(super::sha1(b"abc")),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+        assert_eq!(
+            hex(super::sha1(b"")),
+            "da39a3ee5e6b4b0d3255bfef95601890afd80709"
+        );
+
+        // The sample handshake of RFC 6455 (Section 1.3).
+        assert_eq!(
+            super::accept("dGhlIHNhbXBsZSBub25jZQ=="),
+            "s3pPLMBiTxaQ9kYGzzhZRbK+xOo="
+        );
+    }
+
+    #[test]
+    fn handshake() {
+        let handshake = |request: &str| {
+            let mut response = Vec::new();
+            let res = Foxglove::handshake(&mut Cursor::new(request), &mut response);
+
+            (res.is_ok(), String::from_utf8(response).unwrap())
+        };
+
+        let (ok, response) = handshake(
+            "GET /ws HTTP/1.1\r\nHost: a\r\nUpgrade: websocket\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n",
+        );
+        assert!(ok);
+        assert!(response.starts_with("HTTP/1.1 101 Switching Protocols\r\n"));
+        assert!(response.contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n"));
+
+        // Each of these is rejected as not a WebSocket handshake.
+        for request in [
+            "POST /ws HTTP/1.1\r\nSec-WebSocket-Key: a\r\n\r\n",
+            "GET /ws HTTP/1.0\r\nSec-WebSocket-Key: a\r\n\r\n",
+            "GET /ws\r\nSec-WebSocket-Key: a\r\n\r\n",
+            "GET /ws HTTP/1.1\r\nHost: a\r\n\r\n",
+        ] {
+            let (ok, response) = handshake(request);
+            assert!(!ok, "{}", request);
+            assert!(response.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+        }
+
+        // A handshake that never ends is not read in full.
+        let request = format!(
+            "GET /ws HTTP/1.1\r\nX-Padding: {}\r\n\r\n",
+            "a".repeat(MAX_HANDSHAKE as usize)
+        );
+
+        let (ok, response) = handshake(&request);
+        assert!(!ok);
+        assert!(response.is_empty());
+    }
+
+    #[test]
+    fn frames() {
+        // A masked frame of a client (RFC 6455, Section 5.7).
+        let mut frame = Cursor::new([
+            0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58,
+        ]);
+        assert_eq!(super::read(&mut frame).unwrap(), (0x1, b"Hello".to_vec()));
+
+        // The length of a frame is extended from 126 and 65536 bytes.
+        for (length, header) in [
+            (125, vec![0x82, 125]),
+            (126, vec![0x82, 126, 0x00, 0x7E]),
+            (65535, vec![0x82, 126, 0xFF, 0xFF]),
+            (65536, vec![0x82, 127, 0, 0, 0, 0, 0, 0x01, 0x00, 0x00]),
+        ] {
+            let payload = vec![0xAB; length];
+
+            let mut frame = Vec::new();
+            super::write(&mut frame, 0x2, &payload).unwrap();
+
+            assert_eq!(frame[..header.len()], header[..]);
+            assert_eq!(frame.len(), header.len() + length);
+            assert_eq!(
+                super::read(&mut Cursor::new(frame)).unwrap(),
+                (0x2, payload)
+            );
+        }
+
+        // A frame longer than the largest message is rejected before it is
+        // read.
+        let mut frame = vec![0x82, 0xFF];
+        frame.extend_from_slice(&(MAX_MESSAGE + 1).to_be_bytes());
+        assert!(super::read(&mut Cursor::new(frame)).is_err());
+    }
+}
@@ -0,0 +1,365 @@
+//! Application generator.
+//!
+
+use std::error::Error;
+use std::f64::consts::PI;
+use std::fmt;
+use std::fs::File;
+use std::io::{stdout, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use strem::datastream::frame::sample::detections::bbox::region::{aa, Point};
+use strem::datastream::frame::sample::detections::bbox::BoundingBox;
+use strem::datastream::frame::sample::detections::label::Label;
+use strem::datastream::frame::sample::detections::{Annotation, DetectionRecord};
+use strem::datastream::frame::sample::Sample;
+use strem::datastream::frame::Frame;
+use strem::datastream::io::exporter::DataExporter;
+
+/// Generate a randomized data stream from a scenario.
+///
+/// Each class of the scenario is populated by objects that appear, move, and
+/// disappear over time. The same scenario and seed always produce the same
+/// data stream, which is written to standard output.
+pub struct Generator {
+    path: PathBuf,
+    seed: Option<u64>,
+}
+
+impl Generator {
+    /// Create a new [`Generator`] from the path of a scenario.
+    ///
+    /// If a `seed` is provided, it overrides the seed of the scenario.
+    pub fn new(path: PathBuf, seed: Option<u64>) -> Self {
+        Generator { path, seed }
+    }
+
+    /// Run the [`Generator`].
+    pub fn run(&self) -> Result<(), Box<dyn Error>> {
+        let f = File::open(&self.path).or(Err(Box::new(GeneratorError::from(format!(
+            "{}: no such file found",
+            self.path.display()
+        )))))?;
+
+        let scenario: Scenario = serde_json::from_reader(BufReader::new(f))
+            .map_err(|e| GeneratorError::from(format!("{}: {}", self.path.display(), e)))?;
+
+        scenario.validate()?;
+
+        let frames = scenario.generate(self.seed.unwrap_or(scenario.seed));
+
+        let mut writer = BufWriter::new(stdout().lock());
+        serde_json::to_writer(&mut writer, &DataExporter::new().export(&frames)?)?;
+        writeln!(writer)?;
+
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// A specification of a data stream to generate.
+#[derive(Debug, Deserialize)]
+struct Scenario {
+    /// The seed of the random number generator.
+    #[serde(default)]
+    seed: u64,
+
+    /// The number of frames to generate.
+    frames: usize,
+
+    /// The number of frames per second, used to timestamp each frame.
+    #[serde(default)]
+    rate: Option<f64>,
+
+    /// The channel of all samples.
+    #[serde(default = "Scenario::channel")]
+    channel: String,
+
+    /// The dimensions of the image that all objects are contained within.
+    dimensions: Dimensions,
+
+    /// The classes of objects to populate the data stream with.
+    classes: Vec<Class>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Dimensions {
+    width: f64,
+    height: f64,
+}
+
+/// A class of objects and how they behave.
+#[derive(Debug, Deserialize)]
+struct Class {
+    name: String,
+
+    /// The average number of objects of the class in a frame.
+    density: f64,
+
+    /// The average dimensions of an object of the class.
+    size: Size,
+
+    /// The motion of an object of the class.
+    #[serde(default)]
+    motion: Motion,
+
+    /// The distance (in pixels) an object moves per frame.
+    #[serde(default)]
+    speed: f64,
+
+    /// The average number of frames an object is present for.
+    ///
+    /// By default, this is the number of frames of the scenario.
+    #[serde(default)]
+    lifetime: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Size {
+    w: f64,
+    h: f64,
+}
+
+/// A model of how an object moves between frames.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Motion {
+    /// The object does not move.
+    #[default]
+    Static,
+
+    /// The object moves in a fixed direction, reflecting off the image edges.
+    Linear,
+
+    /// The object moves in a new random direction each frame.
+    Random,
+}
+
+/// An object of a class that is present in the data stream.
+struct Object {
+    id: usize,
+    center: Point,
+    w: f64,
+    h: f64,
+    heading: f64,
+}
+
+impl Scenario {
+    fn channel() -> String {
+        String::from("cam::front")
+    }
+
+    /// Check the scenario is well-formed.
+    fn validate(&self) -> Result<(), Box<dyn Error>> {
+        if self.dimensions.width <= 0.0 || self.dimensions.height <= 0.0 {
+            return Err(Box::new(GeneratorError::from(
+                "dimensions must be positive",
+            )));
+        }
+
+        if let Some(rate) = self.rate {
+            if rate <= 0.0 {
+                return Err(Box::new(GeneratorError::from("rate must be positive")));
+            }
+        }
+
+        for class in self.classes.iter() {
+            if class.density < 0.0 || class.speed < 0.0 || class.lifetime.is_some_and(|l| l < 1.0) {
+                return Err(Box::new(GeneratorError::from(format!(
+                    "class `{}`: density and speed must be non-negative, and lifetime at least 1",
+                    class.name
+                ))));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Generate the [`Frame`] of the scenario.
+    ///
+    /// The population of each class begins at its density. Then, at each frame,
+    /// an object disappears with a probability of the inverse of its lifetime,
+    /// and new objects appear at the rate needed to keep the density.
+    fn generate(&self, seed: u64) -> Vec<Frame> {
+        let mut rng = Random::new(seed);
+        let mut count = 0;
+
+        let mut populations = self
+            .classes
+            .iter()
+            .map(|class| {
+                (0..rng.poisson(class.density))
+                    .map(|_| self.spawn(class, &mut rng, &mut count))
+                    .collect::<Vec<Object>>()
+            })
+            .collect::<Vec<Vec<Object>>>();
+
+        let mut frames = Vec::with_capacity(self.frames);
+
+        for index in 0..self.frames {
+            let mut frame = Frame::new(index);
+            frame.timestamp = self.rate.map(|rate| index as f64 / rate);
+
+            let mut record = DetectionRecord::new(self.channel.clone(), None);
+
+            for (class, objects) in self.classes.iter().zip(populations.iter_mut()) {
+                if index > 0 {
+                    let lifetime = class.lifetime.unwrap_or(self.frames as f64).max(1.0);
+                    objects.retain(|_| rng.uniform() >= 1.0 / lifetime);
+
+                    for _ in 0..rng.poisson(class.density / lifetime) {
+                        objects.push(self.spawn(class, &mut rng, &mut count));
+                    }
+
+                    for object in objects.iter_mut() {
+                        self.step(class, object, &mut rng);
+                    }
+                }
+
+                let label = Label::from(class.name.as_str());
+
+                for object in objects.iter() {
+                    let mut annotation = Annotation::new(
+                        label.clone(),
+                        0.5 + (rng.uniform() / 2.0),
+                        BoundingBox::AxisAligned(aa::Region::new(
+                            object.center.clone(),
+                            object.w,
+                            object.h,
+                        )),
+                    );
+                    annotation.instance_id = Some(object.id.to_string());
+
+                    record
+                        .annotations
+                        .entry(label.clone())
+                        .or_default()
+                        .push(annotation);
+                }
+            }
+
+            frame.samples.push(Sample::ObjectDetection(record));
+            frames.push(frame);
+        }
+
+        frames
+    }
+
+    /// Create a new object of a class at a random position.
+    ///
+    /// The dimensions of the object vary by up to 20% of the class size.
+    fn spawn(&self, class: &Class, rng: &mut Random, count: &mut usize) -> Object {
+        *count += 1;
+
+        Object {
+            id: *count,
+            center: Point::new(
+                rng.uniform() * self.dimensions.width,
+                rng.uniform() * self.dimensions.height,
+            ),
+            w: class.size.w * (0.8 + (0.4 * rng.uniform())),
+            h: class.size.h * (0.8 + (0.4 * rng.uniform())),
+            heading: 2.0 * PI * rng.uniform(),
+        }
+    }
+
+    /// Move an object by its motion for a single frame.
+    fn step(&self, class: &Class, object: &mut Object, rng: &mut Random) {
+        match class.motion {
+            Motion::Static => return,
+            Motion::Linear => {}
+            Motion::Random => object.heading = 2.0 * PI * rng.uniform(),
+        }
+
+        let mut x = object.center.x + (class.speed * object.heading.cos());
+        let mut y = object.center.y + (class.speed * object.heading.sin());
+
+        // Reflect off the image edges.
+        //
+        // This keeps the object within the image, such that the density of the
+        // class is not reduced by objects leaving it.
+        if !(0.0..=self.dimensions.width).contains(&x) {
+            x = x.clamp(0.0, self.dimensions.width);
+            object.heading = PI - object.heading;
+        }
+
+        if !(0.0..=self.dimensions.height).contains(&y) {
+            y = y.clamp(0.0, self.dimensions.height);
+            object.heading = -object.heading;
+        }
+
+        object.center = Point::new(x, y);
+    }
+}
+
+/// A seeded pseudo-random number generator (i.e., SplitMix64).
+///
+/// This is not suitable for cryptographic purposes; however, it is fast and
+/// reproducible across platforms, which is all that generating data requires.
+struct Random {
+    state: u64,
+}
+
+impl Random {
+    fn new(seed: u64) -> Self {
+        Random { state: seed }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+
+        z ^ (z >> 31)
+    }
+
+    /// A uniformly distributed number in [0, 1).
+    fn uniform(&mut self) -> f64 {
+        (self.next() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// A Poisson distributed number with a mean of `lambda`.
+    fn poisson(&mut self, lambda: f64) -> usize {
+        let limit = (-lambda).exp();
+
+        let mut k = 0;
+        let mut p = self.uniform();
+
+        while p > limit {
+            k += 1;
+            p *= self.uniform();
+        }
+
+        k
+    }
+}
+
+#[derive(Debug, Clone)]
+struct GeneratorError {
+    msg: String,
+}
+
+impl From<&str> for GeneratorError {
+    fn from(msg: &str) -> Self {
+        GeneratorError {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for GeneratorError {
+    fn from(msg: String) -> Self {
+        GeneratorError { msg }
+    }
+}
+
+impl fmt::Display for GeneratorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "generator: {}", self.msg)
+    }
+}
+
+impl Error for GeneratorError {}
@@ -0,0 +1,156 @@
+//! Application migrator.
+//!
+
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{stdin, stdout, BufReader, BufWriter, Read, Write};
+use std::path::PathBuf;
+
+use serde_json::Deserializer;
+use strem::datastream::io;
+use strem::datastream::io::version;
+
+/// Upgrade STREM-formatted data to the current version of the format.
+///
+/// The upgraded data is written to standard output with one data stream per
+/// line, accordingly.
+pub struct Migrator {
+    paths: Option<Vec<PathBuf>>,
+}
+
+impl Migrator {
+    /// Create a new [`Migrator`] over the set of paths.
+    ///
+    /// If no paths are provided, standard input ("stdin") is assumed.
+    pub fn new(paths: Option<Vec<PathBuf>>) -> Self {
+        Migrator { paths }
+    }
+
+    /// Run the [`Migrator`].
+    pub fn run(&self) -> Result<(), Box<dyn Error>> {
+        let mut writer = BufWriter::new(stdout().lock());
+
+        if let Some(paths) = &self.paths {
+            for path in paths {
+                let f = File::open(path).or(Err(Box::new(MigratorError::from(format!(
+                    "{}: no such file found",
+                    path.display()
+                )))))?;
+
+                Self::migrate(BufReader::new(f), &mut writer)?;
+            }
+
+            return Ok(());
+        }
+
+        Self::migrate(BufReader::new(stdin().lock()), &mut writer)
+    }
+
+    /// Migrate each data stream from `reader` into `writer`.
+    fn migrate<R: Read, W: Write>(reader: R, writer: &mut W) -> Result<(), Box<dyn Error>> {
+        let stream = Deserializer::from_reader(reader).into_iter::<io::DataStream>();
+
+        for data in stream {
+            let data = version::upgrade(data?)?;
+
+            serde_json::to_writer(&mut *writer, &data)?;
+            writeln!(writer)?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+struct MigratorError {
+    msg: String,
+}
+
+impl From<&str> for MigratorError {
+    fn from(msg: &str) -> Self {
+        MigratorError {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for MigratorError {
+    fn from(msg: String) -> Self {
+        MigratorError { msg }
+    }
+}
+
+impl fmt::Display for MigratorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "migrator: {}", self.msg)
+    }
+}
+
+impl Error for MigratorError {}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+    use strem::config::Configuration;
+    use strem::datastream::frame::sample::detections::label::Label;
+    use strem::datastream::frame::sample::Sample;
+    use strem::datastream::io::importer::Importer;
+    use strem::datastream::io::version::Version;
+    use strem::datastream::DataStream;
+
+    use super::Migrator;
+
+    #[test]
+    fn migrate() {
+        let source = br#"{"version":"0.2.0","frames":[{"index":0,"timestamp":1.5,"samples":[{"type":"@stremf/sample/detection","channel":"cam","image":{"path":"0.png","dimensions":{"width":100,"height":50}},"annotations":[{"class":"car","score":0.9,"bbox":{"type":"@stremf/bbox/aabb","region":{"center":{"x":10.0,"y":20.0},"dimensions":{"w":4.0,"h":2.0}}}}]}]}]}"#;
+
+        let mut output = Vec::new();
+        Migrator::migrate(&source[..], &mut output).unwrap();
+
+        let data: Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(data["version"], Version::current().to_string());
+
+        let frame = &data["frames"][0];
+        assert_eq!(frame["index"], 0);
+        assert_eq!(frame["timestamp"], 1.5);
+
+        let sample = &frame["samples"][0];
+        assert_eq!(sample["type"], "@stremf/sample/detection");
+        assert_eq!(sample["channel"], "cam");
+        assert_eq!(sample["image"]["path"], "0.png");
+        assert_eq!(sample["image"]["dimensions"]["width"], 100);
+        assert_eq!(sample["image"]["dimensions"]["height"], 50);
+
+        let annotation = &sample["annotations"][0];
+        assert_eq!(annotation["class"], "car");
+        assert_eq!(annotation["score"], 0.9);
+        assert_eq!(annotation["bbox"]["type"], "@stremf/bbox/aabb");
+        assert_eq!(annotation["bbox"]["region"]["center"]["x"], 10.0);
+        assert_eq!(annotation["bbox"]["region"]["center"]["y"], 20.0);
+        assert_eq!(annotation["bbox"]["region"]["dimensions"]["w"], 4.0);
+        assert_eq!(annotation["bbox"]["region"]["dimensions"]["h"], 2.0);
+
+        // Re-import the migrated data.
+        let pattern = String::from("[[:car:]]");
+        let config = Configuration::new(&pattern);
+
+        let mut importer = Importer::new(&config);
+        let frames = DataStream::from_slice(&output)
+            .request(&mut importer)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].timestamp, Some(1.5));
+
+        match &frames[0].samples[0] {
+            Sample::ObjectDetection(record) => {
+                assert_eq!(record.channel, "cam");
+                assert_eq!(record.annotations[&Label::from("car")][0].score, 0.9);
+            }
+            _ => panic!("expected a detection"),
+        }
+    }
+}
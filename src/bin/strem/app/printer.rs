@@ -2,25 +2,189 @@
 //!
 
 use std::error::Error;
-use std::fmt;
+use std::ops::ControlFlow;
+use std::sync::Arc;
 
 use colored::*;
+use serde_json::{json, Value};
 use strem::config::Configuration;
-use strem::datastream::frame::Frame;
+use strem::controller::{MatchContext, PrintCallback, Report, ReportCallback};
+use strem::datastream::frame::sample::detections::bbox::BoundingBox;
 use strem::datastream::io::exporter::DataExporter;
+use strem::datastream::trajectory::{self, Trajectory};
 
-pub struct Printer {}
+use super::copier::Copier;
+use super::foxglove::Foxglove;
+
+pub struct Printer {
+    /// The visualization sink that each match is published to, if any.
+    sink: Option<Arc<Foxglove>>,
+
+    /// The copier of the images of each match, if requested.
+    copier: Copier,
+}
 
 impl Printer {
+    /// Create a new [`Printer`] that publishes to `sink`, if any.
+    pub fn new(sink: Option<Arc<Foxglove>>) -> Self {
+        Self {
+            sink,
+            copier: Copier::new(),
+        }
+    }
+
+    /// The callback of the [`Controller`] that prints each match.
+    ///
+    /// [`Controller`]: strem::controller::Controller
+    pub fn callback(&self) -> PrintCallback<'_> {
+        Box::new(|context: &MatchContext, config: &Configuration| self.print(context, config))
+    }
+
+    /// The callback of the [`Controller`] that prints each diagnostic of a
+    /// search.
+    ///
+    /// [`Controller`]: strem::controller::Controller
+    pub fn reporter(&self) -> ReportCallback<'_> {
+        Box::new(|report: &Report, config: &Configuration| {
+            Self::report(report, config);
+            Ok(())
+        })
+    }
+
+    /// Print a [`Report`].
+    ///
+    /// The trace and explanation of a search are printed to the standard
+    /// error, such that they are kept apart from the matches. Partial matches
+    /// are results of their own; therefore, these are printed alongside the
+    /// matches, unless nothing is printed.
+    pub fn report(report: &Report, config: &Configuration) {
+        match report {
+            Report::Symbols {
+                position,
+                frame,
+                truths,
+                falsities,
+            } => eprintln!(
+                "trace: frame {} (index {}): true {:?}, false {:?}",
+                position, frame.index, truths, falsities
+            ),
+            Report::States { offset, steps } => {
+                eprintln!("trace: search from frame {}", offset);
+
+                for step in steps.iter() {
+                    let states = step
+                        .states
+                        .iter()
+                        .map(|state| state.to_string())
+                        .collect::<Vec<String>>();
+
+                    eprintln!(
+                        "trace:   frame {}: states {{{}}}{}",
+                        step.frame,
+                        states.join(", "),
+                        if step.accepting { " (match ends)" } else { "" }
+                    );
+                }
+            }
+            Report::Explanation {
+                offset,
+                consumed,
+                frame,
+                expected,
+            } => {
+                eprintln!(
+                    "explain: the longest partial match covers frames {}..{} ({} frame(s))",
+                    offset,
+                    offset + consumed,
+                    consumed
+                );
+
+                let frame = match frame {
+                    Some(frame) => frame,
+                    None => {
+                        eprintln!("explain: the datastream ended before the pattern was complete");
+                        return;
+                    }
+                };
+
+                eprintln!(
+                    "explain: frame {} (index {}) satisfies none of the expected formula(s)",
+                    offset + consumed,
+                    frame.index
+                );
+
+                for expectation in expected.iter() {
+                    eprintln!(
+                        "explain:   formula {} `{}`:",
+                        expectation.position, expectation.formula
+                    );
+
+                    for reason in expectation.reasons.iter() {
+                        eprintln!("explain:     {}", reason);
+                    }
+                }
+            }
+            Report::Partial {
+                frames,
+                ranges,
+                shortest,
+            } => {
+                if config.quiet {
+                    return;
+                }
+
+                let prefix = match config.datastream {
+                    Some(path) => format!("{}:", path.display()),
+                    None => String::new(),
+                };
+
+                for range in ranges.iter() {
+                    println!(
+                        "partial: {}{}..{} ({} of at least {} frame(s))",
+                        prefix,
+                        frames[range.start].index,
+                        frames[range.end - 1].end(),
+                        range.len(),
+                        shortest
+                    );
+                }
+            }
+        }
+    }
+
     /// Print a [`Match`].
-    pub fn print(frames: &[Frame], config: &Configuration) -> Result<(), Box<dyn Error>> {
+    pub fn print(
+        &self,
+        context: &MatchContext,
+        config: &Configuration,
+    ) -> Result<ControlFlow<()>, Box<dyn Error>> {
+        if let Some(sink) = &self.sink {
+            sink.publish(context);
+        }
+
+        // Copy the images of the match, if requested.
+        //
+        // Similar to publishing, this is a side effect of the match rather
+        // than a part of its message.
+        if let Some(dir) = config.images {
+            self.copier.copy(context, dir)?;
+        }
+
         if config.quiet {
-            return Ok(());
+            return Ok(ControlFlow::Continue(()));
         }
 
         let mut msg = String::new();
 
-        if let Some(path) = config.datastream {
+        // Print the query of the match.
+        //
+        // This is only known if a set of queries is searched.
+        if let Some(query) = context.query {
+            msg = Self::delimit(msg);
+            msg = format!("{}{}", msg, query.cyan());
+        }
+
+        if let Some(path) = context.path {
             let prefix = path.display().to_string();
 
             // Print the prefix.
@@ -30,21 +194,58 @@ impl Printer {
             msg = format!("{}{}", msg, prefix.magenta());
         }
 
-        // Print the bounds of the [`Frame`] set.
+        // Print the channel of the match.
         //
-        // If true, the boundary [start, end) will be printed to the
-        // terminal, accordingly.
-        let start = frames.first().unwrap().index;
-        let end = frames.last().unwrap().index + 1;
+        // This is only known if each channel is matched independently.
+        if let Some(channel) = context.channel {
+            msg = Self::delimit(msg);
+            msg = format!("{}{}", msg, channel.yellow());
+        }
 
         // Print the interval of the match.
         //
-        // This also includes coloring the text appropriately.
+        // The boundary [start, end) is printed, and this also includes
+        // coloring the text appropriately.
+        let range = &context.range;
+
         msg = Self::delimit(msg);
-        msg = format!("{}{}", msg, format!("{}..{}", start, end).green());
+        msg = format!(
+            "{}{}",
+            msg,
+            format!("{}..{}", range.start, range.end).green()
+        );
+
+        // Print the time of the match.
+        //
+        // This is only known if the range of the match does not tell it apart
+        // (i.e., it includes a frame inserted for a missing frame). The time
+        // of an inserted frame is interpolated; therefore, it is rounded to
+        // the microsecond.
+        if let Some(time) = &context.time {
+            let round = |t: f64| (t * 1e6).round() / 1e6;
+
+            msg = Self::delimit(msg);
+            msg = format!(
+                "{}{}",
+                msg,
+                format!("{}s..{}s", round(time.start), round(time.end)).green()
+            );
+        }
+
+        // Print the robustness of the match.
+        //
+        // This is only known if requested, where a positive score is
+        // satisfied by a margin and a negative score is violated by one.
+        if let Some(score) = context.score {
+            msg = Self::delimit(msg);
+            msg = format!("{}{}", msg, score.to_string().blue());
+        }
 
         if config.export {
-            let s = serde_json::to_string(&DataExporter::new().export(frames)?)?;
+            let s = serde_json::to_string(
+                &DataExporter::new()
+                    .export(context.witnesses.as_deref().unwrap_or(context.frames))?,
+            )?;
 
             // Print the exported data.
             //
@@ -54,12 +255,94 @@ impl Printer {
             msg = format!("{}{}", msg, s.red());
         }
 
+        if config.trajectory {
+            let s = Self::trajectories(context).to_string();
+
+            // Print the trajectories.
+            //
+            // Similar to the exported data, this replaces the other parts of
+            // the message, as the match is described in full.
+            msg.clear();
+            msg = Self::delimit(msg);
+            msg = format!("{}{}", msg, s.red());
+        }
+
         // Print a the message, accordingly.
         if !msg.is_empty() {
             println!("{}", msg);
         }
 
-        Ok(())
+        Ok(ControlFlow::Continue(()))
+    }
+
+    /// Describe the trajectories of the witnesses of a [`Match`] as JSON.
+    fn trajectories(context: &MatchContext) -> Value {
+        let frames = context.witnesses.as_deref().unwrap_or(context.frames);
+
+        json!({
+            "query": context.query,
+            "path": context.path.map(|p| p.display().to_string()),
+            "channel": context.channel,
+            "start": context.range.start,
+            "end": context.range.end,
+            "trajectories": trajectory::trajectories(frames)
+                .iter()
+                .map(Self::trajectory)
+                .collect::<Vec<Value>>(),
+        })
+    }
+
+    /// Describe a single [`Trajectory`] as JSON.
+    fn trajectory(trajectory: &Trajectory) -> Value {
+        let waypoints = trajectory
+            .waypoints
+            .iter()
+            .map(|waypoint| {
+                let (center, w, h, rotation) = match &waypoint.bbox {
+                    BoundingBox::AxisAligned(region) => {
+                        (region.center(), region.width(), region.height(), None)
+                    }
+                    BoundingBox::Oriented(region) => (
+                        region.center(),
+                        region.width(),
+                        region.height(),
+                        Some(region.rotation()),
+                    ),
+                    BoundingBox::Cuboid(region) => (
+                        region.footprint().center(),
+                        region.length,
+                        region.width,
+                        Some(region.rotation.yaw),
+                    ),
+                    BoundingBox::Polygon(region) => {
+                        let (xs, ys): (Vec<f64>, Vec<f64>) =
+                            region.points.iter().map(|p| (p.x, p.y)).unzip();
+
+                        let w = xs.iter().fold(f64::NEG_INFINITY, |a, b| a.max(*b))
+                            - xs.iter().fold(f64::INFINITY, |a, b| a.min(*b));
+                        let h = ys.iter().fold(f64::NEG_INFINITY, |a, b| a.max(*b))
+                            - ys.iter().fold(f64::INFINITY, |a, b| a.min(*b));
+
+                        (region.centroid(), w, h, None)
+                    }
+                };
+
+                json!({
+                    "index": waypoint.index,
+                    "timestamp": waypoint.timestamp,
+                    "center": { "x": center.x, "y": center.y },
+                    "dimensions": { "w": w, "h": h },
+                    "rotation": rotation,
+                })
+            })
+            .collect::<Vec<Value>>();
+
+        json!({
+            "channel": trajectory.channel,
+            "class": trajectory.label.as_str(),
+            "instance_id": trajectory.instance_id,
+            "waypoints": waypoints,
+        })
     }
 
     fn delimit(msg: String) -> String {
@@ -74,30 +357,3 @@ impl Printer {
         msg
     }
 }
-
-#[derive(Debug, Clone)]
-struct PrinterError {
-    msg: String,
-}
-
-impl From<&str> for PrinterError {
-    fn from(msg: &str) -> Self {
-        PrinterError {
-            msg: msg.to_string(),
-        }
-    }
-}
-
-impl From<String> for PrinterError {
-    fn from(msg: String) -> Self {
-        PrinterError { msg }
-    }
-}
-
-impl fmt::Display for PrinterError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "printer: {}", self.msg)
-    }
-}
-
-impl Error for PrinterError {}
@@ -0,0 +1,697 @@
+//! Application server.
+//!
+
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::ops::ControlFlow;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+use serde::Deserialize;
+use serde_json::value::RawValue;
+use serde_json::{json, Value};
+use strem::compiler::ir::ops::Vacuity;
+use strem::config::classmap::ClassMap;
+use strem::config::ontology::Ontology;
+use strem::config::Configuration;
+use strem::controller::{Controller, MatchContext};
+use strem::datastream::io::exporter::DataExporter;
+use strem::datastream::DataStream;
+
+/// The largest request body accepted (i.e., 64 MiB).
+const MAX_BODY: usize = 64 * 1024 * 1024;
+
+/// The largest request line and headers accepted (i.e., 8 KiB).
+const MAX_HEAD: u64 = 8 * 1024;
+
+/// The most connections served at once.
+///
+/// A connection beyond these is refused, rather than given its own thread.
+const MAX_CONNECTIONS: usize = 16;
+
+/// The longest time a client may wait between the parts of its request (or
+/// of reading its response).
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A search submitted to the [`Server`].
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Query {
+    /// The SpRE pattern to search with.
+    pattern: String,
+
+    /// The data stream to search over, as one or more STREM documents.
+    datastream: Option<Box<RawValue>>,
+
+    /// The stored data stream to search over, relative to the root.
+    path: Option<PathBuf>,
+
+    /// The maximum number of matches.
+    limit: Option<usize>,
+
+    /// Include the frames of each match.
+    #[serde(default)]
+    export: bool,
+
+    /// Restrict the included frames to the annotations that witness the
+    /// pattern.
+    #[serde(default)]
+    witness: bool,
+}
+
+/// An HTTP response of the [`Server`].
+struct Response {
+    status: u16,
+    body: Value,
+}
+
+impl Response {
+    fn ok(body: Value) -> Self {
+        Response { status: 200, body }
+    }
+
+    fn error(status: u16, msg: impl fmt::Display) -> Self {
+        Response {
+            status,
+            body: json!({ "error": msg.to_string() }),
+        }
+    }
+
+    /// The reason phrase of the status code.
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            408 => "Request Timeout",
+            413 => "Payload Too Large",
+            431 => "Request Header Fields Too Large",
+            503 => "Service Unavailable",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+/// Serve searches over HTTP.
+///
+/// Each search is submitted as JSON with a pattern and either the data stream
+/// itself or the path of a stored one, and its matches are returned as JSON.
+/// The endpoints are:
+///
+/// - `GET /health`: check that the server is running.
+/// - `GET /datastreams`: list the stored data streams.
+/// - `POST /search`: search a data stream with a pattern.
+pub struct Server {
+    address: String,
+
+    /// The settings of each search, shared by all connections.
+    settings: Arc<Settings>,
+
+    /// The number of connections being served.
+    active: Arc<AtomicUsize>,
+}
+
+/// A connection being served by a [`Server`].
+///
+/// The connection is released when this is dropped, even if its thread
+/// panics.
+struct Slot(Arc<AtomicUsize>);
+
+impl Slot {
+    /// Acquire a [`Slot`], unless [`MAX_CONNECTIONS`] are already served.
+    fn acquire(active: &Arc<AtomicUsize>) -> Option<Self> {
+        active
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| {
+                (n < MAX_CONNECTIONS).then_some(n + 1)
+            })
+            .ok()
+            .map(|_| Slot(Arc::clone(active)))
+    }
+}
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// The settings of the searches of a [`Server`].
+///
+/// These are set when the server is started, rather than by each query.
+#[derive(Default)]
+pub struct Settings {
+    /// The directory of the stored data streams, if any.
+    pub root: Option<PathBuf>,
+
+    /// The [`Ontology`] applied to each search, if any.
+    pub ontology: Option<Ontology>,
+
+    /// The truth of universal quantifiers over an empty domain.
+    pub vacuity: Vacuity,
+
+    /// The truth of temporal operators past the end of the data stream.
+    pub end: Vacuity,
+
+    /// Reject patterns with shadowed or unused bindings.
+    pub strict: bool,
+
+    /// The super-classes that a pattern may refer to, if any.
+    pub classmap: Option<ClassMap>,
+}
+
+impl Server {
+    /// Create a new [`Server`] listening on `address`.
+    pub fn new(address: String, settings: Settings) -> Self {
+        Server {
+            address,
+            settings: Arc::new(settings),
+            active: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    /// Run the [`Server`].
+    ///
+    /// Each connection is served on its own thread, up to [`MAX_CONNECTIONS`]
+    /// at once, and the server runs until the process is stopped.
+    pub fn run(&self) -> Result<(), Box<dyn Error>> {
+        let listener = TcpListener::bind(&self.address).map_err(|e| {
+            ServerError::from(format!("{}: failed to listen ({})", self.address, e))
+        })?;
+
+        eprintln!("server: listening on http://{}", listener.local_addr()?);
+
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(e) => {
+                    eprintln!("server: warning: failed to accept connection ({})", e);
+                    continue;
+                }
+            };
+
+            // Refuse the connection when too many are served.
+            //
+            // Each connection would otherwise hold its own thread (and the
+            // body of its request) without bound.
+            let Some(slot) = Slot::acquire(&self.active) else {
+                let response = Response::error(503, "too many connections");
+
+                if let Err(e) = Self::respond(stream, &response) {
+                    eprintln!("server: warning: {}", e);
+                }
+
+                continue;
+            };
+
+            let settings = self.settings.clone();
+
+            thread::spawn(move || {
+                let _slot = slot;
+
+                if let Err(e) = Self::serve(stream, &settings) {
+                    eprintln!("server: warning: {}", e);
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Serve a single request of a connection.
+    ///
+    /// The connection is closed once the response is written.
+    fn serve(stream: TcpStream, settings: &Settings) -> Result<(), Box<dyn Error>> {
+        // Bound the time of each read (and write).
+        //
+        // A client that never completes its request would otherwise keep its
+        // connection forever.
+        stream.set_read_timeout(Some(TIMEOUT))?;
+        stream.set_write_timeout(Some(TIMEOUT))?;
+
+        let mut reader = BufReader::new(stream.try_clone()?);
+
+        let response = match Self::read(&mut reader) {
+            Ok((method, target, body)) => match (method.as_str(), target.as_str()) {
+                ("GET", "/health") => Response::ok(json!({
+                    "status": "ok",
+                    "version": clap::crate_version!(),
+                })),
+                ("GET", "/datastreams") => Self::datastreams(settings.root.as_deref()),
+                ("POST", "/search") => Self::search(&body, settings),
+                (_, "/health" | "/datastreams" | "/search") => {
+                    Response::error(405, format!("`{}` is not allowed on `{}`", method, target))
+                }
+                _ => Response::error(404, format!("`{}` not found", target)),
+            },
+            Err(response) => response,
+        };
+
+        Self::respond(stream, &response)
+    }
+
+    /// Write a response to a connection.
+    fn respond(mut stream: TcpStream, response: &Response) -> Result<(), Box<dyn Error>> {
+        stream.set_write_timeout(Some(TIMEOUT))?;
+
+        let body = serde_json::to_string(&response.body)?;
+
+        write!(
+            stream,
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            response.status,
+            response.reason(),
+            body.len(),
+            body
+        )?;
+
+        stream.flush()?;
+
+        Ok(())
+    }
+
+    /// Read the method, target, and body of a request.
+    ///
+    /// Only a body of a known length (i.e., with `Content-Length`) is read,
+    /// and the request line and headers must be at most [`MAX_HEAD`] bytes
+    /// long.
+    fn read(reader: &mut impl BufRead) -> Result<(String, String, Vec<u8>), Response> {
+        let bad = |msg: &str| Response::error(400, msg);
+
+        let mut head = reader.by_ref().take(MAX_HEAD);
+
+        // Read a line of the request line and headers.
+        let mut next = |msg: &str| {
+            let mut line = String::new();
+
+            match head.read_line(&mut line) {
+                Ok(0) if head.limit() == 0 => Err(Response::error(
+                    431,
+                    format!("request head exceeds {} byte(s)", MAX_HEAD),
+                )),
+                Ok(0) => Err(bad("connection closed before the end of the request head")),
+                Ok(_) => Ok(line),
+                Err(e) if timeout(&e) => Err(Response::error(408, "request timed out")),
+                Err(_) => Err(bad(msg)),
+            }
+        };
+
+        let line = next("malformed request line")?;
+
+        let mut parts = line.split_whitespace();
+        let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+            return Err(bad("malformed request line"));
+        };
+
+        // Ignore the query of the target.
+        //
+        // No endpoint takes any parameters from it.
+        let method = method.to_string();
+        let target = target.split('?').next().unwrap_or_default().to_string();
+
+        let mut length = 0;
+
+        loop {
+            let header = next("malformed header")?;
+            let header = header.trim_end();
+
+            if header.is_empty() {
+                break;
+            }
+
+            if let Some((name, value)) = header.split_once(':') {
+                if name.trim().eq_ignore_ascii_case("content-length") {
+                    length = value
+                        .trim()
+                        .parse::<usize>()
+                        .map_err(|_| bad("malformed `Content-Length`"))?;
+                }
+            }
+        }
+
+        if length > MAX_BODY {
+            return Err(Response::error(
+                413,
+                format!("request body exceeds {} byte(s)", MAX_BODY),
+            ));
+        }
+
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).map_err(|e| {
+            if timeout(&e) {
+                Response::error(408, "request timed out")
+            } else {
+                bad("request body is shorter than `Content-Length`")
+            }
+        })?;
+
+        Ok((method, target, body))
+    }
+
+    /// List the stored data streams.
+    fn datastreams(root: Option<&Path>) -> Response {
+        let Some(root) = root else {
+            return Response::ok(json!({ "datastreams": [] }));
+        };
+
+        let entries = match fs::read_dir(root) {
+            Ok(entries) => entries,
+            Err(e) => return Response::error(500, format!("{}: {}", root.display(), e)),
+        };
+
+        let mut names = entries
+            .filter_map(Result::ok)
+            .filter(|e| e.path().is_file())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .collect::<Vec<String>>();
+
+        names.sort();
+
+        Response::ok(json!({ "datastreams": names }))
+    }
+
+    /// Search a data stream with the pattern of a [`Query`].
+    fn search(body: &[u8], settings: &Settings) -> Response {
+        let query = match serde_json::from_slice::<Query>(body) {
+            Ok(query) => query,
+            Err(e) => return Response::error(400, format!("malformed query ({})", e)),
+        };
+
+        // Load the data stream.
+        //
+        // A stored data stream must be within the root, such that a query
+        // cannot read arbitrary files.
+        let buffer = match (&query.datastream, &query.path) {
+            (Some(datastream), None) => datastream.get().as_bytes().to_vec(),
+            (None, Some(path)) => {
+                let Some(root) = &settings.root else {
+                    return Response::error(400, "no stored data streams (use `--root`)");
+                };
+
+                if !path.components().all(|c| matches!(c, Component::Normal(_))) {
+                    return Response::error(400, format!("{}: invalid path", path.display()));
+                }
+
+                let mut buffer = Vec::new();
+
+                if let Err(e) =
+                    File::open(root.join(path)).and_then(|mut f| f.read_to_end(&mut buffer))
+                {
+                    return Response::error(404, format!("{}: {}", path.display(), e));
+                }
+
+                buffer
+            }
+            _ => return Response::error(400, "expected exactly one of `datastream` or `path`"),
+        };
+
+        let mut config = Configuration::new(&query.pattern);
+        config.limit = query.limit;
+        config.export = query.export || query.witness;
+        config.witness = query.witness;
+        config.ontology = settings.ontology.clone();
+        config.vacuity = settings.vacuity;
+        config.end = settings.end;
+        config.strict = settings.strict;
+        config.classmap = settings.classmap.clone();
+
+        // Each search collects into its own matches, such that concurrent
+        // searches are kept apart.
+        let mut matches = Vec::new();
+
+        let controller = Controller::new(
+            &config,
+            Some(Box::new(
+                |context: &MatchContext, config: &Configuration| {
+                    Self::collect(context, config, &mut matches)
+                },
+            )),
+        );
+
+        let result = controller.run(DataStream::from_slice(&buffer));
+        drop(controller);
+
+        if let Err(e) = result {
+            return Response::error(400, e);
+        }
+
+        Response::ok(json!({
+            "count": matches.len(),
+            "matches": matches,
+        }))
+    }
+
+    /// Collect a match of a search into `matches`.
+    fn collect(
+        context: &MatchContext,
+        config: &Configuration,
+        matches: &mut Vec<Value>,
+    ) -> Result<ControlFlow<()>, Box<dyn Error>> {
+        let mut m = json!({
+            "start": context.range.start,
+            "end": context.range.end,
+        });
+
+        if let Some(channel) = context.channel {
+            m["channel"] = json!(channel);
+        }
+
+        if config.export {
+            m["frames"] = serde_json::to_value(
+                DataExporter::new()
+                    .export(context.witnesses.as_deref().unwrap_or(context.frames))?,
+            )?;
+        }
+
+        matches.push(m);
+
+        Ok(ControlFlow::Continue(()))
+    }
+}
+
+/// Check if an error is the timeout of a read.
+///
+/// Depending on the platform, this is either [`io::ErrorKind::WouldBlock`] or
+/// [`io::ErrorKind::TimedOut`].
+fn timeout(e: &io::Error) -> bool {
+    matches!(
+        e.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
+#[derive(Debug, Clone)]
+struct ServerError {
+    msg: String,
+}
+
+impl From<&str> for ServerError {
+    fn from(msg: &str) -> Self {
+        ServerError {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for ServerError {
+    fn from(msg: String) -> Self {
+        ServerError { msg }
+    }
+}
+
+impl fmt::Display for ServerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "server: {}", self.msg)
+    }
+}
+
+impl Error for ServerError {}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+
+    use serde_json::{json, Value};
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use super::{Server, Settings, Slot, MAX_BODY, MAX_CONNECTIONS, MAX_HEAD};
+
+    /// Send a request to a [`Server`], returning the status and body of its
+    /// response.
+    fn send(request: &str) -> (u16, Value) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            Server::serve(stream, &Settings::default()).unwrap();
+        });
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        server.join().unwrap();
+
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.split_whitespace().nth(1).unwrap().parse().unwrap();
+
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    /// Create a `POST /search` request of the `query`.
+    fn search(query: &Value) -> String {
+        let body = query.to_string();
+
+        format!(
+            "POST /search HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        )
+    }
+
+    #[test]
+    fn read() {
+        let mut request =
+            Cursor::new("POST /search?x=1 HTTP/1.1\r\nHost: a\r\ncontent-length: 4\r\n\r\nbody");
+        assert_eq!(
+            Server::read(&mut request).ok(),
+            Some((
+                String::from("POST"),
+                String::from("/search"),
+                b"body".to_vec()
+            ))
+        );
+
+        let status = |request: String| {
+            Server::read(&mut Cursor::new(request))
+                .err()
+                .map(|response| response.status)
+        };
+
+        assert_eq!(status(String::from("\r\n")), Some(400));
+        assert_eq!(
+            status(String::from(
+                "POST /search HTTP/1.1\r\nContent-Length: x\r\n\r\n"
+            )),
+            Some(400)
+        );
+        assert_eq!(
+            status(String::from(
+                "POST /search HTTP/1.1\r\nContent-Length: 4\r\n\r\nab"
+            )),
+            Some(400)
+        );
+        assert_eq!(
+            status(format!(
+                "POST /search HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+                MAX_BODY + 1
+            )),
+            Some(413)
+        );
+
+        // The request head is bounded, such that a client cannot send headers
+        // without end.
+        assert_eq!(
+            status(format!(
+                "GET /health HTTP/1.1\r\nHost: {}\r\n\r\n",
+                "a".repeat(MAX_HEAD as usize)
+            )),
+            Some(431)
+        );
+        assert_eq!(status(String::from("GET /health HTTP/1.1\r\n")), Some(400));
+    }
+
+    #[test]
+    fn slot() {
+        let active = Arc::new(AtomicUsize::new(0));
+
+        let mut slots = (0..MAX_CONNECTIONS)
+            .map(|_| Slot::acquire(&active).unwrap())
+            .collect::<Vec<Slot>>();
+        assert!(Slot::acquire(&active).is_none());
+
+        // A connection is released once it is served.
+        slots.pop();
+        assert!(Slot::acquire(&active).is_some());
+
+        drop(slots);
+        assert_eq!(active.load(Ordering::Acquire), 0);
+    }
+
+    #[test]
+    fn roundtrip() {
+        let frame = |index: usize| {
+            json!({
+                "index": index,
+                "samples": [{
+                    "type": "@stremf/sample/detection",
+                    "channel": "cam",
+                    "annotations": [{
+                        "class": "car",
+                        "score": 1.0,
+                        "bbox": {
+                            "type": "@stremf/bbox/aabb",
+                            "region": {
+                                "center": { "x": 0.0, "y": 0.0 },
+                                "dimensions": { "w": 1.0, "h": 1.0 }
+                            }
+                        }
+                    }]
+                }]
+            })
+        };
+        let datastream = json!({ "version": "2.0.0", "frames": [frame(0), frame(1), frame(2)] });
+
+        assert_eq!(
+            send(&search(&json!({
+                "pattern": "[[:car:]]{2}",
+                "datastream": datastream,
+            }))),
+            (
+                200,
+                json!({ "count": 1, "matches": [{ "start": 0, "end": 2 }] })
+            )
+        );
+
+        assert_eq!(send("GET /health HTTP/1.1\r\n\r\n").0, 200);
+        assert_eq!(send("GET /search HTTP/1.1\r\n\r\n").0, 405);
+        assert_eq!(send("GET /unknown HTTP/1.1\r\n\r\n").0, 404);
+    }
+
+    #[test]
+    fn error() {
+        let datastream = json!({ "version": "2.0.0", "frames": [] });
+
+        // An invalid pattern is reported, rather than failing the server.
+        let (status, body) = send(&search(&json!({
+            "pattern": "[[:car:]]{3,1}",
+            "datastream": datastream,
+        })));
+        assert_eq!(status, 400);
+        assert!(body["error"]
+            .as_str()
+            .unwrap()
+            .contains("range `{3,1}` is empty"));
+
+        let (status, body) = send(&search(&json!({ "pattern": "[[:car:]]" })));
+        assert_eq!(status, 400);
+        assert_eq!(
+            body["error"],
+            "expected exactly one of `datastream` or `path`"
+        );
+
+        assert_eq!(send(&search(&json!({ "datastream": datastream }))).0, 400);
+    }
+}
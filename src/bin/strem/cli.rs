@@ -3,16 +3,19 @@
 
 use std::path::PathBuf;
 
-use clap::{Arg, ArgAction, Command};
+use clap::{Arg, ArgAction, ArgGroup, Command};
 
 /// Build the Command-Line Interface application.
 ///
 /// The structure of the command is organized follows: (1) parser settings,
-/// (2) tool information, (3) positional arguments, (4) flags, and (5) options.
+/// (2) tool information, (3) positional arguments, (4) flags, (5) options, and
+/// (6) subcommands.
 pub fn build() -> Command {
     Command::new(clap::crate_name!())
         .help_expected(true)
         .dont_collapse_args_in_usage(true)
+        .args_conflicts_with_subcommands(true)
+        .subcommand_negates_reqs(true)
         .version(clap::crate_version!())
         .about(clap::crate_description!())
         .long_about(
@@ -30,7 +33,7 @@ pub fn build() -> Command {
         )
         .arg(
             Arg::new("PATTERN")
-                .required(true)
+                .required_unless_present_any(["pattern-file", "queries"])
                 .action(ArgAction::Set)
                 .value_parser(clap::value_parser!(String))
                 .help("A SpRE pattern used for searching"),
@@ -41,6 +44,33 @@ pub fn build() -> Command {
                 .value_parser(clap::value_parser!(PathBuf))
                 .help("The perception data stream to search over"),
         )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .value_name("FORMAT")
+                .action(ArgAction::Set)
+                .value_parser(["stremf", "coco", "rosbag"])
+                .conflicts_with_all(["buffer", "index", "align"])
+                .help("Read the data stream(s) as `FORMAT`"),
+        )
+        .arg(
+            Arg::new("topic")
+                .long("topic")
+                .value_name("NAME")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(String))
+                .help("Read the detections of the ROS bag topic `NAME`"),
+        )
+        .arg(
+            Arg::new("mqtt")
+                .long("mqtt")
+                .value_name("URL")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(String))
+                .requires("online")
+                .conflicts_with_all(["DATASTREAM", "format", "buffer"])
+                .help("Subscribe to the MQTT topic of `URL` (i.e., mqtt://host[:port]/topic)"),
+        )
         .arg(
             Arg::new("channel")
                 .short('c')
@@ -51,6 +81,45 @@ pub fn build() -> Command {
                 .value_parser(clap::value_parser!(String))
                 .help("The channel to consider in the search"),
         )
+        .arg(
+            Arg::new("merge-channels")
+                .long("merge-channels")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("export")
+                .help("Merge the detections of all channels into a single sample"),
+        )
+        .arg(
+            Arg::new("per-channel")
+                .long("per-channel")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["merge-channels", "trace", "explain", "partial"])
+                .help("Match the pattern against each channel independently"),
+        )
+        .arg(
+            Arg::new("align")
+                .long("align")
+                .value_name("SECS")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(f64))
+                .conflicts_with("index")
+                .help("Merge all files by timestamp, within `SECS` of each other"),
+        )
+        .arg(
+            Arg::new("nms")
+                .long("nms")
+                .value_name("IOU")
+                .action(ArgAction::Set)
+                .value_parser(iou)
+                .help("Suppress same-class detections overlapping above `IOU`"),
+        )
+        .arg(
+            Arg::new("roi")
+                .long("roi")
+                .value_name("X,Y,W,H")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(String))
+                .help("Only consider detections whose boxes intersect a region"),
+        )
         .arg(
             Arg::new("online")
                 .short('o')
@@ -58,6 +127,157 @@ pub fn build() -> Command {
                 .action(ArgAction::SetTrue)
                 .help("Use the online algorithm"),
         )
+        .arg(
+            Arg::new("pattern-file")
+                .short('f')
+                .long("pattern-file")
+                .value_name("FILE")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(PathBuf))
+                .help("Read the pattern from `FILE` (all positional arguments are then data streams)"),
+        )
+        .arg(
+            Arg::new("queries")
+                .long("queries")
+                .value_name("FILE")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(PathBuf))
+                .conflicts_with_all(["pattern-file", "online", "chunk"])
+                .help("Search with each named pattern of `FILE` (all positional arguments are then data streams)"),
+        )
+        .arg(
+            Arg::new("reload")
+                .long("reload")
+                .action(ArgAction::SetTrue)
+                .requires("pattern-file")
+                .requires("online")
+                .help("Reload the pattern when its file changes"),
+        )
+        .arg(
+            Arg::new("window")
+                .long("window")
+                .value_name("NUM")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(usize))
+                .help("Keep at most `NUM` frames when searching online (or in chunks)"),
+        )
+        .arg(
+            Arg::new("chunk")
+                .long("chunk")
+                .value_name("NUM")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(usize))
+                .conflicts_with_all([
+                    "online",
+                    "per-channel",
+                    "trace",
+                    "explain",
+                    "partial",
+                    "index",
+                    "align",
+                ])
+                .help("Search offline in chunks of `NUM` frames to bound memory"),
+        )
+        .arg(
+            Arg::new("stats")
+                .long("stats")
+                .value_name("WINDOW")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(String))
+                .requires("online")
+                .help("Print statistics of the last `WINDOW` frames (or seconds, e.g., `30s`) once per window"),
+        )
+        .arg(
+            Arg::new("deadline")
+                .long("deadline")
+                .value_name("MS")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(f64))
+                .requires("online")
+                .help("Report each frame that takes longer than `MS` milliseconds to search"),
+        )
+        .arg(
+            Arg::new("overrun")
+                .long("overrun")
+                .value_name("POLICY")
+                .action(ArgAction::Set)
+                .value_parser(["report", "drop"])
+                .requires("deadline")
+                .help("Handle frames that miss their deadline by `POLICY`"),
+        )
+        .arg(
+            Arg::new("buffer")
+                .long("buffer")
+                .value_name("NUM")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(usize))
+                .requires("online")
+                .help("Read at most `NUM` documents ahead of the search"),
+        )
+        .arg(
+            Arg::new("overflow")
+                .long("overflow")
+                .value_name("POLICY")
+                .action(ArgAction::Set)
+                .value_parser(["block", "drop-oldest", "drop-newest"])
+                .requires("buffer")
+                .help("Handle documents read while the buffer is full by `POLICY`"),
+        )
+        .arg(
+            Arg::new("foxglove")
+                .long("foxglove")
+                .value_name("ADDRESS")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(String))
+                .help("Stream the matches to Foxglove Studio over a WebSocket on `ADDRESS`"),
+        )
+        .arg(
+            Arg::new("cache")
+                .long("cache")
+                .value_name("DIR")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(PathBuf))
+                .conflicts_with_all([
+                    "online",
+                    "chunk",
+                    "buffer",
+                    "align",
+                    "foxglove",
+                    "export",
+                    "copy-images",
+                    "trajectory",
+                    "trace",
+                    "explain",
+                    "partial",
+                ])
+                .help("Reuse the results of searching unchanged files stored in `DIR`"),
+        )
+        .arg(
+            Arg::new("fuzzy")
+                .long("fuzzy")
+                .value_name("NUM")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(usize))
+                .help("Allow up to `NUM` mismatched frames within a match"),
+        )
+        .arg(
+            Arg::new("vacuous-truth")
+                .long("vacuous-truth")
+                .action(ArgAction::SetTrue)
+                .help("Satisfy universal quantifiers over an empty domain"),
+        )
+        .arg(
+            Arg::new("vacuous-end")
+                .long("vacuous-end")
+                .action(ArgAction::SetTrue)
+                .help("Satisfy temporal operators past the end of the data stream"),
+        )
+        .arg(
+            Arg::new("strict-bindings")
+                .long("strict-bindings")
+                .action(ArgAction::SetTrue)
+                .help("Reject patterns with shadowed or unused bindings"),
+        )
         .arg(
             Arg::new("max-count")
                 .short('m')
@@ -74,6 +294,43 @@ pub fn build() -> Command {
                 .action(ArgAction::SetTrue)
                 .help("Export the data of a match"),
         )
+        .arg(
+            Arg::new("witness")
+                .long("witness")
+                .action(ArgAction::SetTrue)
+                .requires("export")
+                .help("Only export the annotations that satisfy the pattern"),
+        )
+        .arg(
+            Arg::new("trajectory")
+                .long("trajectory")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("export")
+                .help("Print the trajectory of each tracked object that satisfies the pattern"),
+        )
+        .arg(
+            Arg::new("robustness")
+                .long("robustness")
+                .action(ArgAction::SetTrue)
+                .help("Print the robustness of each match (i.e., how strongly it is satisfied)"),
+        )
+        .arg(
+            Arg::new("image-root")
+                .long("image-root")
+                .value_name("DIR")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(PathBuf))
+                .help("Resolve relative image paths against `DIR`"),
+        )
+        .arg(
+            Arg::new("copy-images")
+                .long("copy-images")
+                .value_name("DIR")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(PathBuf))
+                .conflicts_with("quiet")
+                .help("Copy the images of each match into a folder of `DIR`"),
+        )
         .arg(
             Arg::new("quiet")
                 .short('q')
@@ -81,6 +338,29 @@ pub fn build() -> Command {
                 .action(ArgAction::SetTrue)
                 .help("Do not write to standard output"),
         )
+        .arg(
+            Arg::new("trace")
+                .long("trace")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("online")
+                .help("Print the evaluation of each frame to standard error"),
+        )
+        .arg(
+            Arg::new("explain")
+                .long("explain")
+                .action(ArgAction::SetTrue)
+                .conflicts_with("online")
+                .help("Explain the longest partial match if no match is found"),
+        )
+        .arg(
+            Arg::new("partial")
+                .long("partial")
+                .value_name("NUM")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(usize))
+                .conflicts_with("online")
+                .help("Report the `NUM` longest partial matches"),
+        )
         .arg(
             Arg::new("skip")
                 .short('s')
@@ -90,4 +370,369 @@ pub fn build() -> Command {
                 .value_parser(clap::value_parser!(usize))
                 .help("Skip the first `NUM` frames"),
         )
+        .arg(
+            Arg::new("from")
+                .long("from")
+                .value_name("INDEX")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(usize))
+                .help("Start searching from the frame with index `INDEX`"),
+        )
+        .arg(
+            Arg::new("index")
+                .long("index")
+                .action(ArgAction::SetTrue)
+                .help("Persist a frame index alongside each file for seeking"),
+        )
+        .arg(
+            Arg::new("force-version")
+                .long("force-version")
+                .action(ArgAction::SetTrue)
+                .help("Import data regardless of its declared version"),
+        )
+        .arg(
+            Arg::new("strict")
+                .long("strict")
+                .action(ArgAction::SetTrue)
+                .help("Abort on the first malformed frame or annotation"),
+        )
+        .arg(
+            Arg::new("ontology")
+                .long("ontology")
+                .value_name("FILE")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(PathBuf))
+                .help("Map dataset labels to canonical labels from `FILE`"),
+        )
+        .arg(
+            Arg::new("classmap")
+                .long("classmap")
+                .value_name("FILE")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(PathBuf))
+                .help("Group labels under super-classes from `FILE`"),
+        )
+        .arg(
+            Arg::new("where")
+                .short('w')
+                .long("where")
+                .value_name("PREDICATE")
+                .action(ArgAction::Append)
+                .value_parser(clap::value_parser!(String))
+                .help("Only search frames whose metadata satisfies `PREDICATE`"),
+        )
+        .arg(
+            Arg::new("gaps")
+                .long("gaps")
+                .value_name("POLICY")
+                .action(ArgAction::Set)
+                .value_parser(["ignore", "empty", "interpolate", "break"])
+                .help("Handle gaps of missing frames by `POLICY`"),
+        )
+        .arg(
+            Arg::new("transform")
+                .short('t')
+                .long("transform")
+                .value_name("TRANSFORM")
+                .action(ArgAction::Append)
+                .value_parser(clap::value_parser!(String))
+                .help("Apply `TRANSFORM` to each frame before searching (e.g., `decimate:2`)"),
+        )
+        .subcommand(self::migrate())
+        .subcommand(self::bench())
+        .subcommand(self::generate())
+        .subcommand(self::serve())
+        .subcommand(self::daemon())
+}
+
+/// Build the `migrate` subcommand.
+///
+/// This subcommand upgrades STREM-formatted data to the current version of the
+/// format and writes the result to standard output.
+fn migrate() -> Command {
+    Command::new("migrate")
+        .about("Upgrade a data stream to the current version of the format")
+        .arg(
+            Arg::new("DATASTREAM")
+                .action(ArgAction::Append)
+                .value_parser(clap::value_parser!(PathBuf))
+                .help("The perception data stream to upgrade"),
+        )
+}
+
+/// Build the `bench` subcommand.
+///
+/// This subcommand searches a data stream repeatedly and reports the time spent
+/// in each stage of the search.
+fn bench() -> Command {
+    Command::new("bench")
+        .about("Measure the time spent in each stage of a search")
+        .arg(
+            Arg::new("PATTERN")
+                .required(true)
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(String))
+                .help("A SpRE pattern used for searching"),
+        )
+        .arg(
+            Arg::new("DATASTREAM")
+                .action(ArgAction::Append)
+                .value_parser(clap::value_parser!(PathBuf))
+                .help("The perception data stream to search over"),
+        )
+        .arg(
+            Arg::new("iterations")
+                .short('n')
+                .long("iterations")
+                .value_name("NUM")
+                .action(ArgAction::Set)
+                .default_value("10")
+                .value_parser(clap::value_parser!(usize))
+                .help("The number of times to run the search"),
+        )
+        .arg(
+            Arg::new("vacuous-truth")
+                .long("vacuous-truth")
+                .action(ArgAction::SetTrue)
+                .help("Satisfy universal quantifiers over an empty domain"),
+        )
+        .arg(
+            Arg::new("vacuous-end")
+                .long("vacuous-end")
+                .action(ArgAction::SetTrue)
+                .help("Satisfy temporal operators past the end of the data stream"),
+        )
+        .arg(
+            Arg::new("strict-bindings")
+                .long("strict-bindings")
+                .action(ArgAction::SetTrue)
+                .help("Reject patterns with shadowed or unused bindings"),
+        )
+        .arg(
+            Arg::new("classmap")
+                .long("classmap")
+                .value_name("FILE")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(PathBuf))
+                .help("Group labels under super-classes from `FILE`"),
+        )
+}
+
+/// Build the `generate` subcommand.
+///
+/// This subcommand produces a randomized data stream from a scenario and writes
+/// the result to standard output.
+fn generate() -> Command {
+    Command::new("generate")
+        .about("Generate a randomized data stream from a scenario")
+        .arg(
+            Arg::new("SCENARIO")
+                .required(true)
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(PathBuf))
+                .help("The scenario of the data stream to generate"),
+        )
+        .arg(
+            Arg::new("seed")
+                .long("seed")
+                .value_name("NUM")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(u64))
+                .help("Override the seed of the scenario"),
+        )
+}
+
+/// Build the `serve` subcommand.
+///
+/// This subcommand runs a server that searches data streams submitted to it
+/// and returns their matches as JSON over HTTP, or streams them over gRPC.
+fn serve() -> Command {
+    Command::new("serve")
+        .about("Serve searches over HTTP or gRPC")
+        .group(
+            ArgGroup::new("listen")
+                .args(["http", "grpc"])
+                .required(true)
+                .multiple(true),
+        )
+        .arg(
+            Arg::new("http")
+                .long("http")
+                .value_name("ADDRESS")
+                .action(ArgAction::Set)
+                .num_args(0..=1)
+                .default_missing_value("127.0.0.1:8080")
+                .value_parser(clap::value_parser!(String))
+                .help("Listen for HTTP requests on `ADDRESS` (default: `127.0.0.1:8080`)"),
+        )
+        .arg(
+            Arg::new("grpc")
+                .long("grpc")
+                .value_name("ADDRESS")
+                .action(ArgAction::Set)
+                .num_args(0..=1)
+                .default_missing_value("127.0.0.1:50051")
+                .value_parser(clap::value_parser!(String))
+                .help("Listen for gRPC streams on `ADDRESS` (default: `127.0.0.1:50051`)"),
+        )
+        .arg(
+            Arg::new("root")
+                .long("root")
+                .value_name("DIR")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(PathBuf))
+                .help("Serve the data streams stored in `DIR` by path"),
+        )
+        .arg(
+            Arg::new("window")
+                .long("window")
+                .value_name("NUM")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(usize))
+                .help("Keep at most `NUM` frames for each query of a gRPC stream"),
+        )
+        .arg(
+            Arg::new("ontology")
+                .long("ontology")
+                .value_name("FILE")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(PathBuf))
+                .help("Map dataset labels to canonical labels from `FILE`"),
+        )
+        .arg(
+            Arg::new("vacuous-truth")
+                .long("vacuous-truth")
+                .action(ArgAction::SetTrue)
+                .help("Satisfy universal quantifiers over an empty domain"),
+        )
+        .arg(
+            Arg::new("vacuous-end")
+                .long("vacuous-end")
+                .action(ArgAction::SetTrue)
+                .help("Satisfy temporal operators past the end of the data stream"),
+        )
+        .arg(
+            Arg::new("strict-bindings")
+                .long("strict-bindings")
+                .action(ArgAction::SetTrue)
+                .help("Reject patterns with shadowed or unused bindings"),
+        )
+        .arg(
+            Arg::new("classmap")
+                .long("classmap")
+                .value_name("FILE")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(PathBuf))
+                .help("Group labels under super-classes from `FILE`"),
+        )
+}
+
+/// Build the `daemon` subcommand.
+///
+/// This subcommand searches several live sources with several persistent
+/// queries in a single process.
+fn daemon() -> Command {
+    Command::new("daemon")
+        .about("Search live sources with persistent queries")
+        .arg(
+            Arg::new("QUERIES")
+                .required(true)
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(PathBuf))
+                .help("The file of the queries (i.e., an `id` and `pattern` of each)"),
+        )
+        .arg(
+            Arg::new("SOURCE")
+                .action(ArgAction::Append)
+                .value_parser(clap::value_parser!(String))
+                .help("The sources to search (a path, `tcp://HOST:PORT`, or `-` for stdin)"),
+        )
+        .arg(
+            Arg::new("window")
+                .long("window")
+                .value_name("NUM")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(usize))
+                .help("Keep at most `NUM` frames for each query"),
+        )
+        .arg(
+            Arg::new("ontology")
+                .long("ontology")
+                .value_name("FILE")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(PathBuf))
+                .help("Map dataset labels to canonical labels from `FILE`"),
+        )
+        .arg(
+            Arg::new("vacuous-truth")
+                .long("vacuous-truth")
+                .action(ArgAction::SetTrue)
+                .help("Satisfy universal quantifiers over an empty domain"),
+        )
+        .arg(
+            Arg::new("vacuous-end")
+                .long("vacuous-end")
+                .action(ArgAction::SetTrue)
+                .help("Satisfy temporal operators past the end of the data stream"),
+        )
+        .arg(
+            Arg::new("strict-bindings")
+                .long("strict-bindings")
+                .action(ArgAction::SetTrue)
+                .help("Reject patterns with shadowed or unused bindings"),
+        )
+        .arg(
+            Arg::new("classmap")
+                .long("classmap")
+                .value_name("FILE")
+                .action(ArgAction::Set)
+                .value_parser(clap::value_parser!(PathBuf))
+                .help("Group labels under super-classes from `FILE`"),
+        )
+}
+
+/// Parse an Intersection over Union (IoU) threshold.
+///
+/// This must be within `0.0..=1.0`; therefore, `NaN` is rejected as well.
+fn iou(value: &str) -> Result<f64, String> {
+    let threshold: f64 = value.parse().map_err(|e| format!("{}", e))?;
+
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(format!("`{}` is not within 0.0..=1.0", value));
+    }
+
+    Ok(threshold)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn iou() {
+        let nms = |value: &str| {
+            super::build()
+                .try_get_matches_from(["strem", "[[:car:]]", "data.json", "--nms", value])
+                .map(|matches| matches.get_one::<f64>("nms").copied())
+        };
+
+        assert_eq!(nms("0.5").unwrap(), Some(0.5));
+        assert_eq!(nms("1").unwrap(), Some(1.0));
+
+        assert!(nms("1.5").is_err());
+        assert!(nms("-0.1").is_err());
+        assert!(nms("NaN").is_err());
+        assert!(nms("car").is_err());
+    }
+
+    #[test]
+    fn merge_channels() {
+        let matches = super::build().try_get_matches_from([
+            "strem",
+            "[[:car:]]",
+            "data.json",
+            "--merge-channels",
+            "--export",
+        ]);
+
+        assert!(matches.is_err());
+    }
 }
@@ -18,8 +18,8 @@ fn main() {
     let app = App::new(cli::build().get_matches());
 
     match app.run() {
-        Ok(Status::MatchFound) => process::exit(0),
-        Ok(Status::MatchNotFound) => process::exit(1),
+        Ok(Some(Status::MatchFound)) | Ok(None) => process::exit(0),
+        Ok(Some(Status::MatchNotFound)) => process::exit(1),
         Err(e) => {
             eprintln!("strem: error: {}", e);
             process::exit(2);
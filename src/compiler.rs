@@ -3,33 +3,79 @@
 
 use std::error::Error;
 
+use crate::config::classmap::ClassMap;
 use crate::symbolizer::ast::SymbolicAbstractSyntaxTree;
 use crate::symbolizer::Symbolizer;
 
+use self::analyzer::semantic::SemanticAnalyzer;
+use self::analyzer::types::{TypeChecker, TypeError};
+use self::ir::ops::Vacuity;
 use self::lexer::stream::CharStream;
 use self::lexer::Lexer;
-use self::listener::ErrorListener;
+use self::listener::{Diagnostic, Diagnostics, ErrorListener};
+use self::optimizer::Optimizer;
 use self::parser::Parser;
 
 pub mod analyzer;
 pub mod ir;
 pub mod lexer;
 pub mod listener;
+pub mod optimizer;
 pub mod parser;
 
-const ALPHABET: [char; 52] = [
-    'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', 'p', 'q', 'r', 's',
-    't', 'u', 'v', 'w', 'x', 'y', 'z', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L',
-    'M', 'N', 'O', 'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z',
-];
-
-#[derive(Default)]
-pub struct Compiler {}
+#[derive(Clone, Default)]
+pub struct Compiler {
+    vacuity: Vacuity,
+    end: Vacuity,
+    strict: bool,
+    classmap: Option<ClassMap>,
+}
 
 impl Compiler {
     /// Create a new [`Compiler`].
     pub fn new() -> Self {
-        Compiler {}
+        Compiler {
+            vacuity: Vacuity::default(),
+            end: Vacuity::default(),
+            strict: false,
+            classmap: None,
+        }
+    }
+
+    /// Reject patterns that fail semantic analysis.
+    ///
+    /// By default, semantic issues (e.g., unused bindings) are reported as
+    /// warnings and compilation continues.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Set the [`Vacuity`] of universal quantifiers.
+    ///
+    /// This determines whether a universal quantifier over an empty domain is
+    /// satisfied.
+    pub fn vacuity(mut self, vacuity: Vacuity) -> Self {
+        self.vacuity = vacuity;
+        self
+    }
+
+    /// Set the [`Vacuity`] of temporal operators.
+    ///
+    /// This determines whether a temporal operator (i.e., `G` or `U`) is
+    /// satisfied past the end of the data stream.
+    pub fn end(mut self, end: Vacuity) -> Self {
+        self.end = end;
+        self
+    }
+
+    /// Set the [`ClassMap`] of super-classes.
+    ///
+    /// A class of the pattern that is a super-class refers to any of its
+    /// labels (see [`Parser::classmap`]).
+    pub fn classmap(mut self, classmap: Option<ClassMap>) -> Self {
+        self.classmap = classmap;
+        self
     }
 
     /// Compile a Spatial Regular Expression (SpRE) into an Abstract Syntax
@@ -40,14 +86,52 @@ impl Compiler {
     pub fn compile(&self, source: &str) -> Result<SymbolicAbstractSyntaxTree, Box<dyn Error>> {
         let stream = CharStream::from(source);
 
-        let mut lexer = Lexer::new(stream).attach(ErrorListener::new());
+        // Collect the syntax errors of each stage.
+        //
+        // The parser recovers from the errors of the lexer (i.e., the skipped
+        // characters); therefore, all of them are reported together.
+        let mut diagnostics = Diagnostics::new(source);
+
+        let mut lexer = Lexer::new(stream);
         let stream = lexer.lex();
+        diagnostics.extend(lexer.diagnostics());
+
+        let mut parser = Parser::new(stream)
+            .vacuity(self.vacuity)
+            .end(self.end)
+            .classmap(self.classmap.clone());
+
+        let ast = match parser.parse() {
+            Ok(ast) if diagnostics.is_empty() => ast,
+            Ok(_) => return Err(Box::new(diagnostics)),
+            Err(errors) => {
+                diagnostics.extend(errors.iter().map(Diagnostic::from));
+                return Err(Box::new(diagnostics));
+            }
+        };
+
+        // Check the types of each spatial formula.
+        //
+        // Unlike semantic issues, these are always errors, as the formula
+        // cannot be evaluated otherwise.
+        let errors = TypeChecker::new().check(&ast);
+        if !errors.is_empty() {
+            return Err(Box::new(TypeError::from(errors)));
+        }
+
+        let listener = ErrorListener::new();
+        for issue in SemanticAnalyzer::new().analyze(&ast) {
+            if self.strict {
+                return Err(Box::new(issue));
+            }
+
+            listener.report(issue.to_string());
+        }
 
-        let mut parser = Parser::new(stream).attach(ErrorListener::new());
-        let ast = parser.parse();
+        let ast = Optimizer::new().optimize(ast);
 
-        let mut symbolizer = Symbolizer::new(&self::ALPHABET);
-        let ast = symbolizer.symbolize(ast)?;
+        let mut symbolizer = Symbolizer::new();
+        let ast = symbolizer.symbolize(ast);
 
         Ok(ast)
     }
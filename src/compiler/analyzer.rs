@@ -2,3 +2,4 @@
 //!
 
 pub mod semantic;
+pub mod types;
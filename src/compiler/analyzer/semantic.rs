@@ -1,7 +1,21 @@
 //! Semantic analysis framework for SpREs.
 //!
+//! Currently, the analyzer checks the use of quantified variables. In
+//! particular, it detects bindings that shadow a variable of an enclosing
+//! quantifier and bindings that are never referenced.
 
-use super::super::ir::ast::AbstractSyntaxTree;
+use std::error::Error;
+use std::fmt;
+
+use super::super::ir::ast::{AbstractSyntaxTree, OperandKind, SpatialFormula};
+use super::super::ir::ops::{Bindings, Operator, S4uOperatorKind, SpatialOperatorKind};
+use super::super::ir::{Node, NodeId, Tree};
+
+/// The variables bound by a single quantifier.
+///
+/// Each entry tracks the name of the variable and whether it has been
+/// referenced within the scope of the quantifier.
+type Scope = Vec<(String, bool)>;
 
 #[derive(Default)]
 pub struct SemanticAnalyzer {}
@@ -11,8 +25,201 @@ impl SemanticAnalyzer {
         SemanticAnalyzer {}
     }
 
-    #[allow(unused_variables)]
-    pub fn analyze(&self, tree: &AbstractSyntaxTree) {
-        std::unimplemented!()
+    /// Analyze an [`AbstractSyntaxTree`].
+    ///
+    /// This returns the list of issues found in the order they are found. It
+    /// is left to the caller to decide whether to treat them as warnings or
+    /// errors.
+    pub fn analyze(&self, tree: &AbstractSyntaxTree) -> Vec<SemanticError> {
+        let mut issues = Vec::new();
+
+        if let Some(root) = &tree.root {
+            self.analyzeit(root, root.root(), &mut issues);
+        }
+
+        issues
+    }
+
+    /// Recursively visit the regular expression nodes.
+    ///
+    /// Each spatial formula starts with an empty set of scopes, as variables
+    /// may not be shared across frames.
+    fn analyzeit(
+        &self,
+        tree: &Tree<SpatialFormula>,
+        node: NodeId,
+        issues: &mut Vec<SemanticError>,
+    ) {
+        match &tree[node] {
+            Node::Operand(formula) => {
+                self.formula(formula, formula.root(), &mut Vec::new(), issues)
+            }
+            Node::UnaryExpr { child, .. } => self.analyzeit(tree, *child, issues),
+            Node::BinaryExpr { lhs, rhs, .. } => {
+                self.analyzeit(tree, *lhs, issues);
+                self.analyzeit(tree, *rhs, issues);
+            }
+        }
+    }
+
+    /// Recursively visit the spatial formula nodes.
+    fn formula(
+        &self,
+        formula: &SpatialFormula,
+        node: NodeId,
+        scopes: &mut Vec<Scope>,
+        issues: &mut Vec<SemanticError>,
+    ) {
+        match &formula[node] {
+            Node::Operand(OperandKind::Variable(name)) => {
+                // Mark the variable as used.
+                //
+                // A reference resolves to the innermost quantifier that binds
+                // the variable, accordingly.
+                if let Some(entry) = scopes
+                    .iter_mut()
+                    .rev()
+                    .find_map(|s| s.iter_mut().find(|(v, _)| v == name))
+                {
+                    entry.1 = true;
+                }
+            }
+            Node::Operand(_) => {}
+            Node::UnaryExpr { op, child } => match op {
+                Operator::SpatialOperator(SpatialOperatorKind::S4uOperator(
+                    S4uOperatorKind::Exists(b) | S4uOperatorKind::Forall(b, _),
+                )) => self.quantifier(formula, b, *child, None, scopes, issues),
+                Operator::SpatialOperator(SpatialOperatorKind::S4uOperator(
+                    S4uOperatorKind::Capture(name),
+                )) => match &formula[*child] {
+                    // The captured variable is used by the capture itself.
+                    Node::UnaryExpr {
+                        op:
+                            Operator::SpatialOperator(SpatialOperatorKind::S4uOperator(
+                                S4uOperatorKind::Exists(b),
+                            )),
+                        child,
+                    } => self.quantifier(formula, b, *child, Some(name), scopes, issues),
+                    _ => self.formula(formula, *child, scopes, issues),
+                },
+                _ => self.formula(formula, *child, scopes, issues),
+            },
+            Node::BinaryExpr { lhs, rhs, .. } => {
+                self.formula(formula, *lhs, scopes, issues);
+                self.formula(formula, *rhs, scopes, issues);
+            }
+        }
+    }
+
+    /// Visit the bindings and the body of a quantifier.
+    ///
+    /// The `captured` variable, if any, is considered used.
+    fn quantifier(
+        &self,
+        formula: &SpatialFormula,
+        b: &Bindings,
+        child: NodeId,
+        captured: Option<&String>,
+        scopes: &mut Vec<Scope>,
+        issues: &mut Vec<SemanticError>,
+    ) {
+        // Sort the variables.
+        //
+        // The bindings are stored in a map, so a stable order is needed for
+        // issues to be reported deterministically.
+        let mut variables: Vec<(&String, &SpatialFormula)> = b.table.iter().collect();
+        variables.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut scope = Scope::new();
+        for (v, class) in variables {
+            // The class is resolved against the enclosing scopes.
+            self.formula(class, class.root(), scopes, issues);
+
+            if scopes.iter().any(|s| s.iter().any(|(x, _)| x == v)) {
+                issues.push(SemanticError::from(format!(
+                    "variable `{}` shadows a variable of an enclosing quantifier",
+                    v
+                )));
+            }
+
+            scope.push((v.clone(), captured == Some(v)));
+        }
+
+        scopes.push(scope);
+        self.formula(formula, child, scopes, issues);
+
+        for (v, used) in scopes.pop().unwrap_or_default() {
+            if !used {
+                issues.push(SemanticError::from(format!(
+                    "variable `{}` is bound but never used",
+                    v
+                )));
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SemanticError {
+    msg: String,
+}
+
+impl From<&str> for SemanticError {
+    fn from(msg: &str) -> Self {
+        SemanticError {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for SemanticError {
+    fn from(msg: String) -> Self {
+        SemanticError { msg }
+    }
+}
+
+impl fmt::Display for SemanticError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "semantic: {}", self.msg)
+    }
+}
+
+impl Error for SemanticError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::compiler::lexer::stream::CharStream;
+    use crate::compiler::lexer::Lexer;
+    use crate::compiler::parser::Parser;
+
+    fn analyze(source: &str) -> Vec<String> {
+        let stream = Lexer::new(CharStream::from(source)).lex();
+        let ast = Parser::new(stream).parse().unwrap();
+
+        SemanticAnalyzer::new()
+            .analyze(&ast)
+            .iter()
+            .map(|e| e.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn bindings() {
+        assert!(analyze("[E(x := [:car:]) @x(x) > 0]").is_empty());
+
+        assert_eq!(
+            analyze("[E(x := [:car:]) E(x := [:truck:]) @x(x) > 0]"),
+            vec![
+                "semantic: variable `x` shadows a variable of an enclosing quantifier",
+                "semantic: variable `x` is bound but never used",
+            ]
+        );
+
+        assert_eq!(
+            analyze("[A(x := [:car:], y := [:car:]) @x(x) > 0]"),
+            vec!["semantic: variable `y` is bound but never used"]
+        );
     }
 }
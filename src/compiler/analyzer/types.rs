@@ -0,0 +1,380 @@
+//! Type checking framework for SpREs.
+//!
+//! A spatial formula mixes three kinds of expressions: formulas that are
+//! satisfied (or not), regions (i.e., sets of annotations), and numbers. The
+//! checker verifies that each operator is applied to the kind of expressions
+//! it expects, and that each function is known and given the right number of
+//! arguments. Otherwise, the monitors would fail while searching.
+
+use std::error::Error;
+use std::fmt;
+
+use super::super::ir::ast::{AbstractSyntaxTree, OperandKind, SpatialFormula};
+use super::super::ir::ops::{
+    FolOperatorKind, Operator, S4OperatorKind, S4mOperatorKind, S4uOperatorKind,
+    SpatialOperatorKind,
+};
+use super::super::ir::{Node, NodeId};
+
+/// The functions of S4m expressions and their number of arguments.
+///
+/// A function may be overloaded by its number of arguments (e.g., `@dist(a)`
+/// is the distance to the origin, and `@dist(a, b)` the distance between).
+const FUNCTIONS: &[(&str, usize)] = &[
+    ("x", 1),
+    ("y", 1),
+    ("area", 1),
+    ("volume", 1),
+    ("score", 1),
+    ("count", 1),
+    ("dx", 1),
+    ("dy", 1),
+    ("speed", 1),
+    ("dist", 1),
+    ("dist", 2),
+    ("dist2", 1),
+    ("dist2", 2),
+    ("dist3", 1),
+    ("dist3", 2),
+    ("inter_area", 2),
+    ("union_area", 2),
+    ("ratio", 2),
+    ("hausdorff", 2),
+    ("angle", 2),
+    ("relx", 2),
+    ("rely", 2),
+];
+
+/// The kind of an expression.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Type {
+    /// An expression that is satisfied or not (e.g., a comparison).
+    Formula,
+
+    /// A set of annotations (e.g., a class or variable).
+    Region,
+
+    /// A real number (e.g., a function or constant).
+    Number,
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Type::Formula => write!(f, "a formula"),
+            Type::Region => write!(f, "a region"),
+            Type::Number => write!(f, "a number"),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct TypeChecker {}
+
+impl TypeChecker {
+    pub fn new() -> Self {
+        TypeChecker {}
+    }
+
+    /// Check an [`AbstractSyntaxTree`].
+    ///
+    /// This returns the list of errors found in the order they are found.
+    pub fn check(&self, tree: &AbstractSyntaxTree) -> Vec<TypeError> {
+        let mut errors = Vec::new();
+
+        if let Some(root) = &tree.root {
+            for formula in root.operands() {
+                self.expect(formula, formula.root(), Type::Formula, &mut errors);
+            }
+        }
+
+        errors
+    }
+
+    /// Recursively check that a subtree is of the expected [`Type`].
+    fn expect(
+        &self,
+        formula: &SpatialFormula,
+        node: NodeId,
+        expected: Type,
+        errors: &mut Vec<TypeError>,
+    ) {
+        let found = match &formula[node] {
+            Node::Operand(operand) => match (operand, expected) {
+                // A class is also a formula (i.e., the class is present).
+                (
+                    OperandKind::Symbol(_) | OperandKind::Classes(_),
+                    Type::Formula | Type::Region,
+                ) => return,
+                (OperandKind::Variable(_) | OperandKind::Reference(_), Type::Region) => return,
+                (OperandKind::Number(_), Type::Number) => return,
+                (OperandKind::Empty(_), Type::Formula) => return,
+                (
+                    OperandKind::Symbol(_)
+                    | OperandKind::Classes(_)
+                    | OperandKind::Variable(_)
+                    | OperandKind::Reference(_),
+                    _,
+                ) => Type::Region,
+                (OperandKind::Number(_), _) => Type::Number,
+                (OperandKind::Empty(_), _) => Type::Formula,
+            },
+            Node::UnaryExpr { op, child } => match self::unary(op) {
+                Some((found, argument)) if found == expected => {
+                    // Check the classes of the bindings of a quantifier.
+                    if let Operator::SpatialOperator(SpatialOperatorKind::S4uOperator(
+                        S4uOperatorKind::Exists(b) | S4uOperatorKind::Forall(b, _),
+                    )) = op
+                    {
+                        let mut variables = b.table.iter().collect::<Vec<_>>();
+                        variables.sort_by(|a, b| a.0.cmp(b.0));
+
+                        for (_, class) in variables {
+                            self.expect(class, class.root(), Type::Region, errors);
+                        }
+                    }
+
+                    if let Operator::SpatialOperator(SpatialOperatorKind::S4mOperator(
+                        S4mOperatorKind::Function(name),
+                    )) = op
+                    {
+                        self.function(name, 1, errors);
+                    }
+
+                    self.expect(formula, *child, argument, errors);
+                    return;
+                }
+                Some((found, _)) => found,
+                None => {
+                    errors.push(TypeError::from(format!(
+                        "unsupported unary operator `{:?}`",
+                        op
+                    )));
+                    return;
+                }
+            },
+            Node::BinaryExpr { op, lhs, rhs } => match self::binary(op) {
+                Some((found, argument)) if found == expected => {
+                    if let Operator::SpatialOperator(SpatialOperatorKind::S4mOperator(
+                        S4mOperatorKind::Function(name),
+                    )) = op
+                    {
+                        self.function(name, 2, errors);
+                    }
+
+                    self.expect(formula, *lhs, argument, errors);
+                    self.expect(formula, *rhs, argument, errors);
+                    return;
+                }
+                Some((found, _)) => found,
+                None => {
+                    errors.push(TypeError::from(format!(
+                        "unsupported binary operator `{:?}`",
+                        op
+                    )));
+                    return;
+                }
+            },
+        };
+
+        errors.push(TypeError::from(format!(
+            "expected {}, but found {}",
+            expected, found
+        )));
+    }
+
+    /// Check that a function is known and given the number of arguments.
+    fn function(&self, name: &str, arity: usize, errors: &mut Vec<TypeError>) {
+        let arities = FUNCTIONS
+            .iter()
+            .filter(|(f, _)| *f == name)
+            .map(|(_, n)| *n)
+            .collect::<Vec<usize>>();
+
+        if arities.is_empty() {
+            errors.push(TypeError::from(format!("unknown function `@{}`", name)));
+        } else if !arities.contains(&arity) {
+            errors.push(TypeError::from(format!(
+                "function `@{}` expects {} argument(s), but found {}",
+                name,
+                arities
+                    .iter()
+                    .map(|n| n.to_string())
+                    .collect::<Vec<String>>()
+                    .join(" or "),
+                arity
+            )));
+        }
+    }
+}
+
+/// The [`Type`] of a unary expression and of its argument.
+fn unary(op: &Operator) -> Option<(Type, Type)> {
+    match op {
+        Operator::SpatialOperator(op) => match op {
+            SpatialOperatorKind::FolOperator(FolOperatorKind::Negation) => {
+                Some((Type::Formula, Type::Formula))
+            }
+            SpatialOperatorKind::S4uOperator(op) => match op {
+                S4uOperatorKind::NonEmpty | S4uOperatorKind::Text(_) | S4uOperatorKind::Same => {
+                    Some((Type::Formula, Type::Region))
+                }
+                S4uOperatorKind::Exists(_)
+                | S4uOperatorKind::Forall(..)
+                | S4uOperatorKind::Capture(_)
+                | S4uOperatorKind::Channel(_) => Some((Type::Formula, Type::Formula)),
+                _ => None,
+            },
+            SpatialOperatorKind::S4mOperator(op) => match op {
+                S4mOperatorKind::Inverse => Some((Type::Number, Type::Number)),
+                S4mOperatorKind::Function(_) => Some((Type::Number, Type::Region)),
+                _ => None,
+            },
+            SpatialOperatorKind::S4Operator(S4OperatorKind::Complement) => {
+                Some((Type::Region, Type::Region))
+            }
+            _ => None,
+        },
+        Operator::RegexOperator(_) => None,
+    }
+}
+
+/// The [`Type`] of a binary expression and of its arguments.
+fn binary(op: &Operator) -> Option<(Type, Type)> {
+    match op {
+        Operator::SpatialOperator(op) => match op {
+            SpatialOperatorKind::FolOperator(op) => match op {
+                FolOperatorKind::Conjunction | FolOperatorKind::Disjunction => {
+                    Some((Type::Formula, Type::Formula))
+                }
+                FolOperatorKind::LessThan
+                | FolOperatorKind::GreaterThan
+                | FolOperatorKind::LessThanEqualTo
+                | FolOperatorKind::GreaterThanEqualTo => Some((Type::Formula, Type::Number)),
+                FolOperatorKind::Negation => None,
+            },
+            SpatialOperatorKind::S4uOperator(op) => match op {
+                S4uOperatorKind::Within
+                | S4uOperatorKind::Touch(_)
+                | S4uOperatorKind::Relation(_) => Some((Type::Formula, Type::Region)),
+                _ => None,
+            },
+            SpatialOperatorKind::S4mOperator(op) => match op {
+                S4mOperatorKind::Addition
+                | S4mOperatorKind::Subtraction
+                | S4mOperatorKind::Multiplication
+                | S4mOperatorKind::Division => Some((Type::Number, Type::Number)),
+                S4mOperatorKind::Function(_) => Some((Type::Number, Type::Region)),
+                S4mOperatorKind::Inverse => None,
+            },
+            SpatialOperatorKind::S4Operator(op) => match op {
+                S4OperatorKind::Intersection | S4OperatorKind::Union => {
+                    Some((Type::Region, Type::Region))
+                }
+                S4OperatorKind::Complement => None,
+            },
+            SpatialOperatorKind::SolOperator(_) => None,
+        },
+        Operator::RegexOperator(_) => None,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TypeError {
+    msg: String,
+}
+
+impl From<&str> for TypeError {
+    fn from(msg: &str) -> Self {
+        TypeError {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for TypeError {
+    fn from(msg: String) -> Self {
+        TypeError { msg }
+    }
+}
+
+impl From<Vec<TypeError>> for TypeError {
+    /// Combine several [`TypeError`] into one, listed in order.
+    fn from(errors: Vec<TypeError>) -> Self {
+        if errors.len() == 1 {
+            return errors.into_iter().next().unwrap();
+        }
+
+        let msgs: Vec<String> = errors.into_iter().map(|e| e.msg).collect();
+
+        TypeError::from(format!(
+            "found {} error(s):\n  {}",
+            msgs.len(),
+            msgs.join("\n  ")
+        ))
+    }
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "type: {}", self.msg)
+    }
+}
+
+impl Error for TypeError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::compiler::ir::Tree;
+    use crate::compiler::lexer::stream::CharStream;
+    use crate::compiler::lexer::Lexer;
+    use crate::compiler::parser::Parser;
+
+    fn check(source: &str) -> Vec<String> {
+        let stream = Lexer::new(CharStream::from(source)).lex();
+        let ast = Parser::new(stream).parse().unwrap();
+
+        TypeChecker::new()
+            .check(&ast)
+            .iter()
+            .map(|e| e.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn functions() {
+        assert!(check("[E(a := [:car:], b := [:car:]) @dist(a) < @dist(a, b)]").is_empty());
+
+        assert_eq!(
+            check("[@velocity([:car:]) > 1][@area([:car:], [:truck:]) > 1]"),
+            vec![
+                "type: unknown function `@velocity`",
+                "type: function `@area` expects 1 argument(s), but found 2",
+            ]
+        );
+    }
+
+    #[test]
+    fn operands() {
+        let mut tree = Tree::binary(
+            Operator::SpatialOperator(SpatialOperatorKind::FolOperator(FolOperatorKind::LessThan)),
+            OperandKind::Number(1.0),
+            OperandKind::Variable(String::from("x")),
+        );
+        tree = Tree::unary(
+            Operator::SpatialOperator(SpatialOperatorKind::S4uOperator(S4uOperatorKind::NonEmpty)),
+            tree,
+        );
+
+        let ast = AbstractSyntaxTree::new(Some(Tree::from(tree)));
+        let errors = TypeChecker::new()
+            .check(&ast)
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<String>>();
+
+        assert_eq!(errors, vec!["type: expected a region, but found a formula"]);
+    }
+}
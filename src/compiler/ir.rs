@@ -4,52 +4,218 @@
 pub mod ast;
 pub mod ops;
 
+use std::ops::Index;
+
 use self::ops::Operator;
 
-/// Generic representation of an AST.
+/// A typed index of a [`Node`] within a [`Tree`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId(u32);
+
+/// A single node of a [`Tree`].
 ///
-/// This AST is used as an Intermediate Representation (IR) of expressions that
-/// support unary and binary operator expressions.
-#[derive(Debug)]
+/// Rather than owning its children, an expression refers to them by their
+/// [`NodeId`] within the same [`Tree`].
+#[derive(Clone, Debug)]
 pub enum Node<T> {
     Operand(T),
     UnaryExpr {
         op: Operator,
-        child: Box<Self>,
+        child: NodeId,
     },
     BinaryExpr {
         op: Operator,
-        lhs: Box<Self>,
-        rhs: Box<Self>,
+        lhs: NodeId,
+        rhs: NodeId,
     },
 }
 
-impl<T> From<T> for Node<T> {
+/// Generic representation of an AST.
+///
+/// This AST is used as an Intermediate Representation (IR) of expressions that
+/// support unary and binary operator expressions. All nodes are stored in a
+/// single arena in post-order (i.e., children before their parent), such that
+/// each subtree is contiguous and the root is the last node. As the AST is
+/// traversed once per frame per symbol during monitoring, this keeps it
+/// compact in memory, and cloning it is a single allocation.
+#[derive(Clone, Debug)]
+pub struct Tree<T> {
+    nodes: Vec<Node<T>>,
+}
+
+impl<T> From<T> for Tree<T> {
     fn from(value: T) -> Self {
-        Node::Operand(value)
+        Tree {
+            nodes: vec![Node::Operand(value)],
+        }
     }
 }
 
-impl<T> Node<T> {
+impl<T> Index<NodeId> for Tree<T> {
+    type Output = Node<T>;
+
+    fn index(&self, id: NodeId) -> &Self::Output {
+        &self.nodes[id.0 as usize]
+    }
+}
+
+impl<T> Tree<T> {
     pub fn unary<C>(op: Operator, child: C) -> Self
     where
-        C: Into<Node<T>>,
+        C: Into<Tree<T>>,
     {
-        Node::UnaryExpr {
-            op,
-            child: Box::new(child.into()),
-        }
+        let mut tree = child.into();
+        let child = tree.root();
+
+        tree.nodes.push(Node::UnaryExpr { op, child });
+        tree
     }
 
     pub fn binary<L, R>(op: Operator, lhs: L, rhs: R) -> Self
     where
-        L: Into<Node<T>>,
-        R: Into<Node<T>>,
+        L: Into<Tree<T>>,
+        R: Into<Tree<T>>,
+    {
+        let mut tree = lhs.into();
+        let lhs = tree.root();
+
+        // Append the right subtree.
+        //
+        // The indices of the right subtree are relative to its own arena, so
+        // they are offset by the number of nodes that precede it.
+        let offset = tree.nodes.len() as u32;
+
+        tree.nodes
+            .extend(rhs.into().nodes.into_iter().map(|node| match node {
+                Node::Operand(value) => Node::Operand(value),
+                Node::UnaryExpr { op, child } => Node::UnaryExpr {
+                    op,
+                    child: NodeId(child.0 + offset),
+                },
+                Node::BinaryExpr { op, lhs, rhs } => Node::BinaryExpr {
+                    op,
+                    lhs: NodeId(lhs.0 + offset),
+                    rhs: NodeId(rhs.0 + offset),
+                },
+            }));
+
+        let rhs = tree.root();
+
+        tree.nodes.push(Node::BinaryExpr { op, lhs, rhs });
+        tree
+    }
+
+    /// The [`NodeId`] of the root node.
+    pub fn root(&self) -> NodeId {
+        NodeId(self.nodes.len() as u32 - 1)
+    }
+
+    /// The operands of the [`Tree`] from left to right.
+    pub fn operands(&self) -> impl Iterator<Item = &T> {
+        self.nodes.iter().filter_map(|node| match node {
+            Node::Operand(value) => Some(value),
+            _ => None,
+        })
+    }
+
+    /// The nodes of the [`Tree`] in post-order.
+    pub fn nodes(&self) -> impl Iterator<Item = &Node<T>> {
+        self.nodes.iter()
+    }
+
+    /// Copy the subtree rooted at a [`NodeId`] into a new [`Tree`].
+    ///
+    /// As the nodes are stored in post-order, the subtree is the contiguous
+    /// range of nodes that ends at its root.
+    pub fn subtree(&self, node: NodeId) -> Tree<T>
+    where
+        T: Clone,
     {
-        Node::BinaryExpr {
-            op,
-            lhs: Box::new(lhs.into()),
-            rhs: Box::new(rhs.into()),
+        let mut start = node;
+        loop {
+            match &self[start] {
+                Node::Operand(..) => break,
+                Node::UnaryExpr { child, .. } => start = *child,
+                Node::BinaryExpr { lhs, .. } => start = *lhs,
+            }
+        }
+
+        let offset = start.0;
+        let nodes = self.nodes[(start.0 as usize)..=(node.0 as usize)]
+            .iter()
+            .map(|node| match node {
+                Node::Operand(value) => Node::Operand(value.clone()),
+                Node::UnaryExpr { op, child } => Node::UnaryExpr {
+                    op: op.clone(),
+                    child: NodeId(child.0 - offset),
+                },
+                Node::BinaryExpr { op, lhs, rhs } => Node::BinaryExpr {
+                    op: op.clone(),
+                    lhs: NodeId(lhs.0 - offset),
+                    rhs: NodeId(rhs.0 - offset),
+                },
+            })
+            .collect();
+
+        Tree { nodes }
+    }
+
+    /// Transform each operand of the [`Tree`] from left to right.
+    ///
+    /// The structure of the [`Tree`] is preserved.
+    pub fn map<U, F>(self, mut f: F) -> Tree<U>
+    where
+        F: FnMut(T) -> U,
+    {
+        let nodes = self
+            .nodes
+            .into_iter()
+            .map(|node| match node {
+                Node::Operand(value) => Node::Operand(f(value)),
+                Node::UnaryExpr { op, child } => Node::UnaryExpr { op, child },
+                Node::BinaryExpr { op, lhs, rhs } => Node::BinaryExpr { op, lhs, rhs },
+            })
+            .collect();
+
+        Tree { nodes }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ops::{Operator, RegexOperatorKind};
+    use super::{Node, Tree};
+
+    #[test]
+    fn binary() {
+        let lhs = Tree::unary(Operator::RegexOperator(RegexOperatorKind::KleeneStar), 'a');
+        let rhs = Tree::binary(
+            Operator::RegexOperator(RegexOperatorKind::Alternation),
+            'b',
+            'c',
+        );
+
+        let tree = Tree::binary(
+            Operator::RegexOperator(RegexOperatorKind::Concatenation),
+            lhs,
+            rhs,
+        );
+
+        assert_eq!(tree.operands().collect::<String>(), "abc");
+
+        match &tree[tree.root()] {
+            Node::BinaryExpr { lhs, rhs, .. } => {
+                assert!(matches!(tree[*lhs], Node::UnaryExpr { .. }));
+
+                match &tree[*rhs] {
+                    Node::BinaryExpr { lhs, rhs, .. } => {
+                        assert!(matches!(tree[*lhs], Node::Operand('b')));
+                        assert!(matches!(tree[*rhs], Node::Operand('c')));
+                    }
+                    _ => panic!("expected a binary expression"),
+                }
+            }
+            _ => panic!("expected a binary expression"),
         }
     }
 }
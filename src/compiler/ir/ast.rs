@@ -1,28 +1,115 @@
 //! Abstract Syntax Tree (AST) representation.
 //!
 
-use super::super::ir::Node;
+use regex_automata::meta::Regex;
 
-pub type SpatialFormula = Node<OperandKind>;
+use crate::datastream::frame::sample::detections::label::Label;
+
+use super::super::ir::Tree;
+
+pub type SpatialFormula = Tree<OperandKind>;
 
 /// The operands within the AST.
 ///
 /// These kinds of operands are equivalent to the types of data that is stored on
 /// the leaf nodes of the AST.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum OperandKind {
-    Symbol(String),
+    Symbol(Label),
+
+    /// Any class of a set of classes (e.g., `[:car|truck:]` or `[:vehicle.*:]`).
+    Classes(Classes),
     Number(f64),
     Variable(String),
+
+    /// The object captured by name elsewhere in the pattern (i.e., `\v`).
+    ///
+    /// This is resolved against the object bound to the capture during a
+    /// search, and is empty if that object is not within the frame.
+    Reference(String),
+
+    /// No detections within the frame, or within its samples of a channel.
+    ///
+    /// This is only supported as a spatial formula of its own (i.e., `[empty]`).
+    Empty(Option<String>),
+}
+
+/// A set of class labels, given by a pattern over their names.
+///
+/// The pattern is a list of alternatives separated by `|`, where each is a
+/// glob whose `*` matches any (possibly empty) sequence of characters.
+#[derive(Clone, Debug)]
+pub struct Classes {
+    /// The pattern as written (e.g., `car|truck`).
+    pub source: String,
+
+    regex: Regex,
+}
+
+impl Classes {
+    /// Create a new set of [`Classes`] from a pattern.
+    ///
+    /// An error is returned if the pattern (or an alternative of it) is empty.
+    pub fn new(source: &str) -> Result<Self, String> {
+        let mut alternatives = Vec::new();
+
+        for alternative in source.split('|') {
+            if alternative.is_empty() {
+                return Err(format!("empty class within `{}`", source));
+            }
+
+            // Translate the glob into a Regular Expression.
+            //
+            // All other characters are matched literally.
+            alternatives.push(
+                alternative
+                    .split('*')
+                    .map(regex_syntax::escape)
+                    .collect::<Vec<String>>()
+                    .join(".*"),
+            );
+        }
+
+        Self::build(source, &alternatives)
+    }
+
+    /// Create a new set of [`Classes`] of exactly the listed labels.
+    ///
+    /// The `source` is the name the set is referred to by, and is a label of
+    /// the set itself (e.g., a super-class of a
+    /// [`ClassMap`](crate::config::classmap::ClassMap)).
+    pub fn of(source: &str, labels: &[String]) -> Result<Self, String> {
+        let alternatives = std::iter::once(source)
+            .chain(labels.iter().map(String::as_str))
+            .map(regex_syntax::escape)
+            .collect::<Vec<String>>();
+
+        Self::build(source, &alternatives)
+    }
+
+    fn build(source: &str, alternatives: &[String]) -> Result<Self, String> {
+        let regex = Regex::new(&format!("^(?:{})$", alternatives.join("|")))
+            .map_err(|e| format!("invalid class `{}`: {}", source, e))?;
+
+        Ok(Classes {
+            source: source.to_string(),
+            regex,
+        })
+    }
+
+    /// Check if a [`Label`] is within the set of [`Classes`].
+    pub fn contains(&self, label: &Label) -> bool {
+        self.regex.is_match(label.as_str())
+    }
 }
 
 #[derive(Debug)]
 pub struct AbstractSyntaxTree {
-    pub root: Option<Node<SpatialFormula>>,
+    pub root: Option<Tree<SpatialFormula>>,
 }
 
 impl AbstractSyntaxTree {
-    pub fn new(root: Option<Node<SpatialFormula>>) -> Self {
+    pub fn new(root: Option<Tree<SpatialFormula>>) -> Self {
         Self { root }
     }
 }
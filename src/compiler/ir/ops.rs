@@ -1,25 +1,66 @@
 use std::collections::HashMap;
 
+use regex_automata::meta::Regex;
+
 use super::ast::SpatialFormula;
 
 /// Operations kinds supported.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum Operator {
     RegexOperator(RegexOperatorKind),
     SpatialOperator(SpatialOperatorKind),
 }
 
 /// The set of Regular Expression operations allowed in a query.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum RegexOperatorKind {
     KleeneStar,
     Concatenation,
     Alternation,
     Range(RangeKind),
+
+    /// The frames that follow must not begin with a match of the operand.
+    ///
+    /// This is an assertion that consumes no frames (i.e., `(?!...)`).
+    NegativeLookahead,
+
+    /// The frames that precede must end with a match of the operand.
+    ///
+    /// This is an assertion that consumes no frames (i.e., `(?<=...)`).
+    PositiveLookbehind,
+
+    /// Both operands must match the same frames (i.e., `&&`).
+    ///
+    /// This is only supported at the top level of a pattern.
+    Conjunction,
+
+    /// The operand must hold at some frame within the interval of frames
+    /// from the first (i.e., `F[a,b]`).
+    Eventually(usize, usize),
+
+    /// The operand must hold at every frame within the interval of frames
+    /// from the first (i.e., `G[a,b]`).
+    ///
+    /// With [`Vacuity::Classical`], it also holds if the data stream ends
+    /// within the interval and the operand holds at every frame before then.
+    Globally(usize, usize, Vacuity),
+
+    /// The right operand must hold at some frame within the interval of frames
+    /// from the first, and the left operand at every frame before it (i.e.,
+    /// `U[a,b]`).
+    ///
+    /// With [`Vacuity::Classical`], it also holds if the data stream ends
+    /// within the interval and the left operand holds at every frame before
+    /// then.
+    Until(usize, usize, Vacuity),
+
+    /// The right operand must begin within the number of frames after the left
+    /// operand ends, where any frames may occur in between (i.e., `~{<=k}`).
+    Gap(usize),
 }
 
 /// Range operator kinds.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum RangeKind {
     Exactly(usize),
     AtLeast(usize),
@@ -33,7 +74,7 @@ pub enum RangeKind {
 /// non-spatial expressions (e.g., alternation and disjunction). Therefore,
 /// these enumerations provide semantic meaning for symbolically
 /// equivalent operators.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum SpatialOperatorKind {
     FolOperator(FolOperatorKind),
     SolOperator(SolOperatorKind),
@@ -46,7 +87,7 @@ pub enum SpatialOperatorKind {
 ///
 /// For more information on FOL, please see:
 /// [Stanford Encyclopedia of Philosophy: Classical Logic](https://plato.stanford.edu/entries/logic-classical/)
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum FolOperatorKind {
     Negation,
     Conjunction,
@@ -61,7 +102,7 @@ pub enum FolOperatorKind {
 ///
 /// For more information on SOL, please see:
 /// [Stanford Encyclopedia of Philosophy: Second-order and Higher-order logic](https://plato.stanford.edu/entries/logic-higher-order/)
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum SolOperatorKind {
     Exists,
 }
@@ -70,18 +111,133 @@ pub enum SolOperatorKind {
 ///
 /// For more information on S4, please see:
 /// [Combining Spatial and Temporal Logics: Expressiveness vs. Complexity](https://arxiv.org/abs/1)
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum S4uOperatorKind {
     NonEmpty,
-    Exists(HashMap<String, SpatialFormula>),
-    Forall(HashMap<String, SpatialFormula>),
+    Exists(Bindings),
+    Forall(Bindings, Vacuity),
+    Text(TextPredicate),
+
+    /// The region of the lhs is entirely inside the region of the rhs.
+    Within,
+
+    /// The regions touch, but do not overlap, within a tolerance.
+    Touch(f64),
+
+    /// The region of the lhs is entirely to one side of the region of the rhs.
+    Relation(Relation),
+
+    /// The object of a variable of the existential quantifier beneath is
+    /// captured by name, such that it may be referenced in other frames.
+    Capture(String),
+
+    /// The variable is bound to the same tracked object in every frame of a
+    /// match, as if the object were captured by the name of the variable.
+    Same,
+
+    /// The formula is only evaluated against the samples of the channel (i.e.,
+    /// `[CHANNEL: ...]`).
+    ///
+    /// This is always the root of its spatial formula.
+    Channel(String),
+}
+
+/// A condition over the recognized text of an annotation.
+#[derive(Clone, Debug)]
+pub enum TextPredicate {
+    /// The text is exactly the string.
+    Exact(String),
+
+    /// The text contains a match of the Regular Expression.
+    Pattern(Regex),
+}
+
+impl TextPredicate {
+    /// Check if the text satisfies the [`TextPredicate`].
+    pub fn holds(&self, text: &str) -> bool {
+        match self {
+            TextPredicate::Exact(s) => text == s,
+            TextPredicate::Pattern(re) => re.is_match(text),
+        }
+    }
+}
+
+/// The side of a region that another region is on.
+///
+/// The y-axis is taken to point downwards, as it does in an image, such that a
+/// region is above another if it has lesser y-coordinates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Relation {
+    LeftOf,
+    RightOf,
+    Above,
+    Below,
+}
+
+impl Relation {
+    /// Find the [`Relation`] of the name of its predicate, if any.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "leftof" => Some(Relation::LeftOf),
+            "rightof" => Some(Relation::RightOf),
+            "above" => Some(Relation::Above),
+            "below" => Some(Relation::Below),
+            _ => None,
+        }
+    }
+
+    /// The name of the predicate of the [`Relation`].
+    pub fn name(&self) -> &'static str {
+        match self {
+            Relation::LeftOf => "leftof",
+            Relation::RightOf => "rightof",
+            Relation::Above => "above",
+            Relation::Below => "below",
+        }
+    }
+}
+
+/// The truth value of a universal quantifier over an empty domain.
+///
+/// This also applies, separately, to the frames of a temporal operator after
+/// the end of the data stream (e.g., of `G[a,b]`), as these are similarly
+/// empty.
+///
+/// By default, the [`Vacuity::Strict`] semantics are used.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Vacuity {
+    /// The quantifier is false if no valuations exist.
+    #[default]
+    Strict,
+
+    /// The quantifier is (vacuously) true if no valuations exist.
+    Classical,
+}
+
+/// A set of quantified variables.
+///
+/// Each variable is bound to the class (i.e., set of annotations) that it
+/// ranges over.
+#[derive(Clone, Debug)]
+pub struct Bindings {
+    pub table: HashMap<String, SpatialFormula>,
+
+    /// Require all variables to bind to different annotations.
+    pub distinct: bool,
+}
+
+impl Bindings {
+    /// Create a new set of [`Bindings`].
+    pub fn new(table: HashMap<String, SpatialFormula>, distinct: bool) -> Self {
+        Bindings { table, distinct }
+    }
 }
 
 /// S4m operators.
 ///
 /// For more information on S4m, please see:
 ///
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum S4mOperatorKind {
     Function(String),
     Inverse,
@@ -95,7 +251,7 @@ pub enum S4mOperatorKind {
 ///
 /// For more information on S4, please see:
 /// [Combining Spatial and Temporal Logics: Expressiveness vs. Complexity](https://arxiv.org/abs/1110.2726)
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub enum S4OperatorKind {
     Intersection,
     Union,
@@ -4,7 +4,7 @@
 use std::error::Error;
 use std::fmt;
 
-use super::listener::ErrorListener;
+use super::listener::{Diagnostic, ErrorListener};
 
 use self::stream::{CharStream, TokenStream};
 use self::token::TokenKind::*;
@@ -20,6 +20,9 @@ pub struct Lexer {
     current: usize,
     line: usize,
     column: usize,
+
+    /// The errors found so far, in order of position.
+    errors: Vec<LexerError>,
 }
 
 impl Lexer {
@@ -32,6 +35,7 @@ impl Lexer {
             current: 0,
             line: 1,
             column: 0,
+            errors: Vec::new(),
         }
     }
 
@@ -39,7 +43,8 @@ impl Lexer {
     ///
     /// This attachment allows for better syntactical error reporting by the
     /// lexical analysis process based on the provided listener. If an error
-    /// listener is not attached to the [`Lexer`], it panics.
+    /// listener is not attached to the [`Lexer`], the errors are kept instead
+    /// (see [`Self::diagnostics`]).
     pub fn attach(mut self, listener: ErrorListener) -> Self {
         self.listener = Some(listener);
         self
@@ -53,7 +58,48 @@ impl Lexer {
     /// In addition, an [`EndOfFile`] token is appended to the resulting token
     /// stream. Therefore, all returned [`TokenStream`]s will always have at
     /// least this token.
+    ///
+    /// If any errors are found and a listener is attached, all of them are
+    /// reported together (with their positions) as a fatal error once the
+    /// whole stream is analyzed.
     pub fn lex(&mut self) -> TokenStream {
+        let tokens = self.scan();
+
+        if let (false, Some(listener)) = (self.errors.is_empty(), &self.listener) {
+            let errors: Vec<String> = self
+                .errors
+                .iter()
+                .map(|e| format!("{}:{}: {}", e.position.0, e.position.1, e.msg))
+                .collect();
+
+            listener.exit(
+                format!(
+                    "lexer: found {} error(s):\n  {}",
+                    errors.len(),
+                    errors.join("\n  ")
+                ),
+                1,
+            );
+        }
+
+        tokens
+    }
+
+    /// Retrieve the errors found by the last analysis as [`Diagnostic`].
+    ///
+    /// Each error spans the single character that is skipped.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        self.errors
+            .iter()
+            .map(|e| Diagnostic::new(Some(e.position.clone()), 1, e.msg.clone()))
+            .collect()
+    }
+
+    /// Tokenize the complete [`CharStream`].
+    ///
+    /// Errors do not stop the analysis; instead, they are collected and the
+    /// offending characters are skipped.
+    fn scan(&mut self) -> TokenStream {
         let mut tokens = TokenStream::new();
 
         while !self.eof() {
@@ -64,23 +110,23 @@ impl Lexer {
                     Some(token) => tokens.push(token),
                     None => continue,
                 },
-                Err(_) => match &self.listener {
-                    Some(listener) => {
-                        listener.report(format!(
-                            "lexer: ignoring unrecognized character: `{}`.",
-                            self.stream.buffer[self.current - 1]
-                        ));
-                    }
-                    None => panic!(),
-                },
+                Err(e) => self.error(e.to_string()),
             }
         }
 
         // end token stream with EndOfFile
-        tokens.push(Token::eof(Position(self.line, self.current - self.column)));
+        tokens.push(Token::eof(self.position(self.current)));
         tokens
     }
 
+    /// Record an error at the start of the current token.
+    fn error(&mut self, msg: String) {
+        self.errors.push(LexerError {
+            msg,
+            position: self.position(self.base),
+        });
+    }
+
     /// Retrieve the next potential token from the [`CharStream`].
     ///
     /// A token is optionally returned. If a whitespace character is observed,
@@ -116,22 +162,32 @@ impl Lexer {
                 _ => Ok(self.tokenize(Colon)),
             },
             '*' => Ok(self.tokenize(Star)),
+            '.' => Ok(self.tokenize(Dot)),
             '%' => Ok(self.tokenize(Percent)),
+            '#' => Ok(self.tokenize(Hash)),
             '!' => Ok(self.tokenize(Not)),
-            '&' => Ok(self.tokenize(And)),
+            '&' => match self.peek(0) {
+                Some('&') => {
+                    self.advance();
+                    Ok(self.tokenize(AndAnd))
+                }
+                _ => Ok(self.tokenize(And)),
+            },
             '|' => Ok(self.tokenize(Or)),
             '@' => Ok(self.tokenize(At)),
             '-' => Ok(self.tokenize(Minus)),
             '+' => Ok(self.tokenize(Plus)),
             '/' => Ok(self.tokenize(Slash)),
+            '=' => Ok(self.tokenize(Equal)),
+            '~' => Ok(self.tokenize(Tilde)),
+            '?' => Ok(self.tokenize(Question)),
+            '\\' => Ok(self.tokenize(Backslash)),
+            '"' => Ok(self.literalify()),
             '\n' => Ok(self.newline()),
             ' ' | '\r' | '\t' => Ok(self.skip(0)),
             '0'..='9' => Ok(self.numberify()),
             'a'..='z' | 'A'..='Z' | '_' => Ok(self.identifierify()),
-            c => Err(Box::new(LexerError::from(format!(
-                "unknown character `{}'",
-                c
-            )))),
+            c => Err(format!("unknown character `{}`", c).into()),
         }
     }
 
@@ -145,11 +201,7 @@ impl Lexer {
             .take(self.current - self.base)
             .collect();
 
-        Some(Token::new(
-            kind,
-            Position(self.line, self.base - self.column),
-            lexeme,
-        ))
+        Some(Token::new(kind, self.position(self.base), lexeme))
     }
 
     /// Advance the [`current`](Lexer::current), greedily consuming number characters.
@@ -212,6 +264,45 @@ impl Lexer {
         None
     }
 
+    /// Advance the [`current`](Lexer::current), consuming a quoted string.
+    ///
+    /// The lexeme of the resulting [`Literal`] excludes the quotes. Within the
+    /// quotes, a backslash escapes a quote or another backslash; any other
+    /// backslash is kept as-is (e.g., to write Regular Expressions).
+    ///
+    /// If the string is unterminated, an error is recorded and the string ends
+    /// at the end of the line.
+    fn literalify(&mut self) -> Option<Token> {
+        let mut lexeme = String::new();
+
+        loop {
+            match self.peek(0) {
+                Some('"') => {
+                    self.advance();
+                    break;
+                }
+                Some('\\') if matches!(self.peek(1), Some('"' | '\\')) => {
+                    self.advance();
+                    lexeme.push(self.advance());
+                }
+                Some('\n') | None => {
+                    self.error(String::from("unterminated string"));
+                    break;
+                }
+                Some(_) => lexeme.push(self.advance()),
+            }
+        }
+
+        Some(Token::new(Literal, self.position(self.base), lexeme))
+    }
+
+    /// Locate an offset of the [`CharStream`] within the current line.
+    ///
+    /// Columns are counted from one, like lines.
+    fn position(&self, offset: usize) -> Position {
+        Position(self.line, offset - self.column + 1)
+    }
+
     /// Lookahead a number of characters into the character stream.
     ///
     /// If zero is provided, this will peek the current character due to the
@@ -258,25 +349,16 @@ impl Lexer {
 #[derive(Debug, Clone)]
 struct LexerError {
     msg: String,
-}
-
-impl From<&str> for LexerError {
-    fn from(msg: &str) -> Self {
-        LexerError {
-            msg: msg.to_string(),
-        }
-    }
-}
-
-impl From<String> for LexerError {
-    fn from(msg: String) -> Self {
-        LexerError { msg }
-    }
+    position: Position,
 }
 
 impl fmt::Display for LexerError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "lexer: {}", self.msg)
+        write!(
+            f,
+            "lexer: {}:{}: {}",
+            self.position.0, self.position.1, self.msg
+        )
     }
 }
 
@@ -294,7 +376,7 @@ mod tests {
         assert_eq!(
             Token {
                 kind: TokenKind::LeftParen,
-                position: Position(1, 0),
+                position: Position(1, 1),
                 lexeme: String::from("(")
             },
             lexer.next().ok().unwrap().unwrap()
@@ -304,7 +386,7 @@ mod tests {
         assert_eq!(
             Token {
                 kind: TokenKind::LeftParen,
-                position: Position(1, 1),
+                position: Position(1, 2),
                 lexeme: String::from("(")
             },
             lexer.next().ok().unwrap().unwrap()
@@ -314,10 +396,41 @@ mod tests {
         assert_eq!(
             Token {
                 kind: TokenKind::LeftParen,
-                position: Position(1, 2),
+                position: Position(1, 3),
                 lexeme: String::from("(")
             },
             lexer.next().ok().unwrap().unwrap()
         );
     }
+
+    #[test]
+    fn lex_literal() {
+        let mut lexer = Lexer::new(CharStream::from(r#""a\"b\d""#));
+
+        lexer.base = lexer.current;
+        assert_eq!(
+            Token {
+                kind: TokenKind::Literal,
+                position: Position(1, 1),
+                lexeme: String::from(r#"a"b\d"#)
+            },
+            lexer.next().ok().unwrap().unwrap()
+        );
+    }
+
+    #[test]
+    fn collect_errors() {
+        let mut lexer = Lexer::new(CharStream::from("[$:car:]\n[@text(x) = \"a"));
+        lexer.scan();
+
+        let errors: Vec<String> = lexer
+            .errors
+            .iter()
+            .map(|e| format!("{}:{}: {}", e.position.0, e.position.1, e.msg))
+            .collect();
+        assert_eq!(
+            errors,
+            vec!["1:2: unknown character `$`", "2:13: unterminated string"]
+        );
+    }
 }
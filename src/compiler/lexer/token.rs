@@ -13,10 +13,13 @@ pub enum TokenKind {
     RightChevron,
     Comma,
     Colon,
+    Dot,
     Star,
     Percent,
+    Hash,
     Not,
     And,
+    AndAnd,
     Or,
     EndOfFile,
     Integer,
@@ -32,12 +35,17 @@ pub enum TokenKind {
     Slash,
     LeftChevronEqual,
     RightChevronEqual,
+    Equal,
+    Tilde,
+    Question,
+    Backslash,
+    Literal,
 }
 
 /// Locational information used in a [`Token`].
 ///
 /// This includes the row and the column number where the token begins.
-/// **Note**: The beginning of the source is located at (1, 0).
+/// **Note**: The beginning of the source is located at (1, 1).
 #[derive(Clone, Debug, PartialEq)]
 pub struct Position(pub usize, pub usize);
 
@@ -2,9 +2,17 @@
 //!
 //! This error listener is contextualized for the [compiler](../index.html). Therefore, its
 //! usage outside the scope of this is not tested.
+//!
+//! In addition, the [`Diagnostics`] of a pattern are collected here, such that
+//! every syntax error is reported at once with the span of the pattern it is
+//! found at underlined.
 
+use std::error::Error;
+use std::fmt;
 use std::process;
 
+use super::lexer::token::Position;
+
 /// Interface to handle errors.
 ///
 /// This is a general error listener that can be attached to any process that
@@ -34,3 +42,185 @@ impl ErrorListener {
         process::exit(code);
     }
 }
+
+/// A problem found within the source of a pattern.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    /// The position of the source that the problem is found at, if known.
+    pub position: Option<Position>,
+
+    /// The number of characters of the source that the problem spans.
+    pub length: usize,
+
+    pub msg: String,
+}
+
+impl Diagnostic {
+    /// Create a new [`Diagnostic`].
+    pub fn new(position: Option<Position>, length: usize, msg: String) -> Self {
+        Diagnostic {
+            position,
+            length,
+            msg,
+        }
+    }
+}
+
+/// A collector of the [`Diagnostic`] of a pattern.
+///
+/// Each diagnostic is rendered with the line of the source it is found within,
+/// where its span is underlined (similar to `rustc`):
+///
+/// ```text
+/// error: 1:9: syntax error
+///   |
+/// 1 | [[:a:] & ]
+///   |          ^
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Diagnostics {
+    source: String,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    /// Create a new [`Diagnostics`] of the source of a pattern.
+    pub fn new(source: &str) -> Self {
+        Diagnostics {
+            source: source.to_string(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Collect a set of [`Diagnostic`].
+    ///
+    /// The diagnostics are kept in order of their position, such that those of
+    /// different stages (e.g., the lexer and the parser) are interleaved. A
+    /// diagnostic with the same position and message as one already collected
+    /// is skipped.
+    pub fn extend(&mut self, diagnostics: impl IntoIterator<Item = Diagnostic>) {
+        for diagnostic in diagnostics {
+            if !self
+                .diagnostics
+                .iter()
+                .any(|d| d.position == diagnostic.position && d.msg == diagnostic.msg)
+            {
+                self.diagnostics.push(diagnostic);
+            }
+        }
+
+        self.diagnostics.sort_by_key(|d| {
+            d.position
+                .as_ref()
+                .map(|p| (p.0, p.1))
+                .unwrap_or((usize::MAX, usize::MAX))
+        });
+    }
+
+    /// Check if no [`Diagnostic`] is collected.
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    /// Retrieve the collected [`Diagnostic`], in order.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Render a single [`Diagnostic`] with its span of the source.
+    fn render(&self, diagnostic: &Diagnostic) -> String {
+        let Some(Position(line, column)) = diagnostic.position else {
+            return format!("error: {}", diagnostic.msg);
+        };
+
+        // Lines are counted from one; therefore, a position beyond the source
+        // (e.g., of the end of the source) is shown on the last line.
+        let text = self
+            .source
+            .lines()
+            .nth(line.saturating_sub(1))
+            .unwrap_or_default();
+
+        let gutter = " ".repeat(line.to_string().len());
+
+        format!(
+            "error: {}:{}: {}\n{} |\n{} | {}\n{} | {}{}",
+            line,
+            column,
+            diagnostic.msg,
+            gutter,
+            line,
+            text,
+            gutter,
+            " ".repeat(column.saturating_sub(1)),
+            "^".repeat(diagnostic.length.max(1))
+        )
+    }
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "found {} error(s):", self.diagnostics.len())?;
+
+        for diagnostic in self.diagnostics.iter() {
+            write!(f, "\n\n{}", self.render(diagnostic))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Error for Diagnostics {}
+
+#[cfg(test)]
+mod tests {
+    use crate::compiler::Compiler;
+
+    use super::Diagnostics;
+
+    #[test]
+    fn render() {
+        let e = Compiler::new()
+            .compile("[[:a:] & ]\n[[:b:]] $[[:c:]]")
+            .err()
+            .unwrap();
+
+        assert_eq!(
+            e.to_string(),
+            [
+                "found 2 error(s):",
+                "",
+                "error: 1:10: syntax error",
+                "  |",
+                "1 | [[:a:] & ]",
+                "  |          ^",
+                "",
+                "error: 2:9: unknown character `$`",
+                "  |",
+                "2 | [[:b:]] $[[:c:]]",
+                "  |         ^",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[test]
+    fn duplicates() {
+        let errors = |pattern: &str| match Compiler::new().compile(pattern) {
+            Err(e) => e
+                .downcast_ref::<Diagnostics>()
+                .unwrap()
+                .diagnostics()
+                .iter()
+                .map(|d| d.msg.clone())
+                .collect::<Vec<_>>(),
+            Ok(_) => Vec::new(),
+        };
+
+        assert_eq!(errors("[@x([:car:]) == 1.0]"), ["syntax error"]);
+        assert_eq!(
+            errors("[[:a:]]{"),
+            ["expected RightBrace but found EndOfFile"]
+        );
+    }
+}
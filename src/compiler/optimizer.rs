@@ -0,0 +1,311 @@
+//! Simplification of spatial formulas.
+//!
+//! A spatial formula is evaluated against every frame of a data stream;
+//! therefore, any redundancy in a hand-written formula (e.g., constant
+//! arithmetic) is paid for repeatedly. The optimizer rewrites each formula into
+//! an equivalent, simpler one before it is symbolized.
+
+use super::ir::ast::{AbstractSyntaxTree, OperandKind, SpatialFormula};
+use super::ir::ops::{
+    Bindings, FolOperatorKind, Operator, S4OperatorKind, S4mOperatorKind, S4uOperatorKind,
+    SpatialOperatorKind,
+};
+use super::ir::{Node, NodeId, Tree};
+
+/// An associative (and idempotent) binary operator.
+///
+/// A chain of these operators may be regrouped and its duplicate operands
+/// removed without changing its meaning.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Chain {
+    Conjunction,
+    Disjunction,
+    Intersection,
+    Union,
+}
+
+impl Chain {
+    fn of(op: &Operator) -> Option<Self> {
+        match op {
+            Operator::SpatialOperator(SpatialOperatorKind::FolOperator(op)) => match op {
+                FolOperatorKind::Conjunction => Some(Chain::Conjunction),
+                FolOperatorKind::Disjunction => Some(Chain::Disjunction),
+                _ => None,
+            },
+            Operator::SpatialOperator(SpatialOperatorKind::S4Operator(op)) => match op {
+                S4OperatorKind::Intersection => Some(Chain::Intersection),
+                S4OperatorKind::Union => Some(Chain::Union),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Optimizer {}
+
+impl Optimizer {
+    pub fn new() -> Self {
+        Optimizer {}
+    }
+
+    /// Simplify each spatial formula of an [`AbstractSyntaxTree`].
+    ///
+    /// This folds constant arithmetic, removes double negations, and flattens
+    /// chains of conjunctions, disjunctions, intersections, and unions such
+    /// that duplicate operands are evaluated only once.
+    pub fn optimize(&self, ast: AbstractSyntaxTree) -> AbstractSyntaxTree {
+        AbstractSyntaxTree::new(
+            ast.root
+                .map(|root| root.map(|formula| self.formula(&formula, formula.root()))),
+        )
+    }
+
+    /// Recursively simplify the subtree of a spatial formula.
+    fn formula(&self, formula: &SpatialFormula, node: NodeId) -> SpatialFormula {
+        match &formula[node] {
+            Node::Operand(operand) => Tree::from(operand.clone()),
+            Node::UnaryExpr { op, child } => {
+                let op = self.operator(op);
+                let child = self.formula(formula, *child);
+
+                match (&op, &child[child.root()]) {
+                    // Remove a double negation (i.e., `!!x` is `x`).
+                    (
+                        Operator::SpatialOperator(SpatialOperatorKind::FolOperator(
+                            FolOperatorKind::Negation,
+                        )),
+                        Node::UnaryExpr {
+                            op:
+                                Operator::SpatialOperator(SpatialOperatorKind::FolOperator(
+                                    FolOperatorKind::Negation,
+                                )),
+                            child: inner,
+                        },
+                    ) => child.subtree(*inner),
+                    (
+                        Operator::SpatialOperator(SpatialOperatorKind::S4mOperator(
+                            S4mOperatorKind::Inverse,
+                        )),
+                        Node::Operand(OperandKind::Number(x)),
+                    ) => Tree::from(OperandKind::Number(-x)),
+                    _ => Tree::unary(op, child),
+                }
+            }
+            Node::BinaryExpr { op, lhs, rhs } => {
+                let lhs = self.formula(formula, *lhs);
+                let rhs = self.formula(formula, *rhs);
+
+                if let Some(chain) = Chain::of(op) {
+                    // Flatten the chain into its operands.
+                    //
+                    // The operands are kept in order, such that the first of
+                    // any duplicates remains, and are regrouped to the left.
+                    let mut operands: Vec<SpatialFormula> = Vec::new();
+
+                    for operand in self::chain(lhs, chain)
+                        .into_iter()
+                        .chain(self::chain(rhs, chain))
+                    {
+                        if !operands
+                            .iter()
+                            .any(|x| self::equal(x, x.root(), &operand, operand.root()))
+                        {
+                            operands.push(operand);
+                        }
+                    }
+
+                    return operands
+                        .into_iter()
+                        .reduce(|acc, x| Tree::binary(op.clone(), acc, x))
+                        .unwrap();
+                }
+
+                let (a, b) = match (&lhs[lhs.root()], &rhs[rhs.root()]) {
+                    (
+                        Node::Operand(OperandKind::Number(a)),
+                        Node::Operand(OperandKind::Number(b)),
+                    ) => (*a, *b),
+                    _ => return Tree::binary(op.clone(), lhs, rhs),
+                };
+
+                // Fold constant arithmetic.
+                //
+                // This follows the evaluation of the monitor (e.g., a division
+                // by zero is infinite rather than an error).
+                match op {
+                    Operator::SpatialOperator(SpatialOperatorKind::S4mOperator(kind)) => {
+                        let value = match kind {
+                            S4mOperatorKind::Addition => a + b,
+                            S4mOperatorKind::Subtraction => a - b,
+                            S4mOperatorKind::Multiplication => a * b,
+                            S4mOperatorKind::Division => a / b,
+                            _ => return Tree::binary(op.clone(), lhs, rhs),
+                        };
+
+                        Tree::from(OperandKind::Number(value))
+                    }
+                    _ => Tree::binary(op.clone(), lhs, rhs),
+                }
+            }
+        }
+    }
+
+    /// Simplify the classes of the bindings of a quantifier, if any.
+    fn operator(&self, op: &Operator) -> Operator {
+        let bindings = |b: &Bindings| {
+            Bindings::new(
+                b.table
+                    .iter()
+                    .map(|(v, class)| (v.clone(), self.formula(class, class.root())))
+                    .collect(),
+                b.distinct,
+            )
+        };
+
+        match op {
+            Operator::SpatialOperator(SpatialOperatorKind::S4uOperator(
+                S4uOperatorKind::Exists(b),
+            )) => Operator::SpatialOperator(SpatialOperatorKind::S4uOperator(
+                S4uOperatorKind::Exists(bindings(b)),
+            )),
+            Operator::SpatialOperator(SpatialOperatorKind::S4uOperator(
+                S4uOperatorKind::Forall(b, vacuity),
+            )) => Operator::SpatialOperator(SpatialOperatorKind::S4uOperator(
+                S4uOperatorKind::Forall(bindings(b), *vacuity),
+            )),
+            _ => op.clone(),
+        }
+    }
+}
+
+/// Split a formula into the operands of a [`Chain`], from left to right.
+///
+/// If the root of the formula is not the operator of the chain, the formula
+/// is the only operand.
+fn chain(formula: SpatialFormula, kind: Chain) -> Vec<SpatialFormula> {
+    match &formula[formula.root()] {
+        Node::BinaryExpr { op, lhs, rhs } if Chain::of(op) == Some(kind) => {
+            let mut operands = self::chain(formula.subtree(*lhs), kind);
+            operands.extend(self::chain(formula.subtree(*rhs), kind));
+            operands
+        }
+        _ => vec![formula],
+    }
+}
+
+/// Check if two subtrees are structurally equal.
+///
+/// This is conservative: operators that carry more than a kind (e.g.,
+/// quantifiers and text predicates) are never considered equal.
+fn equal(a: &SpatialFormula, x: NodeId, b: &SpatialFormula, y: NodeId) -> bool {
+    match (&a[x], &b[y]) {
+        (Node::Operand(p), Node::Operand(q)) => match (p, q) {
+            (OperandKind::Symbol(p), OperandKind::Symbol(q)) => p == q,
+            (OperandKind::Classes(p), OperandKind::Classes(q)) => p.source == q.source,
+            (OperandKind::Number(p), OperandKind::Number(q)) => p == q,
+            (OperandKind::Variable(p), OperandKind::Variable(q)) => p == q,
+            (OperandKind::Reference(p), OperandKind::Reference(q)) => p == q,
+            _ => false,
+        },
+        (Node::UnaryExpr { op: p, child: c }, Node::UnaryExpr { op: q, child: d }) => {
+            self::same(p, q) && self::equal(a, *c, b, *d)
+        }
+        (
+            Node::BinaryExpr {
+                op: p,
+                lhs: l1,
+                rhs: r1,
+            },
+            Node::BinaryExpr {
+                op: q,
+                lhs: l2,
+                rhs: r2,
+            },
+        ) => self::same(p, q) && self::equal(a, *l1, b, *l2) && self::equal(a, *r1, b, *r2),
+        _ => false,
+    }
+}
+
+/// Check if two operators are the same.
+fn same(p: &Operator, q: &Operator) -> bool {
+    use FolOperatorKind as Fol;
+    use S4OperatorKind as S4;
+    use S4mOperatorKind as S4m;
+    use S4uOperatorKind as S4u;
+
+    let (Operator::SpatialOperator(p), Operator::SpatialOperator(q)) = (p, q) else {
+        return false;
+    };
+
+    match (p, q) {
+        (SpatialOperatorKind::FolOperator(p), SpatialOperatorKind::FolOperator(q)) => matches!(
+            (p, q),
+            (Fol::Negation, Fol::Negation)
+                | (Fol::Conjunction, Fol::Conjunction)
+                | (Fol::Disjunction, Fol::Disjunction)
+                | (Fol::LessThan, Fol::LessThan)
+                | (Fol::GreaterThan, Fol::GreaterThan)
+                | (Fol::LessThanEqualTo, Fol::LessThanEqualTo)
+                | (Fol::GreaterThanEqualTo, Fol::GreaterThanEqualTo)
+        ),
+        (SpatialOperatorKind::S4uOperator(p), SpatialOperatorKind::S4uOperator(q)) => {
+            match (p, q) {
+                (S4u::NonEmpty, S4u::NonEmpty)
+                | (S4u::Within, S4u::Within)
+                | (S4u::Same, S4u::Same) => true,
+                (S4u::Touch(a), S4u::Touch(b)) => a == b,
+                (S4u::Relation(a), S4u::Relation(b)) => a == b,
+                (S4u::Channel(a), S4u::Channel(b)) => a == b,
+                _ => false,
+            }
+        }
+        (SpatialOperatorKind::S4mOperator(p), SpatialOperatorKind::S4mOperator(q)) => {
+            match (p, q) {
+                (S4m::Function(a), S4m::Function(b)) => a == b,
+                _ => matches!(
+                    (p, q),
+                    (S4m::Inverse, S4m::Inverse)
+                        | (S4m::Addition, S4m::Addition)
+                        | (S4m::Subtraction, S4m::Subtraction)
+                        | (S4m::Multiplication, S4m::Multiplication)
+                        | (S4m::Division, S4m::Division)
+                ),
+            }
+        }
+        (SpatialOperatorKind::S4Operator(p), SpatialOperatorKind::S4Operator(q)) => matches!(
+            (p, q),
+            (S4::Intersection, S4::Intersection)
+                | (S4::Union, S4::Union)
+                | (S4::Complement, S4::Complement)
+        ),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compiler::Compiler;
+    use crate::monitor::explain::describe;
+
+    fn optimize(source: &str) -> String {
+        let ast = Compiler::new().compile(source).unwrap();
+        let formula = &ast.fmap()[0].formula;
+
+        describe(formula, formula.root())
+    }
+
+    #[test]
+    fn simplify() {
+        assert_eq!(
+            optimize("[@x([:car:]) > (2.0 * 3.0) + -1.0]"),
+            "@x([:car:]) > 5.0"
+        );
+        assert_eq!(optimize("[!!NE([:car:])]"), "NE([:car:])");
+        assert_eq!(
+            optimize("[NE([:car:]) & (NE([:truck:]) & NE([:car:])) & !NE([:bus:])]"),
+            "(NE([:car:]) & NE([:truck:])) & !NE([:bus:])"
+        );
+    }
+}
@@ -4,25 +4,76 @@
 //! (CFG) definition. For grammar details, see relevant function documentation.
 
 use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
 
-use super::ir::ast::{AbstractSyntaxTree, OperandKind, SpatialFormula};
+use itertools::Itertools;
+use regex_automata::meta::Regex;
+
+use crate::config::classmap::ClassMap;
+use crate::datastream::frame::sample::detections::label::Label;
+
+use super::ir::ast::{AbstractSyntaxTree, Classes, OperandKind, SpatialFormula};
 use super::ir::ops::{
-    FolOperatorKind, Operator, RangeKind, RegexOperatorKind, S4OperatorKind, S4mOperatorKind,
-    S4uOperatorKind, SpatialOperatorKind,
+    Bindings, FolOperatorKind, Operator, RangeKind, RegexOperatorKind, Relation, S4OperatorKind,
+    S4mOperatorKind, S4uOperatorKind, SpatialOperatorKind, TextPredicate, Vacuity,
 };
-use super::ir::Node;
+use super::ir::{Node, NodeId, Tree};
 use super::lexer::stream::TokenStream;
-use super::lexer::token::{Token, TokenKind, TokenKind::*};
-use super::listener::ErrorListener;
+use super::lexer::token::{Position, Token, TokenKind, TokenKind::*};
+use super::listener::Diagnostic;
+
+/// The maximum number of lookarounds within a pattern.
+///
+/// The position of each lookaround is marked within the automaton of the
+/// pattern by its own digit.
+const MAX_LOOKAROUNDS: usize = 10;
+
+/// The maximum value of an integer of a pattern (e.g., of a range).
+///
+/// Each repetition of a range (or frame of an interval) is a copy of its
+/// operand within the automaton of the pattern, which is built in time (and
+/// space) linear in their number.
+const MAX_INTEGER: usize = 10_000;
+
+/// The maximum number of subpatterns of an unordered group.
+///
+/// A group is expanded into the alternation of every order of its
+/// subpatterns, which grows factorially with their number.
+const MAX_GROUP: usize = 6;
 
 /// The SpRE parser.
 ///
 /// The parser is responsible for tracking its current location on the
 /// [`TokenStream`] and asserting the next token is the correct.
+///
+/// Upon a syntax error, the parser recovers by skipping ahead to a point from
+/// which parsing may continue (i.e., the end of the spatial formula, an
+/// alternation, or a closing parenthesis). Therefore, several errors may be
+/// reported at once.
 pub struct Parser {
     stream: TokenStream,
-    listener: Option<ErrorListener>,
     current: usize,
+    vacuity: Vacuity,
+    end: Vacuity,
+
+    /// The super-classes that each class may refer to, if any.
+    classmap: Option<ClassMap>,
+
+    /// The number of lookarounds parsed so far.
+    lookarounds: usize,
+
+    /// Whether a lookaround is being parsed.
+    lookaround: bool,
+
+    /// The names of the objects captured so far.
+    captures: Vec<String>,
+
+    /// The name and position of each reference to a captured object.
+    references: Vec<(String, Position)>,
+
+    /// The errors found so far, in order of position.
+    errors: Vec<ParserError>,
 }
 
 impl Parser {
@@ -30,68 +81,140 @@ impl Parser {
     pub fn new(stream: TokenStream) -> Self {
         Parser {
             stream,
-            listener: None,
             current: 0,
+            vacuity: Vacuity::default(),
+            end: Vacuity::default(),
+            classmap: None,
+            lookarounds: 0,
+            lookaround: false,
+            captures: Vec::new(),
+            references: Vec::new(),
+            errors: Vec::new(),
         }
     }
 
-    /// Attach an [`ErrorListener`] to the [`Parser`].
+    /// Set the [`Vacuity`] of universal quantifiers parsed.
+    pub fn vacuity(mut self, vacuity: Vacuity) -> Self {
+        self.vacuity = vacuity;
+        self
+    }
+
+    /// Set the [`Vacuity`] of temporal operators parsed.
+    pub fn end(mut self, end: Vacuity) -> Self {
+        self.end = end;
+        self
+    }
+
+    /// Set the [`ClassMap`] of the classes parsed.
     ///
-    /// This attachment allows for better syntactical error reporting by the
-    /// parsing process based on the provided listener. If an error listener is
-    /// not attached to the [`Parser`], it panics.
-    pub fn attach(mut self, listener: ErrorListener) -> Self {
-        self.listener = Some(listener);
+    /// A class that is a super-class of the mapping refers to the annotations
+    /// of any of its labels.
+    pub fn classmap(mut self, classmap: Option<ClassMap>) -> Self {
+        self.classmap = classmap;
         self
     }
 
     /// Retrieve the next token from stream and compare against expected.
     ///
-    /// If the next token from stream is not the expected token, then throw a
-    /// fatal error messsage and exit as this situation is not recoverable in its
-    /// current implementation.
-    fn expect(&mut self, kind: TokenKind) -> Token {
+    /// If the next token from stream is not the expected token, then an error
+    /// is recorded and `None` is returned, such that the caller may recover.
+    fn expect(&mut self, kind: TokenKind) -> Option<Token> {
         if self.stream.buffer[self.current].kind != kind {
-            match &self.listener {
-                Some(listener) => {
-                    listener.exit(
+            let token = &self.stream.buffer[self.current];
+
+            self.errors.push(
+                ParserError::at(
+                    &token.position,
+                    format!("expected {:?} but found {:?}", kind, token.kind),
+                )
+                .spanning(token.lexeme.chars().count()),
+            );
+
+            return None;
+        }
+
+        self.current += 1;
+        Some(self.stream.buffer[self.current - 1].clone())
+    }
+
+    /// Expect an [`Integer`] and parse its value.
+    ///
+    /// An integer larger than [`MAX_INTEGER`] is recorded as an error at its
+    /// token.
+    fn integer(&mut self) -> Option<usize> {
+        let token = self.expect(Integer)?;
+
+        match token.lexeme.parse() {
+            Ok(n) if n <= MAX_INTEGER => Some(n),
+            _ => {
+                self.errors.push(
+                    ParserError::at(
+                        &token.position,
                         format!(
-                            "parser: {}:{}: expected {:?} but found {:?}",
-                            self.stream.buffer[self.current].position.0,
-                            self.stream.buffer[self.current].position.1,
-                            kind,
-                            self.stream.buffer[self.current].kind,
+                            "integer `{}` is too large (at most {})",
+                            token.lexeme, MAX_INTEGER
                         ),
-                        1,
-                    );
-                }
-                None => {
-                    panic!();
-                }
+                    )
+                    .spanning(token.lexeme.chars().count()),
+                );
+
+                None
             }
         }
-
-        self.current += 1;
-        self.stream.buffer[self.current - 1].clone()
     }
 
-    /// Fatally report a syntax error.
+    /// Record a syntax error.
     ///
     /// The syntax error can derive from various sources. Therefore, the position
     /// of the offending token is provided as a general tip to debug.
-    fn error(&self) {
-        match &self.listener {
-            Some(listener) => listener.exit(
-                format!(
-                    "parser: {}:{}: syntax error",
-                    self.stream.buffer[self.current].position.0,
-                    self.stream.buffer[self.current].position.1
-                ),
-                2,
-            ),
-            None => {
-                panic!();
+    fn error(&mut self) {
+        let token = &self.stream.buffer[self.current];
+
+        self.errors.push(
+            ParserError::at(&token.position, "syntax error").spanning(token.lexeme.chars().count()),
+        );
+    }
+
+    /// Skip ahead to the end of the spatial formula opened at `open`.
+    ///
+    /// The tokens are skipped until the matching `]` is consumed. Any classes
+    /// within the formula (e.g., `[:car:]`) are skipped over as a whole.
+    fn recover(&mut self, open: usize) {
+        let mut depth = 0;
+
+        for (i, token) in self.stream.buffer.iter().enumerate().skip(open) {
+            match token.kind {
+                LeftBracket => depth += 1,
+                RightBracket => depth -= 1,
+                EndOfFile => break,
+                _ => (),
             }
+
+            if depth == 0 {
+                self.current = i + 1;
+                return;
+            }
+        }
+
+        self.current = self.stream.size - 1;
+    }
+
+    /// Skip ahead to the next alternation or closing parenthesis.
+    ///
+    /// At least one token is skipped. Spatial formulas (i.e., `[...]`) are
+    /// skipped over as a whole.
+    fn synchronize(&mut self) {
+        let mut depth = 0;
+
+        while self.stream.buffer[self.current].kind != EndOfFile {
+            match self.stream.buffer[self.current].kind {
+                Or | RightParen if depth == 0 => return,
+                LeftBracket => depth += 1,
+                RightBracket if depth > 0 => depth -= 1,
+                _ => (),
+            }
+
+            self.current += 1;
         }
     }
 
@@ -112,10 +235,12 @@ impl Parser {
     /// This method parses the initialized [`TokenStream`] and produces a
     /// [`AbstractSyntaxTree`] (aka, an Abstract Syntax Tree) populated with the relevant
     /// information. In most cases this means dropping parentheses.
-    pub fn parse(&mut self) -> AbstractSyntaxTree {
+    ///
+    /// If any syntax errors are found, all of them are returned instead.
+    pub fn parse(&mut self) -> Result<AbstractSyntaxTree, Vec<ParserError>> {
         let root = if let Some(token) = self.peek(1) {
             if token.kind != EndOfFile {
-                self.parse_spre()
+                self.parse_conjunction()
             } else {
                 None
             }
@@ -123,9 +248,90 @@ impl Parser {
             None
         };
 
-        self.expect(EndOfFile);
+        // Report any references to an object that is never captured.
+        //
+        // An object may be referenced before it is captured; therefore, this is
+        // only checked once the whole pattern is parsed.
+        for (name, position) in std::mem::take(&mut self.references) {
+            if !self.captures.contains(&name) {
+                self.errors.push(
+                    ParserError::at(&position, format!("unknown capture `\\{}`", name))
+                        .spanning(name.chars().count() + 1),
+                );
+            }
+        }
+
+        // Report any tokens left over.
+        //
+        // If the pattern has already failed to parse, the left over tokens are
+        // likely a consequence of it and are not reported again.
+        if self.errors.is_empty() {
+            self.expect(EndOfFile);
+        }
+
+        if !self.errors.is_empty() {
+            return Err(self.reported());
+        }
+
+        Ok(AbstractSyntaxTree::new(root))
+    }
+
+    /// Retrieve the errors recorded, each reported once.
+    ///
+    /// An error may be recorded again after a recovery (e.g., by both an inner
+    /// and an outer rule). In addition, a generic syntax error is dropped where
+    /// a more specific error is found at the same position.
+    fn reported(&mut self) -> Vec<ParserError> {
+        let mut errors: Vec<ParserError> = Vec::new();
+
+        for e in std::mem::take(&mut self.errors) {
+            if !errors
+                .iter()
+                .any(|other| other.position == e.position && other.msg == e.msg)
+            {
+                errors.push(e);
+            }
+        }
+
+        let specific = errors
+            .iter()
+            .filter(|e| e.msg != "syntax error")
+            .map(|e| e.position.clone())
+            .collect::<Vec<_>>();
+
+        errors.retain(|e| e.msg != "syntax error" || !specific.contains(&e.position));
+        errors
+    }
+
+    /// Parse a conjunction of Regular Expression-based expressions.
+    ///
+    /// This parse function captures the following grammar:
+    ///
+    /// ```text
+    /// spre ::= phi ('&&' phi)*
+    /// ```
+    ///
+    /// A conjunction matches the frames matched by all of its operands. It is
+    /// only supported at the top level of a pattern (i.e., it binds loosest and
+    /// may not be parenthesized).
+    fn parse_conjunction(&mut self) -> Option<Tree<SpatialFormula>> {
+        let mut node = self.parse_spre();
+
+        while let Some(AndAnd) = self.peek(1).map(|t| t.kind.clone()) {
+            self.expect(AndAnd);
+
+            let right = self.parse_spre();
+            node = match (node, right) {
+                (Some(node), Some(right)) => Some(Tree::binary(
+                    Operator::RegexOperator(RegexOperatorKind::Conjunction),
+                    node,
+                    right,
+                )),
+                _ => None,
+            };
+        }
 
-        AbstractSyntaxTree::new(root)
+        node
     }
 
     /// Parse a Regular Expression-based expression.
@@ -133,55 +339,83 @@ impl Parser {
     /// This parse function captures the following grammar:
     ///
     /// ```text
-    /// phi ::= '(' phi ')' | phi '*' | phi phi | phi '|' phi | phi range
-    ///       | '[' pi ']'
+    /// phi ::= '(' phi ')' | phi '*' | phi '+' | phi phi | phi '|' phi | phi range
+    ///       | '[' pi ']' | empty | lookaround | group | capture | temporal
+    ///       | phi 'U' interval phi | phi '~' gap phi
     /// ```
     ///
     /// Note: The following symbol(s) have a different semantic meaning derived
     /// at parse time:
     ///
     /// `|`: Alternation
-    fn parse_spre(&mut self) -> Option<Node<SpatialFormula>> {
-        let mut node = None;
-
-        if let Some(token) = self.peek(1) {
-            match token.kind {
-                LeftParen => {
-                    self.expect(LeftParen);
-                    node = self.parse_spre();
-                    self.expect(RightParen);
-                }
-                LeftBracket => {
-                    self.expect(LeftBracket);
-                    let tree = self.parse_s4u();
-                    self.expect(RightBracket);
-
-                    node = Some(Node::from(tree.unwrap()));
-                }
-                _ => self.error(),
-            }
-        };
+    fn parse_spre(&mut self) -> Option<Tree<SpatialFormula>> {
+        let mut node = self.parse_spre_primary();
 
         while let Some(token) = self.peek(1) {
             if token.kind != EndOfFile {
+                let range = token.kind == LeftBrace
+                    && matches!(self.peek(2).map(|t| &t.kind), Some(Integer));
+                let prefix = self.temporal(&["G", "F"]);
+                let until = self.temporal(&["U"]);
+
                 match token.kind {
                     // kleene-star
                     Star => {
                         self.expect(Star);
-                        node = Some(Node::unary(
-                            Operator::RegexOperator(RegexOperatorKind::KleeneStar),
-                            node.unwrap(),
-                        ));
+                        node = node.map(|node| {
+                            Tree::unary(
+                                Operator::RegexOperator(RegexOperatorKind::KleeneStar),
+                                node,
+                            )
+                        });
+                    }
+
+                    // one-or-more
+                    //
+                    // This is equivalent to `phi phi*`; however, it is kept as
+                    // the range `{1,}` such that the spatial formulas of `phi`
+                    // are not duplicated.
+                    Plus => {
+                        self.expect(Plus);
+                        node = node.map(|node| {
+                            Tree::unary(
+                                Operator::RegexOperator(RegexOperatorKind::Range(
+                                    RangeKind::AtLeast(1),
+                                )),
+                                node,
+                            )
+                        });
                     }
 
                     // concatenation
-                    LeftParen | LeftBracket => {
+                    //
+                    // A brace only starts a range if followed by its minimum;
+                    // otherwise, it starts an unordered group.
+                    //
+                    // An identifier only starts a temporal operator if followed
+                    // by its interval.
+                    LeftParen | LeftBracket | LeftBrace | Identifier
+                        if !range && (token.kind != Identifier || prefix) =>
+                    {
                         let right = self.parse_spre();
-                        node = Some(Node::binary(
-                            Operator::RegexOperator(RegexOperatorKind::Concatenation),
-                            node.unwrap(),
-                            right.unwrap(),
-                        ));
+                        node = match (node, right) {
+                            (Some(node), Some(right)) => Some(Tree::binary(
+                                Operator::RegexOperator(RegexOperatorKind::Concatenation),
+                                node,
+                                right,
+                            )),
+                            _ => None,
+                        };
+                    }
+
+                    // until
+                    Identifier if until => {
+                        node = self.parse_until(node);
+                    }
+
+                    // gap
+                    Tilde => {
+                        node = self.parse_gap(node);
                     }
 
                     // alternation
@@ -189,20 +423,31 @@ impl Parser {
                         self.expect(Or);
 
                         let right = self.parse_spre();
-                        node = Some(Node::binary(
-                            Operator::RegexOperator(RegexOperatorKind::Alternation),
-                            node.unwrap(),
-                            right.unwrap(),
-                        ))
+                        node = match (node, right) {
+                            (Some(node), Some(right)) => Some(Tree::binary(
+                                Operator::RegexOperator(RegexOperatorKind::Alternation),
+                                node,
+                                right,
+                            )),
+                            _ => None,
+                        };
                     }
 
                     // range
                     LeftBrace => {
                         let range = self.parse_range();
-                        node = Some(Node::unary(
-                            Operator::RegexOperator(RegexOperatorKind::Range(range.unwrap())),
-                            node.unwrap(),
-                        ));
+
+                        if range.is_none() {
+                            self.synchronize();
+                        }
+
+                        node = match (node, range) {
+                            (Some(node), Some(range)) => Some(Tree::unary(
+                                Operator::RegexOperator(RegexOperatorKind::Range(range)),
+                                node,
+                            )),
+                            _ => None,
+                        };
                     }
 
                     _ => break,
@@ -215,49 +460,630 @@ impl Parser {
         node
     }
 
+    /// Parse a Regular Expression-based expression without any postfix or
+    /// infix operators.
+    fn parse_spre_primary(&mut self) -> Option<Tree<SpatialFormula>> {
+        let mut node = None;
+
+        if let Some(token) = self.peek(1) {
+            match token.kind {
+                Identifier if self.temporal(&["G", "F"]) => {
+                    node = self.parse_temporal();
+                }
+                LeftParen
+                    if matches!(self.peek(2).map(|t| &t.kind), Some(Question))
+                        && matches!(self.peek(3).map(|t| &t.kind), Some(LeftChevron)) =>
+                {
+                    node = self.parse_capture();
+                }
+                LeftParen if matches!(self.peek(2).map(|t| &t.kind), Some(Question)) => {
+                    node = self.parse_lookaround();
+                }
+                LeftBrace => {
+                    node = self.parse_group();
+                }
+                LeftParen => {
+                    self.expect(LeftParen);
+                    node = self.parse_spre();
+
+                    if let Some(AndAnd) = self.peek(1).map(|t| t.kind.clone()) {
+                        let position = self.stream.buffer[self.current].position.clone();
+
+                        self.errors.push(ParserError::at(
+                            &position,
+                            "conjunction is only supported at the top level of a pattern",
+                        ));
+
+                        return None;
+                    }
+
+                    if node.is_some() {
+                        self.expect(RightParen)?;
+                    } else if let Some(RightParen) = self.peek(1).map(|t| t.kind.clone()) {
+                        self.expect(RightParen);
+                    }
+                }
+                LeftBracket
+                    if self.peek(2).is_some_and(|t| t.lexeme == "empty")
+                        && !matches!(self.peek(3).map(|t| &t.kind), Some(Colon)) =>
+                {
+                    node = self.parse_empty();
+                }
+                LeftBracket => {
+                    // Recover from an invalid spatial formula.
+                    //
+                    // The remainder of the formula is skipped, such that the
+                    // formulas that follow are still checked.
+                    let open = self.current;
+
+                    self.expect(LeftBracket);
+                    let channel = self.parse_channel();
+
+                    let tree = match self.parse_s4u() {
+                        Some(tree) => self.expect(RightBracket).map(|_| match channel {
+                            Some(channel) => Tree::unary(
+                                Operator::SpatialOperator(SpatialOperatorKind::S4uOperator(
+                                    S4uOperatorKind::Channel(channel),
+                                )),
+                                tree,
+                            ),
+                            None => tree,
+                        }),
+                        None => None,
+                    };
+
+                    if tree.is_none() {
+                        self.recover(open);
+                    }
+
+                    node = tree.map(Tree::from);
+                }
+                _ => {
+                    self.error();
+                    self.synchronize();
+                }
+            }
+        };
+
+        node
+    }
+
+    /// Parse an unordered group.
+    ///
+    /// This parse function captures the following grammar:
+    ///
+    /// ```text
+    /// group ::= '{' phi (',' phi)* '}'
+    /// ```
+    ///
+    /// A group matches its subpatterns occurring consecutively in any order;
+    /// therefore, it is expanded into the alternation of each permutation of
+    /// its subpatterns. At most [`MAX_GROUP`] subpatterns may be grouped.
+    fn parse_group(&mut self) -> Option<Tree<SpatialFormula>> {
+        let position = self.stream.buffer[self.current].position.clone();
+        let lookarounds = self.lookarounds;
+
+        self.expect(LeftBrace)?;
+
+        let mut nodes = vec![self.parse_spre()];
+
+        while let Some(Comma) = self.peek(1).map(|t| t.kind.clone()) {
+            self.expect(Comma);
+            nodes.push(self.parse_spre());
+        }
+
+        self.expect(RightBrace)?;
+
+        if nodes.len() > MAX_GROUP {
+            self.errors.push(ParserError::at(
+                &position,
+                format!(
+                    "at most {} subpatterns of an unordered group are supported",
+                    MAX_GROUP
+                ),
+            ));
+
+            return None;
+        }
+
+        let nodes = nodes.into_iter().collect::<Option<Vec<_>>>()?;
+
+        // Count the lookarounds of each copy.
+        //
+        // Each lookaround within the group is repeated once per permutation,
+        // and each copy is marked separately.
+        let permutations = (1..=nodes.len()).product::<usize>();
+        let within = self.lookarounds - lookarounds;
+
+        if within > 0 {
+            self.lookarounds += within * (permutations - 1);
+
+            if self.lookarounds > MAX_LOOKAROUNDS {
+                self.errors.push(ParserError::at(
+                    &position,
+                    format!(
+                        "at most {} lookarounds are supported (including each order of an unordered group)",
+                        MAX_LOOKAROUNDS
+                    ),
+                ));
+            }
+        }
+
+        (0..nodes.len())
+            .permutations(nodes.len())
+            .map(|order| {
+                order
+                    .into_iter()
+                    .map(|i| nodes[i].clone())
+                    .reduce(|lhs, rhs| {
+                        Tree::binary(
+                            Operator::RegexOperator(RegexOperatorKind::Concatenation),
+                            lhs,
+                            rhs,
+                        )
+                    })
+            })
+            .reduce(|lhs, rhs| match (lhs, rhs) {
+                (Some(lhs), Some(rhs)) => Some(Tree::binary(
+                    Operator::RegexOperator(RegexOperatorKind::Alternation),
+                    lhs,
+                    rhs,
+                )),
+                _ => None,
+            })?
+    }
+
+    /// Parse an optional channel qualifier of a spatial formula.
+    ///
+    /// This parse function captures the following grammar:
+    ///
+    /// ```text
+    /// channel ::= Identifier ':' | Literal ':' | ε
+    /// ```
+    ///
+    /// A literal allows the name of any channel (e.g., `"/camera/front"`).
+    fn parse_channel(&mut self) -> Option<String> {
+        let kind = match (self.peek(1), self.peek(2)) {
+            (Some(token), Some(next)) if next.kind == Colon => token.kind.clone(),
+            _ => return None,
+        };
+
+        if !matches!(kind, Identifier | Literal) {
+            return None;
+        }
+
+        let channel = self.expect(kind)?.lexeme;
+        self.expect(Colon)?;
+
+        Some(channel)
+    }
+
+    /// Parse an empty frame.
+    ///
+    /// This parse function captures the following grammar:
+    ///
+    /// ```text
+    /// empty ::= '[' 'empty' ']' | '[' 'empty' '(' Literal ')' ']'
+    /// ```
+    ///
+    /// The optional literal selects the channel whose samples are checked.
+    fn parse_empty(&mut self) -> Option<Tree<SpatialFormula>> {
+        self.expect(LeftBracket)?;
+        self.expect(Identifier)?;
+
+        let channel = match self.peek(1).map(|t| t.kind.clone()) {
+            Some(LeftParen) => {
+                self.expect(LeftParen)?;
+                let channel = self.expect(Literal)?.lexeme;
+                self.expect(RightParen)?;
+
+                Some(channel)
+            }
+            _ => None,
+        };
+
+        self.expect(RightBracket)?;
+
+        Some(Tree::from(SpatialFormula::from(OperandKind::Empty(
+            channel,
+        ))))
+    }
+
+    /// Parse a negative lookahead or a positive lookbehind.
+    ///
+    /// This parse function captures the following grammar:
+    ///
+    /// ```text
+    /// lookaround ::= '(' '?' '!' phi ')' | '(' '?' '<=' phi ')'
+    /// ```
+    ///
+    /// A lookaround may not be nested within another, and at most
+    /// [`MAX_LOOKAROUNDS`] may be used within a pattern. The subpattern of a
+    /// lookbehind must also be bounded (i.e., without `*` or `{n,}`).
+    fn parse_lookaround(&mut self) -> Option<Tree<SpatialFormula>> {
+        let position = self.stream.buffer[self.current].position.clone();
+
+        self.expect(LeftParen)?;
+        self.expect(Question)?;
+
+        let kind = match self.peek(1).map(|t| &t.kind) {
+            Some(LeftChevronEqual) => {
+                self.expect(LeftChevronEqual)?;
+                RegexOperatorKind::PositiveLookbehind
+            }
+            _ => {
+                self.expect(Not)?;
+                RegexOperatorKind::NegativeLookahead
+            }
+        };
+
+        self.lookarounds += 1;
+
+        if self.lookaround {
+            self.errors.push(ParserError::at(
+                &position,
+                "nested lookaround is not supported",
+            ));
+        } else if self.lookarounds == MAX_LOOKAROUNDS + 1 {
+            self.errors.push(ParserError::at(
+                &position,
+                format!("at most {} lookarounds are supported", MAX_LOOKAROUNDS),
+            ));
+        }
+
+        let within = std::mem::replace(&mut self.lookaround, true);
+        let node = self.parse_spre();
+        self.lookaround = within;
+
+        self.expect(RightParen)?;
+
+        let node = node?;
+
+        let behind = matches!(kind, RegexOperatorKind::PositiveLookbehind);
+
+        if behind && !Self::bounded(&node, node.root()) {
+            self.errors.push(ParserError::at(
+                &position,
+                "lookbehind must be bounded (i.e., without `*` or `{n,}`)",
+            ));
+        }
+
+        Some(Tree::unary(Operator::RegexOperator(kind), node))
+    }
+
+    /// Parse a capture of an object.
+    ///
+    /// This parse function captures the following grammar:
+    ///
+    /// ```text
+    /// capture ::= '(' '?' '<' Identifier '>' pi ')'
+    /// ```
+    ///
+    /// A capture matches a single frame, similar to `'[' pi ']'`. The formula
+    /// must be an existential quantifier that binds the named variable, where
+    /// the object it is bound to may be referenced in other frames.
+    fn parse_capture(&mut self) -> Option<Tree<SpatialFormula>> {
+        let position = self.stream.buffer[self.current].position.clone();
+
+        self.expect(LeftParen)?;
+        self.expect(Question)?;
+        self.expect(LeftChevron)?;
+        let name = self.expect(Identifier)?.lexeme;
+        self.expect(RightChevron)?;
+
+        let formula = self.parse_s4u();
+        self.expect(RightParen)?;
+
+        let formula = formula?;
+
+        // Check the captured variable is bound by the quantifier.
+        //
+        // Only a variable of an existential quantifier refers to a single
+        // object of the frame.
+        let bound = matches!(
+            &formula[formula.root()],
+            Node::UnaryExpr {
+                op: Operator::SpatialOperator(SpatialOperatorKind::S4uOperator(
+                    S4uOperatorKind::Exists(b),
+                )),
+                ..
+            } if b.table.contains_key(&name)
+        );
+
+        if !bound {
+            self.errors.push(ParserError::at(
+                &position,
+                format!(
+                    "capture `{}` must be bound by an existential quantifier (e.g., `E({} := [:car:]) ...`)",
+                    name, name
+                ),
+            ));
+        }
+
+        self.captures.push(name.clone());
+
+        Some(Tree::from(SpatialFormula::unary(
+            Operator::SpatialOperator(SpatialOperatorKind::S4uOperator(S4uOperatorKind::Capture(
+                name,
+            ))),
+            formula,
+        )))
+    }
+
+    /// Parse a bounded temporal operator of a single operand.
+    ///
+    /// This parse function captures the following grammar:
+    ///
+    /// ```text
+    /// temporal ::= ('G' | 'F') interval phi
+    /// ```
+    ///
+    /// The operand is the subpattern that immediately follows (i.e., without
+    /// any postfix or infix operators), which must match a single frame.
+    fn parse_temporal(&mut self) -> Option<Tree<SpatialFormula>> {
+        let position = self.stream.buffer[self.current].position.clone();
+
+        let keyword = self.expect(Identifier)?.lexeme;
+        let interval = self.parse_interval();
+        let operand = self.parse_spre_primary()?;
+
+        let (min, max) = interval?;
+
+        if !self.operand(&operand, &position) {
+            return None;
+        }
+
+        let kind = match keyword.as_str() {
+            "G" => RegexOperatorKind::Globally(min, max, self.end),
+            _ => RegexOperatorKind::Eventually(min, max),
+        };
+
+        Some(Tree::unary(Operator::RegexOperator(kind), operand))
+    }
+
+    /// Parse the until operator of the left operand `lhs`.
+    ///
+    /// This parse function captures the following grammar:
+    ///
+    /// ```text
+    /// until ::= phi 'U' interval phi
+    /// ```
+    ///
+    /// As with [`Self::parse_temporal`], the right operand is the subpattern
+    /// that immediately follows, and both operands must match a single frame.
+    fn parse_until(&mut self, lhs: Option<Tree<SpatialFormula>>) -> Option<Tree<SpatialFormula>> {
+        let position = self.stream.buffer[self.current].position.clone();
+
+        self.expect(Identifier)?;
+        let interval = self.parse_interval();
+        let rhs = self.parse_spre_primary()?;
+
+        let ((min, max), lhs) = (interval?, lhs?);
+
+        if !self.operand(&lhs, &position) || !self.operand(&rhs, &position) {
+            return None;
+        }
+
+        Some(Tree::binary(
+            Operator::RegexOperator(RegexOperatorKind::Until(min, max, self.end)),
+            lhs,
+            rhs,
+        ))
+    }
+
+    /// Parse the gap operator of the left operand `lhs`.
+    ///
+    /// This parse function captures the following grammar:
+    ///
+    /// ```text
+    /// gap ::= phi '~' '{' '<=' Integer '}' phi
+    /// ```
+    ///
+    /// Similar to a concatenation, the right operand is the remainder of the
+    /// subpattern, where it must begin within the (positive) bound of frames
+    /// after the left operand. Upon an error, the parser is synchronized.
+    fn parse_gap(&mut self, lhs: Option<Tree<SpatialFormula>>) -> Option<Tree<SpatialFormula>> {
+        let position = self.stream.buffer[self.current].position.clone();
+
+        let bound = (|| {
+            self.expect(Tilde)?;
+            self.expect(LeftBrace)?;
+            self.expect(LeftChevronEqual)?;
+            let max = self.integer()?;
+            self.expect(RightBrace)?;
+
+            Some(max)
+        })();
+
+        let max = match bound {
+            Some(0) => {
+                self.errors
+                    .push(ParserError::at(&position, "gap `~{<=0}` is empty"));
+
+                None
+            }
+            Some(max) => Some(max),
+            None => {
+                self.synchronize();
+                return None;
+            }
+        };
+
+        let rhs = self.parse_spre();
+
+        Some(Tree::binary(
+            Operator::RegexOperator(RegexOperatorKind::Gap(max?)),
+            lhs?,
+            rhs?,
+        ))
+    }
+
+    /// Parse the interval of a temporal operator.
+    ///
+    /// This parse function captures the following grammar:
+    ///
+    /// ```text
+    /// interval ::= '[' Integer ',' Integer ']'
+    /// ```
+    ///
+    /// The interval is closed, where its bounds count the frames from the
+    /// first frame of the operator. Upon an error, the parser is synchronized.
+    fn parse_interval(&mut self) -> Option<(usize, usize)> {
+        let position = self.stream.buffer[self.current].position.clone();
+
+        let interval = (|| {
+            self.expect(LeftBracket)?;
+            let min = self.integer()?;
+            self.expect(Comma)?;
+            let max = self.integer()?;
+            self.expect(RightBracket)?;
+
+            Some((min, max))
+        })();
+
+        match interval {
+            Some((min, max)) if min > max => {
+                self.errors.push(ParserError::at(
+                    &position,
+                    format!("interval `[{},{}]` is empty", min, max),
+                ));
+
+                None
+            }
+            Some(interval) => Some(interval),
+            None => {
+                self.synchronize();
+                None
+            }
+        }
+    }
+
+    /// Check if the next tokens start a temporal operator of the `keywords`.
+    ///
+    /// The keywords are not reserved (e.g., they may still name variables);
+    /// therefore, a keyword is only a temporal operator if followed by `[`.
+    fn temporal(&self, keywords: &[&str]) -> bool {
+        self.peek(1)
+            .is_some_and(|t| t.kind == Identifier && keywords.contains(&t.lexeme.as_str()))
+            && matches!(self.peek(2).map(|t| &t.kind), Some(LeftBracket))
+    }
+
+    /// Check the operand of a temporal operator at `position` matches a single
+    /// frame, recording an error otherwise.
+    fn operand(&mut self, tree: &Tree<SpatialFormula>, position: &Position) -> bool {
+        if Self::single(tree, tree.root()) {
+            return true;
+        }
+
+        self.errors.push(ParserError::at(
+            position,
+            "operand of a temporal operator must match a single frame",
+        ));
+
+        false
+    }
+
+    /// Check if a subpattern matches exactly one frame.
+    fn single(tree: &Tree<SpatialFormula>, node: NodeId) -> bool {
+        match &tree[node] {
+            Node::Operand(..) => true,
+            Node::BinaryExpr {
+                op: Operator::RegexOperator(RegexOperatorKind::Alternation),
+                lhs,
+                rhs,
+            } => Self::single(tree, *lhs) && Self::single(tree, *rhs),
+            _ => false,
+        }
+    }
+
+    /// Check if a subpattern matches a bounded number of frames.
+    fn bounded(tree: &Tree<SpatialFormula>, node: NodeId) -> bool {
+        match &tree[node] {
+            Node::Operand(..) => true,
+            Node::UnaryExpr {
+                op: Operator::RegexOperator(RegexOperatorKind::KleeneStar),
+                ..
+            }
+            | Node::UnaryExpr {
+                op: Operator::RegexOperator(RegexOperatorKind::Range(RangeKind::AtLeast(..))),
+                ..
+            } => false,
+            Node::UnaryExpr { child, .. } => Self::bounded(tree, *child),
+            Node::BinaryExpr { lhs, rhs, .. } => {
+                Self::bounded(tree, *lhs) && Self::bounded(tree, *rhs)
+            }
+        }
+    }
+
     /// Parse an S4u-based expression.
     ///
     /// This parse function captures the following grammar:
     ///
     /// ```text
-    /// pi ::= '(' pi ')' | pi '&' pi | pi '|' pi | NonEmpty class
+    /// pi ::= '(' pi ')' | pi '&' pi | pi '|' pi | '!' pi | NonEmpty class
     ///      | NonEmpty '(' tau ')' | class
-    ///      | 'E' '(' bindings ')' pi
-    ///      | 'A' '(' bindings ')' pi
-    ///      | psi < psi
+    ///      | 'E' distinct '(' bindings ')' pi
+    ///      | 'A' distinct '(' bindings ')' pi
+    ///      | psi < psi | text
     /// ```
     ///
+    /// From loosest to tightest, the operators bind as `|`, `&`, then `!`,
+    /// where binary operators are left-associative. A quantifier extends as far
+    /// to the right as possible.
+    ///
     /// Note: The following symbol(s) have a different semantic meaning derived
     /// at parse time:
     ///
-    /// `~`: Negation
+    /// `!`: Negation
     /// `&`: Conjunction
     /// `|`: Disjunction
     fn parse_s4u(&mut self) -> Option<SpatialFormula> {
-        let mut node = None;
+        self.parse_infix(0, Self::parse_s4u_primary, |kind| match kind {
+            Or => Some((
+                1,
+                Operator::SpatialOperator(SpatialOperatorKind::FolOperator(
+                    FolOperatorKind::Disjunction,
+                )),
+            )),
+            And => Some((
+                2,
+                Operator::SpatialOperator(SpatialOperatorKind::FolOperator(
+                    FolOperatorKind::Conjunction,
+                )),
+            )),
+            _ => None,
+        })
+    }
+
+    /// Parse an S4u-based expression without any infix operators.
+    ///
+    /// This is an operand of [`Self::parse_s4u`]. A comparison is parsed as a
+    /// whole here, such that comparisons do not chain (e.g., `a < b < c`).
+    fn parse_s4u_primary(&mut self) -> Option<SpatialFormula> {
+        let node;
 
         if let Some(token) = self.peek(1) {
             match token.kind {
                 LeftParen => {
-                    self.expect(LeftParen);
+                    self.expect(LeftParen)?;
                     node = self.parse_s4u();
-                    self.expect(RightParen);
+                    self.expect(RightParen)?;
                 }
 
                 Not => {
-                    self.expect(Not);
+                    self.expect(Not)?;
 
-                    let child = self.parse_s4u();
-                    node = Some(Node::unary(
+                    let child = self.parse_s4u_primary();
+                    node = Some(Tree::unary(
                         Operator::SpatialOperator(SpatialOperatorKind::FolOperator(
                             FolOperatorKind::Negation,
                         )),
-                        child.unwrap(),
+                        child?,
                     ));
                 }
 
                 NonEmpty => {
-                    self.expect(NonEmpty);
+                    self.expect(NonEmpty)?;
 
                     // The behavior of the NonEmpty operator is non-greedy.
                     // Therefore, it should consume only the next token and
@@ -271,9 +1097,9 @@ impl Parser {
                         match token.kind {
                             TokenKind::LeftBracket => self.parse_class(),
                             TokenKind::LeftParen => {
-                                self.expect(LeftParen);
+                                self.expect(LeftParen)?;
                                 let child = self.parse_s4();
-                                self.expect(RightParen);
+                                self.expect(RightParen)?;
 
                                 child
                             }
@@ -287,66 +1113,91 @@ impl Parser {
                         None
                     };
 
-                    node = Some(Node::unary(
+                    node = Some(Tree::unary(
                         Operator::SpatialOperator(SpatialOperatorKind::S4uOperator(
                             S4uOperatorKind::NonEmpty,
                         )),
-                        child.unwrap(),
+                        child?,
                     ));
                 }
 
                 Exists => {
-                    self.expect(Exists);
-                    self.expect(LeftParen);
-                    let table = self.parse_bindings();
-                    self.expect(RightParen);
+                    self.expect(Exists)?;
+                    let distinct = self.parse_distinct();
+                    self.expect(LeftParen)?;
+                    let table = self.parse_bindings()?;
+                    self.expect(RightParen)?;
 
                     let child = self.parse_s4u();
 
-                    node = Some(Node::unary(
+                    node = Some(Tree::unary(
                         Operator::SpatialOperator(SpatialOperatorKind::S4uOperator(
-                            S4uOperatorKind::Exists(table),
+                            S4uOperatorKind::Exists(Bindings::new(table, distinct)),
                         )),
-                        child.unwrap(),
+                        child?,
                     ));
                 }
 
                 Forall => {
-                    self.expect(Forall);
-                    self.expect(LeftParen);
-                    let table = self.parse_bindings();
-                    self.expect(RightParen);
+                    self.expect(Forall)?;
+                    let distinct = self.parse_distinct();
+                    self.expect(LeftParen)?;
+                    let table = self.parse_bindings()?;
+                    self.expect(RightParen)?;
 
                     let child = self.parse_s4u();
 
-                    node = Some(Node::unary(
+                    node = Some(Tree::unary(
                         Operator::SpatialOperator(SpatialOperatorKind::S4uOperator(
-                            S4uOperatorKind::Forall(table),
+                            S4uOperatorKind::Forall(Bindings::new(table, distinct), self.vacuity),
                         )),
-                        child.unwrap(),
+                        child?,
                     ));
                 }
 
-                At | Integer | Real | Minus => {
+                At if self.peek(2).is_some_and(|t| t.lexeme == "text") => {
+                    node = self.parse_text();
+                }
+
+                At if self.peek(2).is_some_and(|t| t.lexeme == "in") => {
+                    node = self.parse_within();
+                }
+
+                At if self.peek(2).is_some_and(|t| t.lexeme == "touch") => {
+                    node = self.parse_touch();
+                }
+
+                At if self
+                    .peek(2)
+                    .is_some_and(|t| Relation::from_name(&t.lexeme).is_some()) =>
+                {
+                    node = self.parse_relation();
+                }
+
+                At if self.peek(2).is_some_and(|t| t.lexeme == "same") => {
+                    node = self.parse_same();
+                }
+
+                At | Hash | Integer | Real | Minus => {
                     let lhs = self.parse_s4m();
 
                     let mut op = None;
                     if let Some(token) = self.peek(1) {
                         match token.kind {
                             LeftChevron => {
-                                self.expect(LeftChevron);
+                                self.expect(LeftChevron)?;
                                 op = Some(Operator::SpatialOperator(
                                     SpatialOperatorKind::FolOperator(FolOperatorKind::LessThan),
                                 ))
                             }
                             RightChevron => {
-                                self.expect(RightChevron);
+                                self.expect(RightChevron)?;
                                 op = Some(Operator::SpatialOperator(
                                     SpatialOperatorKind::FolOperator(FolOperatorKind::GreaterThan),
                                 ))
                             }
                             LeftChevronEqual => {
-                                self.expect(LeftChevronEqual);
+                                self.expect(LeftChevronEqual)?;
                                 op = Some(Operator::SpatialOperator(
                                     SpatialOperatorKind::FolOperator(
                                         FolOperatorKind::LessThanEqualTo,
@@ -354,7 +1205,7 @@ impl Parser {
                                 ))
                             }
                             RightChevronEqual => {
-                                self.expect(RightChevronEqual);
+                                self.expect(RightChevronEqual)?;
                                 op = Some(Operator::SpatialOperator(
                                     SpatialOperatorKind::FolOperator(
                                         FolOperatorKind::GreaterThanEqualTo,
@@ -366,58 +1217,233 @@ impl Parser {
                     }
 
                     let rhs = self.parse_s4m();
-                    node = Some(Node::binary(op.unwrap(), lhs.unwrap(), rhs.unwrap()));
+                    node = Some(Tree::binary(op?, lhs?, rhs?));
                 }
 
                 // class
                 LeftBracket => {
                     node = self.parse_class();
                 }
-                _ => self.error(),
+                _ => {
+                    self.error();
+                    return None;
+                }
+            }
+        } else {
+            self.error();
+            return None;
+        }
+
+        node
+    }
+
+    /// Parse a sequence of operands separated by infix operators.
+    ///
+    /// This implements precedence climbing: each operand is parsed by
+    /// `primary`, and `infix` maps a token to the precedence of its operator
+    /// (higher binds tighter), if any. Only operators of at least `min`
+    /// precedence are consumed, and all operators are left-associative.
+    fn parse_infix(
+        &mut self,
+        min: usize,
+        primary: fn(&mut Self) -> Option<SpatialFormula>,
+        infix: fn(&TokenKind) -> Option<(usize, Operator)>,
+    ) -> Option<SpatialFormula> {
+        let mut node = primary(self);
+
+        while let Some((precedence, op)) = self.peek(1).and_then(|t| infix(&t.kind)) {
+            if precedence < min {
+                break;
+            }
+
+            self.current += 1;
+
+            // Parse the right operand.
+            //
+            // Only tighter operators are consumed by the right operand, such
+            // that an operator of the same precedence groups to the left.
+            let rhs = self.parse_infix(precedence + 1, primary, infix);
+            node = Some(Tree::binary(op, node?, rhs?));
+        }
+
+        node
+    }
+
+    /// Parse a predicate over the recognized text of annotations.
+    ///
+    /// This parse function captures the following grammar:
+    ///
+    /// ```text
+    /// text ::= '@' 'text' '(' tau ')' '=' Literal
+    ///        | '@' 'text' '(' tau ')' '~' Literal
+    /// ```
+    fn parse_text(&mut self) -> Option<SpatialFormula> {
+        self.expect(At)?;
+        self.expect(Identifier)?;
+        self.expect(LeftParen)?;
+        let child = self.parse_s4();
+        self.expect(RightParen)?;
+
+        let predicate = match self.peek(1).map(|t| t.kind.clone()) {
+            Some(Equal) => {
+                self.expect(Equal)?;
+                TextPredicate::Exact(self.expect(Literal)?.lexeme)
+            }
+            Some(Tilde) => {
+                self.expect(Tilde)?;
+                let pattern = self.expect(Literal)?;
+
+                match Regex::new(&pattern.lexeme) {
+                    Ok(re) => TextPredicate::Pattern(re),
+                    Err(_) => {
+                        self.errors.push(ParserError::at(
+                            &pattern.position,
+                            format!("invalid text pattern `{}`", pattern.lexeme),
+                        ));
+
+                        return None;
+                    }
+                }
             }
-        } else {
-            self.error();
+            _ => {
+                self.error();
+                return None;
+            }
+        };
+
+        Some(Tree::unary(
+            Operator::SpatialOperator(SpatialOperatorKind::S4uOperator(S4uOperatorKind::Text(
+                predicate,
+            ))),
+            child?,
+        ))
+    }
+
+    /// Parse a containment predicate.
+    ///
+    /// This parse function captures the following grammar:
+    ///
+    /// ```text
+    /// s4u ::= '@' 'in' '(' s4 ',' s4 ')'
+    /// ```
+    fn parse_within(&mut self) -> Option<SpatialFormula> {
+        self.expect(At)?;
+        self.expect(Identifier)?;
+        self.expect(LeftParen)?;
+        let lhs = self.parse_s4();
+        self.expect(Comma)?;
+        let rhs = self.parse_s4();
+        self.expect(RightParen)?;
+
+        Some(Tree::binary(
+            Operator::SpatialOperator(SpatialOperatorKind::S4uOperator(S4uOperatorKind::Within)),
+            lhs?,
+            rhs?,
+        ))
+    }
+
+    /// Parse an adjacency predicate.
+    ///
+    /// This parse function captures the following grammar:
+    ///
+    /// ```text
+    /// s4u ::= '@' 'touch' '(' s4 ',' s4 ')'
+    ///       | '@' 'touch' '(' s4 ',' s4 ',' (Real | Integer) ')'
+    /// ```
+    ///
+    /// If the tolerance is omitted, it defaults to zero.
+    fn parse_touch(&mut self) -> Option<SpatialFormula> {
+        self.expect(At)?;
+        self.expect(Identifier)?;
+        self.expect(LeftParen)?;
+        let lhs = self.parse_s4();
+        self.expect(Comma)?;
+        let rhs = self.parse_s4();
+
+        let mut tolerance = 0.0;
+        if let Some(Comma) = self.peek(1).map(|t| t.kind.clone()) {
+            self.expect(Comma)?;
+
+            let number = match self.peek(1).map(|t| t.kind.clone()) {
+                Some(Integer) => self.expect(Integer)?,
+                _ => self.expect(Real)?,
+            };
+
+            tolerance = number.lexeme.parse().unwrap();
         }
 
-        while let Some(token) = self.peek(1) {
-            if token.kind != EndOfFile {
-                match token.kind {
-                    // conjunction
-                    And => {
-                        self.expect(And);
-
-                        let right = self.parse_s4u();
-                        node = Some(Node::binary(
-                            Operator::SpatialOperator(SpatialOperatorKind::FolOperator(
-                                FolOperatorKind::Conjunction,
-                            )),
-                            node.unwrap(),
-                            right.unwrap(),
-                        ));
-                    }
+        self.expect(RightParen)?;
 
-                    // disjunction
-                    Or => {
-                        self.expect(Or);
+        Some(Tree::binary(
+            Operator::SpatialOperator(SpatialOperatorKind::S4uOperator(S4uOperatorKind::Touch(
+                tolerance,
+            ))),
+            lhs?,
+            rhs?,
+        ))
+    }
 
-                        let right = self.parse_s4u();
-                        node = Some(Node::binary(
-                            Operator::SpatialOperator(SpatialOperatorKind::FolOperator(
-                                FolOperatorKind::Disjunction,
-                            )),
-                            node.unwrap(),
-                            right.unwrap(),
-                        ));
-                    }
+    /// Parse a relative position predicate.
+    ///
+    /// This parse function captures the following grammar:
+    ///
+    /// ```text
+    /// s4u ::= '@' ('leftof' | 'rightof' | 'above' | 'below') '(' s4 ',' s4 ')'
+    /// ```
+    fn parse_relation(&mut self) -> Option<SpatialFormula> {
+        self.expect(At)?;
+        let relation = Relation::from_name(&self.expect(Identifier)?.lexeme)?;
+        self.expect(LeftParen)?;
+        let lhs = self.parse_s4();
+        self.expect(Comma)?;
+        let rhs = self.parse_s4();
+        self.expect(RightParen)?;
+
+        Some(Tree::binary(
+            Operator::SpatialOperator(SpatialOperatorKind::S4uOperator(S4uOperatorKind::Relation(
+                relation,
+            ))),
+            lhs?,
+            rhs?,
+        ))
+    }
 
-                    _ => break,
-                }
-            } else {
-                break;
+    /// Parse a track-identity predicate.
+    ///
+    /// This parse function captures the following grammar:
+    ///
+    /// ```text
+    /// s4u ::= '@' 'same' '(' Identifier ')'
+    /// ```
+    fn parse_same(&mut self) -> Option<SpatialFormula> {
+        self.expect(At)?;
+        self.expect(Identifier)?;
+        self.expect(LeftParen)?;
+        let name = self.expect(Identifier)?;
+        self.expect(RightParen)?;
+
+        Some(Tree::unary(
+            Operator::SpatialOperator(SpatialOperatorKind::S4uOperator(S4uOperatorKind::Same)),
+            OperandKind::Variable(name.lexeme),
+        ))
+    }
+
+    /// Parse an optional distinct marker of a quantifier.
+    ///
+    /// This parse function captures the following grammar:
+    ///
+    /// ```text
+    /// distinct ::= '#' | ε
+    /// ```
+    fn parse_distinct(&mut self) -> bool {
+        if let Some(token) = self.peek(1) {
+            if token.kind == Hash {
+                self.expect(Hash);
+                return true;
             }
         }
 
-        node
+        false
     }
 
     /// Parse a set of bindings.
@@ -429,30 +1455,30 @@ impl Parser {
     ///            | Identifier Walrus class Comma bindings
     /// ```
     ///
-    fn parse_bindings(&mut self) -> HashMap<String, SpatialFormula> {
+    fn parse_bindings(&mut self) -> Option<HashMap<String, SpatialFormula>> {
         let mut table = HashMap::new();
 
-        let variable = self.expect(Identifier);
-        self.expect(Walrus);
+        let variable = self.expect(Identifier)?;
+        self.expect(Walrus)?;
         let class = self.parse_class();
 
         // Insert the quantified variable.
         //
         // This creates a new entry with the name of the variable that is
         // associated with a [`class`].
-        table.insert(variable.lexeme, class.unwrap());
+        table.insert(variable.lexeme, class?);
 
         if let Some(token) = self.peek(1) {
             match token.kind {
                 Comma => {
-                    self.expect(Comma);
-                    table.extend(self.parse_bindings());
+                    self.expect(Comma)?;
+                    table.extend(self.parse_bindings()?);
                 }
-                _ => return table,
+                _ => return Some(table),
             }
         }
 
-        table
+        Some(table)
     }
 
     /// Parse an S4m-based expression.
@@ -461,155 +1487,143 @@ impl Parser {
     ///
     /// ```text
     /// psi ::= '(' psi ')' | Real | Integer | '\' Identifier '(' tau ')'
-    ///       | '\' Identifier '(' tau ',' tau ')' | '-' psi
-    ///       | psi '-' psi | psi '*' psi | psi '/' psi
+    ///       | '\' Identifier '(' tau ',' tau ')' | '#' tau | '-' psi
+    ///       | psi '+' psi | psi '-' psi | psi '*' psi | psi '/' psi
     /// ```
+    ///
+    /// From loosest to tightest, the operators bind as `+` and `-`, `*` and
+    /// `/`, then the unary `-`, where binary operators are left-associative.
+    ///
+    /// Note: `#` is shorthand for the `count` function, and applies to the
+    /// tightest S4 expression that follows (e.g., a class).
     fn parse_s4m(&mut self) -> Option<SpatialFormula> {
+        self.parse_infix(0, Self::parse_s4m_primary, |kind| match kind {
+            Plus => Some((
+                1,
+                Operator::SpatialOperator(SpatialOperatorKind::S4mOperator(
+                    S4mOperatorKind::Addition,
+                )),
+            )),
+            Minus => Some((
+                1,
+                Operator::SpatialOperator(SpatialOperatorKind::S4mOperator(
+                    S4mOperatorKind::Subtraction,
+                )),
+            )),
+            Star => Some((
+                2,
+                Operator::SpatialOperator(SpatialOperatorKind::S4mOperator(
+                    S4mOperatorKind::Multiplication,
+                )),
+            )),
+            Slash => Some((
+                2,
+                Operator::SpatialOperator(SpatialOperatorKind::S4mOperator(
+                    S4mOperatorKind::Division,
+                )),
+            )),
+            _ => None,
+        })
+    }
+
+    /// Parse an S4m-based expression without any infix operators.
+    fn parse_s4m_primary(&mut self) -> Option<SpatialFormula> {
         let mut node = None;
 
         if let Some(token) = self.peek(1) {
             match token.kind {
                 LeftParen => {
-                    self.expect(LeftParen);
+                    self.expect(LeftParen)?;
                     node = self.parse_s4m();
-                    self.expect(RightParen);
+                    self.expect(RightParen)?;
                 }
 
                 // function
                 At => {
-                    self.expect(At);
-                    let name = self.expect(Identifier);
+                    self.expect(At)?;
+                    let name = self.expect(Identifier)?;
 
-                    self.expect(LeftParen);
+                    self.expect(LeftParen)?;
                     let child = self.parse_s4();
 
                     if let Some(peeked) = self.peek(1) {
                         match peeked.kind {
                             Comma => {
-                                self.expect(Comma);
+                                self.expect(Comma)?;
                                 let right = self.parse_s4();
 
-                                node = Some(Node::binary(
+                                node = Some(Tree::binary(
                                     Operator::SpatialOperator(SpatialOperatorKind::S4mOperator(
                                         S4mOperatorKind::Function(name.lexeme),
                                     )),
-                                    child.unwrap(),
-                                    right.unwrap(),
+                                    child?,
+                                    right?,
                                 ));
                             }
                             _ => {
-                                node = Some(Node::unary(
+                                node = Some(Tree::unary(
                                     Operator::SpatialOperator(SpatialOperatorKind::S4mOperator(
                                         S4mOperatorKind::Function(name.lexeme),
                                     )),
-                                    child.unwrap(),
+                                    child?,
                                 ))
                             }
                         }
                     }
 
-                    self.expect(RightParen);
+                    self.expect(RightParen)?;
+                }
+
+                // count
+                Hash => {
+                    self.expect(Hash)?;
+                    let child = self.parse_s4_primary();
+
+                    node = Some(Tree::unary(
+                        Operator::SpatialOperator(SpatialOperatorKind::S4mOperator(
+                            S4mOperatorKind::Function(String::from("count")),
+                        )),
+                        child?,
+                    ));
                 }
 
                 // reals
                 Real => {
-                    let number = self.expect(Real);
-                    node = Some(Node::from(OperandKind::Number(
+                    let number = self.expect(Real)?;
+                    node = Some(Tree::from(OperandKind::Number(
                         number.lexeme.parse().unwrap(),
                     )));
                 }
 
                 // integer
                 Integer => {
-                    let number = self.expect(Integer);
-                    node = Some(Node::from(OperandKind::Number(
+                    let number = self.expect(Integer)?;
+                    node = Some(Tree::from(OperandKind::Number(
                         number.lexeme.parse().unwrap(),
                     )));
                 }
 
                 // inverse
                 Minus => {
-                    self.expect(Minus);
-                    let child = self.parse_s4m();
+                    self.expect(Minus)?;
+                    let child = self.parse_s4m_primary();
 
-                    node = Some(Node::unary(
+                    node = Some(Tree::unary(
                         Operator::SpatialOperator(SpatialOperatorKind::S4mOperator(
                             S4mOperatorKind::Inverse,
                         )),
-                        child.unwrap(),
+                        child?,
                     ));
                 }
 
-                _ => self.error(),
+                _ => {
+                    self.error();
+                    return None;
+                }
             }
         } else {
             self.error();
-        }
-
-        while let Some(token) = self.peek(1) {
-            if token.kind != EndOfFile {
-                match token.kind {
-                    // addition
-                    Plus => {
-                        self.expect(Plus);
-
-                        let rhs = self.parse_s4m();
-                        node = Some(Node::binary(
-                            Operator::SpatialOperator(SpatialOperatorKind::S4mOperator(
-                                S4mOperatorKind::Addition,
-                            )),
-                            node.unwrap(),
-                            rhs.unwrap(),
-                        ));
-                    }
-
-                    // subtraction
-                    Minus => {
-                        self.expect(Minus);
-
-                        let rhs = self.parse_s4m();
-                        node = Some(Node::binary(
-                            Operator::SpatialOperator(SpatialOperatorKind::S4mOperator(
-                                S4mOperatorKind::Subtraction,
-                            )),
-                            node.unwrap(),
-                            rhs.unwrap(),
-                        ));
-                    }
-
-                    // multiplication
-                    Star => {
-                        self.expect(Star);
-
-                        let rhs = self.parse_s4m();
-                        node = Some(Node::binary(
-                            Operator::SpatialOperator(SpatialOperatorKind::S4mOperator(
-                                S4mOperatorKind::Multiplication,
-                            )),
-                            node.unwrap(),
-                            rhs.unwrap(),
-                        ));
-                    }
-
-                    // division
-                    Slash => {
-                        self.expect(Slash);
-
-                        let rhs = self.parse_s4m();
-                        node = Some(Node::binary(
-                            Operator::SpatialOperator(SpatialOperatorKind::S4mOperator(
-                                S4mOperatorKind::Division,
-                            )),
-                            node.unwrap(),
-                            rhs.unwrap(),
-                        ));
-                    }
-
-                    _ => break,
-                }
-            } else {
-                break;
-            }
+            return None;
         }
 
         node
@@ -621,8 +1635,12 @@ impl Parser {
     ///
     /// ```text
     /// tau ::= '(' tau ')' | tau '&' tau | tau '|' tau | '!' tau | class
+    ///       | Identifier | '\' Identifier
     /// ```
     ///
+    /// From loosest to tightest, the operators bind as `|`, `&`, then `!`,
+    /// where binary operators are left-associative.
+    ///
     /// Note: The following symbol(s) have a different semantic meaning derived
     /// at parse time:
     ///
@@ -630,31 +1648,59 @@ impl Parser {
     /// `|`: Union
     /// `!`: Complementation
     fn parse_s4(&mut self) -> Option<SpatialFormula> {
-        let mut node = None;
+        self.parse_infix(0, Self::parse_s4_primary, |kind| match kind {
+            Or => Some((
+                1,
+                Operator::SpatialOperator(SpatialOperatorKind::S4Operator(S4OperatorKind::Union)),
+            )),
+            And => Some((
+                2,
+                Operator::SpatialOperator(SpatialOperatorKind::S4Operator(
+                    S4OperatorKind::Intersection,
+                )),
+            )),
+            _ => None,
+        })
+    }
+
+    /// Parse an S4-based expression without any infix operators.
+    fn parse_s4_primary(&mut self) -> Option<SpatialFormula> {
+        let node;
 
         if let Some(token) = self.peek(1) {
             match token.kind {
                 LeftParen => {
-                    self.expect(LeftParen);
+                    self.expect(LeftParen)?;
                     node = self.parse_s4();
-                    self.expect(RightParen);
+                    self.expect(RightParen)?;
                 }
 
                 Identifier => {
-                    let name = self.expect(Identifier);
-                    node = Some(Node::from(OperandKind::Variable(name.lexeme)));
+                    let name = self.expect(Identifier)?;
+                    node = Some(Tree::from(OperandKind::Variable(name.lexeme)));
+                }
+
+                // reference
+                Backslash => {
+                    let position = self.stream.buffer[self.current].position.clone();
+
+                    self.expect(Backslash)?;
+                    let name = self.expect(Identifier)?.lexeme;
+
+                    self.references.push((name.clone(), position));
+                    node = Some(Tree::from(OperandKind::Reference(name)));
                 }
 
                 // complementation
                 Not => {
-                    self.expect(Not);
+                    self.expect(Not)?;
 
-                    let child = self.parse_s4();
-                    node = Some(Node::unary(
+                    let child = self.parse_s4_primary();
+                    node = Some(Tree::unary(
                         Operator::SpatialOperator(SpatialOperatorKind::S4Operator(
                             S4OperatorKind::Complement,
                         )),
-                        child.unwrap(),
+                        child?,
                     ));
                 }
 
@@ -662,47 +1708,14 @@ impl Parser {
                 LeftBracket => {
                     node = self.parse_class();
                 }
-                _ => self.error(),
+                _ => {
+                    self.error();
+                    return None;
+                }
             }
         } else {
             self.error();
-        }
-
-        while let Some(token) = self.peek(1) {
-            if token.kind != EndOfFile {
-                match token.kind {
-                    // intersection
-                    And => {
-                        self.expect(And);
-
-                        let right = self.parse_s4();
-                        node = Some(Node::binary(
-                            Operator::SpatialOperator(SpatialOperatorKind::S4Operator(
-                                S4OperatorKind::Intersection,
-                            )),
-                            node.unwrap(),
-                            right.unwrap(),
-                        ));
-                    }
-
-                    // union
-                    Or => {
-                        self.expect(Or);
-
-                        let right = self.parse_s4();
-                        node = Some(Node::binary(
-                            Operator::SpatialOperator(SpatialOperatorKind::S4Operator(
-                                S4OperatorKind::Union,
-                            )),
-                            node.unwrap(),
-                            right.unwrap(),
-                        ));
-                    }
-                    _ => break,
-                }
-            } else {
-                break;
-            }
+            return None;
         }
 
         node
@@ -724,16 +1737,78 @@ impl Parser {
     /// This parse function captures the following grammar:
     ///
     /// ```text
-    /// object ::= '[' ':' Identifier ':' ']'
+    /// object ::= '[' ':' name ('|' name)* ':' ']'
+    /// name   ::= (Identifier | Integer | Real | '.' | '-' | '*')+
     /// ```
+    ///
+    /// The tokens of a name must not be separated (e.g., `vehicle.car`). A
+    /// single name without any `*` is a class (unless a super-class of the
+    /// [`ClassMap`]); otherwise, it is a set of classes.
     fn parse_object(&mut self) -> Option<SpatialFormula> {
-        self.expect(LeftBracket);
-        self.expect(Colon);
-        let name = self.expect(Identifier).lexeme;
-        self.expect(Colon);
-        self.expect(RightBracket);
+        self.expect(LeftBracket)?;
+        let open = self.expect(Colon)?;
+
+        let mut source = String::new();
+        let mut end = None;
+
+        while let Some(token) = self.peek(1).cloned() {
+            match token.kind {
+                Identifier | Integer | Real | Dot | Minus | Star | Or => {
+                    // Check that the token is adjacent to the previous token.
+                    //
+                    // Otherwise, whitespace within a name would be ignored.
+                    if end.is_some_and(|end| end != token.position) {
+                        self.error();
+                        return None;
+                    }
+
+                    self.current += 1;
+                    end = Some(Position(
+                        token.position.0,
+                        token.position.1 + token.lexeme.len(),
+                    ));
+                    source.push_str(&token.lexeme);
+                }
+                _ => break,
+            }
+        }
+
+        self.expect(Colon)?;
+        self.expect(RightBracket)?;
+
+        // Expand a super-class into the labels beneath it.
+        //
+        // A label of the dataset may share the name of a super-class;
+        // therefore, the super-class itself is matched as well.
+        let members = match &self.classmap {
+            Some(classmap) => classmap.members(&source),
+            None => Vec::new(),
+        };
+
+        let classes = if source.is_empty() || source.contains(['|', '*']) {
+            Classes::new(&source)
+        } else if !members.is_empty() {
+            Classes::of(&source, &members)
+        } else {
+            return match Label::intern(&source) {
+                Ok(label) => Some(Tree::from(OperandKind::Symbol(label))),
+                Err(e) => {
+                    self.errors
+                        .push(ParserError::at(&open.position, e.to_string()));
+
+                    None
+                }
+            };
+        };
+
+        match classes {
+            Ok(classes) => Some(Tree::from(OperandKind::Classes(classes))),
+            Err(e) => {
+                self.errors.push(ParserError::at(&open.position, e));
 
-        Some(Node::from(OperandKind::Symbol(name)))
+                None
+            }
+        }
     }
 
     /// Parse a range.
@@ -745,19 +1820,19 @@ impl Parser {
     ///         | '{' Integer ',' Integer '}'
     /// ```
     fn parse_range(&mut self) -> Option<RangeKind> {
-        self.expect(LeftBrace);
-        let min = self.expect(Integer).lexeme.parse().unwrap();
+        let open = self.expect(LeftBrace)?;
+        let min = self.integer()?;
 
         let range: Option<RangeKind> = if let Some(token) = self.peek(1) {
             if token.kind == Comma {
-                self.expect(Comma);
+                self.expect(Comma)?;
 
                 // TODO: It's possible that we match against both an Integer and
                 // Real and provide feedback that the real cannot be used in a
                 // range operation to the user.
                 if let Some(token) = self.peek(1) {
                     if token.kind == Integer {
-                        let max = self.expect(Integer).lexeme.parse().unwrap();
+                        let max = self.integer()?;
                         Some(RangeKind::Between(min, max))
                     } else {
                         Some(RangeKind::AtLeast(min))
@@ -772,8 +1847,349 @@ impl Parser {
             None
         };
 
-        self.expect(RightBrace);
+        self.expect(RightBrace)?;
+
+        match range {
+            Some(RangeKind::Between(min, max)) if min > max => {
+                self.errors.push(ParserError::at(
+                    &open.position,
+                    format!("range `{{{},{}}}` is empty", min, max),
+                ));
+
+                None
+            }
+            range => range,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ParserError {
+    msg: String,
+
+    /// The position of the source that the error is found at, if any.
+    position: Option<Position>,
+
+    /// The number of characters of the source that the error spans.
+    length: usize,
+}
+
+impl ParserError {
+    /// Create a new [`ParserError`] found at a position of the source.
+    ///
+    /// The error spans a single character, unless set otherwise (see
+    /// [`Self::spanning`]).
+    fn at(position: &Position, msg: impl Into<String>) -> Self {
+        ParserError {
+            msg: msg.into(),
+            position: Some(position.clone()),
+            length: 1,
+        }
+    }
+
+    /// Set the number of characters of the source that the error spans.
+    fn spanning(mut self, length: usize) -> Self {
+        self.length = std::cmp::max(length, 1);
+        self
+    }
+}
+
+impl From<&str> for ParserError {
+    fn from(msg: &str) -> Self {
+        ParserError::from(msg.to_string())
+    }
+}
+
+impl From<String> for ParserError {
+    fn from(msg: String) -> Self {
+        ParserError {
+            msg,
+            position: None,
+            length: 1,
+        }
+    }
+}
+
+impl From<&ParserError> for Diagnostic {
+    fn from(e: &ParserError) -> Self {
+        Diagnostic::new(e.position.clone(), e.length, e.msg.clone())
+    }
+}
+
+impl From<Vec<ParserError>> for ParserError {
+    /// Combine several [`ParserError`] into one, listed in order.
+    fn from(errors: Vec<ParserError>) -> Self {
+        if errors.len() == 1 {
+            return errors.into_iter().next().unwrap();
+        }
+
+        let msgs: Vec<String> = errors
+            .into_iter()
+            .map(|e| match e.position {
+                Some(Position(line, column)) => format!("{}:{}: {}", line, column, e.msg),
+                None => e.msg,
+            })
+            .collect();
+
+        ParserError::from(format!(
+            "found {} error(s):\n  {}",
+            msgs.len(),
+            msgs.join("\n  ")
+        ))
+    }
+}
+
+impl fmt::Display for ParserError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.position {
+            Some(Position(line, column)) => write!(f, "parser: {}:{}: {}", line, column, self.msg),
+            None => write!(f, "parser: {}", self.msg),
+        }
+    }
+}
+
+impl Error for ParserError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::compiler::lexer::stream::CharStream;
+    use crate::compiler::lexer::Lexer;
+
+    fn parse(source: &str) -> Result<AbstractSyntaxTree, Vec<String>> {
+        let stream = Lexer::new(CharStream::from(source)).lex();
+
+        Parser::new(stream)
+            .parse()
+            .map_err(|errors| errors.iter().map(|e| e.to_string()).collect())
+    }
+
+    #[test]
+    fn collect_errors() {
+        assert!(parse("[[:a:]][[:b:]]").is_ok());
+
+        assert_eq!(
+            parse("[[:a:] & ][[:b:] |]").err().unwrap(),
+            vec!["parser: 1:10: syntax error", "parser: 1:19: syntax error"]
+        );
+
+        assert_eq!(
+            parse("[@x([:a:]) > 1][[:b:] &]|[[:c:]").err().unwrap(),
+            vec![
+                "parser: 1:24: syntax error",
+                "parser: 1:32: expected RightBracket but found EndOfFile",
+            ]
+        );
+    }
+
+    #[test]
+    fn precedence() {
+        let describe = |source: &str| {
+            let root = parse(source).unwrap().root.unwrap();
+            let formula = root.operands().next().unwrap();
+
+            crate::monitor::explain::describe(formula, formula.root())
+        };
+
+        assert_eq!(
+            describe("[NE([:a:]) | NE([:b:]) & !NE([:c:]) | NE([:d:])]"),
+            "(NE([:a:]) | (NE([:b:]) & !NE([:c:]))) | NE([:d:])"
+        );
+        assert_eq!(
+            describe("[NE([:a:] | [:b:] & ![:c:])]"),
+            "NE([:a:] | ([:b:] & ![:c:]))"
+        );
+        assert_eq!(
+            describe("[@x([:a:]) - 1 - 2 > 2 * 3 + -1 / 4]"),
+            "((@x([:a:]) - 1.0) - 2.0) > ((2.0 * 3.0) + (-1.0 / 4.0))"
+        );
+
+        assert!(parse("[@x([:a:]) < 1 < 2]").is_err());
+    }
+
+    #[test]
+    fn unordered_group() {
+        let operands = |source: &str| parse(source).unwrap().root.unwrap().operands().count();
+
+        // Each order of the subpatterns is an alternative.
+        assert_eq!(operands("{[[:a:]], [[:b:]]}"), 4);
+        assert_eq!(operands("{[[:a:]], [[:b:]], [[:c:]]}"), 18);
+
+        // A brace that follows a subpattern is a range only with a minimum.
+        assert_eq!(operands("[[:a:]]{[[:b:]], [[:c:]]}"), 5);
+        assert_eq!(operands("[[:a:]]{2}"), 1);
+
+        assert_eq!(
+            parse("{[[:a:]], [[:b:]], [[:c:]], [[:d:]], [[:e:]], [[:f:]], [[:g:]]}")
+                .err()
+                .unwrap(),
+            vec!["parser: 1:1: at most 6 subpatterns of an unordered group are supported"]
+        );
+    }
+
+    #[test]
+    fn classes() {
+        let operand = |source: &str| {
+            let root = parse(source).unwrap().root.unwrap();
+            let formula = root.operands().next().unwrap();
+
+            let operand = formula.operands().next().unwrap().clone();
+            operand
+        };
+
+        assert!(matches!(
+            operand("[[:vehicle.car:]]"),
+            OperandKind::Symbol(label) if label == Label::from("vehicle.car")
+        ));
+
+        let OperandKind::Classes(classes) = operand("[NE([:car|vehicle.*:])]") else {
+            panic!("expected a set of classes");
+        };
+
+        assert!(classes.contains(&Label::from("car")));
+        assert!(classes.contains(&Label::from("vehicle.truck")));
+        assert!(!classes.contains(&Label::from("vehicle")));
+        assert!(!classes.contains(&Label::from("vehicleXcar")));
+
+        assert!(parse("[[:vehicle .car:]]").is_err());
+        assert_eq!(
+            parse("[[:car|:]]").err().unwrap(),
+            vec!["parser: 1:3: empty class within `car|`"]
+        );
+    }
+
+    #[test]
+    fn classmap() {
+        let classmap = ClassMap::parse("car, truck -> vehicle").unwrap();
+        let stream = Lexer::new(CharStream::from("[[:vehicle:]]")).lex();
+
+        let root = Parser::new(stream)
+            .classmap(Some(classmap))
+            .parse()
+            .unwrap()
+            .root
+            .unwrap();
+
+        let formula = root.operands().next().unwrap();
+
+        let Some(OperandKind::Classes(classes)) = formula.operands().next() else {
+            panic!("expected a set of classes");
+        };
+
+        assert_eq!(classes.source, "vehicle");
+        assert!(classes.contains(&Label::from("vehicle")));
+        assert!(classes.contains(&Label::from("truck")));
+        assert!(!classes.contains(&Label::from("bus")));
+    }
+
+    #[test]
+    fn one_or_more() {
+        let root = parse("[[:a:]][[:b:]]+").unwrap().root.unwrap();
+
+        // The spatial formula of the repeated subpattern is not duplicated.
+        assert_eq!(root.operands().count(), 2);
+
+        let Node::BinaryExpr { rhs, .. } = root[root.root()] else {
+            panic!("expected a concatenation");
+        };
+
+        assert!(matches!(
+            root[rhs],
+            Node::UnaryExpr {
+                op: Operator::RegexOperator(RegexOperatorKind::Range(RangeKind::AtLeast(1))),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn range() {
+        assert!(parse("[[:car:]]{1,1}").is_ok());
+        assert_eq!(
+            parse("[[:car:]]{3,1}").err().unwrap(),
+            vec!["parser: 1:10: range `{3,1}` is empty"]
+        );
+    }
+
+    #[test]
+    fn conjunction() {
+        let root = parse("[[:a:]]* && [[:b:]][[:c:]]").unwrap().root.unwrap();
+
+        assert!(matches!(
+            root[root.root()],
+            Node::BinaryExpr {
+                op: Operator::RegexOperator(RegexOperatorKind::Conjunction),
+                ..
+            }
+        ));
+
+        assert_eq!(
+            parse("([[:a:]] && [[:b:]])").err().unwrap(),
+            vec!["parser: 1:10: conjunction is only supported at the top level of a pattern"]
+        );
+    }
+
+    #[test]
+    fn temporal() {
+        let root = parse("[[:a:]] U[1,3] [[:b:]] G[0,2] ([[:c:]] | [[:d:]])")
+            .unwrap()
+            .root
+            .unwrap();
+
+        assert!(matches!(
+            root[root.root()],
+            Node::BinaryExpr {
+                op: Operator::RegexOperator(RegexOperatorKind::Concatenation),
+                ..
+            }
+        ));
+
+        // The keywords are not reserved outside of the pattern.
+        assert!(parse("[E(G := [:a:]) NE(G)]").is_ok());
+
+        assert_eq!(
+            parse("F[2,1] [[:a:]]").err().unwrap(),
+            vec!["parser: 1:2: interval `[2,1]` is empty"]
+        );
+        assert_eq!(
+            parse("G[0,99999999999999999999][[:car:]]").err().unwrap()[0],
+            "parser: 1:5: integer `99999999999999999999` is too large (at most 10000)"
+        );
+        assert_eq!(
+            parse("G[0,18446744073709551615] [[:car:]]").err().unwrap()[0],
+            "parser: 1:5: integer `18446744073709551615` is too large (at most 10000)"
+        );
+        assert!(parse("G[0,10000] [[:car:]]").is_ok());
+        assert_eq!(
+            parse("[[:car:]]{99999999999999999999}").err().unwrap(),
+            vec!["parser: 1:11: integer `99999999999999999999` is too large (at most 10000)"]
+        );
+        assert_eq!(
+            parse("G[0,1] [[:a:]]*")
+                .ok()
+                .map(|ast| ast.root.unwrap().operands().count()),
+            Some(1)
+        );
+        assert_eq!(
+            parse("G[0,1] ([[:a:]][[:b:]])").err().unwrap(),
+            vec!["parser: 1:1: operand of a temporal operator must match a single frame"]
+        );
+    }
 
-        range
+    #[test]
+    fn gap() {
+        assert!(parse("[[:a:]] ~{<=3} [[:b:]]").is_ok());
+
+        assert_eq!(
+            parse("[[:a:]] ~{<=0} [[:b:]]").err().unwrap(),
+            vec!["parser: 1:9: gap `~{<=0}` is empty"]
+        );
+        assert_eq!(
+            parse("[[:a:]] ~{<=99999999999999999999} [[:b:]]")
+                .err()
+                .unwrap(),
+            vec!["parser: 1:13: integer `99999999999999999999` is too large (at most 10000)"]
+        );
     }
 }
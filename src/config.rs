@@ -2,6 +2,25 @@
 //!
 
 use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::compiler::ir::ops::Vacuity;
+use crate::compiler::Compiler;
+use crate::controller::deadline::Overrun;
+use crate::controller::metrics::Window;
+use crate::datastream::buffer::Overflow;
+use crate::datastream::gap::Policy;
+use crate::datastream::io::Format;
+use crate::datastream::roi::Roi;
+use crate::datastream::transform::Transform;
+
+use self::classmap::ClassMap;
+use self::ontology::Ontology;
+use self::predicate::Predicate;
+
+pub mod classmap;
+pub mod ontology;
+pub mod predicate;
 
 /// Configuration information for Application.
 ///
@@ -15,21 +34,247 @@ pub struct Configuration<'a> {
     /// the source is standard input.
     pub datastream: Option<&'a PathBuf>,
 
+    /// The format of the data stream.
+    pub format: Format,
+
+    /// The topic of a ROS bag to read the detections of, if selected.
+    ///
+    /// If none is selected, the first topic of a supported type is read.
+    pub topic: Option<&'a String>,
+
+    /// The named patterns searched for instead of the `pattern`, if any.
+    ///
+    /// Each pattern is searched over the same imported frames, such that a
+    /// data stream is imported once for all of them.
+    pub queries: Option<Vec<(String, String)>>,
+
     /// Use the online algorithm.
     pub online: bool,
 
+    /// The file the pattern is reloaded from when it changes, if any.
+    ///
+    /// This is only supported by the online algorithm; the frames kept are
+    /// searched with the new pattern from the next request.
+    pub reload: Option<&'a PathBuf>,
+
+    /// The maximum number of frames kept when searching online.
+    ///
+    /// This bounds the search of patterns with an unbounded horizon (e.g.,
+    /// `*`). Therefore, matches longer than the window are not found.
+    pub window: Option<usize>,
+
+    /// The number of frames of each chunk when searching offline in chunks.
+    ///
+    /// Only the frames of a chunk (and the horizon of the pattern after it)
+    /// are kept in memory, rather than all frames.
+    pub chunk: Option<usize>,
+
+    /// The window of rolling statistics printed when searching online.
+    ///
+    /// A report is printed once per window (e.g., every 100 frames or every
+    /// 30 seconds).
+    pub stats: Option<Window>,
+
+    /// The time budget of each frame when searching online.
+    ///
+    /// The frames are assumed to arrive once per budget, such that a frame
+    /// searched for longer puts the search behind the input.
+    pub deadline: Option<Duration>,
+
+    /// The handling of a frame that misses its deadline.
+    pub overrun: Overrun,
+
+    /// The maximum number of documents read ahead of the online search.
+    ///
+    /// If this is `None`, the data stream is read only as it is searched.
+    pub buffer: Option<usize>,
+
+    /// The handling of a document read while the buffer is full.
+    pub overflow: Overflow,
+
+    /// The maximum number of frames of a match that may violate their symbol.
+    pub fuzzy: usize,
+
+    /// The truth of universal quantifiers over an empty domain.
+    pub vacuity: Vacuity,
+
+    /// The truth of temporal operators past the end of the data stream.
+    pub end: Vacuity,
+
+    /// Reject patterns with shadowed or unused bindings.
+    pub strict: bool,
+
     /// A collection of channels to import.
     pub channels: Option<Vec<&'a String>>,
 
+    /// Merge the detections of all imported channels into a single sample.
+    pub merge: bool,
+
+    /// Match the pattern against the sub-stream of each channel independently.
+    pub split: bool,
+
+    /// Merge all files into a single stream, aligned by timestamp.
+    ///
+    /// Frames captured within this many seconds of each other are merged into
+    /// a single frame.
+    pub align: Option<f64>,
+
+    /// The IoU threshold of Non-Maximum Suppression applied at import.
+    ///
+    /// This is applied to the annotations of each channel before its channels
+    /// are merged, if requested. Cuboids are never suppressed.
+    pub nms: Option<f64>,
+
+    /// The region of interest that annotations must intersect to be imported.
+    pub roi: Option<Roi>,
+
     /// Maximum number of matches to search for.
     pub limit: Option<usize>,
 
     /// Export the data of a match.
+    ///
+    /// Image metadata is only imported when exporting, as it is otherwise
+    /// unused.
     pub export: bool,
 
+    /// Only export the annotations that witness the formulas of a match.
+    pub witness: bool,
+
+    /// Print the trajectory of each tracked object that witnesses a match.
+    pub trajectory: bool,
+
+    /// Compute the robustness of each match (see [`Monitor::score`]).
+    ///
+    /// [`Monitor::score`]: crate::monitor::Monitor::score
+    pub robustness: bool,
+
+    /// The directory that relative image paths are resolved against.
+    ///
+    /// Exported image paths are resolved as well, such that they remain valid
+    /// regardless of where the data was produced.
+    pub image_root: Option<&'a PathBuf>,
+
+    /// The directory that the images of each match are copied to.
+    ///
+    /// Similar to exporting, image metadata is imported to do so.
+    pub images: Option<&'a PathBuf>,
+
+    /// Publish each match to a visualization sink (e.g., Foxglove Studio).
+    ///
+    /// Similar to copying, image metadata is imported to do so.
+    pub publish: bool,
+
     /// Do not print anything.
     pub quiet: bool,
 
+    /// Print the truth of each symbol and the active states of each frame.
+    pub trace: bool,
+
+    /// Explain the longest partial match if no match is found.
+    pub explain: bool,
+
+    /// The number of partial matches to report, ranked by length.
+    pub partial: Option<usize>,
+
+    /// Import data regardless of its declared version.
+    pub force: bool,
+
+    /// Abort on the first malformed frame or annotation.
+    ///
+    /// By default, malformed records are skipped with a warning instead.
+    pub fail_fast: bool,
+
     /// Ignore the first `skip` amount of frames.
     pub skip: Option<usize>,
+
+    /// Ignore frames with an index less than `from`.
+    pub from: Option<usize>,
+
+    /// Persist a frame index alongside each file to seek with.
+    pub index: bool,
+
+    /// A mapping of dataset labels to canonical labels applied at import.
+    pub ontology: Option<Ontology>,
+
+    /// A mapping of labels to super-classes that the pattern may refer to.
+    pub classmap: Option<ClassMap>,
+
+    /// A collection of predicates over frame metadata that must all hold.
+    pub predicates: Option<Vec<Predicate>>,
+
+    /// A sequence of transforms applied to each frame after import.
+    pub transforms: Option<Vec<Transform>>,
+
+    /// The handling of gaps of missing frames in the data stream.
+    pub gaps: Policy,
+}
+
+impl<'a> Configuration<'a> {
+    /// Create a new [`Configuration`] with default settings.
+    ///
+    /// This searches over standard input with the offline algorithm, and all
+    /// other settings are disabled.
+    pub fn new(pattern: &'a String) -> Self {
+        Configuration {
+            pattern,
+            datastream: None,
+            format: Format::default(),
+            topic: None,
+            queries: None,
+            online: false,
+            reload: None,
+            window: None,
+            chunk: None,
+            stats: None,
+            deadline: None,
+            overrun: Overrun::default(),
+            buffer: None,
+            overflow: Overflow::default(),
+            fuzzy: 0,
+            vacuity: Vacuity::default(),
+            end: Vacuity::default(),
+            strict: false,
+            channels: None,
+            merge: false,
+            split: false,
+            align: None,
+            nms: None,
+            roi: None,
+            limit: None,
+            export: false,
+            witness: false,
+            trajectory: false,
+            robustness: false,
+            image_root: None,
+            images: None,
+            publish: false,
+            quiet: false,
+            trace: false,
+            explain: false,
+            partial: None,
+            force: false,
+            fail_fast: false,
+            skip: None,
+            from: None,
+            index: false,
+            ontology: None,
+            classmap: None,
+            predicates: None,
+            transforms: None,
+            gaps: Policy::default(),
+        }
+    }
+
+    /// Create a [`Compiler`] of patterns with the settings of the
+    /// [`Configuration`].
+    ///
+    /// All patterns must be compiled with it, such that a pattern has the same
+    /// semantics regardless of how it is searched.
+    pub fn compiler(&self) -> Compiler {
+        Compiler::new()
+            .vacuity(self.vacuity)
+            .end(self.end)
+            .strict(self.strict)
+            .classmap(self.classmap.clone())
+    }
 }
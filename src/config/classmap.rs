@@ -0,0 +1,160 @@
+//! Class label hierarchies.
+//!
+//! The labels of a dataset are often finer than the classes a pattern is
+//! concerned with (e.g., `car`, `bus`, and `truck` are all vehicles). A
+//! [`ClassMap`] groups labels under user-defined super-classes, such that a
+//! single SpRE can refer to any of them across datasets.
+
+use std::collections::{BTreeSet, HashMap};
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// A mapping from labels to the super-classes they belong to.
+///
+/// The mapping is described by lines of labels followed by the super-class
+/// they belong to, where a super-class may itself belong to another:
+///
+/// ```text
+/// # Vehicles.
+/// car, bus, truck -> vehicle
+/// vehicle, pedestrian -> road_user
+/// ```
+///
+/// Unlike an [`Ontology`](super::ontology::Ontology), labels are kept as they
+/// are, and a label may belong to several super-classes.
+#[derive(Clone, Debug, Default)]
+pub struct ClassMap {
+    parents: HashMap<String, Vec<String>>,
+}
+
+impl ClassMap {
+    /// Load a [`ClassMap`] from a file.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let source = fs::read_to_string(path).map_err(|e| {
+            ClassMapError::from(format!("{}: unable to read file: {}", path.display(), e))
+        })?;
+
+        Self::parse(&source)
+    }
+
+    /// Parse a [`ClassMap`] from its textual representation.
+    ///
+    /// Blank lines and comments (i.e., from a `#` to the end of the line) are
+    /// ignored. An error is returned if any other line is malformed.
+    pub fn parse(source: &str) -> Result<Self, Box<dyn Error>> {
+        let mut parents: HashMap<String, Vec<String>> = HashMap::new();
+
+        for (n, line) in source.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            let malformed = || {
+                ClassMapError::from(format!(
+                    "{}: expected `LABEL, ... -> CLASS`, found `{}`",
+                    n + 1,
+                    line
+                ))
+            };
+
+            let (labels, class) = line.split_once("->").ok_or_else(malformed)?;
+            let class = class.trim();
+
+            if class.is_empty() || class.contains(',') {
+                return Err(Box::new(malformed()));
+            }
+
+            for label in labels.split(',').map(str::trim) {
+                if label.is_empty() {
+                    return Err(Box::new(malformed()));
+                }
+
+                let entry = parents.entry(label.to_string()).or_default();
+
+                if !entry.iter().any(|c| c == class) {
+                    entry.push(class.to_string());
+                }
+            }
+        }
+
+        Ok(ClassMap { parents })
+    }
+
+    /// Retrieve the labels that belong to a super-class.
+    ///
+    /// This includes the labels of any super-class beneath it, and is empty if
+    /// it is not a super-class. The labels are sorted, such that the result is
+    /// reproducible.
+    pub fn members(&self, class: &str) -> Vec<String> {
+        let mut members = BTreeSet::new();
+        let mut pending = vec![class];
+
+        while let Some(class) = pending.pop() {
+            for (label, parents) in self.parents.iter() {
+                if parents.iter().any(|p| p == class)
+                    && label != class
+                    && members.insert(label.clone())
+                {
+                    pending.push(label);
+                }
+            }
+        }
+
+        members.into_iter().collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ClassMapError {
+    msg: String,
+}
+
+impl From<&str> for ClassMapError {
+    fn from(msg: &str) -> Self {
+        ClassMapError {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for ClassMapError {
+    fn from(msg: String) -> Self {
+        ClassMapError { msg }
+    }
+}
+
+impl fmt::Display for ClassMapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "classmap: {}", self.msg)
+    }
+}
+
+impl Error for ClassMapError {}
+
+#[cfg(test)]
+mod tests {
+    use super::ClassMap;
+
+    #[test]
+    fn members() {
+        let classmap = ClassMap::parse(
+            "# Vehicles.\ncar, bus, truck -> vehicle\n\nvehicle, pedestrian -> road_user # All.\n",
+        )
+        .unwrap();
+
+        assert_eq!(classmap.members("vehicle"), ["bus", "car", "truck"]);
+        assert_eq!(
+            classmap.members("road_user"),
+            ["bus", "car", "pedestrian", "truck", "vehicle"]
+        );
+        assert!(classmap.members("car").is_empty());
+
+        assert!(ClassMap::parse("car -> vehicle, truck").is_err());
+        assert!(ClassMap::parse("car, -> vehicle").is_err());
+        assert!(ClassMap::parse("car vehicle").is_err());
+    }
+}
@@ -0,0 +1,145 @@
+//! Class label aliasing.
+//!
+//! Datasets rarely agree on a label vocabulary (e.g., `car` versus
+//! `vehicle.car`). An [`Ontology`] maps dataset-specific labels onto a set of
+//! canonical labels so a single SpRE can be used across datasets.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// A mapping from dataset labels to canonical labels.
+///
+/// The mapping is described by a JSON object where each key is a canonical
+/// label and each value is the list of labels that alias it:
+///
+/// ```json
+/// {
+///   "car": ["vehicle.car", "taxi", "van"],
+///   "pedestrian": ["person", "human.pedestrian.adult"]
+/// }
+/// ```
+///
+/// Only JSON is supported; a YAML file (i.e., `.yaml` or `.yml`) is rejected
+/// rather than misread.
+#[derive(Clone, Debug, Default)]
+pub struct Ontology {
+    aliases: HashMap<String, String>,
+}
+
+impl Ontology {
+    /// Load an [`Ontology`] from a file.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        if let Some("yaml" | "yml") = path.extension().and_then(|e| e.to_str()) {
+            return Err(Box::new(OntologyError::from(format!(
+                "{}: YAML is not supported (write the mapping as JSON)",
+                path.display()
+            ))));
+        }
+
+        let source = fs::read_to_string(path).map_err(|e| {
+            OntologyError::from(format!("{}: unable to read file: {}", path.display(), e))
+        })?;
+
+        Self::parse(&source)
+    }
+
+    /// Parse an [`Ontology`] from its JSON representation.
+    ///
+    /// An alias may only refer to a single canonical label. Therefore, if the
+    /// same alias is listed under two different labels, an error is returned.
+    pub fn parse(source: &str) -> Result<Self, Box<dyn Error>> {
+        let table: HashMap<String, Vec<String>> = serde_json::from_str(source)
+            .map_err(|e| OntologyError::from(format!("malformed mapping: {}", e)))?;
+
+        let mut aliases = HashMap::new();
+
+        for (canonical, labels) in table.into_iter() {
+            for label in labels.into_iter() {
+                if let Some(previous) = aliases.get(&label) {
+                    if previous != &canonical {
+                        return Err(Box::new(OntologyError::from(format!(
+                            "alias `{}` maps to both `{}` and `{}`",
+                            label, previous, canonical
+                        ))));
+                    }
+                }
+
+                aliases.insert(label, canonical.clone());
+            }
+        }
+
+        Ok(Ontology { aliases })
+    }
+
+    /// Resolve a label to its canonical label.
+    ///
+    /// If no alias exists for the label, the label itself is returned.
+    pub fn resolve<'a>(&'a self, label: &'a str) -> &'a str {
+        match self.aliases.get(label) {
+            Some(canonical) => canonical,
+            None => label,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct OntologyError {
+    msg: String,
+}
+
+impl From<&str> for OntologyError {
+    fn from(msg: &str) -> Self {
+        OntologyError {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for OntologyError {
+    fn from(msg: String) -> Self {
+        OntologyError { msg }
+    }
+}
+
+impl fmt::Display for OntologyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ontology: {}", self.msg)
+    }
+}
+
+impl Error for OntologyError {}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::Ontology;
+
+    #[test]
+    fn resolve_aliases() {
+        let ontology = Ontology::parse(r#"{ "car": ["vehicle.car", "taxi"] }"#).unwrap();
+
+        assert_eq!(ontology.resolve("vehicle.car"), "car");
+        assert_eq!(ontology.resolve("taxi"), "car");
+        assert_eq!(ontology.resolve("car"), "car");
+        assert_eq!(ontology.resolve("truck"), "truck");
+    }
+
+    #[test]
+    fn reject_conflicting_aliases() {
+        let ontology = Ontology::parse(r#"{ "car": ["van"], "truck": ["van"] }"#);
+        assert!(ontology.is_err());
+    }
+
+    #[test]
+    fn reject_yaml() {
+        let e = Ontology::load(Path::new("ontology.yaml")).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "ontology: ontology.yaml: YAML is not supported (write the mapping as JSON)"
+        );
+    }
+}
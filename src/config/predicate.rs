@@ -0,0 +1,183 @@
+//! Frame metadata predicates.
+//!
+//! Frames may carry arbitrary metadata (e.g., weather, location, scene tags).
+//! A [`Predicate`] compares a single metadata entry against a value such that
+//! a search may be restricted to frames of interest (e.g., night scenes).
+
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+
+use indexmap::IndexMap;
+use serde_json::Value;
+
+/// The comparison operators of a [`Predicate`].
+///
+/// The operators are listed such that two-character operators are matched
+/// before their one-character prefixes.
+const OPERATORS: [(&str, Comparison); 6] = [
+    ("!=", Comparison::NotEqual),
+    ("<=", Comparison::LessThanEqualTo),
+    (">=", Comparison::GreaterThanEqualTo),
+    ("=", Comparison::Equal),
+    ("<", Comparison::LessThan),
+    (">", Comparison::GreaterThan),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    LessThan,
+    LessThanEqualTo,
+    GreaterThan,
+    GreaterThanEqualTo,
+}
+
+/// A condition over the metadata of a frame.
+///
+/// A predicate is written as `KEY<OP>VALUE` where `<OP>` is one of `=`, `!=`,
+/// `<`, `<=`, `>`, or `>=`. The `VALUE` is interpreted as JSON if possible
+/// (e.g., `true`, `10.5`); otherwise, it is interpreted as a string:
+///
+/// ```text
+/// time=night
+/// rain=true
+/// temperature<10
+/// ```
+#[derive(Clone, Debug)]
+pub struct Predicate {
+    key: String,
+    comparison: Comparison,
+    value: Value,
+}
+
+impl Predicate {
+    /// Parse a [`Predicate`] from its textual representation.
+    pub fn parse(source: &str) -> Result<Self, Box<dyn Error>> {
+        let (at, symbol, comparison) = OPERATORS
+            .iter()
+            .filter_map(|(symbol, comparison)| {
+                source.find(symbol).map(|at| (at, *symbol, *comparison))
+            })
+            .min_by_key(|(at, ..)| *at)
+            .ok_or(PredicateError::from(format!(
+                "`{}`: expected a comparison (e.g., `KEY=VALUE`)",
+                source
+            )))?;
+
+        let key = source[..at].trim();
+        let value = source[(at + symbol.len())..].trim();
+
+        if key.is_empty() {
+            return Err(Box::new(PredicateError::from(format!(
+                "`{}`: missing metadata key",
+                source
+            ))));
+        }
+
+        Ok(Predicate {
+            key: key.to_string(),
+            comparison,
+            value: serde_json::from_str(value).unwrap_or(Value::String(value.to_string())),
+        })
+    }
+
+    /// Check if the metadata satisfies the [`Predicate`].
+    ///
+    /// If the metadata has no entry for the key, the predicate is not
+    /// satisfied. For an equality comparison against an array (e.g., a list of
+    /// scene tags), the predicate is satisfied if the array contains the value.
+    /// Ordering comparisons are only defined between two numbers or two
+    /// strings.
+    pub fn holds(&self, metadata: &IndexMap<String, Value>) -> bool {
+        let Some(entry) = metadata.get(&self.key) else {
+            return false;
+        };
+
+        let equal = match entry {
+            Value::Array(values) if !self.value.is_array() => values.contains(&self.value),
+            _ => *entry == self.value,
+        };
+
+        match self.comparison {
+            Comparison::Equal => equal,
+            Comparison::NotEqual => !equal,
+            comparison => {
+                let ordering = match (entry, &self.value) {
+                    (Value::Number(a), Value::Number(b)) => match (a.as_f64(), b.as_f64()) {
+                        (Some(a), Some(b)) => a.partial_cmp(&b),
+                        _ => None,
+                    },
+                    (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+                    _ => None,
+                };
+
+                match ordering {
+                    Some(ordering) => match comparison {
+                        Comparison::LessThan => ordering == Ordering::Less,
+                        Comparison::LessThanEqualTo => ordering != Ordering::Greater,
+                        Comparison::GreaterThan => ordering == Ordering::Greater,
+                        _ => ordering != Ordering::Less,
+                    },
+                    None => false,
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct PredicateError {
+    msg: String,
+}
+
+impl From<&str> for PredicateError {
+    fn from(msg: &str) -> Self {
+        PredicateError {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for PredicateError {
+    fn from(msg: String) -> Self {
+        PredicateError { msg }
+    }
+}
+
+impl fmt::Display for PredicateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "predicate: {}", self.msg)
+    }
+}
+
+impl Error for PredicateError {}
+
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexMap;
+    use serde_json::{json, Value};
+
+    use super::Predicate;
+
+    #[test]
+    fn holds() {
+        let metadata: IndexMap<String, Value> = serde_json::from_value(json!({
+            "time": "night",
+            "temperature": 8.5,
+            "tags": ["rain", "urban"]
+        }))
+        .unwrap();
+
+        let check = |source: &str| Predicate::parse(source).unwrap().holds(&metadata);
+
+        assert!(check("time=night"));
+        assert!(!check("time!=night"));
+        assert!(check("temperature<10"));
+        assert!(!check("temperature>=10"));
+        assert!(check("tags=rain"));
+        assert!(!check("weather=clear"));
+        assert!(Predicate::parse("night").is_err());
+    }
+}
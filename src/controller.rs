@@ -3,20 +3,122 @@
 //! This module is responsible for managing and controlling the behavior of the
 //! matching framework.
 
+use std::collections::HashSet;
 use std::error::Error;
-use std::io::Read;
+use std::fmt;
+use std::ops::{ControlFlow, Range};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
-use crate::compiler::Compiler;
+use indexmap::IndexMap;
+use serde_json::de::Read;
+
+use crate::compiler::ir::ast::SpatialFormula;
 use crate::config::Configuration;
+use crate::datastream::frame::sample::detections::label::Label;
 use crate::datastream::frame::Frame;
+use crate::datastream::gap::{Gaps, Policy};
 use crate::datastream::io::importer::Importer;
+use crate::datastream::transform::Pipeline;
+use crate::datastream::window::Window;
 use crate::datastream::DataStream;
 use crate::matcher;
+use crate::matcher::automata::dfa::forward::Step;
+use crate::matcher::automata::dfa::lookaround::Direction;
+use crate::matcher::bitmap::Bitmap;
 use crate::matcher::offline;
 use crate::matcher::online;
 use crate::matcher::Matching;
+use crate::monitor::explain;
+use crate::monitor::Monitor;
+use crate::symbolizer::ast::SymbolicAbstractSyntaxTree;
+
+use self::deadline::{Deadline, Overrun};
+use self::metrics::{Metrics, Snapshot};
+use self::watcher::Watcher;
+
+pub mod daemon;
+pub mod deadline;
+pub mod metrics;
+pub mod watcher;
+
+/// A callback to handle each match.
+///
+/// The search is stopped early if the callback returns [`ControlFlow::Break`]
+/// (e.g., once an event of interest is found). As a closure, the callback may
+/// keep its own state across matches (e.g., the matches collected so far).
+pub type PrintCallback<'a> = Box<
+    dyn FnMut(&MatchContext, &Configuration) -> Result<ControlFlow<()>, Box<dyn Error>> + Send + 'a,
+>;
+
+/// A callback to handle each diagnostic of a search.
+///
+/// Similar to the [`PrintCallback`], this leaves the reporting (e.g., printing)
+/// of the diagnostics to the caller.
+pub type ReportCallback<'a> =
+    Box<dyn FnMut(&Report, &Configuration) -> Result<(), Box<dyn Error>> + Send + 'a>;
+
+/// A diagnostic of a search, as requested by the [`Configuration`].
+///
+/// The positions of frames are relative to the frames searched (i.e., starting
+/// from zero), similar to the range of a [`Match`].
+///
+/// [`Match`]: crate::matcher::Match
+#[derive(Debug)]
+pub enum Report<'a> {
+    /// The spatial formulas satisfied (or not) by a frame.
+    ///
+    /// The formulas are numbered by their order in the pattern, starting from
+    /// zero.
+    Symbols {
+        position: usize,
+        frame: &'a Frame,
+        truths: Vec<usize>,
+        falsities: Vec<usize>,
+    },
+
+    /// The active states of the matcher after each frame consumed by a search
+    /// from the frame at `offset`.
+    States { offset: usize, steps: &'a [Step] },
+
+    /// The longest partial match of a search without a match.
+    ///
+    /// The search from `offset` consumed `consumed` frames before failing at
+    /// `frame`, where none of the `expected` formulas were satisfied. If the
+    /// frames ran out first, there is no such frame.
+    Explanation {
+        offset: usize,
+        consumed: usize,
+        frame: Option<&'a Frame>,
+        expected: Vec<Expectation>,
+    },
+
+    /// The longest partial matches of a search that do not overlap, longest
+    /// first.
+    ///
+    /// Each is the range of the frames consumed by a search that failed (or
+    /// ran out of frames), whereas a complete match spans at least `shortest`
+    /// frames.
+    Partial {
+        frames: &'a [Frame],
+        ranges: Vec<Range<usize>>,
+        shortest: usize,
+    },
+}
+
+/// A spatial formula expected (but not satisfied) by a frame.
+#[derive(Debug)]
+pub struct Expectation {
+    /// The position of the formula in the pattern, starting from zero.
+    pub position: usize,
+
+    /// The formula as written in the pattern.
+    pub formula: String,
 
-type PrintCallback = fn(&[Frame], &Configuration) -> Result<(), Box<dyn Error>>;
+    /// The reasons the formula is not satisfied (see [`explain::explain`]).
+    pub reasons: Vec<String>,
+}
 
 #[derive(Debug)]
 pub enum Status {
@@ -24,6 +126,53 @@ pub enum Status {
     MatchNotFound,
 }
 
+/// A match found by the [`Controller`], and where it was found.
+///
+/// This is passed to the callback of each match, such that it need not be
+/// reconstructed from the [`Configuration`].
+#[derive(Debug)]
+pub struct MatchContext<'a> {
+    /// The frames of the match.
+    pub frames: &'a [Frame],
+
+    /// The indices of the frames of the match (i.e., `[start, end)`).
+    pub range: Range<usize>,
+
+    /// The file the match was found in. If this is `None`, then the source is
+    /// standard input (or multiple files merged into one).
+    pub path: Option<&'a Path>,
+
+    /// The channel of the match, if each channel is matched independently.
+    pub channel: Option<&'a str>,
+
+    /// The identifier of the query of the match, if a set of queries is
+    /// searched.
+    pub query: Option<&'a str>,
+
+    /// The pattern that was matched.
+    pub pattern: &'a str,
+
+    /// The time of the match, from the timestamp of its first frame to that of
+    /// its last frame.
+    ///
+    /// This is only given if the match includes a frame inserted for a missing
+    /// frame without an index of its own (see [`Frame::inserted`]), where the
+    /// `range` does not tell it apart from the frames around it.
+    pub time: Option<Range<f64>>,
+
+    /// The frames of the match, restricted to the annotations that witness the
+    /// formulas of the pattern (see [`Monitor::witnesses`]).
+    ///
+    /// This is only computed if requested by the [`Configuration`].
+    pub witnesses: Option<Vec<Frame>>,
+
+    /// The robustness of the match (see [`Monitor::score`]).
+    ///
+    /// Similar to the witnesses, this is only computed if requested by the
+    /// [`Configuration`].
+    pub score: Option<f64>,
+}
+
 /// The main driver to perform matching.
 ///
 /// This includes processing datastreams, monitoring, and matching. The main
@@ -32,13 +181,62 @@ pub struct Controller<'a> {
     config: &'a Configuration<'a>,
 
     /// A callback to use (e.g., printing results).
-    callback: Option<PrintCallback>,
+    ///
+    /// This is behind a lock, such that the callback may be called while the
+    /// controller is shared.
+    callback: Mutex<Option<PrintCallback<'a>>>,
+
+    /// A callback to use for the diagnostics of a search, if any.
+    reporter: Mutex<Option<ReportCallback<'a>>>,
+
+    /// The rolling statistics of an online search, if requested.
+    ///
+    /// This is behind a lock, such that it may be read (e.g., by another
+    /// thread) while the search runs.
+    metrics: Mutex<Option<Metrics>>,
+
+    /// The pattern reloaded while searching online, if any.
+    ///
+    /// This replaces the pattern of the [`Configuration`] for all matches
+    /// found after the reload.
+    reloaded: Mutex<Option<String>>,
+
+    /// The identifier and pattern of the query searched, if a set of queries
+    /// is searched.
+    ///
+    /// Similar to a reloaded pattern, this replaces the pattern of the
+    /// [`Configuration`] for the matches of the query.
+    query: Mutex<Option<(String, String)>>,
 }
 
 impl<'a> Controller<'a> {
     /// Create new [`Controller`] with associated [`Configuration`].
-    pub fn new(config: &'a Configuration, callback: Option<PrintCallback>) -> Self {
-        Self { config, callback }
+    pub fn new(config: &'a Configuration, callback: Option<PrintCallback<'a>>) -> Self {
+        Self {
+            config,
+            callback: Mutex::new(callback),
+            reporter: Mutex::new(None),
+            metrics: Mutex::new(config.stats.map(Metrics::new)),
+            reloaded: Mutex::new(None),
+            query: Mutex::new(None),
+        }
+    }
+
+    /// Set the callback of the diagnostics of a search (see [`Report`]).
+    ///
+    /// Without it, the diagnostics requested by the [`Configuration`] (e.g.,
+    /// a trace) are not computed.
+    pub fn reporter(self, reporter: ReportCallback<'a>) -> Self {
+        *self.reporter.lock().unwrap() = Some(reporter);
+        self
+    }
+
+    /// Take a [`Snapshot`] of the rolling statistics of the online search.
+    ///
+    /// This returns `None` if statistics were not requested by the
+    /// [`Configuration`].
+    pub fn metrics(&self) -> Option<Snapshot> {
+        self.metrics.lock().unwrap().as_ref().map(Metrics::snapshot)
     }
 
     /// Entrypoint to execute the [`Controller`].
@@ -46,34 +244,52 @@ impl<'a> Controller<'a> {
     /// The [`DataStream`] only needs to be supplied. This allows the same
     /// [`Controller`] to be reused for differing streams without creating a new
     /// one for each run, accordingly.
-    pub fn run<R: Read>(&self, datastream: DataStream<R>) -> Result<Status, Box<dyn Error>> {
+    pub fn run<'d, R: Read<'d>>(
+        &self,
+        datastream: DataStream<'d, R>,
+    ) -> Result<Status, Box<dyn Error>> {
+        if self.config.queries.is_some() && (self.config.online || self.config.chunk.is_some()) {
+            return Err(Box::new(ControllerError::from(
+                "a set of queries is only searched offline",
+            )));
+        }
+
         if self.config.online {
             return self.online(datastream);
         }
 
+        if let Some(size) = self.config.chunk {
+            return self.chunked(datastream, size);
+        }
+
         self.offline(datastream)
     }
 
     /// Run the offline matching algorithm.
-    pub fn offline<R: Read>(
+    pub fn offline<'d, R: Read<'d>>(
         &self,
-        mut datastream: DataStream<R>,
+        mut datastream: DataStream<'d, R>,
     ) -> Result<Status, Box<dyn Error>> {
-        // Set the initial status to no matches found.
-        //
-        // This is changed upon the condition that any match is found; else, no
-        // match found is used.
-        let mut status = Status::MatchNotFound;
-
         // Compile the SpRE into an S-AST ("Symbolic AST").
         //
         // This also produces the symbolic mapping between uniques characters and
         // spatial formulas.
-        let compiler = Compiler::new();
-        let ast = compiler.compile(self.config.pattern)?;
+        let compiler = self.config.compiler();
 
-        // Build [`offline::Matcher`].
-        let matcher = offline::Matcher::from(&ast);
+        // Compile each query, if a set of queries is searched.
+        //
+        // All queries are compiled before the [`DataStream`] is imported, such
+        // that an invalid query is reported without importing it.
+        let queries = match &self.config.queries {
+            Some(queries) => queries
+                .iter()
+                .map(|(id, pattern)| match compiler.compile(pattern) {
+                    Ok(ast) => Ok((Some((id.clone(), pattern.clone())), ast)),
+                    Err(e) => Err(ControllerError::from(format!("query `{}`: {}", id, e))),
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            None => vec![(None, compiler.compile(self.config.pattern)?)],
+        };
 
         // Load all [`Frame`](s) into the [`DataStream`].
         //
@@ -82,13 +298,76 @@ impl<'a> Controller<'a> {
         // online where it is possible that only some frames are loaded and is
         // done so incrementally.
         let mut importer = Importer::new(self.config);
+        let mut gaps = Gaps::new(self.config.gaps);
+        let mut pipeline = self.pipeline(queries.iter().map(|(_, ast)| ast));
+
+        // The positions of the [`Frame`] that begin a new segment.
+        //
+        // If the data stream is broken at its gaps, each segment is searched
+        // separately, such that no match spans across a gap.
+        let mut breaks = vec![0];
 
         while let Some(frames) = datastream.request(&mut importer)? {
-            for frame in frames {
-                datastream.append(frame);
+            for (i, segment) in gaps.fill(frames).into_iter().enumerate() {
+                if i > 0 {
+                    breaks.push(datastream.frames.len());
+                }
+
+                for frame in pipeline.apply(segment) {
+                    datastream.append(frame);
+                }
             }
         }
 
+        Self::report(&importer);
+        self.gaps(&gaps);
+
+        for (_, ast) in queries.iter() {
+            self.absent(ast, &importer, &pipeline);
+        }
+
+        breaks.push(datastream.frames.len());
+        let segments = breaks
+            .windows(2)
+            .map(|w| w[0]..w[1])
+            .filter(|r| !r.is_empty())
+            .collect::<Vec<Range<usize>>>();
+
+        // Search the imported frames with each query.
+        //
+        // The frames are shared by all queries; therefore, the data stream is
+        // imported once regardless of the number of queries.
+        let mut status = Status::MatchNotFound;
+
+        for (query, ast) in queries {
+            *self.query.lock().unwrap() = query;
+
+            if matches!(
+                self.find(&ast, &datastream.frames, &segments)?,
+                Status::MatchFound
+            ) {
+                status = Status::MatchFound;
+
+                // Stop at the first match, if nothing is printed.
+                if self.config.quiet {
+                    break;
+                }
+            }
+        }
+
+        Ok(status)
+    }
+
+    /// Search the frames (imported in full) with a single pattern.
+    fn find(
+        &self,
+        ast: &SymbolicAbstractSyntaxTree,
+        frames: &[Frame],
+        segments: &[Range<usize>],
+    ) -> Result<Status, Box<dyn Error>> {
+        // Build [`offline::Matcher`].
+        let matcher = offline::Matcher::try_from(ast)?.fuzzy(self.config.fuzzy);
+
         // A counter for the number of [`Match`].
         //
         // Ideally, this variable should be stored at a higher level as it is
@@ -96,49 +375,209 @@ impl<'a> Controller<'a> {
         // in the [`Configuration`] struct, it is declared here.
         let mut count = 0;
 
-        let mut offset = 0;
-        while offset < datastream.frames.len() {
-            if let Some(m) = matcher.leftmost(&datastream.frames[offset..])? {
-                // Set status to [`Status::MatchFound`].
-                //
-                // A match has been found, so the status can be set. This is only
-                // set a single time.
-                if matches!(status, Status::MatchNotFound) {
-                    status = Status::MatchFound;
+        // Search each channel independently.
+        //
+        // Each channel is projected into its own sub-stream of [`Frame`], and
+        // the matches of each are tagged by their channel.
+        if self.config.split {
+            for channel in Self::channels(frames) {
+                let frames = frames
+                    .iter()
+                    .map(|f| f.channel(&channel))
+                    .collect::<Vec<Frame>>();
+
+                let bitmap = matcher.evaluate(&frames)?;
+
+                if self
+                    .search(
+                        &matcher,
+                        &bitmap,
+                        &frames,
+                        segments,
+                        Some(&channel),
+                        &mut count,
+                    )?
+                    .is_break()
+                {
+                    break;
                 }
+            }
+
+            return Ok(Self::status(count));
+        }
+
+        // Evaluate each symbol against each [`Frame`] once.
+        //
+        // The search below is retried from many offsets, so the truth values
+        // are precomputed rather than invoking the spatial monitors again for
+        // each retry.
+        let bitmap = matcher.evaluate(frames)?;
+
+        if self.config.trace {
+            self.trace(&bitmap, frames)?;
+        }
+
+        // Set the status by the number of matches found.
+        //
+        // Whether the search was stopped early is irrelevant, as only reports
+        // on the search follow it.
+        let _ = self.search(&matcher, &bitmap, frames, segments, None, &mut count)?;
+        let status = Self::status(count);
+
+        if self.config.explain && matches!(status, Status::MatchNotFound) {
+            self.explain(&matcher, &bitmap, frames)?;
+        }
+
+        if let Some(n) = self.config.partial {
+            self.partial(&matcher, &bitmap, frames, ast, n)?;
+        }
+
+        Ok(status)
+    }
+
+    /// Run the offline matching algorithm over chunks of the data stream.
+    ///
+    /// Rather than loading all frames into memory, frames are buffered until a
+    /// chunk of `size` frames (and the horizon of the pattern and the reach of
+    /// its lookaheads after it) is loaded. The searches from each frame of the
+    /// chunk are then run, and the frames before the next search are discarded
+    /// (except those a lookbehind may look at). As a match never spans more
+    /// frames than the horizon, the matches are the same as those of
+    /// [`Self::offline`].
+    pub fn chunked<'d, R: Read<'d>>(
+        &self,
+        mut datastream: DataStream<'d, R>,
+        size: usize,
+    ) -> Result<Status, Box<dyn Error>> {
+        let mut status = Status::MatchNotFound;
+
+        if size == 0 {
+            return Err(Box::new(ControllerError::from(
+                "the size of a chunk must be positive",
+            )));
+        }
+
+        let compiler = self.config.compiler();
+        let ast = compiler.compile(self.config.pattern)?;
+
+        // Compute the horizon.
+        //
+        // The horizon determines the overlap between chunks. If the pattern has
+        // no horizon (e.g., with `*` or `{n,}`), a window must bound it instead.
+        let horizon = match (matcher::horizon(&ast), self.config.window) {
+            (Some(horizon), Some(window)) => std::cmp::min(horizon, window),
+            (horizon, window) => horizon.or(window).ok_or(ControllerError::from(
+                "a chunked search requires a pattern with a bounded horizon (or `--window`)",
+            ))?,
+        };
 
-                // Increment `count` and check for limit.
+        // A lookahead may look at the frames after a match; therefore, these
+        // are loaded before a chunk is searched as well.
+        let horizon = horizon
+            + matcher::reach(&ast, Direction::Ahead).ok_or(ControllerError::from(
+                "a chunked search requires a pattern with a bounded lookahead",
+            ))?;
+
+        // Similarly, a lookbehind may look at the frames before a match;
+        // therefore, these are kept after a chunk is searched.
+        let behind = matcher::reach(&ast, Direction::Behind).unwrap_or(0);
+
+        // The end of a chunk is not the end of the data stream; therefore, a
+        // temporal operator may not hold vacuously at it.
+        if matcher::vacuous(&ast) {
+            return Err(Box::new(ControllerError::from(
+                "a chunked search requires a pattern without temporal operators that hold past the end of the data stream",
+            )));
+        }
+
+        let matcher = offline::Matcher::try_from(&ast)?.fuzzy(self.config.fuzzy);
+
+        let mut importer = Importer::new(self.config);
+        let mut gaps = Gaps::new(self.config.gaps);
+        let mut pipeline = self.pipeline([&ast]);
+
+        let mut count = 0;
+        let mut exceeded = false;
+
+        // The position of the next search in the frames kept.
+        let mut start = 0;
+
+        'stream: while let Some(frames) = datastream.request(&mut importer)? {
+            for (i, segment) in gaps.fill(frames).into_iter().enumerate() {
+                // Search the remainder of the segment before a gap.
                 //
-                // This is done before display the [`Match`] as a `limit` of 0
-                // may be requested.
-                count += 1;
+                // If the data stream is broken at its gaps, no match spans across
+                // a gap; therefore, the frames before it are discarded.
+                if i > 0 {
+                    let end = datastream.frames.len();
 
-                if let Some(limit) = self.config.limit {
-                    if count > limit {
-                        break;
+                    if self
+                        .chunk(
+                            &matcher,
+                            &datastream.frames,
+                            start..end,
+                            &mut status,
+                            &mut count,
+                        )?
+                        .is_none()
+                    {
+                        exceeded = true;
+                        break 'stream;
                     }
-                }
 
-                // Handle [`Match`].
-                if let Some(callback) = self.callback {
-                    callback(
-                        &datastream.frames[(offset + m.start)..(offset + m.end)],
-                        self.config,
-                    )?;
+                    datastream.frames.clear();
+                    start = 0;
                 }
 
-                offset += m.end;
-                continue;
+                for frame in pipeline.apply(segment) {
+                    datastream.append(frame);
+
+                    if datastream.frames.len() >= start + size + horizon {
+                        match self.chunk(
+                            &matcher,
+                            &datastream.frames,
+                            start..(start + size),
+                            &mut status,
+                            &mut count,
+                        )? {
+                            Some(resume) => {
+                                start = std::cmp::min(resume, behind);
+                                datastream.frames.drain(..(resume - start));
+                            }
+                            None => {
+                                exceeded = true;
+                                break 'stream;
+                            }
+                        }
+                    }
+                }
             }
+        }
 
-            offset += 1;
+        // Search the remainder of the data stream.
+        if !exceeded {
+            let end = datastream.frames.len();
+            self.chunk(
+                &matcher,
+                &datastream.frames,
+                start..end,
+                &mut status,
+                &mut count,
+            )?;
         }
 
+        Self::report(&importer);
+        self.absent(&ast, &importer, &pipeline);
+        self.gaps(&gaps);
+
         Ok(status)
     }
 
     /// Run the online matching algorithm.
-    pub fn online<R: Read>(&self, mut datastream: DataStream<R>) -> Result<Status, Box<dyn Error>> {
+    pub fn online<'d, R: Read<'d>>(
+        &self,
+        mut datastream: DataStream<'d, R>,
+    ) -> Result<Status, Box<dyn Error>> {
         // Set the initial status to no matches found.
         //
         // This is changed upon the condition that any match is found; else, no
@@ -149,19 +588,8 @@ impl<'a> Controller<'a> {
         //
         // This also produces the symbolic mapping between uniques characters and
         // spatial formulas.
-        let compiler = Compiler::new();
-        let ast = compiler.compile(self.config.pattern)?;
-
-        // Compute the horizon.
-        //
-        // The horizon places a limit on the number of [`Frame`] that are loaded
-        // into the [`DataStream`].
-        if let Some(size) = matcher::horizon(&ast) {
-            datastream.capacity(size);
-        }
-
-        // Build [`online::Matcher`].
-        let matcher = online::Matcher::from(&ast);
+        let compiler = self.config.compiler();
+        let mut ast = compiler.compile(self.config.pattern)?;
 
         // A counter for the number of [`Match`].
         //
@@ -178,53 +606,1159 @@ impl<'a> Controller<'a> {
         // algorithm.
         let mut importer = Importer::new(self.config);
 
-        while let Some(frames) = datastream.request(&mut importer)? {
-            for frame in frames {
-                if let Some(capacity) = datastream.capacity {
-                    if datastream.frames.len() >= capacity {
-                        // Remove the least recent [`Frame`] from the [`DataStream`].
-                        //
-                        // This procedure can be thought of as a LRU cache.
-                        //
-                        // OPTIMIZATION: The use of `remove` shifts all elements to
-                        // the right one index to the left. Therefore, it may be
-                        // worthwhile to find a better operation to remove the LRU
-                        // element (e.g., use a reversed vector with `pop`).
-                        datastream.frames.remove(0);
+        // The sub-stream of each channel, if searched independently.
+        //
+        // A channel is added once it is first seen; therefore, the frames
+        // before then are not part of its sub-stream.
+        let mut windows: IndexMap<String, Window> = IndexMap::new();
+
+        // The most recent [`Frame`] of the [`DataStream`] searched.
+        //
+        // This is bounded by the capacity, such that the least recent frame is
+        // evicted as each new frame arrives.
+        let mut window = Window::new(None);
+
+        let mut gaps = Gaps::new(self.config.gaps);
+        let mut pipeline = self.pipeline([&ast]);
+
+        let mut deadline = self
+            .config
+            .deadline
+            .map(|budget| Deadline::new(budget, self.config.overrun));
+
+        // The file the pattern is reloaded from, if any.
+        let mut watcher = self
+            .config
+            .reload
+            .map(|path| Watcher::new(path, self.config.pattern));
+
+        // Search with the current pattern until it is reloaded.
+        //
+        // The [`DataStream`] (and the frames kept of it) outlives each pattern,
+        // such that a reload neither drops the input nor the frames searched.
+        loop {
+            // Compute the horizon.
+            //
+            // The horizon places a limit on the number of [`Frame`] that are loaded
+            // into the [`DataStream`]. If a window is requested, it truncates the
+            // horizon, which is otherwise unbounded for patterns with `*` or `{n,}`.
+            let capacity = match (matcher::horizon(&ast), self.config.window) {
+                (Some(horizon), Some(window)) => Some(std::cmp::min(horizon, window)),
+                (horizon, window) => horizon.or(window),
+            };
+
+            // Drop the frames beyond the capacity.
+            //
+            // The capacity changes if the pattern is reloaded; the most recent
+            // frames are kept, such that the search continues where it left off.
+            window.resize(capacity);
+            windows.values_mut().for_each(|w| w.resize(capacity));
+
+            if let Some(window) = self.config.window {
+                let shortest = matcher::shortest(&ast);
+
+                if window < shortest {
+                    eprintln!(
+                        "controller: warning: window of {} frame(s) is shorter than the shortest match of {} frame(s)",
+                        window, shortest
+                    );
+                } else if let Some((prefix, suffix)) = matcher::partial(&ast) {
+                    if window < prefix + suffix {
+                        eprintln!(
+                            "controller: warning: window of {} frame(s) is shorter than the bounded prefix and suffix of {} frame(s)",
+                            window,
+                            prefix + suffix
+                        );
                     }
                 }
+            }
 
-                datastream.append(frame);
+            // Build [`online::Matcher`].
+            let matcher = online::Matcher::try_from(&ast)?.fuzzy(self.config.fuzzy);
+            let formulas = matcher.formulas();
 
-                if let Some(m) = matcher.leftmost(&datastream.frames[..])? {
-                    // Set status to [`Status::MatchFound`].
-                    //
-                    // A match has been found, so the status can be set. This is only
-                    // set a single time.
-                    if matches!(status, Status::MatchNotFound) {
-                        status = Status::MatchFound;
+            // The reloaded pattern, if any.
+            let mut reloaded = None;
+
+            'stream: loop {
+                // Reload the pattern if its file has changed.
+                //
+                // This is only checked between requests, such that the frames of
+                // a request are all searched with the same pattern. If the new
+                // pattern is invalid, the current one is kept.
+                if let Some(pattern) = watcher.as_mut().and_then(Watcher::poll) {
+                    match compiler.compile(&pattern) {
+                        Ok(next) => {
+                            eprintln!("controller: reloaded pattern `{}`", pattern);
+                            reloaded = Some((pattern, next));
+                            break 'stream;
+                        }
+                        Err(e) => eprintln!(
+                            "controller: warning: failed to reload pattern ({}), keeping the previous one",
+                            e
+                        ),
                     }
+                }
 
-                    // Increment `count` and check for limit.
+                let Some(frames) = datastream.request(&mut importer)? else {
+                    break 'stream;
+                };
+
+                for (i, segment) in gaps.fill(frames).into_iter().enumerate() {
+                    // Restart the search after a gap.
                     //
-                    // This is done before display the [`Match`] as a `limit` of 0
-                    // may be requested.
-                    count += 1;
+                    // The frames before the gap are discarded, such that no match
+                    // spans across it.
+                    if i > 0 {
+                        window.clear();
+                        windows.values_mut().for_each(Window::clear);
+                    }
 
-                    if let Some(limit) = self.config.limit {
-                        if count > limit {
-                            break;
+                    for frame in pipeline.apply(segment) {
+                        // Drop the frame if the search is behind the input.
+                        //
+                        // A dropped frame is never searched, such that a match may
+                        // span across it (i.e., as an ignored gap).
+                        if deadline.as_mut().is_some_and(Deadline::skip) {
+                            continue;
                         }
-                    }
 
-                    // Handle [`Match`].
-                    if let Some(callback) = self.callback {
-                        callback(&datastream.frames[m.start..m.end], self.config)?;
+                        let start = Instant::now();
+                        let before = count;
+                        let index = frame.index;
+
+                        let flow = 'frame: {
+                            if self.config.split {
+                                for channel in Self::channels(std::slice::from_ref(&frame)) {
+                                    windows
+                                        .entry(channel)
+                                        .or_insert_with(|| Window::new(window.capacity()));
+                                }
+
+                                for (channel, window) in windows.iter_mut() {
+                                    window.push(frame.channel(channel));
+
+                                    if let Some(m) = matcher.leftmost(window.frames())? {
+                                        status = Status::MatchFound;
+
+                                        if self
+                                            .handle(
+                                                &window.frames()[m.start..m.end],
+                                                Some(channel),
+                                                &formulas,
+                                                &mut count,
+                                            )?
+                                            .is_break()
+                                        {
+                                            break 'frame ControlFlow::Break(());
+                                        }
+                                    }
+                                }
+
+                                break 'frame ControlFlow::Continue(());
+                            }
+
+                            // Append the [`Frame`] to the window.
+                            //
+                            // If full, the least recent [`Frame`] is evicted. This
+                            // procedure can be thought of as a LRU cache.
+                            window.push(frame);
+
+                            if let Some(m) = matcher.leftmost(window.frames())? {
+                                // Set status to [`Status::MatchFound`].
+                                //
+                                // A match has been found, so the status can be set, even if
+                                // the limit of matches is exceeded.
+                                status = Status::MatchFound;
+
+                                if self
+                                    .handle(
+                                        &window.frames()[m.start..m.end],
+                                        None,
+                                        &formulas,
+                                        &mut count,
+                                    )?
+                                    .is_break()
+                                {
+                                    break 'frame ControlFlow::Break(());
+                                }
+                            }
+
+                            ControlFlow::Continue(())
+                        };
+
+                        // Record the frame.
+                        //
+                        // The match that exceeds the limit of matches is counted
+                        // but not handled; therefore, it is excluded.
+                        let latency = start.elapsed();
+                        let handled = |n: usize| self.config.limit.map_or(n, |limit| n.min(limit));
+                        self.observe(
+                            latency,
+                            handled(count) - handled(before),
+                            datastream.dropped(),
+                        );
+
+                        if let Some(deadline) = deadline.as_mut() {
+                            if deadline.measure(latency) && self.config.overrun == Overrun::Report {
+                                eprintln!(
+                                    "controller: warning: frame {} took {:.3}ms, missing the deadline of {:.3}ms",
+                                    index,
+                                    latency.as_secs_f64() * 1000.0,
+                                    deadline.budget().as_secs_f64() * 1000.0
+                                );
+                            }
+                        }
+
+                        if flow.is_break() {
+                            break 'stream;
+                        }
                     }
                 }
             }
+
+            match reloaded {
+                Some((pattern, next)) => {
+                    *self.reloaded.lock().unwrap() = Some(pattern);
+                    pipeline = pipeline.track(next.motion());
+                    ast = next;
+                }
+                None => break,
+            }
+        }
+
+        Self::report(&importer);
+        self.absent(&ast, &importer, &pipeline);
+        self.gaps(&gaps);
+
+        if let Some(deadline) = &deadline {
+            Self::deadline(deadline);
+        }
+
+        if datastream.dropped() > 0 {
+            eprintln!(
+                "controller: warning: dropped {} document(s) of the input while the buffer was full",
+                datastream.dropped()
+            );
+        }
+
+        // Report the frames since the last report, if any.
+        if let Some(metrics) = self.metrics.lock().unwrap().as_ref() {
+            if metrics.pending() {
+                eprintln!("stats: {}", metrics.snapshot());
+            }
         }
 
         Ok(status)
     }
+
+    /// Record a frame processed by the online search.
+    ///
+    /// The `latency` is the time taken to search the frame, `matches` is the
+    /// number of matches handled at it, and `dropped` is the number of
+    /// documents of the input dropped so far. If a report is due, the current
+    /// statistics are printed.
+    fn observe(&self, latency: Duration, matches: usize, dropped: usize) {
+        let mut metrics = self.metrics.lock().unwrap();
+
+        if let Some(metrics) = metrics.as_mut() {
+            metrics.overflow(dropped);
+            metrics.record(latency, matches);
+
+            if metrics.due() {
+                eprintln!("stats: {}", metrics.snapshot());
+            }
+        }
+    }
+
+    /// Search the [`Frame`] for all non-overlapping matches, offline.
+    ///
+    /// Each segment of the frames is searched separately, and each match is
+    /// handled in order, tagged by its `channel` if given. The `count` of
+    /// matches is shared between searches, such that the limit of matches
+    /// applies to all of them. This breaks if no further matches are needed.
+    fn search(
+        &self,
+        matcher: &offline::Matcher,
+        bitmap: &Bitmap,
+        frames: &[Frame],
+        segments: &[Range<usize>],
+        channel: Option<&str>,
+        count: &mut usize,
+    ) -> Result<ControlFlow<()>, Box<dyn Error>> {
+        let formulas = matcher.formulas();
+
+        for segment in segments.iter() {
+            let frames = &frames[segment.clone()];
+
+            // Restrict the [`Bitmap`] to the segment.
+            //
+            // The search from an offset only ends at the last row of the
+            // bitmap; therefore, the rows after the segment must be excluded.
+            let bitmap = match segments.len() {
+                1 => bitmap.clone(),
+                _ => bitmap.slice(segment.clone()),
+            };
+
+            let mut offset = 0;
+            while offset < frames.len() {
+                // Report the states of the search, if requested.
+                //
+                // The positions of the search are relative to the segment;
+                // therefore, these are shifted to those of all frames.
+                if self.config.trace {
+                    for mut steps in matcher.trace(&bitmap, offset)? {
+                        for step in steps.iter_mut() {
+                            step.frame += segment.start;
+                        }
+
+                        self.diagnose(&Report::States {
+                            offset: segment.start + offset,
+                            steps: &steps,
+                        })?;
+                    }
+                }
+
+                if let Some(m) = matcher.leftmost_at(&bitmap, offset)? {
+                    if self
+                        .handle(
+                            &frames[(offset + m.start)..(offset + m.end)],
+                            channel,
+                            &formulas,
+                            count,
+                        )?
+                        .is_break()
+                    {
+                        return Ok(ControlFlow::Break(()));
+                    }
+
+                    offset += m.end;
+                    continue;
+                }
+
+                offset += 1;
+            }
+        }
+
+        Ok(ControlFlow::Continue(()))
+    }
+
+    /// Search a chunk of [`Frame`] for non-overlapping matches, offline.
+    ///
+    /// Only the searches from the frames in `searches` are run, such that the
+    /// frames before it are only looked at (e.g., by a lookbehind). This
+    /// returns the position of the next search, or `None` if no further
+    /// matches are needed.
+    fn chunk(
+        &self,
+        matcher: &offline::Matcher,
+        frames: &[Frame],
+        searches: Range<usize>,
+        status: &mut Status,
+        count: &mut usize,
+    ) -> Result<Option<usize>, Box<dyn Error>> {
+        let bitmap = matcher.evaluate(frames)?;
+        let formulas = matcher.formulas();
+
+        let mut offset = searches.start;
+        while offset < searches.end {
+            if let Some(m) = matcher.leftmost_at(&bitmap, offset)? {
+                *status = Status::MatchFound;
+
+                if self
+                    .handle(
+                        &frames[(offset + m.start)..(offset + m.end)],
+                        None,
+                        &formulas,
+                        count,
+                    )?
+                    .is_break()
+                {
+                    return Ok(None);
+                }
+
+                offset += m.end;
+                continue;
+            }
+
+            offset += 1;
+        }
+
+        Ok(Some(offset))
+    }
+
+    /// Handle a match.
+    ///
+    /// This breaks if no further matches are needed (or the callback breaks),
+    /// such that the search (and the import of the data stream) can be stopped
+    /// early. If the limit of matches is already exceeded, the match is not
+    /// handled.
+    fn handle(
+        &self,
+        frames: &[Frame],
+        channel: Option<&str>,
+        formulas: &[&SpatialFormula],
+        count: &mut usize,
+    ) -> Result<ControlFlow<()>, Box<dyn Error>> {
+        // Increment `count` and check for limit.
+        //
+        // This is done before display the [`Match`] as a `limit` of 0
+        // may be requested.
+        *count += 1;
+
+        if let Some(limit) = self.config.limit {
+            if *count > limit {
+                return Ok(ControlFlow::Break(()));
+            }
+        }
+
+        // Handle [`Match`].
+        if let Some(callback) = self.callback.lock().unwrap().as_mut() {
+            let (range, time) = match (frames.first(), frames.last()) {
+                (Some(first), Some(last)) => (
+                    first.index..last.end(),
+                    match (first.timestamp, last.timestamp) {
+                        (Some(start), Some(end)) if frames.iter().any(|f| f.inserted) => {
+                            Some(start..end)
+                        }
+                        _ => None,
+                    },
+                ),
+                _ => return Err(Box::new(ControllerError::from("empty match"))),
+            };
+
+            let reloaded = self.reloaded.lock().unwrap();
+            let query = self.query.lock().unwrap();
+
+            let context = MatchContext {
+                frames,
+                range,
+                time,
+                path: self.config.datastream.map(|p| p.as_path()),
+                channel,
+                query: query.as_ref().map(|(id, _)| id.as_str()),
+                pattern: reloaded
+                    .as_deref()
+                    .or(query.as_ref().map(|(_, pattern)| pattern.as_str()))
+                    .unwrap_or(self.config.pattern),
+                witnesses: match self.config.witness || self.config.trajectory {
+                    true => {
+                        let monitor = Monitor::new();
+
+                        Some(
+                            frames
+                                .iter()
+                                .map(|f| monitor.witnesses(f, formulas))
+                                .collect::<Result<Vec<Frame>, _>>()?,
+                        )
+                    }
+                    false => None,
+                },
+                score: match self.config.robustness {
+                    true => Some(Monitor::new().score(frames, formulas)?),
+                    false => None,
+                },
+            };
+
+            if callback(&context, self.config)?.is_break() {
+                return Ok(ControlFlow::Break(()));
+            }
+        }
+
+        match self.satisfied(*count) {
+            true => Ok(ControlFlow::Break(())),
+            false => Ok(ControlFlow::Continue(())),
+        }
+    }
+
+    /// The [`Status`] of a search after `count` matches.
+    ///
+    /// A match is counted even if the limit of matches is exceeded, so the
+    /// status is found for any match.
+    fn status(count: usize) -> Status {
+        match count {
+            0 => Status::MatchNotFound,
+            _ => Status::MatchFound,
+        }
+    }
+
+    /// Check if no further matches are needed after `count` matches.
+    ///
+    /// This is the case once the limit of matches is reached or, if nothing is
+    /// printed, once any match is found (i.e., only its existence is needed).
+    fn satisfied(&self, count: usize) -> bool {
+        if let Some(limit) = self.config.limit {
+            if count >= limit {
+                return true;
+            }
+        }
+
+        self.config.quiet && count > 0
+    }
+
+    /// The channels of the samples of the [`Frame`], in order of appearance.
+    fn channels(frames: &[Frame]) -> Vec<String> {
+        let mut channels: Vec<String> = Vec::new();
+
+        for sample in frames.iter().flat_map(|f| f.samples.iter()) {
+            if !channels.iter().any(|c| c == sample.channel()) {
+                channels.push(sample.channel().to_string());
+            }
+        }
+
+        channels
+    }
+
+    /// Report the number of malformed records skipped by the [`Importer`].
+    fn report(importer: &Importer) {
+        if importer.skipped() > 0 {
+            eprintln!(
+                "importer: warning: skipped {} malformed record(s)",
+                importer.skipped()
+            );
+        }
+    }
+
+    /// Report the truth of each symbol for each [`Frame`].
+    fn trace(&self, bitmap: &Bitmap, frames: &[Frame]) -> Result<(), Box<dyn Error>> {
+        for (row, frame) in frames.iter().enumerate() {
+            let (truths, falsities): (Vec<usize>, Vec<usize>) =
+                (0..bitmap.columns()).partition(|column| bitmap.get(row, *column));
+
+            self.diagnose(&Report::Symbols {
+                position: row,
+                frame,
+                truths,
+                falsities,
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Pass a [`Report`] to the reporter, if any.
+    fn diagnose(&self, report: &Report) -> Result<(), Box<dyn Error>> {
+        match self.reporter.lock().unwrap().as_mut() {
+            Some(reporter) => reporter(report, self.config),
+            None => Ok(()),
+        }
+    }
+
+    /// Explain the longest partial match of an unsuccessful search.
+    ///
+    /// The search from each frame is measured by the number of frames it
+    /// consumed before failing. For the furthest search, the spatial formulas
+    /// expected at the failing frame are explained, accordingly.
+    fn explain(
+        &self,
+        matcher: &offline::Matcher,
+        bitmap: &Bitmap,
+        frames: &[Frame],
+    ) -> Result<(), Box<dyn Error>> {
+        let mut best = (0, 0, Vec::new());
+
+        for offset in 0..frames.len() {
+            let (consumed, expected) = matcher.progress(bitmap, offset)?;
+
+            if offset == 0 || consumed > best.1 {
+                best = (offset, consumed, expected);
+            }
+        }
+
+        let (offset, consumed, expected) = best;
+
+        // Explain the expected formulas at the failing frame.
+        //
+        // If the frames ran out before the pattern was complete, no frame
+        // failed, such that there is nothing to explain.
+        let frame = frames.get(offset + consumed);
+        let expected = match frame {
+            Some(frame) => expected
+                .into_iter()
+                .map(|i| {
+                    let formula = matcher.dfa.fmap[i].1;
+
+                    Ok(Expectation {
+                        position: i,
+                        formula: explain::describe(formula, formula.root()),
+                        reasons: explain::explain(frame, formula)?,
+                    })
+                })
+                .collect::<Result<Vec<Expectation>, Box<dyn Error>>>()?,
+            None => Vec::new(),
+        };
+
+        self.diagnose(&Report::Explanation {
+            offset,
+            consumed,
+            frame,
+            expected,
+        })
+    }
+
+    /// Report the longest partial matches of a search.
+    ///
+    /// A partial match is a search from a frame that failed (or ran out of
+    /// frames) after consuming at least one frame. The `n` longest partial
+    /// matches that do not overlap are reported (see [`Self::rank`]).
+    fn partial(
+        &self,
+        matcher: &offline::Matcher,
+        bitmap: &Bitmap,
+        frames: &[Frame],
+        ast: &SymbolicAbstractSyntaxTree,
+        n: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut candidates = Vec::new();
+
+        for offset in 0..frames.len() {
+            if matcher.leftmost_at(bitmap, offset)?.is_some() {
+                continue;
+            }
+
+            let (consumed, _) = matcher.progress(bitmap, offset)?;
+            if consumed > 0 {
+                candidates.push(offset..(offset + consumed));
+            }
+        }
+
+        self.diagnose(&Report::Partial {
+            frames,
+            ranges: Self::rank(candidates, n),
+            shortest: matcher::shortest(ast),
+        })
+    }
+
+    /// Select the `n` longest partial matches that do not overlap.
+    ///
+    /// The partial matches are ranked by length, longest first, where those of
+    /// the same length are ordered by their first frame.
+    fn rank(mut candidates: Vec<Range<usize>>, n: usize) -> Vec<Range<usize>> {
+        // The sort is stable, such that the order of the candidates (i.e., by
+        // their first frame) is kept for those of the same length.
+        candidates.sort_by_key(|range| std::cmp::Reverse(range.len()));
+
+        let mut selected: Vec<Range<usize>> = Vec::new();
+        for range in candidates {
+            if selected.len() >= n {
+                break;
+            }
+
+            // Skip partial matches that overlap a longer one.
+            //
+            // A search from within a partial match often fails at the same
+            // frame, so these are redundant.
+            if selected
+                .iter()
+                .any(|other| range.start < other.end && other.start < range.end)
+            {
+                continue;
+            }
+
+            selected.push(range);
+        }
+
+        selected
+    }
+
+    /// Report the gaps of the data stream, if they are ignored.
+    ///
+    /// A gap otherwise silently shortens the time a match spans.
+    fn gaps(&self, gaps: &Gaps) {
+        if self.config.gaps == Policy::Ignore && gaps.count() > 0 {
+            eprintln!(
+                "controller: warning: found {} gap(s) with {} missing frame(s) (use `--gaps` to handle)",
+                gaps.count(),
+                gaps.missing()
+            );
+        }
+    }
+
+    /// Report the frames that missed their deadline, if any.
+    fn deadline(deadline: &Deadline) {
+        if deadline.missed() > 0 {
+            eprintln!(
+                "controller: warning: {} frame(s) missed the deadline of {:.3}ms, and {} frame(s) were dropped",
+                deadline.missed(),
+                deadline.budget().as_secs_f64() * 1000.0,
+                deadline.dropped()
+            );
+        }
+    }
+
+    /// Build the [`Pipeline`] of transforms applied to each imported [`Frame`].
+    ///
+    /// The objects of each frame are tracked only if the motion of an object
+    /// is used by a pattern `asts`.
+    fn pipeline<'b>(
+        &self,
+        asts: impl IntoIterator<Item = &'b SymbolicAbstractSyntaxTree>,
+    ) -> Pipeline {
+        Pipeline::new(self.config.transforms.clone().unwrap_or_default())
+            .track(asts.into_iter().any(|ast| ast.motion()))
+    }
+
+    /// Report the classes of the pattern never seen by the [`Importer`].
+    ///
+    /// A pattern with a misspelled class (e.g., `[:pedestrain:]`) otherwise
+    /// silently produces no matches. If any transforms are applied, the
+    /// classes of the transformed frames are used instead. The warning names
+    /// the data stream, such that it can be told apart when searching many.
+    fn absent(&self, ast: &SymbolicAbstractSyntaxTree, importer: &Importer, pipeline: &Pipeline) {
+        let labels: &HashSet<Label> = match pipeline.is_empty() {
+            true => importer.labels(),
+            false => pipeline.labels(),
+        };
+
+        let absent = ast
+            .labels()
+            .into_iter()
+            .filter(|label| !labels.contains(label))
+            .map(|label| format!("`{}`", label.as_str()))
+            .chain(
+                ast.classes()
+                    .into_iter()
+                    .filter(|classes| !labels.iter().any(|label| classes.contains(label)))
+                    .map(|classes| format!("`{}`", classes.source)),
+            )
+            .collect::<Vec<String>>();
+
+        if !absent.is_empty() {
+            let prefix = match self.config.datastream {
+                Some(path) => format!("{}: ", path.display()),
+                None => String::new(),
+            };
+
+            eprintln!(
+                "controller: warning: {}class(es) not found in the datastream: {}",
+                prefix,
+                absent.join(", ")
+            );
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ControllerError {
+    msg: String,
+}
+
+impl From<&str> for ControllerError {
+    fn from(msg: &str) -> Self {
+        ControllerError {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for ControllerError {
+    fn from(msg: String) -> Self {
+        ControllerError { msg }
+    }
+}
+
+impl fmt::Display for ControllerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "controller: {}", self.msg)
+    }
+}
+
+impl Error for ControllerError {}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::ops::{ControlFlow, Range};
+
+    use crate::compiler::ir::ops::Vacuity;
+    use crate::config::Configuration;
+    use crate::datastream::gap::Policy;
+    use crate::datastream::DataStream;
+    use crate::support;
+
+    use super::{Controller, MatchContext, Report, Status};
+
+    /// Create a data stream with a single detection of each class, in order.
+    fn datastream(classes: &[&str]) -> String {
+        let frames = classes
+            .iter()
+            .enumerate()
+            .map(|(i, class)| {
+                format!(
+                    r#"{{"index":{},"samples":[{{"type":"@stremf/sample/detection","channel":"cam","annotations":[{{"class":"{}","score":1.0,"bbox":{{"type":"@stremf/bbox/aabb","region":{{"center":{{"x":0.0,"y":0.0}},"dimensions":{{"w":1.0,"h":1.0}}}}}}}}]}}]}}"#,
+                    i, class
+                )
+            })
+            .collect::<Vec<String>>();
+
+        format!(r#"{{"version":"2.0.0","frames":[{}]}}"#, frames.join(","))
+    }
+
+    /// Run the [`Controller`] over the data stream, returning the range of
+    /// each match.
+    fn ranges(config: &Configuration, source: &str) -> Vec<Range<usize>> {
+        let mut ranges = Vec::new();
+
+        let controller = Controller::new(
+            config,
+            Some(Box::new(|context: &MatchContext, _: &Configuration| {
+                ranges.push(context.range.clone());
+                Ok(ControlFlow::Continue(()))
+            })),
+        );
+        controller
+            .run(DataStream::from_slice(source.as_bytes()))
+            .unwrap();
+
+        drop(controller);
+        ranges
+    }
+
+    #[test]
+    fn callback_break() {
+        let frame = r#"{"index":0,"samples":[{"type":"@stremf/sample/detection","channel":"cam","annotations":[{"class":"car","score":1.0,"bbox":{"type":"@stremf/bbox/aabb","region":{"center":{"x":0.0,"y":0.0},"dimensions":{"w":1.0,"h":1.0}}}}]}]}"#;
+        let source = format!(
+            r#"{{"version":"0.2.0","frames":[{}]}}"#,
+            [frame; 3].join(",")
+        );
+
+        let pattern = String::from("[[:car:]]");
+        let mut config = Configuration::new(&pattern);
+
+        for online in [false, true] {
+            config.online = online;
+
+            let mut calls = 0;
+
+            // Stop the search after the first match.
+            let controller = Controller::new(
+                &config,
+                Some(Box::new(|context: &MatchContext, _: &Configuration| {
+                    assert_eq!(context.range, 0..1);
+                    assert_eq!(context.pattern, "[[:car:]]");
+
+                    calls += 1;
+                    Ok(ControlFlow::Break(()))
+                })),
+            );
+            let status = controller
+                .run(DataStream::from_slice(source.as_bytes()))
+                .unwrap();
+
+            drop(controller);
+
+            assert!(matches!(status, Status::MatchFound));
+            assert_eq!(calls, 1);
+        }
+    }
+
+    #[test]
+    fn split() {
+        // A frame with a single detection of a class by a channel.
+        let frame = |index: usize, channel: &str, class: &str| {
+            format!(
+                r#"{{"index":{},"samples":[{{"type":"@stremf/sample/detection","channel":"{}","annotations":[{{"class":"{}","score":1.0,"bbox":{{"type":"@stremf/bbox/aabb","region":{{"center":{{"x":0.0,"y":0.0}},"dimensions":{{"w":1.0,"h":1.0}}}}}}}}]}}]}}"#,
+                index, channel, class
+            )
+        };
+
+        // The car and truck of the first two frames are seen by different
+        // channels, whereas those of the last two are seen by the front.
+        let source = format!(
+            r#"{{"version":"2.0.0","frames":[{}]}}"#,
+            [
+                frame(0, "front", "car"),
+                frame(1, "rear", "truck"),
+                frame(2, "front", "car"),
+                frame(3, "front", "truck"),
+            ]
+            .join(",")
+        );
+
+        let pattern = String::from("[[:car:]][[:truck:]]");
+        let mut config = Configuration::new(&pattern);
+
+        let search = |config: &Configuration| {
+            let mut matches = Vec::new();
+
+            let controller = Controller::new(
+                config,
+                Some(Box::new(|context: &MatchContext, _: &Configuration| {
+                    matches.push((context.range.clone(), context.channel.map(String::from)));
+                    Ok(ControlFlow::Continue(()))
+                })),
+            );
+            controller
+                .run(DataStream::from_slice(source.as_bytes()))
+                .unwrap();
+
+            drop(controller);
+            matches
+        };
+
+        // The mixed stream matches across the channels.
+        assert_eq!(search(&config), [(0..2, None), (2..4, None)]);
+
+        // Each channel alone only matches the frames it saw both.
+        config.split = true;
+        assert_eq!(search(&config), [(2..4, Some(String::from("front")))]);
+    }
+
+    #[test]
+    fn inserted() {
+        let source = r#"{"version":"2.0.0","frames":[{"index":0,"timestamp":0.0,"samples":[]},{"index":1,"timestamp":0.1,"samples":[]},{"index":2,"timestamp":0.4,"samples":[]}]}"#;
+
+        let pattern = String::from("[empty]");
+        let mut config = Configuration::new(&pattern);
+        config.gaps = Policy::Empty;
+
+        let mut matches = Vec::new();
+
+        let controller = Controller::new(
+            &config,
+            Some(Box::new(|context: &MatchContext, _: &Configuration| {
+                matches.push((context.range.clone(), context.time.clone()));
+                Ok(ControlFlow::Continue(()))
+            })),
+        );
+        controller
+            .run(DataStream::from_slice(source.as_bytes()))
+            .unwrap();
+
+        drop(controller);
+
+        // The frames inserted after the second frame share its index;
+        // therefore, these are told apart by their time.
+        let times = matches
+            .iter()
+            .map(|(range, time)| {
+                (
+                    range.clone(),
+                    time.as_ref().map(|t| (t.start * 10.0).round()),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            times,
+            [
+                (0..1, None),
+                (1..2, None),
+                (1..2, Some(2.0)),
+                (1..2, Some(3.0)),
+                (2..3, None)
+            ]
+        );
+    }
+
+    #[test]
+    fn explain() {
+        let pattern = String::from("[[:car:]][[:truck:]]");
+        let mut config = Configuration::new(&pattern);
+        config.explain = true;
+
+        let source = datastream(&["bus", "car", "car"]);
+        let mut explained = None;
+
+        let controller = Controller::new(&config, None).reporter(Box::new(
+            |report: &Report, _: &Configuration| {
+                if let Report::Explanation {
+                    offset,
+                    consumed,
+                    frame,
+                    expected,
+                } = report
+                {
+                    let expected = expected.iter().map(|e| e.position).collect::<Vec<_>>();
+                    explained = Some((*offset, *consumed, frame.map(|f| f.index), expected));
+                }
+
+                Ok(())
+            },
+        ));
+
+        let status = controller
+            .run(DataStream::from_slice(source.as_bytes()))
+            .unwrap();
+        assert!(matches!(status, Status::MatchNotFound));
+
+        // The search from the first car fails at the second car, which is not
+        // the expected truck.
+        drop(controller);
+        assert_eq!(explained, Some((1, 1, Some(2), vec![1])));
+    }
+
+    #[test]
+    fn partial() {
+        let pattern = String::from("[[:a:]][[:a:]][[:a:]][[:b:]]");
+        let mut config = Configuration::new(&pattern);
+
+        let source = datastream(&["a", "a", "a", "c", "a", "a", "c", "a", "c"]);
+
+        let mut partial = |n: usize| {
+            let mut ranges = Vec::new();
+            config.partial = Some(n);
+
+            let controller = Controller::new(&config, None).reporter(Box::new(
+                |report: &Report, _: &Configuration| {
+                    if let Report::Partial { ranges: r, .. } = report {
+                        ranges = r.clone();
+                    }
+
+                    Ok(())
+                },
+            ));
+
+            controller
+                .run(DataStream::from_slice(source.as_bytes()))
+                .unwrap();
+
+            drop(controller);
+            ranges
+        };
+
+        // The searches from the second and third frames fail within the
+        // longest partial match; therefore, these are skipped.
+        assert_eq!(partial(3), [0..3, 4..6, 7..8]);
+        assert_eq!(partial(2), [0..3, 4..6]);
+        assert!(partial(0).is_empty());
+    }
+
+    #[test]
+    fn queries() {
+        let frame = r#"{"index":0,"samples":[{"type":"@stremf/sample/detection","channel":"cam","annotations":[{"class":"car","score":1.0,"bbox":{"type":"@stremf/bbox/aabb","region":{"center":{"x":0.0,"y":0.0},"dimensions":{"w":1.0,"h":1.0}}}}]}]}"#;
+        let source = format!(
+            r#"{{"version":"0.2.0","frames":[{},{}]}}"#,
+            frame,
+            frame.replace(r#""index":0"#, r#""index":1"#)
+        );
+
+        let pattern = String::new();
+        let mut config = Configuration::new(&pattern);
+        config.queries = Some(vec![
+            (String::from("one"), String::from("[[:car:]]")),
+            (String::from("two"), String::from("[[:car:]]{2}")),
+        ]);
+
+        let mut matches = Vec::new();
+
+        // Record each match with its query.
+        let controller = Controller::new(
+            &config,
+            Some(Box::new(|context: &MatchContext, _: &Configuration| {
+                matches.push((context.query.unwrap().to_string(), context.range.clone()));
+                Ok(ControlFlow::Continue(()))
+            })),
+        );
+        let status = controller
+            .run(DataStream::from_slice(source.as_bytes()))
+            .unwrap();
+
+        drop(controller);
+
+        assert!(matches!(status, Status::MatchFound));
+        assert_eq!(
+            matches,
+            [
+                (String::from("one"), 0..1),
+                (String::from("one"), 1..2),
+                (String::from("two"), 0..2),
+            ]
+        );
+    }
+
+    #[test]
+    fn chunked() {
+        let classes = (0..40)
+            .map(|i| match (i * 7) % 5 {
+                0 | 3 => "car",
+                1 => "pedestrian",
+                _ => "truck",
+            })
+            .collect::<Vec<&str>>();
+        let source = self::datastream(&classes);
+
+        for pattern in [
+            "[[:car:]]",
+            "[[:car:]]{2}",
+            "[[:car:]][[:truck:]]{1,3}[[:pedestrian:]]",
+            "[[:car:]] ~{<=4} [[:pedestrian:]]",
+            "[[:car:]](?![[:truck:]][[:truck:]]|[[:pedestrian:]])",
+            "(?<=[[:pedestrian:]][[:truck:]]{0,2})[[:car:]]",
+        ] {
+            let pattern = String::from(pattern);
+            let mut config = Configuration::new(&pattern);
+
+            let expected = self::ranges(&config, &source);
+            assert!(!expected.is_empty(), "{}", pattern);
+
+            // The matches are the same for any size of chunk.
+            for size in 1..=7 {
+                config.chunk = Some(size);
+                assert_eq!(
+                    self::ranges(&config, &source),
+                    expected,
+                    "{} (chunk of {})",
+                    pattern,
+                    size
+                );
+            }
+        }
+
+        // The end of a chunk is not the end of the data stream.
+        let pattern = String::from("G[0,2] [[:car:]]");
+        let mut config = Configuration::new(&pattern);
+        config.end = Vacuity::Classical;
+        config.chunk = Some(2);
+
+        let controller = Controller::new(&config, None);
+        assert!(controller
+            .run(DataStream::from_slice(source.as_bytes()))
+            .is_err());
+    }
+
+    #[test]
+    fn reload() {
+        let path = std::env::temp_dir().join(format!("strem-reload-{}.spre", std::process::id()));
+        fs::write(&path, "[[:car:]]").unwrap();
+
+        let pattern = fs::read_to_string(&path).unwrap();
+
+        // Change the pattern after it is loaded, but before it is searched.
+        //
+        // The file is checked once before the first request; therefore, the
+        // change is found without waiting for an interval (see [`Watcher`]).
+        fs::write(&path, "[[:pedestrian:]]").unwrap();
+
+        let datastream = DataStream::from_frames(
+            (0..2).map(|i| support::frame(i, &[("car", None, 0.0), ("pedestrian", None, 0.0)])),
+        );
+
+        let mut config = Configuration::new(&pattern);
+        config.online = true;
+        config.reload = Some(&path);
+
+        let mut matches = Vec::new();
+
+        let controller = Controller::new(
+            &config,
+            Some(Box::new(|context: &MatchContext, _: &Configuration| {
+                matches.push((context.pattern.to_string(), context.range.clone()));
+                Ok(ControlFlow::Continue(()))
+            })),
+        );
+        controller.run(datastream).unwrap();
+
+        drop(controller);
+        fs::remove_file(&path).unwrap();
+
+        // The new pattern is searched, rather than the one loaded.
+        assert_eq!(
+            matches,
+            [
+                (String::from("[[:pedestrian:]]"), 0..1),
+                (String::from("[[:pedestrian:]]"), 1..2),
+            ]
+        );
+    }
 }
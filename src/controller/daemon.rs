@@ -0,0 +1,238 @@
+//! Persistent queries over live data streams.
+//!
+//! Monitoring several patterns over several live sources would otherwise take
+//! a process per pattern per source, each importing the same frames. A set of
+//! [`Query`] is instead compiled once, and each source is searched by a
+//! [`Session`] that runs all of them over its frames as they arrive.
+
+use std::error::Error;
+use std::ops::Range;
+
+use crate::compiler::Compiler;
+use crate::datastream::frame::Frame;
+use crate::datastream::transform::Pipeline;
+use crate::datastream::window::Window;
+use crate::matcher;
+use crate::matcher::online;
+use crate::matcher::Matching;
+use crate::symbolizer::ast::SymbolicAbstractSyntaxTree;
+
+/// A compiled pattern identified by a name.
+pub struct Query {
+    /// The identifier of the query, reported with each of its matches.
+    pub id: String,
+
+    ast: SymbolicAbstractSyntaxTree,
+
+    /// The maximum number of frames needed to find a match, if bounded.
+    capacity: Option<usize>,
+}
+
+impl Query {
+    /// Compile a new [`Query`] with the `compiler`.
+    ///
+    /// The `compiler` should be that of the search (see
+    /// [`Configuration::compiler`](crate::config::Configuration::compiler)),
+    /// such that the pattern has the same semantics. If a `window` is given,
+    /// it truncates the horizon of the pattern (see
+    /// [`Configuration::window`](crate::config::Configuration::window)).
+    pub fn compile(
+        id: &str,
+        pattern: &str,
+        compiler: &Compiler,
+        window: Option<usize>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let ast = compiler.compile(pattern)?;
+
+        let capacity = match (matcher::horizon(&ast), window) {
+            (Some(horizon), Some(window)) => Some(std::cmp::min(horizon, window)),
+            (horizon, window) => horizon.or(window),
+        };
+
+        Ok(Query {
+            id: id.to_string(),
+            ast,
+            capacity,
+        })
+    }
+
+    /// The maximum number of frames needed to find a match, if bounded.
+    pub fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+}
+
+/// A match of a [`Query`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Event<'a> {
+    /// The identifier of the query matched.
+    pub query: &'a str,
+
+    /// The indices of the frames of the match (i.e., `[start, end)`).
+    pub range: Range<usize>,
+}
+
+/// A search of a single data stream by a set of [`Query`].
+///
+/// The frames are kept once for all queries, such that only the most recent
+/// frames needed by any query are kept. If any query is unbounded, all frames
+/// are kept.
+pub struct Session<'a> {
+    queries: Vec<(&'a Query, online::Matcher<'a>)>,
+
+    /// The most recent frames of the data stream.
+    window: Window,
+
+    /// The tracks of the objects of the last frame, from which the motion of
+    /// each next frame is found (see [`Pipeline`]). The objects are only
+    /// tracked if a query uses their motion.
+    tracks: Pipeline,
+}
+
+impl<'a> Session<'a> {
+    /// Create a new [`Session`] of the `queries`.
+    pub fn new(queries: &'a [Query]) -> Result<Self, Box<dyn Error>> {
+        let capacity = queries
+            .iter()
+            .map(|q| q.capacity)
+            .try_fold(0, |acc, c| c.map(|c| acc.max(c)));
+
+        Ok(Session {
+            queries: queries
+                .iter()
+                .map(|q| Ok((q, online::Matcher::try_from(&q.ast)?)))
+                .collect::<Result<_, Box<dyn Error>>>()?,
+            window: Window::new(capacity),
+            tracks: Pipeline::new(Vec::new()).track(queries.iter().any(|q| q.ast.motion())),
+        })
+    }
+
+    /// Search the next [`Frame`] of the data stream.
+    ///
+    /// This returns the match of each query that ends at the frame, in the
+    /// order of the queries.
+    pub fn push(&mut self, frame: Frame) -> Result<Vec<Event<'a>>, Box<dyn Error>> {
+        // Find the motion of the tracked objects of the frame.
+        //
+        // Otherwise, a motion (e.g., `@speed`) of the pattern would never
+        // hold, as it is only found as the frames are imported by a search.
+        for frame in self.tracks.apply(vec![frame]) {
+            self.window.push(frame);
+        }
+
+        let mut events = Vec::new();
+
+        for (query, matcher) in self.queries.iter() {
+            // Search only the frames of the query.
+            //
+            // The frames are kept for the query that needs the most; however,
+            // searching more than the capacity of a query could find a match
+            // that it would not find alone.
+            let start = match query.capacity {
+                Some(capacity) => self.window.len().saturating_sub(capacity),
+                None => 0,
+            };
+
+            let frames = &self.window.frames()[start..];
+
+            if let Some(m) = matcher.leftmost(frames)? {
+                let frames = &frames[m.start..m.end];
+
+                if let (Some(first), Some(last)) = (frames.first(), frames.last()) {
+                    events.push(Event {
+                        query: &query.id,
+                        range: first.index..last.end(),
+                    });
+                }
+            }
+        }
+
+        Ok(events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compiler::ir::ops::Vacuity;
+    use crate::compiler::Compiler;
+    use crate::support::{frame, frames};
+
+    use super::{Query, Session};
+
+    #[test]
+    fn queries() {
+        let queries = [
+            Query::compile("cars", "[[:car:]]{2}", &Compiler::new(), None).unwrap(),
+            Query::compile(
+                "crossing",
+                "[[:car:]][[:pedestrian:]]",
+                &Compiler::new(),
+                None,
+            )
+            .unwrap(),
+        ];
+
+        let mut session = Session::new(&queries).unwrap();
+        assert_eq!(session.window.capacity(), Some(2));
+
+        let mut events = Vec::new();
+
+        for frame in frames(&["car", "car", "pedestrian"]) {
+            for event in session.push(frame).unwrap() {
+                events.push((event.query, event.range));
+            }
+        }
+
+        assert_eq!(events, [("cars", 0..2), ("crossing", 1..3)]);
+    }
+
+    #[test]
+    fn compiler() {
+        let pattern = "[A(x := [:truck:]) @dist(x, x) < 1.0]";
+
+        // Search the same frames with the strict and classical vacuity.
+        let events = |compiler: &Compiler| {
+            let queries = [Query::compile("trucks", pattern, compiler, None).unwrap()];
+            let mut session = Session::new(&queries).unwrap();
+
+            let mut count = 0;
+            for frame in frames(&["car", "car"]) {
+                count += session.push(frame).unwrap().len();
+            }
+
+            count
+        };
+
+        assert_eq!(events(&Compiler::new()), 0);
+        assert_eq!(events(&Compiler::new().vacuity(Vacuity::Classical)), 2);
+    }
+
+    #[test]
+    fn motion() {
+        let queries = [Query::compile(
+            "moving",
+            "[E(v := [:car:]) @dx(v) > 1.0]",
+            &Compiler::new(),
+            None,
+        )
+        .unwrap()];
+        let mut session = Session::new(&queries).unwrap();
+
+        // The car moves by 5.0 between the first two frames only, and a car
+        // that is not tracked has no motion.
+        let mut events = Vec::new();
+
+        for f in [
+            frame(0, &[("car", Some("a"), 0.0)]),
+            frame(1, &[("car", Some("a"), 5.0)]),
+            frame(2, &[("car", Some("a"), 5.0)]),
+            frame(3, &[("car", None, 10.0)]),
+        ] {
+            for event in session.push(f).unwrap() {
+                events.push((event.range.start, event.range.end));
+            }
+        }
+
+        assert_eq!(events, [(1, 2)]);
+    }
+}
@@ -0,0 +1,176 @@
+//! Real-time deadlines of an online search.
+//!
+//! On a vehicle, frames arrive at a fixed rate. If a frame takes longer to
+//! search than the time until the next frame arrives, the search falls behind
+//! the input. A [`Deadline`] measures each frame against a time budget and
+//! handles an overrun by an [`Overrun`] policy.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// The handling of a frame that misses its deadline.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Overrun {
+    /// Report each frame that misses its deadline, and search all frames.
+    #[default]
+    Report,
+
+    /// Drop the frames that arrive while the search is behind, until it has
+    /// caught up with the input.
+    Drop,
+}
+
+impl FromStr for Overrun {
+    type Err = DeadlineError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "report" => Ok(Overrun::Report),
+            "drop" => Ok(Overrun::Drop),
+            _ => Err(DeadlineError::from(format!(
+                "unknown policy `{}` (expected `report` or `drop`)",
+                s
+            ))),
+        }
+    }
+}
+
+/// A time budget of each frame of a search.
+///
+/// The frames are assumed to arrive once per budget. Therefore, the time a
+/// frame takes beyond its budget is carried over to the next frame, and the
+/// search is behind the input until this is paid off.
+#[derive(Clone, Debug)]
+pub struct Deadline {
+    budget: Duration,
+    policy: Overrun,
+
+    /// The time the search is behind the input.
+    behind: Duration,
+
+    /// The number of frames that missed their deadline.
+    missed: usize,
+
+    /// The number of frames dropped to catch up.
+    dropped: usize,
+}
+
+impl Deadline {
+    /// Create a new [`Deadline`] of a `budget` per frame.
+    pub fn new(budget: Duration, policy: Overrun) -> Self {
+        Deadline {
+            budget,
+            policy,
+            behind: Duration::ZERO,
+            missed: 0,
+            dropped: 0,
+        }
+    }
+
+    /// The time budget of each frame.
+    pub fn budget(&self) -> Duration {
+        self.budget
+    }
+
+    /// The time the search is currently behind the input.
+    pub fn behind(&self) -> Duration {
+        self.behind
+    }
+
+    /// The number of frames that missed their deadline so far.
+    pub fn missed(&self) -> usize {
+        self.missed
+    }
+
+    /// The number of frames dropped so far.
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Check if the next frame should be dropped.
+    ///
+    /// A frame is only dropped if the policy is to drop frames and the search
+    /// is behind by at least a budget (i.e., the frame has no time left). The
+    /// budget of a dropped frame is used to catch up instead.
+    pub fn skip(&mut self) -> bool {
+        if self.policy != Overrun::Drop || self.behind < self.budget {
+            return false;
+        }
+
+        self.behind -= self.budget;
+        self.dropped += 1;
+
+        true
+    }
+
+    /// Measure the time taken to search a frame against its deadline.
+    ///
+    /// This returns whether the frame missed its deadline.
+    pub fn measure(&mut self, latency: Duration) -> bool {
+        self.behind = (self.behind + latency).saturating_sub(self.budget);
+
+        if latency > self.budget {
+            self.missed += 1;
+            return true;
+        }
+
+        false
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DeadlineError {
+    msg: String,
+}
+
+impl From<&str> for DeadlineError {
+    fn from(msg: &str) -> Self {
+        DeadlineError {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for DeadlineError {
+    fn from(msg: String) -> Self {
+        DeadlineError { msg }
+    }
+}
+
+impl fmt::Display for DeadlineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "deadline: {}", self.msg)
+    }
+}
+
+impl Error for DeadlineError {}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Deadline, Overrun};
+
+    #[test]
+    fn catch_up() {
+        let ms = Duration::from_millis;
+        let mut deadline = Deadline::new(ms(10), Overrun::Drop);
+
+        assert!(!deadline.measure(ms(4)));
+        assert!(!deadline.skip());
+
+        // A frame of 35ms is behind by 25ms, such that the next two frames are
+        // dropped and the third is searched with 5ms left to catch up.
+        assert!(deadline.measure(ms(35)));
+        assert!(deadline.skip());
+        assert!(deadline.skip());
+        assert!(!deadline.skip());
+        assert_eq!(deadline.behind(), ms(5));
+
+        assert!(!deadline.measure(ms(2)));
+        assert_eq!(deadline.behind(), ms(0));
+        assert_eq!((deadline.missed(), deadline.dropped()), (1, 2));
+    }
+}
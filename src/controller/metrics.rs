@@ -0,0 +1,323 @@
+//! Rolling statistics of an online search.
+//!
+//! A long-running online search (e.g., monitoring a live data stream) gives no
+//! sign of its health until a match is found. [`Metrics`] keeps the statistics
+//! of the most recently processed frames, such that the throughput, latency,
+//! and rate of matches can be observed while searching.
+
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// The span of the most recent frames that statistics are kept over.
+///
+/// A window is written as a number of frames (e.g., `100`) or a number of
+/// seconds (e.g., `30s`), where seconds are measured by the wall clock.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Window {
+    Frames(usize),
+    Seconds(f64),
+}
+
+impl FromStr for Window {
+    type Err = MetricsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let window = match s.strip_suffix('s') {
+            Some(seconds) => seconds
+                .parse::<f64>()
+                .ok()
+                .filter(|x| *x > 0.0 && x.is_finite())
+                .map(Window::Seconds),
+            None => s
+                .parse::<usize>()
+                .ok()
+                .filter(|n| *n > 0)
+                .map(Window::Frames),
+        };
+
+        window.ok_or(MetricsError::from(format!(
+            "`{}`: expected a positive number of frames (e.g., `100`) or seconds (e.g., `30s`)",
+            s
+        )))
+    }
+}
+
+impl fmt::Display for Window {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Window::Frames(n) => write!(f, "{} frame(s)", n),
+            Window::Seconds(x) => write!(f, "{}s", x),
+        }
+    }
+}
+
+/// The statistics of a single processed frame.
+#[derive(Clone, Debug)]
+struct Entry {
+    /// When the frame finished processing.
+    at: Instant,
+
+    /// The time taken to search the frame.
+    latency: Duration,
+
+    /// The number of matches found at the frame.
+    matches: usize,
+}
+
+/// The rolling statistics of the frames processed by a search.
+#[derive(Clone, Debug)]
+pub struct Metrics {
+    window: Window,
+
+    /// The entries of the frames within the window, from oldest to newest.
+    entries: VecDeque<Entry>,
+
+    /// The number of frames processed in total.
+    frames: usize,
+
+    /// The number of matches found in total.
+    matches: usize,
+
+    /// The number of documents of the input dropped in total.
+    dropped: usize,
+
+    /// When the last report was due, and the number of frames since.
+    reported: (Instant, usize),
+}
+
+impl Metrics {
+    /// Create new [`Metrics`] kept over the [`Window`].
+    pub fn new(window: Window) -> Self {
+        Metrics {
+            window,
+            entries: VecDeque::new(),
+            frames: 0,
+            matches: 0,
+            dropped: 0,
+            reported: (Instant::now(), 0),
+        }
+    }
+
+    /// Record a processed frame.
+    ///
+    /// The `latency` is the time taken to search the frame, and `matches` is
+    /// the number of matches found at it.
+    pub fn record(&mut self, latency: Duration, matches: usize) {
+        self.record_at(Instant::now(), latency, matches);
+    }
+
+    fn record_at(&mut self, now: Instant, latency: Duration, matches: usize) {
+        self.frames += 1;
+        self.matches += matches;
+        self.reported.1 += 1;
+
+        self.entries.push_back(Entry {
+            at: now,
+            latency,
+            matches,
+        });
+
+        // Evict the entries outside the window.
+        //
+        // The newest entry is always kept, such that a window of seconds
+        // shorter than the time between frames still reports the last frame.
+        while self.entries.len() > 1 {
+            let expired = match self.window {
+                Window::Frames(n) => self.entries.len() > n,
+                Window::Seconds(x) => now.duration_since(self.entries[0].at).as_secs_f64() > x,
+            };
+
+            if !expired {
+                break;
+            }
+
+            self.entries.pop_front();
+        }
+    }
+
+    /// Record the number of documents of the input dropped so far.
+    ///
+    /// Documents are dropped before they are searched (e.g., by a full input
+    /// buffer); therefore, they are counted separately from the frames.
+    pub fn overflow(&mut self, dropped: usize) {
+        self.dropped = dropped;
+    }
+
+    /// Check if a report is due.
+    ///
+    /// A report is due once per window (i.e., every `n` frames or `x`
+    /// seconds), and the next report is then due a window from now.
+    pub fn due(&mut self) -> bool {
+        let due = match self.window {
+            Window::Frames(n) => self.reported.1 >= n,
+            Window::Seconds(x) => self.reported.0.elapsed().as_secs_f64() >= x,
+        };
+
+        if due {
+            self.reported = (Instant::now(), 0);
+        }
+
+        due
+    }
+
+    /// Check if any frames were recorded since the last report was due.
+    pub fn pending(&self) -> bool {
+        self.reported.1 > 0
+    }
+
+    /// Take a [`Snapshot`] of the current statistics.
+    pub fn snapshot(&self) -> Snapshot {
+        let latencies = self.entries.iter().map(|e| e.latency);
+
+        // Compute the throughput over the window.
+        //
+        // This requires at least two frames that are apart in time; otherwise,
+        // the throughput is unknown.
+        let throughput = match (self.entries.front(), self.entries.back()) {
+            (Some(first), Some(last)) if last.at > first.at => {
+                let elapsed = last.at.duration_since(first.at).as_secs_f64();
+                Some((self.entries.len() - 1) as f64 / elapsed)
+            }
+            _ => None,
+        };
+
+        Snapshot {
+            window: self.window,
+            frames: self.entries.len(),
+            matches: self.entries.iter().map(|e| e.matches).sum(),
+            throughput,
+            latency: latencies.clone().sum::<Duration>() / self.entries.len().max(1) as u32,
+            peak: latencies.max().unwrap_or_default(),
+            total: (self.frames, self.matches),
+            dropped: self.dropped,
+        }
+    }
+}
+
+/// The statistics of a search at a point in time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    /// The window the statistics are kept over.
+    pub window: Window,
+
+    /// The number of frames within the window.
+    pub frames: usize,
+
+    /// The number of matches found within the window.
+    pub matches: usize,
+
+    /// The number of frames processed per second within the window, if known.
+    pub throughput: Option<f64>,
+
+    /// The mean time taken to search a frame within the window.
+    pub latency: Duration,
+
+    /// The longest time taken to search a frame within the window.
+    pub peak: Duration,
+
+    /// The number of frames and matches since the search began.
+    pub total: (usize, usize),
+
+    /// The number of documents of the input dropped since the search began.
+    pub dropped: usize,
+}
+
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "last {}: {} frame(s), {} match(es), ",
+            self.window, self.frames, self.matches
+        )?;
+
+        if let Some(throughput) = self.throughput {
+            write!(f, "{:.1} frame(s)/s, ", throughput)?;
+        }
+
+        write!(
+            f,
+            "latency {:.3}ms (peak {:.3}ms); total: {} frame(s), {} match(es)",
+            self.latency.as_secs_f64() * 1000.0,
+            self.peak.as_secs_f64() * 1000.0,
+            self.total.0,
+            self.total.1
+        )?;
+
+        if self.dropped > 0 {
+            write!(f, ", {} dropped document(s)", self.dropped)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MetricsError {
+    msg: String,
+}
+
+impl From<&str> for MetricsError {
+    fn from(msg: &str) -> Self {
+        MetricsError {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for MetricsError {
+    fn from(msg: String) -> Self {
+        MetricsError { msg }
+    }
+}
+
+impl fmt::Display for MetricsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "metrics: {}", self.msg)
+    }
+}
+
+impl Error for MetricsError {}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::{Metrics, Window};
+
+    #[test]
+    fn window() {
+        assert_eq!("100".parse::<Window>().unwrap(), Window::Frames(100));
+        assert_eq!("2.5s".parse::<Window>().unwrap(), Window::Seconds(2.5));
+        assert!("0".parse::<Window>().is_err());
+        assert!("-1s".parse::<Window>().is_err());
+    }
+
+    #[test]
+    fn rolling() {
+        let start = Instant::now();
+        let ms = Duration::from_millis;
+
+        let mut frames = Metrics::new(Window::Frames(2));
+        let mut seconds = Metrics::new(Window::Seconds(1.0));
+
+        for (i, (latency, matches)) in [(4, 1), (2, 0), (6, 2)].into_iter().enumerate() {
+            let now = start + Duration::from_secs(i as u64);
+
+            frames.record_at(now, ms(latency), matches);
+            seconds.record_at(now, ms(latency), matches);
+        }
+
+        let snapshot = frames.snapshot();
+        assert_eq!((snapshot.frames, snapshot.matches), (2, 2));
+        assert_eq!((snapshot.latency, snapshot.peak), (ms(4), ms(6)));
+        assert_eq!(snapshot.throughput, Some(1.0));
+        assert_eq!(snapshot.total, (3, 3));
+
+        // Only the frames within the last second are kept.
+        let snapshot = seconds.snapshot();
+        assert_eq!((snapshot.frames, snapshot.matches), (2, 2));
+    }
+}
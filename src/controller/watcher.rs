@@ -0,0 +1,144 @@
+//! Watching of the pattern file of an online search.
+//!
+//! A long-running online search (e.g., monitoring a live data stream) would
+//! otherwise need to be restarted to change its pattern, which drops the input
+//! and the frames kept of it. A [`Watcher`] detects when the file of the
+//! pattern changes, such that the pattern is reloaded in place.
+
+use std::fs;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::hash::Fnv;
+
+/// The shortest time between two checks of the file, by default.
+const INTERVAL: Duration = Duration::from_millis(500);
+
+/// A watcher of the file of a pattern.
+///
+/// The modification time of a file may be unchanged by a write (e.g., within
+/// the resolution of the file system); therefore, the contents of the file
+/// are compared with those last loaded instead.
+#[derive(Clone, Debug)]
+pub struct Watcher {
+    path: PathBuf,
+
+    /// The hash of the pattern last loaded.
+    hash: u64,
+
+    /// The shortest time between two checks of the file.
+    interval: Duration,
+
+    /// When the file was last checked, if ever.
+    checked: Option<Instant>,
+
+    /// Whether the file could not be read when last checked.
+    failed: bool,
+}
+
+impl Watcher {
+    /// Create a new [`Watcher`] of the file at `path`.
+    ///
+    /// The `pattern` is the one loaded from the file, such that a change made
+    /// before the watcher is created is still detected.
+    pub fn new(path: &Path, pattern: &str) -> Self {
+        Watcher {
+            path: path.to_path_buf(),
+            hash: Self::hash(pattern),
+            interval: INTERVAL,
+            checked: None,
+            failed: false,
+        }
+    }
+
+    /// Set the shortest time between two checks of the file.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Check if the file has changed since it was last loaded.
+    ///
+    /// This returns the new pattern of the file (without surrounding
+    /// whitespace), if changed. The file is checked at most once per interval,
+    /// and a file that cannot be read is reported once and skipped until it
+    /// can be read again.
+    pub fn poll(&mut self) -> Option<String> {
+        if self.checked.is_some_and(|c| c.elapsed() < self.interval) {
+            return None;
+        }
+
+        self.checked = Some(Instant::now());
+
+        let pattern = match fs::read_to_string(&self.path) {
+            Ok(pattern) => pattern.trim().to_string(),
+            Err(e) => {
+                if !self.failed {
+                    eprintln!(
+                        "controller: warning: {}: failed to reload pattern ({})",
+                        self.path.display(),
+                        e
+                    );
+                }
+
+                self.failed = true;
+                return None;
+            }
+        };
+
+        self.failed = false;
+
+        let hash = Self::hash(&pattern);
+
+        if hash == self.hash {
+            return None;
+        }
+
+        self.hash = hash;
+        Some(pattern)
+    }
+
+    /// The hash of a pattern, without surrounding whitespace.
+    fn hash(pattern: &str) -> u64 {
+        let mut hasher = Fnv::new();
+        hasher.write(pattern.trim().as_bytes());
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::time::Duration;
+
+    use super::Watcher;
+
+    #[test]
+    fn poll() {
+        let path = std::env::temp_dir().join(format!("strem-watcher-{}.spre", std::process::id()));
+        fs::write(&path, "[[:car:]]").unwrap();
+
+        let mut watcher = Watcher::new(&path, "[[:car:]]").with_interval(Duration::ZERO);
+        assert_eq!(watcher.poll(), None);
+
+        // A change of the same length is detected, even if the modification
+        // time of the file is unchanged.
+        fs::write(&path, "[[:bus:]]").unwrap();
+        assert_eq!(watcher.poll(), Some(String::from("[[:bus:]]")));
+        assert_eq!(watcher.poll(), None);
+
+        // Rewriting the same pattern is not a change.
+        fs::write(&path, "[[:bus:]]\n").unwrap();
+        assert_eq!(watcher.poll(), None);
+
+        // The file is checked at most once per interval.
+        let mut watcher = Watcher::new(&path, "[[:car:]]").with_interval(Duration::from_secs(3600));
+        assert_eq!(watcher.poll(), Some(String::from("[[:bus:]]")));
+
+        fs::write(&path, "[[:car:]]").unwrap();
+        assert_eq!(watcher.poll(), None);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
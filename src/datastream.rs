@@ -2,34 +2,74 @@
 //!
 //! This is the format from which all importers must import to.
 
+use std::borrow::Cow;
+use std::collections::VecDeque;
 use std::error::Error;
 use std::fmt;
-use std::io::Read;
 
-use serde_json::de::IoRead;
-use serde_json::StreamDeserializer;
+use serde::Deserialize;
+use serde_json::de::{IoRead, Read, SliceRead};
+use serde_json::{Deserializer, StreamDeserializer};
 
+use self::buffer::{Buffer, Overflow};
 use self::frame::Frame;
+use self::index::{Document, FrameIndex, Header};
 use self::io::importer::Importer;
 
+pub mod buffer;
 pub mod frame;
+pub mod gap;
+pub mod index;
 pub mod io;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+pub mod roi;
+pub mod trajectory;
+pub mod transform;
+pub mod window;
+
+/// The documents converted from another format, in the order they are read.
+type Documents = Box<dyn Iterator<Item = Result<io::Converted, Box<dyn Error>>> + Send>;
 
 /// An interface to interact with perception stream data.
 ///
 /// It should be further noted that this interface provides basic mechanisms to
 /// reading/writing of the stream regardless of offline/online application.
-pub struct DataStream<'a, R: Read> {
+pub struct DataStream<'a, R: Read<'a>> {
     pub frames: Vec<Frame>,
 
     /// The source from which data is loaded.
-    pub stream: StreamDeserializer<'a, IoRead<R>, io::DataStream>,
+    pub stream: StreamDeserializer<'a, R, io::DataStream<'a>>,
+
+    /// Data already deserialized that is imported before the `stream`.
+    pending: VecDeque<io::DataStream<'a>>,
+
+    /// The in-memory buffer of the `stream`, if any.
+    ///
+    /// This allows the remainder of the buffer to be parsed frame by frame
+    /// when a document is malformed.
+    source: Option<&'a [u8]>,
+
+    /// The bounded queue the data is read from instead of the `stream`, if any.
+    buffer: Option<Buffer>,
+
+    /// The frames built in memory that are requested instead of the `stream`,
+    /// if any.
+    memory: Option<Box<dyn Iterator<Item = Frame> + Send>>,
+
+    /// The records of another format that could not be converted, which are
+    /// rejected through the [`Importer`] before any data is imported.
+    rejected: VecDeque<String>,
+
+    /// The documents of another source (e.g., a ROS bag) that are imported
+    /// instead of the `stream`, if any.
+    documents: Option<Documents>,
 
     /// A limit on the number of frames to keep in memory.
     pub capacity: Option<usize>,
 }
 
-impl<R: Read> DataStream<'_, R> {
+impl<R: std::io::Read> DataStream<'_, IoRead<R>> {
     /// Create a new [`DataStream`] with the selected format.
     ///
     /// This function creates an empty [`DataStream`] instance that still must
@@ -41,25 +81,435 @@ impl<R: Read> DataStream<'_, R> {
             frames: Vec::new(),
             capacity: None,
             stream,
+            pending: VecDeque::new(),
+            source: None,
+            buffer: None,
+            memory: None,
+            rejected: VecDeque::new(),
+            documents: None,
+        }
+    }
+}
+
+impl DataStream<'static, SliceRead<'static>> {
+    /// Create a new [`DataStream`] read through a bounded [`Buffer`].
+    ///
+    /// The `source` is read on a separate thread into a queue of at most
+    /// `capacity` documents, and a document read while the queue is full is
+    /// handled by the [`Overflow`] policy (see [`DataStream::dropped`]).
+    pub fn buffered<R: std::io::Read + Send + 'static>(
+        source: R,
+        capacity: usize,
+        overflow: Overflow,
+    ) -> Self {
+        DataStream {
+            frames: Vec::new(),
+            capacity: None,
+            stream: StreamDeserializer::new(SliceRead::new(&[])),
+            pending: VecDeque::new(),
+            source: None,
+            buffer: Some(Buffer::spawn(source, capacity, overflow)),
+            memory: None,
+            rejected: VecDeque::new(),
+            documents: None,
+        }
+    }
+
+    /// Create a new [`DataStream`] of frames built in memory.
+    ///
+    /// This avoids serializing frames that are produced in the same process
+    /// (e.g., by a simulator) only to parse them again. The frames are
+    /// requested one at a time as they are produced, such that an unbounded
+    /// iterator may be searched online. As the frames are not imported, the
+    /// options applied during import (e.g., an ontology) do not apply.
+    pub fn from_frames<I>(frames: I) -> Self
+    where
+        I: IntoIterator<Item = Frame>,
+        I::IntoIter: Send + 'static,
+    {
+        DataStream {
+            frames: Vec::new(),
+            capacity: None,
+            stream: StreamDeserializer::new(SliceRead::new(&[])),
+            pending: VecDeque::new(),
+            source: None,
+            buffer: None,
+            memory: Some(Box::new(frames.into_iter())),
+            rejected: VecDeque::new(),
+            documents: None,
+        }
+    }
+
+    /// Create a new [`DataStream`] from a ROS bag (see [`io::rosbag`]).
+    ///
+    /// Each detection message of the `topic` (or, if none, of the first topic
+    /// of a supported type) is converted into a frame as it is read; therefore,
+    /// a bag (e.g., piped from a recording) may be searched online.
+    #[cfg(feature = "rosbag")]
+    pub fn from_rosbag<R: std::io::Read + Send + 'static>(
+        source: R,
+        topic: Option<String>,
+    ) -> Result<Self, Box<dyn Error>> {
+        Ok(DataStream {
+            frames: Vec::new(),
+            capacity: None,
+            stream: StreamDeserializer::new(SliceRead::new(&[])),
+            pending: VecDeque::new(),
+            source: None,
+            buffer: None,
+            memory: None,
+            rejected: VecDeque::new(),
+            documents: Some(Box::new(io::rosbag::Bag::new(source, topic)?)),
+        })
+    }
+
+    /// Create a new [`DataStream`] of the messages of an MQTT topic.
+    ///
+    /// The `url` is of the broker and topic (i.e., `mqtt://host[:port]/topic`),
+    /// and each message is imported as it arrives (see [`mqtt::Subscriber`]).
+    /// As the topic never ends, this is only searched online.
+    #[cfg(feature = "mqtt")]
+    pub fn subscribe(url: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(DataStream {
+            frames: Vec::new(),
+            capacity: None,
+            stream: StreamDeserializer::new(SliceRead::new(&[])),
+            pending: VecDeque::new(),
+            source: None,
+            buffer: None,
+            memory: None,
+            rejected: VecDeque::new(),
+            documents: Some(Box::new(mqtt::Subscriber::connect(url)?)),
+        })
+    }
+}
+
+impl<'a> DataStream<'a, SliceRead<'a>> {
+    /// Create a new [`DataStream`] from an in-memory buffer.
+    ///
+    /// Unlike [`DataStream::new`], strings are borrowed from the buffer during
+    /// import where possible, which avoids an allocation for each of them.
+    pub fn from_slice(source: &'a [u8]) -> Self {
+        let stream = StreamDeserializer::new(SliceRead::new(source));
+
+        DataStream {
+            frames: Vec::new(),
+            capacity: None,
+            stream,
+            pending: VecDeque::new(),
+            source: Some(source),
+            buffer: None,
+            memory: None,
+            rejected: VecDeque::new(),
+            documents: None,
+        }
+    }
+
+    /// Create a new [`DataStream`] that starts at frame `from`.
+    ///
+    /// The [`FrameIndex`] of the buffer is used to seek to the first frame with
+    /// an index of at least `from`; the frames before it are never parsed.
+    pub fn from_index(
+        source: &'a [u8],
+        index: &FrameIndex,
+        from: usize,
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut pending: VecDeque<io::DataStream<'a>> = VecDeque::new();
+        let mut document = None;
+
+        for e in index.entries[index.seek(from)..].iter() {
+            let frame = serde_json::from_slice(&source[e.offset..(e.offset + e.length)])
+                .map_err(|e| DataStreamError::from(e.to_string()))?;
+
+            // Group the frames by document.
+            //
+            // Each document declares its own version; therefore, frames of
+            // different documents must be imported separately.
+            match pending.back_mut() {
+                Some(data) if document == Some(e.document) => data.frames.push(frame),
+                _ => {
+                    pending.push_back(io::DataStream {
+                        version: Cow::Owned(index.documents[e.document].clone()),
+                        frames: vec![frame],
+                    });
+
+                    document = Some(e.document);
+                }
+            }
+        }
+
+        Ok(DataStream {
+            frames: Vec::new(),
+            capacity: None,
+            stream: StreamDeserializer::new(SliceRead::new(&[])),
+            pending,
+            source: None,
+            buffer: None,
+            memory: None,
+            rejected: VecDeque::new(),
+            documents: None,
+        })
+    }
+
+    /// Create a new [`DataStream`] from a COCO document (see [`io::coco`]).
+    ///
+    /// The document is converted in full before any frame is requested. An
+    /// annotation that cannot be converted is rejected once the first frame is
+    /// requested.
+    pub fn from_coco(source: &'a [u8]) -> Result<Self, Box<dyn Error>> {
+        let (data, rejected) = io::coco::convert(source)?;
+
+        let mut pending = VecDeque::new();
+        pending.push_back(data);
+
+        Ok(DataStream {
+            frames: Vec::new(),
+            capacity: None,
+            stream: StreamDeserializer::new(SliceRead::new(&[])),
+            pending,
+            source: None,
+            buffer: None,
+            memory: None,
+            rejected: VecDeque::from(rejected),
+            documents: None,
+        })
+    }
+
+    /// Create a new [`DataStream`] that merges several buffers by timestamp.
+    ///
+    /// The frames of all buffers are ordered by timestamp, and those captured
+    /// within `tolerance` seconds of the first frame of a group are merged into
+    /// a single frame (i.e., their samples are combined). The merged frames are
+    /// then indexed from zero. As each buffer is upgraded to the current
+    /// version first, a frame without a timestamp cannot be aligned.
+    pub fn align(sources: &[&'a [u8]], tolerance: f64) -> Result<Self, Box<dyn Error>> {
+        let mut frames = Vec::new();
+
+        for source in sources.iter() {
+            for data in Deserializer::from_slice(source).into_iter::<io::DataStream<'a>>() {
+                let data = data.map_err(|e| DataStreamError::from(e.to_string()))?;
+                frames.extend(io::version::upgrade(data)?.frames);
+            }
+        }
+
+        let mut timestamps = Vec::with_capacity(frames.len());
+
+        for frame in frames.iter() {
+            match frame.timestamp() {
+                Some(timestamp) => timestamps.push(timestamp),
+                None => {
+                    return Err(Box::new(DataStreamError::from(
+                        "cannot align a frame without a timestamp",
+                    )))
+                }
+            }
+        }
+
+        // Order the frames by timestamp.
+        //
+        // The sort is stable; therefore, frames with the same timestamp are kept
+        // in the order of their buffers.
+        let mut order = (0..frames.len()).collect::<Vec<usize>>();
+        order.sort_by(|a, b| timestamps[*a].total_cmp(&timestamps[*b]));
+
+        let mut frames = frames.into_iter().map(Some).collect::<Vec<_>>();
+        let mut aligned: Vec<io::Frame<'a>> = Vec::new();
+        let mut start = f64::NEG_INFINITY;
+
+        for i in order {
+            let frame = frames[i].take().unwrap();
+
+            match aligned.last_mut() {
+                Some(last) if timestamps[i] - start <= tolerance => last.merge(frame),
+                _ => {
+                    start = timestamps[i];
+                    aligned.push(frame);
+                }
+            }
+        }
+
+        for (index, frame) in aligned.iter_mut().enumerate() {
+            frame.reindex(index);
         }
+
+        let mut pending = VecDeque::new();
+        pending.push_back(io::DataStream {
+            version: Cow::Owned(io::version::Version::current().to_string()),
+            frames: aligned,
+        });
+
+        Ok(DataStream {
+            frames: Vec::new(),
+            capacity: None,
+            stream: StreamDeserializer::new(SliceRead::new(&[])),
+            pending,
+            source: None,
+            buffer: None,
+            memory: None,
+            rejected: VecDeque::new(),
+            documents: None,
+        })
     }
+}
 
+impl<'a, R: Read<'a>> DataStream<'a, R> {
     /// Set the `capacity` of the [`DataStream`].
     pub fn capacity(&mut self, size: usize) {
         self.capacity = Some(size);
     }
 
+    /// The number of documents dropped by the [`Buffer`], if any.
+    ///
+    /// This is always zero unless the [`DataStream`] is buffered.
+    pub fn dropped(&self) -> usize {
+        self.buffer.as_ref().map_or(0, Buffer::dropped)
+    }
+
     /// Request the next frame from the [`DataImport`].
     pub fn request(
         &mut self,
         importer: &mut Importer,
     ) -> Result<Option<Vec<Frame>>, Box<dyn Error>> {
+        while let Some(msg) = self.rejected.pop_front() {
+            importer.reject(msg)?;
+        }
+
+        if let Some(data) = self.pending.pop_front() {
+            return importer.import(data);
+        }
+
+        if let Some(memory) = self.memory.as_mut() {
+            return Ok(memory.next().map(|frame| {
+                importer.admit(&frame);
+                vec![frame]
+            }));
+        }
+
+        if let Some(documents) = self.documents.as_mut() {
+            loop {
+                match documents.next().transpose()? {
+                    Some(io::Converted::Document(data)) => return importer.import(data),
+                    Some(io::Converted::Rejected(msg)) => importer.reject(msg)?,
+                    None => return Ok(None),
+                }
+            }
+        }
+
+        if let Some(buffer) = &self.buffer {
+            return match buffer.next() {
+                Some(Ok(data)) => importer.import(data),
+                Some(Err(e)) => Err(Box::new(DataStreamError::from(e))),
+                None => Ok(None),
+            };
+        }
+
         match self.stream.next() {
-            Some(data) => importer.import(data?),
+            Some(Ok(data)) => importer.import(data),
+            Some(Err(e)) => match self.source {
+                // Recover from a malformed document.
+                //
+                // The stream cannot continue past an error; therefore, the
+                // remainder of the buffer is parsed frame by frame instead such
+                // that only the malformed frames are skipped.
+                Some(source) if importer.lenient() => {
+                    let offset = self.stream.byte_offset();
+                    self.source = None;
+
+                    self.recover(&source[offset..], importer)?;
+                    self.request(importer)
+                }
+                _ => Err(Box::new(DataStreamError::from(e.to_string()))),
+            },
             None => Ok(None),
         }
     }
 
+    /// Parse each frame of the documents of `source` individually.
+    ///
+    /// Frames that are malformed are rejected through the [`Importer`], and
+    /// the remaining frames are queued to be imported. If the index of a
+    /// malformed frame is known, the frame is kept without any samples such
+    /// that the indices of a match are unchanged. A frame with a malformed
+    /// annotation keeps its other annotations (see [`Self::salvage`]). A
+    /// document that is not valid JSON cannot be recovered from.
+    fn recover(&mut self, source: &'a [u8], importer: &mut Importer) -> Result<(), Box<dyn Error>> {
+        let stream = Deserializer::from_slice(source).into_iter::<Document>();
+
+        for document in stream {
+            let document = document.map_err(|e| DataStreamError::from(e.to_string()))?;
+            let mut frames = Vec::new();
+
+            for frame in document.frames {
+                match serde_json::from_str(frame.get()) {
+                    Ok(frame) => frames.push(frame),
+                    Err(e) => match serde_json::from_str::<Header>(frame.get()) {
+                        Ok(header) => match Self::salvage(frame.get(), header.index, importer)? {
+                            Some(frame) => frames.push(frame),
+                            None => {
+                                importer.reject(format!("frame {}: {}", header.index, e))?;
+                                frames.push(io::Frame::new(header.index));
+                            }
+                        },
+                        Err(_) => importer.reject(format!("frame: {}", e))?,
+                    },
+                }
+            }
+
+            self.pending.push_back(io::DataStream {
+                version: document.version,
+                frames,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Parse a malformed frame without its malformed annotations.
+    ///
+    /// Each annotation of a sample is parsed individually, such that only those
+    /// that are malformed are rejected through the [`Importer`]. If the frame is
+    /// malformed otherwise (e.g., a sample without a channel), `None` is
+    /// returned instead.
+    fn salvage(
+        source: &str,
+        index: usize,
+        importer: &mut Importer,
+    ) -> Result<Option<io::Frame<'a>>, Box<dyn Error>> {
+        let Ok(mut value) = serde_json::from_str::<serde_json::Value>(source) else {
+            return Ok(None);
+        };
+
+        let mut rejected = Vec::new();
+
+        if let Some(samples) = value.get_mut("samples").and_then(|s| s.as_array_mut()) {
+            for sample in samples.iter_mut() {
+                let Some(annotations) =
+                    sample.get_mut("annotations").and_then(|a| a.as_array_mut())
+                else {
+                    continue;
+                };
+
+                annotations.retain(|annotation| match io::Annotation::deserialize(annotation) {
+                    Ok(_) => true,
+                    Err(e) => {
+                        rejected.push(e.to_string());
+                        false
+                    }
+                });
+            }
+        }
+
+        let Ok(frame) = io::Frame::deserialize(value) else {
+            return Ok(None);
+        };
+
+        for e in rejected {
+            importer.reject(format!("frame {}: annotation: {}", index, e))?;
+        }
+
+        Ok(Some(frame))
+    }
+
     /// Insert a [`Frame`] at the specified index.
     ///
     /// # Panics
@@ -78,7 +528,7 @@ impl<R: Read> DataStream<'_, R> {
     }
 }
 
-impl<'a, R: Read> fmt::Debug for DataStream<'a, R> {
+impl<'a, R: Read<'a>> fmt::Debug for DataStream<'a, R> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("DataStream")
             .field("frames", &self.frames)
@@ -113,3 +563,192 @@ impl fmt::Display for DataStreamError {
 }
 
 impl Error for DataStreamError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Configuration;
+
+    use super::frame::sample::classification::ClassificationRecord;
+    use super::frame::sample::detections::label::Label;
+    use super::frame::sample::Sample;
+    use super::frame::Frame;
+    use super::io::importer::Importer;
+    use super::DataStream;
+
+    #[test]
+    fn recover() {
+        let source = br#"{"version":"2.0.0","frames":[{"index":0,"samples":[]},{"index":1,"samples":0},{"index":2,"samples":[]}]}"#;
+
+        let pattern = String::from("[[:car:]]");
+        let mut config = Configuration::new(&pattern);
+
+        let mut importer = Importer::new(&config);
+        let frames = DataStream::from_slice(source)
+            .request(&mut importer)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            frames.iter().map(|f| f.index).collect::<Vec<_>>(),
+            [0, 1, 2]
+        );
+        assert_eq!(importer.skipped(), 1);
+
+        config.fail_fast = true;
+
+        let mut importer = Importer::new(&config);
+        assert!(DataStream::from_slice(source)
+            .request(&mut importer)
+            .is_err());
+    }
+
+    #[test]
+    fn recover_annotations() {
+        let source = br#"{"version":"2.0.0","frames":[{"index":0,"samples":[{"type":"@stremf/sample/detection","channel":"cam","annotations":[{"class":"car","score":1.0,"bbox":{"type":"@stremf/bbox/aabb","region":{"center":{"x":1.0,"y":1.0},"dimensions":{"w":2.0,"h":2.0}}}},{"class":"car","score":"oops","bbox":{"type":"@stremf/bbox/aabb","region":{"center":{"x":1.0,"y":1.0},"dimensions":{"w":2.0,"h":2.0}}}}]}]}]}"#;
+
+        let pattern = String::from("[[:car:]]");
+        let config = Configuration::new(&pattern);
+
+        let mut importer = Importer::new(&config);
+        let frames = DataStream::from_slice(source)
+            .request(&mut importer)
+            .unwrap()
+            .unwrap();
+
+        // Only the malformed annotation is rejected.
+        let Sample::ObjectDetection(record) = &frames[0].samples[0] else {
+            panic!("expected a detection");
+        };
+
+        assert_eq!(record.annotations[&Label::from("car")].len(), 1);
+        assert_eq!(importer.skipped(), 1);
+    }
+
+    #[test]
+    fn align() {
+        let a = br#"{"version":"2.0.0","frames":[{"index":0,"timestamp":0.0,"samples":[]},{"index":1,"timestamp":0.2,"samples":[]}]}"#;
+        let b = br#"{"version":"2.0.0","frames":[{"index":0,"timestamp":0.01,"samples":[]},{"index":1,"timestamp":0.1,"samples":[]}]}"#;
+
+        let pattern = String::from("[[:car:]]");
+        let config = Configuration::new(&pattern);
+
+        let mut importer = Importer::new(&config);
+        let frames = DataStream::align(&[a, b], 0.05)
+            .unwrap()
+            .request(&mut importer)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(
+            frames.iter().map(|f| f.timestamp).collect::<Vec<_>>(),
+            [Some(0.0), Some(0.1), Some(0.2)]
+        );
+        assert_eq!(
+            frames.iter().map(|f| f.index).collect::<Vec<_>>(),
+            [0, 1, 2]
+        );
+
+        let c = br#"{"version":"2.0.0","frames":[{"index":0,"samples":[]}]}"#;
+        assert!(DataStream::align(&[a, c], 0.05).is_err());
+    }
+
+    #[test]
+    fn from_frames() {
+        let pattern = String::from("[[:car:]]");
+        let config = Configuration::new(&pattern);
+
+        let mut importer = Importer::new(&config);
+        let mut datastream = DataStream::from_frames((0..2).map(|i| {
+            let mut record = ClassificationRecord::new(String::from("cam"));
+            record.labels.insert(Label::from("car"), 1.0);
+
+            let mut frame = Frame::new(i);
+            frame.samples.push(Sample::Classification(record));
+
+            frame
+        }));
+
+        let mut indices = Vec::new();
+        while let Some(frames) = datastream.request(&mut importer).unwrap() {
+            indices.extend(frames.iter().map(|f| f.index));
+        }
+
+        assert_eq!(indices, [0, 1]);
+        assert!(importer.labels().contains(&Label::from("car")));
+    }
+
+    #[test]
+    fn images() {
+        let source = br#"{"version":"2.0.0","frames":[{"index":0,"samples":[{"type":"@stremf/sample/detection","channel":"cam","image":{"path":"0.png","dimensions":{"width":10,"height":10}},"annotations":[]}]}]}"#;
+
+        let image = |config: &Configuration| {
+            let mut importer = Importer::new(config);
+            let frames = DataStream::from_slice(source)
+                .request(&mut importer)
+                .unwrap()
+                .unwrap();
+
+            match &frames[0].samples[0] {
+                Sample::ObjectDetection(record) => record.image.is_some(),
+                _ => panic!("expected a detection"),
+            }
+        };
+
+        let pattern = String::from("[[:car:]]");
+        let mut config = Configuration::new(&pattern);
+        assert!(!image(&config));
+
+        config.publish = true;
+        assert!(image(&config));
+    }
+
+    #[test]
+    fn merge_suppress() {
+        let annotation = |score: f64, x: f64| {
+            format!(
+                r#"{{"class":"car","score":{:.1},"bbox":{{"type":"@stremf/bbox/aabb","region":{{"center":{{"x":{:.1},"y":50.0}},"dimensions":{{"w":20.0,"h":20.0}}}}}}}}"#,
+                score, x
+            )
+        };
+
+        // Each channel has a duplicate of its own, and both channels see a car
+        // at the same position of their image.
+        let source = format!(
+            r#"{{"version":"2.0.0","frames":[{{"index":0,"samples":[{{"type":"@stremf/sample/detection","channel":"a","image":{{"path":"a.png","dimensions":{{"width":100,"height":100}}}},"annotations":[{},{}]}},{{"type":"@stremf/sample/detection","channel":"b","image":{{"path":"b.png","dimensions":{{"width":100,"height":100}}}},"annotations":[{},{}]}}]}}]}}"#,
+            annotation(0.9, 50.0),
+            annotation(0.7, 52.0),
+            annotation(0.8, 51.0),
+            annotation(0.6, 53.0)
+        );
+
+        let pattern = String::from("[[:car:]]");
+        let mut config = Configuration::new(&pattern);
+        config.merge = true;
+        config.nms = Some(0.5);
+        config.publish = true;
+
+        let mut importer = Importer::new(&config);
+        let frames = DataStream::from_slice(source.as_bytes())
+            .request(&mut importer)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(frames[0].samples.len(), 1);
+
+        match &frames[0].samples[0] {
+            Sample::ObjectDetection(record) => {
+                assert_eq!(record.channel, "a+b");
+                assert_eq!(record.images().len(), 2);
+
+                // The duplicates are suppressed within each channel, but not
+                // across channels.
+                let scores: Vec<f64> = record.annotations[&Label::from("car")]
+                    .iter()
+                    .map(|a| a.score)
+                    .collect();
+                assert_eq!(scores, vec![0.9, 0.8]);
+            }
+            _ => panic!("expected a detection"),
+        }
+    }
+}
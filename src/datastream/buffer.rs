@@ -0,0 +1,263 @@
+//! Bounded buffering of a data stream.
+//!
+//! A live source (e.g., a socket or queue) produces documents at its own rate,
+//! regardless of how fast they are searched. If the search cannot keep up, an
+//! unbounded read grows without limit. A [`Buffer`] reads the source on its own
+//! thread into a queue of a fixed capacity, and handles a full queue by an
+//! [`Overflow`] policy.
+
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+use serde_json::de::IoRead;
+use serde_json::StreamDeserializer;
+
+use super::io;
+
+/// The handling of a document read while the [`Buffer`] is full.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Overflow {
+    /// Stop reading the source until there is room (i.e., apply backpressure).
+    #[default]
+    Block,
+
+    /// Drop the oldest document of the buffer to make room.
+    DropOldest,
+
+    /// Drop the document read.
+    DropNewest,
+}
+
+impl FromStr for Overflow {
+    type Err = BufferError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "block" => Ok(Overflow::Block),
+            "drop-oldest" => Ok(Overflow::DropOldest),
+            "drop-newest" => Ok(Overflow::DropNewest),
+            _ => Err(BufferError::from(format!(
+                "unknown policy `{}` (expected `block`, `drop-oldest`, or `drop-newest`)",
+                s
+            ))),
+        }
+    }
+}
+
+/// A document read from the source, or the error that ended the source.
+type Document = Result<io::DataStream<'static>, String>;
+
+/// The queue shared between the reader of the source and the [`Buffer`].
+struct Shared {
+    state: Mutex<State>,
+
+    /// Signaled when a document is queued or the source is closed.
+    ready: Condvar,
+
+    /// Signaled when a document is taken from the queue.
+    space: Condvar,
+
+    capacity: usize,
+    overflow: Overflow,
+}
+
+struct State {
+    documents: VecDeque<Document>,
+
+    /// Whether the source has ended.
+    closed: bool,
+
+    /// The number of documents dropped.
+    dropped: usize,
+}
+
+impl Shared {
+    /// Queue a document read from the source.
+    ///
+    /// An error is always queued, such that it is never silently dropped.
+    fn push(&self, document: Document) {
+        let mut state = self.state.lock().unwrap();
+
+        while state.documents.len() >= self.capacity && document.is_ok() {
+            match self.overflow {
+                Overflow::Block => state = self.space.wait(state).unwrap(),
+                Overflow::DropOldest => {
+                    state.documents.pop_front();
+                    state.dropped += 1;
+                }
+                Overflow::DropNewest => {
+                    state.dropped += 1;
+                    return;
+                }
+            }
+        }
+
+        state.documents.push_back(document);
+        self.ready.notify_one();
+    }
+
+    /// Mark the source as ended.
+    fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.ready.notify_one();
+    }
+}
+
+/// A queue of the documents of a source, read on a separate thread.
+pub struct Buffer {
+    shared: Arc<Shared>,
+}
+
+impl Buffer {
+    fn new(capacity: usize, overflow: Overflow) -> Self {
+        Buffer {
+            shared: Arc::new(Shared {
+                state: Mutex::new(State {
+                    documents: VecDeque::new(),
+                    closed: false,
+                    dropped: 0,
+                }),
+                ready: Condvar::new(),
+                space: Condvar::new(),
+                capacity: capacity.max(1),
+                overflow,
+            }),
+        }
+    }
+
+    /// Start reading a `source` into a new [`Buffer`].
+    ///
+    /// At most `capacity` documents are queued at a time; a document read
+    /// while the buffer is full is handled by the [`Overflow`] policy. The
+    /// source is read until it ends or a document is malformed.
+    pub fn spawn<R: std::io::Read + Send + 'static>(
+        source: R,
+        capacity: usize,
+        overflow: Overflow,
+    ) -> Self {
+        let buffer = Buffer::new(capacity, overflow);
+        let shared = Arc::clone(&buffer.shared);
+
+        thread::spawn(move || {
+            let stream = StreamDeserializer::<_, io::DataStream<'static>>::new(IoRead::new(source));
+
+            for data in stream {
+                let failed = data.is_err();
+                shared.push(data.map_err(|e| e.to_string()));
+
+                if failed {
+                    break;
+                }
+            }
+
+            shared.close();
+        });
+
+        buffer
+    }
+
+    /// Take the next document, waiting for one to be read if needed.
+    ///
+    /// This returns `None` once the source has ended and all of its documents
+    /// are taken.
+    pub fn next(&self) -> Option<Document> {
+        let mut state = self.shared.state.lock().unwrap();
+
+        loop {
+            if let Some(document) = state.documents.pop_front() {
+                self.shared.space.notify_one();
+                return Some(document);
+            }
+
+            if state.closed {
+                return None;
+            }
+
+            state = self.shared.ready.wait(state).unwrap();
+        }
+    }
+
+    /// The number of documents dropped so far.
+    pub fn dropped(&self) -> usize {
+        self.shared.state.lock().unwrap().dropped
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BufferError {
+    msg: String,
+}
+
+impl From<&str> for BufferError {
+    fn from(msg: &str) -> Self {
+        BufferError {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for BufferError {
+    fn from(msg: String) -> Self {
+        BufferError { msg }
+    }
+}
+
+impl fmt::Display for BufferError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "buffer: {}", self.msg)
+    }
+}
+
+impl Error for BufferError {}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::de::IoRead;
+    use serde_json::StreamDeserializer;
+
+    use super::{Buffer, Overflow};
+
+    fn document(index: usize) -> super::Document {
+        let source = format!(
+            r#"{{"version":"2.0.0","frames":[{{"index":{},"samples":[]}}]}}"#,
+            index
+        );
+
+        // Deserialize as the reader of a source does.
+        //
+        // The strings of a document are owned, as they cannot be borrowed from
+        // a reader.
+        let mut stream = StreamDeserializer::new(IoRead::new(source.as_bytes()));
+        Ok(stream.next().unwrap().unwrap())
+    }
+
+    fn drain(buffer: &Buffer) -> Vec<usize> {
+        buffer.shared.close();
+
+        std::iter::from_fn(|| buffer.next())
+            .map(|d| serde_json::to_value(d.unwrap()).unwrap()["frames"][0]["index"].clone())
+            .map(|i| i.as_u64().unwrap() as usize)
+            .collect()
+    }
+
+    #[test]
+    fn overflow() {
+        for (overflow, expected) in [
+            (Overflow::DropOldest, [1, 2]),
+            (Overflow::DropNewest, [0, 1]),
+        ] {
+            let buffer = Buffer::new(2, overflow);
+
+            for i in 0..3 {
+                buffer.shared.push(document(i));
+            }
+
+            assert_eq!(buffer.dropped(), 1);
+            assert_eq!(drain(&buffer), expected);
+        }
+    }
+}
@@ -1,3 +1,9 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use indexmap::IndexMap;
+use serde_json::Value;
+
 use self::sample::Sample;
 
 pub mod sample;
@@ -12,8 +18,28 @@ pub mod sample;
 pub struct Frame {
     pub index: usize,
 
+    /// The time (in seconds) the frame was captured, if known.
+    pub timestamp: Option<f64>,
+
+    /// Arbitrary information about the frame (e.g., weather, scene tags).
+    pub metadata: IndexMap<String, Value>,
+
     // A mapping between the channel name and data sample
     pub samples: Vec<Sample>,
+
+    /// Whether the frame was inserted for a missing frame without an index of
+    /// its own (see [`Gaps`]), such that it shares the index of the frame
+    /// before it.
+    ///
+    /// [`Gaps`]: super::gap::Gaps
+    pub inserted: bool,
+
+    /// The index of the last frame this frame stands for.
+    ///
+    /// Consecutive duplicates of a frame may be dropped after it is searched
+    /// (e.g., online); therefore, this is shared with the copies of the frame
+    /// (e.g., of each channel), such that they are all extended.
+    until: Arc<AtomicUsize>,
 }
 
 impl Frame {
@@ -21,7 +47,44 @@ impl Frame {
     pub fn new(index: usize) -> Self {
         Frame {
             index,
+            timestamp: None,
+            metadata: IndexMap::new(),
             samples: Vec::new(),
+            inserted: false,
+            until: Arc::new(AtomicUsize::new(index)),
+        }
+    }
+
+    /// The index after the last frame this frame stands for.
+    ///
+    /// This is the end of the range of original frames that this frame covers,
+    /// which includes the duplicates of it that were dropped, if any.
+    pub fn end(&self) -> usize {
+        std::cmp::max(self.index, self.until.load(Ordering::Relaxed)) + 1
+    }
+
+    /// Extend the [`Frame`] to stand for the frames up to `index`.
+    pub fn extend(&self, index: usize) {
+        self.until.fetch_max(index, Ordering::Relaxed);
+    }
+
+    /// Create a copy of the [`Frame`] with only the samples of a channel.
+    ///
+    /// The index, timestamp, and metadata of the frame are kept, such that the
+    /// frame is a part of the sub-stream of the channel.
+    pub fn channel(&self, channel: &str) -> Frame {
+        Frame {
+            index: self.index,
+            timestamp: self.timestamp,
+            metadata: self.metadata.clone(),
+            samples: self
+                .samples
+                .iter()
+                .filter(|s| s.channel() == channel)
+                .cloned()
+                .collect(),
+            inserted: self.inserted,
+            until: Arc::clone(&self.until),
         }
     }
 }
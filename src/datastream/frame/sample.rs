@@ -1,6 +1,11 @@
+use self::classification::ClassificationRecord;
 use self::detections::DetectionRecord;
+use self::pointcloud::PointCloudRecord;
 
+pub mod classification;
 pub mod detections;
+pub mod pointcloud;
+pub mod pose;
 
 /// A kind of data captured by a channel of the perception sytem.
 ///
@@ -12,4 +17,26 @@ pub mod detections;
 pub enum Sample {
     /// A sample of object detection(s).
     ObjectDetection(DetectionRecord),
+
+    /// A sample of a LiDAR point cloud.
+    PointCloud(PointCloudRecord),
+
+    /// A sample of text detection(s) (e.g., OCR).
+    ///
+    /// Each annotation carries the recognized text of its region.
+    Text(DetectionRecord),
+
+    /// A sample of frame-level labels (e.g., weather or scene classification).
+    Classification(ClassificationRecord),
+}
+
+impl Sample {
+    /// The name of the channel that captured the sample.
+    pub fn channel(&self) -> &str {
+        match self {
+            Sample::ObjectDetection(record) | Sample::Text(record) => &record.channel,
+            Sample::PointCloud(record) => &record.channel,
+            Sample::Classification(record) => &record.channel,
+        }
+    }
 }
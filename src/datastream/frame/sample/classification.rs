@@ -0,0 +1,36 @@
+//! Frame-level classification samples.
+//!
+
+use indexmap::IndexMap;
+
+use super::detections::label::Label;
+use super::pose::Pose;
+
+/// A sample record of labels assigned to a frame as a whole.
+///
+/// Unlike a [`DetectionRecord`](super::detections::DetectionRecord), the labels
+/// are not localized to a region of the frame (e.g., weather or scene
+/// classification).
+#[derive(Clone, Debug)]
+pub struct ClassificationRecord {
+    pub channel: String,
+
+    /// The pose of the sensor, if known.
+    pub pose: Option<Pose>,
+
+    /// A mapping between labels and their confidence ("score").
+    ///
+    /// The labels are kept in the order they are inserted.
+    pub labels: IndexMap<Label, f64>,
+}
+
+impl ClassificationRecord {
+    /// Create a new [`ClassificationRecord`] without any labels.
+    pub fn new(channel: String) -> Self {
+        ClassificationRecord {
+            channel,
+            pose: None,
+            labels: IndexMap::new(),
+        }
+    }
+}
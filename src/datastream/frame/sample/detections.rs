@@ -1,9 +1,14 @@
-use std::collections::HashMap;
 use std::path::PathBuf;
 
+use indexmap::IndexMap;
+
+use self::bbox::region::cuboid;
 use self::bbox::BoundingBox;
+use self::label::Label;
+use super::pose::Pose;
 
 pub mod bbox;
+pub mod label;
 
 /// A sample record of object detections produced for a single frame.
 ///
@@ -14,8 +19,19 @@ pub struct DetectionRecord {
     pub channel: String,
     pub image: Option<Image>,
 
+    /// The pose of the sensor, if known.
+    pub pose: Option<Pose>,
+
     /// A mapping between labels and annotations (i.e., bounding boxes).
-    pub annotations: HashMap<String, Vec<Annotation>>,
+    ///
+    /// The labels are kept in the order they are inserted, such that iterating
+    /// over the annotations (e.g., when exporting) is reproducible.
+    pub annotations: IndexMap<Label, Vec<Annotation>>,
+
+    /// The images of the channels merged into this record, by channel.
+    ///
+    /// This is only populated by [`Self::merge`], see [`Self::images`].
+    pub merged: Vec<(String, Image)>,
 }
 
 impl DetectionRecord {
@@ -24,7 +40,81 @@ impl DetectionRecord {
         DetectionRecord {
             channel,
             image,
-            annotations: HashMap::new(),
+            pose: None,
+            annotations: IndexMap::new(),
+            merged: Vec::new(),
+        }
+    }
+
+    /// Retrieve the images of the [`DetectionRecord`] with their channel.
+    ///
+    /// This is its own image, or else the image of each channel merged into
+    /// it (see [`Self::merge`]).
+    pub fn images(&self) -> Vec<(&str, &Image)> {
+        match &self.image {
+            Some(image) => vec![(self.channel.as_str(), image)],
+            None => self
+                .merged
+                .iter()
+                .map(|(channel, image)| (channel.as_str(), image))
+                .collect(),
+        }
+    }
+
+    /// Perform Non-Maximum Suppression (NMS) on the annotations.
+    ///
+    /// For each label, annotations are visited from highest to lowest score. An
+    /// annotation is discarded if its Intersection over Union (IoU) with an
+    /// already kept annotation of the same label exceeds the `threshold`.
+    ///
+    /// The IoU of a 3D bounding box is not defined (see [`BoundingBox::iou`]);
+    /// therefore, cuboids are never suppressed.
+    pub fn suppress(&mut self, threshold: f64) {
+        for annotations in self.annotations.values_mut() {
+            annotations.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+            let mut kept: Vec<Annotation> = Vec::with_capacity(annotations.len());
+
+            for annotation in annotations.drain(..) {
+                let overlaps = kept.iter().any(|k| match k.bbox.iou(&annotation.bbox) {
+                    Some(iou) => iou > threshold,
+                    None => false,
+                });
+
+                if !overlaps {
+                    kept.push(annotation);
+                }
+            }
+
+            *annotations = kept;
+        }
+    }
+
+    /// Merge another [`DetectionRecord`] into this record.
+    ///
+    /// The annotations of both records are unioned into a single set, and the
+    /// channel names are joined. As the merged record no longer corresponds to
+    /// a single image (or sensor), the pose is dropped and the image of each
+    /// channel is kept by its channel instead (see [`Self::images`]).
+    pub fn merge(&mut self, other: DetectionRecord) {
+        if let Some(image) = self.image.take() {
+            self.merged.push((self.channel.clone(), image));
+        }
+
+        if let Some(image) = other.image {
+            self.merged.push((other.channel.clone(), image));
+        }
+
+        self.merged.extend(other.merged);
+
+        self.channel = format!("{}+{}", self.channel, other.channel);
+        self.pose = None;
+
+        for (label, annotations) in other.annotations.into_iter() {
+            self.annotations
+                .entry(label)
+                .or_default()
+                .extend(annotations);
         }
     }
 }
@@ -33,18 +123,95 @@ impl DetectionRecord {
 ///
 /// This fundamentally includes the label, the region, and the confidence
 /// ("score") of the resulting detection.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Annotation {
-    pub label: String,
+    pub label: Label,
     pub score: f64,
     pub bbox: BoundingBox,
+
+    /// The 3D bounding box of the same object, if known.
+    ///
+    /// Datasets such as nuScenes or Waymo pair each image-space box with a
+    /// box in the space of the vehicle (e.g., from LiDAR).
+    pub bbox3d: Option<cuboid::Region>,
+
+    /// The identifier of the tracked object instance across frames, if known.
+    pub instance_id: Option<String>,
+
+    /// The recognized text within the region, if any.
+    pub text: Option<String>,
+
+    /// Additional dataset-specific fields (e.g., visibility, activity, color).
+    pub attributes: IndexMap<String, Attribute>,
+
+    /// The motion of the tracked object since the previous frame, if known.
+    pub motion: Option<Motion>,
 }
 
 impl Annotation {
     /// Create a new [`Annotation`] with associated data.
-    pub fn new(label: String, score: f64, bbox: BoundingBox) -> Self {
-        Annotation { label, score, bbox }
+    ///
+    /// The [`Annotation`] is created without a 3D bounding box, an instance
+    /// identifier, text, any attributes, or motion.
+    pub fn new(label: Label, score: f64, bbox: BoundingBox) -> Self {
+        Annotation {
+            label,
+            score,
+            bbox,
+            bbox3d: None,
+            instance_id: None,
+            text: None,
+            attributes: IndexMap::new(),
+            motion: None,
+        }
     }
+
+    /// Retrieve the 3D bounding box of the [`Annotation`], if any.
+    ///
+    /// This is its own bounding box if a cuboid (e.g., of a LiDAR detection),
+    /// or else the 3D bounding box paired with it.
+    pub fn cuboid(&self) -> Option<&cuboid::Region> {
+        match &self.bbox {
+            BoundingBox::Cuboid(region) => Some(region),
+            _ => self.bbox3d.as_ref(),
+        }
+    }
+}
+
+/// The displacement of a tracked object between consecutive frames.
+///
+/// This is the change of the center of its bounding box from the previous
+/// frame of the data stream, if the object is within it on the same channel.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Motion {
+    pub dx: f64,
+    pub dy: f64,
+
+    /// The time (in seconds) between the frames, if both have a timestamp.
+    pub dt: Option<f64>,
+}
+
+impl Motion {
+    /// The distance moved per second, or else per frame.
+    ///
+    /// The time between frames is only known if both have a timestamp;
+    /// otherwise, the distance moved between the frames is used as is.
+    pub fn speed(&self) -> f64 {
+        let distance = self.dx.hypot(self.dy);
+
+        match self.dt {
+            Some(dt) if dt > 0.0 => distance / dt,
+            _ => distance,
+        }
+    }
+}
+
+/// A scalar attribute value of an [`Annotation`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Attribute {
+    Bool(bool),
+    Number(f64),
+    String(String),
 }
 
 /// An interface to handle image metadata.
@@ -75,4 +242,75 @@ impl Image {
 #[derive(Clone, Debug)]
 pub enum ImageSource {
     File(PathBuf),
+
+    /// The encoded contents of the image (e.g., PNG) embedded in the data.
+    Embedded(Vec<u8>),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::bbox::region::{aa, Point};
+    use super::bbox::BoundingBox;
+    use super::label::Label;
+    use super::{Annotation, DetectionRecord, Image, ImageSource};
+
+    fn annotation(label: &str, score: f64, x: f64) -> Annotation {
+        Annotation::new(
+            Label::from(label),
+            score,
+            BoundingBox::AxisAligned(aa::Region::new(Point::new(x, 0.0), 10.0, 10.0)),
+        )
+    }
+
+    #[test]
+    fn suppress_overlapping_annotations() {
+        let mut record = DetectionRecord::new(String::from("cam"), None);
+
+        record.annotations.insert(
+            Label::from("car"),
+            vec![
+                annotation("car", 0.5, 1.0),
+                annotation("car", 0.9, 0.0),
+                annotation("car", 0.7, 50.0),
+            ],
+        );
+
+        record.suppress(0.5);
+
+        let scores: Vec<f64> = record.annotations[&Label::from("car")]
+            .iter()
+            .map(|a| a.score)
+            .collect();
+        assert_eq!(scores, vec![0.9, 0.7]);
+    }
+
+    #[test]
+    fn merge_keeps_images() {
+        let image = |path: &str| Image::new(ImageSource::File(PathBuf::from(path)), 10, 10);
+
+        let mut record = DetectionRecord::new(String::from("a"), Some(image("a.png")));
+        record.merge(DetectionRecord::new(String::from("b"), None));
+        record.merge(DetectionRecord::new(
+            String::from("c"),
+            Some(image("c.png")),
+        ));
+
+        assert_eq!(record.channel, "a+b+c");
+        assert!(record.image.is_none());
+
+        let images: Vec<(&str, String)> = record
+            .images()
+            .into_iter()
+            .map(|(channel, image)| match &image.source {
+                ImageSource::File(path) => (channel, path.display().to_string()),
+                ImageSource::Embedded(..) => unreachable!(),
+            })
+            .collect();
+        assert_eq!(
+            images,
+            vec![("a", String::from("a.png")), ("c", String::from("c.png"))]
+        );
+    }
 }
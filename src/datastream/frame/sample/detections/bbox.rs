@@ -1,59 +1,473 @@
+use self::region::{polygon, Point};
+
 pub mod region;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum BoundingBox {
     /// An Axis-Aligned Bounding Box (AABB) annotation.
     AxisAligned(region::aa::Region),
 
     /// An Oriented Bounding Box (OBB) annotation.
     Oriented(region::oriented::Region),
+
+    /// A 3D bounding box (i.e., cuboid) annotation (e.g., from LiDAR).
+    ///
+    /// As a 2D region, a cuboid is its footprint on the ground plane (see
+    /// [`region::cuboid::Region::footprint`]), such that it may be compared
+    /// with the other boxes of the same (bird's-eye) space.
+    Cuboid(region::cuboid::Region),
+
+    /// A polygonal (e.g., instance segmentation) annotation.
+    Polygon(region::polygon::Region),
 }
 
 impl BoundingBox {
+    /// Compute the area of a [`BoundingBox`].
+    ///
+    /// The area of a cuboid is that of its footprint.
+    pub fn area(&self) -> f64 {
+        match self {
+            BoundingBox::AxisAligned(region) => region.area(),
+            BoundingBox::Oriented(region) => region.area(),
+            BoundingBox::Cuboid(region) => region.length * region.width,
+            BoundingBox::Polygon(region) => region.area(),
+        }
+    }
+
+    /// Compute the center of a [`BoundingBox`].
+    ///
+    /// The center of a cuboid is that of its footprint, and the center of a
+    /// polygon is its centroid.
+    pub fn center(&self) -> Point {
+        match self {
+            BoundingBox::AxisAligned(region) => region.center(),
+            BoundingBox::Oriented(region) => region.center(),
+            BoundingBox::Cuboid(region) => Point::new(region.center.x, region.center.y),
+            BoundingBox::Polygon(region) => region.centroid(),
+        }
+    }
+
+    /// Retrieve the corners of a [`BoundingBox`].
+    ///
+    /// The corners are listed in order around the boundary of the box (i.e.,
+    /// top-left, top-right, bottom-right, and bottom-left). The corners of a
+    /// polygon are its vertices.
+    pub fn corners(&self) -> Vec<Point> {
+        match self {
+            BoundingBox::AxisAligned(region) => vec![
+                Point::new(region.min.x, region.min.y),
+                Point::new(region.max.x, region.min.y),
+                Point::new(region.max.x, region.max.y),
+                Point::new(region.min.x, region.max.y),
+            ],
+            BoundingBox::Oriented(region) => vec![
+                region.tl.clone(),
+                region.tr.clone(),
+                region.br.clone(),
+                region.bl.clone(),
+            ],
+            BoundingBox::Cuboid(region) => {
+                let footprint = region.footprint();
+                vec![footprint.tl, footprint.tr, footprint.br, footprint.bl]
+            }
+            BoundingBox::Polygon(region) => region.points.clone(),
+        }
+    }
+
+    /// Convert a [`BoundingBox`] into a polygon of its corners.
+    pub fn polygon(&self) -> polygon::Region {
+        match self {
+            BoundingBox::Polygon(region) => region.clone(),
+            _ => polygon::Region::new(self.corners()),
+        }
+    }
+
+    /// Compute the Hausdorff distance of a [`BoundingBox`].
+    ///
+    /// This is the greatest distance from a point in either box to the closest
+    /// point in the other box. As both boxes are convex, it is sufficient to
+    /// only consider the corners of each box. For a concave polygon, this only
+    /// considers its vertices, and thus may be an underestimate.
+    pub fn hausdorff(&self, other: &BoundingBox) -> f64 {
+        if let Some((a, b)) = self::polygons(self, other) {
+            let ab = a.points.iter().map(|p| b.distance(p)).fold(0.0, f64::max);
+            let ba = b.points.iter().map(|p| a.distance(p)).fold(0.0, f64::max);
+
+            return f64::max(ab, ba);
+        }
+
+        let a = self.corners();
+        let b = other.corners();
+
+        let ab = a.iter().map(|p| self::distance(p, &b)).fold(0.0, f64::max);
+        let ba = b.iter().map(|p| self::distance(p, &a)).fold(0.0, f64::max);
+
+        f64::max(ab, ba)
+    }
+
+    /// Check if a [`BoundingBox`] is entirely inside this one.
+    ///
+    /// As both boxes are convex, it is sufficient to check that each corner of
+    /// the other box is inside this one. Points on the boundary are inside.
+    pub fn contains(&self, other: &BoundingBox) -> bool {
+        if let Some((a, b)) = self::polygons(self, other) {
+            return a.encloses(&b);
+        }
+
+        let polygon = self.corners();
+
+        other
+            .corners()
+            .iter()
+            .all(|point| self::inside(point, &polygon))
+    }
+
+    /// Check if a [`BoundingBox`] overlaps this one.
+    ///
+    /// As both boxes are convex, they overlap unless an axis separates them.
+    /// Boxes whose boundaries only meet also overlap. Two cuboids must also
+    /// overlap along the z-axis.
+    pub fn overlaps(&self, other: &BoundingBox) -> bool {
+        if let (BoundingBox::Cuboid(a), BoundingBox::Cuboid(b)) = (self, other) {
+            if (a.center.z - b.center.z).abs() > (a.height + b.height) / 2.0 {
+                return false;
+            }
+        }
+
+        if let Some((a, b)) = self::polygons(self, other) {
+            return a.overlaps(&b);
+        }
+
+        self::separation(&self.corners(), &other.corners()) <= 0.0
+    }
+
+    /// Check if a [`BoundingBox`] touches this one.
+    ///
+    /// The boxes touch if their boundaries are within `tolerance` of each other
+    /// while their interiors overlap by at most `tolerance`. Therefore, a zero
+    /// tolerance requires the boundaries to meet exactly. The interior of a
+    /// polygon may not overlap the other box at all.
+    pub fn touches(&self, other: &BoundingBox, tolerance: f64) -> bool {
+        if let Some((a, b)) = self::polygons(self, other) {
+            if a.intersection_area(&b) > 0.0 {
+                return false;
+            }
+
+            let gap = a
+                .points
+                .iter()
+                .map(|p| b.distance(p))
+                .chain(b.points.iter().map(|p| a.distance(p)))
+                .fold(f64::INFINITY, f64::min);
+
+            return gap <= tolerance;
+        }
+
+        let a = self.corners();
+        let b = other.corners();
+
+        let separation = self::separation(&a, &b);
+        if separation < -tolerance {
+            return false;
+        }
+
+        if separation <= 0.0 {
+            return true;
+        }
+
+        // Compute the gap between the boxes.
+        //
+        // The closest points of two disjoint convex polygons always include a
+        // corner of either one. Therefore, only corners need to be considered.
+        let gap = a
+            .iter()
+            .map(|p| self::distance(p, &b))
+            .chain(b.iter().map(|p| self::distance(p, &a)))
+            .fold(f64::INFINITY, f64::min);
+
+        gap <= tolerance
+    }
+
+    /// Compute the area of the intersection of a [`BoundingBox`].
+    ///
+    /// Similar to the IoU, the area is only computed between two 2D boxes
+    /// (i.e., Axis-Aligned or Oriented), or between a polygon and any other
+    /// box. For all other combinations, `None` is returned.
+    pub fn intersection_area(&self, other: &BoundingBox) -> Option<f64> {
+        if let Some((a, b)) = self::polygons(self, other) {
+            return Some(a.intersection_area(&b));
+        }
+
+        match (self, other) {
+            (BoundingBox::Cuboid(_), _) | (_, BoundingBox::Cuboid(_)) => None,
+            _ => Some(
+                self.intersects(other)
+                    .map(|region| region.area())
+                    .unwrap_or(0.0),
+            ),
+        }
+    }
+
+    /// Compute the area of the union of a [`BoundingBox`].
+    ///
+    /// Similar to [`BoundingBox::intersection_area`], the area is only computed
+    /// between two 2D boxes, or between a polygon and any other box.
+    pub fn union_area(&self, other: &BoundingBox) -> Option<f64> {
+        self.intersection_area(other)
+            .map(|intersection| self.area() + other.area() - intersection)
+    }
+
+    /// Compute the Intersection over Union (IoU) of a [`BoundingBox`].
+    ///
+    /// Similar to [`BoundingBox::intersection_area`], the IoU is only computed
+    /// between two 2D boxes, or between a polygon and any other box. For all
+    /// other combinations, `None` is returned.
+    pub fn iou(&self, other: &BoundingBox) -> Option<f64> {
+        let intersection = self.intersection_area(other)?;
+
+        let union = self.area() + other.area() - intersection;
+        if union <= 0.0 {
+            return Some(0.0);
+        }
+
+        Some(intersection / union)
+    }
+
     /// Compute the intersection of a [`BoundingBox`].
     ///
     /// This is a general function that attempts to compute the intersection
-    /// between different types of [`BoundingBox`] kinds, accordingly.
-    ///
-    /// TODO: Support intersection computation between two different kinds of
-    /// bounding boxes if possible (e.g., AABB and OBB).
+    /// between different types of [`BoundingBox`] kinds, accordingly. The
+    /// intersection of an Oriented Bounding Box (with either kind) is a
+    /// polygon. Cuboids and polygons are not supported (see
+    /// [`BoundingBox::overlaps`] instead), such that `None` is returned.
     pub fn intersects(&self, other: &BoundingBox) -> Option<BoundingBox> {
-        // Compute the intersection between two Axis-Aligned Bounding Boxes.
-        //
-        // This requires that both bounding boxes are AABBs in order to
-        // effectively compute the intersection.
-        if let BoundingBox::AxisAligned(a) = &self {
-            if let BoundingBox::AxisAligned(b) = &other {
-                // Compute the intersection.
-                //
-                // We first check if an intersection exists. If yes, then we
-                // return the appropriate bounding box wrapping the region.
-                if let Some(region) = a.intersects(b) {
-                    return Some(BoundingBox::AxisAligned(region));
-                }
+        match (self, other) {
+            // Compute the intersection between two Axis-Aligned Bounding Boxes.
+            //
+            // The intersection of two AABBs is itself an AABB.
+            (BoundingBox::AxisAligned(a), BoundingBox::AxisAligned(b)) => {
+                a.intersects(b).map(BoundingBox::AxisAligned)
+            }
+
+            // Compute the intersection between two Oriented Bounding Boxes.
+            (BoundingBox::Oriented(a), BoundingBox::Oriented(b)) => {
+                a.intersects(b).map(BoundingBox::Polygon)
             }
 
-            return None; // exit early
+            // Compute the intersection between an AABB and an OBB.
+            //
+            // An AABB is an OBB without rotation; therefore, both are clipped
+            // as polygons of their corners, in the same way.
+            (BoundingBox::AxisAligned(_), BoundingBox::Oriented(_))
+            | (BoundingBox::Oriented(_), BoundingBox::AxisAligned(_)) => self
+                .polygon()
+                .intersects(&other.polygon())
+                .map(BoundingBox::Polygon),
+            _ => None,
         }
+    }
+}
 
-        // Compute the intersection between two Oriented Bounding Boxes.
-        //
-        // This requires that both bounding boxes are OBBs in order to
-        // effectively compute the intersection.
-        if let BoundingBox::Oriented(a) = &self {
-            if let BoundingBox::Oriented(b) = &other {
-                // Compute the intersection.
-                //
-                // We first check if an intersection exists. If yes, then we
-                // return the appropriate bounding box wrapping the region.
-                if let Some(region) = a.intersects(b) {
-                    return Some(BoundingBox::Oriented(region));
-                }
+/// Convert a pair of boxes into polygons, if either is a polygon.
+///
+/// The operations between convex boxes do not hold for (concave) polygons;
+/// therefore, any pair with a polygon is compared as a pair of polygons.
+fn polygons(a: &BoundingBox, b: &BoundingBox) -> Option<(polygon::Region, polygon::Region)> {
+    match (a, b) {
+        (BoundingBox::Polygon(_), _) | (_, BoundingBox::Polygon(_)) => {
+            Some((a.polygon(), b.polygon()))
+        }
+        _ => None,
+    }
+}
+
+/// Compute the distance from a point to a convex polygon.
+///
+/// The polygon is given by its vertices in order around its boundary. If the
+/// point is within the polygon, the distance is zero.
+fn distance(point: &Point, polygon: &[Point]) -> f64 {
+    if self::inside(point, polygon) {
+        return 0.0;
+    }
+
+    let mut closest = f64::INFINITY;
+
+    for (i, a) in polygon.iter().enumerate() {
+        let b = &polygon[(i + 1) % polygon.len()];
+
+        // Compute the distance to the closest point of the edge.
+        let (dx, dy) = (b.x - a.x, b.y - a.y);
+        let length = dx * dx + dy * dy;
+
+        let t = if length > 0.0 {
+            (((point.x - a.x) * dx + (point.y - a.y) * dy) / length).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+
+        let (x, y) = (a.x + t * dx, a.y + t * dy);
+        closest = f64::min(closest, f64::hypot(point.x - x, point.y - y));
+    }
+
+    closest
+}
+
+/// Compute the separation between two convex polygons.
+///
+/// By the Separating Axis Theorem, the polygons are disjoint if and only if
+/// their projections onto the normal of some edge are disjoint. The largest
+/// such gap is returned, which is negative (i.e., the depth of penetration) if
+/// the interiors of the polygons overlap.
+fn separation(a: &[Point], b: &[Point]) -> f64 {
+    let mut separation = f64::NEG_INFINITY;
+
+    for polygon in [a, b] {
+        for (i, p) in polygon.iter().enumerate() {
+            let q = &polygon[(i + 1) % polygon.len()];
+
+            let (nx, ny) = (q.y - p.y, p.x - q.x);
+            let length = f64::hypot(nx, ny);
+
+            if length == 0.0 {
+                continue;
             }
 
-            return None; // exit early
+            let project = |points: &[Point]| {
+                points
+                    .iter()
+                    .map(|r| (r.x * nx + r.y * ny) / length)
+                    .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), x| {
+                        (f64::min(min, x), f64::max(max, x))
+                    })
+            };
+
+            let (amin, amax) = project(a);
+            let (bmin, bmax) = project(b);
+
+            separation = f64::max(separation, f64::max(bmin - amax, amin - bmax));
         }
+    }
+
+    separation
+}
+
+/// Check if a point is inside a convex polygon.
+///
+/// The polygon is given by its vertices in order around its boundary. The point
+/// is inside only if it lies on the same side of every edge, regardless of the
+/// winding order of the vertices.
+fn inside(point: &Point, polygon: &[Point]) -> bool {
+    let mut sides = (true, true);
+
+    for (i, a) in polygon.iter().enumerate() {
+        let b = &polygon[(i + 1) % polygon.len()];
+
+        let cross = (b.x - a.x) * (point.y - a.y) - (b.y - a.y) * (point.x - a.x);
+        sides = (sides.0 && cross >= 0.0, sides.1 && cross <= 0.0);
+    }
+
+    sides.0 || sides.1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::region::{aa, cuboid, oriented, Point, Point3};
+    use super::BoundingBox;
+    use crate::datastream::frame::sample::pose::Rotation;
+
+    #[test]
+    fn overlap_areas() {
+        let a = BoundingBox::AxisAligned(aa::Region::new(Point::new(1.0, 1.0), 2.0, 2.0));
+        let b = BoundingBox::AxisAligned(aa::Region::new(Point::new(2.0, 2.0), 2.0, 2.0));
+        let c = BoundingBox::AxisAligned(aa::Region::new(Point::new(9.0, 9.0), 2.0, 2.0));
+
+        assert_eq!(a.intersection_area(&b), Some(1.0));
+        assert_eq!(a.union_area(&b), Some(7.0));
+
+        assert_eq!(a.intersection_area(&c), Some(0.0));
+        assert_eq!(a.union_area(&c), Some(8.0));
+
+        // An OBB without rotation intersects as its AABB.
+        let d = BoundingBox::Oriented(oriented::Region::new(Point::new(2.0, 2.0), 2.0, 2.0, 0.0));
+
+        assert!(matches!(a.intersects(&d), Some(BoundingBox::Polygon(_))));
+        assert_eq!(a.intersection_area(&d), Some(1.0));
+        assert_eq!(d.iou(&a), Some(1.0 / 7.0));
+        assert!(c.intersects(&d).is_none());
+    }
+
+    #[test]
+    fn hausdorff() {
+        let a = BoundingBox::AxisAligned(aa::Region::new(Point::new(0.0, 0.0), 2.0, 2.0));
+        let b = BoundingBox::AxisAligned(aa::Region::new(Point::new(0.0, 0.0), 4.0, 2.0));
+        let c = BoundingBox::AxisAligned(aa::Region::new(Point::new(3.0, 4.0), 2.0, 2.0));
+
+        assert_eq!(a.hausdorff(&a), 0.0);
+        assert_eq!(a.hausdorff(&b), 1.0);
+        assert_eq!(b.hausdorff(&a), 1.0);
+        assert_eq!(a.hausdorff(&c), 5.0);
+    }
+
+    #[test]
+    fn adjacency() {
+        let a = BoundingBox::AxisAligned(aa::Region::new(Point::new(0.0, 0.0), 2.0, 2.0));
+        let b = BoundingBox::AxisAligned(aa::Region::new(Point::new(2.0, 0.0), 2.0, 2.0));
+        let c = BoundingBox::AxisAligned(aa::Region::new(Point::new(2.5, 0.0), 2.0, 2.0));
+        let d = BoundingBox::AxisAligned(aa::Region::new(Point::new(1.5, 0.0), 2.0, 2.0));
+
+        assert!(a.touches(&b, 0.0));
+        assert!(!a.touches(&c, 0.0));
+        assert!(a.touches(&c, 0.5));
+        assert!(!a.touches(&d, 0.0));
+        assert!(a.touches(&d, 0.5));
+        assert!(!a.touches(&a, 0.5));
+    }
+
+    #[test]
+    fn containment() {
+        let a = BoundingBox::AxisAligned(aa::Region::new(Point::new(0.0, 0.0), 2.0, 2.0));
+        let b = BoundingBox::AxisAligned(aa::Region::new(Point::new(0.0, 0.0), 4.0, 2.0));
+        let c = BoundingBox::Oriented(oriented::Region::new(
+            Point::new(0.0, 0.0),
+            2.0,
+            2.0,
+            std::f64::consts::FRAC_PI_4,
+        ));
+
+        assert!(b.contains(&a));
+        assert!(!a.contains(&b));
+
+        assert!(!a.contains(&c));
+        assert!(!b.contains(&c));
+        assert!(c.contains(&BoundingBox::AxisAligned(aa::Region::new(
+            Point::new(0.0, 0.0),
+            1.0,
+            1.0
+        ))));
+    }
+
+    #[test]
+    fn cuboids() {
+        let cuboid = |x: f64, z: f64| {
+            BoundingBox::Cuboid(cuboid::Region::new(
+                Point3::new(x, 0.0, z),
+                2.0,
+                1.5,
+                4.0,
+                Rotation::default(),
+            ))
+        };
+
+        let a = cuboid(0.0, 0.0);
 
-        None
+        assert_eq!(a.area(), 8.0);
+        assert!(a.overlaps(&cuboid(3.0, 1.0)));
+        assert!(!a.overlaps(&cuboid(3.0, 2.0)));
+        assert!(!a.overlaps(&cuboid(5.0, 0.0)));
+        assert!(a.contains(&BoundingBox::AxisAligned(aa::Region::new(
+            Point::new(0.0, 0.0),
+            4.0,
+            2.0
+        ))));
     }
 }
@@ -1,8 +1,10 @@
 pub mod aa;
+pub mod cuboid;
 pub mod oriented;
+pub mod polygon;
 
 /// A Z axis-aligned point (i.e., 2D).
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Point {
     pub x: f64,
     pub y: f64,
@@ -14,3 +16,18 @@ impl Point {
         Point { x, y }
     }
 }
+
+/// A point in three dimensions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Point3 {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Point3 {
+    /// Create a new [`Point3`] with (x, y, z) coordinates.
+    pub fn new(x: f64, y: f64, z: f64) -> Self {
+        Point3 { x, y, z }
+    }
+}
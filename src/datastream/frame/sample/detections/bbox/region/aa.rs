@@ -4,7 +4,7 @@ use super::Point;
 ///
 /// The selected representation of the region uses the major and minor coordinates
 /// (i.e., the corners) to represent the rectangle.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Region {
     pub min: Point,
     pub max: Point,
@@ -43,6 +43,12 @@ impl Region {
         self.max.y - self.min.y
     }
 
+    /// Compute the area of the Axis-Aligned region.
+    #[inline]
+    pub fn area(&self) -> f64 {
+        self.width() * self.height()
+    }
+
     /// Compute the intersection of a [`Region`].
     ///
     /// This computes the intersection between two Axis-Aligned regions,
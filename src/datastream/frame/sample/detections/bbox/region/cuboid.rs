@@ -0,0 +1,83 @@
+use super::oriented;
+use super::{Point, Point3};
+use crate::datastream::frame::sample::pose::Rotation;
+
+/// A Cuboid Region.
+///
+/// The selected representation of the region uses the center point, the
+/// dimensions, and the orientation of the box in three dimensions. The length
+/// lies along the heading (i.e., the yaw) of the box, the width across it, and
+/// the height along the z-axis.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Region {
+    pub center: Point3,
+    pub width: f64,
+    pub height: f64,
+    pub length: f64,
+    pub rotation: Rotation,
+}
+
+impl Region {
+    /// Create a new [`Region`].
+    pub fn new(center: Point3, width: f64, height: f64, length: f64, rotation: Rotation) -> Self {
+        Region {
+            center,
+            width,
+            height,
+            length,
+            rotation,
+        }
+    }
+
+    /// Compute the volume of the Cuboid region.
+    #[inline]
+    pub fn volume(&self) -> f64 {
+        self.width * self.height * self.length
+    }
+
+    /// Compute the footprint of the Cuboid region.
+    ///
+    /// This is the region covered by the box on the ground (i.e., x-y) plane,
+    /// as seen from above. Only the yaw is considered, as the pitch and roll
+    /// of objects on the ground are negligible.
+    pub fn footprint(&self) -> oriented::Region {
+        oriented::Region::new(
+            Point::new(self.center.x, self.center.y),
+            self.length,
+            self.width,
+            self.rotation.yaw,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::datastream::frame::sample::detections::bbox::region::{Point, Point3};
+    use crate::datastream::frame::sample::pose::Rotation;
+
+    use super::Region;
+
+    #[test]
+    fn footprint() {
+        let region = Region::new(
+            Point3::new(1.0, 2.0, 0.5),
+            2.0,
+            1.5,
+            4.0,
+            Rotation {
+                yaw: std::f64::consts::FRAC_PI_2,
+                pitch: 0.0,
+                roll: 0.0,
+            },
+        );
+
+        let footprint = region.footprint();
+
+        assert_eq!(region.volume(), 12.0);
+        assert_eq!(footprint.center(), Point::new(1.0, 2.0));
+        assert!((footprint.width() - 4.0).abs() < 1e-9);
+        assert!((footprint.height() - 2.0).abs() < 1e-9);
+        assert!((footprint.tl.x - 2.0).abs() < 1e-9);
+        assert!((footprint.tl.y - 0.0).abs() < 1e-9);
+    }
+}
@@ -1,10 +1,10 @@
-use super::Point;
+use super::{polygon, Point};
 
 /// An Oriented Region.
 ///
 /// The selected representation of the region uses the four coordinates of the
 /// region (i.e., the corners) to represent the rectangle.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Region {
     pub tl: Point,
     pub tr: Point,
@@ -63,6 +63,12 @@ impl Region {
         f64::sqrt((self.tl.x - self.bl.x).powi(2) + (self.tl.y - self.bl.y).powi(2))
     }
 
+    /// Compute the area of the Oriented region.
+    #[inline]
+    pub fn area(&self) -> f64 {
+        self.width() * self.height()
+    }
+
     /// Compute the rotation of the Oriented region.
     #[inline]
     pub fn rotation(&self) -> f64 {
@@ -71,11 +77,21 @@ impl Region {
 
     /// Compute the intersection of a [`Region`].
     ///
-    /// This computes the intersection between two Oriented regions,
-    /// accordingly
-    #[allow(unused_variables)]
-    pub fn intersects(&self, other: &Region) -> Option<Region> {
-        unimplemented!("support for intersection of oriented regions coming soon!")
+    /// This computes the intersection between two Oriented regions, which is
+    /// a convex polygon of up to eight corners rather than an Oriented region
+    /// itself (unless both are aligned with each other).
+    pub fn intersects(&self, other: &Region) -> Option<polygon::Region> {
+        self.polygon().intersects(&other.polygon())
+    }
+
+    /// Convert the Oriented region into a polygon of its corners.
+    pub fn polygon(&self) -> polygon::Region {
+        polygon::Region::new(vec![
+            self.tl.clone(),
+            self.tr.clone(),
+            self.br.clone(),
+            self.bl.clone(),
+        ])
     }
 }
 
@@ -95,4 +111,19 @@ mod tests {
         assert_eq!(region.height(), 10.0);
         assert_eq!(region.rotation(), 0.0);
     }
+
+    #[test]
+    fn intersection() {
+        let a = Region::new(Point::new(0.0, 0.0), 2.0, 2.0, 0.0);
+        let b = Region::new(Point::new(0.0, 0.0), 2.0, 2.0, std::f64::consts::FRAC_PI_4);
+        let c = Region::new(Point::new(5.0, 0.0), 2.0, 2.0, std::f64::consts::FRAC_PI_4);
+
+        // The intersection is a regular octagon.
+        let octagon = a.intersects(&b).unwrap();
+
+        assert_eq!(octagon.points.len(), 8);
+        assert!((octagon.area() - (8.0 * (f64::sqrt(2.0) - 1.0))).abs() < 1e-9);
+
+        assert!(a.intersects(&c).is_none());
+    }
 }
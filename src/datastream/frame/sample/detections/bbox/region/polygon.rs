@@ -0,0 +1,385 @@
+use super::Point;
+
+/// A Polygonal Region.
+///
+/// The selected representation of the region uses the vertices of a simple
+/// polygon (i.e., one whose edges do not cross) in order around its boundary,
+/// such as the outline of a segmentation mask. Unlike the other regions, the
+/// polygon may be concave.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Region {
+    pub points: Vec<Point>,
+}
+
+impl Region {
+    /// Create a new [`Region`].
+    pub fn new(points: Vec<Point>) -> Self {
+        Region { points }
+    }
+
+    /// Compute the area of the Polygonal region.
+    #[inline]
+    pub fn area(&self) -> f64 {
+        self::signed(&self.points).abs()
+    }
+
+    /// Compute the centroid of the Polygonal region.
+    ///
+    /// This is the center of mass of the region, which is not necessarily
+    /// within a concave region. A region without an area (e.g., a line) has
+    /// the mean of its vertices as its centroid instead.
+    pub fn centroid(&self) -> Point {
+        let area = self::signed(&self.points);
+
+        if area == 0.0 {
+            let n = self.points.len().max(1) as f64;
+
+            return Point::new(
+                self.points.iter().map(|p| p.x).sum::<f64>() / n,
+                self.points.iter().map(|p| p.y).sum::<f64>() / n,
+            );
+        }
+
+        let (mut x, mut y) = (0.0, 0.0);
+
+        for (a, b) in self.edges() {
+            let cross = (a.x * b.y) - (b.x * a.y);
+
+            x += (a.x + b.x) * cross;
+            y += (a.y + b.y) * cross;
+        }
+
+        Point::new(x / (6.0 * area), y / (6.0 * area))
+    }
+
+    /// Check if a [`Point`] is inside the Polygonal region.
+    ///
+    /// This counts the crossings of a ray from the point with the boundary
+    /// (i.e., the even-odd rule), such that concave regions are supported.
+    /// Points on the boundary are inside.
+    pub fn contains(&self, point: &Point) -> bool {
+        let mut inside = false;
+
+        for (a, b) in self.edges() {
+            if self::touches(point, a, b) {
+                return true;
+            }
+
+            if (a.y > point.y) != (b.y > point.y) {
+                let x = a.x + ((point.y - a.y) * (b.x - a.x) / (b.y - a.y));
+
+                if point.x < x {
+                    inside = !inside;
+                }
+            }
+        }
+
+        inside
+    }
+
+    /// Check if a [`Region`] is entirely inside this one.
+    ///
+    /// Each vertex of the other region must be inside this one; moreover, as
+    /// this region may be concave, no edge of the other region may cross its
+    /// boundary.
+    pub fn encloses(&self, other: &Region) -> bool {
+        other.points.iter().all(|p| self.contains(p))
+            && !self
+                .edges()
+                .any(|(a, b)| other.edges().any(|(c, d)| self::crosses(a, b, c, d)))
+    }
+
+    /// Check if a [`Region`] overlaps this one.
+    ///
+    /// The regions overlap if their boundaries meet, or if either is entirely
+    /// inside the other.
+    pub fn overlaps(&self, other: &Region) -> bool {
+        self.edges()
+            .any(|(a, b)| other.edges().any(|(c, d)| self::meets(a, b, c, d)))
+            || other.points.first().is_some_and(|p| self.contains(p))
+            || self.points.first().is_some_and(|p| other.contains(p))
+    }
+
+    /// Compute the distance from a [`Point`] to the Polygonal region.
+    ///
+    /// If the point is within the region, the distance is zero.
+    pub fn distance(&self, point: &Point) -> f64 {
+        if self.contains(point) {
+            return 0.0;
+        }
+
+        self.edges()
+            .map(|(a, b)| {
+                // Compute the distance to the closest point of the edge.
+                let (dx, dy) = (b.x - a.x, b.y - a.y);
+                let length = dx * dx + dy * dy;
+
+                let t = if length > 0.0 {
+                    (((point.x - a.x) * dx + (point.y - a.y) * dy) / length).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+
+                f64::hypot(point.x - (a.x + t * dx), point.y - (a.y + t * dy))
+            })
+            .fold(f64::INFINITY, f64::min)
+    }
+
+    /// Compute the area of the intersection of a [`Region`].
+    ///
+    /// The other region is split into triangles, and this region is clipped
+    /// to each of them in turn. As the triangles cover the other region
+    /// exactly, the areas of the clipped regions sum to that of the
+    /// intersection, even if both regions are concave.
+    pub fn intersection_area(&self, other: &Region) -> f64 {
+        let mut subject = self.points.clone();
+
+        if self::signed(&subject) < 0.0 {
+            subject.reverse();
+        }
+
+        other
+            .triangles()
+            .iter()
+            .map(|triangle| self::signed(&self::clip(&subject, triangle)).abs())
+            .sum()
+    }
+
+    /// Compute the intersection of a convex [`Region`].
+    ///
+    /// This region is clipped to the other, which must be convex (e.g., a
+    /// box). If this region is also convex, so is the intersection. Regions
+    /// whose boundaries only meet do not intersect.
+    pub fn intersects(&self, convex: &Region) -> Option<Region> {
+        let mut subject = self.points.clone();
+        let mut convex = convex.points.clone();
+
+        for points in [&mut subject, &mut convex] {
+            if self::signed(points) < 0.0 {
+                points.reverse();
+            }
+        }
+
+        let region = Region::new(self::clip(&subject, &convex));
+
+        match region.area() > 0.0 {
+            true => Some(region),
+            false => None,
+        }
+    }
+
+    /// Compute the area of the union of a [`Region`].
+    pub fn union_area(&self, other: &Region) -> f64 {
+        self.area() + other.area() - self.intersection_area(other)
+    }
+
+    /// The edges of the boundary, in order.
+    fn edges(&self) -> impl Iterator<Item = (&Point, &Point)> {
+        let n = self.points.len();
+
+        (0..n).map(move |i| (&self.points[i], &self.points[(i + 1) % n]))
+    }
+
+    /// Split the Polygonal region into triangles.
+    ///
+    /// This clips the "ears" of the region (i.e., triangles of consecutive
+    /// vertices that contain no other vertex) until a single triangle remains.
+    /// Each triangle is counterclockwise.
+    fn triangles(&self) -> Vec<[Point; 3]> {
+        let mut points = self.points.clone();
+
+        if self::signed(&points) < 0.0 {
+            points.reverse();
+        }
+
+        let mut indices: Vec<usize> = (0..points.len()).collect();
+        let mut triangles = Vec::new();
+
+        while indices.len() > 3 {
+            let n = indices.len();
+
+            let ear = (0..n).find(|&i| {
+                let (a, b, c) = (indices[(i + n - 1) % n], indices[i], indices[(i + 1) % n]);
+
+                let cross = self::cross(&points[a], &points[b], &points[c]);
+
+                // A vertex along a straight edge is clipped without a triangle.
+                if cross == 0.0 {
+                    return true;
+                }
+
+                cross > 0.0
+                    && indices.iter().all(|&j| {
+                        j == a
+                            || j == b
+                            || j == c
+                            || !self::within(&points[j], &points[a], &points[b], &points[c])
+                    })
+            });
+
+            // Stop clipping if the region is not simple.
+            //
+            // The remainder is split as a fan from its first vertex instead,
+            // which is exact for convex remainders.
+            let Some(i) = ear else {
+                break;
+            };
+
+            let (a, b, c) = (indices[(i + n - 1) % n], indices[i], indices[(i + 1) % n]);
+
+            if self::cross(&points[a], &points[b], &points[c]) != 0.0 {
+                triangles.push([points[a].clone(), points[b].clone(), points[c].clone()]);
+            }
+
+            indices.remove(i);
+        }
+
+        for k in 1..indices.len().saturating_sub(1) {
+            triangles.push([
+                points[indices[0]].clone(),
+                points[indices[k]].clone(),
+                points[indices[k + 1]].clone(),
+            ]);
+        }
+
+        triangles
+    }
+}
+
+/// Compute the signed area of a polygon.
+///
+/// The area is positive if the vertices are counterclockwise (i.e., with the
+/// y-axis pointing up), and negative otherwise.
+fn signed(points: &[Point]) -> f64 {
+    let n = points.len();
+
+    (0..n)
+        .map(|i| {
+            let (a, b) = (&points[i], &points[(i + 1) % n]);
+            (a.x * b.y) - (b.x * a.y)
+        })
+        .sum::<f64>()
+        / 2.0
+}
+
+/// Compute the cross product of the vectors from `a` to `b` and `a` to `c`.
+///
+/// This is positive if `c` is to the left of the line from `a` to `b`,
+/// negative if to the right, and zero if on the line.
+fn cross(a: &Point, b: &Point, c: &Point) -> f64 {
+    ((b.x - a.x) * (c.y - a.y)) - ((b.y - a.y) * (c.x - a.x))
+}
+
+/// Check if a point is on the segment from `a` to `b`.
+fn touches(p: &Point, a: &Point, b: &Point) -> bool {
+    self::cross(a, b, p) == 0.0
+        && p.x >= f64::min(a.x, b.x)
+        && p.x <= f64::max(a.x, b.x)
+        && p.y >= f64::min(a.y, b.y)
+        && p.y <= f64::max(a.y, b.y)
+}
+
+/// Check if the segments from `a` to `b` and `c` to `d` meet at any point.
+fn meets(a: &Point, b: &Point, c: &Point, d: &Point) -> bool {
+    self::crosses(a, b, c, d)
+        || self::touches(c, a, b)
+        || self::touches(d, a, b)
+        || self::touches(a, c, d)
+        || self::touches(b, c, d)
+}
+
+/// Check if the segments from `a` to `b` and `c` to `d` cross.
+///
+/// The segments cross only if they meet at a single point within both (i.e.,
+/// not at an endpoint of either).
+fn crosses(a: &Point, b: &Point, c: &Point, d: &Point) -> bool {
+    let (abc, abd) = (self::cross(a, b, c), self::cross(a, b, d));
+    let (cda, cdb) = (self::cross(c, d, a), self::cross(c, d, b));
+
+    ((abc > 0.0 && abd < 0.0) || (abc < 0.0 && abd > 0.0))
+        && ((cda > 0.0 && cdb < 0.0) || (cda < 0.0 && cdb > 0.0))
+}
+
+/// Check if a point is within a counterclockwise triangle (or on its boundary).
+fn within(p: &Point, a: &Point, b: &Point, c: &Point) -> bool {
+    self::cross(a, b, p) >= 0.0 && self::cross(b, c, p) >= 0.0 && self::cross(c, a, p) >= 0.0
+}
+
+/// Clip a counterclockwise polygon to a convex counterclockwise polygon.
+///
+/// This keeps the part of the polygon on the inner side of each edge of the
+/// convex polygon in turn (i.e., the Sutherland-Hodgman algorithm). The polygon
+/// may be concave, in which case its parts may be joined along the boundary of
+/// the convex polygon; however, the joins have no area.
+fn clip(polygon: &[Point], convex: &[Point]) -> Vec<Point> {
+    let mut output = polygon.to_vec();
+
+    for i in 0..convex.len() {
+        let (a, b) = (&convex[i], &convex[(i + 1) % convex.len()]);
+        let input = std::mem::take(&mut output);
+
+        for (j, p) in input.iter().enumerate() {
+            let q = &input[(j + 1) % input.len()];
+            let (sp, sq) = (self::cross(a, b, p), self::cross(a, b, q));
+
+            if sp >= 0.0 {
+                output.push(p.clone());
+            }
+
+            if (sp >= 0.0) != (sq >= 0.0) {
+                let t = sp / (sp - sq);
+                output.push(Point::new(p.x + t * (q.x - p.x), p.y + t * (q.y - p.y)));
+            }
+        }
+
+        if output.is_empty() {
+            break;
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::datastream::frame::sample::detections::bbox::region::Point;
+
+    use super::Region;
+
+    fn region(points: &[(f64, f64)]) -> Region {
+        Region::new(points.iter().map(|&(x, y)| Point::new(x, y)).collect())
+    }
+
+    #[test]
+    fn concave_operations() {
+        // An L-shaped region of area 3, in clockwise order.
+        let a = region(&[
+            (0.0, 0.0),
+            (0.0, 2.0),
+            (2.0, 2.0),
+            (2.0, 1.0),
+            (1.0, 1.0),
+            (1.0, 0.0),
+        ]);
+        let b = region(&[(1.0, 0.0), (3.0, 0.0), (3.0, 2.0), (1.0, 2.0)]);
+        let c = region(&[(1.5, 0.2), (1.8, 0.2), (1.8, 0.8), (1.5, 0.8)]);
+
+        assert_eq!(a.area(), 3.0);
+        assert_eq!(a.centroid(), Point::new(5.0 / 6.0, 7.0 / 6.0));
+
+        assert!(a.contains(&Point::new(0.5, 0.5)));
+        assert!(!a.contains(&Point::new(1.5, 0.5)));
+        assert!(a.contains(&Point::new(1.0, 0.5)));
+
+        assert_eq!(a.intersection_area(&b), 1.0);
+        assert_eq!(b.intersection_area(&a), 1.0);
+        assert_eq!(a.union_area(&b), 6.0);
+
+        assert!(a.overlaps(&b));
+        assert!(!a.overlaps(&c));
+        assert_eq!(a.intersection_area(&c), 0.0);
+
+        assert!(b.encloses(&c));
+        assert!(!a.encloses(&region(&[(0.2, 0.2), (0.2, 1.8), (1.8, 1.8), (1.8, 0.2)])));
+    }
+}
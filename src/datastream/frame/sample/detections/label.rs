@@ -0,0 +1,188 @@
+//! Interned class labels.
+//!
+//! The same few class labels are repeated across every annotation of a
+//! datastream. Therefore, each unique label is stored once and shared by
+//! reference, which makes comparing and hashing labels cheap.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, OnceLock, RwLock, Weak};
+
+/// The maximum number of unique labels that may be interned at once.
+///
+/// A label is reclaimed once it is no longer referred to; nonetheless, a
+/// long-running process (e.g., a server) fed with ever-changing class names
+/// in a single request would otherwise grow without bound.
+pub const MAX_LABELS: usize = 1 << 16;
+
+/// The global table of interned labels.
+static INTERNER: OnceLock<RwLock<Interner>> = OnceLock::new();
+
+#[derive(Default)]
+struct Interner {
+    names: HashMap<Box<str>, Weak<str>>,
+}
+
+impl Interner {
+    /// Look up a name that is still referred to.
+    fn get(&self, name: &str) -> Option<Label> {
+        self.names.get(name).and_then(Weak::upgrade).map(Label)
+    }
+
+    /// Look up or store a name, unless `max` names are already referred to.
+    fn intern(&mut self, name: &str, max: usize) -> Result<Label, LabelError> {
+        if let Some(label) = self.get(name) {
+            return Ok(label);
+        }
+
+        if self.names.len() >= max {
+            // Reclaim the names no longer referred to.
+            self.names.retain(|_, name| name.strong_count() > 0);
+        }
+
+        if self.names.len() >= max {
+            return Err(LabelError::from(format!(
+                "`{}`: at most {} unique labels are supported",
+                name, max
+            )));
+        }
+
+        let label: Arc<str> = Arc::from(name);
+        self.names.insert(Box::from(name), Arc::downgrade(&label));
+
+        Ok(Label(label))
+    }
+}
+
+/// An interned class label.
+///
+/// Labels with the same name share the same storage, which is reclaimed once
+/// the last [`Label`] referring to it is dropped. At most [`MAX_LABELS`] may
+/// be interned at once.
+#[derive(Clone)]
+pub struct Label(Arc<str>);
+
+impl Label {
+    /// Intern a label by its name.
+    ///
+    /// If the name has already been interned, the existing [`Label`] is
+    /// returned. An error is returned if the name is new and [`MAX_LABELS`]
+    /// are already interned.
+    pub fn intern(name: &str) -> Result<Self, LabelError> {
+        let interner = INTERNER.get_or_init(Default::default);
+
+        if let Some(label) = interner.read().unwrap().get(name) {
+            return Ok(label);
+        }
+
+        // Another thread may have interned the same name between releasing the
+        // read lock and acquiring the write lock, which is checked again.
+        interner.write().unwrap().intern(name, MAX_LABELS)
+    }
+
+    /// Retrieve the name of the label.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq for Label {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Label {}
+
+impl Hash for Label {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::ptr::hash(Arc::as_ptr(&self.0) as *const u8, state)
+    }
+}
+
+impl From<&str> for Label {
+    /// Intern a label known ahead of time.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the label cannot be interned (see [`Label::intern`]).
+    /// Therefore, labels read from external data should be interned through
+    /// [`Label::intern`] instead.
+    fn from(name: &str) -> Self {
+        Label::intern(name).unwrap()
+    }
+}
+
+impl fmt::Display for Label {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl fmt::Debug for Label {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.as_str())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LabelError {
+    msg: String,
+}
+
+impl From<&str> for LabelError {
+    fn from(msg: &str) -> Self {
+        LabelError {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for LabelError {
+    fn from(msg: String) -> Self {
+        LabelError { msg }
+    }
+}
+
+impl fmt::Display for LabelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "label: {}", self.msg)
+    }
+}
+
+impl Error for LabelError {}
+
+#[cfg(test)]
+mod tests {
+    use super::{Interner, Label};
+
+    #[test]
+    fn intern() {
+        let car = Label::intern("car").unwrap();
+
+        assert_eq!(car, Label::intern("car").unwrap());
+        assert_ne!(car, Label::intern("truck").unwrap());
+        assert_eq!(car.as_str(), "car");
+    }
+
+    #[test]
+    fn limit() {
+        let mut interner = Interner::default();
+
+        let car = interner.intern("car", 2).unwrap();
+        let truck = interner.intern("truck", 2).unwrap();
+
+        assert!(interner.intern("bus", 2).is_err());
+        assert_eq!(car, interner.intern("car", 2).unwrap());
+
+        // Labels no longer referred to are reclaimed.
+        drop(truck);
+
+        let bus = interner.intern("bus", 2).unwrap();
+
+        assert_eq!(bus.as_str(), "bus");
+        assert_ne!(bus, car);
+    }
+}
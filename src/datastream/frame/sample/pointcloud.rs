@@ -0,0 +1,52 @@
+//! LiDAR point-cloud samples.
+//!
+
+use super::detections::label::Label;
+use super::pose::Pose;
+
+/// A sample record of a point cloud captured for a single frame.
+///
+/// The points themselves are not loaded. Instead, the record refers to where
+/// the points are stored along with a summary of them, such that frames may
+/// carry LiDAR data alongside camera detections.
+#[derive(Clone, Debug)]
+pub struct PointCloudRecord {
+    pub channel: String,
+
+    /// The path (or URI) of the stored points.
+    pub path: String,
+
+    /// The total number of points.
+    pub points: usize,
+
+    /// The pose of the sensor, if known.
+    pub pose: Option<Pose>,
+
+    /// The number of points within each bounding box, if known.
+    pub statistics: Vec<PointStatistics>,
+}
+
+impl PointCloudRecord {
+    /// Create a new [`PointCloudRecord`] without any statistics.
+    pub fn new(channel: String, path: String, points: usize) -> Self {
+        PointCloudRecord {
+            channel,
+            path,
+            points,
+            pose: None,
+            statistics: Vec::new(),
+        }
+    }
+}
+
+/// A summary of the points within a single bounding box.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PointStatistics {
+    pub label: Label,
+
+    /// The identifier of the tracked object instance, if known.
+    pub instance_id: Option<String>,
+
+    /// The number of points within the bounding box.
+    pub points: usize,
+}
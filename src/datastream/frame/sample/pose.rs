@@ -0,0 +1,44 @@
+//! Sensor poses.
+//!
+
+/// The pose of a sensor (i.e., its extrinsics).
+///
+/// The pose relates the coordinate frame of the sensor to a common reference
+/// frame (e.g., the ego vehicle), such that the samples of several sensors may
+/// be related geometrically.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pose {
+    /// The position of the sensor.
+    pub translation: Translation,
+
+    /// The orientation of the sensor.
+    pub rotation: Rotation,
+}
+
+impl Pose {
+    /// Create a new [`Pose`].
+    pub fn new(translation: Translation, rotation: Rotation) -> Self {
+        Pose {
+            translation,
+            rotation,
+        }
+    }
+}
+
+/// A position in three dimensions.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Translation {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+/// An orientation in three dimensions.
+///
+/// The angles are in radians.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Rotation {
+    pub yaw: f64,
+    pub pitch: f64,
+    pub roll: f64,
+}
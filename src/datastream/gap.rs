@@ -0,0 +1,425 @@
+//! Detection and handling of missing frames.
+//!
+//! A data stream may be missing frames (e.g., dropped by a recorder). As the
+//! temporal operators of a pattern count frames, a gap silently shortens the
+//! time a match spans. A gap is detected from the indices of the frames or,
+//! if the indices are contiguous, from their timestamps.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use super::frame::sample::detections::bbox::region::{aa, cuboid, oriented, Point, Point3};
+use super::frame::sample::detections::bbox::BoundingBox;
+use super::frame::sample::detections::{Annotation, DetectionRecord};
+use super::frame::sample::pose::Rotation;
+use super::frame::sample::Sample;
+use super::frame::Frame;
+
+/// The handling of a gap in a data stream.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Policy {
+    /// Search across the gap as if no frames were missing.
+    #[default]
+    Ignore,
+
+    /// Insert a frame without any samples for each missing frame.
+    Empty,
+
+    /// Insert a frame for each missing frame, where the annotations of each
+    /// tracked object are linearly interpolated between the frames around the
+    /// gap.
+    Interpolate,
+
+    /// Break the data stream at the gap, such that no match spans across it.
+    Break,
+}
+
+impl FromStr for Policy {
+    type Err = GapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ignore" => Ok(Policy::Ignore),
+            "empty" => Ok(Policy::Empty),
+            "interpolate" => Ok(Policy::Interpolate),
+            "break" => Ok(Policy::Break),
+            _ => Err(GapError::from(format!(
+                "unknown policy `{}` (expected `ignore`, `empty`, `interpolate`, or `break`)",
+                s
+            ))),
+        }
+    }
+}
+
+/// A detector of gaps that handles each by a [`Policy`].
+///
+/// The frames of a data stream may be imported in batches (e.g., when
+/// searching online); therefore, the last frame of each batch is kept to
+/// detect a gap at the start of the next batch.
+#[derive(Clone, Debug)]
+pub struct Gaps {
+    policy: Policy,
+
+    /// The last frame seen.
+    previous: Option<Frame>,
+
+    /// The shortest time between two frames seen so far.
+    period: Option<f64>,
+
+    /// The number of gaps found.
+    count: usize,
+
+    /// The number of frames missing from all gaps.
+    missing: usize,
+}
+
+impl Gaps {
+    /// Create a new [`Gaps`] handled by the [`Policy`].
+    pub fn new(policy: Policy) -> Self {
+        Gaps {
+            policy,
+            previous: None,
+            period: None,
+            count: 0,
+            missing: 0,
+        }
+    }
+
+    /// The number of gaps found so far.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// The number of frames missing from all gaps found so far.
+    pub fn missing(&self) -> usize {
+        self.missing
+    }
+
+    /// Handle the gaps of a batch of [`Frame`].
+    ///
+    /// The frames are returned as one or more segments, where each segment
+    /// after the first begins after a gap. Therefore, unless the [`Policy`] is
+    /// to break the data stream, only a single segment is returned. If a batch
+    /// begins after a gap, the first segment is empty.
+    pub fn fill(&mut self, frames: Vec<Frame>) -> Vec<Vec<Frame>> {
+        let mut segments = vec![Vec::new()];
+
+        for frame in frames {
+            if let Some(previous) = self.previous.take() {
+                let missing = self.measure(&previous, &frame);
+
+                if missing > 0 {
+                    self.count += 1;
+                    self.missing += missing;
+
+                    match self.policy {
+                        Policy::Ignore => {}
+                        Policy::Empty | Policy::Interpolate => {
+                            let segment = segments.last_mut().unwrap();
+
+                            for j in 1..=missing {
+                                let t = j as f64 / (missing + 1) as f64;
+                                segment.push(self.insert(&previous, &frame, t, j));
+                            }
+                        }
+                        Policy::Break => segments.push(Vec::new()),
+                    }
+                }
+            }
+
+            self.previous = Some(frame.clone());
+            segments.last_mut().unwrap().push(frame);
+        }
+
+        segments
+    }
+
+    /// Measure the number of frames missing between two frames.
+    ///
+    /// The indices are used if they are not contiguous. Otherwise, the time
+    /// between the frames is compared against the shortest time seen so far.
+    fn measure(&mut self, previous: &Frame, next: &Frame) -> usize {
+        let missing = next.index.saturating_sub(previous.index + 1);
+
+        if missing > 0 {
+            return missing;
+        }
+
+        let (Some(a), Some(b)) = (previous.timestamp, next.timestamp) else {
+            return 0;
+        };
+
+        let dt = b - a;
+
+        if dt <= 0.0 {
+            return 0;
+        }
+
+        match self.period {
+            Some(period) if dt < period => {
+                self.period = Some(dt);
+                0
+            }
+            Some(period) => ((dt / period).round() as usize).saturating_sub(1),
+            None => {
+                self.period = Some(dt);
+                0
+            }
+        }
+    }
+
+    /// Create the `j`-th missing frame at `t` of the way between two frames.
+    ///
+    /// If the indices of the frames are contiguous (i.e., the gap was found
+    /// from their timestamps), the inserted frame shares the index of the
+    /// frame before the gap, and is marked as such (see [`Frame::inserted`]).
+    fn insert(&self, previous: &Frame, next: &Frame, t: f64, j: usize) -> Frame {
+        let contiguous = next.index <= previous.index + 1;
+
+        let mut frame = match contiguous {
+            true => Frame::new(previous.index),
+            false => Frame::new(previous.index + j),
+        };
+        frame.inserted = contiguous;
+
+        if let (Some(a), Some(b)) = (previous.timestamp, next.timestamp) {
+            frame.timestamp = Some(lerp(a, b, t));
+        }
+
+        if self.policy == Policy::Interpolate {
+            frame.samples = self::interpolate(previous, next, t);
+        }
+
+        frame
+    }
+}
+
+/// Interpolate the annotations of each tracked object between two frames.
+///
+/// An annotation is interpolated if an annotation of the same label and
+/// instance (i.e., `instance_id`) exists in the sample of the same channel of
+/// both frames, and both of their bounding boxes are of the same kind. All
+/// other annotations and samples are not.
+fn interpolate(previous: &Frame, next: &Frame, t: f64) -> Vec<Sample> {
+    let mut samples = Vec::new();
+
+    for sample in previous.samples.iter() {
+        let (a, text) = match sample {
+            Sample::ObjectDetection(record) => (record, false),
+            Sample::Text(record) => (record, true),
+            _ => continue,
+        };
+
+        let b = next.samples.iter().find_map(|s| match s {
+            Sample::ObjectDetection(record) if !text && record.channel == a.channel => Some(record),
+            Sample::Text(record) if text && record.channel == a.channel => Some(record),
+            _ => None,
+        });
+
+        let Some(b) = b else {
+            continue;
+        };
+
+        let mut record = DetectionRecord::new(a.channel.clone(), None);
+
+        for (label, annotations) in a.annotations.iter() {
+            for x in annotations.iter() {
+                let Some(id) = &x.instance_id else {
+                    continue;
+                };
+
+                let y = b.annotations.get(label).and_then(|annotations| {
+                    annotations
+                        .iter()
+                        .find(|y| y.instance_id.as_ref() == Some(id))
+                });
+
+                let bbox = match (&x.bbox, y.map(|y| &y.bbox)) {
+                    (BoundingBox::AxisAligned(p), Some(BoundingBox::AxisAligned(q))) => {
+                        BoundingBox::AxisAligned(aa::Region::new(
+                            self::point(p.center(), q.center(), t),
+                            lerp(p.width(), q.width(), t),
+                            lerp(p.height(), q.height(), t),
+                        ))
+                    }
+                    (BoundingBox::Oriented(p), Some(BoundingBox::Oriented(q))) => {
+                        BoundingBox::Oriented(oriented::Region::new(
+                            self::point(p.center(), q.center(), t),
+                            lerp(p.width(), q.width(), t),
+                            lerp(p.height(), q.height(), t),
+                            lerp(p.rotation(), q.rotation(), t),
+                        ))
+                    }
+                    (BoundingBox::Cuboid(p), Some(BoundingBox::Cuboid(q))) => {
+                        BoundingBox::Cuboid(cuboid::Region::new(
+                            Point3::new(
+                                lerp(p.center.x, q.center.x, t),
+                                lerp(p.center.y, q.center.y, t),
+                                lerp(p.center.z, q.center.z, t),
+                            ),
+                            lerp(p.width, q.width, t),
+                            lerp(p.height, q.height, t),
+                            lerp(p.length, q.length, t),
+                            Rotation {
+                                yaw: lerp(p.rotation.yaw, q.rotation.yaw, t),
+                                pitch: lerp(p.rotation.pitch, q.rotation.pitch, t),
+                                roll: lerp(p.rotation.roll, q.rotation.roll, t),
+                            },
+                        ))
+                    }
+                    _ => continue,
+                };
+
+                let mut annotation =
+                    Annotation::new(label.clone(), lerp(x.score, y.unwrap().score, t), bbox);
+                annotation.instance_id = Some(id.clone());
+                annotation.text = x.text.clone();
+                annotation.attributes = x.attributes.clone();
+
+                record
+                    .annotations
+                    .entry(label.clone())
+                    .or_default()
+                    .push(annotation);
+            }
+        }
+
+        samples.push(match text {
+            true => Sample::Text(record),
+            false => Sample::ObjectDetection(record),
+        });
+    }
+
+    samples
+}
+
+/// Linearly interpolate between two points.
+fn point(a: Point, b: Point, t: f64) -> Point {
+    Point::new(lerp(a.x, b.x, t), lerp(a.y, b.y, t))
+}
+
+/// Linearly interpolate between two values.
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + ((b - a) * t)
+}
+
+#[derive(Debug, Clone)]
+pub struct GapError {
+    msg: String,
+}
+
+impl From<&str> for GapError {
+    fn from(msg: &str) -> Self {
+        GapError {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for GapError {
+    fn from(msg: String) -> Self {
+        GapError { msg }
+    }
+}
+
+impl fmt::Display for GapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "gap: {}", self.msg)
+    }
+}
+
+impl Error for GapError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::datastream::frame::sample::detections::bbox::BoundingBox;
+    use crate::datastream::frame::sample::detections::label::Label;
+    use crate::datastream::frame::sample::Sample;
+    use crate::support::frame;
+
+    use super::{Gaps, Policy};
+
+    #[test]
+    fn interpolate() {
+        let mut gaps = Gaps::new(Policy::Interpolate);
+        let segments = gaps.fill(vec![
+            frame(0, &[("car", Some("1"), 0.0)]),
+            frame(3, &[("car", Some("1"), 30.0)]),
+        ]);
+
+        assert_eq!(segments.len(), 1);
+        assert_eq!(gaps.missing(), 2);
+
+        let centers = segments[0]
+            .iter()
+            .map(|f| match &f.samples[0] {
+                Sample::ObjectDetection(record) => {
+                    match &record.annotations[&Label::from("car")][0].bbox {
+                        BoundingBox::AxisAligned(region) => region.center().x,
+                        _ => unreachable!(),
+                    }
+                }
+                _ => unreachable!(),
+            })
+            .collect::<Vec<f64>>();
+
+        assert_eq!(centers, [0.0, 10.0, 20.0, 30.0]);
+    }
+
+    #[test]
+    fn break_across_batches() {
+        let mut gaps = Gaps::new(Policy::Break);
+
+        assert_eq!(
+            gaps.fill(vec![
+                frame(0, &[("car", Some("1"), 0.0)]),
+                frame(1, &[("car", Some("1"), 0.0)])
+            ])
+            .len(),
+            1
+        );
+
+        let segments = gaps.fill(vec![
+            frame(5, &[("car", Some("1"), 0.0)]),
+            frame(6, &[("car", Some("1"), 0.0)]),
+            frame(8, &[("car", Some("1"), 0.0)]),
+        ]);
+        assert_eq!(
+            segments.iter().map(|s| s.len()).collect::<Vec<usize>>(),
+            [0, 2, 1]
+        );
+        assert_eq!(gaps.count(), 2);
+    }
+
+    #[test]
+    fn inserted() {
+        let timed = |index: usize, timestamp: f64| {
+            let mut frame = frame(index, &[]);
+            frame.timestamp = Some(timestamp);
+            frame
+        };
+
+        // The gap between the indices is filled with frames of their own.
+        let segments = Gaps::new(Policy::Empty).fill(vec![timed(0, 0.0), timed(2, 0.2)]);
+        assert_eq!(
+            segments[0]
+                .iter()
+                .map(|f| (f.index, f.inserted))
+                .collect::<Vec<_>>(),
+            [(0, false), (1, false), (2, false)]
+        );
+
+        // The gap between the timestamps is filled with frames that share the
+        // index before it, which are marked as such.
+        let segments =
+            Gaps::new(Policy::Empty).fill(vec![timed(0, 0.0), timed(1, 0.1), timed(2, 0.4)]);
+        assert_eq!(
+            segments[0]
+                .iter()
+                .map(|f| (f.index, f.inserted))
+                .collect::<Vec<_>>(),
+            [(0, false), (1, false), (1, true), (1, true), (2, false)]
+        );
+    }
+}
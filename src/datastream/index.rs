@@ -0,0 +1,313 @@
+//! Frame index for random access.
+//!
+//! A [`FrameIndex`] records the byte range of each frame within a
+//! STREM-formatted file. This allows a frame to be deserialized without
+//! deserializing the frames before it, so matching may start at an arbitrary
+//! frame. The index may be persisted alongside the file to be reused.
+
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::hash::{Hash, Hasher};
+use std::io::{BufReader, BufWriter};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use serde_json::Deserializer;
+
+use crate::hash::Fnv;
+
+/// The extension appended to the path of an indexed file.
+pub const EXTENSION: &str = "idx";
+
+/// The number of bytes at each end of an indexed file that are hashed.
+const BLOCK: usize = 4096;
+
+/// A top-level STREM document with its frames left unparsed.
+#[derive(Deserialize)]
+pub struct Document<'a> {
+    #[serde(borrow)]
+    pub version: Cow<'a, str>,
+
+    #[serde(borrow)]
+    pub frames: Vec<&'a RawValue>,
+}
+
+/// The only part of a frame needed to index it.
+#[derive(Deserialize)]
+pub struct Header {
+    pub index: usize,
+}
+
+/// The location of a single frame.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Entry {
+    /// The index of the frame as declared in the data.
+    pub index: usize,
+
+    /// The position of the document that contains the frame.
+    pub document: usize,
+
+    /// The byte offset of the frame from the start of the file.
+    pub offset: usize,
+
+    /// The number of bytes of the frame.
+    pub length: usize,
+}
+
+/// An index of byte offsets of each frame within a file.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FrameIndex {
+    /// The number of bytes of the indexed file.
+    pub length: usize,
+
+    /// The last modification time of the indexed file (in nanoseconds since
+    /// the Unix epoch), if known.
+    pub modified: Option<u64>,
+
+    /// The hash of the first and last blocks of the indexed file.
+    pub hash: u64,
+
+    /// The declared version of each document.
+    pub documents: Vec<String>,
+
+    /// The frames of all documents, in order.
+    pub entries: Vec<Entry>,
+}
+
+impl FrameIndex {
+    /// Build a [`FrameIndex`] from the contents of a file.
+    ///
+    /// Only the `index` of each frame is deserialized; the remainder of the
+    /// frame is scanned over to find its extent.
+    pub fn build(source: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let mut documents = Vec::new();
+        let mut entries = Vec::new();
+
+        let stream = Deserializer::from_slice(source).into_iter::<Document>();
+
+        for (d, document) in stream.enumerate() {
+            let document = document.map_err(|e| FrameIndexError::from(e.to_string()))?;
+            documents.push(document.version.to_string());
+
+            for frame in document.frames {
+                let header: Header = serde_json::from_str(frame.get())
+                    .map_err(|e| FrameIndexError::from(e.to_string()))?;
+
+                entries.push(Entry {
+                    index: header.index,
+                    document: d,
+                    offset: frame.get().as_ptr() as usize - source.as_ptr() as usize,
+                    length: frame.get().len(),
+                });
+            }
+        }
+
+        Ok(FrameIndex {
+            length: source.len(),
+            modified: None,
+            hash: self::hash(source),
+            documents,
+            entries,
+        })
+    }
+
+    /// Load a persisted [`FrameIndex`] from `path`.
+    pub fn load(path: &Path) -> Result<Self, Box<dyn Error>> {
+        let f = File::open(path).map_err(|e| {
+            FrameIndexError::from(format!(
+                "{}: {}",
+                path.display(),
+                e.to_string().to_lowercase()
+            ))
+        })?;
+
+        Ok(serde_json::from_reader(BufReader::new(f))
+            .map_err(|e| FrameIndexError::from(format!("{}: {}", path.display(), e)))?)
+    }
+
+    /// Persist the [`FrameIndex`] to `path`.
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let f = File::create(path).map_err(|e| {
+            FrameIndexError::from(format!(
+                "{}: {}",
+                path.display(),
+                e.to_string().to_lowercase()
+            ))
+        })?;
+
+        Ok(serde_json::to_writer(BufWriter::new(f), self)?)
+    }
+
+    /// The path of the persisted index of the file at `path`.
+    ///
+    /// This is the path of the file with [`EXTENSION`] appended (e.g.,
+    /// `data.json` is indexed by `data.json.idx`).
+    pub fn path(path: &Path) -> PathBuf {
+        let mut name = path.as_os_str().to_owned();
+        name.push(".");
+        name.push(EXTENSION);
+
+        PathBuf::from(name)
+    }
+
+    /// Load the persisted index of the file at `path`, or build it.
+    ///
+    /// If no index exists, or the index no longer matches the contents of the
+    /// file, a new index is built and persisted in its place. Failing to
+    /// persist the index (e.g., in a read-only directory) is not an error, as
+    /// the index is only needed by this process.
+    pub fn open(path: &Path, source: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let ipath = Self::path(path);
+        let modified = self::modified(path);
+
+        if fs::metadata(&ipath).is_ok() {
+            if let Ok(index) = Self::load(&ipath) {
+                if index.matches(source, modified) {
+                    return Ok(index);
+                }
+            }
+        }
+
+        let mut index = Self::build(source)?;
+        index.modified = modified;
+
+        if let Err(e) = index.save(&ipath) {
+            eprintln!(
+                "index: warning: {}: failed to store index ({})",
+                ipath.display(),
+                e
+            );
+        }
+
+        Ok(index)
+    }
+
+    /// Check if the [`FrameIndex`] is consistent with the contents of a file.
+    ///
+    /// This is a quick check rather than a full validation: the length, last
+    /// modification time, and first and last blocks of the file must be
+    /// unchanged, and each entry must span a JSON object.
+    pub fn matches(&self, source: &[u8], modified: Option<u64>) -> bool {
+        self.length == source.len()
+            && self.modified == modified
+            && self.hash == self::hash(source)
+            && self.entries.iter().all(|e| {
+                e.length > 0
+                    && e.document < self.documents.len()
+                    && source.get(e.offset) == Some(&b'{')
+                    && source.get(e.offset + e.length - 1) == Some(&b'}')
+            })
+    }
+
+    /// Find the position of the first entry with an index of at least `from`.
+    pub fn seek(&self, from: usize) -> usize {
+        self.entries
+            .iter()
+            .position(|e| e.index >= from)
+            .unwrap_or(self.entries.len())
+    }
+}
+
+/// Hash the first and last [`BLOCK`] bytes of a file.
+///
+/// Only the ends of the file are hashed, such that checking an index remains
+/// cheap for large files. As the index is persisted, the ends are hashed with
+/// [`Fnv`], which is the same across builds and platforms.
+fn hash(source: &[u8]) -> u64 {
+    let mut hasher = Fnv::new();
+
+    source[..source.len().min(BLOCK)].hash(&mut hasher);
+    source[source.len().saturating_sub(BLOCK)..].hash(&mut hasher);
+
+    hasher.finish()
+}
+
+/// The last modification time of the file at `path`, if known.
+fn modified(path: &Path) -> Option<u64> {
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    let elapsed = modified.duration_since(UNIX_EPOCH).ok()?;
+
+    u64::try_from(elapsed.as_nanos()).ok()
+}
+
+#[derive(Debug, Clone)]
+struct FrameIndexError {
+    msg: String,
+}
+
+impl From<&str> for FrameIndexError {
+    fn from(msg: &str) -> Self {
+        FrameIndexError {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for FrameIndexError {
+    fn from(msg: String) -> Self {
+        FrameIndexError { msg }
+    }
+}
+
+impl fmt::Display for FrameIndexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "index: {}", self.msg)
+    }
+}
+
+impl Error for FrameIndexError {}
+
+#[cfg(test)]
+mod tests {
+    use super::FrameIndex;
+
+    #[test]
+    fn build() {
+        let source =
+            br#"{"version":"2.0.0","frames":[{"index":3,"samples":[]},{"index":4,"samples":[]}]}
+{"version":"2.0.0","frames":[{"index":5,"samples":[]}]}"#;
+
+        let index = FrameIndex::build(source).unwrap();
+
+        assert_eq!(index.documents.len(), 2);
+        assert_eq!(index.entries.len(), 3);
+        assert!(index.matches(source, None));
+
+        let e = &index.entries[index.seek(4)];
+        assert_eq!(e.index, 4);
+        assert_eq!(
+            &source[e.offset..(e.offset + e.length)],
+            br#"{"index":4,"samples":[]}"#
+        );
+    }
+
+    #[test]
+    fn stale() {
+        let source = br#"{"version":"2.0.0","frames":[{"index":3,"samples":[]}]}"#;
+        let index = FrameIndex::build(source).unwrap();
+
+        // The file is edited in place, without changing its length.
+        let edited = br#"{"version":"2.0.0","frames":[{"index":4,"samples":[]}]}"#;
+        assert!(!index.matches(edited, None));
+
+        // The file is touched, without changing its contents.
+        assert!(!index.matches(source, Some(1)));
+    }
+
+    #[test]
+    fn unwritable() {
+        let source = br#"{"version":"2.0.0","frames":[{"index":3,"samples":[]}]}"#;
+
+        // The index cannot be stored in a directory that does not exist.
+        let path = std::env::temp_dir()
+            .join("strem-index-missing")
+            .join("a.json");
+
+        let index = FrameIndex::open(&path, source).unwrap();
+        assert_eq!(index.entries.len(), 1);
+    }
+}
@@ -1,34 +1,248 @@
+//! The STREM format ("stremf").
+//!
+//! These structures mirror the JSON schema of the format and are used solely
+//! for (de)serialization. The current schema is `v2`, which is a superset of
+//! `v1`; data of `v1` is upgraded through the [`version`] adapters.
+//!
+//! Strings are borrowed from the input buffer where possible (i.e., when the
+//! input is read from a slice and the string contains no escapes) to avoid
+//! allocating for each label and channel of a large file.
+
+use std::borrow::Cow;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 
+pub mod base64;
+
+pub mod coco;
 pub mod exporter;
 pub mod importer;
+#[cfg(feature = "rosbag")]
+pub mod rosbag;
+pub mod version;
+
+/// The format of the data imported.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Format {
+    /// The STREM format ("stremf").
+    #[default]
+    Stremf,
+
+    /// The COCO annotation format (see [`coco`]).
+    Coco,
+
+    /// A ROS 1 bag of detection messages (see `rosbag`).
+    ///
+    /// This is only read with the `rosbag` feature.
+    Rosbag,
+}
+
+impl FromStr for Format {
+    type Err = FormatError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "stremf" => Ok(Format::Stremf),
+            "coco" => Ok(Format::Coco),
+            "rosbag" => Ok(Format::Rosbag),
+            _ => Err(FormatError::from(format!(
+                "unknown format `{}` (expected `stremf`, `coco`, or `rosbag`)",
+                s
+            ))),
+        }
+    }
+}
 
 #[derive(Debug, Deserialize, Serialize)]
-pub struct DataStream {
-    version: String,
-    frames: Vec<Frame>,
+pub struct DataStream<'a> {
+    #[serde(borrow)]
+    pub version: Cow<'a, str>,
+
+    #[serde(borrow)]
+    pub frames: Vec<Frame<'a>>,
+}
+
+/// A document converted from another format (e.g., a ROS bag) as it is read.
+///
+/// A record that cannot be converted is `Rejected` with the reason, such that
+/// the remaining records are still read (see [`importer::Importer::reject`]).
+pub enum Converted {
+    Document(DataStream<'static>),
+    Rejected(String),
 }
 
 #[derive(Debug, Deserialize, Serialize)]
-pub struct Frame {
+pub struct Frame<'a> {
     index: usize,
-    samples: Vec<Sample>,
+
+    /// The time (in seconds) the frame was captured (since `v2`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timestamp: Option<f64>,
+
+    /// Arbitrary information about the frame (since `v2`).
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    metadata: IndexMap<String, serde_json::Value>,
+
+    #[serde(borrow)]
+    samples: Vec<Sample<'a>>,
+}
+
+impl<'a> Frame<'a> {
+    /// Create a new [`Frame`] without any samples.
+    pub fn new(index: usize) -> Self {
+        Frame {
+            index,
+            timestamp: None,
+            metadata: IndexMap::new(),
+            samples: Vec::new(),
+        }
+    }
+
+    /// The time (in seconds) the frame was captured, if known.
+    pub fn timestamp(&self) -> Option<f64> {
+        self.timestamp
+    }
+
+    /// Set the index of the frame.
+    pub fn reindex(&mut self, index: usize) {
+        self.index = index;
+    }
+
+    /// Merge the samples and metadata of another frame into this one.
+    ///
+    /// The index and timestamp of this frame are kept. If both frames share a
+    /// metadata key, the value of this frame is kept.
+    pub fn merge(&mut self, other: Frame<'a>) {
+        for (key, value) in other.metadata {
+            self.metadata.entry(key).or_insert(value);
+        }
+
+        self.samples.extend(other.samples);
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(tag = "type")]
-pub enum Sample {
+pub enum Sample<'a> {
     #[serde(rename = "@stremf/sample/detection")]
     ObjectDetection {
-        channel: String,
-        image: Image,
-        annotations: Vec<Annotation>,
+        #[serde(borrow)]
+        channel: Cow<'a, str>,
+
+        /// The associated image (optional since `v2`).
+        #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+        image: Option<Image<'a>>,
+
+        /// The pose of the sensor (since `v2`).
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pose: Option<Pose>,
+
+        #[serde(borrow)]
+        annotations: Vec<Annotation<'a>>,
+    },
+
+    /// Text detections with the recognized text of each region (since `v2`).
+    #[serde(rename = "@stremf/sample/text")]
+    Text {
+        #[serde(borrow)]
+        channel: Cow<'a, str>,
+
+        #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+        image: Option<Image<'a>>,
+
+        /// The pose of the sensor.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pose: Option<Pose>,
+
+        #[serde(borrow)]
+        annotations: Vec<Annotation<'a>>,
+    },
+
+    /// A LiDAR point cloud (since `v2`).
+    #[serde(rename = "@stremf/sample/pointcloud")]
+    PointCloud {
+        #[serde(borrow)]
+        channel: Cow<'a, str>,
+
+        #[serde(borrow)]
+        path: Cow<'a, str>,
+        points: usize,
+
+        /// The pose of the sensor.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pose: Option<Pose>,
+
+        /// The number of points within each bounding box.
+        #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+        statistics: Option<Vec<PointStatistics<'a>>>,
+    },
+
+    /// Frame-level labels without regions (since `v2`).
+    #[serde(rename = "@stremf/sample/classification")]
+    Classification {
+        #[serde(borrow)]
+        channel: Cow<'a, str>,
+
+        #[serde(borrow)]
+        labels: Vec<Classification<'a>>,
+
+        /// The pose of the sensor.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pose: Option<Pose>,
     },
 }
 
 #[derive(Debug, Deserialize, Serialize)]
-pub struct Image {
-    path: String,
+pub struct Pose {
+    translation: PoseTranslation,
+    rotation: PoseRotation,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PoseTranslation {
+    x: f64,
+    y: f64,
+    z: f64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PoseRotation {
+    yaw: f64,
+    pitch: f64,
+    roll: f64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Classification<'a> {
+    #[serde(borrow)]
+    class: Cow<'a, str>,
+    score: f64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PointStatistics<'a> {
+    #[serde(borrow)]
+    class: Cow<'a, str>,
+
+    #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+    instance_id: Option<Cow<'a, str>>,
+
+    points: usize,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Image<'a> {
+    #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+    path: Option<Cow<'a, str>>,
+
+    /// The Base64-encoded contents of the image (since `v2`).
+    #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+    data: Option<Cow<'a, str>>,
+
     dimensions: ImageDimensions,
 }
 
@@ -39,10 +253,37 @@ pub struct ImageDimensions {
 }
 
 #[derive(Debug, Deserialize, Serialize)]
-pub struct Annotation {
-    class: String,
+pub struct Annotation<'a> {
+    #[serde(borrow)]
+    class: Cow<'a, str>,
     score: f64,
+
+    /// The identifier of the tracked object instance (since `v2`).
+    #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+    instance_id: Option<Cow<'a, str>>,
+
+    /// The recognized text within the region (since `v2`).
+    #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+    text: Option<Cow<'a, str>>,
+
+    /// Additional dataset-specific attributes (since `v2`).
+    #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+    attributes: Option<IndexMap<Cow<'a, str>, Attribute<'a>>>,
+
     bbox: BoundingBox,
+
+    /// The 3D bounding box of the same object (since `v2`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    bbox3d: Option<BoundingBox>,
+}
+
+/// A scalar attribute value of an [`Annotation`].
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum Attribute<'a> {
+    Bool(bool),
+    Number(f64),
+    String(#[serde(borrow)] Cow<'a, str>),
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -53,6 +294,14 @@ pub enum BoundingBox {
 
     #[serde(rename = "@stremf/bbox/obb")]
     Oriented { region: OrientedRegion },
+
+    /// A 3D bounding box (since `v2`).
+    #[serde(rename = "@stremf/bbox/cuboid")]
+    Cuboid { region: CuboidRegion },
+
+    /// A polygon (e.g., of an instance segmentation mask).
+    #[serde(rename = "@stremf/bbox/polygon")]
+    Polygon { region: PolygonRegion },
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -91,3 +340,69 @@ pub struct OrientedRegionDimensions {
     w: f64,
     h: f64,
 }
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CuboidRegion {
+    center: CuboidRegionCenter,
+    dimensions: CuboidRegionDimensions,
+    rotation: CuboidRegionRotation,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CuboidRegionCenter {
+    x: f64,
+    y: f64,
+    z: f64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CuboidRegionDimensions {
+    w: f64,
+    h: f64,
+    l: f64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CuboidRegionRotation {
+    yaw: f64,
+    pitch: f64,
+    roll: f64,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PolygonRegion {
+    points: Vec<PolygonRegionPoint>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PolygonRegionPoint {
+    x: f64,
+    y: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct FormatError {
+    msg: String,
+}
+
+impl From<&str> for FormatError {
+    fn from(msg: &str) -> Self {
+        FormatError {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for FormatError {
+    fn from(msg: String) -> Self {
+        FormatError { msg }
+    }
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "format: {}", self.msg)
+    }
+}
+
+impl Error for FormatError {}
@@ -0,0 +1,129 @@
+//! Base64 encoding.
+//!
+//! This implements the standard alphabet with padding (RFC 4648), as used to
+//! embed binary data (e.g., images) within STREM-formatted data.
+
+use std::error::Error;
+use std::fmt;
+
+/// The standard Base64 alphabet.
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encode the bytes as Base64.
+pub fn encode(bytes: &[u8]) -> String {
+    let mut res = String::with_capacity(bytes.len().div_ceil(3) * 4);
+
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+
+        for i in 0..4 {
+            // Pad the characters beyond the end of the chunk.
+            //
+            // A chunk of `k` bytes is encoded by the first `k + 1` characters.
+            if i <= chunk.len() {
+                res.push(ALPHABET[((n >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            } else {
+                res.push('=');
+            }
+        }
+    }
+
+    res
+}
+
+/// Decode the Base64 text into bytes.
+///
+/// Whitespace is ignored, such that line-wrapped text may be decoded.
+pub fn decode(text: &str) -> Result<Vec<u8>, Box<dyn Error>> {
+    let text: Vec<u8> = text.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+
+    if !text.len().is_multiple_of(4) {
+        return Err(Box::new(Base64Error::from("invalid length")));
+    }
+
+    let mut res = Vec::with_capacity(text.len() / 4 * 3);
+
+    for (c, chunk) in text.chunks(4).enumerate() {
+        let last = c == text.len() / 4 - 1;
+        let padding = chunk.iter().rev().take_while(|b| **b == b'=').count();
+
+        if padding > 2 || (padding > 0 && !last) {
+            return Err(Box::new(Base64Error::from("invalid padding")));
+        }
+
+        let mut n = 0u32;
+
+        for b in chunk[..(4 - padding)].iter() {
+            let value = ALPHABET
+                .iter()
+                .position(|a| a == b)
+                .ok_or(Base64Error::from(format!(
+                    "invalid character `{}`",
+                    *b as char
+                )))?;
+
+            n = (n << 6) | value as u32;
+        }
+
+        n <<= 6 * padding;
+        res.extend_from_slice(&n.to_be_bytes()[1..(4 - padding)]);
+    }
+
+    Ok(res)
+}
+
+#[derive(Debug, Clone)]
+struct Base64Error {
+    msg: String,
+}
+
+impl From<&str> for Base64Error {
+    fn from(msg: &str) -> Self {
+        Base64Error {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for Base64Error {
+    fn from(msg: String) -> Self {
+        Base64Error { msg }
+    }
+}
+
+impl fmt::Display for Base64Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "base64: {}", self.msg)
+    }
+}
+
+impl Error for Base64Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode};
+
+    #[test]
+    fn roundtrip() {
+        for (bytes, text) in [
+            (&b""[..], ""),
+            (b"f", "Zg=="),
+            (b"fo", "Zm8="),
+            (b"foo", "Zm9v"),
+            (b"foob", "Zm9vYg=="),
+        ] {
+            assert_eq!(encode(bytes), text);
+            assert_eq!(decode(text).unwrap(), bytes);
+        }
+
+        assert!(decode("Zg=").is_err());
+        assert!(decode("Zg==Zm8=").is_err());
+        assert!(decode("Z!==").is_err());
+    }
+}
@@ -0,0 +1,352 @@
+//! The COCO annotation format.
+//!
+//! Many perception pipelines emit COCO-style JSON (i.e., `images`,
+//! `annotations`, and `categories`) rather than STREM-formatted data. A COCO
+//! document is converted into an equivalent [`io::DataStream`], such that it is
+//! imported like any other (e.g., with an ontology).
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use indexmap::IndexMap;
+use serde::Deserialize;
+
+use super::super::io;
+use super::version::Version;
+
+/// The channel of the samples of a COCO document.
+///
+/// COCO does not distinguish between sensors; therefore, all images are
+/// samples of the same channel.
+pub const CHANNEL: &str = "coco";
+
+#[derive(Deserialize)]
+struct Document {
+    images: Vec<Image>,
+
+    #[serde(default)]
+    annotations: Vec<Annotation>,
+
+    categories: Vec<Category>,
+}
+
+#[derive(Deserialize)]
+struct Image {
+    id: u64,
+
+    #[serde(default)]
+    file_name: Option<String>,
+
+    width: u32,
+    height: u32,
+
+    /// The video of the image, if any.
+    #[serde(default)]
+    video_id: Option<u64>,
+
+    /// The position of the image within its video, if any.
+    #[serde(default)]
+    frame_id: Option<usize>,
+}
+
+#[derive(Deserialize)]
+struct Annotation {
+    image_id: u64,
+    category_id: u64,
+
+    /// The region as `[x, y, width, height]` from the top-left corner.
+    bbox: [f64; 4],
+
+    /// The confidence of a detection; ground truth has none.
+    #[serde(default)]
+    score: Option<f64>,
+
+    /// The identifier of the tracked object, if any.
+    #[serde(default, alias = "instance_id")]
+    track_id: Option<Id>,
+}
+
+#[derive(Deserialize)]
+struct Category {
+    id: u64,
+    name: String,
+}
+
+/// An identifier that is either a number or a string.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Id {
+    Number(u64),
+    String(String),
+}
+
+impl fmt::Display for Id {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Id::Number(n) => write!(f, "{}", n),
+            Id::String(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// Convert a COCO document into an [`io::DataStream`].
+///
+/// Each image is a frame, ordered by its position within its video (i.e.,
+/// `frame_id`) if known, and otherwise by its identifier. The frames are then
+/// indexed from zero. A document of more than one video (i.e., `video_id`) is
+/// rejected, as the frames of different videos have no order between them.
+/// An annotation of an unknown category is left out, and
+/// the reason is returned alongside, such that it may be rejected (see
+/// [`io::importer::Importer::reject`]).
+pub fn convert(source: &[u8]) -> Result<(io::DataStream<'static>, Vec<String>), Box<dyn Error>> {
+    let document: Document =
+        serde_json::from_slice(source).map_err(|e| CocoError::from(e.to_string()))?;
+
+    let categories = document
+        .categories
+        .iter()
+        .map(|c| (c.id, c.name.as_str()))
+        .collect::<HashMap<u64, &str>>();
+
+    // Check that the images are of a single video.
+    //
+    // Otherwise, the positions of the images of different videos would be
+    // interleaved into a single sequence of frames.
+    let mut videos = document.images.iter().filter_map(|image| image.video_id);
+
+    if let Some(first) = videos.next() {
+        if let Some(other) = videos.find(|&id| id != first) {
+            return Err(Box::new(CocoError::from(format!(
+                "images of more than one video (`{}` and `{}`)",
+                first, other
+            ))));
+        }
+    }
+
+    let mut images = document.images.iter().collect::<Vec<&Image>>();
+    images.sort_by_key(|image| (image.frame_id, image.id));
+
+    let positions = images
+        .iter()
+        .enumerate()
+        .map(|(i, image)| (image.id, i))
+        .collect::<HashMap<u64, usize>>();
+
+    let mut annotations: Vec<Vec<io::Annotation>> = images.iter().map(|_| Vec::new()).collect();
+    let mut rejected = Vec::new();
+
+    for annotation in document.annotations.iter() {
+        let position = positions
+            .get(&annotation.image_id)
+            .ok_or(CocoError::from(format!(
+                "annotation of unknown image `{}`",
+                annotation.image_id
+            )))?;
+
+        let Some(class) = categories.get(&annotation.category_id) else {
+            rejected.push(format!(
+                "coco: annotation of unknown category `{}`",
+                annotation.category_id
+            ));
+            continue;
+        };
+
+        let [x, y, w, h] = annotation.bbox;
+
+        annotations[*position].push(io::Annotation {
+            class: Cow::Owned(class.to_string()),
+            score: annotation.score.unwrap_or(1.0),
+            instance_id: annotation
+                .track_id
+                .as_ref()
+                .map(|id| Cow::Owned(id.to_string())),
+            text: None,
+            attributes: None,
+            bbox: io::BoundingBox::AxisAligned {
+                region: io::AxisAlignedRegion {
+                    center: io::AxisAlignedRegionCenter {
+                        x: x + w / 2.0,
+                        y: y + h / 2.0,
+                    },
+                    dimensions: io::AxisAlignedRegionDimensions { w, h },
+                },
+            },
+            bbox3d: None,
+        });
+    }
+
+    let frames = images
+        .iter()
+        .zip(annotations)
+        .enumerate()
+        .map(|(index, (image, annotations))| io::Frame {
+            index,
+            timestamp: None,
+            metadata: IndexMap::new(),
+            samples: vec![io::Sample::ObjectDetection {
+                channel: Cow::Borrowed(CHANNEL),
+                image: Some(io::Image {
+                    path: image.file_name.clone().map(Cow::Owned),
+                    data: None,
+                    dimensions: io::ImageDimensions {
+                        width: image.width,
+                        height: image.height,
+                    },
+                }),
+                pose: None,
+                annotations,
+            }],
+        })
+        .collect();
+
+    let data = io::DataStream {
+        version: Cow::Owned(Version::current().to_string()),
+        frames,
+    };
+
+    Ok((data, rejected))
+}
+
+#[derive(Debug, Clone)]
+struct CocoError {
+    msg: String,
+}
+
+impl From<&str> for CocoError {
+    fn from(msg: &str) -> Self {
+        CocoError {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for CocoError {
+    fn from(msg: String) -> Self {
+        CocoError { msg }
+    }
+}
+
+impl fmt::Display for CocoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "coco: {}", self.msg)
+    }
+}
+
+impl Error for CocoError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Configuration;
+    use crate::datastream::frame::sample::detections::bbox::BoundingBox;
+    use crate::datastream::frame::sample::detections::label::Label;
+    use crate::datastream::frame::sample::Sample;
+    use crate::datastream::io::importer::Importer;
+    use crate::datastream::DataStream;
+
+    #[test]
+    fn convert() {
+        let source = br#"{
+            "images": [
+                {"id": 7, "file_name": "b.jpg", "width": 640, "height": 480},
+                {"id": 3, "file_name": "a.jpg", "width": 640, "height": 480}
+            ],
+            "annotations": [
+                {"id": 1, "image_id": 7, "category_id": 1, "bbox": [10.0, 20.0, 30.0, 40.0], "track_id": 5}
+            ],
+            "categories": [{"id": 1, "name": "car"}]
+        }"#;
+
+        let pattern = String::from("[[:car:]]");
+        let config = Configuration::new(&pattern);
+
+        let mut importer = Importer::new(&config);
+        let frames = DataStream::from_coco(source)
+            .unwrap()
+            .request(&mut importer)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(frames.len(), 2);
+        assert!(frames[0].samples.iter().all(|s| match s {
+            Sample::ObjectDetection(record) => record.annotations.is_empty(),
+            _ => false,
+        }));
+
+        let Sample::ObjectDetection(record) = &frames[1].samples[0] else {
+            panic!("expected a detection sample");
+        };
+
+        let car = &record.annotations[&Label::from("car")][0];
+        assert_eq!(car.instance_id.as_deref(), Some("5"));
+
+        let BoundingBox::AxisAligned(region) = &car.bbox else {
+            panic!("expected an axis-aligned bounding box");
+        };
+        assert_eq!((region.center().x, region.center().y), (25.0, 40.0));
+
+        assert!(DataStream::from_coco(br#"{"images":[],"categories":[],"annotations":[{"image_id":1,"category_id":1,"bbox":[0,0,1,1]}]}"#).is_err());
+    }
+
+    #[test]
+    fn videos() {
+        let source = |second: u64| {
+            format!(
+                r#"{{
+                    "images": [
+                        {{"id": 1, "video_id": 1, "frame_id": 1, "width": 640, "height": 480}},
+                        {{"id": 2, "video_id": {}, "frame_id": 0, "width": 640, "height": 480}}
+                    ],
+                    "categories": []
+                }}"#,
+                second
+            )
+        };
+
+        assert!(DataStream::from_coco(source(1).as_bytes()).is_ok());
+        assert!(DataStream::from_coco(source(2).as_bytes())
+            .err()
+            .unwrap()
+            .to_string()
+            .contains("more than one video (`1` and `2`)"));
+    }
+
+    #[test]
+    fn unknown_category() {
+        let source = br#"{
+            "images": [{"id": 1, "width": 640, "height": 480}],
+            "annotations": [
+                {"image_id": 1, "category_id": 1, "bbox": [0.0, 0.0, 1.0, 1.0]},
+                {"image_id": 1, "category_id": 9, "bbox": [0.0, 0.0, 1.0, 1.0]}
+            ],
+            "categories": [{"id": 1, "name": "car"}]
+        }"#;
+
+        let pattern = String::from("[[:car:]]");
+        let mut config = Configuration::new(&pattern);
+
+        // The annotation is skipped, unless the import is strict.
+        let mut importer = Importer::new(&config);
+        let frames = DataStream::from_coco(source)
+            .unwrap()
+            .request(&mut importer)
+            .unwrap()
+            .unwrap();
+
+        let Sample::ObjectDetection(record) = &frames[0].samples[0] else {
+            panic!("expected a detection sample");
+        };
+
+        assert_eq!(record.annotations[&Label::from("car")].len(), 1);
+        assert_eq!(importer.skipped(), 1);
+
+        config.fail_fast = true;
+
+        let mut importer = Importer::new(&config);
+        assert!(DataStream::from_coco(source)
+            .unwrap()
+            .request(&mut importer)
+            .is_err());
+    }
+}
@@ -1,11 +1,18 @@
+use std::borrow::Cow;
 use std::error::Error;
 use std::fmt;
 
+use indexmap::IndexMap;
+
+use crate::datastream::frame::sample::detections::bbox::region::cuboid;
 use crate::datastream::frame::sample::detections::bbox::BoundingBox;
-use crate::datastream::frame::sample::detections::ImageSource;
+use crate::datastream::frame::sample::detections::{Attribute, ImageSource};
+use crate::datastream::frame::sample::pose::Pose;
 use crate::datastream::frame::sample::Sample;
 use crate::datastream::frame::Frame;
 use crate::datastream::io;
+use crate::datastream::io::base64;
+use crate::datastream::io::version::Version;
 
 #[derive(Default)]
 pub struct DataExporter {}
@@ -20,9 +27,9 @@ impl DataExporter {
     ///
     /// This accepts a set of [`Frame`] and transforms it into a single
     /// serializable [`io::DataStream`].
-    pub fn export(&self, frames: &[Frame]) -> Result<io::DataStream, Box<dyn Error>> {
+    pub fn export<'a>(&self, frames: &'a [Frame]) -> Result<io::DataStream<'a>, Box<dyn Error>> {
         let mut datastream = io::DataStream {
-            version: String::from(env!("CARGO_PKG_VERSION")),
+            version: Cow::Owned(Version::current().to_string()),
             frames: Vec::new(),
         };
 
@@ -31,16 +38,46 @@ impl DataExporter {
 
             for s in f.samples.iter() {
                 match s {
-                    Sample::ObjectDetection(record) => {
-                        let i = record.image.as_ref().map(|i| io::Image {
-                            path: match &i.source {
-                                ImageSource::File(path) => String::from(path.to_str().unwrap()),
-                            },
-                            dimensions: io::ImageDimensions {
-                                width: i.width,
-                                height: i.height,
-                            },
-                        });
+                    Sample::ObjectDetection(record) | Sample::Text(record) => {
+                        // Reject a record of merged channels.
+                        //
+                        // Its channel does not exist in the data, and its
+                        // annotations (and images) no longer belong to any
+                        // single channel.
+                        if !record.merged.is_empty() {
+                            return Err(Box::new(DataExporterError::from(format!(
+                                "{}: cannot export a sample of merged channels",
+                                record.channel
+                            ))));
+                        }
+
+                        let i = match record.image.as_ref() {
+                            Some(i) => Some(io::Image {
+                                path: match &i.source {
+                                    ImageSource::File(path) => match path.to_str() {
+                                        Some(path) => Some(Cow::Borrowed(path)),
+                                        None => {
+                                            return Err(Box::new(DataExporterError::from(format!(
+                                                "{}: invalid path",
+                                                path.display()
+                                            ))))
+                                        }
+                                    },
+                                    ImageSource::Embedded(..) => None,
+                                },
+                                data: match &i.source {
+                                    ImageSource::Embedded(bytes) => {
+                                        Some(Cow::Owned(base64::encode(bytes)))
+                                    }
+                                    ImageSource::File(..) => None,
+                                },
+                                dimensions: io::ImageDimensions {
+                                    width: i.width,
+                                    height: i.height,
+                                },
+                            }),
+                            None => None,
+                        };
 
                         let mut a = Vec::new();
                         for annotations in record.annotations.values() {
@@ -73,20 +110,83 @@ impl DataExporter {
                                             rotation: region.rotation(),
                                         },
                                     },
+                                    BoundingBox::Cuboid(region) => self::cuboid(region),
+                                    BoundingBox::Polygon(region) => io::BoundingBox::Polygon {
+                                        region: io::PolygonRegion {
+                                            points: region
+                                                .points
+                                                .iter()
+                                                .map(|p| io::PolygonRegionPoint { x: p.x, y: p.y })
+                                                .collect(),
+                                        },
+                                    },
                                 };
 
                                 a.push(io::Annotation {
-                                    class: annotation.label.clone(),
+                                    class: Cow::Borrowed(annotation.label.as_str()),
                                     score: annotation.score,
+                                    instance_id: annotation
+                                        .instance_id
+                                        .as_deref()
+                                        .map(Cow::Borrowed),
+                                    text: annotation.text.as_deref().map(Cow::Borrowed),
+                                    bbox3d: annotation.bbox3d.as_ref().map(self::cuboid),
+                                    attributes: self::attributes(&annotation.attributes),
                                     bbox,
                                 })
                             }
                         }
 
-                        samples.push(io::Sample::ObjectDetection {
-                            channel: record.channel.clone(),
-                            image: i.unwrap(),
-                            annotations: a,
+                        let channel = Cow::Borrowed(record.channel.as_str());
+
+                        samples.push(match s {
+                            Sample::Text(..) => io::Sample::Text {
+                                channel,
+                                image: i,
+                                pose: record.pose.as_ref().map(self::pose),
+                                annotations: a,
+                            },
+                            _ => io::Sample::ObjectDetection {
+                                channel,
+                                image: i,
+                                pose: record.pose.as_ref().map(self::pose),
+                                annotations: a,
+                            },
+                        })
+                    }
+                    Sample::Classification(record) => samples.push(io::Sample::Classification {
+                        channel: Cow::Borrowed(&record.channel),
+                        pose: record.pose.as_ref().map(self::pose),
+                        labels: record
+                            .labels
+                            .iter()
+                            .map(|(label, score)| io::Classification {
+                                class: Cow::Borrowed(label.as_str()),
+                                score: *score,
+                            })
+                            .collect(),
+                    }),
+                    Sample::PointCloud(record) => {
+                        let statistics = record
+                            .statistics
+                            .iter()
+                            .map(|s| io::PointStatistics {
+                                class: Cow::Borrowed(s.label.as_str()),
+                                instance_id: s.instance_id.as_deref().map(Cow::Borrowed),
+                                points: s.points,
+                            })
+                            .collect::<Vec<_>>();
+
+                        samples.push(io::Sample::PointCloud {
+                            channel: Cow::Borrowed(&record.channel),
+                            path: Cow::Borrowed(&record.path),
+                            points: record.points,
+                            pose: record.pose.as_ref().map(self::pose),
+                            statistics: if statistics.is_empty() {
+                                None
+                            } else {
+                                Some(statistics)
+                            },
                         })
                     }
                 }
@@ -94,6 +194,8 @@ impl DataExporter {
 
             datastream.frames.push(io::Frame {
                 index: f.index,
+                timestamp: f.timestamp,
+                metadata: f.metadata.clone(),
                 samples,
             });
         }
@@ -102,6 +204,71 @@ impl DataExporter {
     }
 }
 
+/// Convert the attributes of an annotation into its stremf representation.
+///
+/// The attributes are omitted from the output if there are none.
+fn attributes(
+    attributes: &IndexMap<String, Attribute>,
+) -> Option<IndexMap<Cow<'_, str>, io::Attribute<'_>>> {
+    if attributes.is_empty() {
+        return None;
+    }
+
+    Some(
+        attributes
+            .iter()
+            .map(|(key, value)| {
+                let value = match value {
+                    Attribute::Bool(b) => io::Attribute::Bool(*b),
+                    Attribute::Number(n) => io::Attribute::Number(*n),
+                    Attribute::String(s) => io::Attribute::String(Cow::Borrowed(s)),
+                };
+
+                (Cow::Borrowed(key.as_str()), value)
+            })
+            .collect(),
+    )
+}
+
+/// Convert a 3D bounding box into its stremf representation.
+fn cuboid(region: &cuboid::Region) -> io::BoundingBox {
+    io::BoundingBox::Cuboid {
+        region: io::CuboidRegion {
+            center: io::CuboidRegionCenter {
+                x: region.center.x,
+                y: region.center.y,
+                z: region.center.z,
+            },
+            dimensions: io::CuboidRegionDimensions {
+                w: region.width,
+                h: region.height,
+                l: region.length,
+            },
+            rotation: io::CuboidRegionRotation {
+                yaw: region.rotation.yaw,
+                pitch: region.rotation.pitch,
+                roll: region.rotation.roll,
+            },
+        },
+    }
+}
+
+/// Convert the pose of a sensor into its stremf representation.
+fn pose(pose: &Pose) -> io::Pose {
+    io::Pose {
+        translation: io::PoseTranslation {
+            x: pose.translation.x,
+            y: pose.translation.y,
+            z: pose.translation.z,
+        },
+        rotation: io::PoseRotation {
+            yaw: pose.rotation.yaw,
+            pitch: pose.rotation.pitch,
+            roll: pose.rotation.roll,
+        },
+    }
+}
+
 #[derive(Debug, Clone)]
 struct DataExporterError {
     msg: String,
@@ -128,3 +295,100 @@ impl fmt::Display for DataExporterError {
 }
 
 impl Error for DataExporterError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Configuration;
+    use crate::datastream::frame::sample::detections::{DetectionRecord, Image, ImageSource};
+    use crate::datastream::frame::sample::Sample;
+    use crate::datastream::frame::Frame;
+    use crate::datastream::io;
+    use crate::datastream::io::importer::Importer;
+
+    use super::DataExporter;
+
+    #[test]
+    fn roundtrip_without_image() {
+        let source = r#"{"version":"2.0.0","frames":[{"index":0,"samples":[{"type":"@stremf/sample/detection","channel":"cam","annotations":[]}]}]}"#;
+
+        let pattern = String::from("[[:car:]]");
+        let mut config = Configuration::new(&pattern);
+        config.export = true;
+
+        let data: io::DataStream = serde_json::from_str(source).unwrap();
+        let frames = Importer::new(&config).import(data).unwrap().unwrap();
+
+        match &frames[0].samples[0] {
+            Sample::ObjectDetection(record) => assert!(record.image.is_none()),
+            _ => unreachable!(),
+        }
+
+        let exported = DataExporter::new().export(&frames).unwrap();
+        assert_eq!(serde_json::to_string(&exported).unwrap(), source);
+    }
+
+    #[test]
+    fn roundtrip_pointcloud() {
+        let source = r#"{"version":"2.0.0","frames":[{"index":0,"samples":[{"type":"@stremf/sample/pointcloud","channel":"lidar","path":"points/0.bin","points":1024,"pose":{"translation":{"x":1.0,"y":2.0,"z":3.0},"rotation":{"yaw":0.5,"pitch":0.0,"roll":0.0}},"statistics":[{"class":"car","instance_id":"7","points":120},{"class":"pedestrian","points":8}]},{"type":"@stremf/sample/pointcloud","channel":"radar","path":"points/1.bin","points":0}]}]}"#;
+
+        let pattern = String::from("[[:car:]]");
+        let mut config = Configuration::new(&pattern);
+        config.export = true;
+
+        let data: io::DataStream = serde_json::from_str(source).unwrap();
+        let frames = Importer::new(&config).import(data).unwrap().unwrap();
+
+        match &frames[0].samples[0] {
+            Sample::PointCloud(record) => {
+                assert_eq!(record.points, 1024);
+                assert_eq!(record.statistics.len(), 2);
+                assert_eq!(record.statistics[0].label.as_str(), "car");
+                assert_eq!(record.statistics[0].instance_id.as_deref(), Some("7"));
+                assert_eq!(record.statistics[1].instance_id, None);
+            }
+            _ => unreachable!(),
+        }
+
+        // The statistics are omitted from the output if there are none.
+        let exported = DataExporter::new().export(&frames).unwrap();
+        assert_eq!(serde_json::to_string(&exported).unwrap(), source);
+    }
+
+    #[test]
+    fn deterministic() {
+        let source = r#"{"version":"2.0.0","frames":[{"index":0,"metadata":{"weather":"rain","location":"boston","daytime":true,"scene":"a","tags":["x"]},"samples":[{"type":"@stremf/sample/detection","channel":"cam","annotations":[{"class":"car","score":0.9,"attributes":{"visibility":"full","occluded":false,"color":"red","parked":true,"lane":2.0},"bbox":{"type":"@stremf/bbox/aabb","region":{"center":{"x":1.0,"y":2.0},"dimensions":{"w":3.0,"h":4.0}}}}]}]}]}"#;
+
+        let pattern = String::from("[[:car:]]");
+        let mut config = Configuration::new(&pattern);
+        config.export = true;
+
+        // Import and export the data twice, independently.
+        let export = || {
+            let data: io::DataStream = serde_json::from_str(source).unwrap();
+            let frames = Importer::new(&config).import(data).unwrap().unwrap();
+
+            serde_json::to_vec(&DataExporter::new().export(&frames).unwrap()).unwrap()
+        };
+
+        let exported = export();
+        assert_eq!(exported, export());
+        assert_eq!(exported, source.as_bytes());
+    }
+
+    #[test]
+    fn merged_channels() {
+        let record = |channel: &str| {
+            let image = Image::new(ImageSource::File(format!("{}.png", channel).into()), 1, 1);
+            DetectionRecord::new(String::from(channel), Some(image))
+        };
+
+        let mut merged = record("a");
+        merged.merge(record("b"));
+
+        let mut frame = Frame::new(0);
+        frame.samples.push(Sample::ObjectDetection(merged));
+
+        // The images of the merged channels cannot be exported as one sample.
+        assert!(DataExporter::new().export(&[frame]).is_err());
+    }
+}
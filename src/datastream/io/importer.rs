@@ -1,48 +1,130 @@
+use std::borrow::Cow;
+use std::collections::HashSet;
 use std::error::Error;
 use std::fmt;
 use std::path::PathBuf;
 
+use indexmap::IndexMap;
+
 use crate::config::Configuration;
+use crate::datastream::frame::sample::classification::ClassificationRecord;
 use crate::datastream::frame::sample::detections::bbox::region::aa;
+use crate::datastream::frame::sample::detections::bbox::region::cuboid;
 use crate::datastream::frame::sample::detections::bbox::region::oriented;
-use crate::datastream::frame::sample::detections::bbox::region::Point;
+use crate::datastream::frame::sample::detections::bbox::region::polygon;
+use crate::datastream::frame::sample::detections::bbox::region::{Point, Point3};
 use crate::datastream::frame::sample::detections::bbox::BoundingBox;
 use crate::datastream::frame::sample::detections::{
-    Annotation, DetectionRecord, Image, ImageSource,
+    label::Label, Annotation, Attribute, DetectionRecord, Image, ImageSource,
 };
+use crate::datastream::frame::sample::pointcloud::{PointCloudRecord, PointStatistics};
+use crate::datastream::frame::sample::pose::{Pose, Rotation, Translation};
 use crate::datastream::frame::sample::Sample;
 use crate::datastream::frame::Frame;
 
 use super::super::io;
+use super::base64;
+use super::version;
 
 /// A reader for importing STREM-formatted data.
 pub struct Importer<'a> {
     config: &'a Configuration<'a>,
     count: usize,
+
+    /// The number of malformed records skipped.
+    skipped: usize,
+
+    /// The set of labels seen so far.
+    labels: HashSet<Label>,
 }
 
 impl<'a> Importer<'a> {
     /// Create a new [`Importer`].
     pub fn new(config: &'a Configuration<'a>) -> Self {
-        Importer { config, count: 0 }
+        Importer {
+            config,
+            count: 0,
+            skipped: 0,
+            labels: HashSet::new(),
+        }
+    }
+
+    /// The number of malformed records skipped so far.
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// The set of labels imported so far.
+    ///
+    /// The labels are recorded after being resolved against the [`Ontology`],
+    /// if any. Therefore, they are comparable to the labels of a pattern.
+    pub fn labels(&self) -> &HashSet<Label> {
+        &self.labels
+    }
+
+    /// Check if malformed records are skipped rather than rejected.
+    pub fn lenient(&self) -> bool {
+        !self.config.fail_fast
+    }
+
+    /// Reject a malformed record.
+    ///
+    /// If the import is lenient, a warning is printed and the record is
+    /// counted as skipped; otherwise, an error is returned.
+    pub fn reject(&mut self, msg: String) -> Result<(), Box<dyn Error>> {
+        if !self.lenient() {
+            return Err(Box::new(ImporterError::from(msg)));
+        }
+
+        eprintln!("importer: warning: {} (skipped)", msg);
+        self.skipped += 1;
+
+        Ok(())
+    }
+
+    /// Admit a [`Frame`] that is not imported (e.g., built in memory).
+    ///
+    /// The frame is kept as is; only its labels are recorded.
+    pub fn admit(&mut self, frame: &Frame) {
+        for sample in frame.samples.iter() {
+            match sample {
+                Sample::ObjectDetection(record) | Sample::Text(record) => {
+                    self.labels.extend(record.annotations.keys().cloned());
+                }
+                Sample::Classification(record) => self.labels.extend(record.labels.keys().cloned()),
+                Sample::PointCloud(record) => {
+                    self.labels
+                        .extend(record.statistics.iter().map(|s| s.label.clone()));
+                }
+            }
+        }
     }
 
     /// From the [`io::DataStrema`], import a series of [`Frame`].
     ///
     /// This accepts a single deserialized [`io::DataStream`] and transforms it
     /// into a set of [`Frame`].
-    pub fn import(&mut self, data: io::DataStream) -> Result<Option<Vec<Frame>>, Box<dyn Error>> {
-        if data.version != env!("CARGO_PKG_VERSION") {
-            return Err(Box::new(ImporterError::from(format!(
-                "mismatched version... expected v{}",
-                env!("CARGO_PKG_VERSION")
-            ))));
-        }
+    pub fn import(
+        &mut self,
+        data: io::DataStream<'_>,
+    ) -> Result<Option<Vec<Frame>>, Box<dyn Error>> {
+        let data = self.upgrade(data)?;
 
         let mut frames = Vec::new();
 
         for f in data.frames.iter() {
             let mut frame = Frame::new(f.index);
+            frame.timestamp = f.timestamp;
+
+            // Skip this [`f`] if before the starting frame.
+            //
+            // This is checked before the skip count, such that frames are only
+            // skipped from the starting frame onwards.
+            if let Some(from) = self.config.from {
+                if f.index < from {
+                    continue;
+                }
+            }
 
             // Skip this [`f`] if skip count not reached.
             //
@@ -61,29 +143,86 @@ impl<'a> Importer<'a> {
                 }
             }
 
+            frame.metadata = f.metadata.clone();
+
+            // Import no samples if the [`f`] does not satisfy all predicates.
+            //
+            // The frame itself is kept so the indices of a match are unchanged;
+            // however, as it has no samples, no spatial formula holds on it.
+            if let Some(predicates) = &self.config.predicates {
+                if !predicates.iter().all(|p| p.holds(&frame.metadata)) {
+                    frames.push(frame);
+                    continue;
+                }
+            }
+
             for s in f.samples.iter() {
                 let sample = match s {
                     io::Sample::ObjectDetection {
                         channel,
                         image,
+                        pose,
+                        annotations,
+                    }
+                    | io::Sample::Text {
+                        channel,
+                        image,
+                        pose,
                         annotations,
                     } => {
                         if let Some(channels) = &self.config.channels {
-                            if !channels.contains(&channel) {
+                            if !channels.iter().any(|c| *c == channel) {
                                 // The channel from the data is not in the
                                 // specified channels. Therefore, we skip it.
                                 continue;
                             }
                         }
 
-                        let mut record = DetectionRecord::new(
-                            channel.clone(),
-                            Some(Image::new(
-                                ImageSource::File(PathBuf::from(&image.path)),
-                                image.dimensions.width,
-                                image.dimensions.height,
-                            )),
-                        );
+                        // Build the [`Image`] only if it is exported (or copied,
+                        // or published).
+                        //
+                        // The image metadata is never used for matching, so it
+                        // is pure overhead for runs that only report matches.
+                        let image = match image.as_ref().filter(|_| {
+                            self.config.export
+                                || self.config.images.is_some()
+                                || self.config.publish
+                        }) {
+                            Some(image) => {
+                                let source = match (&image.path, &image.data) {
+                                    (Some(path), None) => Some(ImageSource::File(self.path(path))),
+                                    (None, Some(data)) => match base64::decode(data) {
+                                        Ok(bytes) => Some(ImageSource::Embedded(bytes)),
+                                        Err(e) => {
+                                            self.reject(format!(
+                                                "frame {}: image: {}",
+                                                f.index, e
+                                            ))?;
+                                            None
+                                        }
+                                    },
+                                    _ => {
+                                        self.reject(format!(
+                                            "frame {}: image: expected either a `path` or `data`",
+                                            f.index
+                                        ))?;
+                                        None
+                                    }
+                                };
+
+                                source.map(|source| {
+                                    Image::new(
+                                        source,
+                                        image.dimensions.width,
+                                        image.dimensions.height,
+                                    )
+                                })
+                            }
+                            None => None,
+                        };
+
+                        let mut record = DetectionRecord::new(channel.to_string(), image);
+                        record.pose = pose.as_ref().map(self::pose);
 
                         // Add annotations to the [`DetectionRecord`].
                         for a in annotations.iter() {
@@ -107,27 +246,270 @@ impl<'a> Importer<'a> {
                                         region.rotation,
                                     ))
                                 }
+                                io::BoundingBox::Cuboid { region } => {
+                                    BoundingBox::Cuboid(self::cuboid(region))
+                                }
+                                io::BoundingBox::Polygon { region } => {
+                                    if region.points.len() < 3 {
+                                        self.reject(format!(
+                                            "frame {}: a polygon must have at least three points",
+                                            f.index
+                                        ))?;
+                                        continue;
+                                    }
+
+                                    BoundingBox::Polygon(polygon::Region::new(
+                                        region
+                                            .points
+                                            .iter()
+                                            .map(|p| Point::new(p.x, p.y))
+                                            .collect(),
+                                    ))
+                                }
+                            };
+
+                            // Skip annotations outside of the region of
+                            // interest, if any.
+                            if let Some(roi) = &self.config.roi {
+                                if !roi.intersects(&bbox) {
+                                    continue;
+                                }
+                            }
+
+                            let label = self.label(&a.class)?;
+
+                            let mut annotation = Annotation::new(label.clone(), a.score, bbox);
+                            annotation.bbox3d = match &a.bbox3d {
+                                Some(io::BoundingBox::Cuboid { region }) => {
+                                    Some(self::cuboid(region))
+                                }
+                                Some(_) => {
+                                    self.reject(format!(
+                                        "frame {}: `bbox3d` must be a 3D bounding box",
+                                        f.index
+                                    ))?;
+                                    None
+                                }
+                                None => None,
                             };
+                            annotation.instance_id =
+                                a.instance_id.as_ref().map(|id| id.to_string());
+                            annotation.text = a.text.as_ref().map(|text| text.to_string());
+
+                            if let Some(attributes) = &a.attributes {
+                                annotation.attributes = self::attributes(attributes);
+                            }
 
                             record
                                 .annotations
-                                .entry(a.class.clone())
+                                .entry(label)
                                 .or_default()
-                                .push(Annotation::new(a.class.clone(), a.score, bbox));
+                                .push(annotation);
                         }
 
-                        Sample::ObjectDetection(record)
+                        match s {
+                            io::Sample::Text { .. } => Sample::Text(record),
+                            _ => Sample::ObjectDetection(record),
+                        }
+                    }
+                    io::Sample::PointCloud {
+                        channel,
+                        path,
+                        points,
+                        pose,
+                        statistics,
+                    } => {
+                        if let Some(channels) = &self.config.channels {
+                            if !channels.iter().any(|c| *c == channel) {
+                                continue;
+                            }
+                        }
+
+                        let mut record =
+                            PointCloudRecord::new(channel.to_string(), path.to_string(), *points);
+                        record.pose = pose.as_ref().map(self::pose);
+
+                        for s in statistics.iter().flatten() {
+                            record.statistics.push(PointStatistics {
+                                label: self.label(&s.class)?,
+                                instance_id: s.instance_id.as_ref().map(|id| id.to_string()),
+                                points: s.points,
+                            });
+                        }
+
+                        Sample::PointCloud(record)
+                    }
+                    io::Sample::Classification {
+                        channel,
+                        labels,
+                        pose,
+                    } => {
+                        if let Some(channels) = &self.config.channels {
+                            if !channels.iter().any(|c| *c == channel) {
+                                continue;
+                            }
+                        }
+
+                        let mut record = ClassificationRecord::new(channel.to_string());
+                        record.pose = pose.as_ref().map(self::pose);
+
+                        for l in labels.iter() {
+                            record.labels.insert(self.label(&l.class)?, l.score);
+                        }
+
+                        Sample::Classification(record)
                     }
                 };
 
                 frame.samples.push(sample);
             }
 
+            // Suppress duplicate annotations.
+            //
+            // Overlapping detections of the same class otherwise produce
+            // duplicate witnesses during monitoring. This precedes the merge,
+            // as the boxes of different channels are in different image spaces
+            // and cannot be compared.
+            if let Some(threshold) = self.config.nms {
+                for sample in frame.samples.iter_mut() {
+                    if let Sample::ObjectDetection(record) | Sample::Text(record) = sample {
+                        record.suppress(threshold);
+                    }
+                }
+            }
+
+            // Merge the samples of the [`Frame`].
+            //
+            // If requested, the detections from all channels are unioned into
+            // a single [`DetectionRecord`] such that formulas are evaluated
+            // against all channels at once rather than independently.
+            if self.config.merge {
+                frame.samples = self::merge(frame.samples);
+            }
+
             frames.push(frame);
         }
 
         Ok(Some(frames))
     }
+
+    /// Resolve the [`Label`] of a class from the data.
+    ///
+    /// If an [`Ontology`] is provided, the label from the data is replaced with
+    /// its canonical label. An error is returned if the label cannot be
+    /// interned (i.e., too many unique labels were seen).
+    fn label(&mut self, class: &str) -> Result<Label, Box<dyn Error>> {
+        let label = Label::intern(match &self.config.ontology {
+            Some(ontology) => ontology.resolve(class),
+            None => class,
+        })?;
+
+        self.labels.insert(label.clone());
+        Ok(label)
+    }
+
+    /// Resolve the path of an image from the data.
+    ///
+    /// If an image root is provided, a relative path is resolved against it;
+    /// otherwise, the path is kept as-is.
+    fn path(&self, path: &str) -> PathBuf {
+        let path = PathBuf::from(path);
+
+        match self.config.image_root {
+            Some(root) if path.is_relative() => root.join(path),
+            _ => path,
+        }
+    }
+
+    /// Upgrade the [`io::DataStream`] to the current version.
+    ///
+    /// If the version check is forced, the data is always returned as-is.
+    fn upgrade<'b>(&self, data: io::DataStream<'b>) -> Result<io::DataStream<'b>, Box<dyn Error>> {
+        if self.config.force {
+            return Ok(data);
+        }
+
+        version::upgrade(data).map_err(|e| {
+            Box::new(ImporterError::from(format!(
+                "{} (use `--force-version` to override)",
+                e
+            ))) as Box<dyn Error>
+        })
+    }
+}
+
+/// Convert the stremf attributes of an annotation.
+fn attributes(
+    attributes: &IndexMap<Cow<'_, str>, io::Attribute<'_>>,
+) -> IndexMap<String, Attribute> {
+    attributes
+        .iter()
+        .map(|(key, value)| {
+            let value = match value {
+                io::Attribute::Bool(b) => Attribute::Bool(*b),
+                io::Attribute::Number(n) => Attribute::Number(*n),
+                io::Attribute::String(s) => Attribute::String(s.to_string()),
+            };
+
+            (key.to_string(), value)
+        })
+        .collect()
+}
+
+/// Convert a stremf 3D bounding box.
+fn cuboid(region: &io::CuboidRegion) -> cuboid::Region {
+    cuboid::Region::new(
+        Point3::new(region.center.x, region.center.y, region.center.z),
+        region.dimensions.w,
+        region.dimensions.h,
+        region.dimensions.l,
+        Rotation {
+            yaw: region.rotation.yaw,
+            pitch: region.rotation.pitch,
+            roll: region.rotation.roll,
+        },
+    )
+}
+
+/// Convert the stremf pose of a sensor.
+fn pose(pose: &io::Pose) -> Pose {
+    Pose::new(
+        Translation {
+            x: pose.translation.x,
+            y: pose.translation.y,
+            z: pose.translation.z,
+        },
+        Rotation {
+            yaw: pose.rotation.yaw,
+            pitch: pose.rotation.pitch,
+            roll: pose.rotation.roll,
+        },
+    )
+}
+
+/// Merge a set of [`Sample`] into a single [`Sample`] per kind.
+///
+/// The first [`DetectionRecord`] found is used as the base record from which
+/// all other records are merged into. Samples of other kinds are kept as-is.
+fn merge(samples: Vec<Sample>) -> Vec<Sample> {
+    let mut merged: Option<DetectionRecord> = None;
+    let mut others = Vec::new();
+
+    for sample in samples.into_iter() {
+        match sample {
+            Sample::ObjectDetection(record) => match &mut merged {
+                Some(base) => base.merge(record),
+                None => merged = Some(record),
+            },
+            sample => others.push(sample),
+        }
+    }
+
+    merged
+        .into_iter()
+        .map(Sample::ObjectDetection)
+        .chain(others)
+        .collect()
 }
 
 #[derive(Debug, Clone)]
@@ -0,0 +1,723 @@
+//! ROS 1 bag files.
+//!
+//! Robot logs are commonly recorded as ROS bags, where detections are messages
+//! of a topic (e.g., `vision_msgs/Detection2DArray`). A [`Bag`] reads the
+//! records of a bag in order, and converts each detection message of a topic
+//! into an [`io::DataStream`] of a single frame as it is read. Therefore, a bag
+//! is searched incrementally (e.g., online) without converting it first.
+//!
+//! Only uncompressed chunks are supported; a compressed bag must first be
+//! decompressed (e.g., with `rosbag decompress`).
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::io::{Cursor, ErrorKind, Read};
+
+use indexmap::IndexMap;
+
+use self::msg::{Definition, Value};
+
+use super::super::io;
+use super::version::Version;
+
+pub mod msg;
+
+/// The first line of a bag of the supported version.
+const MAGIC: &[u8] = b"#ROSBAG V2.0\n";
+
+/// The message types of detections that can be converted.
+const TYPES: [&str; 1] = ["vision_msgs/Detection2DArray"];
+
+/// The opcode of a record of a chunk.
+const CHUNK: u8 = 0x05;
+
+/// The opcode of a record of a connection.
+const CONNECTION: u8 = 0x07;
+
+/// The opcode of a record of a message.
+const MESSAGE: u8 = 0x02;
+
+/// A record of a bag.
+struct Record {
+    header: HashMap<String, Vec<u8>>,
+    data: Vec<u8>,
+}
+
+impl Record {
+    /// Read the next record of `reader`, if any.
+    fn read(reader: &mut impl Read) -> Result<Option<Self>, Box<dyn Error>> {
+        let mut length = [0; 4];
+
+        match reader.read_exact(&mut length) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(Box::new(e)),
+        }
+
+        let header = self::fields(&self::bytes(reader, u32::from_le_bytes(length))?)?;
+
+        reader.read_exact(&mut length)?;
+        let data = self::bytes(reader, u32::from_le_bytes(length))?;
+
+        Ok(Some(Record { header, data }))
+    }
+
+    /// The opcode of the record.
+    fn op(&self) -> Option<u8> {
+        self.header.get("op").and_then(|op| op.first()).copied()
+    }
+
+    /// A field of the header as text.
+    fn text(&self, name: &str) -> Result<String, Box<dyn Error>> {
+        match self.header.get(name) {
+            Some(value) => Ok(String::from_utf8_lossy(value).into_owned()),
+            None => Err(Box::new(RosbagError::from(format!(
+                "record without a `{}` field",
+                name
+            )))),
+        }
+    }
+
+    /// A field of the header as a number of `N` bytes.
+    fn number<const N: usize>(&self, name: &str) -> Result<[u8; N], Box<dyn Error>> {
+        self.header
+            .get(name)
+            .and_then(|value| value.as_slice().try_into().ok())
+            .ok_or(Box::new(RosbagError::from(format!(
+                "record without a valid `{}` field",
+                name
+            ))))
+    }
+}
+
+/// A reader of the detection messages of a bag.
+///
+/// The detections of a single topic are read: either the topic selected or,
+/// otherwise, the first topic of a supported type. Each message is a frame,
+/// indexed from zero, of the channel named by the topic.
+pub struct Bag<R: Read> {
+    reader: R,
+
+    /// The topic read, once known.
+    topic: Option<String>,
+
+    /// The definition of each connection of the topic, by its identifier.
+    connections: HashMap<u32, Definition>,
+
+    /// The records of the chunk being read, if any.
+    chunk: Option<Cursor<Vec<u8>>>,
+
+    /// The index of the next frame.
+    index: usize,
+
+    /// Whether the bag has ended (or failed).
+    ended: bool,
+}
+
+impl<R: Read> Bag<R> {
+    /// Create a new [`Bag`] of `reader`, reading the detections of `topic`.
+    pub fn new(mut reader: R, topic: Option<String>) -> Result<Self, Box<dyn Error>> {
+        let mut magic = [0; MAGIC.len()];
+
+        if reader.read_exact(&mut magic).is_err() || magic != MAGIC {
+            return Err(Box::new(RosbagError::from(
+                "not a bag (expected version 2.0)",
+            )));
+        }
+
+        Ok(Bag {
+            reader,
+            topic,
+            connections: HashMap::new(),
+            chunk: None,
+            index: 0,
+            ended: false,
+        })
+    }
+
+    /// Read the next detection message as an [`io::DataStream`].
+    ///
+    /// A connection or message that is malformed is rejected, as the records
+    /// after it may still be read; otherwise, an error ends the bag.
+    fn read(&mut self) -> Result<Option<io::Converted>, Box<dyn Error>> {
+        loop {
+            let record = match self.chunk.as_mut() {
+                Some(chunk) => match Record::read(chunk)? {
+                    Some(record) => record,
+                    None => {
+                        self.chunk = None;
+                        continue;
+                    }
+                },
+                None => match Record::read(&mut self.reader)? {
+                    Some(record) => record,
+                    None => return Ok(None),
+                },
+            };
+
+            match record.op() {
+                Some(CHUNK) => {
+                    let compression = record.text("compression")?;
+
+                    if compression != "none" {
+                        return Err(Box::new(RosbagError::from(format!(
+                            "`{}` compressed chunks are not supported (decompress the bag first)",
+                            compression
+                        ))));
+                    }
+
+                    self.chunk = Some(Cursor::new(record.data));
+                }
+                Some(CONNECTION) => {
+                    if let Err(e) = self.connect(&record) {
+                        return Ok(Some(io::Converted::Rejected(e.to_string())));
+                    }
+                }
+                Some(MESSAGE) => match self.message(&record) {
+                    Ok(Some(data)) => return Ok(Some(io::Converted::Document(data))),
+                    Ok(None) => {}
+                    Err(e) => return Ok(Some(io::Converted::Rejected(e.to_string()))),
+                },
+                _ => {}
+            }
+        }
+    }
+
+    /// Convert a message into a single frame, if of the topic read.
+    fn message(
+        &mut self,
+        record: &Record,
+    ) -> Result<Option<io::DataStream<'static>>, Box<dyn Error>> {
+        let id = u32::from_le_bytes(record.number("conn")?);
+
+        let Some(definition) = self.connections.get(&id) else {
+            return Ok(None);
+        };
+
+        let time = record.number::<8>("time")?;
+        let secs = u32::from_le_bytes(time[..4].try_into().unwrap());
+        let nsecs = u32::from_le_bytes(time[4..].try_into().unwrap());
+
+        let message = definition.decode(&record.data)?;
+
+        Ok(Some(
+            self.convert(&message, secs as f64 + nsecs as f64 * 1e-9),
+        ))
+    }
+
+    /// Record a connection, if of the topic read.
+    ///
+    /// A connection may be recorded more than once (e.g., within each chunk
+    /// and again at the end of the bag).
+    fn connect(&mut self, record: &Record) -> Result<(), Box<dyn Error>> {
+        let id = u32::from_le_bytes(record.number("conn")?);
+
+        if self.connections.contains_key(&id) {
+            return Ok(());
+        }
+
+        let topic = record.text("topic")?;
+        let fields = self::fields(&record.data)?;
+
+        let text = |name: &str| {
+            fields
+                .get(name)
+                .map(|value| String::from_utf8_lossy(value).into_owned())
+                .unwrap_or_default()
+        };
+
+        let kind = text("type");
+
+        if !TYPES.contains(&kind.as_str()) {
+            return Ok(());
+        }
+
+        match &self.topic {
+            Some(selected) if *selected != topic => return Ok(()),
+            Some(_) => {}
+            None => self.topic = Some(topic),
+        }
+
+        self.connections
+            .insert(id, Definition::parse(&kind, &text("message_definition"))?);
+
+        Ok(())
+    }
+
+    /// Convert a detection message received at `time` into a single frame.
+    ///
+    /// The class of a detection is its hypothesis of the highest score, and a
+    /// detection without any is skipped. The time of the message is that of
+    /// its header, if set, and otherwise, the time it was recorded.
+    fn convert(&mut self, message: &Value, time: f64) -> io::DataStream<'static> {
+        let number = |value: &Value, paths: &[&str]| {
+            paths
+                .iter()
+                .find_map(|path| value.get(path).and_then(Value::as_f64))
+        };
+
+        let text = |value: &Value, paths: &[&str]| {
+            paths
+                .iter()
+                .find_map(|path| value.get(path).and_then(Value::as_text))
+                .filter(|text| !text.is_empty())
+        };
+
+        let mut annotations = Vec::new();
+
+        for detection in message
+            .get("detections")
+            .and_then(Value::as_array)
+            .unwrap_or_default()
+        {
+            let best = detection
+                .get("results")
+                .and_then(Value::as_array)
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|result| {
+                    let class = text(result, &["id", "hypothesis.class_id"])?;
+                    let score = number(result, &["score", "hypothesis.score"])?;
+
+                    Some((class, score))
+                })
+                .max_by(|a, b| a.1.total_cmp(&b.1));
+
+            let Some((class, score)) = best else {
+                continue;
+            };
+
+            let x = number(detection, &["bbox.center.x", "bbox.center.position.x"]);
+            let y = number(detection, &["bbox.center.y", "bbox.center.position.y"]);
+            let w = number(detection, &["bbox.size_x"]);
+            let h = number(detection, &["bbox.size_y"]);
+            let rotation = number(detection, &["bbox.center.theta"]).unwrap_or_default();
+
+            let (Some(x), Some(y), Some(w), Some(h)) = (x, y, w, h) else {
+                continue;
+            };
+
+            annotations.push(io::Annotation {
+                class: Cow::Owned(class),
+                score,
+                instance_id: text(detection, &["tracking_id", "id"]).map(Cow::Owned),
+                text: None,
+                attributes: None,
+                bbox: match rotation == 0.0 {
+                    true => io::BoundingBox::AxisAligned {
+                        region: io::AxisAlignedRegion {
+                            center: io::AxisAlignedRegionCenter { x, y },
+                            dimensions: io::AxisAlignedRegionDimensions { w, h },
+                        },
+                    },
+                    false => io::BoundingBox::Oriented {
+                        region: io::OrientedRegion {
+                            center: io::OrientedRegionCenterPoint { x, y },
+                            dimensions: io::OrientedRegionDimensions { w, h },
+                            rotation,
+                        },
+                    },
+                },
+                bbox3d: None,
+            });
+        }
+
+        let stamp = number(message, &["header.stamp"]).filter(|t| *t > 0.0);
+
+        let frame = io::Frame {
+            index: self.index,
+            timestamp: Some(stamp.unwrap_or(time)),
+            metadata: IndexMap::new(),
+            samples: vec![io::Sample::ObjectDetection {
+                channel: Cow::Owned(self.topic.clone().unwrap_or_default()),
+                image: None,
+                pose: None,
+                annotations,
+            }],
+        };
+
+        self.index += 1;
+
+        io::DataStream {
+            version: Cow::Owned(Version::current().to_string()),
+            frames: vec![frame],
+        }
+    }
+}
+
+impl<R: Read> Iterator for Bag<R> {
+    type Item = Result<io::Converted, Box<dyn Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.ended {
+            return None;
+        }
+
+        match self.read() {
+            Ok(Some(data)) => Some(Ok(data)),
+            Ok(None) => {
+                self.ended = true;
+                None
+            }
+            Err(e) => {
+                self.ended = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Read `length` bytes of `reader`.
+fn bytes(reader: &mut impl Read, length: u32) -> Result<Vec<u8>, Box<dyn Error>> {
+    let mut buffer = Vec::new();
+    reader.take(length as u64).read_to_end(&mut buffer)?;
+
+    if buffer.len() != length as usize {
+        return Err(Box::new(RosbagError::from("bag ends unexpectedly")));
+    }
+
+    Ok(buffer)
+}
+
+/// Parse the `name=value` fields of a header, each prefixed by its length.
+fn fields(mut header: &[u8]) -> Result<HashMap<String, Vec<u8>>, Box<dyn Error>> {
+    let mut fields = HashMap::new();
+
+    while !header.is_empty() {
+        let length = header
+            .get(..4)
+            .map(|n| u32::from_le_bytes(n.try_into().unwrap()) as usize)
+            .filter(|n| header.len() >= 4 + n)
+            .ok_or(RosbagError::from("malformed record header"))?;
+
+        let field = &header[4..(4 + length)];
+        let split = field
+            .iter()
+            .position(|b| *b == b'=')
+            .ok_or(RosbagError::from("malformed record header"))?;
+
+        fields.insert(
+            String::from_utf8_lossy(&field[..split]).into_owned(),
+            field[(split + 1)..].to_vec(),
+        );
+
+        header = &header[(4 + length)..];
+    }
+
+    Ok(fields)
+}
+
+#[derive(Debug, Clone)]
+struct RosbagError {
+    msg: String,
+}
+
+impl From<&str> for RosbagError {
+    fn from(msg: &str) -> Self {
+        RosbagError {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for RosbagError {
+    fn from(msg: String) -> Self {
+        RosbagError { msg }
+    }
+}
+
+impl fmt::Display for RosbagError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "rosbag: {}", self.msg)
+    }
+}
+
+impl Error for RosbagError {}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use crate::config::Configuration;
+    use crate::datastream::frame::sample::detections::bbox::BoundingBox;
+    use crate::datastream::frame::sample::detections::label::Label;
+    use crate::datastream::frame::sample::Sample;
+    use crate::datastream::io::importer::Importer;
+    use crate::datastream::DataStream;
+
+    const DEFINITION: &str = "std_msgs/Header header
+vision_msgs/Detection2D[] detections
+================================================================================
+MSG: std_msgs/Header
+uint32 seq
+time stamp
+string frame_id
+================================================================================
+MSG: vision_msgs/Detection2D
+ObjectHypothesisWithPose[] results
+BoundingBox2D bbox
+string tracking_id
+================================================================================
+MSG: vision_msgs/ObjectHypothesisWithPose
+ObjectHypothesis hypothesis
+================================================================================
+MSG: vision_msgs/ObjectHypothesis
+string class_id
+float64 score
+================================================================================
+MSG: vision_msgs/BoundingBox2D
+Pose2D center
+float64 size_x
+float64 size_y
+================================================================================
+MSG: vision_msgs/Pose2D
+Point2D position
+float64 theta
+================================================================================
+MSG: vision_msgs/Point2D
+float64 x
+float64 y
+";
+
+    fn string(s: &str) -> Vec<u8> {
+        [&(s.len() as u32).to_le_bytes()[..], s.as_bytes()].concat()
+    }
+
+    fn fields(fields: &[(&str, &[u8])]) -> Vec<u8> {
+        fields
+            .iter()
+            .flat_map(|(k, v)| {
+                let field = [k.as_bytes(), b"=", v].concat();
+                [&(field.len() as u32).to_le_bytes()[..], &field].concat()
+            })
+            .collect()
+    }
+
+    fn record(header: &[(&str, &[u8])], data: &[u8]) -> Vec<u8> {
+        let header = fields(header);
+
+        [
+            &(header.len() as u32).to_le_bytes()[..],
+            &header,
+            &(data.len() as u32).to_le_bytes(),
+            data,
+        ]
+        .concat()
+    }
+
+    /// A message of a single detection of `class` at `(x, y)`.
+    fn message(stamp: u32, class: &str, x: f64, theta: f64) -> Vec<u8> {
+        let mut data = Vec::new();
+
+        data.extend(0u32.to_le_bytes());
+        data.extend(stamp.to_le_bytes());
+        data.extend(0u32.to_le_bytes());
+        data.extend(string("cam"));
+
+        data.extend(1u32.to_le_bytes());
+        data.extend(2u32.to_le_bytes());
+        data.extend(string("background"));
+        data.extend(0.1f64.to_le_bytes());
+        data.extend(string(class));
+        data.extend(0.9f64.to_le_bytes());
+
+        for n in [x, 20.0, theta, 4.0, 2.0] {
+            data.extend(n.to_le_bytes());
+        }
+
+        data.extend(string("7"));
+
+        data
+    }
+
+    #[test]
+    fn convert() {
+        let connection = record(
+            &[
+                ("op", &[0x07]),
+                ("conn", &0u32.to_le_bytes()),
+                ("topic", b"/det"),
+            ],
+            &fields(&[
+                ("type", b"vision_msgs/Detection2DArray"),
+                ("message_definition", DEFINITION.as_bytes()),
+            ]),
+        );
+
+        let messages = [message(0, "car", 10.0, 0.0), message(42, "car", 12.0, 0.5)]
+            .iter()
+            .enumerate()
+            .flat_map(|(i, data)| {
+                let time = [(i as u32 + 5).to_le_bytes(), 0u32.to_le_bytes()].concat();
+                record(
+                    &[
+                        ("op", &[0x02]),
+                        ("conn", &0u32.to_le_bytes()),
+                        ("time", &time),
+                    ],
+                    data,
+                )
+            })
+            .collect::<Vec<u8>>();
+
+        let chunk = record(
+            &[("op", &[0x05]), ("compression", b"none")],
+            &[connection.clone(), messages].concat(),
+        );
+
+        let bag = [super::MAGIC, &chunk, &connection].concat();
+
+        let pattern = String::from("[[:car:]]");
+        let config = Configuration::new(&pattern);
+
+        let mut importer = Importer::new(&config);
+        let mut datastream = DataStream::from_rosbag(Cursor::new(bag), None).unwrap();
+
+        let mut frames = Vec::new();
+
+        while let Some(batch) = datastream.request(&mut importer).unwrap() {
+            frames.extend(batch);
+        }
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].timestamp, Some(5.0));
+        assert_eq!(frames[1].timestamp, Some(42.0));
+
+        let Sample::ObjectDetection(detections) = &frames[1].samples[0] else {
+            panic!("expected a detection sample");
+        };
+
+        assert_eq!(detections.channel, "/det");
+
+        let car = &detections.annotations[&Label::from("car")][0];
+        assert_eq!(car.instance_id.as_deref(), Some("7"));
+        assert!(matches!(car.bbox, BoundingBox::Oriented(_)));
+
+        let chunk = record(&[("op", &[0x05]), ("compression", b"bz2")], &[]);
+        let bag = [super::MAGIC, &chunk].concat();
+
+        let mut datastream = DataStream::from_rosbag(Cursor::new(bag), None).unwrap();
+        assert!(datastream.request(&mut importer).is_err());
+    }
+
+    #[test]
+    fn malformed() {
+        let connection = record(
+            &[
+                ("op", &[0x07]),
+                ("conn", &0u32.to_le_bytes()),
+                ("topic", b"/det"),
+            ],
+            &fields(&[
+                ("type", b"vision_msgs/Detection2DArray"),
+                ("message_definition", DEFINITION.as_bytes()),
+            ]),
+        );
+
+        // The second message ends before its detections.
+        let message = message(0, "car", 10.0, 0.0);
+        let messages = [&message[..], &message[..8], &message[..]]
+            .iter()
+            .flat_map(|data| {
+                record(
+                    &[
+                        ("op", &[0x02]),
+                        ("conn", &0u32.to_le_bytes()),
+                        ("time", &[0; 8]),
+                    ],
+                    data,
+                )
+            })
+            .collect::<Vec<u8>>();
+
+        let bag = [super::MAGIC, &connection, &messages].concat();
+
+        let pattern = String::from("[[:car:]]");
+        let mut config = Configuration::new(&pattern);
+
+        // The message is skipped, unless the import is strict.
+        let mut importer = Importer::new(&config);
+        let mut datastream = DataStream::from_rosbag(Cursor::new(bag.clone()), None).unwrap();
+
+        let mut frames = Vec::new();
+
+        while let Some(batch) = datastream.request(&mut importer).unwrap() {
+            frames.extend(batch);
+        }
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(importer.skipped(), 1);
+
+        config.fail_fast = true;
+
+        let mut importer = Importer::new(&config);
+        let mut datastream = DataStream::from_rosbag(Cursor::new(bag), None).unwrap();
+
+        assert!(datastream.request(&mut importer).unwrap().is_some());
+        assert!(datastream.request(&mut importer).is_err());
+    }
+
+    #[test]
+    fn crafted() {
+        // Read a bag of one message of the `definition`, returning the number
+        // of frames read and of records skipped.
+        let read = |definition: &str, data: &[u8]| {
+            let connection = record(
+                &[
+                    ("op", &[0x07]),
+                    ("conn", &0u32.to_le_bytes()),
+                    ("topic", b"/det"),
+                ],
+                &fields(&[
+                    ("type", b"vision_msgs/Detection2DArray"),
+                    ("message_definition", definition.as_bytes()),
+                ]),
+            );
+
+            let message = record(
+                &[
+                    ("op", &[0x02]),
+                    ("conn", &0u32.to_le_bytes()),
+                    ("time", &[0; 8]),
+                ],
+                data,
+            );
+
+            let bag = [super::MAGIC, &connection, &message].concat();
+
+            let pattern = String::from("[[:car:]]");
+            let config = Configuration::new(&pattern);
+
+            let mut importer = Importer::new(&config);
+            let mut datastream = DataStream::from_rosbag(Cursor::new(bag), None).unwrap();
+
+            let mut frames = 0;
+
+            while let Some(batch) = datastream.request(&mut importer).unwrap() {
+                frames += batch.len();
+            }
+
+            (frames, importer.skipped())
+        };
+
+        // A type that contains itself is rejected, rather than decoded
+        // without end.
+        assert_eq!(read("Detection2DArray a", &[]), (0, 1));
+
+        // An array longer than the rest of the message is rejected, rather
+        // than allocated.
+        let definition = "vision_msgs/E[] a
+================================================================================
+MSG: vision_msgs/E
+";
+        assert_eq!(read(definition, &[0xff, 0xff, 0xff, 0x7f]), (0, 1));
+
+        // An array of empty messages is bounded by the rest of the message.
+        assert_eq!(
+            read(definition, &[0x02, 0x00, 0x00, 0x00, 0x00, 0x00]),
+            (1, 0)
+        );
+    }
+}
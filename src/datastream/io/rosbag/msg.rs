@@ -0,0 +1,468 @@
+//! ROS message definitions and their decoding.
+//!
+//! A bag stores the full definition of each message type it records (i.e., the
+//! fields of the type and of each nested type). Messages are decoded against
+//! these definitions rather than fixed layouts, such that differing versions of
+//! a message package (e.g., `vision_msgs`) are read alike.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+/// A builtin type of a field.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Primitive {
+    Bool,
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Int64,
+    UInt64,
+    Float32,
+    Float64,
+    String,
+    Time,
+    Duration,
+}
+
+impl Primitive {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "bool" => Some(Primitive::Bool),
+            "int8" => Some(Primitive::Int8),
+            "uint8" | "byte" | "char" => Some(Primitive::UInt8),
+            "int16" => Some(Primitive::Int16),
+            "uint16" => Some(Primitive::UInt16),
+            "int32" => Some(Primitive::Int32),
+            "uint32" => Some(Primitive::UInt32),
+            "int64" => Some(Primitive::Int64),
+            "uint64" => Some(Primitive::UInt64),
+            "float32" => Some(Primitive::Float32),
+            "float64" => Some(Primitive::Float64),
+            "string" => Some(Primitive::String),
+            "time" => Some(Primitive::Time),
+            "duration" => Some(Primitive::Duration),
+            _ => None,
+        }
+    }
+
+    /// The number of bytes of a serialized value (at least, if a string).
+    fn size(&self) -> usize {
+        match self {
+            Primitive::Bool | Primitive::Int8 | Primitive::UInt8 => 1,
+            Primitive::Int16 | Primitive::UInt16 => 2,
+            Primitive::Int32 | Primitive::UInt32 | Primitive::Float32 | Primitive::String => 4,
+            Primitive::Int64
+            | Primitive::UInt64
+            | Primitive::Float64
+            | Primitive::Time
+            | Primitive::Duration => 8,
+        }
+    }
+}
+
+/// The type of a field.
+#[derive(Clone, Debug, PartialEq)]
+enum Kind {
+    Primitive(Primitive),
+
+    /// A nested message, by its full name (e.g., `std_msgs/Header`).
+    Message(String),
+}
+
+/// A field of a message definition.
+#[derive(Clone, Debug, PartialEq)]
+struct Field {
+    name: String,
+    kind: Kind,
+
+    /// The length of the field, if an array (`None` if of variable length).
+    array: Option<Option<usize>>,
+}
+
+/// A decoded value of a field.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    String(String),
+
+    /// A time (or duration) in seconds.
+    Time(f64),
+
+    Array(Vec<Value>),
+
+    /// An array of bytes (e.g., the pixels of an image), which is skipped.
+    Bytes,
+
+    Message(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// Find the value at a `.`-separated path of field names.
+    pub fn get(&self, path: &str) -> Option<&Value> {
+        path.split('.').try_fold(self, |value, name| match value {
+            Value::Message(fields) => fields.iter().find(|(n, _)| n == name).map(|(_, v)| v),
+            _ => None,
+        })
+    }
+
+    /// The value as a number, if numeric.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Int(n) => Some(*n as f64),
+            Value::UInt(n) => Some(*n as f64),
+            Value::Float(n) | Value::Time(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// The value as text, if a string or an integer (e.g., an identifier).
+    pub fn as_text(&self) -> Option<String> {
+        match self {
+            Value::String(s) => Some(s.clone()),
+            Value::Int(n) => Some(n.to_string()),
+            Value::UInt(n) => Some(n.to_string()),
+            _ => None,
+        }
+    }
+
+    /// The elements of the value, if an array.
+    pub fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+/// The definitions of a message type and of each type nested within it.
+#[derive(Clone, Debug)]
+pub struct Definition {
+    /// The full name of the message type.
+    name: String,
+
+    messages: HashMap<String, Vec<Field>>,
+
+    /// The fewest bytes of a serialized message of each type.
+    sizes: HashMap<String, usize>,
+}
+
+impl Definition {
+    /// Parse the `message_definition` of a connection of type `name`.
+    ///
+    /// The definition of the type is followed by those of its nested types,
+    /// each introduced by a `MSG: package/Type` line after a separator. A type
+    /// that contains itself (i.e., of a message without end) is rejected.
+    pub fn parse(name: &str, text: &str) -> Result<Self, Box<dyn Error>> {
+        let mut messages = HashMap::new();
+        let mut current = name.to_string();
+        let mut fields = Vec::new();
+
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+
+            if line.is_empty() {
+                continue;
+            }
+
+            if line.starts_with("==") {
+                messages.insert(std::mem::take(&mut current), std::mem::take(&mut fields));
+                continue;
+            }
+
+            if let Some(next) = line.strip_prefix("MSG:") {
+                current = next.trim().to_string();
+                continue;
+            }
+
+            let (kind, rest) =
+                line.split_once(char::is_whitespace)
+                    .ok_or(MsgError::from(format!(
+                        "{}: malformed field `{}`",
+                        current, line
+                    )))?;
+
+            // Skip a constant.
+            //
+            // Constants (e.g., `uint8 LEFT=1`) are not serialized.
+            if rest.contains('=') {
+                continue;
+            }
+
+            fields.push(Field {
+                name: rest.trim().to_string(),
+                kind: self::kind(kind, &current),
+                array: self::array(kind)?,
+            });
+        }
+
+        messages.insert(current, fields);
+
+        let mut sizes = HashMap::new();
+
+        for name in messages.keys() {
+            self::size(name, &messages, &mut sizes, &mut Vec::new())?;
+        }
+
+        Ok(Definition {
+            name: name.to_string(),
+            messages,
+            sizes,
+        })
+    }
+
+    /// Decode a serialized message of this type.
+    pub fn decode(&self, data: &[u8]) -> Result<Value, Box<dyn Error>> {
+        let mut cursor = Cursor { data, offset: 0 };
+
+        self.message(&self.name, &mut cursor)
+    }
+
+    fn message(&self, name: &str, cursor: &mut Cursor) -> Result<Value, Box<dyn Error>> {
+        let fields = self
+            .messages
+            .get(name)
+            .ok_or(MsgError::from(format!("unknown message type `{}`", name)))?;
+
+        let mut values = Vec::with_capacity(fields.len());
+
+        for field in fields.iter() {
+            let value = match field.array {
+                None => self.value(&field.kind, cursor)?,
+                Some(length) => {
+                    let length = match length {
+                        Some(n) => n,
+                        None => cursor.u32()? as usize,
+                    };
+
+                    // Check that the elements fit within the rest of the data.
+                    //
+                    // Otherwise, a malformed length would allocate its elements
+                    // before the data is found to end. An element of no bytes
+                    // (e.g., an empty message) is counted as one, such that
+                    // its length is bounded as well.
+                    let size = match &field.kind {
+                        Kind::Primitive(primitive) => primitive.size(),
+                        Kind::Message(name) => self.sizes.get(name).copied().unwrap_or_default(),
+                    };
+
+                    if length
+                        .checked_mul(size.max(1))
+                        .is_none_or(|n| n > cursor.remaining())
+                    {
+                        return Err(Box::new(MsgError::from("message ends unexpectedly")));
+                    }
+
+                    match field.kind {
+                        Kind::Primitive(Primitive::UInt8 | Primitive::Int8) => {
+                            cursor.take(length)?;
+                            Value::Bytes
+                        }
+                        _ => Value::Array(
+                            (0..length)
+                                .map(|_| self.value(&field.kind, cursor))
+                                .collect::<Result<Vec<_>, _>>()?,
+                        ),
+                    }
+                }
+            };
+
+            values.push((field.name.clone(), value));
+        }
+
+        Ok(Value::Message(values))
+    }
+
+    fn value(&self, kind: &Kind, cursor: &mut Cursor) -> Result<Value, Box<dyn Error>> {
+        let primitive = match kind {
+            Kind::Primitive(primitive) => primitive,
+            Kind::Message(name) => return self.message(name, cursor),
+        };
+
+        Ok(match primitive {
+            Primitive::Bool => Value::Bool(cursor.take(1)?[0] != 0),
+            Primitive::Int8 => Value::Int(cursor.take(1)?[0] as i8 as i64),
+            Primitive::UInt8 => Value::UInt(cursor.take(1)?[0] as u64),
+            Primitive::Int16 => Value::Int(i16::from_le_bytes(cursor.array()?) as i64),
+            Primitive::UInt16 => Value::UInt(u16::from_le_bytes(cursor.array()?) as u64),
+            Primitive::Int32 => Value::Int(i32::from_le_bytes(cursor.array()?) as i64),
+            Primitive::UInt32 => Value::UInt(cursor.u32()? as u64),
+            Primitive::Int64 => Value::Int(i64::from_le_bytes(cursor.array()?)),
+            Primitive::UInt64 => Value::UInt(u64::from_le_bytes(cursor.array()?)),
+            Primitive::Float32 => Value::Float(f32::from_le_bytes(cursor.array()?) as f64),
+            Primitive::Float64 => Value::Float(f64::from_le_bytes(cursor.array()?)),
+            Primitive::String => {
+                let length = cursor.u32()? as usize;
+                Value::String(String::from_utf8_lossy(cursor.take(length)?).into_owned())
+            }
+            Primitive::Time => {
+                let (secs, nsecs) = (cursor.u32()?, cursor.u32()?);
+                Value::Time(secs as f64 + nsecs as f64 * 1e-9)
+            }
+            Primitive::Duration => {
+                let secs = i32::from_le_bytes(cursor.array()?);
+                let nsecs = i32::from_le_bytes(cursor.array()?);
+                Value::Time(secs as f64 + nsecs as f64 * 1e-9)
+            }
+        })
+    }
+}
+
+/// Find the fewest bytes of a serialized message of type `name`.
+///
+/// The sizes found are kept in `sizes`, and `path` is the types that contain
+/// the type, such that a type that contains itself is rejected. A type that
+/// is not defined has no bytes (and fails to decode instead).
+fn size(
+    name: &str,
+    messages: &HashMap<String, Vec<Field>>,
+    sizes: &mut HashMap<String, usize>,
+    path: &mut Vec<String>,
+) -> Result<usize, Box<dyn Error>> {
+    if let Some(size) = sizes.get(name) {
+        return Ok(*size);
+    }
+
+    if path.iter().any(|n| n == name) {
+        return Err(Box::new(MsgError::from(format!(
+            "recursive message type `{}`",
+            name
+        ))));
+    }
+
+    let Some(fields) = messages.get(name) else {
+        return Ok(0);
+    };
+
+    path.push(name.to_string());
+
+    let mut total: usize = 0;
+
+    for field in fields.iter() {
+        let size = match &field.kind {
+            Kind::Primitive(primitive) => primitive.size(),
+            Kind::Message(nested) => self::size(nested, messages, sizes, path)?,
+        };
+
+        total = total.saturating_add(match field.array {
+            None => size,
+            Some(None) => 4,
+            Some(Some(n)) => n.saturating_mul(size),
+        });
+    }
+
+    path.pop();
+    sizes.insert(name.to_string(), total);
+
+    Ok(total)
+}
+
+/// Resolve the type of a field declared within the message `current`.
+///
+/// A nested type without a package is of the same package, except for
+/// `Header`, which is always `std_msgs/Header`.
+fn kind(declared: &str, current: &str) -> Kind {
+    let name = declared.split('[').next().unwrap_or_default();
+
+    if let Some(primitive) = Primitive::parse(name) {
+        return Kind::Primitive(primitive);
+    }
+
+    if name.contains('/') {
+        return Kind::Message(name.to_string());
+    }
+
+    if name == "Header" {
+        return Kind::Message(String::from("std_msgs/Header"));
+    }
+
+    match current.split_once('/') {
+        Some((package, _)) => Kind::Message(format!("{}/{}", package, name)),
+        None => Kind::Message(name.to_string()),
+    }
+}
+
+/// The length of an array type (e.g., `float64[36]` or `uint8[]`), if any.
+fn array(declared: &str) -> Result<Option<Option<usize>>, Box<dyn Error>> {
+    let Some((_, length)) = declared.split_once('[') else {
+        return Ok(None);
+    };
+
+    match length.trim_end_matches(']') {
+        "" => Ok(Some(None)),
+        n => match n.parse::<usize>() {
+            Ok(n) => Ok(Some(Some(n))),
+            Err(_) => Err(Box::new(MsgError::from(format!(
+                "malformed array type `{}`",
+                declared
+            )))),
+        },
+    }
+}
+
+/// A reader of serialized data.
+struct Cursor<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], Box<dyn Error>> {
+        let end = self
+            .offset
+            .checked_add(n)
+            .filter(|end| *end <= self.data.len())
+            .ok_or(MsgError::from("message ends unexpectedly"))?;
+
+        let bytes = &self.data[self.offset..end];
+        self.offset = end;
+
+        Ok(bytes)
+    }
+
+    /// The number of bytes not yet read.
+    fn remaining(&self) -> usize {
+        self.data.len() - self.offset
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], Box<dyn Error>> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn u32(&mut self) -> Result<u32, Box<dyn Error>> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct MsgError {
+    msg: String,
+}
+
+impl From<&str> for MsgError {
+    fn from(msg: &str) -> Self {
+        MsgError {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for MsgError {
+    fn from(msg: String) -> Self {
+        MsgError { msg }
+    }
+}
+
+impl fmt::Display for MsgError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "msg: {}", self.msg)
+    }
+}
+
+impl Error for MsgError {}
@@ -0,0 +1,190 @@
+//! Versioning of the STREM format.
+//!
+//! Each STREM-formatted file declares the version of the schema it was written
+//! with. This module determines whether a file may be read as-is and, if not,
+//! provides the adapters necessary to upgrade the file to the current version.
+//!
+//! The schema version is independent of the crate version. Prior to `v2`, files
+//! were stamped with the version of the crate that wrote them; all such `0.x`
+//! versions correspond to `v1` of the schema.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use super::DataStream;
+
+/// A function that upgrades a [`DataStream`] to the current version.
+pub type Adapter = for<'a> fn(DataStream<'a>) -> Result<DataStream<'a>, Box<dyn Error>>;
+
+/// A semantic version (i.e., `MAJOR.MINOR.PATCH`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl Version {
+    /// Create a new [`Version`].
+    pub fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Version {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// The version of the STREM format schema produced by this crate.
+    pub fn current() -> Self {
+        Version::new(2, 0, 0)
+    }
+
+    /// Check if this [`Version`] is compatible with another.
+    ///
+    /// This follows the semantic versioning rules used by Cargo: versions are
+    /// compatible if they share the same leftmost non-zero component (e.g.,
+    /// `1.2.0` and `1.5.3`, or `0.2.0` and `0.2.7`). A `0.0.x` version is only
+    /// compatible with itself.
+    pub fn compatible(&self, other: &Version) -> bool {
+        if self.major != other.major {
+            return false;
+        }
+
+        if self.major == 0 {
+            if self.minor == 0 {
+                return other.minor == 0 && self.patch == other.patch;
+            }
+
+            return self.minor == other.minor;
+        }
+
+        true
+    }
+
+    /// Retrieve the [`Adapter`] that upgrades data of this [`Version`].
+    ///
+    /// If no adapter exists, the data cannot be upgraded.
+    pub fn adapter(&self) -> Option<Adapter> {
+        match self.major {
+            0 => Some(self::v1),
+            _ => None,
+        }
+    }
+}
+
+/// Upgrade the [`DataStream`] to the current version.
+///
+/// Data of a compatible version is returned as-is. Otherwise, the data is
+/// passed through the [`Adapter`] of its version, if one exists.
+pub fn upgrade(data: DataStream<'_>) -> Result<DataStream<'_>, Box<dyn Error>> {
+    let current = Version::current();
+    let version: Version = data.version.parse()?;
+
+    if version.compatible(&current) {
+        return Ok(data);
+    }
+
+    if let Some(adapter) = version.adapter() {
+        return adapter(data);
+    }
+
+    Err(Box::new(VersionError::from(format!(
+        "unsupported version v{}... expected v{}",
+        version, current
+    ))))
+}
+
+impl FromStr for Version {
+    type Err = VersionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Ignore any pre-release or build metadata.
+        //
+        // The format is only versioned by its core components, so any suffix
+        // (e.g., `-alpha` or `+build`) is not considered.
+        let core = s.trim().split(['-', '+']).next().unwrap_or_default();
+
+        let mut components = core.split('.').map(|c| c.parse::<u64>());
+
+        match (
+            components.next(),
+            components.next(),
+            components.next(),
+            components.next(),
+        ) {
+            (Some(Ok(major)), Some(Ok(minor)), Some(Ok(patch)), None) => {
+                Ok(Version::new(major, minor, patch))
+            }
+            _ => Err(VersionError::from(format!("invalid version `{}`", s))),
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Upgrade data from `v1` (i.e., `0.x`).
+///
+/// All fields introduced by `v2` are optional, and no fields of `v1` were
+/// removed or changed. Therefore, only the version is updated.
+fn v1(mut data: DataStream<'_>) -> Result<DataStream<'_>, Box<dyn Error>> {
+    data.version = Version::current().to_string().into();
+    Ok(data)
+}
+
+#[derive(Debug, Clone)]
+pub struct VersionError {
+    msg: String,
+}
+
+impl From<&str> for VersionError {
+    fn from(msg: &str) -> Self {
+        VersionError {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for VersionError {
+    fn from(msg: String) -> Self {
+        VersionError { msg }
+    }
+}
+
+impl fmt::Display for VersionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "version: {}", self.msg)
+    }
+}
+
+impl Error for VersionError {}
+
+#[cfg(test)]
+mod tests {
+    use super::Version;
+
+    #[test]
+    fn parse_version() {
+        assert_eq!("0.2.1".parse::<Version>().unwrap(), Version::new(0, 2, 1));
+        assert_eq!(
+            "1.0.0-rc.1".parse::<Version>().unwrap(),
+            Version::new(1, 0, 0)
+        );
+        assert!("0.2".parse::<Version>().is_err());
+        assert!("a.b.c".parse::<Version>().is_err());
+    }
+
+    #[test]
+    fn compatible_versions() {
+        assert!(Version::new(0, 2, 0).compatible(&Version::new(0, 2, 9)));
+        assert!(!Version::new(0, 1, 1).compatible(&Version::new(0, 2, 0)));
+        assert!(Version::new(1, 2, 0).compatible(&Version::new(1, 7, 3)));
+        assert!(!Version::new(1, 0, 0).compatible(&Version::new(2, 0, 0)));
+        assert!(Version::new(0, 0, 3).compatible(&Version::new(0, 0, 3)));
+        assert!(!Version::new(0, 0, 3).compatible(&Version::new(0, 0, 4)));
+    }
+}
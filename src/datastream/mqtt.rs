@@ -0,0 +1,510 @@
+//! A live data stream of an MQTT topic.
+//!
+//! Edge devices commonly publish their perception output to an MQTT broker
+//! rather than to a file or socket. A [`Subscriber`] connects to a broker (MQTT
+//! 3.1.1), subscribes to a topic, and yields the STREM-formatted documents of
+//! each message as it arrives. A live feed may be idle for long periods, and
+//! its connection may drop; therefore, the connection is kept alive while idle
+//! and re-established when lost, such that the search continues regardless.
+
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::io::{ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::de::IoRead;
+use serde_json::StreamDeserializer;
+
+use super::io;
+
+/// The default port of a broker.
+const PORT: u16 = 1883;
+
+/// The interval the broker expects a packet of the client within.
+const KEEPALIVE: Duration = Duration::from_secs(30);
+
+/// The initial and maximum delay between attempts to reconnect.
+const BACKOFF: (Duration, Duration) = (Duration::from_millis(500), Duration::from_secs(30));
+
+// The type of each packet handled.
+//
+// Only the packets of a subscriber of at most once (i.e., QoS 0) are needed.
+const CONNECT: u8 = 0x10;
+const CONNACK: u8 = 0x20;
+const PUBLISH: u8 = 0x30;
+const PUBACK: u8 = 0x40;
+const SUBSCRIBE: u8 = 0x82;
+const SUBACK: u8 = 0x90;
+const PINGREQ: u8 = 0xc0;
+const DISCONNECT: u8 = 0xe0;
+
+/// An open connection to a broker.
+struct Connection {
+    stream: TcpStream,
+
+    /// The time a packet was last sent.
+    sent: Instant,
+
+    /// Whether a ping is not yet answered.
+    pinged: bool,
+}
+
+/// A subscriber of the STREM-formatted messages of an MQTT topic.
+///
+/// Each message holds one or more documents (e.g., of a single frame). The
+/// subscription is at most once (i.e., QoS 0), such that messages published
+/// while disconnected are not received.
+pub struct Subscriber {
+    /// The address of the broker (i.e., `host:port`).
+    address: String,
+
+    topic: String,
+
+    /// The connection to the broker, unless lost.
+    connection: Option<Connection>,
+
+    /// The documents received but not yet taken.
+    documents: VecDeque<io::Converted>,
+}
+
+impl Subscriber {
+    /// Subscribe to the topic of `url` (i.e., `mqtt://host[:port]/topic`).
+    ///
+    /// The first connection must succeed, as an unreachable broker is more
+    /// likely misconfigured than temporarily down.
+    pub fn connect(url: &str) -> Result<Self, Box<dyn Error>> {
+        let rest = url.strip_prefix("mqtt://").ok_or(MqttError::from(format!(
+            "invalid url `{}` (expected `mqtt://host[:port]/topic`)",
+            url
+        )))?;
+
+        let (address, topic) = match rest.split_once('/') {
+            Some((address, topic)) if !address.is_empty() && !topic.is_empty() => (address, topic),
+            _ => {
+                return Err(Box::new(MqttError::from(format!(
+                    "invalid url `{}` (expected `mqtt://host[:port]/topic`)",
+                    url
+                ))))
+            }
+        };
+
+        let address = match address.contains(':') {
+            true => address.to_string(),
+            false => format!("{}:{}", address, PORT),
+        };
+
+        let mut subscriber = Subscriber {
+            address,
+            topic: topic.to_string(),
+            connection: None,
+            documents: VecDeque::new(),
+        };
+
+        subscriber.connection = Some(subscriber.open()?);
+
+        Ok(subscriber)
+    }
+
+    /// Open a new connection to the broker and subscribe to the topic.
+    fn open(&self) -> Result<Connection, Box<dyn Error>> {
+        let mut stream = TcpStream::connect(&self.address)
+            .map_err(|e| MqttError::from(format!("{}: {}", self.address, e)))?;
+
+        stream.set_read_timeout(Some(KEEPALIVE / 2))?;
+
+        // Connect with a clean session.
+        //
+        // Nothing is kept by the broker across connections, as only messages
+        // of at most once are subscribed to.
+        let mut packet = self::string("MQTT");
+        packet.extend([4, 0x02]);
+        packet.extend((KEEPALIVE.as_secs() as u16).to_be_bytes());
+        packet.extend(self::string(&format!("strem-{}", std::process::id())));
+
+        self::send(&mut stream, CONNECT, &packet)?;
+
+        match self::receive(&mut stream)? {
+            (CONNACK, body) if body.get(1) == Some(&0) => {}
+            (CONNACK, body) => {
+                return Err(Box::new(MqttError::from(format!(
+                    "connection refused by broker (code {})",
+                    body.get(1).copied().unwrap_or_default()
+                ))))
+            }
+            _ => {
+                return Err(Box::new(MqttError::from(
+                    "expected acknowledgment of connection",
+                )))
+            }
+        }
+
+        let mut packet = 1u16.to_be_bytes().to_vec();
+        packet.extend(self::string(&self.topic));
+        packet.push(0);
+
+        self::send(&mut stream, SUBSCRIBE, &packet)?;
+
+        // Wait for the acknowledgment of the subscription.
+        //
+        // A message of the topic may already arrive before it; therefore, it
+        // is skipped rather than rejected.
+        loop {
+            match self::receive(&mut stream)? {
+                (SUBACK, body) if body.get(2).is_some_and(|code| *code < 0x80) => break,
+                (SUBACK, _) => {
+                    return Err(Box::new(MqttError::from(format!(
+                        "subscription to `{}` refused by broker",
+                        self.topic
+                    ))))
+                }
+                _ => continue,
+            }
+        }
+
+        Ok(Connection {
+            stream,
+            sent: Instant::now(),
+            pinged: false,
+        })
+    }
+
+    /// Reconnect to the broker, retrying until successful.
+    ///
+    /// The delay between attempts grows up to a maximum, such that a broker
+    /// that is down for long is not flooded with attempts.
+    fn reconnect(&mut self, cause: Box<dyn Error>) -> Connection {
+        eprintln!("mqtt: warning: connection lost ({}), reconnecting", cause);
+
+        let mut delay = BACKOFF.0;
+
+        loop {
+            thread::sleep(delay);
+
+            match self.open() {
+                Ok(connection) => {
+                    eprintln!("mqtt: reconnected to {}", self.address);
+                    return connection;
+                }
+                Err(_) => delay = std::cmp::min(delay * 2, BACKOFF.1),
+            }
+        }
+    }
+
+    /// Wait for the next message of the topic, and return its payload.
+    ///
+    /// While idle, the broker is pinged such that it keeps the connection
+    /// open. If a ping is not answered, the connection is treated as lost.
+    fn wait(connection: &mut Connection) -> Result<Vec<u8>, Box<dyn Error>> {
+        loop {
+            if connection.sent.elapsed() >= KEEPALIVE / 2 {
+                if connection.pinged {
+                    return Err(Box::new(MqttError::from("broker did not answer ping")));
+                }
+
+                self::send(&mut connection.stream, PINGREQ, &[])?;
+                connection.sent = Instant::now();
+                connection.pinged = true;
+            }
+
+            let (kind, body) = match self::receive(&mut connection.stream) {
+                Ok(packet) => packet,
+                Err(e)
+                    if e.downcast_ref::<std::io::Error>().is_some_and(|e| {
+                        matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut)
+                    }) =>
+                {
+                    continue
+                }
+                Err(e) => return Err(e),
+            };
+
+            connection.pinged = false;
+
+            if kind & 0xf0 != PUBLISH {
+                continue;
+            }
+
+            // Split the topic (and identifier, if any) from the payload.
+            //
+            // A message of at least once is acknowledged, although only at
+            // most once is requested.
+            let qos = (kind >> 1) & 0x03;
+            let length = body
+                .get(..2)
+                .map(|n| u16::from_be_bytes([n[0], n[1]]) as usize)
+                .ok_or(MqttError::from("malformed message"))?;
+
+            let mut offset = 2 + length;
+
+            if qos > 0 {
+                let id = body
+                    .get(offset..(offset + 2))
+                    .ok_or(MqttError::from("malformed message"))?
+                    .to_vec();
+
+                self::send(&mut connection.stream, PUBACK, &id)?;
+                connection.sent = Instant::now();
+                offset += 2;
+            }
+
+            return match body.get(offset..) {
+                Some(payload) => Ok(payload.to_vec()),
+                None => Err(Box::new(MqttError::from("malformed message"))),
+            };
+        }
+    }
+}
+
+impl Iterator for Subscriber {
+    type Item = Result<io::Converted, Box<dyn Error>>;
+
+    /// Take the next document, waiting for a message if needed.
+    ///
+    /// This never ends, as the topic may always be published to again.
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.documents.is_empty() {
+            let mut connection = match self.connection.take() {
+                Some(connection) => connection,
+                None => self.reconnect(Box::new(MqttError::from("not connected"))),
+            };
+
+            let payload = match Self::wait(&mut connection) {
+                Ok(payload) => payload,
+                Err(e) => {
+                    self.connection = Some(self.reconnect(e));
+                    continue;
+                }
+            };
+
+            self.connection = Some(connection);
+
+            let stream = StreamDeserializer::<_, io::DataStream<'static>>::new(IoRead::new(
+                payload.as_slice(),
+            ));
+
+            // Reject the remainder of a malformed message.
+            //
+            // The documents of a message cannot be parsed past an error;
+            // however, the next message is parsed as usual.
+            for data in stream {
+                match data {
+                    Ok(data) => self.documents.push_back(io::Converted::Document(data)),
+                    Err(e) => {
+                        self.documents.push_back(io::Converted::Rejected(
+                            MqttError::from(format!("malformed message ({})", e)).to_string(),
+                        ));
+                        break;
+                    }
+                }
+            }
+        }
+
+        self.documents.pop_front().map(Ok)
+    }
+}
+
+impl Drop for Subscriber {
+    fn drop(&mut self) {
+        if let Some(mut connection) = self.connection.take() {
+            let _ = self::send(&mut connection.stream, DISCONNECT, &[]);
+        }
+    }
+}
+
+/// Encode a string prefixed by its length.
+fn string(s: &str) -> Vec<u8> {
+    let mut bytes = (s.len() as u16).to_be_bytes().to_vec();
+    bytes.extend(s.as_bytes());
+
+    bytes
+}
+
+/// Send a packet of `kind` with the `body`.
+fn send(stream: &mut TcpStream, kind: u8, body: &[u8]) -> Result<(), Box<dyn Error>> {
+    let mut packet = vec![kind];
+    let mut length = body.len();
+
+    // Encode the remaining length.
+    //
+    // Each byte holds seven bits of the length, and its highest bit is set if
+    // more bytes follow.
+    loop {
+        let byte = (length % 128) as u8;
+        length /= 128;
+
+        match length > 0 {
+            true => packet.push(byte | 0x80),
+            false => {
+                packet.push(byte);
+                break;
+            }
+        }
+    }
+
+    packet.extend(body);
+    stream.write_all(&packet)?;
+
+    Ok(())
+}
+
+/// Receive the next packet, and return its kind and body.
+///
+/// Only the first byte is read within the timeout of the `stream`; once a
+/// packet has started, the remainder of it is waited for.
+fn receive(stream: &mut TcpStream) -> Result<(u8, Vec<u8>), Box<dyn Error>> {
+    let mut kind = [0];
+
+    if stream.read(&mut kind)? == 0 {
+        return Err(Box::new(MqttError::from("connection closed by broker")));
+    }
+
+    let timeout = stream.read_timeout()?;
+    stream.set_read_timeout(None)?;
+
+    let mut length = 0;
+    let mut byte = [0x80];
+    let mut shift = 0;
+
+    while byte[0] & 0x80 != 0 {
+        if shift > 21 {
+            return Err(Box::new(MqttError::from("malformed packet length")));
+        }
+
+        stream.read_exact(&mut byte)?;
+        length |= ((byte[0] & 0x7f) as usize) << shift;
+        shift += 7;
+    }
+
+    let mut body = vec![0; length];
+    stream.read_exact(&mut body)?;
+
+    stream.set_read_timeout(timeout)?;
+
+    Ok((kind[0], body))
+}
+
+#[derive(Debug, Clone)]
+struct MqttError {
+    msg: String,
+}
+
+impl From<&str> for MqttError {
+    fn from(msg: &str) -> Self {
+        MqttError {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for MqttError {
+    fn from(msg: String) -> Self {
+        MqttError { msg }
+    }
+}
+
+impl fmt::Display for MqttError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "mqtt: {}", self.msg)
+    }
+}
+
+impl Error for MqttError {}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use crate::config::Configuration;
+    use crate::datastream::io::importer::Importer;
+    use crate::datastream::DataStream;
+
+    use super::*;
+
+    /// Accept a subscriber, and publish each of the `messages` to it.
+    fn serve(listener: &TcpListener, messages: &[&str]) {
+        let (mut stream, _) = listener.accept().unwrap();
+
+        assert_eq!(receive(&mut stream).unwrap().0, CONNECT);
+        send(&mut stream, CONNACK, &[0, 0]).unwrap();
+
+        assert_eq!(receive(&mut stream).unwrap().0, SUBSCRIBE);
+        send(&mut stream, SUBACK, &[0, 1, 0]).unwrap();
+
+        for message in messages {
+            let mut body = string("frames");
+            body.extend(message.as_bytes());
+
+            send(&mut stream, PUBLISH, &body).unwrap();
+        }
+    }
+
+    #[test]
+    fn reconnect() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("mqtt://{}/frames", listener.local_addr().unwrap());
+
+        let broker = thread::spawn(move || {
+            serve(
+                &listener,
+                &[r#"{"version":"2.0.0","frames":[{"index":0,"samples":[]}]}"#],
+            );
+            serve(
+                &listener,
+                &[r#"{"version":"2.0.0","frames":[{"index":1,"samples":[]}]}"#],
+            );
+        });
+
+        let mut subscriber = Subscriber::connect(&url).unwrap();
+
+        assert!(subscriber.next().unwrap().is_ok());
+        assert!(subscriber.next().unwrap().is_ok());
+
+        broker.join().unwrap();
+
+        assert!(Subscriber::connect("tcp://localhost/frames").is_err());
+        assert!(Subscriber::connect("mqtt://localhost").is_err());
+    }
+
+    #[test]
+    fn malformed() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("mqtt://{}/frames", listener.local_addr().unwrap());
+
+        let messages = [
+            r#"{"version":"2.0.0","frames":[{"index":0,"samples":0}]}"#,
+            r#"{"version":"2.0.0","frames":[{"index":1,"samples":[]}]}"#,
+        ];
+
+        let broker = thread::spawn(move || {
+            serve(&listener, &messages);
+            serve(&listener, &messages);
+        });
+
+        let pattern = String::from("[[:car:]]");
+        let mut config = Configuration::new(&pattern);
+
+        // The malformed message is skipped, unless the import is strict.
+        let mut importer = Importer::new(&config);
+        let frames = DataStream::subscribe(&url)
+            .unwrap()
+            .request(&mut importer)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(frames[0].index, 1);
+        assert_eq!(importer.skipped(), 1);
+
+        config.fail_fast = true;
+
+        let mut importer = Importer::new(&config);
+        assert!(DataStream::subscribe(&url)
+            .unwrap()
+            .request(&mut importer)
+            .is_err());
+
+        broker.join().unwrap();
+    }
+}
@@ -0,0 +1,108 @@
+//! Restriction of annotations to a region of interest.
+//!
+//! Only part of an image may be relevant to a scenario (e.g., the ego lane of
+//! a front camera). A [`Roi`] restricts the annotations imported to those
+//! whose bounding boxes intersect the region, such that detections elsewhere
+//! never satisfy a pattern.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+use super::frame::sample::detections::bbox::region::{aa, Point};
+use super::frame::sample::detections::bbox::BoundingBox;
+
+/// A rectangular region of interest of an image.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Roi {
+    region: BoundingBox,
+}
+
+impl Roi {
+    /// Create a new [`Roi`] from its top-left corner, width, and height.
+    pub fn new(x: f64, y: f64, width: f64, height: f64) -> Self {
+        Roi {
+            region: BoundingBox::AxisAligned(aa::Region::new(
+                Point::new(x + (width / 2.0), y + (height / 2.0)),
+                width,
+                height,
+            )),
+        }
+    }
+
+    /// Check if a [`BoundingBox`] intersects the region.
+    ///
+    /// A box that only touches the border of the region intersects it.
+    pub fn intersects(&self, bbox: &BoundingBox) -> bool {
+        self.region.overlaps(bbox)
+    }
+}
+
+impl FromStr for Roi {
+    type Err = RoiError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s
+            .split(',')
+            .map(|v| v.trim().parse::<f64>())
+            .collect::<Result<Vec<f64>, _>>()
+            .ok()
+            .filter(|values| values.len() == 4 && values.iter().all(|v| v.is_finite()));
+
+        match values.as_deref() {
+            Some(&[x, y, w, h]) if w > 0.0 && h > 0.0 => Ok(Roi::new(x, y, w, h)),
+            _ => Err(RoiError::from(format!(
+                "`{}`: expected `x,y,w,h` with a positive width and height",
+                s
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RoiError {
+    msg: String,
+}
+
+impl From<&str> for RoiError {
+    fn from(msg: &str) -> Self {
+        RoiError {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for RoiError {
+    fn from(msg: String) -> Self {
+        RoiError { msg }
+    }
+}
+
+impl fmt::Display for RoiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "roi: {}", self.msg)
+    }
+}
+
+impl Error for RoiError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::datastream::frame::sample::detections::bbox::region::{aa, Point};
+    use crate::datastream::frame::sample::detections::bbox::BoundingBox;
+
+    use super::Roi;
+
+    #[test]
+    fn intersects() {
+        let roi = "0,0,10,10".parse::<Roi>().unwrap();
+        let bbox = |x: f64| BoundingBox::AxisAligned(aa::Region::new(Point::new(x, 5.0), 2.0, 2.0));
+
+        assert!(roi.intersects(&bbox(5.0)));
+        assert!(roi.intersects(&bbox(10.5)));
+        assert!(!roi.intersects(&bbox(12.0)));
+
+        assert!("0,0,10".parse::<Roi>().is_err());
+        assert!("0,0,-10,10".parse::<Roi>().is_err());
+    }
+}
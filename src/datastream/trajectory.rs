@@ -0,0 +1,113 @@
+//! Extraction of the trajectories of tracked objects.
+//!
+//! A match reports the range of frames it spans. For downstream analysis
+//! (e.g., speed profiles or path plots), the path of each object within the
+//! frames is needed instead. A [`Trajectory`] collects the bounding boxes of
+//! the same tracked object (i.e., with the same `instance_id`) over time.
+
+use super::frame::sample::detections::bbox::BoundingBox;
+use super::frame::sample::detections::label::Label;
+use super::frame::sample::Sample;
+use super::frame::Frame;
+
+/// The position of a tracked object within a single frame.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Waypoint {
+    /// The index of the frame.
+    pub index: usize,
+
+    /// The timestamp of the frame, if known.
+    pub timestamp: Option<f64>,
+
+    pub bbox: BoundingBox,
+}
+
+/// The path of a tracked object over a series of frames.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trajectory {
+    /// The channel the object is detected by.
+    pub channel: String,
+
+    /// The class of the object when it is first detected.
+    pub label: Label,
+
+    pub instance_id: String,
+
+    /// The position of the object in each frame it is detected, in order.
+    pub waypoints: Vec<Waypoint>,
+}
+
+/// Extract the [`Trajectory`] of each tracked object within the frames.
+///
+/// The trajectories are listed in the order their objects first appear, and
+/// objects are distinguished by channel, as the same identifier may be used
+/// by independent trackers. Annotations without an instance identifier cannot
+/// be associated across frames; therefore, they are skipped.
+pub fn trajectories(frames: &[Frame]) -> Vec<Trajectory> {
+    let mut trajectories: Vec<Trajectory> = Vec::new();
+
+    for frame in frames.iter() {
+        for sample in frame.samples.iter() {
+            let (Sample::ObjectDetection(record) | Sample::Text(record)) = sample else {
+                continue;
+            };
+
+            for annotation in record.annotations.values().flatten() {
+                let Some(id) = &annotation.instance_id else {
+                    continue;
+                };
+
+                let waypoint = Waypoint {
+                    index: frame.index,
+                    timestamp: frame.timestamp,
+                    bbox: annotation.bbox.clone(),
+                };
+
+                match trajectories
+                    .iter_mut()
+                    .find(|t| t.channel == record.channel && &t.instance_id == id)
+                {
+                    Some(trajectory) => trajectory.waypoints.push(waypoint),
+                    None => trajectories.push(Trajectory {
+                        channel: record.channel.clone(),
+                        label: annotation.label.clone(),
+                        instance_id: id.clone(),
+                        waypoints: vec![waypoint],
+                    }),
+                }
+            }
+        }
+    }
+
+    trajectories
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::support::frame;
+
+    #[test]
+    fn group_by_instance() {
+        let frames = vec![
+            frame(3, &[("car", Some("a"), 0.0), ("car", None, 50.0)]),
+            frame(4, &[("car", Some("b"), 20.0), ("car", Some("a"), 5.0)]),
+        ];
+
+        let trajectories = trajectories(&frames);
+
+        assert_eq!(trajectories.len(), 2);
+        assert_eq!(trajectories[0].instance_id, "a");
+        assert_eq!(
+            trajectories[0]
+                .waypoints
+                .iter()
+                .map(|w| w.index)
+                .collect::<Vec<_>>(),
+            vec![3, 4]
+        );
+        assert_eq!(trajectories[1].instance_id, "b");
+        assert_eq!(trajectories[1].waypoints.len(), 1);
+    }
+}
@@ -0,0 +1,545 @@
+//! Transforms of imported frames.
+//!
+//! A [`Transform`] preprocesses the frames of a data stream after they are
+//! imported but before they are matched against. Transforms are composed into a
+//! [`Pipeline`], such that a data stream need not be rewritten on disk for each
+//! kind of preprocessing (e.g., a lower frame rate).
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+
+use super::frame::sample::detections::bbox::region::Point;
+use super::frame::sample::detections::label::Label;
+use super::frame::sample::detections::Motion;
+use super::frame::sample::Sample;
+use super::frame::Frame;
+
+/// A single stage of preprocessing.
+///
+/// A transform is written as `NAME:ARGUMENT`:
+///
+/// ```text
+/// decimate:2
+/// score:0.5
+/// remap:taxi=car
+/// dedup:2.0
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum Transform {
+    /// Keep every `n`-th frame, starting from the first.
+    Decimate(usize),
+
+    /// Remove annotations (and frame-level labels) with a score below this.
+    Score(f64),
+
+    /// Rename a class to another.
+    Remap(Label, Label),
+
+    /// Drop consecutive frames with the same detections as the frame before,
+    /// where the bounding boxes may differ by up to this distance (in pixels).
+    Dedup(f64),
+}
+
+impl Transform {
+    /// Parse a [`Transform`] from its textual representation.
+    pub fn parse(source: &str) -> Result<Self, Box<dyn Error>> {
+        let (name, argument) = source.split_once(':').ok_or(TransformError::from(format!(
+            "`{}`: expected a transform (e.g., `decimate:2`)",
+            source
+        )))?;
+
+        let argument = argument.trim();
+
+        match name.trim() {
+            "decimate" => match argument.parse::<usize>() {
+                Ok(n) if n > 0 => Ok(Transform::Decimate(n)),
+                _ => Err(Box::new(TransformError::from(format!(
+                    "`{}`: expected a positive number of frames",
+                    source
+                )))),
+            },
+            "score" => match argument.parse::<f64>() {
+                Ok(threshold) => Ok(Transform::Score(threshold)),
+                Err(_) => Err(Box::new(TransformError::from(format!(
+                    "`{}`: expected a score threshold",
+                    source
+                )))),
+            },
+            "remap" => match argument.split_once('=') {
+                Some((from, to)) if !from.trim().is_empty() && !to.trim().is_empty() => Ok(
+                    Transform::Remap(Label::intern(from.trim())?, Label::intern(to.trim())?),
+                ),
+                _ => Err(Box::new(TransformError::from(format!(
+                    "`{}`: expected a class mapping (e.g., `remap:FROM=TO`)",
+                    source
+                )))),
+            },
+            "dedup" => match argument.parse::<f64>() {
+                Ok(tolerance) if tolerance >= 0.0 => Ok(Transform::Dedup(tolerance)),
+                _ => Err(Box::new(TransformError::from(format!(
+                    "`{}`: expected a non-negative tolerance",
+                    source
+                )))),
+            },
+            name => Err(Box::new(TransformError::from(format!(
+                "`{}`: unknown transform `{}`",
+                source, name
+            )))),
+        }
+    }
+
+    /// Apply the [`Transform`] to a single [`Frame`].
+    ///
+    /// The `position` is the number of frames seen by this stage before the
+    /// frame. If the frame is removed, `None` is returned.
+    fn apply(&self, mut frame: Frame, position: usize) -> Option<Frame> {
+        match self {
+            Transform::Decimate(n) => {
+                if !position.is_multiple_of(*n) {
+                    return None;
+                }
+            }
+            Transform::Score(threshold) => {
+                for sample in frame.samples.iter_mut() {
+                    match sample {
+                        Sample::ObjectDetection(record) | Sample::Text(record) => {
+                            for annotations in record.annotations.values_mut() {
+                                annotations.retain(|a| a.score >= *threshold);
+                            }
+
+                            record
+                                .annotations
+                                .retain(|_, annotations| !annotations.is_empty());
+                        }
+                        Sample::Classification(record) => {
+                            record.labels.retain(|_, score| *score >= *threshold);
+                        }
+                        Sample::PointCloud(..) => {}
+                    }
+                }
+            }
+            Transform::Remap(from, to) => {
+                for sample in frame.samples.iter_mut() {
+                    match sample {
+                        Sample::ObjectDetection(record) | Sample::Text(record) => {
+                            if let Some(mut annotations) = record.annotations.shift_remove(from) {
+                                for annotation in annotations.iter_mut() {
+                                    annotation.label = to.clone();
+                                }
+
+                                record
+                                    .annotations
+                                    .entry(to.clone())
+                                    .or_default()
+                                    .extend(annotations);
+                            }
+                        }
+                        Sample::Classification(record) => {
+                            if let Some(score) = record.labels.shift_remove(from) {
+                                let entry = record.labels.entry(to.clone()).or_insert(score);
+                                *entry = f64::max(*entry, score);
+                            }
+                        }
+                        Sample::PointCloud(..) => {}
+                    }
+                }
+            }
+            Transform::Dedup(..) => {}
+        }
+
+        Some(frame)
+    }
+}
+
+/// Check if a [`Frame`] has the same detections as another.
+///
+/// The samples must be of the same kind and channel, in the same order, and
+/// each annotation must have the same class, instance, and text as its
+/// counterpart, with a bounding box within `tolerance` of it. Point clouds
+/// cannot be compared; therefore, a frame with one is never a duplicate.
+fn duplicate(frame: &Frame, other: &Frame, tolerance: f64) -> bool {
+    if frame.samples.len() != other.samples.len() {
+        return false;
+    }
+
+    frame
+        .samples
+        .iter()
+        .zip(other.samples.iter())
+        .all(|pair| match pair {
+            (Sample::ObjectDetection(a), Sample::ObjectDetection(b))
+            | (Sample::Text(a), Sample::Text(b)) => {
+                a.channel == b.channel
+                    && a.annotations.len() == b.annotations.len()
+                    && a.annotations.iter().all(|(label, xs)| {
+                        b.annotations.get(label).is_some_and(|ys| {
+                            xs.len() == ys.len()
+                                && xs.iter().zip(ys.iter()).all(|(x, y)| {
+                                    x.instance_id == y.instance_id
+                                        && x.text == y.text
+                                        && x.bbox.hausdorff(&y.bbox) <= tolerance
+                                })
+                        })
+                    })
+            }
+            (Sample::Classification(a), Sample::Classification(b)) => {
+                a.channel == b.channel
+                    && a.labels.len() == b.labels.len()
+                    && a.labels.keys().all(|label| b.labels.contains_key(label))
+            }
+            _ => false,
+        })
+}
+
+/// A sequence of [`Transform`] applied in order.
+///
+/// The frames of a data stream may be imported in batches (e.g., when
+/// searching online); therefore, the pipeline keeps the state of each stage
+/// between batches.
+#[derive(Clone, Debug)]
+pub struct Pipeline {
+    transforms: Vec<Transform>,
+
+    /// The number of frames seen by each stage.
+    positions: Vec<usize>,
+
+    /// The last frame kept by each stage, if it drops duplicates.
+    previous: Vec<Option<Frame>>,
+
+    /// The set of labels of the transformed frames.
+    labels: HashSet<Label>,
+
+    /// Whether the [`Motion`] of each tracked object is found.
+    track: bool,
+
+    /// The tracked objects of the last transformed frame, if any.
+    tracks: Option<Tracks>,
+}
+
+impl Pipeline {
+    /// Create a new [`Pipeline`] of transforms.
+    pub fn new(transforms: Vec<Transform>) -> Self {
+        Pipeline {
+            positions: vec![0; transforms.len()],
+            previous: vec![None; transforms.len()],
+            transforms,
+            labels: HashSet::new(),
+            track: false,
+            tracks: None,
+        }
+    }
+
+    /// Set whether the [`Motion`] of each tracked object is found.
+    ///
+    /// Tracking is only needed if the pattern uses the motion (e.g., `@speed`)
+    /// of an object; otherwise, the frames are left as is. The objects tracked
+    /// so far are forgotten once tracking stops.
+    pub fn track(mut self, track: bool) -> Self {
+        if !track {
+            self.tracks = None;
+        }
+
+        self.track = track;
+        self
+    }
+
+    /// Check if the [`Pipeline`] has no transforms.
+    pub fn is_empty(&self) -> bool {
+        self.transforms.is_empty()
+    }
+
+    /// The set of labels of the frames transformed so far.
+    pub fn labels(&self) -> &HashSet<Label> {
+        &self.labels
+    }
+
+    /// Apply each [`Transform`] to a batch of [`Frame`], in order.
+    ///
+    /// A frame dropped as a duplicate extends the frame it duplicates, such
+    /// that a match ending at that frame covers the dropped frames, even if
+    /// the frame is of a previous batch. The [`Motion`] of each tracked object
+    /// is then found from the frame before it, if tracked (see [`Tracks`]).
+    pub fn apply(&mut self, frames: Vec<Frame>) -> Vec<Frame> {
+        if self.is_empty() && !self.track {
+            return frames;
+        }
+
+        let mut frames = frames;
+
+        for ((transform, position), previous) in self
+            .transforms
+            .iter()
+            .zip(self.positions.iter_mut())
+            .zip(self.previous.iter_mut())
+        {
+            frames = frames
+                .into_iter()
+                .filter_map(|frame| {
+                    *position += 1;
+
+                    if let Transform::Dedup(tolerance) = transform {
+                        if let Some(kept) = previous {
+                            if self::duplicate(kept, &frame, *tolerance) {
+                                kept.extend(frame.end() - 1);
+                                return None;
+                            }
+                        }
+
+                        *previous = Some(frame.clone());
+                    }
+
+                    transform.apply(frame, *position - 1)
+                })
+                .collect();
+        }
+
+        if self.track {
+            for frame in frames.iter_mut() {
+                let tracks = Tracks::new(frame);
+
+                if let Some(previous) = &self.tracks {
+                    previous.track(frame);
+                }
+
+                self.tracks = Some(tracks);
+            }
+        }
+
+        if self.is_empty() {
+            return frames;
+        }
+
+        for sample in frames.iter().flat_map(|f| f.samples.iter()) {
+            match sample {
+                Sample::ObjectDetection(record) | Sample::Text(record) => {
+                    self.labels.extend(record.annotations.keys().cloned());
+                }
+                Sample::Classification(record) => self.labels.extend(record.labels.keys().cloned()),
+                Sample::PointCloud(..) => {}
+            }
+        }
+
+        frames
+    }
+}
+
+/// The positions of the tracked objects within a [`Frame`].
+///
+/// Each object is keyed by the channel of its sample and its instance
+/// identifier, such that the same object seen by different channels is not
+/// confused.
+#[derive(Clone, Debug)]
+struct Tracks {
+    timestamp: Option<f64>,
+    positions: HashMap<(String, String), Point>,
+}
+
+impl Tracks {
+    /// Gather the positions of the tracked objects of a [`Frame`].
+    fn new(frame: &Frame) -> Self {
+        let mut positions = HashMap::new();
+
+        for sample in frame.samples.iter() {
+            if let Sample::ObjectDetection(record) | Sample::Text(record) = sample {
+                for annotation in record.annotations.values().flatten() {
+                    if let Some(id) = &annotation.instance_id {
+                        positions.insert(
+                            (record.channel.clone(), id.clone()),
+                            annotation.bbox.center(),
+                        );
+                    }
+                }
+            }
+        }
+
+        Tracks {
+            timestamp: frame.timestamp,
+            positions,
+        }
+    }
+
+    /// Set the [`Motion`] of each object of a later [`Frame`] that is tracked.
+    ///
+    /// Objects that are not within these tracks (e.g., that just appeared) are
+    /// left without motion.
+    fn track(&self, frame: &mut Frame) {
+        if self.positions.is_empty() {
+            return;
+        }
+
+        let dt = match (self.timestamp, frame.timestamp) {
+            (Some(before), Some(after)) => Some(after - before),
+            _ => None,
+        };
+
+        for sample in frame.samples.iter_mut() {
+            if let Sample::ObjectDetection(record) | Sample::Text(record) = sample {
+                for annotation in record.annotations.values_mut().flatten() {
+                    let Some(id) = &annotation.instance_id else {
+                        continue;
+                    };
+
+                    let key = (record.channel.clone(), id.clone());
+
+                    if let Some(before) = self.positions.get(&key) {
+                        let after = annotation.bbox.center();
+
+                        annotation.motion = Some(Motion {
+                            dx: after.x - before.x,
+                            dy: after.y - before.y,
+                            dt,
+                        });
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct TransformError {
+    msg: String,
+}
+
+impl From<&str> for TransformError {
+    fn from(msg: &str) -> Self {
+        TransformError {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for TransformError {
+    fn from(msg: String) -> Self {
+        TransformError { msg }
+    }
+}
+
+impl fmt::Display for TransformError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "transform: {}", self.msg)
+    }
+}
+
+impl Error for TransformError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::datastream::frame::sample::classification::ClassificationRecord;
+    use crate::datastream::frame::sample::detections::bbox::region::{aa, Point};
+    use crate::datastream::frame::sample::detections::bbox::BoundingBox;
+    use crate::datastream::frame::sample::detections::label::Label;
+    use crate::datastream::frame::sample::detections::{Annotation, DetectionRecord};
+    use crate::datastream::frame::sample::Sample;
+    use crate::datastream::frame::Frame;
+
+    use super::{Pipeline, Transform};
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            Transform::parse("decimate:3").unwrap(),
+            Transform::Decimate(3)
+        );
+        assert_eq!(
+            Transform::parse("score: 0.5").unwrap(),
+            Transform::Score(0.5)
+        );
+        assert!(Transform::parse("decimate:0").is_err());
+        assert!(Transform::parse("remap:car").is_err());
+        assert!(Transform::parse("dedup:-1").is_err());
+        assert!(Transform::parse("blur:2").is_err());
+    }
+
+    #[test]
+    fn decimate_across_batches() {
+        let mut pipeline = Pipeline::new(vec![Transform::Decimate(2)]);
+
+        let mut indices = Vec::new();
+        for batch in [0..3, 3..4, 4..7] {
+            let frames = batch.map(Frame::new).collect();
+            indices.extend(pipeline.apply(frames).iter().map(|f| f.index));
+        }
+
+        assert_eq!(indices, [0, 2, 4, 6]);
+    }
+
+    #[test]
+    fn dedup_across_batches() {
+        let mut pipeline = Pipeline::new(vec![Transform::Dedup(0.0)]);
+
+        let mut frames = Vec::new();
+        for batch in [0..3, 3..5] {
+            let mut batch: Vec<Frame> = batch.map(Frame::new).collect();
+
+            for frame in batch.iter_mut().filter(|f| f.index == 2) {
+                frame
+                    .samples
+                    .push(Sample::Classification(ClassificationRecord::new(
+                        String::from("cam"),
+                    )));
+            }
+
+            frames.extend(pipeline.apply(batch));
+        }
+
+        assert_eq!(
+            frames
+                .iter()
+                .map(|f| (f.index, f.end()))
+                .collect::<Vec<_>>(),
+            [(0, 2), (2, 3), (3, 5)]
+        );
+    }
+
+    #[test]
+    fn motion_across_batches() {
+        let frame = |index: usize, x: f64| {
+            let mut annotation = Annotation::new(
+                Label::from("car"),
+                1.0,
+                BoundingBox::AxisAligned(aa::Region::new(Point::new(x, 0.0), 2.0, 2.0)),
+            );
+            annotation.instance_id = Some(String::from("a"));
+
+            let mut record = DetectionRecord::new(String::from("cam"), None);
+            record
+                .annotations
+                .insert(Label::from("car"), vec![annotation]);
+
+            let mut frame = Frame::new(index);
+            frame.timestamp = Some(index as f64 * 0.5);
+            frame.samples.push(Sample::ObjectDetection(record));
+
+            frame
+        };
+
+        // The objects are only tracked if asked for.
+        let frames = Pipeline::new(Vec::new()).apply(vec![frame(0, 0.0), frame(1, 3.0)]);
+        assert!(frames.iter().all(|f| match &f.samples[0] {
+            Sample::ObjectDetection(record) =>
+                record.annotations[&Label::from("car")][0].motion.is_none(),
+            _ => false,
+        }));
+
+        let mut pipeline = Pipeline::new(Vec::new()).track(true);
+
+        let mut frames = pipeline.apply(vec![frame(0, 0.0), frame(1, 3.0)]);
+        frames.extend(pipeline.apply(vec![frame(2, 7.0)]));
+
+        let motions = frames
+            .iter()
+            .map(|f| match &f.samples[0] {
+                Sample::ObjectDetection(record) => {
+                    record.annotations[&Label::from("car")][0].motion
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert!(motions[0].is_none());
+        assert_eq!(motions[1].map(|m| (m.dx, m.dy)), Some((3.0, 0.0)));
+        assert_eq!(motions[2].map(|m| m.speed()), Some(8.0));
+    }
+}
@@ -0,0 +1,120 @@
+//! A bounded window of the most recent frames.
+//!
+//! An online search keeps only the frames that a match may span (i.e., its
+//! horizon), evicting the least recent frame as each new frame arrives. Removing
+//! the first element of a [`Vec`] shifts all others; therefore, a [`Window`]
+//! instead advances the start of its frames, and only shifts them once the
+//! evicted frames outnumber the kept ones. The cost per frame is then constant
+//! (amortized) regardless of the capacity, while the frames remain contiguous
+//! for the matcher.
+
+use super::frame::Frame;
+
+/// A window of the most recent frames of a data stream.
+#[derive(Clone, Debug, Default)]
+pub struct Window {
+    /// The frames, of which those before `start` are evicted.
+    frames: Vec<Frame>,
+
+    /// The position of the least recent frame kept.
+    start: usize,
+
+    /// The maximum number of frames kept, if bounded.
+    capacity: Option<usize>,
+}
+
+impl Window {
+    /// Create a new, empty [`Window`] of at most `capacity` frames.
+    pub fn new(capacity: Option<usize>) -> Self {
+        Window {
+            frames: Vec::new(),
+            start: 0,
+            capacity,
+        }
+    }
+
+    /// The maximum number of frames kept, if bounded.
+    pub fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+
+    /// Change the `capacity` of the [`Window`].
+    ///
+    /// If the window holds more frames than the new capacity, the least recent
+    /// frames are evicted.
+    pub fn resize(&mut self, capacity: Option<usize>) {
+        self.capacity = capacity;
+
+        if let Some(capacity) = capacity {
+            self.evict(self.len().saturating_sub(capacity));
+        }
+    }
+
+    /// Append a [`Frame`], evicting the least recent frame if full.
+    pub fn push(&mut self, frame: Frame) {
+        if let Some(capacity) = self.capacity {
+            self.evict((self.len() + 1).saturating_sub(capacity));
+        }
+
+        self.frames.push(frame);
+    }
+
+    /// The frames kept, from least to most recent.
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames[self.start..]
+    }
+
+    /// The number of frames kept.
+    pub fn len(&self) -> usize {
+        self.frames.len() - self.start
+    }
+
+    /// Check if no frames are kept.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Evict all frames.
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.start = 0;
+    }
+
+    /// Evict the `n` least recent frames.
+    ///
+    /// The evicted frames are only dropped once they outnumber the frames
+    /// kept, such that each frame is shifted a constant number of times.
+    fn evict(&mut self, n: usize) {
+        self.start += std::cmp::min(n, self.len());
+
+        if self.start > 0 && self.start >= self.len() {
+            self.frames.drain(..self.start);
+            self.start = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evict_least_recent() {
+        let mut window = Window::new(Some(3));
+
+        for i in 0..10 {
+            window.push(Frame::new(i));
+            assert!(window.len() <= 3);
+        }
+
+        let indices = |w: &Window| w.frames().iter().map(|f| f.index).collect::<Vec<_>>();
+        assert_eq!(indices(&window), [7, 8, 9]);
+
+        window.resize(Some(2));
+        assert_eq!(indices(&window), [8, 9]);
+
+        window.resize(None);
+        window.push(Frame::new(10));
+        assert_eq!(indices(&window), [8, 9, 10]);
+    }
+}
@@ -0,0 +1,64 @@
+//! Stable hashing.
+//!
+//! The hasher of the standard library may differ across builds and platforms.
+//! Therefore, hashes that outlive the process (e.g., stored in a file) are
+//! computed with [`Fnv`] instead.
+
+use std::hash::Hasher;
+
+/// The 64-bit FNV-1a hash.
+///
+/// Integers are hashed as 64-bit little-endian bytes, such that the hash of a
+/// value does not depend on the platform.
+pub struct Fnv(u64);
+
+impl Fnv {
+    const OFFSET: u64 = 0xcbf29ce484222325;
+    const PRIME: u64 = 0x100000001b3;
+
+    pub fn new() -> Self {
+        Fnv(Self::OFFSET)
+    }
+}
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hasher for Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(Self::PRIME);
+        }
+    }
+
+    fn write_usize(&mut self, n: usize) {
+        self.write(&(n as u64).to_le_bytes());
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::hash::Hasher;
+
+    use super::Fnv;
+
+    #[test]
+    fn fnv() {
+        let hash = |bytes: &[u8]| {
+            let mut hasher = Fnv::new();
+            hasher.write(bytes);
+            hasher.finish()
+        };
+
+        assert_eq!(hash(b""), 0xcbf29ce484222325);
+        assert_eq!(hash(b"a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(hash(b"foobar"), 0x85944171f73967e8);
+    }
+}
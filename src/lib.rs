@@ -8,6 +8,12 @@ pub mod compiler;
 pub mod config;
 pub mod controller;
 pub mod datastream;
+pub mod hash;
 pub mod matcher;
 pub mod monitor;
+#[cfg(feature = "grpc")]
+pub mod server;
 pub mod symbolizer;
+
+#[cfg(test)]
+mod support;
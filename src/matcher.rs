@@ -3,12 +3,24 @@
 
 use std::error::Error;
 
-use crate::compiler::ir::ops::{Operator, RangeKind, RegexOperatorKind};
-use crate::compiler::ir::Node;
+use indexmap::IndexSet;
+use itertools::Itertools;
+
+use crate::compiler::ir::ast::{OperandKind, SpatialFormula};
+use crate::compiler::ir::ops::{
+    Operator, RangeKind, RegexOperatorKind, S4uOperatorKind, SpatialOperatorKind, Vacuity,
+};
+use crate::compiler::ir::{Node, NodeId, Tree};
+use crate::datastream::frame::sample::Sample;
 use crate::datastream::frame::Frame;
+use crate::monitor::Captures;
 use crate::symbolizer::ast::{SymbolicAbstractSyntaxTree, SymbolicFormula};
 
+use self::automata::dfa::lookaround::{Direction, MARKERS};
+use self::automata::Alphabet;
+
 pub mod automata;
+pub mod bitmap;
 pub mod offline;
 pub mod online;
 
@@ -19,6 +31,9 @@ pub mod online;
 pub trait Matching {
     /// Find a possible leftmost [`Match`] from the set of [`Frame`].
     fn leftmost(&self, frames: &[Frame]) -> Result<Option<Match>, Box<dyn Error>>;
+
+    /// The spatial formulas of the pattern.
+    fn formulas(&self) -> Vec<&SpatialFormula>;
 }
 
 /// A range of valid indices.
@@ -43,23 +58,224 @@ impl Match {
 ///
 /// This traverses the outer components of a SpRE related solely to the RE-based
 /// patterns and symbols.
-pub fn regexify(ast: &SymbolicAbstractSyntaxTree) -> String {
+///
+/// Only the operand of a conjunction at `node` is constructed (see
+/// [`conjuncts`]), where each lookaround is replaced by its byte of
+/// [`MARKERS`], in the order the lookarounds appear in it (see
+/// [`lookarounds`]).
+pub fn regexify(ast: &SymbolicAbstractSyntaxTree, node: NodeId) -> String {
     if let Some(root) = &ast.root {
-        return self::regexit(root);
+        let alphabet = Alphabet::new(ast.fmap().len());
+        let mut markers = MARKERS.iter();
+
+        return self::regexit(root, node, &alphabet, &mut markers);
     }
 
     String::new()
 }
 
+/// Find the operands of the conjunction of a [`SymbolicAbstractSyntaxTree`].
+///
+/// A conjunction is only supported at the top level of a pattern, and each of
+/// its operands is matched by its own automaton. A pattern without a
+/// conjunction is its only operand.
+pub fn conjuncts(ast: &SymbolicAbstractSyntaxTree) -> Vec<NodeId> {
+    let mut res = Vec::new();
+
+    if let Some(root) = &ast.root {
+        let mut node = root.root();
+
+        while let Node::BinaryExpr {
+            op: Operator::RegexOperator(RegexOperatorKind::Conjunction),
+            lhs,
+            rhs,
+        } = &root[node]
+        {
+            res.push(*rhs);
+            node = *lhs;
+        }
+
+        res.push(node);
+        res.reverse();
+    }
+
+    res
+}
+
+/// Find the names of the objects captured by a [`SymbolicAbstractSyntaxTree`].
+///
+/// Each name is listed once, in the order of the spatial formulas (see
+/// [`captured`]).
+pub fn captures(ast: &SymbolicAbstractSyntaxTree) -> Vec<String> {
+    let formulas = ast.fmap().iter().map(|x| &x.formula).collect::<Vec<_>>();
+
+    self::captured(&formulas)
+}
+
+/// Find the names of the objects captured by a set of spatial formulas.
+///
+/// A capture is always the root of its spatial formula, whereas the variable
+/// of each track-identity predicate (i.e., `@same`) is captured by its own
+/// name. Each name is listed once, in the order of the spatial formulas.
+pub fn captured(formulas: &[&SpatialFormula]) -> Vec<String> {
+    let mut res = IndexSet::new();
+
+    for formula in formulas.iter() {
+        if let Node::UnaryExpr {
+            op:
+                Operator::SpatialOperator(SpatialOperatorKind::S4uOperator(S4uOperatorKind::Capture(
+                    name,
+                ))),
+            ..
+        } = &formula[formula.root()]
+        {
+            res.insert(name.clone());
+        }
+
+        for node in formula.nodes() {
+            if let Node::UnaryExpr {
+                op:
+                    Operator::SpatialOperator(SpatialOperatorKind::S4uOperator(S4uOperatorKind::Same)),
+                child,
+            } = node
+            {
+                if let Node::Operand(OperandKind::Variable(name)) = &formula[*child] {
+                    res.insert(name.clone());
+                }
+            }
+        }
+    }
+
+    res.into_iter().collect()
+}
+
+/// Enumerate the candidate [`Captures`] of a set of names within the frames.
+///
+/// Each candidate captures a tracked object (i.e., an instance identifier)
+/// found within the `frames` by each name, such that a match may be searched
+/// for each. Without any names, the only candidate captures nothing.
+pub fn bindings(names: &[String], frames: &[Frame]) -> Vec<Captures> {
+    if names.is_empty() {
+        return vec![Captures::new()];
+    }
+
+    let mut ids = IndexSet::new();
+
+    for frame in frames.iter() {
+        for sample in frame.samples.iter() {
+            if let Sample::ObjectDetection(record) | Sample::Text(record) = sample {
+                ids.extend(
+                    record
+                        .annotations
+                        .values()
+                        .flatten()
+                        .filter_map(|a| a.instance_id.as_ref()),
+                );
+            }
+        }
+    }
+
+    names
+        .iter()
+        .map(|_| ids.iter())
+        .multi_cartesian_product()
+        .map(|ids| {
+            names
+                .iter()
+                .cloned()
+                .zip(ids.into_iter().map(|id| (*id).clone()))
+                .collect()
+        })
+        .collect()
+}
+
+/// Construct the Regular Expression (RE) of each lookaround of a [`SymbolicAbstractSyntaxTree`].
+///
+/// The lookarounds of the operand of a conjunction at `node` are listed in
+/// the order they appear in it. As lookarounds may not be nested, none of
+/// these contain a marker.
+pub fn lookarounds(ast: &SymbolicAbstractSyntaxTree, node: NodeId) -> Vec<(Direction, String)> {
+    let mut res = Vec::new();
+
+    if let Some(root) = &ast.root {
+        let alphabet = Alphabet::new(ast.fmap().len());
+        self::lookaroundit(root, node, &alphabet, &mut res);
+    }
+
+    res
+}
+
+/// Recursively collect the RE of each lookaround.
+///
+/// This is a helper function that walks the root [`Node`] of a
+/// [`SymbolicAbstractSyntaxTree`] in the same order as [`regexit`].
+fn lookaroundit(
+    tree: &Tree<SymbolicFormula>,
+    node: NodeId,
+    alphabet: &Alphabet,
+    res: &mut Vec<(Direction, String)>,
+) {
+    match &tree[node] {
+        Node::Operand(..) => (),
+        Node::UnaryExpr {
+            op: Operator::RegexOperator(kind),
+            child,
+        } if self::direction(kind).is_some() => {
+            let regex = self::regexit(tree, *child, alphabet, &mut [].iter());
+            res.extend(self::direction(kind).map(|d| (d, regex)));
+        }
+        Node::UnaryExpr { child, .. } => self::lookaroundit(tree, *child, alphabet, res),
+        Node::BinaryExpr { lhs, rhs, .. } => {
+            self::lookaroundit(tree, *lhs, alphabet, res);
+            self::lookaroundit(tree, *rhs, alphabet, res);
+        }
+    }
+}
+
+/// The [`Direction`] of a lookaround operator, if it is one.
+fn direction(kind: &RegexOperatorKind) -> Option<Direction> {
+    match kind {
+        RegexOperatorKind::NegativeLookahead => Some(Direction::Ahead),
+        RegexOperatorKind::PositiveLookbehind => Some(Direction::Behind),
+        _ => None,
+    }
+}
+
 /// Recursively construct an RE.
 ///
 /// This is the helper function that walks the root [`Node`] of a
-/// [`SymbolicAbstractSyntaxTree`] to build the appropriate pattern.
-fn regexit(node: &Node<SymbolicFormula>) -> String {
-    match node {
-        Node::Operand(formula) => String::from(formula.symbol),
+/// [`SymbolicAbstractSyntaxTree`] to build the appropriate pattern. Each
+/// lookaround is replaced by the next of the `markers`.
+fn regexit<'a>(
+    tree: &Tree<SymbolicFormula>,
+    node: NodeId,
+    alphabet: &Alphabet,
+    markers: &mut impl Iterator<Item = &'a u8>,
+) -> String {
+    match &tree[node] {
+        Node::Operand(formula) => {
+            // Encode the symbol.
+            //
+            // Symbols that span multiple bytes must be grouped such that
+            // operators apply to the symbol as a whole.
+            let symbol = alphabet.encode(formula.symbol);
+            let symbol = String::from_utf8_lossy(&symbol);
+
+            if symbol.len() > 1 {
+                return format!("({})", symbol);
+            }
+
+            symbol.to_string()
+        }
+        Node::UnaryExpr {
+            op: Operator::RegexOperator(kind),
+            ..
+        } if self::direction(kind).is_some() => markers
+            .next()
+            .map(|m| (*m as char).to_string())
+            .unwrap_or_default(),
         Node::UnaryExpr { op, child } => {
-            let child = self::regexit(child);
+            let child = self::regexit(tree, *child, alphabet, markers);
 
             match op {
                 Operator::RegexOperator(kind) => match kind {
@@ -69,19 +285,69 @@ fn regexit(node: &Node<SymbolicFormula>) -> String {
                         RangeKind::AtLeast(min) => format!("({}{{{},}})", child, min),
                         RangeKind::Between(min, max) => format!("({}{{{},{}}})", child, min, max),
                     },
+
+                    // Skip any frames until the interval is reached.
+                    //
+                    // The operand matches a single frame; therefore, each
+                    // repetition of it is the next frame of the interval.
+                    RegexOperatorKind::Eventually(min, max) => {
+                        format!("({}{{{},{}}}{})", alphabet.any(), min, max, child)
+                    }
+                    RegexOperatorKind::Globally(min, max, vacuity) => match vacuity {
+                        Vacuity::Strict => format!(
+                            "({}{{{}}}(?:{}){{{}}})",
+                            alphabet.any(),
+                            min,
+                            child,
+                            max - min + 1
+                        ),
+
+                        // The interval may also be cut short by the end of the
+                        // data stream (i.e., of the haystack), such that only
+                        // the frames of it before then must be matched.
+                        Vacuity::Classical => format!(
+                            "({any}{{{min}}}(?:{child}){{{n}}}|{any}{{0,{min}}}(?:{child}){{0,{m}}}$)",
+                            any = alphabet.any(),
+                            min = min,
+                            child = child,
+                            n = max - min + 1,
+                            m = max - min,
+                        ),
+                    },
                     _ => String::new(),
                 },
                 _ => String::new(),
             }
         }
         Node::BinaryExpr { op, lhs, rhs } => {
-            let lhs = self::regexit(lhs);
-            let rhs = self::regexit(rhs);
+            let lhs = self::regexit(tree, *lhs, alphabet, markers);
+            let rhs = self::regexit(tree, *rhs, alphabet, markers);
 
             match op {
                 Operator::RegexOperator(kind) => match kind {
                     RegexOperatorKind::Concatenation => format!("({}{})", lhs, rhs),
                     RegexOperatorKind::Alternation => format!("({}|{})", lhs, rhs),
+                    RegexOperatorKind::Until(min, max, vacuity) => match vacuity {
+                        Vacuity::Strict => format!("((?:{}){{{},{}}}{})", lhs, min, max, rhs),
+
+                        // Similarly, the right operand need not hold if the data
+                        // stream ends within the interval.
+                        Vacuity::Classical => format!(
+                            "((?:{lhs}){{{min},{max}}}{rhs}|(?:{lhs}){{0,{max}}}$)",
+                            lhs = lhs,
+                            rhs = rhs,
+                            min = min,
+                            max = max,
+                        ),
+                    },
+
+                    // Skip any frames between the operands.
+                    //
+                    // The right operand begins within `max` frames after the
+                    // left operand; therefore, at most `max - 1` are skipped.
+                    RegexOperatorKind::Gap(max) => {
+                        format!("({}{}{{0,{}}}{})", lhs, alphabet.any(), max - 1, rhs)
+                    }
                     _ => String::new(),
                 },
                 _ => String::new(),
@@ -96,21 +362,250 @@ fn regexit(node: &Node<SymbolicFormula>) -> String {
 /// patterns and symbols.
 pub fn horizon(ast: &SymbolicAbstractSyntaxTree) -> Option<usize> {
     if let Some(root) = &ast.root {
-        return self::horizonit(root);
+        return self::horizonit(root, root.root());
     }
 
     None
 }
 
+/// Compute the length of the shortest match of a Regular Expression (RE).
+///
+/// Unlike the horizon, this is always bounded. Therefore, it is used to check
+/// a user-specified window is large enough for any match to be found.
+pub fn shortest(ast: &SymbolicAbstractSyntaxTree) -> usize {
+    if let Some(root) = &ast.root {
+        return self::shortestit(root, root.root());
+    }
+
+    0
+}
+
+/// Compute the reach of the lookarounds of a Regular Expression (RE).
+///
+/// A lookaround may look at frames beyond a match (e.g., to exclude a match
+/// followed by an event). This is the most frames a lookaround may look at
+/// after the end of a match (or, if looking [`Direction::Behind`], before its
+/// start), or `None` if unbounded.
+pub fn reach(ast: &SymbolicAbstractSyntaxTree, direction: Direction) -> Option<usize> {
+    if let Some(root) = &ast.root {
+        return self::reachit(root, root.root(), direction);
+    }
+
+    Some(0)
+}
+
+/// Check whether a temporal operator of a Regular Expression (RE) may hold
+/// vacuously.
+///
+/// With [`Vacuity::Classical`] (see [`crate::compiler::Compiler::end`]), a
+/// temporal operator also holds if the data stream ends within its interval.
+/// This end is only known once the data stream is searched as a whole (i.e.,
+/// offline).
+pub fn vacuous(ast: &SymbolicAbstractSyntaxTree) -> bool {
+    ast.root.as_ref().is_some_and(|root| {
+        root.nodes().any(|node| {
+            matches!(
+                node,
+                Node::UnaryExpr {
+                    op: Operator::RegexOperator(RegexOperatorKind::Globally(
+                        ..,
+                        Vacuity::Classical
+                    )),
+                    ..
+                } | Node::BinaryExpr {
+                    op: Operator::RegexOperator(RegexOperatorKind::Until(.., Vacuity::Classical)),
+                    ..
+                }
+            )
+        })
+    })
+}
+
+/// Recursively compute the reach of the lookarounds of an RE.
+///
+/// This is a helper function that walks the root [`Node`] of a
+/// [`SymbolicAbstractSyntaxTree`] similar to [`horizonit`].
+fn reachit(tree: &Tree<SymbolicFormula>, node: NodeId, direction: Direction) -> Option<usize> {
+    match &tree[node] {
+        Node::Operand(..) => Some(0),
+        Node::UnaryExpr {
+            op: Operator::RegexOperator(kind),
+            child,
+        } if self::direction(kind).is_some() => {
+            if self::direction(kind) == Some(direction) {
+                return self::horizonit(tree, *child);
+            }
+
+            Some(0)
+        }
+        Node::UnaryExpr { child, .. } => self::reachit(tree, *child, direction),
+        Node::BinaryExpr { op, lhs, rhs } => {
+            let first = self::reachit(tree, *lhs, direction)?;
+            let second = self::reachit(tree, *rhs, direction)?;
+
+            match op {
+                Operator::RegexOperator(
+                    RegexOperatorKind::Alternation | RegexOperatorKind::Conjunction,
+                ) => Some(std::cmp::max(first, second)),
+
+                // The frames of the other operand are part of the match; therefore,
+                // only a lookaround that looks beyond them reaches beyond it.
+                _ => match direction {
+                    Direction::Ahead => Some(std::cmp::max(
+                        first.saturating_sub(self::shortestit(tree, *rhs)),
+                        second,
+                    )),
+                    Direction::Behind => Some(std::cmp::max(
+                        first,
+                        second.saturating_sub(self::shortestit(tree, *lhs)),
+                    )),
+                },
+            }
+        }
+    }
+}
+
+/// Compute the partial horizon of a Regular Expression (RE).
+///
+/// A pattern without a horizon may still begin and end with bounded components
+/// (e.g., `a{2} b* c{3}`). The horizon of its bounded prefix and suffix is
+/// returned as `(prefix, suffix)`, or `None` if the pattern has a horizon (see
+/// [`horizon`]). A window shorter than their sum (e.g., 5 frames above)
+/// truncates every match that spans the longest prefix and suffix.
+pub fn partial(ast: &SymbolicAbstractSyntaxTree) -> Option<(usize, usize)> {
+    let root = ast.root.as_ref()?;
+
+    if self::horizonit(root, root.root()).is_some() {
+        return None;
+    }
+
+    let (prefix, ..) = self::prefixit(root, root.root(), false);
+    let (suffix, ..) = self::prefixit(root, root.root(), true);
+
+    Some((prefix, suffix))
+}
+
+/// Recursively compute the horizon of the bounded prefix of an RE.
+///
+/// This walks the sequence of components of a [`Node`] from its first (or, if
+/// `reverse`, its last) component until one without a horizon is reached. The
+/// horizon of the components walked is returned, along with whether all of
+/// the components of the [`Node`] were walked (i.e., it is bounded).
+fn prefixit(tree: &Tree<SymbolicFormula>, node: NodeId, reverse: bool) -> (usize, bool) {
+    match &tree[node] {
+        Node::BinaryExpr {
+            op:
+                Operator::RegexOperator(
+                    kind @ (RegexOperatorKind::Concatenation | RegexOperatorKind::Gap(..)),
+                ),
+            lhs,
+            rhs,
+        } => {
+            let (first, second) = if reverse { (rhs, lhs) } else { (lhs, rhs) };
+
+            let (first, bounded) = self::prefixit(tree, *first, reverse);
+
+            if !bounded {
+                return (first, false);
+            }
+
+            // The frames skipped between the operands of a gap are kept as well.
+            let skipped = match kind {
+                RegexOperatorKind::Gap(max) => max - 1,
+                _ => 0,
+            };
+
+            let (second, bounded) = self::prefixit(tree, *second, reverse);
+            (
+                first.saturating_add(skipped).saturating_add(second),
+                bounded,
+            )
+        }
+        _ => match self::horizonit(tree, node) {
+            Some(horizon) => (horizon, true),
+            None => (0, false),
+        },
+    }
+}
+
+/// Recursively compute the length of the shortest match of an RE.
+///
+/// This is a helper function that walks the root [`Node`] of a
+/// [`SymbolicAbstractSyntaxTree`] similar to [`horizonit`].
+fn shortestit(tree: &Tree<SymbolicFormula>, node: NodeId) -> usize {
+    match &tree[node] {
+        Node::Operand(..) => 1,
+        Node::UnaryExpr {
+            op: Operator::RegexOperator(kind),
+            ..
+        } if self::direction(kind).is_some() => 0,
+        Node::UnaryExpr { op, child } => {
+            let ret = self::shortestit(tree, *child);
+
+            match op {
+                Operator::RegexOperator(kind) => match kind {
+                    RegexOperatorKind::KleeneStar => 0,
+                    RegexOperatorKind::Range(kind) => match kind {
+                        RangeKind::Exactly(min)
+                        | RangeKind::AtLeast(min)
+                        | RangeKind::Between(min, ..) => ret.saturating_mul(*min),
+                    },
+                    RegexOperatorKind::Eventually(min, ..) => min.saturating_add(ret),
+                    RegexOperatorKind::Globally(min, max, Vacuity::Strict) => {
+                        min.saturating_add(ret.saturating_mul(max - min).saturating_add(ret))
+                    }
+
+                    // A match may end with the data stream (i.e., be empty).
+                    RegexOperatorKind::Globally(.., Vacuity::Classical) => 0,
+                    _ => ret,
+                },
+                _ => ret,
+            }
+        }
+        Node::BinaryExpr { op, lhs, rhs } => {
+            let lhs = self::shortestit(tree, *lhs);
+            let rhs = self::shortestit(tree, *rhs);
+
+            match op {
+                Operator::RegexOperator(RegexOperatorKind::Alternation) => std::cmp::min(lhs, rhs),
+                Operator::RegexOperator(RegexOperatorKind::Conjunction) => std::cmp::max(lhs, rhs),
+                Operator::RegexOperator(RegexOperatorKind::Until(min, _, Vacuity::Strict)) => {
+                    lhs.saturating_mul(*min).saturating_add(rhs)
+                }
+                Operator::RegexOperator(RegexOperatorKind::Until(.., Vacuity::Classical)) => 0,
+                _ => lhs.saturating_add(rhs),
+            }
+        }
+    }
+}
+
 /// Recursively compute the horizon of an RE.
 ///
 /// This is a helper function that walks the root [`Node`] of a
 /// [`SymbolicAbstractSyntaxTree`] to build the appropriate pattern.
-fn horizonit(node: &Node<SymbolicFormula>) -> Option<usize> {
-    match node {
+fn horizonit(tree: &Tree<SymbolicFormula>, node: NodeId) -> Option<usize> {
+    match &tree[node] {
         Node::Operand(..) => Some(1),
+
+        // A lookahead consumes no frames.
+        //
+        // The frames it looks at may extend beyond the end of a match, and
+        // therefore do not count towards its length.
+        Node::UnaryExpr {
+            op: Operator::RegexOperator(RegexOperatorKind::NegativeLookahead),
+            ..
+        } => Some(0),
+
+        // A lookbehind also consumes no frames.
+        //
+        // However, the frames it looks at must be kept to find a match;
+        // therefore, these count towards the horizon (it is always bounded).
+        Node::UnaryExpr {
+            op: Operator::RegexOperator(RegexOperatorKind::PositiveLookbehind),
+            child,
+        } => self::horizonit(tree, *child),
         Node::UnaryExpr { op, child } => {
-            let ret = self::horizonit(child);
+            let ret = self::horizonit(tree, *child);
 
             match op {
                 Operator::RegexOperator(kind) => match kind {
@@ -118,7 +613,7 @@ fn horizonit(node: &Node<SymbolicFormula>) -> Option<usize> {
                     RegexOperatorKind::Range(kind) => match kind {
                         RangeKind::Exactly(size) => {
                             if let Some(ret) = ret {
-                                return Some(ret * (*size));
+                                return Some(ret.saturating_mul(*size));
                             }
 
                             None
@@ -126,27 +621,33 @@ fn horizonit(node: &Node<SymbolicFormula>) -> Option<usize> {
                         RangeKind::AtLeast(..) => None,
                         RangeKind::Between(.., max) => {
                             if let Some(ret) = ret {
-                                return Some(ret * (*max));
+                                return Some(ret.saturating_mul(*max));
                             }
 
                             None
                         }
                     },
+                    RegexOperatorKind::Eventually(.., max) => {
+                        ret.map(|ret| max.saturating_add(ret))
+                    }
+                    RegexOperatorKind::Globally(min, max, ..) => ret.map(|ret| {
+                        min.saturating_add(ret.saturating_mul(max - min).saturating_add(ret))
+                    }),
                     _ => None,
                 },
                 _ => None,
             }
         }
         Node::BinaryExpr { op, lhs, rhs } => {
-            let lhs = self::horizonit(lhs);
-            let rhs = self::horizonit(rhs);
+            let lhs = self::horizonit(tree, *lhs);
+            let rhs = self::horizonit(tree, *rhs);
 
             match op {
                 Operator::RegexOperator(kind) => match kind {
                     RegexOperatorKind::Concatenation => {
                         if let Some(lhs) = lhs {
                             if let Some(rhs) = rhs {
-                                return Some(lhs + rhs);
+                                return Some(lhs.saturating_add(rhs));
                             }
                         }
 
@@ -161,6 +662,26 @@ fn horizonit(node: &Node<SymbolicFormula>) -> Option<usize> {
 
                         None
                     }
+
+                    // A match of a conjunction is a match of each operand.
+                    //
+                    // Therefore, it is bounded by the shorter horizon.
+                    RegexOperatorKind::Conjunction => match (lhs, rhs) {
+                        (Some(lhs), Some(rhs)) => Some(std::cmp::min(lhs, rhs)),
+                        (lhs, rhs) => lhs.or(rhs),
+                    },
+                    RegexOperatorKind::Until(_, max, ..) => match (lhs, rhs) {
+                        (Some(lhs), Some(rhs)) => {
+                            Some(lhs.saturating_mul(*max).saturating_add(rhs))
+                        }
+                        _ => None,
+                    },
+                    RegexOperatorKind::Gap(max) => match (lhs, rhs) {
+                        (Some(lhs), Some(rhs)) => {
+                            Some(lhs.saturating_add(max - 1).saturating_add(rhs))
+                        }
+                        _ => None,
+                    },
                     _ => None,
                 },
                 _ => None,
@@ -168,3 +689,417 @@ fn horizonit(node: &Node<SymbolicFormula>) -> Option<usize> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::compiler::ir::ops::Vacuity;
+    use crate::compiler::Compiler;
+    use crate::datastream::frame::Frame;
+    use crate::support::frame;
+
+    use super::automata::dfa::lookaround::Direction;
+    use super::{offline, online, Matching};
+
+    /// The interval of a match, if found.
+    type Interval = Option<(usize, usize)>;
+
+    /// Search the frames offline and online, returning the leftmost match of
+    /// each as its interval.
+    fn search(source: &str, frames: &[Frame]) -> (Interval, Interval) {
+        let ast = Compiler::new().compile(source).unwrap();
+
+        let offline = offline::Matcher::try_from(&ast)
+            .unwrap()
+            .leftmost(frames)
+            .unwrap();
+        let online = online::Matcher::try_from(&ast)
+            .unwrap()
+            .leftmost(frames)
+            .unwrap();
+
+        (
+            offline.map(|m| (m.start, m.end)),
+            online.map(|m| (m.start, m.end)),
+        )
+    }
+
+    #[test]
+    fn captures() {
+        let ast = Compiler::new()
+            .compile("(?<v> E(v := [:car:]) @x(v) < 10.0) [@x(\\v) > 50.0]")
+            .unwrap();
+
+        let offline = offline::Matcher::try_from(&ast).unwrap();
+        let online = online::Matcher::try_from(&ast).unwrap();
+
+        // The car that moves away is not the car that started nearby.
+        let frames = vec![
+            frame(0, &[("car", Some("a"), 0.0), ("car", Some("b"), 20.0)]),
+            frame(1, &[("car", Some("a"), 5.0), ("car", Some("b"), 60.0)]),
+        ];
+
+        assert!(offline.leftmost(&frames).unwrap().is_none());
+        assert!(online.leftmost(&frames).unwrap().is_none());
+
+        // The same car moves away.
+        let frames = vec![
+            frame(0, &[("car", Some("a"), 0.0), ("car", Some("b"), 20.0)]),
+            frame(1, &[("car", Some("a"), 60.0), ("car", Some("b"), 20.0)]),
+        ];
+
+        let m = offline.leftmost(&frames).unwrap().unwrap();
+        assert_eq!((m.start, m.end), (0, 2));
+
+        let bitmap = offline.evaluate(&frames).unwrap();
+        let m = offline.leftmost_at(&bitmap, 0).unwrap().unwrap();
+        assert_eq!((m.start, m.end), (0, 2));
+
+        let m = online.leftmost(&frames).unwrap().unwrap();
+        assert_eq!((m.start, m.end), (0, 2));
+    }
+
+    #[test]
+    fn same() {
+        let ast = Compiler::new()
+            .compile("[E(v := [:car:]) @same(v) & @x(v) < 10.0]{2}")
+            .unwrap();
+
+        let offline = offline::Matcher::try_from(&ast).unwrap();
+        let online = online::Matcher::try_from(&ast).unwrap();
+
+        // A different car is nearby in each frame.
+        let frames = vec![
+            frame(0, &[("car", Some("a"), 0.0), ("car", Some("b"), 20.0)]),
+            frame(1, &[("car", Some("a"), 20.0), ("car", Some("b"), 0.0)]),
+        ];
+
+        assert!(offline.leftmost(&frames).unwrap().is_none());
+        assert!(online.leftmost(&frames).unwrap().is_none());
+
+        // The same car stays nearby.
+        let frames = vec![
+            frame(0, &[("car", Some("a"), 0.0), ("car", Some("b"), 20.0)]),
+            frame(1, &[("car", Some("a"), 5.0), ("car", Some("b"), 0.0)]),
+        ];
+
+        let m = offline.leftmost(&frames).unwrap().unwrap();
+        assert_eq!((m.start, m.end), (0, 2));
+
+        let m = online.leftmost(&frames).unwrap().unwrap();
+        assert_eq!((m.start, m.end), (0, 2));
+    }
+
+    #[test]
+    fn temporal() {
+        let frames = [0.0, 0.0, 5.0, 60.0, 0.0]
+            .iter()
+            .enumerate()
+            .map(|(i, x)| frame(i, &[("car", Some("a"), *x)]))
+            .collect::<Vec<Frame>>();
+
+        // The car stays near until it moves away within four frames.
+        //
+        // A match found online always ends at the last frame.
+        assert_eq!(
+            search(
+                "[@x([:car:]) < 10.0] U[0,3] [@x([:car:]) > 50.0]",
+                &frames[..4]
+            ),
+            (Some((0, 4)), Some((0, 4)))
+        );
+        assert_eq!(
+            search(
+                "[@x([:car:]) < 10.0] U[0,2] [@x([:car:]) > 50.0]",
+                &frames[..4]
+            ),
+            (None, Some((1, 4)))
+        );
+
+        assert_eq!(
+            search("G[1,2] [@x([:car:]) < 10.0]", &frames),
+            (Some((0, 3)), None)
+        );
+        assert_eq!(
+            search("F[2,3] [@x([:car:]) > 50.0]", &frames[..4]),
+            (Some((0, 4)), Some((0, 4)))
+        );
+    }
+
+    #[test]
+    fn vacuity() {
+        let frames = [0.0, 0.0, 5.0, 60.0]
+            .iter()
+            .enumerate()
+            .map(|(i, x)| frame(i, &[("car", Some("a"), *x)]))
+            .collect::<Vec<Frame>>();
+
+        let search = |source: &str, vacuity: Vacuity, frames: &[Frame]| {
+            let ast = Compiler::new().end(vacuity).compile(source).unwrap();
+
+            offline::Matcher::try_from(&ast)
+                .unwrap()
+                .leftmost(frames)
+                .unwrap()
+                .map(|m| (m.start, m.end))
+        };
+
+        // The data stream ends before the interval does.
+        let source = "G[1,4] [@x([:car:]) < 10.0]";
+        assert_eq!(search(source, Vacuity::Strict, &frames[..3]), None);
+        assert_eq!(
+            search(source, Vacuity::Classical, &frames[..3]),
+            Some((0, 3))
+        );
+
+        // The car moves away before the data stream ends.
+        assert_eq!(search(source, Vacuity::Classical, &frames), None);
+
+        let source = "[@x([:car:]) < 10.0] U[1,5] [@x([:car:]) > 50.0]";
+        assert_eq!(search(source, Vacuity::Strict, &frames[..3]), None);
+        assert_eq!(
+            search(source, Vacuity::Classical, &frames[..3]),
+            Some((0, 3))
+        );
+        assert_eq!(search(source, Vacuity::Strict, &frames), Some((0, 4)));
+        assert_eq!(search(source, Vacuity::Classical, &frames), Some((0, 4)));
+
+        // The end of the data stream is unknown online.
+        let ast = Compiler::new()
+            .end(Vacuity::Classical)
+            .compile(source)
+            .unwrap();
+        assert!(online::Matcher::try_from(&ast).is_err());
+
+        // A quantifier alone does not depend on it.
+        let ast = Compiler::new()
+            .vacuity(Vacuity::Classical)
+            .compile("[A(x := [:car:]) @x(x) < 10.0]")
+            .unwrap();
+        assert!(online::Matcher::try_from(&ast).is_ok());
+
+        // The vacuity of quantifiers does not apply to temporal operators.
+        let ast = Compiler::new()
+            .vacuity(Vacuity::Classical)
+            .compile(source)
+            .unwrap();
+        assert!(online::Matcher::try_from(&ast).is_ok());
+        assert!(offline::Matcher::try_from(&ast)
+            .unwrap()
+            .leftmost(&frames[..3])
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn gap() {
+        let frames = [0.0, 0.0, 5.0, 60.0]
+            .iter()
+            .enumerate()
+            .map(|(i, x)| frame(i, &[("car", Some("a"), *x)]))
+            .collect::<Vec<Frame>>();
+
+        // The car moves away within three frames of the first, but only
+        // within two frames of the second.
+        //
+        // The offline search is anchored at the first frame, where a match
+        // found online always ends at the last frame.
+        assert_eq!(
+            search("[@x([:car:]) < 1.0] ~{<=3} [@x([:car:]) > 50.0]", &frames),
+            (Some((0, 4)), Some((0, 4)))
+        );
+        assert_eq!(
+            search("[@x([:car:]) < 1.0] ~{<=2} [@x([:car:]) > 50.0]", &frames),
+            (None, Some((1, 4)))
+        );
+        assert_eq!(
+            search("[@x([:car:]) < 1.0] ~{<=1} [@x([:car:]) > 50.0]", &frames),
+            (None, None)
+        );
+
+        assert!(Compiler::new()
+            .compile("[[:car:]] ~{<=0} [[:car:]]")
+            .is_err());
+    }
+
+    #[test]
+    fn trace() {
+        let ast = Compiler::new().compile("[[:a:]][[:b:]]").unwrap();
+        let matcher = offline::Matcher::try_from(&ast).unwrap();
+
+        let steps = |labels: &[&str]| {
+            let bitmap = matcher.evaluate(&crate::support::frames(labels)).unwrap();
+
+            matcher.trace(&bitmap, 0).unwrap()[0]
+                .iter()
+                .map(|step| (step.frame, step.accepting))
+                .collect::<Vec<_>>()
+        };
+
+        // The match ends with the frame consumed last, even though it is only
+        // seen after consuming the next frame (or the end of the frames).
+        assert_eq!(steps(&["a", "b", "c"]), [(0, false), (1, true), (2, false)]);
+        assert_eq!(steps(&["a", "b"]), [(0, false), (1, true)]);
+        assert_eq!(steps(&["b", "a"]), [(0, false)]);
+    }
+
+    #[test]
+    fn many_formulas() {
+        // A distinct formula for each of 60 classes, such that the symbols of
+        // the pattern are not limited to the 52 letters of the alphabet.
+        let labels = (0..60).map(|i| format!("c{}", i)).collect::<Vec<String>>();
+        let labels = labels.iter().map(String::as_str).collect::<Vec<&str>>();
+
+        let source = labels
+            .iter()
+            .map(|label| format!("[[:{}:]]", label))
+            .collect::<String>();
+
+        let frames = crate::support::frames(&labels);
+        assert_eq!(search(&source, &frames), (Some((0, 60)), Some((0, 60))));
+
+        // The frames of the formulas past the 52nd are out of order.
+        let mut shuffled = labels.clone();
+        shuffled.swap(52, 53);
+
+        let frames = crate::support::frames(&shuffled);
+        assert_eq!(search(&source, &frames), (None, None));
+
+        // A match of the formulas on either side of the 52nd only.
+        let source = format!("[[:c51:]][[:c52:]][[:c53:]] | {}", source);
+        let frames = crate::support::frames(&["c51", "c52", "c53"]);
+        assert_eq!(search(&source, &frames), (Some((0, 3)), Some((0, 3))));
+    }
+
+    #[test]
+    fn analysis() {
+        let analyze = |source: &str| {
+            let ast = Compiler::new().compile(source).unwrap();
+
+            (
+                super::shortest(&ast),
+                super::horizon(&ast),
+                super::partial(&ast),
+            )
+        };
+
+        // Bounded.
+        assert_eq!(analyze("[[:car:]]{2,4}"), (2, Some(4), None));
+        assert_eq!(
+            analyze("[[:car:]] | [[:car:]][[:car:]]"),
+            (1, Some(2), None)
+        );
+        assert_eq!(analyze("[[:car:]] ~{<=3} [[:car:]]"), (2, Some(4), None));
+
+        // Unbounded.
+        assert_eq!(analyze("[[:car:]]*"), (0, None, Some((0, 0))));
+        assert_eq!(analyze("[[:car:]]{2,}"), (2, None, Some((0, 0))));
+        assert_eq!(
+            analyze("[[:car:]]{1,2}[[:truck:]]*[[:car:]]{3}"),
+            (4, None, Some((2, 3)))
+        );
+        assert_eq!(
+            analyze("[[:car:]] ~{<=3} [[:truck:]]* [[:car:]]"),
+            (2, None, Some((3, 1)))
+        );
+
+        // Lookarounds consume no frames; however, a lookbehind must be kept.
+        assert_eq!(analyze("[[:car:]](?![[:brake:]])"), (1, Some(1), None));
+        assert_eq!(analyze("(?<=[[:brake:]]{2})[[:car:]]"), (1, Some(3), None));
+        assert_eq!(
+            analyze("(?<=[[:brake:]])[[:car:]][[:truck:]]*"),
+            (1, None, Some((2, 0)))
+        );
+    }
+
+    #[test]
+    fn reach() {
+        let reach = |source: &str| {
+            let ast = Compiler::new().compile(source).unwrap();
+
+            (
+                super::reach(&ast, Direction::Ahead),
+                super::reach(&ast, Direction::Behind),
+            )
+        };
+
+        assert_eq!(reach("[[:car:]]{2}"), (Some(0), Some(0)));
+
+        // A lookahead at the end of a match looks beyond it.
+        assert_eq!(
+            reach("[[:car:]](?![[:brake:]]|[[:car:]][[:brake:]])"),
+            (Some(2), Some(0))
+        );
+        assert_eq!(reach("[[:car:]](?![[:brake:]]*)"), (None, Some(0)));
+
+        // Unless the frames it looks at are part of the match.
+        assert_eq!(
+            reach("[[:car:]](?![[:brake:]]{2})[[:car:]]"),
+            (Some(1), Some(0))
+        );
+        assert_eq!(
+            reach("[[:car:]](?![[:brake:]])[[:car:]] | [[:car:]]"),
+            (Some(0), Some(0))
+        );
+
+        // Similarly, a lookbehind at the start of a match looks before it.
+        assert_eq!(reach("(?<=[[:brake:]]{2})[[:car:]]"), (Some(0), Some(2)));
+        assert_eq!(
+            reach("[[:car:]](?<=[[:car:]][[:brake:]]{0,2})"),
+            (Some(0), Some(2))
+        );
+    }
+
+    #[test]
+    fn fuzzy() {
+        let find = |source: &str, xs: &[f64], k: usize| {
+            let frames = xs
+                .iter()
+                .enumerate()
+                .map(|(i, x)| frame(i, &[("car", Some("a"), *x)]))
+                .collect::<Vec<Frame>>();
+
+            let ast = Compiler::new().compile(source).unwrap();
+
+            let offline = offline::Matcher::try_from(&ast)
+                .unwrap()
+                .fuzzy(k)
+                .leftmost(&frames)
+                .unwrap();
+            let online = online::Matcher::try_from(&ast)
+                .unwrap()
+                .fuzzy(k)
+                .leftmost(&frames)
+                .unwrap();
+
+            (
+                offline.map(|m| (m.start, m.end)),
+                online.map(|m| (m.start, m.end)),
+            )
+        };
+
+        let source = "[@x([:car:]) < 10.0]{4}";
+
+        // Each frame far from the origin is a substitution.
+        assert_eq!(find(source, &[0.0, 0.0, 50.0, 0.0], 0), (None, None));
+        assert_eq!(
+            find(source, &[0.0, 0.0, 50.0, 0.0], 1),
+            (Some((0, 4)), Some((0, 4)))
+        );
+        assert_eq!(find(source, &[0.0, 50.0, 50.0, 0.0], 1), (None, None));
+        assert_eq!(
+            find(source, &[0.0, 50.0, 50.0, 0.0], 2),
+            (Some((0, 4)), Some((0, 4)))
+        );
+
+        // Without any substitutions, the search is exact.
+        for xs in [[0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 50.0, 0.0]] {
+            let frames = xs
+                .iter()
+                .enumerate()
+                .map(|(i, x)| frame(i, &[("car", Some("a"), *x)]))
+                .collect::<Vec<Frame>>();
+
+            assert_eq!(find(source, &xs, 0), search(source, &frames));
+        }
+    }
+}
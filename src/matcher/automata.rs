@@ -1,13 +1,75 @@
+use std::fmt;
+
 use regex_automata::dfa::{dense, Automaton};
 use regex_automata::util::primitives::StateID;
 
+use crate::symbolizer::ast::Symbol;
+
 pub mod dfa;
 
+/// The set of digits used to encode a [`Symbol`].
+const DIGITS: &[u8; 52] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// The byte transitioned on when no spatial formula is satisfied.
+///
+/// This must never be a member of [`DIGITS`] such that it never matches any
+/// part of a pattern.
+pub const BLANK: u8 = b'_';
+
+/// The input alphabet of an automaton.
+///
+/// Each [`Symbol`] is encoded as a fixed-width sequence of bytes from
+/// [`DIGITS`] such that any number of symbols may be represented. The width is
+/// the smallest needed for the number of symbols, so patterns with few
+/// formulas are encoded as a single byte per symbol.
+#[derive(Debug, Clone, Copy)]
+pub struct Alphabet {
+    width: usize,
+}
+
+impl Alphabet {
+    /// Create a new [`Alphabet`] that can encode `size` symbols.
+    pub fn new(size: usize) -> Self {
+        let mut width = 1;
+        let mut capacity = DIGITS.len();
+
+        while capacity < size {
+            width += 1;
+            capacity = capacity.saturating_mul(DIGITS.len());
+        }
+
+        Alphabet { width }
+    }
+
+    /// Encode a [`Symbol`] as a sequence of bytes.
+    ///
+    /// The most significant digit is placed first.
+    pub fn encode(&self, symbol: Symbol) -> Vec<u8> {
+        let mut bytes = vec![DIGITS[0]; self.width];
+        let mut value = symbol;
+
+        for byte in bytes.iter_mut().rev() {
+            *byte = DIGITS[value % DIGITS.len()];
+            value /= DIGITS.len();
+        }
+
+        bytes
+    }
+
+    /// The Regular Expression of any one frame.
+    ///
+    /// A frame transitions on the symbol of each satisfied formula, or on
+    /// [`BLANK`] if none are satisfied.
+    pub fn any(&self) -> String {
+        format!("(?:[a-zA-Z]{{{}}}|{})", self.width, BLANK as char)
+    }
+}
+
 /// The underlying representation of the `regex-automata` DFA. From their own
 /// implementation, this is the default choice.
 pub type AutomatonType = dense::DFA<Vec<u32>>;
 
-#[derive(Hash, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, Hash, PartialEq, Eq, Debug)]
 pub enum State {
     Start(StateID),
     Accepting(StateID),
@@ -32,6 +94,30 @@ impl State {
         }
     }
 
+    /// Transition on a sequence of bytes.
+    ///
+    /// As matches are delayed by a single byte, whether a match ends before
+    /// the sequence is determined by the state after the first byte; the
+    /// remaining bytes of the sequence are then taken from there.
+    pub fn next(&self, bytes: &[u8], automaton: &AutomatonType) -> Self {
+        let mut sid = *self.id();
+        let mut accepting = false;
+
+        for (i, byte) in bytes.iter().enumerate() {
+            sid = automaton.next_state(sid, *byte);
+
+            if i == 0 {
+                accepting = automaton.is_match_state(sid);
+            }
+        }
+
+        if accepting {
+            return Self::Accepting(sid);
+        }
+
+        Self::new(sid, automaton)
+    }
+
     /// Retreive the associated [`StateID`].
     #[inline]
     pub fn id(&self) -> &StateID {
@@ -43,3 +129,31 @@ impl State {
         }
     }
 }
+
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            State::Start(sid) => write!(f, "{} (start)", sid.as_usize()),
+            State::Accepting(sid) => write!(f, "{} (accepting)", sid.as_usize()),
+            State::Dead(sid) => write!(f, "{} (dead)", sid.as_usize()),
+            State::Normal(sid) => write!(f, "{}", sid.as_usize()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode() {
+        let alphabet = Alphabet::new(52);
+        assert_eq!(alphabet.encode(0), b"a");
+        assert_eq!(alphabet.encode(51), b"Z");
+
+        let alphabet = Alphabet::new(53);
+        assert_eq!(alphabet.encode(0), b"aa");
+        assert_eq!(alphabet.encode(52), b"ba");
+        assert_eq!(alphabet.any(), "(?:[a-zA-Z]{2}|_)");
+    }
+}
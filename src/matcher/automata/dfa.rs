@@ -1,10 +1,14 @@
+use std::collections::HashMap;
 use std::error::Error;
 
 use regex_automata::HalfMatch;
 
 use crate::datastream::frame::Frame;
 
+use super::State;
+
 pub mod forward;
+pub mod lookaround;
 pub mod reverse;
 
 /// A trait for all DFA's.
@@ -23,3 +27,52 @@ pub trait DeterministicFiniteAutomaton {
 /// This is set as the end part of a match is exclusive (i.e., open), so the
 /// actual end index should be offset, accordingly.
 pub const OFFSET: usize = 1;
+
+/// Take the next transitions of a set of states, allowing mismatched frames.
+///
+/// Each state transitions on the satisfied symbols (`truths`) as usual, where
+/// `next` takes the transition on the symbol at a position (or on [`BLANK`](super::BLANK) if
+/// `None`). In addition, a state with fewer than `fuzz` mismatched frames also
+/// transitions on each symbol that is not satisfied, at the cost of one more
+/// mismatched frame. Each state keeps the fewest mismatched frames it is
+/// reached with.
+pub fn fuzz<F>(
+    states: HashMap<State, usize>,
+    fuzz: usize,
+    symbols: usize,
+    truths: &[usize],
+    next: F,
+) -> HashMap<State, usize>
+where
+    F: Fn(&State, Option<usize>) -> State,
+{
+    let mut nexts: HashMap<State, usize> = HashMap::new();
+    let mut insert = |state: State, errors: usize| {
+        nexts
+            .entry(state)
+            .and_modify(|e| *e = usize::min(*e, errors))
+            .or_insert(errors);
+    };
+
+    for (state, errors) in states.into_iter() {
+        for i in truths.iter() {
+            insert(next(&state, Some(*i)), errors);
+        }
+
+        if truths.is_empty() {
+            insert(next(&state, None), errors);
+        }
+
+        // Substitute a mismatched frame.
+        //
+        // This is equivalent to an edit distance of substitutions only, such
+        // that the length of a match is unchanged.
+        if errors < fuzz {
+            for i in (0..symbols).filter(|i| !truths.contains(i)) {
+                insert(next(&state, Some(i)), errors + 1);
+            }
+        }
+    }
+
+    nexts
+}
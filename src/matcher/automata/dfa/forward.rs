@@ -8,11 +8,14 @@ use regex_automata::util::syntax;
 use regex_automata::{Anchored, HalfMatch, PatternID};
 
 use crate::compiler::ir::ast::SpatialFormula;
+use crate::compiler::ir::NodeId;
 use crate::datastream::frame::Frame;
-use crate::matcher::automata::{AutomatonType, State};
-use crate::monitor::Monitor;
+use crate::matcher::automata::{Alphabet, AutomatonType, State, BLANK};
+use crate::matcher::bitmap::Bitmap;
+use crate::monitor::{Captures, Monitor, MonitorError};
 use crate::symbolizer::ast::SymbolicAbstractSyntaxTree as AST;
 
+use super::lookaround::{self, Direction, Lookaround};
 use super::DeterministicFiniteAutomaton;
 
 /// A forward matching DFA.
@@ -21,7 +24,62 @@ use super::DeterministicFiniteAutomaton;
 /// only to find the end position of a search.
 pub struct DeterministicFiniteAutomata<'a> {
     pub automata: AutomatonType,
-    pub fmap: HashMap<char, &'a SpatialFormula>,
+
+    /// The encoded symbol of each spatial formula.
+    ///
+    /// The position of a formula is used as its column in a [`Bitmap`].
+    pub fmap: Vec<(Vec<u8>, &'a SpatialFormula)>,
+
+    /// The maximum number of frames of a match that may violate their symbol.
+    pub fuzz: usize,
+
+    /// The subpattern of each lookaround, in order of its marker.
+    pub lookarounds: Vec<Lookaround>,
+
+    /// The other operands of a conjunction, which must match the same frames.
+    pub conjuncts: Vec<DeterministicFiniteAutomata<'a>>,
+
+    /// The names of the objects captured by the pattern.
+    pub captures: Vec<String>,
+}
+
+/// The active states of a search after consuming a frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Step {
+    /// The position of the frame consumed.
+    pub frame: usize,
+
+    /// The identifiers of the states that are not dead, in ascending order.
+    pub states: Vec<usize>,
+
+    /// Whether a match ends with the frame consumed.
+    pub accepting: bool,
+}
+
+impl Step {
+    /// Create a new [`Step`] from the active states after consuming `frame`.
+    ///
+    /// Whether a match ends with the frame is only known after consuming the
+    /// next frame; therefore, it is set afterwards.
+    fn new<'s>(frame: usize, states: impl Iterator<Item = &'s State>) -> Self {
+        let mut states = states
+            .filter_map(|state| match state {
+                State::Dead(..) => None,
+                State::Start(sid) | State::Accepting(sid) | State::Normal(sid) => {
+                    Some(sid.as_usize())
+                }
+            })
+            .collect::<Vec<usize>>();
+
+        states.sort_unstable();
+        states.dedup();
+
+        Step {
+            frame,
+            states,
+            accepting: false,
+        }
+    }
 }
 
 impl DeterministicFiniteAutomaton for DeterministicFiniteAutomata<'_> {
@@ -35,94 +93,412 @@ impl DeterministicFiniteAutomaton for DeterministicFiniteAutomata<'_> {
     /// to collect all possible matches over the complete haystack.
     fn run(&self, haystack: &[Frame]) -> Result<Vec<HalfMatch>, Box<dyn Error>> {
         let mut mats = Vec::new();
-        let mut states = HashSet::new();
+
+        // Search with each candidate of the captured objects.
+        //
+        // A match of any candidate is a match of the pattern (i.e., some
+        // objects exist such that the pattern is satisfied).
+        for captures in crate::matcher::bindings(&self.captures, haystack) {
+            for m in self.simulate(0, haystack.len(), |at| {
+                self.evaluate(&haystack[at], &captures)
+            })? {
+                if !mats.contains(&m) {
+                    mats.push(m);
+                }
+            }
+        }
+
+        Ok(mats)
+    }
+}
+
+impl<'a> DeterministicFiniteAutomata<'a> {
+    /// Create a new forward-matching DFA.
+    ///
+    /// This function is exposed if a different configuration is requierd.
+    /// Otherwise, for all other cases, use the [`self::build`] interface to
+    /// construct this DFA.
+    pub fn new(automata: AutomatonType, fmap: Vec<(Vec<u8>, &'a SpatialFormula)>) -> Self {
+        DeterministicFiniteAutomata {
+            automata,
+            fmap,
+            fuzz: 0,
+            lookarounds: Vec::new(),
+            conjuncts: Vec::new(),
+            captures: Vec::new(),
+        }
+    }
+
+    /// Simulate the DFA over a precomputed [`Bitmap`].
+    ///
+    /// This is equivalent to [`DeterministicFiniteAutomaton::run`] on the
+    /// frames from `offset` onwards; however, no spatial formulas are evaluated.
+    pub fn run_bitmap(
+        &self,
+        bitmap: &Bitmap,
+        offset: usize,
+    ) -> Result<Vec<HalfMatch>, Box<dyn Error>> {
+        let mut mats = Vec::new();
+
+        for k in 0..self.blocks(bitmap) {
+            for m in self.simulate(offset, bitmap.len() - offset, |at| {
+                Ok(self.block(bitmap, at, k))
+            })? {
+                if !mats.contains(&m) {
+                    mats.push(m);
+                }
+            }
+        }
+
+        Ok(mats)
+    }
+
+    /// Trace a search of this DFA alone over a precomputed [`Bitmap`].
+    ///
+    /// This returns the active states after each frame consumed by the search
+    /// from `offset`, for each candidate of the captured objects. The search
+    /// stops once all states are dead.
+    pub fn trace(&self, bitmap: &Bitmap, offset: usize) -> Result<Vec<Vec<Step>>, Box<dyn Error>> {
+        let mut traces = Vec::new();
+
+        for k in 0..self.blocks(bitmap) {
+            let mut steps = Vec::new();
+
+            self.search(
+                offset,
+                bitmap.len() - offset,
+                &|at| Ok(self.block(bitmap, at, k)),
+                Some(&mut steps),
+            )?;
+
+            traces.push(steps);
+        }
+
+        Ok(traces)
+    }
+
+    /// Measure the progress of a search over a precomputed [`Bitmap`].
+    ///
+    /// The DFA is simulated from `offset` until all states are dead. This
+    /// returns the number of frames consumed before then, along with the
+    /// positions of the spatial formulas that would have kept a state alive at
+    /// the next frame. If the frames run out first, all frames are consumed.
+    ///
+    /// Of a conjunction, the operand that fails first is measured. Of the
+    /// candidates of the captured objects, the one that progresses furthest is
+    /// measured.
+    pub fn progress(
+        &self,
+        bitmap: &Bitmap,
+        offset: usize,
+    ) -> Result<(usize, Vec<usize>), Box<dyn Error>> {
+        let mut furthest: Option<(usize, Vec<usize>)> = None;
+
+        for k in 0..self.blocks(bitmap) {
+            let mut progress = self.measure(bitmap, offset, k)?;
+
+            for conjunct in self.conjuncts.iter() {
+                let other = conjunct.measure(bitmap, offset, k)?;
+
+                if other.0 < progress.0 {
+                    progress = other;
+                }
+            }
+
+            if furthest.as_ref().is_none_or(|f| progress.0 > f.0) {
+                furthest = Some(progress);
+            }
+        }
+
+        Ok(furthest.unwrap_or_default())
+    }
+
+    /// Measure the progress of a search of this DFA alone over the `k`-th block.
+    fn measure(
+        &self,
+        bitmap: &Bitmap,
+        offset: usize,
+        k: usize,
+    ) -> Result<(usize, Vec<usize>), Box<dyn Error>> {
+        let len = bitmap.len() - offset;
+        let truths = |at: usize| Ok(self.block(bitmap, at, k));
+
+        let mut states = HashMap::new();
+        states.insert(self.initial()?, 0);
+        self.close(&mut states, offset, bitmap.len(), &truths)?;
+
+        for at in 0..len {
+            let mut nexts: HashMap<State, usize> = states
+                .keys()
+                .flat_map(|state| self.transition(*state, &self.block(bitmap, offset + at, k)))
+                .map(|state| (state, 0))
+                .collect();
+
+            self.close(&mut nexts, offset + at + 1, bitmap.len(), &truths)?;
+
+            if nexts.keys().all(|state| matches!(state, State::Dead(..))) {
+                let expected = (0..self.fmap.len())
+                    .filter(|i| {
+                        states.keys().any(|state| {
+                            !matches!(
+                                state.next(&self.fmap[*i].0, &self.automata),
+                                State::Dead(..)
+                            )
+                        })
+                    })
+                    .collect();
+
+                return Ok((at, expected));
+            }
+
+            states = nexts;
+        }
+
+        Ok((len, Vec::new()))
+    }
+
+    /// Evaluate each spatial formula against each [`Frame`].
+    ///
+    /// The resulting [`Bitmap`] may be reused by [`Self::run_bitmap`] for any
+    /// offset into the frames.
+    ///
+    /// If objects are captured, the formulas are evaluated for each candidate
+    /// of the captured objects (see [`crate::matcher::bindings`]), where each
+    /// candidate is given its own block of columns.
+    pub fn bitmap(&self, frames: &[Frame]) -> Result<Bitmap, MonitorError> {
+        let bindings = crate::matcher::bindings(&self.captures, frames);
+        let mut bitmap = Bitmap::new(frames.len(), self.fmap.len() * bindings.len());
+
+        for (row, frame) in frames.iter().enumerate() {
+            for (k, captures) in bindings.iter().enumerate() {
+                for column in self.evaluate(frame, captures)? {
+                    bitmap.set(row, k * self.fmap.len() + column);
+                }
+            }
+        }
+
+        Ok(bitmap)
+    }
+
+    /// The number of blocks of columns of a [`Bitmap`] (see [`Self::bitmap`]).
+    fn blocks(&self, bitmap: &Bitmap) -> usize {
+        bitmap.columns().checked_div(self.fmap.len()).unwrap_or(1)
+    }
+
+    /// Retrieve the positions of the satisfied formulas of the `k`-th block of
+    /// columns of a [`Bitmap`] at `row`.
+    fn block(&self, bitmap: &Bitmap, row: usize, k: usize) -> Vec<usize> {
+        bitmap
+            .ones(row)
+            .into_iter()
+            .filter(|column| column / self.fmap.len() == k)
+            .map(|column| column % self.fmap.len())
+            .collect()
+    }
+
+    /// Find the spatial formulas satisfied by the [`Frame`].
+    ///
+    /// This returns the positions of the satisfied formulas in `fmap`, where
+    /// each captured object is resolved by `captures`.
+    fn evaluate(&self, frame: &Frame, captures: &Captures) -> Result<Vec<usize>, MonitorError> {
+        let monitor = Monitor::new();
+        let mut truths = Vec::new();
+
+        for (i, (_, formula)) in self.fmap.iter().enumerate() {
+            if monitor.evaluate_with(frame, formula, captures)? {
+                truths.push(i);
+            }
+        }
+
+        Ok(truths)
+    }
+
+    /// Simulate the DFA over `len` steps.
+    ///
+    /// At each step, `truths` provides the positions of the spatial formulas
+    /// that are satisfied by the frame at a position, where the search starts
+    /// at `offset`. The frames before the `offset` are only looked at by a
+    /// lookbehind.
+    fn simulate<F>(
+        &self,
+        offset: usize,
+        len: usize,
+        truths: F,
+    ) -> Result<Vec<HalfMatch>, Box<dyn Error>>
+    where
+        F: Fn(usize) -> Result<Vec<usize>, MonitorError>,
+    {
+        let mut mats = self.search(offset, len, &truths, None)?;
+
+        // Keep only the matches of every operand of a conjunction.
+        //
+        // Each operand is simulated over the same frames, such that a match
+        // ends where all of them do (i.e., the product of their automata).
+        for conjunct in self.conjuncts.iter() {
+            if mats.is_empty() {
+                break;
+            }
+
+            let ends = conjunct
+                .search(offset, len, &truths, None)?
+                .iter()
+                .map(|m| m.offset())
+                .collect::<HashSet<usize>>();
+
+            mats.retain(|m| ends.contains(&m.offset()));
+        }
+
+        Ok(mats)
+    }
+
+    /// Simulate this DFA alone over `len` steps.
+    ///
+    /// If `steps` is given, the active states after each frame consumed are
+    /// recorded (see [`Self::trace`]).
+    fn search<F>(
+        &self,
+        offset: usize,
+        len: usize,
+        truths: &F,
+        mut steps: Option<&mut Vec<Step>>,
+    ) -> Result<Vec<HalfMatch>, Box<dyn Error>>
+    where
+        F: Fn(usize) -> Result<Vec<usize>, MonitorError>,
+    {
+        let mut mats = Vec::new();
+        let mut states = HashMap::new();
 
         // Initialize states with the start state of the DFA.
-        states.insert(self.initial()?);
+        //
+        // Each state is paired with the fewest number of mismatched frames
+        // needed to reach it.
+        states.insert(self.initial()?, 0);
+        self.close(&mut states, offset, offset + len, truths)?;
+
+        for at in 0..len {
+            let ones = truths(offset + at)?;
 
-        for (at, frame) in haystack.iter().enumerate() {
             // Get the next set of states.
             //
-            // This should generate a new [`HashSet`] with only the next set of
+            // This should generate a new [`HashMap`] with only the next set of
             // states. We do not keep a historical record of previously visited
             // states to reduce memory usage.
-            states = states
-                .into_iter()
-                .flat_map(|state| self.transition(state, frame))
-                .collect();
+            states = super::fuzz(
+                states,
+                self.fuzz,
+                self.fmap.len(),
+                &ones,
+                |state, i| match i {
+                    Some(i) => state.next(&self.fmap[i].0, &self.automata),
+                    None => state.next(&[BLANK], &self.automata),
+                },
+            );
+
+            if let Some(steps) = steps.as_deref_mut() {
+                steps.push(Step::new(offset + at, states.keys()));
+            }
 
             // For each state, take action upon it.
             //
             // It is important to produce any [`HalfMatch`] when an
             // [`State::Accepting`] is seen.
-            for state in states.iter() {
+            for state in states.keys() {
                 match state {
                     State::Accepting(..) => {
                         mats.push(HalfMatch::new(PatternID::new(0)?, at));
+
+                        // A match is only seen after consuming the frame that
+                        // follows it; therefore, it ends at the previous step.
+                        if let (Some(steps), Some(last)) = (steps.as_deref_mut(), at.checked_sub(1))
+                        {
+                            steps[last].accepting = true;
+                        }
                     }
                     _ => continue,
                 }
             }
 
+            self.close(&mut states, offset + at + 1, offset + len, truths)?;
+
             // Return current matches if all states are dead.
             //
             // This is checked after producing potential [`HalfMatch`] as the
             // condition to exit is only when all branches are dead---contrary to
             // single branch execution.
-            if states.iter().all(|state| matches!(state, State::Dead(..))) {
+            if states.keys().all(|state| matches!(state, State::Dead(..))) {
                 return Ok(mats);
             }
         }
 
-        for state in states {
-            if let Some(m) = self.eoi(state, haystack)? {
+        for state in states.into_keys() {
+            if let Some(m) = self.eoi(state, len)? {
                 mats.push(m);
+
+                if let Some(step) = steps.as_deref_mut().and_then(|s| s.last_mut()) {
+                    step.accepting = true;
+                }
             }
         }
 
         Ok(mats)
     }
-}
 
-impl<'a> DeterministicFiniteAutomata<'a> {
-    /// Create a new forward-matching DFA.
+    /// Take the transitions on the marker of each lookaround that holds at `at`.
     ///
-    /// This function is exposed if a different configuration is requierd.
-    /// Otherwise, for all other cases, use the [`self::build`] interface to
-    /// construct this DFA.
-    pub fn new(automata: AutomatonType, fmap: HashMap<char, &'a SpatialFormula>) -> Self {
-        DeterministicFiniteAutomata { automata, fmap }
+    /// A lookahead looks at the frames from `at` until `end`, and a lookbehind
+    /// at the frames before `at`, where `truths` provides the spatial formulas
+    /// satisfied by the frame at each position.
+    fn close<F>(
+        &self,
+        states: &mut HashMap<State, usize>,
+        at: usize,
+        end: usize,
+        truths: &F,
+    ) -> Result<(), Box<dyn Error>>
+    where
+        F: Fn(usize) -> Result<Vec<usize>, MonitorError>,
+    {
+        if self.lookarounds.is_empty() {
+            return Ok(());
+        }
+
+        let symbols = self
+            .fmap
+            .iter()
+            .map(|(symbol, _)| symbol.as_slice())
+            .collect::<Vec<&[u8]>>();
+
+        lookaround::closure(states, &self.automata, self.lookarounds.len(), |k| {
+            let lookaround = &self.lookarounds[k];
+
+            lookaround.holds(&symbols, |i| match lookaround.direction() {
+                Direction::Ahead if at + i < end => truths(at + i).map(Some),
+                Direction::Behind if i < at => truths(at - 1 - i).map(Some),
+                _ => Ok(None),
+            })
+        })
     }
 
     /// Take the next transition on the `Frame`.
     ///
     /// For this implementation, whether to take a transition is determined by
-    /// whether the [`Monitor`] evaluates to true on the [`Frame`]. The cases are
-    /// as follows:
+    /// whether the [`Monitor`] evaluated to true on the [`Frame`] (i.e., the
+    /// formula is among the `truths`). The cases are as follows:
     ///
     /// I. If true, transition on the corresponding symbol from the [`State`].
-    /// II. If false, transition on a blank symbol from the [`State`].
+    /// II. If false, transition on the [`BLANK`] byte from the [`State`].
     ///
     /// For (II), this is similar to transitioning on a byte that is not in teh
     /// pattern of a traditional RE.
-    fn transition(&self, state: State, frame: &Frame) -> HashSet<State> {
-        let monitor = Monitor::new();
+    fn transition(&self, state: State, truths: &[usize]) -> HashSet<State> {
         let mut nexts = HashSet::new();
 
-        for (symbol, formula) in self.fmap.iter() {
-            if monitor.evaluate(frame, formula) {
-                let sid = self.automata.next_state(*state.id(), *symbol as u8);
-                let next = State::new(sid, &self.automata);
-
-                nexts.insert(next);
-            }
+        for i in truths.iter() {
+            nexts.insert(state.next(&self.fmap[*i].0, &self.automata));
         }
 
         if nexts.is_empty() {
-            let sid = self.automata.next_state(*state.id(), b'Z');
-            let next = State::new(sid, &self.automata);
-
-            nexts.insert(next);
+            nexts.insert(state.next(&[BLANK], &self.automata));
         }
 
         nexts
@@ -132,9 +508,9 @@ impl<'a> DeterministicFiniteAutomata<'a> {
     ///
     /// The End of Input (EOI) is checked for a final match. If taking the EOI
     /// transition results in a match state, then return as final match.
-    fn eoi(&self, state: State, haystack: &[Frame]) -> Result<Option<HalfMatch>, Box<dyn Error>> {
+    fn eoi(&self, state: State, len: usize) -> Result<Option<HalfMatch>, Box<dyn Error>> {
         if let State::Accepting(..) = self.transitioneoi(state) {
-            return Ok(Some(HalfMatch::new(PatternID::new(0)?, haystack.len())));
+            return Ok(Some(HalfMatch::new(PatternID::new(0)?, len)));
         }
 
         Ok(None)
@@ -182,7 +558,35 @@ impl<'a> DeterministicFiniteAutomata<'a> {
 /// The `regex-automata` library is used primarily here to construct the
 /// underlying state machine that performs matching. We then wrap this result
 /// into a [`DeterministicFiniteAutomata`] for simple interfacing.
-pub fn build(ast: &AST) -> Result<DeterministicFiniteAutomata, Box<dyn Error>> {
+///
+/// Each operand of a conjunction is built into its own DFA, where the first is
+/// returned with the others as its conjuncts.
+pub fn build(ast: &AST) -> Result<DeterministicFiniteAutomata<'_>, Box<dyn Error>> {
+    let mut dfas = crate::matcher::conjuncts(ast)
+        .into_iter()
+        .map(|node| self::conjunct(ast, Some(node)))
+        .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+
+    if dfas.is_empty() {
+        return self::conjunct(ast, None);
+    }
+
+    let mut dfa = dfas.remove(0);
+    dfa.conjuncts = dfas;
+    dfa.captures = crate::matcher::captures(ast);
+
+    Ok(dfa)
+}
+
+/// Build a forward searching DFA of the operand of a conjunction at `node`.
+fn conjunct(
+    ast: &AST,
+    node: Option<NodeId>,
+) -> Result<DeterministicFiniteAutomata<'_>, Box<dyn Error>> {
+    let regex = node
+        .map(|node| crate::matcher::regexify(ast, node))
+        .unwrap_or_default();
+
     let automata = dense::Builder::new()
         .configure(
             dense::Config::new()
@@ -193,13 +597,22 @@ pub fn build(ast: &AST) -> Result<DeterministicFiniteAutomata, Box<dyn Error>> {
         )
         .syntax(syntax::Config::new().unicode(false).utf8(true))
         .thompson(thompson::Config::new().reverse(false).utf8(true))
-        .build(&super::super::super::regexify(ast))?;
+        .build(&regex)?;
 
+    let alphabet = Alphabet::new(ast.fmap().len());
     let fmap = ast
         .fmap()
         .iter()
-        .map(|x| (x.symbol, &x.formula))
-        .collect::<HashMap<char, &SpatialFormula>>();
+        .map(|x| (alphabet.encode(x.symbol), &x.formula))
+        .collect::<Vec<(Vec<u8>, &SpatialFormula)>>();
+
+    let mut dfa = DeterministicFiniteAutomata::new(automata, fmap);
+    dfa.lookarounds = node
+        .map(|node| crate::matcher::lookarounds(ast, node))
+        .unwrap_or_default()
+        .iter()
+        .map(|(direction, regex)| Lookaround::build(*direction, regex))
+        .collect::<Result<Vec<Lookaround>, Box<dyn Error>>>()?;
 
-    Ok(DeterministicFiniteAutomata::new(automata, fmap))
+    Ok(dfa)
 }
@@ -0,0 +1,293 @@
+//! Lookaround assertions.
+//!
+//! A lookaround asserts a condition on the frames around its position without
+//! consuming any of them: a negative lookahead (i.e., `(?!...)`) asserts that
+//! the frames from its position do not begin with a match of its subpattern,
+//! and a positive lookbehind (i.e., `(?<=...)`) asserts that the frames before
+//! its position end with a match of it. As the underlying automaton cannot
+//! express this, the position of each lookaround is marked within the pattern
+//! by a byte of [`MARKERS`], and each subpattern is compiled into its own
+//! [`Lookaround`]. While simulating the automaton of the pattern, the marker of
+//! a lookaround is only transitioned on where it holds, such that the search
+//! runs over the product of the pattern with each lookbehind and with the
+//! complement of each lookahead.
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+
+use regex_automata::dfa::{dense, Automaton, StartKind};
+use regex_automata::nfa::thompson;
+use regex_automata::util::start::Config;
+use regex_automata::util::syntax;
+use regex_automata::Anchored;
+
+use crate::matcher::automata::{AutomatonType, State, BLANK};
+use crate::monitor::MonitorError;
+
+/// The byte marking the position of each lookaround within a pattern.
+///
+/// These must never be a member of the digits of a symbol nor the
+/// [`BLANK`] byte, such that a marker is never transitioned on by a frame.
+pub const MARKERS: &[u8; 10] = b"0123456789";
+
+/// The direction a [`Lookaround`] looks in from its position.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    /// A negative lookahead.
+    Ahead,
+
+    /// A positive lookbehind.
+    Behind,
+}
+
+/// The subpattern of a lookaround.
+pub struct Lookaround {
+    automata: AutomatonType,
+    direction: Direction,
+}
+
+impl Lookaround {
+    /// Build a new [`Lookaround`] from the Regular Expression (RE) of its
+    /// subpattern.
+    ///
+    /// A lookbehind is matched backwards from its position; therefore, its
+    /// automaton is built in reverse.
+    pub fn build(direction: Direction, regex: &str) -> Result<Self, Box<dyn Error>> {
+        let automata = dense::Builder::new()
+            .configure(
+                dense::Config::new()
+                    .minimize(true)
+                    .accelerate(false)
+                    .start_kind(StartKind::Anchored)
+                    .specialize_start_states(true),
+            )
+            .syntax(syntax::Config::new().unicode(false).utf8(true))
+            .thompson(
+                thompson::Config::new()
+                    .reverse(direction == Direction::Behind)
+                    .utf8(true),
+            )
+            .build(regex)?;
+
+        Ok(Lookaround {
+            automata,
+            direction,
+        })
+    }
+
+    /// The direction of the [`Lookaround`].
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Check if the [`Lookaround`] holds at a position.
+    ///
+    /// The `truths` provide the positions (in `symbols`, encoded forwards) of
+    /// the spatial formulas satisfied by each frame in the direction of the
+    /// lookaround, or `None` past the last such frame. That is, the frames
+    /// from the position onwards for a lookahead, and the frames before the
+    /// position backwards for a lookbehind.
+    pub fn holds<F>(&self, symbols: &[&[u8]], truths: F) -> Result<bool, Box<dyn Error>>
+    where
+        F: FnMut(usize) -> Result<Option<Vec<usize>>, MonitorError>,
+    {
+        match self.direction {
+            Direction::Ahead => Ok(!self.matches(symbols, truths)?),
+            Direction::Behind => {
+                let reversed = symbols
+                    .iter()
+                    .map(|s| s.iter().rev().copied().collect())
+                    .collect::<Vec<Vec<u8>>>();
+
+                let reversed = reversed.iter().map(Vec::as_slice).collect::<Vec<&[u8]>>();
+
+                self.matches(&reversed, truths)
+            }
+        }
+    }
+
+    /// Check if the frames in the direction of the [`Lookaround`] begin with
+    /// a match.
+    ///
+    /// An empty match counts as a match.
+    fn matches<F>(&self, symbols: &[&[u8]], mut truths: F) -> Result<bool, Box<dyn Error>>
+    where
+        F: FnMut(usize) -> Result<Option<Vec<usize>>, MonitorError>,
+    {
+        let sid = self
+            .automata
+            .start_state(&Config::new().anchored(Anchored::Yes))?;
+
+        let mut states = HashSet::new();
+        states.insert(State::new(sid, &self.automata));
+
+        let mut at = 0;
+
+        while let Some(truths) = truths(at)? {
+            states = states
+                .iter()
+                .flat_map(|state| {
+                    let mut nexts = truths
+                        .iter()
+                        .map(|i| state.next(symbols[*i], &self.automata))
+                        .collect::<Vec<State>>();
+
+                    if nexts.is_empty() {
+                        nexts.push(state.next(&[BLANK], &self.automata));
+                    }
+
+                    nexts
+                })
+                .collect();
+
+            if states
+                .iter()
+                .any(|state| matches!(state, State::Accepting(..)))
+            {
+                return Ok(true);
+            }
+
+            if states.iter().all(|state| matches!(state, State::Dead(..))) {
+                return Ok(false);
+            }
+
+            at += 1;
+        }
+
+        Ok(states.iter().any(|state| {
+            self.automata
+                .is_match_state(self.automata.next_eoi_state(*state.id()))
+        }))
+    }
+}
+
+/// Take the transitions on the marker of each lookaround that holds.
+///
+/// A marker consumes no frame; therefore, the states reached are added to the
+/// set of `states`, and are themselves closed over. The `holds` function
+/// checks if the lookaround at a position of [`MARKERS`] holds at the current
+/// position, and is only called if a state transitions on its marker.
+pub fn closure<F>(
+    states: &mut HashMap<State, usize>,
+    automata: &AutomatonType,
+    count: usize,
+    mut holds: F,
+) -> Result<(), Box<dyn Error>>
+where
+    F: FnMut(usize) -> Result<bool, Box<dyn Error>>,
+{
+    let mut cache: HashMap<usize, bool> = HashMap::new();
+    let mut pending = states
+        .iter()
+        .map(|(state, errors)| (*state, *errors))
+        .collect::<Vec<(State, usize)>>();
+
+    while let Some((state, errors)) = pending.pop() {
+        for (k, marker) in MARKERS.iter().enumerate().take(count) {
+            let next = state.next(&[*marker], automata);
+
+            if matches!(next, State::Dead(..)) {
+                continue;
+            }
+
+            let holds = match cache.get(&k) {
+                Some(res) => *res,
+                None => {
+                    let res = holds(k)?;
+                    cache.insert(k, res);
+                    res
+                }
+            };
+
+            // Keep the fewest mismatched frames of each state.
+            //
+            // A state already reached with as few is not closed over again,
+            // which also ends any cycle of markers.
+            if !holds || states.get(&next).is_some_and(|e| *e <= errors) {
+                continue;
+            }
+
+            states.insert(next, errors);
+            pending.push((next, errors));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compiler::Compiler;
+    use crate::matcher::{offline, online, Matching};
+    use crate::support::frames;
+
+    #[test]
+    fn negative_lookahead() {
+        let ast = Compiler::new()
+            .compile("[[:car:]](?![[:brake:]]|[[:car:]][[:brake:]])")
+            .unwrap();
+
+        let offline = offline::Matcher::try_from(&ast).unwrap();
+
+        // A car followed by braking within two frames.
+        let braking = frames(&["car", "car", "brake"]);
+        let m = offline.leftmost(&braking).unwrap();
+        assert!(m.is_none());
+
+        let m = offline.leftmost(&braking[1..]).unwrap();
+        assert!(m.is_none());
+
+        // A car not followed by braking.
+        let cruising = frames(&["car", "car", "car"]);
+        let m = offline.leftmost(&cruising).unwrap().unwrap();
+        assert_eq!((m.start, m.end), (0, 1));
+
+        // The frames after a match are unknown online.
+        assert!(online::Matcher::try_from(&ast).is_err());
+
+        // Unless the lookahead only looks at frames of the match.
+        let ast = Compiler::new()
+            .compile("[[:car:]](?![[:brake:]])[[:car:]]")
+            .unwrap();
+
+        let online = online::Matcher::try_from(&ast).unwrap();
+
+        let m = online.leftmost(&braking[..2]).unwrap().unwrap();
+        assert_eq!((m.start, m.end), (0, 2));
+
+        let m = online.leftmost(&frames(&["car", "brake"])).unwrap();
+        assert!(m.is_none());
+    }
+
+    #[test]
+    fn positive_lookbehind() {
+        let ast = Compiler::new()
+            .compile("(?<=[[:brake:]])[[:car:]]")
+            .unwrap();
+
+        let offline = offline::Matcher::try_from(&ast).unwrap();
+        let online = online::Matcher::try_from(&ast).unwrap();
+
+        // A car preceded by braking, where the braking is not reported.
+        let braking = frames(&["car", "brake", "car"]);
+        let bitmap = offline.evaluate(&braking).unwrap();
+
+        let m = offline.leftmost_at(&bitmap, 0).unwrap();
+        assert!(m.is_none());
+
+        let m = offline.leftmost_at(&bitmap, 2).unwrap().unwrap();
+        assert_eq!((m.start, m.end), (0, 1));
+
+        // The frames before a search are otherwise not looked at.
+        let m = offline.leftmost(&braking[2..]).unwrap();
+        assert!(m.is_none());
+
+        let m = online.leftmost(&braking).unwrap().unwrap();
+        assert_eq!((m.start, m.end), (2, 3));
+
+        // A car not preceded by braking.
+        let cruising = frames(&["brake", "car", "car"]);
+        let m = online.leftmost(&cruising).unwrap();
+        assert!(m.is_none());
+    }
+}
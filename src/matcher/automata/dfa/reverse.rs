@@ -8,11 +8,13 @@ use regex_automata::util::syntax;
 use regex_automata::{Anchored, HalfMatch, PatternID};
 
 use crate::compiler::ir::ast::SpatialFormula;
+use crate::compiler::ir::NodeId;
 use crate::datastream::frame::Frame;
-use crate::matcher::automata::{AutomatonType, State};
-use crate::monitor::Monitor;
+use crate::matcher::automata::{Alphabet, AutomatonType, State, BLANK};
+use crate::monitor::{Captures, Monitor, MonitorError};
 use crate::symbolizer::ast::SymbolicAbstractSyntaxTree as AST;
 
+use super::lookaround::{self, Direction, Lookaround};
 use super::{DeterministicFiniteAutomaton, OFFSET};
 
 /// A reverse matching DFA.
@@ -21,7 +23,19 @@ use super::{DeterministicFiniteAutomaton, OFFSET};
 /// only to find the start position of a search.
 pub struct DeterministicFiniteAutomata<'a> {
     pub automata: AutomatonType,
-    pub fmap: HashMap<char, &'a SpatialFormula>,
+    pub fmap: HashMap<Vec<u8>, &'a SpatialFormula>,
+
+    /// The maximum number of frames of a match that may violate their symbol.
+    pub fuzz: usize,
+
+    /// The subpattern of each lookaround, in order of its marker.
+    pub lookarounds: Vec<Lookaround>,
+
+    /// The other operands of a conjunction, which must match the same frames.
+    pub conjuncts: Vec<DeterministicFiniteAutomata<'a>>,
+
+    /// The names of the objects captured by the pattern.
+    pub captures: Vec<String>,
 }
 
 impl DeterministicFiniteAutomaton for DeterministicFiniteAutomata<'_> {
@@ -35,27 +49,131 @@ impl DeterministicFiniteAutomaton for DeterministicFiniteAutomata<'_> {
     /// to collect all possible matches over the complete haystack.
     fn run(&self, haystack: &[Frame]) -> Result<Vec<HalfMatch>, Box<dyn Error>> {
         let mut mats = Vec::new();
-        let mut states = HashSet::new();
+
+        // Search with each candidate of the captured objects.
+        //
+        // A match of any candidate is a match of the pattern (i.e., some
+        // objects exist such that the pattern is satisfied).
+        for captures in crate::matcher::bindings(&self.captures, haystack) {
+            for m in self.product(haystack, &captures)? {
+                if !mats.contains(&m) {
+                    mats.push(m);
+                }
+            }
+        }
+
+        Ok(mats)
+    }
+}
+
+impl<'a> DeterministicFiniteAutomata<'a> {
+    /// Create a new reverse-matching DFA.
+    ///
+    /// This function is exposed if a different configuration is requierd.
+    /// Otherwise, for all other cases, use the [`self::build`] interface to
+    /// construct this DFA.
+    pub fn new(automata: AutomatonType, fmap: HashMap<Vec<u8>, &'a SpatialFormula>) -> Self {
+        DeterministicFiniteAutomata {
+            automata,
+            fmap,
+            fuzz: 0,
+            lookarounds: Vec::new(),
+            conjuncts: Vec::new(),
+            captures: Vec::new(),
+        }
+    }
+
+    /// Simulate the DFA and each operand of a conjunction on a slice of
+    /// [`Frame`], where each captured object is resolved by `captures`.
+    fn product(
+        &self,
+        haystack: &[Frame],
+        captures: &Captures,
+    ) -> Result<Vec<HalfMatch>, Box<dyn Error>> {
+        let mut mats = self.search(haystack, captures)?;
+
+        // Keep only the matches of every operand of a conjunction.
+        //
+        // Each operand is simulated over the same frames, such that a match
+        // starts where all of them do (i.e., the product of their automata).
+        for conjunct in self.conjuncts.iter() {
+            if mats.is_empty() {
+                break;
+            }
+
+            let starts = conjunct
+                .search(haystack, captures)?
+                .iter()
+                .map(|m| m.offset())
+                .collect::<HashSet<usize>>();
+
+            mats.retain(|m| starts.contains(&m.offset()));
+        }
+
+        Ok(mats)
+    }
+
+    /// Simulate this DFA alone on a slice of [`Frame`].
+    fn search(
+        &self,
+        haystack: &[Frame],
+        captures: &Captures,
+    ) -> Result<Vec<HalfMatch>, Box<dyn Error>> {
+        let mut mats = Vec::new();
+        let mut states = HashMap::new();
+        let symbols = self.fmap.keys().collect::<Vec<&Vec<u8>>>();
 
         // Initialize states with the start state of the DFA.
-        states.insert(self.initial()?);
+        //
+        // Each state is paired with the fewest number of mismatched frames
+        // needed to reach it.
+        states.insert(self.initial()?, 0);
+
+        // Keep the satisfied symbols of each frame evaluated, if needed.
+        //
+        // A lookahead looks at the frames after its position, which have
+        // already been searched, while a lookbehind looks at the frames before
+        // its position, which have yet to be searched.
+        let mut seen = vec![None; haystack.len()];
+
+        if !self.lookarounds.is_empty() {
+            self.close(
+                &mut states,
+                &symbols,
+                haystack,
+                captures,
+                &mut seen,
+                haystack.len(),
+            )?;
+        }
 
         for (at, frame) in haystack.iter().enumerate().rev() {
+            let truths = match seen[at].take() {
+                Some(truths) => truths,
+                None => self.truths(frame, &symbols, captures)?,
+            };
+
             // Get the next set of states.
             //
-            // This should generate a new [`HashSet`] with only the next set of
+            // This should generate a new [`HashMap`] with only the next set of
             // states. We do not keep a historical record of previously visited
             // states to reduce memory usage.
-            states = states
-                .into_iter()
-                .flat_map(|state| self.transition(state, frame))
-                .collect();
+            states = super::fuzz(
+                states,
+                self.fuzz,
+                symbols.len(),
+                &truths,
+                |state, i| match i {
+                    Some(i) => state.next(symbols[i], &self.automata),
+                    None => state.next(&[BLANK], &self.automata),
+                },
+            );
 
             // For each state, take action upon it.
             //
             // It is important to produce any [`HalfMatch`] when an
             // [`State::Accepting`] is seen.
-            for state in states.iter() {
+            for state in states.keys() {
                 match state {
                     State::Accepting(..) => {
                         // Because reported matches follow a half-open range
@@ -70,17 +188,22 @@ impl DeterministicFiniteAutomaton for DeterministicFiniteAutomata<'_> {
                 }
             }
 
+            if !self.lookarounds.is_empty() {
+                seen[at] = Some(truths);
+                self.close(&mut states, &symbols, haystack, captures, &mut seen, at)?;
+            }
+
             // Return current matches if all states are dead.
             //
             // This is checked after producing potential [`HalfMatch`] as the
             // condition to exit is only when all branches are dead---contrary to
             // single branch execution.
-            if states.iter().all(|state| matches!(state, State::Dead(..))) {
+            if states.keys().all(|state| matches!(state, State::Dead(..))) {
                 return Ok(mats);
             }
         }
 
-        for state in states {
+        for state in states.into_keys() {
             if let Some(m) = self.eoi(state)? {
                 mats.push(m);
             }
@@ -88,50 +211,73 @@ impl DeterministicFiniteAutomaton for DeterministicFiniteAutomata<'_> {
 
         Ok(mats)
     }
-}
 
-impl<'a> DeterministicFiniteAutomata<'a> {
-    /// Create a new reverse-matching DFA.
-    ///
-    /// This function is exposed if a different configuration is requierd.
-    /// Otherwise, for all other cases, use the [`self::build`] interface to
-    /// construct this DFA.
-    pub fn new(automata: AutomatonType, fmap: HashMap<char, &'a SpatialFormula>) -> Self {
-        DeterministicFiniteAutomata { automata, fmap }
-    }
-
-    /// Take the next transition on the `Frame`.
+    /// Find the symbols satisfied by the `Frame`.
     ///
     /// For this implementation, whether to take a transition is determined by
-    /// whether the [`Monitor`] evaluates to true on the [`Frame`]. The cases are
-    /// as follows:
-    ///
-    /// I. If true, transition on the corresponding symbol from the [`State`].
-    /// II. If false, transition on a blank symbol from the [`State`].
-    ///
-    /// For (II), this is similar to transitioning on a byte that is not in teh
-    /// pattern of a traditional RE.
-    fn transition(&self, state: State, frame: &Frame) -> HashSet<State> {
+    /// whether the [`Monitor`] evaluates to true on the [`Frame`]. This returns
+    /// the positions (in `symbols`) of each symbol whose formula is true. If
+    /// none are, the transition is taken on the [`BLANK`] byte instead, which
+    /// is similar to a byte that is not in the pattern of a traditional RE.
+    fn truths(
+        &self,
+        frame: &Frame,
+        symbols: &[&Vec<u8>],
+        captures: &Captures,
+    ) -> Result<Vec<usize>, MonitorError> {
         let monitor = Monitor::new();
-        let mut nexts = HashSet::new();
-
-        for (symbol, formula) in self.fmap.iter() {
-            if monitor.evaluate(frame, formula) {
-                let sid = self.automata.next_state(*state.id(), *symbol as u8);
-                let next = State::new(sid, &self.automata);
+        let mut truths = Vec::new();
 
-                nexts.insert(next);
+        for (i, symbol) in symbols.iter().enumerate() {
+            if monitor.evaluate_with(frame, self.fmap[*symbol], captures)? {
+                truths.push(i);
             }
         }
 
-        if nexts.is_empty() {
-            let sid = self.automata.next_state(*state.id(), b'Z');
-            let next = State::new(sid, &self.automata);
+        Ok(truths)
+    }
 
-            nexts.insert(next);
-        }
+    /// Take the transitions on the marker of each lookaround that holds at
+    /// `at`.
+    ///
+    /// The satisfied symbols of each frame looked at are kept in `seen`, such
+    /// that each frame of the `haystack` is evaluated at most once.
+    fn close(
+        &self,
+        states: &mut HashMap<State, usize>,
+        symbols: &[&Vec<u8>],
+        haystack: &[Frame],
+        captures: &Captures,
+        seen: &mut [Option<Vec<usize>>],
+        at: usize,
+    ) -> Result<(), Box<dyn Error>> {
+        // Encode each symbol forwards.
+        //
+        // Unlike the pattern, each lookaround is given its symbols forwards.
+        let forwards = symbols
+            .iter()
+            .map(|s| s.iter().rev().copied().collect())
+            .collect::<Vec<Vec<u8>>>();
+
+        let forwards = forwards.iter().map(Vec::as_slice).collect::<Vec<&[u8]>>();
+
+        lookaround::closure(states, &self.automata, self.lookarounds.len(), |k| {
+            let lookaround = &self.lookarounds[k];
+
+            lookaround.holds(&forwards, |i| {
+                let frame = match lookaround.direction() {
+                    Direction::Ahead if at + i < haystack.len() => at + i,
+                    Direction::Behind if i < at => at - 1 - i,
+                    _ => return Ok(None),
+                };
 
-        nexts
+                if seen[frame].is_none() {
+                    seen[frame] = Some(self.truths(&haystack[frame], symbols, captures)?);
+                }
+
+                Ok(seen[frame].clone())
+            })
+        })
     }
 
     /// Check EOI.
@@ -188,7 +334,35 @@ impl<'a> DeterministicFiniteAutomata<'a> {
 /// The `regex-automata` library is used primarily here to construct the
 /// underlying state machine that performs matching. We then wrap this result
 /// into a [`DeterministicFiniteAutomata`] for simple interfacing.
-pub fn build(ast: &AST) -> Result<DeterministicFiniteAutomata, Box<dyn Error>> {
+///
+/// Each operand of a conjunction is built into its own DFA, where the first is
+/// returned with the others as its conjuncts.
+pub fn build(ast: &AST) -> Result<DeterministicFiniteAutomata<'_>, Box<dyn Error>> {
+    let mut dfas = crate::matcher::conjuncts(ast)
+        .into_iter()
+        .map(|node| self::conjunct(ast, Some(node)))
+        .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+
+    if dfas.is_empty() {
+        return self::conjunct(ast, None);
+    }
+
+    let mut dfa = dfas.remove(0);
+    dfa.conjuncts = dfas;
+    dfa.captures = crate::matcher::captures(ast);
+
+    Ok(dfa)
+}
+
+/// Build a reverse searching DFA of the operand of a conjunction at `node`.
+fn conjunct(
+    ast: &AST,
+    node: Option<NodeId>,
+) -> Result<DeterministicFiniteAutomata<'_>, Box<dyn Error>> {
+    let regex = node
+        .map(|node| crate::matcher::regexify(ast, node))
+        .unwrap_or_default();
+
     let automata = dense::Builder::new()
         .configure(
             dense::Config::new()
@@ -199,13 +373,31 @@ pub fn build(ast: &AST) -> Result<DeterministicFiniteAutomata, Box<dyn Error>> {
         )
         .syntax(syntax::Config::new().unicode(false).utf8(true))
         .thompson(thompson::Config::new().reverse(true).utf8(true))
-        .build(&super::super::super::regexify(ast))?;
+        .build(&regex)?;
 
+    // Encode each symbol in reverse.
+    //
+    // The reverse DFA matches the reversed pattern byte-by-byte; therefore, the
+    // bytes of each symbol must also be fed in reverse.
+    let alphabet = Alphabet::new(ast.fmap().len());
     let fmap = ast
         .fmap()
         .iter()
-        .map(|x| (x.symbol, &x.formula))
-        .collect::<HashMap<char, &SpatialFormula>>();
+        .map(|x| {
+            let mut symbol = alphabet.encode(x.symbol);
+            symbol.reverse();
+
+            (symbol, &x.formula)
+        })
+        .collect::<HashMap<Vec<u8>, &SpatialFormula>>();
+
+    let mut dfa = DeterministicFiniteAutomata::new(automata, fmap);
+    dfa.lookarounds = node
+        .map(|node| crate::matcher::lookarounds(ast, node))
+        .unwrap_or_default()
+        .iter()
+        .map(|(direction, regex)| Lookaround::build(*direction, regex))
+        .collect::<Result<Vec<Lookaround>, Box<dyn Error>>>()?;
 
-    Ok(DeterministicFiniteAutomata::new(automata, fmap))
+    Ok(dfa)
 }
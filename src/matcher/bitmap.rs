@@ -0,0 +1,106 @@
+//! Precomputed truth values of symbols.
+//!
+
+use std::ops::Range;
+
+/// The number of bits of a word.
+const BITS: usize = u64::BITS as usize;
+
+/// A bitmap of truth values of each symbol for each frame.
+///
+/// Each row corresponds to a frame, and each column corresponds to a symbol.
+/// A bit is set if the spatial formula of the symbol is satisfied by the frame.
+#[derive(Debug, Clone)]
+pub struct Bitmap {
+    rows: usize,
+    columns: usize,
+    words: usize,
+    bits: Vec<u64>,
+}
+
+impl Bitmap {
+    /// Create a new [`Bitmap`] with all bits unset.
+    pub fn new(rows: usize, columns: usize) -> Self {
+        let words = columns.div_ceil(BITS);
+
+        Bitmap {
+            rows,
+            columns,
+            words,
+            bits: vec![0; rows * words],
+        }
+    }
+
+    /// The number of rows (i.e., frames).
+    pub fn len(&self) -> usize {
+        self.rows
+    }
+
+    /// The number of columns (i.e., symbols).
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    /// Check if there are no rows.
+    pub fn is_empty(&self) -> bool {
+        self.rows == 0
+    }
+
+    /// Set the bit at `row` and `column`.
+    pub fn set(&mut self, row: usize, column: usize) {
+        self.bits[row * self.words + column / BITS] |= 1 << (column % BITS);
+    }
+
+    /// Retrieve the bit at `row` and `column`.
+    pub fn get(&self, row: usize, column: usize) -> bool {
+        self.bits[row * self.words + column / BITS] & (1 << (column % BITS)) != 0
+    }
+
+    /// Copy a range of rows into a new [`Bitmap`].
+    pub fn slice(&self, rows: Range<usize>) -> Bitmap {
+        Bitmap {
+            rows: rows.len(),
+            columns: self.columns,
+            words: self.words,
+            bits: self.bits[(rows.start * self.words)..(rows.end * self.words)].to_vec(),
+        }
+    }
+
+    /// Retrieve the columns of the set bits of `row`.
+    pub fn ones(&self, row: usize) -> Vec<usize> {
+        let mut columns = Vec::new();
+
+        for (w, word) in self.bits[(row * self.words)..((row + 1) * self.words)]
+            .iter()
+            .enumerate()
+        {
+            let mut word = *word;
+
+            while word != 0 {
+                columns.push(w * BITS + word.trailing_zeros() as usize);
+                word &= word - 1;
+            }
+        }
+
+        columns
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Bitmap;
+
+    #[test]
+    fn ones() {
+        let mut bitmap = Bitmap::new(2, 70);
+
+        bitmap.set(0, 3);
+        bitmap.set(1, 0);
+        bitmap.set(1, 69);
+
+        assert!(bitmap.get(0, 3));
+        assert!(!bitmap.get(1, 3));
+        assert_eq!(bitmap.ones(0), vec![3]);
+        assert_eq!(bitmap.ones(1), vec![0, 69]);
+    }
+}
@@ -1,11 +1,16 @@
 use std::error::Error;
 
+use regex_automata::HalfMatch;
+
+use crate::compiler::ir::ast::SpatialFormula;
 use crate::datastream::frame::Frame;
+use crate::monitor::MonitorError;
 use crate::symbolizer::ast::SymbolicAbstractSyntaxTree;
 
 use super::super::matcher::Matching;
-use super::automata::dfa::forward::DeterministicFiniteAutomata;
+use super::automata::dfa::forward::{DeterministicFiniteAutomata, Step};
 use super::automata::dfa::{forward, DeterministicFiniteAutomaton};
+use super::bitmap::Bitmap;
 use super::Match;
 
 /// An interface for [`Matching`] offline.
@@ -36,11 +41,73 @@ impl Matching for Matcher<'_> {
     /// As such, the [`Match`] acts as the index relative to the length of the
     /// slice of [`Frame`] provided.
     fn leftmost(&self, frames: &[Frame]) -> Result<Option<Match>, Box<dyn Error>> {
+        Self::longest(self.dfa.run(frames)?)
+    }
+
+    fn formulas(&self) -> Vec<&SpatialFormula> {
+        self.dfa.fmap.iter().map(|(_, formula)| *formula).collect()
+    }
+}
+
+impl Matcher<'_> {
+    /// Allow up to `k` frames of a match to violate their required symbol.
+    pub fn fuzzy(mut self, k: usize) -> Self {
+        self.dfa.fuzz = k;
+
+        for conjunct in self.dfa.conjuncts.iter_mut() {
+            conjunct.fuzz = k;
+        }
+
+        self
+    }
+
+    /// Evaluate each symbol of the pattern against each [`Frame`] once.
+    ///
+    /// The resulting [`Bitmap`] may be searched from any offset with
+    /// [`Self::leftmost_at`] without invoking the spatial monitors again.
+    pub fn evaluate(&self, frames: &[Frame]) -> Result<Bitmap, MonitorError> {
+        self.dfa.bitmap(frames)
+    }
+
+    /// Find the leftmost match from `offset` over a precomputed [`Bitmap`].
+    ///
+    /// This is equivalent to [`Matching::leftmost`] on the frames from `offset`
+    /// onwards; therefore, the indices of the [`Match`] are relative to
+    /// `offset`. However, a lookbehind may also look at the frames before
+    /// `offset`.
+    pub fn leftmost_at(
+        &self,
+        bitmap: &Bitmap,
+        offset: usize,
+    ) -> Result<Option<Match>, Box<dyn Error>> {
+        Self::longest(self.dfa.run_bitmap(bitmap, offset)?)
+    }
+
+    /// Measure the progress of a search from `offset` over a [`Bitmap`].
+    ///
+    /// This returns the number of frames consumed before the search failed,
+    /// and the positions of the spatial formulas expected at the failing frame.
+    pub fn progress(
+        &self,
+        bitmap: &Bitmap,
+        offset: usize,
+    ) -> Result<(usize, Vec<usize>), Box<dyn Error>> {
+        self.dfa.progress(bitmap, offset)
+    }
+
+    /// Trace a search from `offset` over a [`Bitmap`].
+    ///
+    /// This returns the active states of the DFA after each frame consumed,
+    /// for each candidate of the captured objects (see [`Step`]).
+    pub fn trace(&self, bitmap: &Bitmap, offset: usize) -> Result<Vec<Vec<Step>>, Box<dyn Error>> {
+        self.dfa.trace(bitmap, offset)
+    }
+
+    /// Select the longest non-empty match from the set of [`HalfMatch`].
+    fn longest(mats: Vec<HalfMatch>) -> Result<Option<Match>, Box<dyn Error>> {
         let start: usize = 0;
 
-        let end = self
-            .dfa
-            .run(frames)?
+        let end = mats
             .into_iter()
             .filter(|m| start != start + m.offset())
             .map(|m| start + m.offset())
@@ -54,21 +121,20 @@ impl Matching for Matcher<'_> {
     }
 }
 
-impl<'a> From<&'a SymbolicAbstractSyntaxTree> for Matcher<'a> {
-    fn from(ast: &'a SymbolicAbstractSyntaxTree) -> Self {
+impl<'a> TryFrom<&'a SymbolicAbstractSyntaxTree> for Matcher<'a> {
+    type Error = Box<dyn Error>;
+
+    fn try_from(ast: &'a SymbolicAbstractSyntaxTree) -> Result<Self, Self::Error> {
         // Construct the DFA.
         //
         // Here we use the forward factory to construct a DFA from the s-AST
         // provided that is first converted into an RE.
         //
-        // # Panics
-        //
-        // Here, we assume that all previous methods have passed where we can
-        // safely assume that constructing a valid DFA is guaranteed. This may
-        // need further handled in the future for patterns that may break the
-        // underlying library used.
-        let dfa = forward::build(ast).unwrap();
+        // A pattern that passes the compiler may still exceed the limits of
+        // the underlying library used (e.g., the size of the DFA); therefore,
+        // this is reported as an error rather than assumed to succeed.
+        let dfa = forward::build(ast)?;
 
-        Matcher { dfa }
+        Ok(Matcher { dfa })
     }
 }
@@ -1,9 +1,12 @@
 use std::error::Error;
+use std::fmt;
 
+use crate::compiler::ir::ast::SpatialFormula;
 use crate::datastream::frame::Frame;
 use crate::symbolizer::ast::SymbolicAbstractSyntaxTree;
 
 use super::super::matcher::Matching;
+use super::automata::dfa::lookaround::Direction;
 use super::automata::dfa::reverse::DeterministicFiniteAutomata;
 use super::automata::dfa::{reverse, DeterministicFiniteAutomaton};
 use super::Match;
@@ -52,23 +55,87 @@ impl<'a> Matching for Matcher<'a> {
 
         Ok(None)
     }
+
+    fn formulas(&self) -> Vec<&SpatialFormula> {
+        self.dfa.fmap.values().copied().collect()
+    }
+}
+
+impl Matcher<'_> {
+    /// Allow up to `k` frames of a match to violate their required symbol.
+    pub fn fuzzy(mut self, k: usize) -> Self {
+        self.dfa.fuzz = k;
+
+        for conjunct in self.dfa.conjuncts.iter_mut() {
+            conjunct.fuzz = k;
+        }
+
+        self
+    }
 }
 
-impl<'a> From<&'a SymbolicAbstractSyntaxTree> for Matcher<'a> {
-    fn from(ast: &'a SymbolicAbstractSyntaxTree) -> Self {
+impl<'a> TryFrom<&'a SymbolicAbstractSyntaxTree> for Matcher<'a> {
+    type Error = Box<dyn Error>;
+
+    fn try_from(ast: &'a SymbolicAbstractSyntaxTree) -> Result<Self, Self::Error> {
         // Construct the DFA.
         //
-        // Here we use the forward factory to construct a DFA from the s-AST
+        // Here we use the reverse factory to construct a DFA from the s-AST
         // provided that is first converted into an RE.
         //
-        // # Panics
+        // A pattern that passes the compiler may still exceed the limits of
+        // the underlying library used (e.g., the size of the DFA); therefore,
+        // this is reported as an error rather than assumed to succeed.
+        let dfa = reverse::build(ast)?;
+
+        // Check the reach of the lookaheads.
         //
-        // Here, we assume that all previous methods have passed where we can
-        // safely assume that constructing a valid DFA is guaranteed. This may
-        // need further handled in the future for patterns that may break the
-        // underlying library used.
-        let dfa = reverse::build(ast).unwrap();
+        // A match is reported as soon as its last frame arrives; therefore, a
+        // lookahead that looks beyond it would only ever see the frames that
+        // have not arrived (and so always pass).
+        if super::reach(ast, Direction::Ahead) != Some(0) {
+            return Err(Box::new(MatcherError::from(
+                "a lookahead may look beyond the end of a match, which is unknown online (search offline instead)",
+            )));
+        }
+
+        // Check the vacuity of the temporal operators.
+        //
+        // Similarly, the end of the data stream is unknown online, such that a
+        // temporal operator may never hold vacuously.
+        if super::vacuous(ast) {
+            return Err(Box::new(MatcherError::from(
+                "a temporal operator may hold past the end of the data stream, which is unknown online (search offline instead)",
+            )));
+        }
 
-        Matcher { dfa }
+        Ok(Matcher { dfa })
     }
 }
+
+#[derive(Debug, Clone)]
+struct MatcherError {
+    msg: String,
+}
+
+impl From<&str> for MatcherError {
+    fn from(msg: &str) -> Self {
+        MatcherError {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for MatcherError {
+    fn from(msg: String) -> Self {
+        MatcherError { msg }
+    }
+}
+
+impl fmt::Display for MatcherError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "matcher: {}", self.msg)
+    }
+}
+
+impl Error for MatcherError {}
@@ -3,14 +3,33 @@
 //! Currently, the implemented monitors include evaluation of S4/S4u topological
 //! formulas interpreted over frames.
 
-use crate::compiler::ir::ast::SpatialFormula;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+use indexmap::IndexMap;
+
+use crate::compiler::ir::ast::{OperandKind, SpatialFormula};
+use crate::compiler::ir::ops::{Operator, S4uOperatorKind, SpatialOperatorKind};
+use crate::compiler::ir::{Node, NodeId};
+use crate::datastream::frame::sample::detections::label::Label;
+use crate::datastream::frame::sample::detections::{Annotation, DetectionRecord};
 use crate::datastream::frame::sample::Sample;
 use crate::datastream::frame::Frame;
 
+pub mod classification;
+pub mod explain;
+pub mod kernel;
 pub mod s4;
 pub mod s4m;
 pub mod s4u;
 
+/// The tracked object captured by each name of a pattern.
+///
+/// Each name maps to the instance identifier of an object (see
+/// [`Annotation::instance_id`]).
+pub type Captures = HashMap<String, String>;
+
 /// The main monitor.
 ///
 /// This is a entrypoint for monitoring spatial formulas found within SpREs. This
@@ -28,18 +47,299 @@ impl Monitor {
 
     /// The main interface to evaluating a frame sample against a spatial formula.
     ///
-    /// This considers all possible sample types.
-    pub fn evaluate(&self, frame: &Frame, formula: &SpatialFormula) -> bool {
-        for sample in frame.samples.iter() {
+    /// This considers all possible sample types. An error is returned if the
+    /// formula cannot be evaluated (e.g., an unsupported operator), rather than
+    /// it being treated as unsatisfied.
+    pub fn evaluate(&self, frame: &Frame, formula: &SpatialFormula) -> Result<bool, MonitorError> {
+        self.evaluate_with(frame, formula, &Captures::new())
+    }
+
+    /// Evaluate a frame sample against a spatial formula with captured objects.
+    ///
+    /// This is similar to [`Self::evaluate`], where each reference to a
+    /// captured object resolves to the annotation of the sample with its
+    /// instance identifier, if any.
+    pub fn evaluate_with(
+        &self,
+        frame: &Frame,
+        formula: &SpatialFormula,
+        captures: &Captures,
+    ) -> Result<bool, MonitorError> {
+        // Check for an empty frame.
+        //
+        // Unlike other formulas, this is a property of the frame as a whole
+        // rather than of each of its samples.
+        if let Node::Operand(OperandKind::Empty(channel)) = &formula[formula.root()] {
+            return Ok(self::empty(frame, channel.as_deref()));
+        }
+
+        let (channel, root) = self::scope(formula);
+
+        for sample in frame.samples.iter().filter(|s| self::within(s, channel)) {
             match sample {
-                Sample::ObjectDetection(record) => {
-                    if s4u::Monitor::evaluate(&record.annotations, None, formula) {
-                        return true;
+                Sample::ObjectDetection(record) | Sample::Text(record) => {
+                    let table = self::table(&record.annotations, captures);
+
+                    if s4u::Monitor::evaluate(&record.annotations, table.as_ref(), formula, root)? {
+                        return Ok(true);
                     }
                 }
+                Sample::Classification(record) => {
+                    if classification::Monitor::evaluate(&record.labels, formula, root) {
+                        return Ok(true);
+                    }
+                }
+                Sample::PointCloud(..) => {}
             };
         }
 
-        false
+        Ok(false)
     }
+
+    /// Compute the robustness of a spatial formula against a frame sample.
+    ///
+    /// This is the quantitative counterpart of [`Self::evaluate_with`] (see
+    /// [`s4u::Monitor::robustness`]), where the greatest degree of any sample
+    /// is taken. Frame-level labels and empty frames are either satisfied or
+    /// not; therefore, their degree is infinite.
+    pub fn robustness_with(
+        &self,
+        frame: &Frame,
+        formula: &SpatialFormula,
+        captures: &Captures,
+    ) -> Result<f64, MonitorError> {
+        let degree = |satisfied: bool| match satisfied {
+            true => f64::INFINITY,
+            false => f64::NEG_INFINITY,
+        };
+
+        if let Node::Operand(OperandKind::Empty(channel)) = &formula[formula.root()] {
+            return Ok(degree(self::empty(frame, channel.as_deref())));
+        }
+
+        let (channel, root) = self::scope(formula);
+        let mut robustness = f64::NEG_INFINITY;
+
+        for sample in frame.samples.iter().filter(|s| self::within(s, channel)) {
+            let d = match sample {
+                Sample::ObjectDetection(record) | Sample::Text(record) => {
+                    let table = self::table(&record.annotations, captures);
+
+                    s4u::Monitor::robustness(&record.annotations, table.as_ref(), formula, root)?
+                }
+                Sample::Classification(record) => degree(classification::Monitor::evaluate(
+                    &record.labels,
+                    formula,
+                    root,
+                )),
+                Sample::PointCloud(..) => continue,
+            };
+
+            robustness = robustness.max(d);
+        }
+
+        Ok(robustness)
+    }
+
+    /// Compute the robustness of a match of a set of formulas over its frames.
+    ///
+    /// Each frame may be matched by any of the formulas; therefore, it takes
+    /// the greatest degree of them, and the match takes the least degree of
+    /// its frames. A negative score is only possible for a frame that does not
+    /// satisfy any formula (e.g., of an approximate match, or skipped by a
+    /// temporal operator). If objects are captured, the greatest score of any
+    /// candidate of the captured objects is taken.
+    pub fn score(
+        &self,
+        frames: &[Frame],
+        formulas: &[&SpatialFormula],
+    ) -> Result<f64, MonitorError> {
+        let names = crate::matcher::captured(formulas);
+
+        let mut score = f64::NEG_INFINITY;
+
+        for captures in crate::matcher::bindings(&names, frames) {
+            let mut least = f64::INFINITY;
+
+            for frame in frames.iter() {
+                let mut greatest = f64::NEG_INFINITY;
+
+                for formula in formulas.iter() {
+                    greatest = greatest.max(self.robustness_with(frame, formula, &captures)?);
+                }
+
+                least = least.min(greatest);
+            }
+
+            score = score.max(least);
+        }
+
+        Ok(score)
+    }
+
+    /// Restrict a frame to the annotations that witness a set of formulas.
+    ///
+    /// Each sample keeps only the annotations that witness any formula that it
+    /// satisfies (see [`s4u::Monitor::witnesses`]), and a sample of frame-level
+    /// labels is kept if it satisfies any formula. All other samples are
+    /// removed.
+    pub fn witnesses(
+        &self,
+        frame: &Frame,
+        formulas: &[&SpatialFormula],
+    ) -> Result<Frame, MonitorError> {
+        let mut witnessed = Frame::new(frame.index);
+        witnessed.timestamp = frame.timestamp;
+        witnessed.metadata = frame.metadata.clone();
+
+        for sample in frame.samples.iter() {
+            match sample {
+                Sample::ObjectDetection(record) | Sample::Text(record) => {
+                    let mut annotations = Vec::new();
+
+                    for formula in formulas.iter() {
+                        let (channel, root) = self::scope(formula);
+
+                        if !self::within(sample, channel) {
+                            continue;
+                        }
+
+                        for annotation in
+                            s4u::Monitor::witnesses(&record.annotations, None, formula, root)?
+                        {
+                            if !annotations.contains(&annotation) {
+                                annotations.push(annotation);
+                            }
+                        }
+                    }
+
+                    if annotations.is_empty() {
+                        continue;
+                    }
+
+                    let mut witness =
+                        DetectionRecord::new(record.channel.clone(), record.image.clone());
+                    witness.pose = record.pose;
+                    witness.merged = record.merged.clone();
+
+                    for annotation in annotations {
+                        witness
+                            .annotations
+                            .entry(annotation.label.clone())
+                            .or_default()
+                            .push(annotation);
+                    }
+
+                    witnessed.samples.push(match sample {
+                        Sample::Text(..) => Sample::Text(witness),
+                        _ => Sample::ObjectDetection(witness),
+                    });
+                }
+                Sample::Classification(record) => {
+                    if formulas.iter().any(|formula| {
+                        let (channel, root) = self::scope(formula);
+
+                        self::within(sample, channel)
+                            && classification::Monitor::evaluate(&record.labels, formula, root)
+                    }) {
+                        witnessed.samples.push(sample.clone());
+                    }
+                }
+                Sample::PointCloud(..) => {}
+            }
+        }
+
+        Ok(witnessed)
+    }
+}
+
+/// Create the lookup table of the captured objects within a sample.
+///
+/// Each captured object is keyed by [`s4u::reference`] of its name. If no
+/// objects are captured, no table is created.
+fn table(
+    detections: &IndexMap<Label, Vec<Annotation>>,
+    captures: &Captures,
+) -> Option<HashMap<String, Annotation>> {
+    if captures.is_empty() {
+        return None;
+    }
+
+    let mut table = HashMap::new();
+
+    for (name, id) in captures.iter() {
+        if let Some(annotation) = detections
+            .values()
+            .flatten()
+            .find(|a| a.instance_id.as_ref() == Some(id))
+        {
+            table.insert(s4u::reference(name), annotation.clone());
+        }
+    }
+
+    Some(table)
 }
+
+/// Find the channel that a spatial formula is scoped to, if any.
+///
+/// This returns the channel of the qualifier (i.e., `[CHANNEL: ...]`) and the
+/// node of the formula beneath it. Otherwise, the formula is evaluated against
+/// every sample from its root.
+pub fn scope(formula: &SpatialFormula) -> (Option<&str>, NodeId) {
+    match &formula[formula.root()] {
+        Node::UnaryExpr {
+            op:
+                Operator::SpatialOperator(SpatialOperatorKind::S4uOperator(S4uOperatorKind::Channel(
+                    channel,
+                ))),
+            child,
+        } => (Some(channel), *child),
+        _ => (None, formula.root()),
+    }
+}
+
+/// Check if a [`Sample`] is from a channel, if any.
+pub fn within(sample: &Sample, channel: Option<&str>) -> bool {
+    channel.is_none_or(|channel| sample.channel() == channel)
+}
+
+/// Check if a [`Frame`] has no detections.
+///
+/// If a `channel` is provided, only the samples of that channel are checked.
+/// Frame-level labels are not detections, and therefore are not checked.
+pub fn empty(frame: &Frame, channel: Option<&str>) -> bool {
+    frame.samples.iter().all(|sample| match sample {
+        Sample::ObjectDetection(record) | Sample::Text(record) => {
+            channel.is_some_and(|channel| channel != record.channel)
+                || record.annotations.values().all(Vec::is_empty)
+        }
+        _ => true,
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct MonitorError {
+    msg: String,
+}
+
+impl From<&str> for MonitorError {
+    fn from(msg: &str) -> Self {
+        MonitorError {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for MonitorError {
+    fn from(msg: String) -> Self {
+        MonitorError { msg }
+    }
+}
+
+impl fmt::Display for MonitorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "monitor: {}", self.msg)
+    }
+}
+
+impl Error for MonitorError {}
@@ -0,0 +1,56 @@
+use indexmap::IndexMap;
+
+use crate::compiler::ir::ast::{OperandKind, SpatialFormula};
+use crate::compiler::ir::ops::{FolOperatorKind, Operator, SpatialOperatorKind};
+use crate::compiler::ir::{Node, NodeId};
+use crate::datastream::frame::sample::detections::label::Label;
+
+/// A monitor for evaluating formulas against frame-level labels.
+///
+/// As the labels are not localized, only bare label atoms (e.g., `[:night:]`)
+/// and their negation, conjunction, and disjunction may be satisfied. All
+/// other formulas are not satisfied.
+#[derive(Default)]
+pub struct Monitor {}
+
+impl Monitor {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    /// Evaluate formula satisfaction against a set of labels.
+    ///
+    /// This returns is a boolean result. If true, the formula is satisifed;
+    /// else, if false, then it is not satisfied.
+    pub fn evaluate(labels: &IndexMap<Label, f64>, formula: &SpatialFormula, node: NodeId) -> bool {
+        match &formula[node] {
+            Node::Operand(OperandKind::Symbol(label)) => labels.contains_key(label),
+            Node::Operand(OperandKind::Classes(classes)) => {
+                labels.keys().any(|label| classes.contains(label))
+            }
+            Node::UnaryExpr {
+                op:
+                    Operator::SpatialOperator(SpatialOperatorKind::FolOperator(
+                        FolOperatorKind::Negation,
+                    )),
+                child,
+            } => !Monitor::evaluate(labels, formula, *child),
+            Node::BinaryExpr {
+                op: Operator::SpatialOperator(SpatialOperatorKind::FolOperator(op)),
+                lhs,
+                rhs,
+            } => match op {
+                FolOperatorKind::Conjunction => {
+                    Monitor::evaluate(labels, formula, *lhs)
+                        && Monitor::evaluate(labels, formula, *rhs)
+                }
+                FolOperatorKind::Disjunction => {
+                    Monitor::evaluate(labels, formula, *lhs)
+                        || Monitor::evaluate(labels, formula, *rhs)
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,484 @@
+//! Explanations of unsatisfied spatial formulas.
+//!
+//! Rather than a truth value, these functions produce the reasons a spatial
+//! formula is not satisfied by a frame (e.g., the values of a failed
+//! comparison). This is used to diagnose patterns that do not match.
+
+use std::collections::HashMap;
+
+use indexmap::IndexMap;
+
+use crate::compiler::ir::ast::{OperandKind, SpatialFormula};
+use crate::compiler::ir::ops::{
+    FolOperatorKind, Operator, S4OperatorKind, S4mOperatorKind, S4uOperatorKind,
+    SpatialOperatorKind,
+};
+use crate::compiler::ir::{Node, NodeId};
+use crate::datastream::frame::sample::detections::label::Label;
+use crate::datastream::frame::sample::detections::Annotation;
+use crate::datastream::frame::sample::Sample;
+use crate::datastream::frame::Frame;
+
+use super::{classification, s4m, s4u, MonitorError};
+
+/// The maximum number of valuations explained per quantifier.
+///
+/// A quantifier may have many valuations. Therefore, only the first few are
+/// explained to keep the output readable.
+const VALUATIONS: usize = 3;
+
+/// Explain why a spatial formula is not satisfied by a [`Frame`].
+///
+/// Each sample of the frame is explained independently, and each reason is
+/// prefixed by the channel of its sample. If the formula is satisfied, no
+/// reasons are returned.
+pub fn explain(frame: &Frame, formula: &SpatialFormula) -> Result<Vec<String>, MonitorError> {
+    let mut reasons = Vec::new();
+
+    // Explain a frame that is not empty.
+    //
+    // This is a property of the frame as a whole; therefore, it is not
+    // explained per sample.
+    if let Node::Operand(OperandKind::Empty(channel)) = &formula[formula.root()] {
+        if !super::empty(frame, channel.as_deref()) {
+            reasons.push(match channel {
+                Some(channel) => format!("{}: the channel has detections", channel),
+                None => String::from("the frame has detections"),
+            });
+        }
+
+        return Ok(reasons);
+    }
+
+    let (channel, root) = super::scope(formula);
+
+    for sample in frame.samples.iter() {
+        if !super::within(sample, channel) {
+            continue;
+        }
+
+        match sample {
+            Sample::ObjectDetection(record) | Sample::Text(record) => {
+                for reason in self::reasons(&record.annotations, None, formula, root)? {
+                    reasons.push(format!("{}: {}", record.channel, reason));
+                }
+            }
+            Sample::Classification(record) => {
+                if !classification::Monitor::evaluate(&record.labels, formula, root) {
+                    reasons.push(format!(
+                        "{}: `{}` is not satisfied by the frame-level labels",
+                        record.channel,
+                        self::describe(formula, root)
+                    ));
+                }
+            }
+            Sample::PointCloud(..) => {}
+        }
+    }
+
+    if frame.samples.is_empty() {
+        reasons.push(String::from("the frame has no samples"));
+    } else if let Some(channel) = channel {
+        if !frame
+            .samples
+            .iter()
+            .any(|s| super::within(s, Some(channel)))
+        {
+            reasons.push(format!("{}: the channel has no samples", channel));
+        }
+    }
+
+    Ok(reasons)
+}
+
+/// Recursively explain why an S4u formula is not satisfied.
+fn reasons(
+    detections: &IndexMap<Label, Vec<Annotation>>,
+    table: Option<&HashMap<String, Annotation>>,
+    formula: &SpatialFormula,
+    node: NodeId,
+) -> Result<Vec<String>, MonitorError> {
+    if s4u::Monitor::evaluate(detections, table, formula, node)? {
+        return Ok(Vec::new());
+    }
+
+    let reasons = match &formula[node] {
+        Node::Operand(OperandKind::Symbol(label)) => vec![format!("no `{}` found", label)],
+        Node::Operand(OperandKind::Classes(classes)) => {
+            vec![format!("no `{}` found", classes.source)]
+        }
+        Node::UnaryExpr { op, child } => match op {
+            Operator::SpatialOperator(SpatialOperatorKind::S4uOperator(kind)) => match kind {
+                S4uOperatorKind::NonEmpty => {
+                    vec![format!("`{}` is empty", self::describe(formula, *child))]
+                }
+                S4uOperatorKind::Exists(b) | S4uOperatorKind::Forall(b, _) => {
+                    let mut count = 0;
+                    let mut reasons = Vec::new();
+
+                    // Explain the failing valuations.
+                    //
+                    // For an existential quantifier, every valuation fails;
+                    // otherwise, only some valuations of a universal quantifier
+                    // may fail (and the others produce no reasons).
+                    for lookup in s4u::valuations(detections, table, b)? {
+                        let failures = self::reasons(detections, Some(&lookup), formula, *child)?;
+
+                        if failures.is_empty() {
+                            continue;
+                        }
+
+                        count += 1;
+                        if count <= VALUATIONS {
+                            let valuation = self::valuation(&lookup, b.table.keys());
+
+                            for failure in failures {
+                                reasons.push(format!("for {}: {}", valuation, failure));
+                            }
+                        }
+                    }
+
+                    if count > VALUATIONS {
+                        reasons.push(format!("and {} more valuation(s)", count - VALUATIONS));
+                    }
+
+                    if reasons.is_empty() {
+                        let mut variables = b.table.keys().cloned().collect::<Vec<String>>();
+                        variables.sort();
+
+                        reasons.push(format!(
+                            "no valuation of `{}` exists",
+                            variables.join("`, `")
+                        ));
+                    }
+
+                    reasons
+                }
+                _ => vec![format!(
+                    "`{}` is not satisfied",
+                    self::describe(formula, node)
+                )],
+            },
+            Operator::SpatialOperator(SpatialOperatorKind::FolOperator(
+                FolOperatorKind::Negation,
+            )) => vec![format!(
+                "`{}` is satisfied",
+                self::describe(formula, *child)
+            )],
+            _ => vec![format!(
+                "`{}` is not satisfied",
+                self::describe(formula, node)
+            )],
+        },
+        Node::BinaryExpr {
+            op: Operator::SpatialOperator(SpatialOperatorKind::FolOperator(kind)),
+            lhs,
+            rhs,
+        } => match kind {
+            FolOperatorKind::Conjunction | FolOperatorKind::Disjunction => {
+                let mut reasons = self::reasons(detections, table, formula, *lhs)?;
+                reasons.extend(self::reasons(detections, table, formula, *rhs)?);
+
+                reasons
+            }
+            _ => {
+                let values = s4m::Monitor::evaluate(detections, table, formula, *lhs)?;
+                let bounds = s4m::Monitor::evaluate(detections, table, formula, *rhs)?;
+
+                if values.is_empty() {
+                    return Ok(vec![format!(
+                        "`{}` has no value",
+                        self::describe(formula, *lhs)
+                    )]);
+                }
+
+                vec![format!(
+                    "`{}` was {}, expected {} {}",
+                    self::describe(formula, *lhs),
+                    self::values(&values),
+                    self::symbol(kind),
+                    self::values(&bounds)
+                )]
+            }
+        },
+        _ => vec![format!(
+            "`{}` is not satisfied",
+            self::describe(formula, node)
+        )],
+    };
+
+    Ok(reasons)
+}
+
+/// Describe a spatial formula in the syntax of a SpRE.
+///
+/// Binary operands are always parenthesized. Therefore, the description may
+/// differ from the pattern as written, but it is equivalent.
+pub fn describe(formula: &SpatialFormula, node: NodeId) -> String {
+    let paren = |node: NodeId| match &formula[node] {
+        Node::BinaryExpr {
+            op:
+                Operator::SpatialOperator(SpatialOperatorKind::S4mOperator(S4mOperatorKind::Function(
+                    ..,
+                ))),
+            ..
+        } => self::describe(formula, node),
+        Node::BinaryExpr { .. } => format!("({})", self::describe(formula, node)),
+        _ => self::describe(formula, node),
+    };
+
+    match &formula[node] {
+        Node::Operand(op) => match op {
+            OperandKind::Symbol(label) => format!("[:{}:]", label),
+            OperandKind::Classes(classes) => format!("[:{}:]", classes.source),
+            OperandKind::Number(num) => format!("{:?}", num),
+            OperandKind::Variable(name) => name.clone(),
+            OperandKind::Reference(name) => format!("\\{}", name),
+            OperandKind::Empty(None) => String::from("empty"),
+            OperandKind::Empty(Some(channel)) => format!("empty({:?})", channel),
+        },
+        Node::UnaryExpr { op, child } => match op {
+            Operator::SpatialOperator(op) => match op {
+                SpatialOperatorKind::S4uOperator(S4uOperatorKind::NonEmpty) => {
+                    format!("NE({})", self::describe(formula, *child))
+                }
+                SpatialOperatorKind::S4uOperator(S4uOperatorKind::Exists(b)) => {
+                    format!("E({}) {}", self::bindings(b.table.iter()), paren(*child))
+                }
+                SpatialOperatorKind::S4uOperator(S4uOperatorKind::Forall(b, _)) => {
+                    format!("A({}) {}", self::bindings(b.table.iter()), paren(*child))
+                }
+                SpatialOperatorKind::S4uOperator(S4uOperatorKind::Capture(name)) => {
+                    format!("?<{}> {}", name, self::describe(formula, *child))
+                }
+                SpatialOperatorKind::S4uOperator(S4uOperatorKind::Text(..)) => {
+                    format!("@text({}) ...", self::describe(formula, *child))
+                }
+                SpatialOperatorKind::S4uOperator(S4uOperatorKind::Same) => {
+                    format!("@same({})", self::describe(formula, *child))
+                }
+                SpatialOperatorKind::S4uOperator(S4uOperatorKind::Channel(channel)) => {
+                    format!("{:?}: {}", channel, self::describe(formula, *child))
+                }
+                SpatialOperatorKind::S4mOperator(S4mOperatorKind::Function(name)) => {
+                    format!("@{}({})", name, self::describe(formula, *child))
+                }
+                SpatialOperatorKind::S4mOperator(S4mOperatorKind::Inverse) => {
+                    format!("-{}", paren(*child))
+                }
+                _ => format!("!{}", paren(*child)),
+            },
+            _ => format!("{:?}({})", op, self::describe(formula, *child)),
+        },
+        Node::BinaryExpr { op, lhs, rhs } => {
+            let infix = match op {
+                Operator::SpatialOperator(op) => match op {
+                    SpatialOperatorKind::FolOperator(kind) => self::symbol(kind),
+                    SpatialOperatorKind::S4Operator(S4OperatorKind::Intersection) => "&",
+                    SpatialOperatorKind::S4Operator(_) => "|",
+                    SpatialOperatorKind::S4mOperator(S4mOperatorKind::Addition) => "+",
+                    SpatialOperatorKind::S4mOperator(S4mOperatorKind::Subtraction) => "-",
+                    SpatialOperatorKind::S4mOperator(S4mOperatorKind::Multiplication) => "*",
+                    SpatialOperatorKind::S4mOperator(S4mOperatorKind::Division) => "/",
+                    SpatialOperatorKind::S4mOperator(S4mOperatorKind::Function(name)) => {
+                        return format!(
+                            "@{}({}, {})",
+                            name,
+                            self::describe(formula, *lhs),
+                            self::describe(formula, *rhs)
+                        );
+                    }
+                    SpatialOperatorKind::S4uOperator(S4uOperatorKind::Within) => {
+                        return format!(
+                            "@in({}, {})",
+                            self::describe(formula, *lhs),
+                            self::describe(formula, *rhs)
+                        );
+                    }
+                    SpatialOperatorKind::S4uOperator(S4uOperatorKind::Relation(relation)) => {
+                        return format!(
+                            "@{}({}, {})",
+                            relation.name(),
+                            self::describe(formula, *lhs),
+                            self::describe(formula, *rhs)
+                        );
+                    }
+                    SpatialOperatorKind::S4uOperator(S4uOperatorKind::Touch(tolerance)) => {
+                        return format!(
+                            "@touch({}, {}, {:?})",
+                            self::describe(formula, *lhs),
+                            self::describe(formula, *rhs),
+                            tolerance
+                        );
+                    }
+                    _ => "?",
+                },
+                _ => "?",
+            };
+
+            format!("{} {} {}", paren(*lhs), infix, paren(*rhs))
+        }
+    }
+}
+
+/// The symbol of a FOL operator as written in a SpRE.
+fn symbol(kind: &FolOperatorKind) -> &'static str {
+    match kind {
+        FolOperatorKind::Negation => "!",
+        FolOperatorKind::Conjunction => "&",
+        FolOperatorKind::Disjunction => "|",
+        FolOperatorKind::LessThan => "<",
+        FolOperatorKind::GreaterThan => ">",
+        FolOperatorKind::LessThanEqualTo => "<=",
+        FolOperatorKind::GreaterThanEqualTo => ">=",
+    }
+}
+
+/// Describe a set of bindings, ordered by variable.
+fn bindings<'a>(table: impl Iterator<Item = (&'a String, &'a SpatialFormula)>) -> String {
+    let mut bindings = table
+        .map(|(v, class)| format!("{} := {}", v, self::describe(class, class.root())))
+        .collect::<Vec<String>>();
+    bindings.sort();
+
+    bindings.join(", ")
+}
+
+/// Describe a valuation of a set of variables, ordered by variable.
+///
+/// Each annotation is identified by its label and the center of its bounding
+/// box.
+fn valuation<'a>(
+    lookup: &HashMap<String, Annotation>,
+    variables: impl Iterator<Item = &'a String>,
+) -> String {
+    let mut valuation = variables
+        .filter_map(|v| lookup.get(v).map(|a| (v, a)))
+        .map(|(v, a)| {
+            let center = a.bbox.center();
+
+            format!("{} := {} at ({:.1}, {:.1})", v, a.label, center.x, center.y)
+        })
+        .collect::<Vec<String>>();
+    valuation.sort();
+
+    valuation.join(", ")
+}
+
+/// Describe a set of values.
+///
+/// Each value is rounded to two decimal places. A single value is written
+/// as-is; otherwise, the values are listed.
+fn values(values: &[f64]) -> String {
+    let values = values
+        .iter()
+        .map(|value| format!("{:.2}", value))
+        .collect::<Vec<String>>();
+
+    match &values[..] {
+        [value] => value.clone(),
+        _ => format!("[{}]", values.join(", ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::compiler::Compiler;
+    use crate::datastream::frame::sample::detections::bbox::region::{aa, Point};
+    use crate::datastream::frame::sample::detections::bbox::BoundingBox;
+    use crate::datastream::frame::sample::detections::label::Label;
+    use crate::datastream::frame::sample::detections::{Annotation, DetectionRecord};
+    use crate::datastream::frame::sample::Sample;
+    use crate::datastream::frame::Frame;
+    use crate::monitor::Monitor;
+
+    use super::{describe, explain};
+
+    #[test]
+    fn describe_formulas() {
+        let ast = Compiler::new()
+            .compile(
+                "[E(v := [:car:], p := [:pedestrian:]) @dist(v, p) + 1 > 2.5 & NE([:a:] & ![:b:])]",
+            )
+            .unwrap();
+
+        let formula = &ast.fmap()[0].formula;
+        assert_eq!(
+            describe(formula, formula.root()),
+            "E(p := [:pedestrian:], v := [:car:]) (((@dist(v, p) + 1.0) > 2.5) & NE([:a:] & ![:b:]))"
+        );
+    }
+
+    #[test]
+    fn empty_frames() {
+        let mut record = DetectionRecord::new(String::from("cam"), None);
+        record.annotations.insert(
+            Label::from("car"),
+            vec![Annotation::new(
+                Label::from("car"),
+                1.0,
+                BoundingBox::AxisAligned(aa::Region::new(Point::new(0.0, 0.0), 2.0, 2.0)),
+            )],
+        );
+
+        let mut frame = Frame::new(0);
+        frame.samples.push(Sample::ObjectDetection(record));
+
+        let ast = Compiler::new()
+            .compile("[empty][empty(\"lidar\")]")
+            .unwrap();
+        let (empty, lidar) = (&ast.fmap()[0].formula, &ast.fmap()[1].formula);
+
+        assert!(!Monitor::new().evaluate(&frame, empty).unwrap());
+        assert!(Monitor::new().evaluate(&frame, lidar).unwrap());
+        assert!(Monitor::new().evaluate(&Frame::new(1), empty).unwrap());
+
+        assert_eq!(
+            explain(&frame, empty).unwrap(),
+            vec!["the frame has detections"]
+        );
+        assert_eq!(describe(lidar, lidar.root()), "empty(\"lidar\")");
+    }
+
+    #[test]
+    fn channels() {
+        let record = |channel: &str, class: &str| {
+            let mut record = DetectionRecord::new(String::from(channel), None);
+            record.annotations.insert(
+                Label::from(class),
+                vec![Annotation::new(
+                    Label::from(class),
+                    1.0,
+                    BoundingBox::AxisAligned(aa::Region::new(Point::new(0.0, 0.0), 2.0, 2.0)),
+                )],
+            );
+
+            Sample::ObjectDetection(record)
+        };
+
+        let mut frame = Frame::new(0);
+        frame.samples.push(record("front", "car"));
+        frame.samples.push(record("back", "pedestrian"));
+
+        let ast = Compiler::new()
+            .compile("[front: [:car:]][back: [:car:]][\"back\": [:pedestrian:]][lidar: [:car:]]")
+            .unwrap();
+        let formulas = ast.fmap().iter().map(|x| &x.formula).collect::<Vec<_>>();
+
+        let evaluate = |i: usize| Monitor::new().evaluate(&frame, formulas[i]).unwrap();
+        assert!(evaluate(0) && !evaluate(1) && evaluate(2) && !evaluate(3));
+
+        assert_eq!(
+            explain(&frame, formulas[1]).unwrap(),
+            vec!["back: no `car` found"]
+        );
+        assert_eq!(
+            explain(&frame, formulas[3]).unwrap(),
+            vec!["lidar: the channel has no samples"]
+        );
+        assert_eq!(
+            describe(formulas[0], formulas[0].root()),
+            "\"front\": [:car:]"
+        );
+    }
+}
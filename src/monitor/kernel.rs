@@ -0,0 +1,325 @@
+//! Vectorized geometry kernels.
+//!
+//! Evaluating a spatial formula often computes the same geometric primitive
+//! over every annotation (or pair of annotations) of a frame. These kernels
+//! gather the relevant coordinates into Structure-of-Arrays (SoA) buffers and
+//! compute several annotations at once with SIMD instructions.
+//!
+//! The results are identical to, and in the same order as, computing each
+//! primitive individually.
+
+use wide::{f64x4, CmpLt};
+
+use crate::datastream::frame::sample::detections::bbox::BoundingBox;
+use crate::datastream::frame::sample::detections::Annotation;
+
+/// The number of values computed at once.
+const LANES: usize = 4;
+
+/// Load the `LANES` values of `values` starting at `i`.
+///
+/// If fewer than `LANES` values remain, the missing lanes are set to `pad`.
+#[inline]
+fn load(values: &[f64], i: usize, pad: f64) -> f64x4 {
+    let mut lanes = [pad; LANES];
+    let n = usize::min(LANES, values.len() - i);
+
+    lanes[..n].copy_from_slice(&values[i..(i + n)]);
+    f64x4::from(lanes)
+}
+
+/// The center points of a set of annotations.
+#[derive(Debug, Default)]
+pub struct Centers {
+    pub x: Vec<f64>,
+    pub y: Vec<f64>,
+}
+
+impl Centers {
+    /// Gather the center points of each [`Annotation`].
+    pub fn new(annotations: &[Annotation]) -> Self {
+        let mut centers = Centers {
+            x: Vec::with_capacity(annotations.len()),
+            y: Vec::with_capacity(annotations.len()),
+        };
+
+        for annotation in annotations.iter() {
+            let center = annotation.bbox.center();
+
+            centers.x.push(center.x);
+            centers.y.push(center.y);
+        }
+
+        centers
+    }
+
+    /// The number of center points.
+    pub fn len(&self) -> usize {
+        self.x.len()
+    }
+
+    /// Check if there are no center points.
+    pub fn is_empty(&self) -> bool {
+        self.x.is_empty()
+    }
+}
+
+/// The 3D center points of a set of annotations.
+#[derive(Debug, Default)]
+pub struct Centers3 {
+    pub x: Vec<f64>,
+    pub y: Vec<f64>,
+    pub z: Vec<f64>,
+}
+
+impl Centers3 {
+    /// Gather the 3D center points of each [`Annotation`].
+    ///
+    /// Annotations without a 3D bounding box are skipped.
+    pub fn new(annotations: &[Annotation]) -> Self {
+        let mut centers = Centers3::default();
+
+        for region in annotations.iter().filter_map(|a| a.cuboid()) {
+            centers.x.push(region.center.x);
+            centers.y.push(region.center.y);
+            centers.z.push(region.center.z);
+        }
+
+        centers
+    }
+
+    /// The number of center points.
+    pub fn len(&self) -> usize {
+        self.x.len()
+    }
+
+    /// Check if there are no center points.
+    pub fn is_empty(&self) -> bool {
+        self.x.is_empty()
+    }
+}
+
+/// The extents of a set of Axis-Aligned annotations.
+#[derive(Debug, Default)]
+pub struct Extents {
+    pub min_x: Vec<f64>,
+    pub min_y: Vec<f64>,
+    pub max_x: Vec<f64>,
+    pub max_y: Vec<f64>,
+}
+
+impl Extents {
+    /// Gather the extents of each [`Annotation`].
+    ///
+    /// If any annotation is not Axis-Aligned, `None` is returned.
+    pub fn new(annotations: &[Annotation]) -> Option<Self> {
+        let mut extents = Extents::default();
+
+        for annotation in annotations.iter() {
+            match &annotation.bbox {
+                BoundingBox::AxisAligned(region) => {
+                    extents.min_x.push(region.min.x);
+                    extents.min_y.push(region.min.y);
+                    extents.max_x.push(region.max.x);
+                    extents.max_y.push(region.max.y);
+                }
+                _ => return None,
+            }
+        }
+
+        Some(extents)
+    }
+
+    /// The number of extents.
+    pub fn len(&self) -> usize {
+        self.min_x.len()
+    }
+
+    /// Check if there are no extents.
+    pub fn is_empty(&self) -> bool {
+        self.min_x.is_empty()
+    }
+}
+
+/// Compute the distance of each center point to the origin.
+pub fn norms(centers: &Centers) -> Vec<f64> {
+    let mut res = Vec::with_capacity(centers.len());
+
+    for i in (0..centers.len()).step_by(LANES) {
+        let x = load(&centers.x, i, 0.0);
+        let y = load(&centers.y, i, 0.0);
+
+        let n = usize::min(LANES, centers.len() - i);
+        res.extend_from_slice(&(x * x + y * y).sqrt().to_array()[..n]);
+    }
+
+    res
+}
+
+/// Compute the distance between each pair of center points.
+///
+/// The distances are ordered by the center points of `a`, then `b`.
+pub fn distances(a: &Centers, b: &Centers) -> Vec<f64> {
+    let mut res = Vec::with_capacity(a.len() * b.len());
+
+    for (ax, ay) in a.x.iter().zip(a.y.iter()) {
+        let ax = f64x4::splat(*ax);
+        let ay = f64x4::splat(*ay);
+
+        for i in (0..b.len()).step_by(LANES) {
+            let dx = load(&b.x, i, 0.0) - ax;
+            let dy = load(&b.y, i, 0.0) - ay;
+
+            let n = usize::min(LANES, b.len() - i);
+            res.extend_from_slice(&(dx * dx + dy * dy).sqrt().to_array()[..n]);
+        }
+    }
+
+    res
+}
+
+/// Compute the distance of each 3D center point to the origin.
+pub fn norms3(centers: &Centers3) -> Vec<f64> {
+    let mut res = Vec::with_capacity(centers.len());
+
+    for i in (0..centers.len()).step_by(LANES) {
+        let x = load(&centers.x, i, 0.0);
+        let y = load(&centers.y, i, 0.0);
+        let z = load(&centers.z, i, 0.0);
+
+        let n = usize::min(LANES, centers.len() - i);
+        res.extend_from_slice(&(x * x + y * y + z * z).sqrt().to_array()[..n]);
+    }
+
+    res
+}
+
+/// Compute the distance between each pair of 3D center points.
+///
+/// The distances are ordered by the center points of `a`, then `b`.
+pub fn distances3(a: &Centers3, b: &Centers3) -> Vec<f64> {
+    let mut res = Vec::with_capacity(a.len() * b.len());
+
+    for i in 0..a.len() {
+        let ax = f64x4::splat(a.x[i]);
+        let ay = f64x4::splat(a.y[i]);
+        let az = f64x4::splat(a.z[i]);
+
+        for j in (0..b.len()).step_by(LANES) {
+            let dx = load(&b.x, j, 0.0) - ax;
+            let dy = load(&b.y, j, 0.0) - ay;
+            let dz = load(&b.z, j, 0.0) - az;
+
+            let n = usize::min(LANES, b.len() - j);
+            res.extend_from_slice(&(dx * dx + dy * dy + dz * dz).sqrt().to_array()[..n]);
+        }
+    }
+
+    res
+}
+
+/// Find each pair of overlapping extents.
+///
+/// The pairs of indices are ordered by the extents of `a`, then `b`.
+pub fn overlaps(a: &Extents, b: &Extents) -> Vec<(usize, usize)> {
+    let mut res = Vec::new();
+
+    for i in 0..a.len() {
+        let amin_x = f64x4::splat(a.min_x[i]);
+        let amin_y = f64x4::splat(a.min_y[i]);
+        let amax_x = f64x4::splat(a.max_x[i]);
+        let amax_y = f64x4::splat(a.max_y[i]);
+
+        for j in (0..b.len()).step_by(LANES) {
+            // Pad the missing lanes with NaN.
+            //
+            // Any comparison against NaN is false, so the padded lanes never
+            // report an overlap.
+            let mask = amin_x.cmp_lt(load(&b.max_x, j, f64::NAN))
+                & load(&b.min_x, j, f64::NAN).cmp_lt(amax_x)
+                & amin_y.cmp_lt(load(&b.max_y, j, f64::NAN))
+                & load(&b.min_y, j, f64::NAN).cmp_lt(amax_y);
+
+            let mask = mask.move_mask();
+
+            for k in 0..LANES {
+                if mask & (1 << k) != 0 {
+                    res.push((i, j + k));
+                }
+            }
+        }
+    }
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::datastream::frame::sample::detections::bbox::region::{aa, cuboid, Point, Point3};
+    use crate::datastream::frame::sample::detections::label::Label;
+    use crate::datastream::frame::sample::pose::Rotation;
+
+    fn annotation(x: f64, y: f64) -> Annotation {
+        let mut annotation = Annotation::new(
+            Label::from("car"),
+            1.0,
+            BoundingBox::AxisAligned(aa::Region::new(Point::new(x, y), 10.0, 10.0)),
+        );
+
+        annotation.bbox3d = Some(cuboid::Region::new(
+            Point3::new(x, y, x - y),
+            2.0,
+            1.5,
+            4.0,
+            Rotation::default(),
+        ));
+
+        annotation
+    }
+
+    #[test]
+    fn scalar_equivalence() {
+        let annotations: Vec<Annotation> = (0..7)
+            .map(|i| annotation(i as f64 * 7.0, i as f64 * 3.0))
+            .collect();
+
+        let centers = Centers::new(&annotations);
+        let extents = Extents::new(&annotations).unwrap();
+
+        let centers3 = Centers3::new(&annotations);
+
+        let mut distances = Vec::new();
+        let mut distances3 = Vec::new();
+        let mut overlaps = Vec::new();
+
+        for (i, l) in annotations.iter().enumerate() {
+            for (j, r) in annotations.iter().enumerate() {
+                let (a, b) = match (&l.bbox, &r.bbox) {
+                    (BoundingBox::AxisAligned(a), BoundingBox::AxisAligned(b)) => (a, b),
+                    _ => unreachable!(),
+                };
+
+                let (ac, bc) = (a.center(), b.center());
+                distances.push(f64::sqrt((bc.x - ac.x).powi(2) + (bc.y - ac.y).powi(2)));
+
+                let (ac, bc) = (
+                    &l.bbox3d.as_ref().unwrap().center,
+                    &r.bbox3d.as_ref().unwrap().center,
+                );
+                distances3.push(f64::sqrt(
+                    (bc.x - ac.x).powi(2) + (bc.y - ac.y).powi(2) + (bc.z - ac.z).powi(2),
+                ));
+
+                if a.intersects(b).is_some() {
+                    overlaps.push((i, j));
+                }
+            }
+        }
+
+        assert_eq!(super::distances(&centers, &centers), distances);
+        assert_eq!(super::distances3(&centers3, &centers3), distances3);
+        assert_eq!(super::overlaps(&extents, &extents), overlaps);
+    }
+}
@@ -1,10 +1,17 @@
 use std::collections::HashMap;
 
+use indexmap::IndexMap;
+
 use crate::compiler::ir::ast::{OperandKind, SpatialFormula};
 use crate::compiler::ir::ops::{Operator, S4OperatorKind, SpatialOperatorKind};
-use crate::compiler::ir::Node;
+use crate::compiler::ir::{Node, NodeId};
+use crate::datastream::frame::sample::detections::bbox::BoundingBox;
+use crate::datastream::frame::sample::detections::label::Label;
 use crate::datastream::frame::sample::detections::Annotation;
 
+use super::kernel::{self, Extents};
+use super::MonitorError;
+
 /// A monitor for evaluating S4 formulas.
 #[derive(Default)]
 pub struct Monitor {}
@@ -16,86 +23,156 @@ impl Monitor {
 
     /// Evaluate formula satisfaction against set of annotations.
     ///
-    /// This returns is a boolean result. If true, the formula is satisifed;
-    /// else, if false, then it is not satisfied.
+    /// This returns the annotations that satisfy the formula. If empty, the
+    /// formula is not satisfied. An error is returned if a variable is not
+    /// bound or an operator is not supported.
     pub fn evaluate(
-        detections: &HashMap<String, Vec<Annotation>>,
+        detections: &IndexMap<Label, Vec<Annotation>>,
         table: Option<&HashMap<String, Annotation>>,
         formula: &SpatialFormula,
-    ) -> Vec<Annotation> {
-        match formula {
+        node: NodeId,
+    ) -> Result<Vec<Annotation>, MonitorError> {
+        match &formula[node] {
             Node::Operand(op) => match op {
                 OperandKind::Symbol(label) => {
                     // Retrieve an annotation with the same class category as
                     // specified by the label.
                     if let Some(annotations) = detections.get(label) {
-                        return annotations.clone();
+                        return Ok(annotations.clone());
                     }
 
-                    Vec::new()
+                    Ok(Vec::new())
+                }
+                OperandKind::Classes(classes) => {
+                    // Retrieve the annotations of each class within the set.
+                    //
+                    // The classes are matched by name; therefore, each label
+                    // of the frame is checked in turn.
+                    Ok(detections
+                        .iter()
+                        .filter(|(label, _)| classes.contains(label))
+                        .flat_map(|(_, annotations)| annotations.iter().cloned())
+                        .collect())
                 }
                 OperandKind::Variable(name) => {
                     // Retrieve annoation by look-up.
                     //
-                    // If no entry exists on the table, return an empty list,
-                    // accordingly.
-                    if let Some(table) = table {
-                        if let Some(annotation) = table.get(name) {
-                            return vec![annotation.clone()];
-                        }
+                    // Each variable is bound by a quantifier before it is
+                    // evaluated; otherwise, it is not declared.
+                    match table.and_then(|table| table.get(name)) {
+                        Some(annotation) => Ok(vec![annotation.clone()]),
+                        None => Err(MonitorError::from(format!(
+                            "s4: unknown variable `{}`",
+                            name
+                        ))),
                     }
-
-                    Vec::new()
                 }
-                _ => panic!("monitor: s4: operand: unsupported `{:?}`", op),
+                OperandKind::Reference(name) => {
+                    // Retrieve the captured object by look-up.
+                    //
+                    // The object may not be within every frame; therefore, the
+                    // region is empty rather than an error if not found.
+                    Ok(table
+                        .and_then(|table| table.get(&super::s4u::reference(name)))
+                        .map(|annotation| vec![annotation.clone()])
+                        .unwrap_or_default())
+                }
+                _ => Err(MonitorError::from(format!(
+                    "s4: operand: unsupported `{:?}`",
+                    op
+                ))),
             },
-            Node::UnaryExpr { op, .. } => match op {
+            Node::UnaryExpr { op, child } => match op {
                 Operator::SpatialOperator(SpatialOperatorKind::S4Operator(
                     S4OperatorKind::Complement,
                 )) => {
-                    todo!()
+                    // Retrieve the annotations outside of the region.
+                    //
+                    // A region is the set of its annotations; therefore, the
+                    // complement is relative to the annotations of the frame
+                    // (i.e., every other object) rather than its image.
+                    let region = Monitor::evaluate(detections, table, formula, *child)?;
+
+                    Ok(detections
+                        .values()
+                        .flatten()
+                        .filter(|annotation| !region.contains(annotation))
+                        .cloned()
+                        .collect())
                 }
-                _ => panic!("monitor: s4: unrecognized unary operator"),
+                _ => Err(MonitorError::from(format!(
+                    "s4: unary: operator: unsupported `{:?}`",
+                    op
+                ))),
             },
             Node::BinaryExpr { op, lhs, rhs } => {
-                let lhs = Monitor::evaluate(detections, table, lhs);
-                let rhs = Monitor::evaluate(detections, table, rhs);
+                let lhs = Monitor::evaluate(detections, table, formula, *lhs)?;
+                let rhs = Monitor::evaluate(detections, table, formula, *rhs)?;
 
                 match op {
-                    Operator::SpatialOperator(op) => match op {
-                        SpatialOperatorKind::S4Operator(op) => match op {
-                            S4OperatorKind::Intersection => {
-                                // If either left or rhs is empty, then one
-                                // side is not satisfied. Therefore, the
-                                // resulting formula is not satisifed, entirely.
-                                if lhs.is_empty() || rhs.is_empty() {
-                                    return Vec::new();
-                                }
+                    Operator::SpatialOperator(SpatialOperatorKind::S4Operator(op)) => match op {
+                        S4OperatorKind::Intersection => {
+                            // If either left or rhs is empty, then one
+                            // side is not satisfied. Therefore, the
+                            // resulting formula is not satisifed, entirely.
+                            if lhs.is_empty() || rhs.is_empty() {
+                                return Ok(Vec::new());
+                            }
 
-                                let mut intersections = Vec::new();
+                            let mut intersections = Vec::new();
 
-                                for l in lhs.iter() {
-                                    for r in rhs.iter() {
-                                        if l.bbox.intersects(&r.bbox).is_some() {
-                                            intersections.push(l.clone());
-                                            intersections.push(r.clone());
-                                        }
-                                    }
+                            // Use the vectorized kernel if possible.
+                            //
+                            // The kernel only supports Axis-Aligned regions;
+                            // otherwise, each pair is checked individually.
+                            if let (Some(a), Some(b)) = (Extents::new(&lhs), Extents::new(&rhs)) {
+                                for (i, j) in kernel::overlaps(&a, &b) {
+                                    intersections.push(lhs[i].clone());
+                                    intersections.push(rhs[j].clone());
                                 }
 
-                                intersections
+                                return Ok(intersections);
                             }
-                            S4OperatorKind::Union => {
-                                // We don't care which one satisfied---just as
-                                // long as left or right is valid. Therefore, we
-                                // append all solutions.
-                                lhs.into_iter().chain(rhs).collect()
+
+                            for l in lhs.iter() {
+                                for r in rhs.iter() {
+                                    // Check 3D bounding boxes and polygons for
+                                    // an overlap.
+                                    //
+                                    // The intersection of two cuboids (or two
+                                    // polygons) is not itself one in general;
+                                    // however, only its existence is needed.
+                                    let intersects = match (&l.bbox, &r.bbox) {
+                                        (BoundingBox::Cuboid(_), _)
+                                        | (_, BoundingBox::Cuboid(_))
+                                        | (BoundingBox::Polygon(_), _)
+                                        | (_, BoundingBox::Polygon(_)) => l.bbox.overlaps(&r.bbox),
+                                        _ => l.bbox.intersects(&r.bbox).is_some(),
+                                    };
+
+                                    if intersects {
+                                        intersections.push(l.clone());
+                                        intersections.push(r.clone());
+                                    }
+                                }
                             }
-                            _ => panic!("monitor: s4: unknown binary operator"),
-                        },
-                        _ => panic!("monitor: unknown binary operator {:#?}", op),
+
+                            Ok(intersections)
+                        }
+                        S4OperatorKind::Union => {
+                            // We don't care which one satisfied---just as
+                            // long as left or right is valid. Therefore, we
+                            // append all solutions.
+                            Ok(lhs.into_iter().chain(rhs).collect())
+                        }
+                        S4OperatorKind::Complement => Err(MonitorError::from(
+                            "s4: binary: operator: unsupported `Complement`",
+                        )),
                     },
-                    _ => panic!("monitor: unknown binary operator {:#?}", op),
+                    _ => Err(MonitorError::from(format!(
+                        "s4: binary: operator: unsupported `{:?}`",
+                        op
+                    ))),
                 }
             }
         }
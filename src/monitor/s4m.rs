@@ -1,15 +1,19 @@
 use std::collections::HashMap;
 
+use indexmap::IndexMap;
+use itertools::Itertools;
+
 use crate::{
     compiler::ir::{
         ast::{OperandKind, SpatialFormula},
-        ops::{Operator, S4mOperatorKind, SpatialOperatorKind},
-        Node,
+        ops::{Operator, Relation, S4mOperatorKind, SpatialOperatorKind},
+        Node, NodeId,
     },
-    datastream::frame::sample::detections::{bbox::BoundingBox, Annotation},
+    datastream::frame::sample::detections::{bbox::BoundingBox, label::Label, Annotation},
 };
 
-use super::s4;
+use super::kernel::{self, Centers, Centers3};
+use super::{s4, MonitorError};
 
 /// A monitor for evaluating S4m expressions.
 ///
@@ -27,22 +31,29 @@ impl Monitor {
     /// Evaluate the formula against the set of annotations.
     ///
     /// This returns a set of possible real numbers obtained from evaluating the
-    /// expression, accordingly.
+    /// expression, accordingly. An error is returned if a function or operator
+    /// is not supported.
     pub fn evaluate(
-        detections: &HashMap<String, Vec<Annotation>>,
+        detections: &IndexMap<Label, Vec<Annotation>>,
         table: Option<&HashMap<String, Annotation>>,
         formula: &SpatialFormula,
-    ) -> Vec<f64> {
-        match formula {
+        node: NodeId,
+    ) -> Result<Vec<f64>, MonitorError> {
+        let values = match &formula[node] {
             Node::Operand(op) => match op {
                 OperandKind::Number(num) => vec![*num],
-                _ => panic!("monitor: s4m: operand: unsupported `{:?}`", op),
+                _ => {
+                    return Err(MonitorError::from(format!(
+                        "s4m: operand: unsupported `{:?}`",
+                        op
+                    )))
+                }
             },
             Node::UnaryExpr { op, child } => match op {
                 Operator::SpatialOperator(op) => match op {
                     SpatialOperatorKind::S4mOperator(op) => match op {
                         S4mOperatorKind::Inverse => {
-                            let res = Monitor::evaluate(detections, table, child);
+                            let res = Monitor::evaluate(detections, table, formula, *child)?;
                             res.iter().map(|x| -x).collect()
                         }
                         S4mOperatorKind::Function(name) => match &name[..] {
@@ -52,19 +63,9 @@ impl Monitor {
                             // entirely dependent on the format/representation
                             // selected by the user.
                             "x" => {
-                                let annotations = s4::Monitor::evaluate(detections, table, child);
-
-                                let mut res = Vec::new();
-                                for annotation in annotations.iter() {
-                                    let center = match &annotation.bbox {
-                                        BoundingBox::AxisAligned(region) => region.center(),
-                                        BoundingBox::Oriented(region) => region.center(),
-                                    };
-
-                                    res.push(center.x);
-                                }
-
-                                res
+                                let annotations =
+                                    s4::Monitor::evaluate(detections, table, formula, *child)?;
+                                Centers::new(&annotations).x
                             }
 
                             // Retrieve the y-coordinate value.
@@ -73,19 +74,9 @@ impl Monitor {
                             // entirely dependent on the format/representation
                             // selected by the user.
                             "y" => {
-                                let annotations = s4::Monitor::evaluate(detections, table, child);
-
-                                let mut res = Vec::new();
-                                for annotation in annotations.iter() {
-                                    let center = match &annotation.bbox {
-                                        BoundingBox::AxisAligned(region) => region.center(),
-                                        BoundingBox::Oriented(region) => region.center(),
-                                    };
-
-                                    res.push(center.y);
-                                }
-
-                                res
+                                let annotations =
+                                    s4::Monitor::evaluate(detections, table, formula, *child)?;
+                                Centers::new(&annotations).y
                             }
 
                             // Compute the distance from an annotation to origin.
@@ -93,28 +84,31 @@ impl Monitor {
                             // This is equivalent to computing the Euclidean
                             // distance between a bounding box and the origin
                             // point of the space.
-                            "dist" => {
-                                let annotations = s4::Monitor::evaluate(detections, table, child);
-
-                                let mut res = Vec::new();
-                                for annotation in annotations.iter() {
-                                    let center = match &annotation.bbox {
-                                        BoundingBox::AxisAligned(region) => region.center(),
-                                        BoundingBox::Oriented(region) => region.center(),
-                                    };
-
-                                    res.push(f64::sqrt((center.x).powi(2) + (center.y).powi(2)));
-                                }
+                            "dist" | "dist2" => {
+                                let annotations =
+                                    s4::Monitor::evaluate(detections, table, formula, *child)?;
+                                kernel::norms(&Centers::new(&annotations))
+                            }
 
-                                res
+                            // Compute the distance from an annotation to origin
+                            // in three dimensions.
+                            //
+                            // This uses the 3D bounding box of each annotation,
+                            // such that annotations without one are skipped.
+                            "dist3" => {
+                                let annotations =
+                                    s4::Monitor::evaluate(detections, table, formula, *child)?;
+                                kernel::norms3(&Centers3::new(&annotations))
                             }
 
                             // Compute the area of the annotation.
                             //
-                            // This works only on 2D-based bounding boxes such as
-                            // Axis-Aligned or Oriented.
+                            // This works on 2D-based bounding boxes such as
+                            // Axis-Aligned, Oriented, or polygons, and on the
+                            // footprint of a 3D bounding box.
                             "area" => {
-                                let annotations = s4::Monitor::evaluate(detections, table, child);
+                                let annotations =
+                                    s4::Monitor::evaluate(detections, table, formula, *child)?;
 
                                 let mut res = Vec::new();
                                 for annotation in annotations.iter() {
@@ -125,6 +119,8 @@ impl Monitor {
                                         BoundingBox::Oriented(region) => {
                                             region.width() * region.height()
                                         }
+                                        BoundingBox::Cuboid(region) => region.length * region.width,
+                                        BoundingBox::Polygon(region) => region.area(),
                                     };
 
                                     res.push(area);
@@ -132,23 +128,125 @@ impl Monitor {
 
                                 res
                             }
-                            _ => panic!(
-                                "monitor: s4m: unary: operator: function not supported: `{}`",
-                                name
-                            ),
+
+                            // Compute the volume of the annotation.
+                            //
+                            // This uses the 3D bounding box of each annotation,
+                            // such that annotations without one are skipped.
+                            "volume" => {
+                                let annotations =
+                                    s4::Monitor::evaluate(detections, table, formula, *child)?;
+
+                                annotations
+                                    .iter()
+                                    .filter_map(|a| a.cuboid())
+                                    .map(|region| region.volume())
+                                    .collect()
+                            }
+
+                            // Retrieve the confidence score of the annotation.
+                            //
+                            // This is the score given by the detector, as
+                            // imported (e.g., within the range [0, 1]).
+                            "score" => {
+                                let annotations =
+                                    s4::Monitor::evaluate(detections, table, formula, *child)?;
+
+                                annotations.iter().map(|a| a.score).collect()
+                            }
+
+                            // Retrieve the motion of the tracked object.
+                            //
+                            // This is found from the frame before it as it is
+                            // imported, such that annotations without any
+                            // motion (e.g., untracked) are skipped.
+                            "dx" | "dy" | "speed" => {
+                                let annotations =
+                                    s4::Monitor::evaluate(detections, table, formula, *child)?;
+
+                                annotations
+                                    .iter()
+                                    .filter_map(|a| a.motion)
+                                    .map(|motion| match &name[..] {
+                                        "dx" => motion.dx,
+                                        "dy" => motion.dy,
+                                        _ => motion.speed(),
+                                    })
+                                    .collect()
+                            }
+
+                            // Count the annotations of the region.
+                            //
+                            // An annotation may be within a region more than
+                            // once (e.g., an intersection); therefore, each is
+                            // mapped back to its position within the frame and
+                            // each position is only counted once. Identical
+                            // annotations (e.g., duplicates of a detector) are
+                            // matched in order, such that each is still a
+                            // different object. An empty region counts zero.
+                            "count" => {
+                                let annotations =
+                                    s4::Monitor::evaluate(detections, table, formula, *child)?;
+
+                                let frame = detections.values().flatten().collect::<Vec<_>>();
+                                let mut taken = vec![false; frame.len()];
+
+                                // An annotation outside of the frame (e.g., a
+                                // captured object) is counted by its value.
+                                let mut unique: Vec<&Annotation> = Vec::new();
+                                for annotation in annotations.iter() {
+                                    let position = frame
+                                        .iter()
+                                        .enumerate()
+                                        .position(|(i, b)| !taken[i] && *b == annotation);
+
+                                    match position {
+                                        Some(i) => taken[i] = true,
+                                        None if frame.contains(&annotation) => (),
+                                        None if !unique.contains(&annotation) => {
+                                            unique.push(annotation)
+                                        }
+                                        None => (),
+                                    }
+                                }
+
+                                let count = taken.iter().filter(|t| **t).count() + unique.len();
+                                vec![count as f64]
+                            }
+                            _ => {
+                                return Err(MonitorError::from(format!(
+                                    "s4m: unary: operator: function not supported: `{}`",
+                                    name
+                                )))
+                            }
                         },
-                        _ => panic!("monitor: s4m: unary: operator: unsupported `{:?}`", op),
+                        _ => {
+                            return Err(MonitorError::from(format!(
+                                "s4m: unary: operator: unsupported `{:?}`",
+                                op
+                            )))
+                        }
                     },
-                    _ => panic!("monitor: s4m: unary: operator: unsupported `{:?}`", op),
+                    _ => {
+                        return Err(MonitorError::from(format!(
+                            "s4m: unary: operator: unsupported `{:?}`",
+                            op
+                        )))
+                    }
                 },
-                _ => panic!("monitor: s4m: unary: operator: unsupported `{:?}`", op),
+                _ => {
+                    return Err(MonitorError::from(format!(
+                        "s4m: unary: operator: unsupported `{:?}`",
+                        op
+                    )))
+                }
             },
             Node::BinaryExpr { op, lhs, rhs } => match op {
                 Operator::SpatialOperator(op) => match op {
                     SpatialOperatorKind::S4mOperator(op) => match op {
                         S4mOperatorKind::Addition => {
-                            let lhs = Monitor::evaluate(detections, table, lhs);
-                            let rhs = Monitor::evaluate(detections, table, rhs);
+                            let lhs = Monitor::evaluate(detections, table, formula, *lhs)?;
+                            let rhs = Monitor::evaluate(detections, table, formula, *rhs)?;
 
                             // Compute the addition of all possibilities.
                             //
@@ -165,8 +263,8 @@ impl Monitor {
                             res
                         }
                         S4mOperatorKind::Subtraction => {
-                            let lhs = Monitor::evaluate(detections, table, lhs);
-                            let rhs = Monitor::evaluate(detections, table, rhs);
+                            let lhs = Monitor::evaluate(detections, table, formula, *lhs)?;
+                            let rhs = Monitor::evaluate(detections, table, formula, *rhs)?;
 
                             // Compute the subtraction of all possibilities.
                             //
@@ -183,8 +281,8 @@ impl Monitor {
                             res
                         }
                         S4mOperatorKind::Multiplication => {
-                            let lhs = Monitor::evaluate(detections, table, lhs);
-                            let rhs = Monitor::evaluate(detections, table, rhs);
+                            let lhs = Monitor::evaluate(detections, table, formula, *lhs)?;
+                            let rhs = Monitor::evaluate(detections, table, formula, *rhs)?;
 
                             // Compute the multiplication of all possibilities.
                             //
@@ -201,8 +299,8 @@ impl Monitor {
                             res
                         }
                         S4mOperatorKind::Division => {
-                            let lhs = Monitor::evaluate(detections, table, lhs);
-                            let rhs = Monitor::evaluate(detections, table, rhs);
+                            let lhs = Monitor::evaluate(detections, table, formula, *lhs)?;
+                            let rhs = Monitor::evaluate(detections, table, formula, *rhs)?;
 
                             // Compute the division of all possibilities.
                             //
@@ -225,76 +323,354 @@ impl Monitor {
                             // This is equivalent to computing the Euclidean
                             // distance between a bounding box and another
                             // bounding box in space.
-                            "dist" => {
-                                let lhs = s4::Monitor::evaluate(detections, table, lhs);
-                                let rhs = s4::Monitor::evaluate(detections, table, rhs);
+                            "dist" | "dist2" => {
+                                let lhs = s4::Monitor::evaluate(detections, table, formula, *lhs)?;
+                                let rhs = s4::Monitor::evaluate(detections, table, formula, *rhs)?;
 
-                                let mut res = Vec::new();
+                                kernel::distances(&Centers::new(&lhs), &Centers::new(&rhs))
+                            }
 
-                                for l in lhs.iter() {
-                                    for r in rhs.iter() {
-                                        if let Some(distance) = self::euclidean(&l.bbox, &r.bbox) {
-                                            res.push(distance)
-                                        }
-                                    }
-                                }
-                                res
+                            // Compute the distance from an annotation to another
+                            // annotation in three dimensions.
+                            //
+                            // This uses the 3D bounding box of each annotation,
+                            // such that annotations without one are skipped.
+                            "dist3" => {
+                                let lhs = s4::Monitor::evaluate(detections, table, formula, *lhs)?;
+                                let rhs = s4::Monitor::evaluate(detections, table, formula, *rhs)?;
+
+                                kernel::distances3(&Centers3::new(&lhs), &Centers3::new(&rhs))
+                            }
+
+                            // Compute the area of the intersection between an
+                            // annotation and another annotation.
+                            //
+                            // This works between 2D-based bounding boxes such
+                            // as Axis-Aligned, Oriented, or polygons, such that
+                            // pairs with a 3D bounding box are not supported.
+                            "inter_area" => {
+                                let lhs = s4::Monitor::evaluate(detections, table, formula, *lhs)?;
+                                let rhs = s4::Monitor::evaluate(detections, table, formula, *rhs)?;
+
+                                lhs.iter()
+                                    .cartesian_product(rhs.iter())
+                                    .map(|(a, b)| {
+                                        a.bbox.intersection_area(&b.bbox).ok_or_else(|| {
+                                            MonitorError::from(
+                                                "s4m: `@inter_area` is not supported with 3D bounding boxes",
+                                            )
+                                        })
+                                    })
+                                    .collect::<Result<Vec<f64>, MonitorError>>()?
+                            }
+
+                            // Compute the area of the union between an annotation
+                            // and another annotation.
+                            //
+                            // This works between 2D-based bounding boxes such
+                            // as Axis-Aligned, Oriented, or polygons, such that
+                            // pairs with a 3D bounding box are not supported.
+                            "union_area" => {
+                                let lhs = s4::Monitor::evaluate(detections, table, formula, *lhs)?;
+                                let rhs = s4::Monitor::evaluate(detections, table, formula, *rhs)?;
+
+                                lhs.iter()
+                                    .cartesian_product(rhs.iter())
+                                    .map(|(a, b)| {
+                                        a.bbox.union_area(&b.bbox).ok_or_else(|| {
+                                            MonitorError::from(
+                                                "s4m: `@union_area` is not supported with 3D bounding boxes",
+                                            )
+                                        })
+                                    })
+                                    .collect::<Result<Vec<f64>, MonitorError>>()?
+                            }
+
+                            // Compute the ratio of the area of an annotation to
+                            // the area of another annotation.
+                            //
+                            // This works on 2D-based bounding boxes such as
+                            // Axis-Aligned, Oriented, or polygons, in any
+                            // combination. The ratio to an annotation without
+                            // an area (e.g., a degenerate box) is undefined,
+                            // such that such pairs are skipped.
+                            "ratio" => {
+                                let lhs = s4::Monitor::evaluate(detections, table, formula, *lhs)?;
+                                let rhs = s4::Monitor::evaluate(detections, table, formula, *rhs)?;
+
+                                lhs.iter()
+                                    .cartesian_product(rhs.iter())
+                                    .filter(|(_, b)| b.bbox.area() > 0.0)
+                                    .map(|(a, b)| a.bbox.area() / b.bbox.area())
+                                    .collect()
+                            }
+
+                            // Compute the Hausdorff distance from an annotation
+                            // to another annotation.
+                            //
+                            // Unlike `dist`, this considers the whole region of
+                            // each bounding box rather than only its center.
+                            "hausdorff" => {
+                                let lhs = s4::Monitor::evaluate(detections, table, formula, *lhs)?;
+                                let rhs = s4::Monitor::evaluate(detections, table, formula, *rhs)?;
+
+                                lhs.iter()
+                                    .cartesian_product(rhs.iter())
+                                    .map(|(a, b)| a.bbox.hausdorff(&b.bbox))
+                                    .collect()
+                            }
+
+                            // Compute the angle of the vector from an annotation
+                            // to another annotation.
+                            //
+                            // The angle is in degrees, measured from the x-axis
+                            // towards the y-axis, and within the range
+                            // [-180, 180].
+                            "angle" => {
+                                let lhs = s4::Monitor::evaluate(detections, table, formula, *lhs)?;
+                                let rhs = s4::Monitor::evaluate(detections, table, formula, *rhs)?;
+
+                                let (dx, dy) =
+                                    self::offsets(&Centers::new(&lhs), &Centers::new(&rhs));
+                                dx.iter()
+                                    .zip(dy.iter())
+                                    .map(|(dx, dy)| dy.atan2(*dx).to_degrees())
+                                    .collect()
+                            }
+
+                            // Compute the x-component of the vector from an
+                            // annotation to another annotation.
+                            "relx" => {
+                                let lhs = s4::Monitor::evaluate(detections, table, formula, *lhs)?;
+                                let rhs = s4::Monitor::evaluate(detections, table, formula, *rhs)?;
+
+                                self::offsets(&Centers::new(&lhs), &Centers::new(&rhs)).0
+                            }
+
+                            // Compute the y-component of the vector from an
+                            // annotation to another annotation.
+                            "rely" => {
+                                let lhs = s4::Monitor::evaluate(detections, table, formula, *lhs)?;
+                                let rhs = s4::Monitor::evaluate(detections, table, formula, *rhs)?;
+
+                                self::offsets(&Centers::new(&lhs), &Centers::new(&rhs)).1
+                            }
+                            _ => {
+                                return Err(MonitorError::from(format!(
+                                    "s4m: binary: operator: function not supported: `{}`",
+                                    name
+                                )))
                             }
-                            _ => panic!(
-                                "monitor: s4m: binary: operator: function not supported: `{}`",
-                                name
-                            ),
                         },
-                        _ => panic!("monitor: s4m: binary: operator: unsupported `{:?}`", op),
+                        _ => {
+                            return Err(MonitorError::from(format!(
+                                "s4m: binary: operator: unsupported `{:?}`",
+                                op
+                            )))
+                        }
                     },
-                    _ => panic!("monitor: s4m: binary: operator: unsupported `{:?}`", op),
+                    _ => {
+                        return Err(MonitorError::from(format!(
+                            "s4m: binary: operator: unsupported `{:?}`",
+                            op
+                        )))
+                    }
                 },
-                _ => panic!("monitor: s4m: binary: operator: unsupported `{:?}`", op),
+                _ => {
+                    return Err(MonitorError::from(format!(
+                        "s4m: binary: operator: unsupported `{:?}`",
+                        op
+                    )))
+                }
             },
-        }
+        };
+
+        Ok(values)
     }
 }
 
-/// Compute the Euclidean distance between [`BoundingBox`].
+/// Compute the separation of a bounding box to a side of another.
 ///
-/// This performs a distance computation based on the center point of the
-/// relevant bounding boxes, accordingly.
-fn euclidean(a: &BoundingBox, b: &BoundingBox) -> Option<f64> {
-    if let BoundingBox::AxisAligned(a) = a {
-        if let BoundingBox::AxisAligned(b) = b {
-            let a = a.center();
-            let b = b.center();
-
-            return Some(f64::sqrt((b.x - a.x).powi(2) + (b.y - a.y).powi(2)));
+/// This is the gap between the extents of the boxes along the axis of the
+/// [`Relation`], which is positive if `a` is entirely on that side of `b`, zero
+/// if they touch, and negative (i.e., the depth of their overlap) otherwise.
+pub fn separation(relation: Relation, a: &BoundingBox, b: &BoundingBox) -> f64 {
+    let extents = |bbox: &BoundingBox| {
+        bbox.corners().iter().fold(
+            (
+                f64::INFINITY,
+                f64::NEG_INFINITY,
+                f64::INFINITY,
+                f64::NEG_INFINITY,
+            ),
+            |(x0, x1, y0, y1), p| (x0.min(p.x), x1.max(p.x), y0.min(p.y), y1.max(p.y)),
+        )
+    };
+
+    let (ax0, ax1, ay0, ay1) = extents(a);
+    let (bx0, bx1, by0, by1) = extents(b);
+
+    match relation {
+        Relation::LeftOf => bx0 - ax1,
+        Relation::RightOf => ax0 - bx1,
+        Relation::Above => by0 - ay1,
+        Relation::Below => ay0 - by1,
+    }
+}
+
+/// Compute the vector between each pair of center points.
+///
+/// The vectors point from the center points of `a` to those of `b`, and are
+/// ordered by the center points of `a`, then `b`.
+fn offsets(a: &Centers, b: &Centers) -> (Vec<f64>, Vec<f64>) {
+    let mut dx = Vec::with_capacity(a.len() * b.len());
+    let mut dy = Vec::with_capacity(a.len() * b.len());
+
+    for (ax, ay) in a.x.iter().zip(a.y.iter()) {
+        for (bx, by) in b.x.iter().zip(b.y.iter()) {
+            dx.push(bx - ax);
+            dy.push(by - ay);
         }
     }
 
-    if let BoundingBox::AxisAligned(a) = a {
-        if let BoundingBox::Oriented(b) = b {
-            let a = a.center();
-            let b = b.center();
+    (dx, dy)
+}
 
-            return Some(f64::sqrt((b.x - a.x).powi(2) + (b.y - a.y).powi(2)));
-        }
+#[cfg(test)]
+mod tests {
+    use indexmap::IndexMap;
+
+    use crate::compiler::ir::ast::{OperandKind, SpatialFormula};
+    use crate::compiler::ir::ops::{
+        Operator, S4OperatorKind, S4mOperatorKind, SpatialOperatorKind,
+    };
+    use crate::datastream::frame::sample::detections::bbox::region::{aa, Point};
+    use crate::datastream::frame::sample::detections::bbox::BoundingBox;
+    use crate::datastream::frame::sample::detections::label::Label;
+    use crate::datastream::frame::sample::detections::Annotation;
+
+    use super::Monitor;
+
+    fn annotation(class: &str, x: f64, y: f64, w: f64, h: f64) -> Annotation {
+        Annotation::new(
+            Label::from(class),
+            1.0,
+            BoundingBox::AxisAligned(aa::Region::new(Point::new(x, y), w, h)),
+        )
     }
 
-    if let BoundingBox::Oriented(a) = a {
-        if let BoundingBox::AxisAligned(b) = b {
-            let a = a.center();
-            let b = b.center();
+    /// Evaluate the function `name` from an annotation to another.
+    fn evaluate(name: &str, a: Annotation, b: Annotation) -> Vec<f64> {
+        let formula = SpatialFormula::binary(
+            Operator::SpatialOperator(SpatialOperatorKind::S4mOperator(S4mOperatorKind::Function(
+                String::from(name),
+            ))),
+            SpatialFormula::from(OperandKind::Symbol(a.label.clone())),
+            SpatialFormula::from(OperandKind::Symbol(b.label.clone())),
+        );
+
+        let mut detections = IndexMap::new();
+        detections.insert(a.label.clone(), vec![a]);
+        detections.insert(b.label.clone(), vec![b]);
+
+        Monitor::evaluate(&detections, None, &formula, formula.root()).unwrap()
+    }
 
-            return Some(f64::sqrt((b.x - a.x).powi(2) + (b.y - a.y).powi(2)));
+    #[test]
+    fn angle() {
+        let angle = |x: f64, y: f64| {
+            evaluate(
+                "angle",
+                annotation("car", 1.0, 1.0, 2.0, 2.0),
+                annotation("truck", 1.0 + x, 1.0 + y, 2.0, 2.0),
+            )[0]
+        };
+
+        // The angle is measured from the x-axis towards the y-axis, with its
+        // sign given by the y-component of the vector.
+        let cases = [
+            ((1.0, 0.0), 0.0),
+            ((1.0, 1.0), 45.0),
+            ((-1.0, 1.0), 135.0),
+            ((-1.0, -1.0), -135.0),
+            ((1.0, -1.0), -45.0),
+            ((-1.0, 0.0), 180.0),
+        ];
+
+        for ((x, y), expected) in cases {
+            assert!((angle(x, y) - expected).abs() < 1e-9, "({}, {})", x, y);
         }
     }
 
-    if let BoundingBox::Oriented(a) = a {
-        if let BoundingBox::Oriented(b) = b {
-            let a = a.center();
-            let b = b.center();
+    #[test]
+    fn ratio() {
+        let car = annotation("car", 0.0, 0.0, 2.0, 2.0);
+        let truck = annotation("truck", 0.0, 0.0, 4.0, 2.0);
 
-            return Some(f64::sqrt((b.x - a.x).powi(2) + (b.y - a.y).powi(2)));
-        }
+        assert_eq!(evaluate("ratio", truck.clone(), car.clone()), [2.0]);
+        assert_eq!(evaluate("ratio", car.clone(), truck), [0.5]);
+
+        // The ratio to a box without an area is skipped.
+        let line = annotation("truck", 0.0, 0.0, 4.0, 0.0);
+
+        assert_eq!(evaluate("ratio", line.clone(), car), [0.0]);
+        assert!(evaluate("ratio", line.clone(), line).is_empty());
     }
 
-    None
+    #[test]
+    fn relative() {
+        let car = annotation("car", 1.0, 2.0, 2.0, 2.0);
+        let truck = annotation("truck", 4.0, -2.0, 4.0, 2.0);
+
+        // The vector is from the center of the first box to the second.
+        assert_eq!(evaluate("relx", car.clone(), truck.clone()), [3.0]);
+        assert_eq!(evaluate("rely", car.clone(), truck.clone()), [-4.0]);
+        assert_eq!(evaluate("relx", truck.clone(), car.clone()), [-3.0]);
+        assert_eq!(evaluate("rely", truck, car), [4.0]);
+    }
+
+    #[test]
+    fn count() {
+        let count = Operator::SpatialOperator(SpatialOperatorKind::S4mOperator(
+            S4mOperatorKind::Function(String::from("count")),
+        ));
+
+        let pedestrian = annotation("pedestrian", 0.0, 0.0, 2.0, 2.0);
+        let car = annotation("car", 1.0, 1.0, 2.0, 2.0);
+
+        let mut detections = IndexMap::new();
+        detections.insert(
+            pedestrian.label.clone(),
+            vec![pedestrian.clone(), pedestrian.clone()],
+        );
+        detections.insert(car.label.clone(), vec![car.clone()]);
+
+        // Identical annotations are still different objects.
+        let formula = SpatialFormula::unary(
+            count.clone(),
+            SpatialFormula::from(OperandKind::Symbol(pedestrian.label.clone())),
+        );
+
+        assert_eq!(
+            Monitor::evaluate(&detections, None, &formula, formula.root()).unwrap(),
+            [2.0]
+        );
+
+        // The car overlaps both pedestrians; however, it is counted once.
+        let formula = SpatialFormula::unary(
+            count,
+            SpatialFormula::binary(
+                Operator::SpatialOperator(SpatialOperatorKind::S4Operator(
+                    S4OperatorKind::Intersection,
+                )),
+                SpatialFormula::from(OperandKind::Symbol(pedestrian.label.clone())),
+                SpatialFormula::from(OperandKind::Symbol(car.label.clone())),
+            ),
+        );
+
+        assert_eq!(
+            Monitor::evaluate(&detections, None, &formula, formula.root()).unwrap(),
+            [3.0]
+        );
+    }
 }
@@ -1,13 +1,17 @@
 use std::collections::HashMap;
 
+use indexmap::IndexMap;
 use itertools::Itertools;
 
 use crate::compiler::ir::ast::{OperandKind, SpatialFormula};
-use crate::compiler::ir::ops::{FolOperatorKind, Operator, S4uOperatorKind, SpatialOperatorKind};
-use crate::compiler::ir::Node;
+use crate::compiler::ir::ops::{
+    Bindings, FolOperatorKind, Operator, S4uOperatorKind, SpatialOperatorKind, Vacuity,
+};
+use crate::compiler::ir::{Node, NodeId};
+use crate::datastream::frame::sample::detections::label::Label;
 use crate::datastream::frame::sample::detections::Annotation;
 
-use super::{s4, s4m};
+use super::{s4, s4m, MonitorError};
 
 /// A monitor for evaluating S4u formulas.
 ///
@@ -24,191 +28,177 @@ impl Monitor {
     /// Evaluate formula satisfaction against set of annotations.
     ///
     /// This returns is a boolean result. If true, the formula is satisifed;
-    /// else, if false, then it is not satisfied.
+    /// else, if false, then it is not satisfied. An error is returned if the
+    /// formula cannot be evaluated (e.g., an unsupported operator).
     pub fn evaluate(
-        detections: &HashMap<String, Vec<Annotation>>,
+        detections: &IndexMap<Label, Vec<Annotation>>,
         table: Option<&HashMap<String, Annotation>>,
         formula: &SpatialFormula,
-    ) -> bool {
-        match formula {
+        node: NodeId,
+    ) -> Result<bool, MonitorError> {
+        match &formula[node] {
             Node::Operand(op) => match op {
-                OperandKind::Symbol(label) => {
-                    if detections.get(label).is_some() {
-                        return true;
-                    }
-
-                    false
+                OperandKind::Symbol(label) => Ok(detections.get(label).is_some()),
+                OperandKind::Classes(classes) => {
+                    Ok(detections.keys().any(|label| classes.contains(label)))
                 }
-                _ => panic!("monitor: s4u: operand: unsupported `{:?}`", op),
+                _ => Err(MonitorError::from(format!(
+                    "s4u: operand: unsupported `{:?}`",
+                    op
+                ))),
             },
             Node::UnaryExpr { op, child } => match op {
                 Operator::SpatialOperator(op) => match op {
                     SpatialOperatorKind::S4uOperator(op) => match op {
                         S4uOperatorKind::NonEmpty => {
-                            !s4::Monitor::evaluate(detections, table, child).is_empty()
+                            Ok(!s4::Monitor::evaluate(detections, table, formula, *child)?
+                                .is_empty())
                         }
 
-                        S4uOperatorKind::Exists(t) => {
-                            // For each variable, resolve valuations.
+                        S4uOperatorKind::Text(predicate) => {
+                            // Check the recognized text of each annotation.
                             //
-                            // The valuations of each variable return a, possibly
-                            // empty, list of annotations.
-                            let mut bindings = Vec::new();
-
-                            for (v, formula) in t.iter() {
-                                let mut entries = Vec::new();
-
-                                // Create an entry for each annotation.
-                                //
-                                // For each annotation retrieved from the
-                                // [`formula`], create an entry with its
-                                // corresponding variable.
-                                for a in s4::Monitor::evaluate(detections, table, formula) {
-                                    entries.push((v.clone(), a));
-                                }
+                            // Annotations without any text (e.g., those of
+                            // object detections) never satisfy the predicate.
+                            Ok(s4::Monitor::evaluate(detections, table, formula, *child)?
+                                .iter()
+                                .any(|a| a.text.as_deref().is_some_and(|t| predicate.holds(t))))
+                        }
 
-                                bindings.push(entries);
+                        S4uOperatorKind::Exists(b) => {
+                            for lookup in self::valuations(detections, table, b)? {
+                                if Monitor::evaluate(detections, Some(&lookup), formula, *child)? {
+                                    return Ok(true);
+                                }
                             }
 
-                            // For each binding, create a table.
+                            Ok(false)
+                        }
+
+                        S4uOperatorKind::Capture(name) => {
+                            // Retrieve the captured object.
                             //
-                            // In this case, we must create all possible
-                            // combinations of tables in order to effectively
-                            // find a possible satisfying formula.
-                            let mut res = Vec::new();
-                            for entries in bindings.into_iter().multi_cartesian_product() {
-                                // Create a lookup table.
-                                //
-                                // This table maps a variable to an annotation,
-                                // accordingly.
-                                let mut lookup: HashMap<String, Annotation> = HashMap::new();
-
-                                if let Some(table) = table {
-                                    // Extend the lookup table.
-                                    //
-                                    // The lookup table needs to check for parent
-                                    // lookup tables declared beforehand and
-                                    // include them accordingly.
-                                    //
-                                    // p.s., To resolve name clashes, we use the
-                                    // the most recent name (i.e., the youngest
-                                    // lookup table).
-                                    for (v, annotation) in table.iter() {
-                                        lookup.insert(v.clone(), annotation.clone());
-                                    }
-                                }
+                            // The object is bound during a search (see
+                            // [`super::Monitor::evaluate_with`]); otherwise,
+                            // nothing is captured.
+                            let Some(captured) =
+                                table.and_then(|table| table.get(&self::reference(name)))
+                            else {
+                                return Ok(false);
+                            };
 
-                                // Insert the most recent entries.
-                                //
-                                // This ensures that the most recent definitions
-                                // are used, accordingly.
-                                for (v, annotation) in entries.iter() {
-                                    lookup.insert(v.clone(), annotation.clone());
-                                }
+                            let Node::UnaryExpr {
+                                op:
+                                    Operator::SpatialOperator(SpatialOperatorKind::S4uOperator(
+                                        S4uOperatorKind::Exists(b),
+                                    )),
+                                child,
+                            } = &formula[*child]
+                            else {
+                                return Err(MonitorError::from(format!(
+                                    "s4u: capture `{}` is not bound by a quantifier",
+                                    name
+                                )));
+                            };
 
-                                res.push(Monitor::evaluate(detections, Some(&lookup), child));
+                            // Only consider the valuations of the captured object.
+                            //
+                            // The other variables of the quantifier are bound
+                            // as usual.
+                            for lookup in self::valuations(detections, table, b)? {
+                                if lookup.get(name) == Some(captured)
+                                    && Monitor::evaluate(
+                                        detections,
+                                        Some(&lookup),
+                                        formula,
+                                        *child,
+                                    )?
+                                {
+                                    return Ok(true);
+                                }
                             }
 
-                            res.iter().any(|x| *x)
+                            Ok(false)
                         }
 
-                        S4uOperatorKind::Forall(t) => {
-                            // For each variable, resolve valuations.
+                        S4uOperatorKind::Same => {
+                            let Node::Operand(OperandKind::Variable(name)) = &formula[*child]
+                            else {
+                                return Err(MonitorError::from(
+                                    "s4u: same: operand is not a variable",
+                                ));
+                            };
+
+                            // Compare the bound object with the tracked object.
                             //
-                            // The valuations of each variable return a, possibly
-                            // empty, list of annotations.
-                            let mut bindings = Vec::new();
-
-                            for (v, formula) in t.iter() {
-                                let mut entries = Vec::new();
-
-                                // Create an entry for each annotation.
-                                //
-                                // For each annotation retrieved from the
-                                // [`formula`], create an entry with its
-                                // corresponding variable.
-                                for a in s4::Monitor::evaluate(detections, table, formula) {
-                                    entries.push((v.clone(), a));
-                                }
+                            // The tracked object of the variable is captured by
+                            // its name during a search (see
+                            // [`super::Monitor::evaluate_with`]); otherwise,
+                            // nothing is tracked.
+                            let bound = s4::Monitor::evaluate(detections, table, formula, *child)?;
 
-                                bindings.push(entries);
-                            }
+                            Ok(table
+                                .and_then(|table| table.get(&self::reference(name)))
+                                .is_some_and(|tracked| bound.contains(tracked)))
+                        }
 
-                            // For each binding, create a table.
-                            //
-                            // In this case, we must create all possible
-                            // combinations of tables in order to effectively
-                            // find a possible satisfying formula.
-                            let mut res = Vec::new();
-                            for entries in bindings.into_iter().multi_cartesian_product() {
-                                // Create a lookup table.
-                                //
-                                // This table maps a variable to an annotation,
-                                // accordingly.
-                                let mut lookup: HashMap<String, Annotation> = HashMap::new();
-
-                                if let Some(table) = table {
-                                    // Extend the lookup table.
-                                    //
-                                    // The lookup table needs to check for parent
-                                    // lookup tables declared beforehand and
-                                    // include them accordingly.
-                                    //
-                                    // p.s., To resolve name clashes, we use the
-                                    // the most recent name (i.e., the youngest
-                                    // lookup table).
-                                    for (v, annotation) in table.iter() {
-                                        lookup.insert(v.clone(), annotation.clone());
-                                    }
-                                }
+                        S4uOperatorKind::Forall(b, vacuity) => {
+                            let mut empty = true;
 
-                                // Insert the most recent entries.
-                                //
-                                // This ensures that the most recent definitions
-                                // are used, accordingly.
-                                for (v, annotation) in entries.iter() {
-                                    lookup.insert(v.clone(), annotation.clone());
-                                }
+                            for lookup in self::valuations(detections, table, b)? {
+                                empty = false;
 
-                                res.push(Monitor::evaluate(detections, Some(&lookup), child));
+                                if !Monitor::evaluate(detections, Some(&lookup), formula, *child)? {
+                                    return Ok(false);
+                                }
                             }
 
-                            if res.is_empty() {
-                                return false;
+                            // Determine the truth over an empty domain.
+                            //
+                            // If no valuations exist, then the result depends on
+                            // the [`Vacuity`] selected at compile time.
+                            if empty {
+                                return Ok(*vacuity == Vacuity::Classical);
                             }
 
-                            res.iter().all(|x| *x)
-                        }
-                    },
-                    SpatialOperatorKind::FolOperator(op) => match op {
-                        FolOperatorKind::Negation => {
-                            let res = Monitor::evaluate(detections, table, child);
-                            !res
+                            Ok(true)
                         }
-                        _ => panic!("monitor: s4u: unrecognized unary FOL operator"),
+                        _ => Err(MonitorError::from(format!(
+                            "s4u: unary: operator: unsupported `{:?}`",
+                            op
+                        ))),
                     },
-                    _ => panic!("monitor: s4u: unrecognized unary operator"),
+                    SpatialOperatorKind::FolOperator(FolOperatorKind::Negation) => {
+                        Ok(!Monitor::evaluate(detections, table, formula, *child)?)
+                    }
+                    _ => Err(MonitorError::from(format!(
+                        "s4u: unary: operator: unsupported `{:?}`",
+                        op
+                    ))),
                 },
-                _ => panic!("monitor: s4u: unrecognized unary operator"),
+                _ => Err(MonitorError::from(format!(
+                    "s4u: unary: operator: unsupported `{:?}`",
+                    op
+                ))),
             },
             Node::BinaryExpr { op, lhs, rhs } => match op {
                 Operator::SpatialOperator(kind) => match kind {
                     SpatialOperatorKind::FolOperator(kind) => match kind {
                         FolOperatorKind::Conjunction => {
-                            let lhs = Monitor::evaluate(detections, table, lhs);
-                            let rhs = Monitor::evaluate(detections, table, rhs);
+                            let lhs = Monitor::evaluate(detections, table, formula, *lhs)?;
+                            let rhs = Monitor::evaluate(detections, table, formula, *rhs)?;
 
-                            lhs && rhs
+                            Ok(lhs && rhs)
                         }
                         FolOperatorKind::Disjunction => {
-                            let lhs = Monitor::evaluate(detections, table, lhs);
-                            let rhs = Monitor::evaluate(detections, table, rhs);
+                            let lhs = Monitor::evaluate(detections, table, formula, *lhs)?;
+                            let rhs = Monitor::evaluate(detections, table, formula, *rhs)?;
 
-                            lhs || rhs
+                            Ok(lhs || rhs)
                         }
                         FolOperatorKind::LessThan => {
-                            let lhs = s4m::Monitor::evaluate(detections, table, lhs);
-                            let rhs = s4m::Monitor::evaluate(detections, table, rhs);
+                            let lhs = s4m::Monitor::evaluate(detections, table, formula, *lhs)?;
+                            let rhs = s4m::Monitor::evaluate(detections, table, formula, *rhs)?;
 
                             // Compute the comparison of all possible options.
                             //
@@ -217,16 +207,16 @@ impl Monitor {
                             for l in lhs.iter() {
                                 for r in rhs.iter() {
                                     if l < r {
-                                        return true;
+                                        return Ok(true);
                                     }
                                 }
                             }
 
-                            false
+                            Ok(false)
                         }
                         FolOperatorKind::GreaterThan => {
-                            let lhs = s4m::Monitor::evaluate(detections, table, lhs);
-                            let rhs = s4m::Monitor::evaluate(detections, table, rhs);
+                            let lhs = s4m::Monitor::evaluate(detections, table, formula, *lhs)?;
+                            let rhs = s4m::Monitor::evaluate(detections, table, formula, *rhs)?;
 
                             // Compute the comparison of all possible options.
                             //
@@ -235,16 +225,16 @@ impl Monitor {
                             for l in lhs.iter() {
                                 for r in rhs.iter() {
                                     if l > r {
-                                        return true;
+                                        return Ok(true);
                                     }
                                 }
                             }
 
-                            false
+                            Ok(false)
                         }
                         FolOperatorKind::LessThanEqualTo => {
-                            let lhs = s4m::Monitor::evaluate(detections, table, lhs);
-                            let rhs = s4m::Monitor::evaluate(detections, table, rhs);
+                            let lhs = s4m::Monitor::evaluate(detections, table, formula, *lhs)?;
+                            let rhs = s4m::Monitor::evaluate(detections, table, formula, *rhs)?;
 
                             // Compute the comparison of all possible options.
                             //
@@ -253,16 +243,16 @@ impl Monitor {
                             for l in lhs.iter() {
                                 for r in rhs.iter() {
                                     if l <= r {
-                                        return true;
+                                        return Ok(true);
                                     }
                                 }
                             }
 
-                            false
+                            Ok(false)
                         }
                         FolOperatorKind::GreaterThanEqualTo => {
-                            let lhs = s4m::Monitor::evaluate(detections, table, lhs);
-                            let rhs = s4m::Monitor::evaluate(detections, table, rhs);
+                            let lhs = s4m::Monitor::evaluate(detections, table, formula, *lhs)?;
+                            let rhs = s4m::Monitor::evaluate(detections, table, formula, *rhs)?;
 
                             // Compute the comparison of all possible options.
                             //
@@ -271,19 +261,896 @@ impl Monitor {
                             for l in lhs.iter() {
                                 for r in rhs.iter() {
                                     if l >= r {
-                                        return true;
+                                        return Ok(true);
                                     }
                                 }
                             }
 
-                            false
+                            Ok(false)
                         }
-                        _ => panic!("monitor: unkown FOL operator {:#?}", kind),
+                        FolOperatorKind::Negation => Err(MonitorError::from(
+                            "s4u: binary: operator: unsupported `Negation`",
+                        )),
                     },
-                    _ => panic!("monitor: unknown binary operator {:#?}", kind),
+                    SpatialOperatorKind::S4uOperator(S4uOperatorKind::Within) => {
+                        let lhs = s4::Monitor::evaluate(detections, table, formula, *lhs)?;
+                        let rhs = s4::Monitor::evaluate(detections, table, formula, *rhs)?;
+
+                        // Check the containment of all possible options.
+                        //
+                        // Similar to comparisons, the predicate is satisfied if
+                        // any pair of annotations satisfies it.
+                        Ok(lhs
+                            .iter()
+                            .cartesian_product(rhs.iter())
+                            .any(|(a, b)| b.bbox.contains(&a.bbox)))
+                    }
+                    SpatialOperatorKind::S4uOperator(S4uOperatorKind::Touch(tolerance)) => {
+                        let lhs = s4::Monitor::evaluate(detections, table, formula, *lhs)?;
+                        let rhs = s4::Monitor::evaluate(detections, table, formula, *rhs)?;
+
+                        // Check the adjacency of all possible options.
+                        //
+                        // Similar to containment, the predicate is satisfied
+                        // if any pair of annotations satisfies it.
+                        Ok(lhs
+                            .iter()
+                            .cartesian_product(rhs.iter())
+                            .any(|(a, b)| a.bbox.touches(&b.bbox, *tolerance)))
+                    }
+                    SpatialOperatorKind::S4uOperator(S4uOperatorKind::Relation(relation)) => {
+                        let lhs = s4::Monitor::evaluate(detections, table, formula, *lhs)?;
+                        let rhs = s4::Monitor::evaluate(detections, table, formula, *rhs)?;
+
+                        // Check the relative position of all possible options.
+                        //
+                        // Similar to containment, the predicate is satisfied
+                        // if any pair of annotations satisfies it.
+                        Ok(lhs
+                            .iter()
+                            .cartesian_product(rhs.iter())
+                            .any(|(a, b)| s4m::separation(*relation, &a.bbox, &b.bbox) >= 0.0))
+                    }
+                    _ => Err(MonitorError::from(format!(
+                        "s4u: binary: operator: unsupported `{:?}`",
+                        kind
+                    ))),
                 },
-                _ => panic!("monitor: unknown binary operator {:#?}", op),
+                _ => Err(MonitorError::from(format!(
+                    "s4u: binary: operator: unsupported `{:?}`",
+                    op
+                ))),
+            },
+        }
+    }
+
+    /// Compute the robustness of a formula against a set of annotations.
+    ///
+    /// This is the quantitative counterpart of [`Monitor::evaluate`], where
+    /// the degree is positive if the formula is satisfied and negative if not
+    /// (and zero at the boundary of a comparison). A comparison is the signed
+    /// distance of its most satisfying pair of values (and a relative position
+    /// predicate the separation of its regions), a conjunction (or a
+    /// universal quantifier) the least of its degrees, and a disjunction (or an
+    /// existential quantifier) the greatest. Any other formula is either
+    /// satisfied or not; therefore, its degree is infinite.
+    pub fn robustness(
+        detections: &IndexMap<Label, Vec<Annotation>>,
+        table: Option<&HashMap<String, Annotation>>,
+        formula: &SpatialFormula,
+        node: NodeId,
+    ) -> Result<f64, MonitorError> {
+        match &formula[node] {
+            Node::UnaryExpr {
+                op: Operator::SpatialOperator(op),
+                child,
+            } => match op {
+                SpatialOperatorKind::FolOperator(FolOperatorKind::Negation) => {
+                    Ok(-Monitor::robustness(detections, table, formula, *child)?)
+                }
+                SpatialOperatorKind::S4uOperator(S4uOperatorKind::Exists(b)) => {
+                    let mut degree = f64::NEG_INFINITY;
+
+                    for lookup in self::valuations(detections, table, b)? {
+                        degree = degree.max(Monitor::robustness(
+                            detections,
+                            Some(&lookup),
+                            formula,
+                            *child,
+                        )?);
+                    }
+
+                    Ok(degree)
+                }
+                SpatialOperatorKind::S4uOperator(S4uOperatorKind::Capture(name)) => {
+                    // Only consider the valuations of the captured object.
+                    //
+                    // Otherwise, the capture is not satisfied (see
+                    // [`Monitor::evaluate`]).
+                    let (
+                        Some(captured),
+                        Node::UnaryExpr {
+                            op:
+                                Operator::SpatialOperator(SpatialOperatorKind::S4uOperator(
+                                    S4uOperatorKind::Exists(b),
+                                )),
+                            child,
+                        },
+                    ) = (
+                        table.and_then(|table| table.get(&self::reference(name))),
+                        &formula[*child],
+                    )
+                    else {
+                        return Ok(self::degree(Monitor::evaluate(
+                            detections, table, formula, node,
+                        )?));
+                    };
+
+                    let mut degree = f64::NEG_INFINITY;
+
+                    for lookup in self::valuations(detections, table, b)? {
+                        if lookup.get(name) == Some(captured) {
+                            degree = degree.max(Monitor::robustness(
+                                detections,
+                                Some(&lookup),
+                                formula,
+                                *child,
+                            )?);
+                        }
+                    }
+
+                    Ok(degree)
+                }
+                SpatialOperatorKind::S4uOperator(S4uOperatorKind::Forall(b, vacuity)) => {
+                    let mut degree = None;
+
+                    for lookup in self::valuations(detections, table, b)? {
+                        let d = Monitor::robustness(detections, Some(&lookup), formula, *child)?;
+                        degree = Some(degree.map_or(d, |degree: f64| degree.min(d)));
+                    }
+
+                    // Determine the degree over an empty domain.
+                    //
+                    // Similar to its truth, this depends on the [`Vacuity`]
+                    // selected at compile time.
+                    Ok(degree.unwrap_or(match vacuity {
+                        Vacuity::Classical => f64::INFINITY,
+                        Vacuity::Strict => f64::NEG_INFINITY,
+                    }))
+                }
+                _ => Ok(self::degree(Monitor::evaluate(
+                    detections, table, formula, node,
+                )?)),
             },
+            Node::BinaryExpr {
+                op: Operator::SpatialOperator(SpatialOperatorKind::FolOperator(kind)),
+                lhs,
+                rhs,
+            } => {
+                let distance = |sign: f64| -> Result<f64, MonitorError> {
+                    let lhs = s4m::Monitor::evaluate(detections, table, formula, *lhs)?;
+                    let rhs = s4m::Monitor::evaluate(detections, table, formula, *rhs)?;
+
+                    // Compute the distance of all possible options.
+                    //
+                    // Similar to the truth of a comparison, the most
+                    // satisfying pair of values determines the degree.
+                    Ok(lhs
+                        .iter()
+                        .cartesian_product(rhs.iter())
+                        .map(|(l, r)| sign * (r - l))
+                        .fold(f64::NEG_INFINITY, f64::max))
+                };
+
+                match kind {
+                    FolOperatorKind::Conjunction => {
+                        let lhs = Monitor::robustness(detections, table, formula, *lhs)?;
+                        let rhs = Monitor::robustness(detections, table, formula, *rhs)?;
+
+                        Ok(lhs.min(rhs))
+                    }
+                    FolOperatorKind::Disjunction => {
+                        let lhs = Monitor::robustness(detections, table, formula, *lhs)?;
+                        let rhs = Monitor::robustness(detections, table, formula, *rhs)?;
+
+                        Ok(lhs.max(rhs))
+                    }
+                    FolOperatorKind::LessThan | FolOperatorKind::LessThanEqualTo => distance(1.0),
+                    FolOperatorKind::GreaterThan | FolOperatorKind::GreaterThanEqualTo => {
+                        distance(-1.0)
+                    }
+                    FolOperatorKind::Negation => Err(MonitorError::from(
+                        "s4u: binary: operator: unsupported `Negation`",
+                    )),
+                }
+            }
+            Node::BinaryExpr {
+                op:
+                    Operator::SpatialOperator(SpatialOperatorKind::S4uOperator(
+                        S4uOperatorKind::Relation(relation),
+                    )),
+                lhs,
+                rhs,
+            } => {
+                let lhs = s4::Monitor::evaluate(detections, table, formula, *lhs)?;
+                let rhs = s4::Monitor::evaluate(detections, table, formula, *rhs)?;
+
+                // Compute the separation of all possible options.
+                //
+                // Similar to a comparison, the most separated pair of
+                // annotations determines the degree.
+                Ok(lhs
+                    .iter()
+                    .cartesian_product(rhs.iter())
+                    .map(|(a, b)| s4m::separation(*relation, &a.bbox, &b.bbox))
+                    .fold(f64::NEG_INFINITY, f64::max))
+            }
+            _ => Ok(self::degree(Monitor::evaluate(
+                detections, table, formula, node,
+            )?)),
+        }
+    }
+
+    /// Find the annotations that witness the satisfaction of a formula.
+    ///
+    /// For a quantifier, these are the annotations bound by each satisfying
+    /// valuation; otherwise, they are the annotations referenced by each
+    /// satisfied predicate. A negated formula has no witnesses, as nothing
+    /// in particular satisfies it. If the formula is not satisfied, no
+    /// annotations are returned.
+    pub fn witnesses(
+        detections: &IndexMap<Label, Vec<Annotation>>,
+        table: Option<&HashMap<String, Annotation>>,
+        formula: &SpatialFormula,
+        node: NodeId,
+    ) -> Result<Vec<Annotation>, MonitorError> {
+        if !Monitor::evaluate(detections, table, formula, node)? {
+            return Ok(Vec::new());
         }
+
+        let mut witnesses = Vec::new();
+
+        match &formula[node] {
+            Node::UnaryExpr {
+                op:
+                    Operator::SpatialOperator(SpatialOperatorKind::S4uOperator(
+                        S4uOperatorKind::Exists(b) | S4uOperatorKind::Forall(b, _),
+                    )),
+                child,
+            } => {
+                for lookup in self::valuations(detections, table, b)? {
+                    witnesses.extend(Monitor::witnesses(
+                        detections,
+                        Some(&lookup),
+                        formula,
+                        *child,
+                    )?);
+                }
+            }
+            Node::UnaryExpr {
+                op:
+                    Operator::SpatialOperator(SpatialOperatorKind::FolOperator(
+                        FolOperatorKind::Negation,
+                    )),
+                ..
+            } => {}
+            Node::BinaryExpr {
+                op:
+                    Operator::SpatialOperator(SpatialOperatorKind::FolOperator(
+                        FolOperatorKind::Conjunction | FolOperatorKind::Disjunction,
+                    )),
+                lhs,
+                rhs,
+            } => {
+                witnesses.extend(Monitor::witnesses(detections, table, formula, *lhs)?);
+                witnesses.extend(Monitor::witnesses(detections, table, formula, *rhs)?);
+            }
+            Node::UnaryExpr {
+                op:
+                    Operator::SpatialOperator(SpatialOperatorKind::S4uOperator(S4uOperatorKind::Text(
+                        predicate,
+                    ))),
+                child,
+            } => {
+                // Keep the annotations with matching text.
+                //
+                // The other annotations of the region do not contribute to the
+                // satisfaction of the predicate.
+                witnesses.extend(
+                    self::operands(detections, table, formula, *child)
+                        .into_iter()
+                        .filter(|a| a.text.as_deref().is_some_and(|t| predicate.holds(t))),
+                );
+            }
+            _ => witnesses.extend(self::operands(detections, table, formula, node)),
+        }
+
+        let mut unique: Vec<Annotation> = Vec::new();
+        for annotation in witnesses {
+            if !unique.contains(&annotation) {
+                unique.push(annotation);
+            }
+        }
+
+        Ok(unique)
+    }
+}
+
+/// Collect the annotations referenced by the operands of a subtree.
+///
+/// A class refers to all of its annotations, and a variable to the annotation
+/// it is bound to in the lookup `table`.
+fn operands(
+    detections: &IndexMap<Label, Vec<Annotation>>,
+    table: Option<&HashMap<String, Annotation>>,
+    formula: &SpatialFormula,
+    node: NodeId,
+) -> Vec<Annotation> {
+    match &formula[node] {
+        Node::Operand(OperandKind::Symbol(label)) => {
+            detections.get(label).cloned().unwrap_or_default()
+        }
+        Node::Operand(OperandKind::Classes(classes)) => detections
+            .iter()
+            .filter(|(label, _)| classes.contains(label))
+            .flat_map(|(_, annotations)| annotations.iter().cloned())
+            .collect(),
+        Node::Operand(OperandKind::Variable(name)) => table
+            .and_then(|table| table.get(name))
+            .map(|annotation| vec![annotation.clone()])
+            .unwrap_or_default(),
+        Node::Operand(OperandKind::Reference(name)) => table
+            .and_then(|table| table.get(&self::reference(name)))
+            .map(|annotation| vec![annotation.clone()])
+            .unwrap_or_default(),
+        Node::Operand(OperandKind::Number(..) | OperandKind::Empty(..)) => Vec::new(),
+        Node::UnaryExpr { child, .. } => self::operands(detections, table, formula, *child),
+        Node::BinaryExpr { lhs, rhs, .. } => {
+            let mut annotations = self::operands(detections, table, formula, *lhs);
+            annotations.extend(self::operands(detections, table, formula, *rhs));
+            annotations
+        }
+    }
+}
+
+/// The degree of robustness of a formula that is either satisfied or not.
+fn degree(satisfied: bool) -> f64 {
+    match satisfied {
+        true => f64::INFINITY,
+        false => f64::NEG_INFINITY,
+    }
+}
+
+/// The key of the object captured by `name` within a lookup table.
+///
+/// As variables are identifiers, this never clashes with a variable.
+pub fn reference(name: &str) -> String {
+    format!("\\{}", name)
+}
+
+/// Check that each entry is bound to a different [`Annotation`].
+///
+/// Annotations are compared by their index within the frame (see
+/// [`domain`]) rather than by value, such that identical detections (e.g.,
+/// duplicates of a detector) are still different objects.
+fn distinct(entries: &[(String, (usize, Annotation))]) -> bool {
+    entries
+        .iter()
+        .tuple_combinations()
+        .all(|((_, (a, _)), (_, (b, _)))| a != b)
+}
+
+/// Evaluate the class of a variable, along with the index of each annotation.
+///
+/// An annotation of the frame is indexed by its position among all
+/// annotations of the `detections`, regardless of how the class is written
+/// (e.g., `[:car:] | [:truck:]`). Any other annotation (e.g., the region of
+/// an intersection) is given the next index past those of the frame, such
+/// that it is different from every other.
+fn domain(
+    detections: &IndexMap<Label, Vec<Annotation>>,
+    table: Option<&HashMap<String, Annotation>>,
+    class: &SpatialFormula,
+    next: &mut usize,
+) -> Result<Vec<(usize, Annotation)>, MonitorError> {
+    let root = &class[class.root()];
+
+    if !matches!(
+        root,
+        Node::Operand(OperandKind::Symbol(..) | OperandKind::Classes(..))
+    ) {
+        let annotations = detections.values().flatten().collect::<Vec<&Annotation>>();
+
+        // Map each annotation back to its position within the frame.
+        //
+        // Identical annotations (e.g., duplicates of a detector) are matched
+        // in order, such that each is still a different object.
+        let mut taken = vec![false; annotations.len()];
+
+        return Ok(
+            s4::Monitor::evaluate(detections, table, class, class.root())?
+                .into_iter()
+                .map(|a| {
+                    let position = annotations
+                        .iter()
+                        .enumerate()
+                        .position(|(i, b)| !taken[i] && **b == a);
+
+                    match position {
+                        Some(i) => {
+                            taken[i] = true;
+                            (i, a)
+                        }
+                        None => {
+                            *next += 1;
+                            (*next - 1, a)
+                        }
+                    }
+                })
+                .collect(),
+        );
+    }
+
+    Ok(detections
+        .iter()
+        .flat_map(|(label, annotations)| annotations.iter().map(move |a| (label, a)))
+        .enumerate()
+        .filter(|(_, (label, _))| match root {
+            Node::Operand(OperandKind::Symbol(symbol)) => symbol == *label,
+            Node::Operand(OperandKind::Classes(classes)) => classes.contains(label),
+            _ => false,
+        })
+        .map(|(i, (_, a))| (i, a.clone()))
+        .collect())
+}
+
+/// Enumerate the valuations of a set of [`Bindings`].
+///
+/// Each valuation is a lookup table that maps each variable to an annotation
+/// of its class. The lookup table extends the parent `table`, if any, such
+/// that variables declared beforehand remain accessible. An error is returned
+/// if the class of a variable cannot be evaluated.
+pub fn valuations<'a>(
+    detections: &'a IndexMap<Label, Vec<Annotation>>,
+    table: Option<&'a HashMap<String, Annotation>>,
+    b: &'a Bindings,
+) -> Result<impl Iterator<Item = HashMap<String, Annotation>> + 'a, MonitorError> {
+    // For each variable, resolve valuations.
+    //
+    // The valuations of each variable return a, possibly empty, list of
+    // annotations.
+    let mut bindings = Vec::new();
+    let mut next = detections.values().map(Vec::len).sum::<usize>();
+
+    for (v, class) in b.table.iter() {
+        let mut entries = Vec::new();
+
+        // Create an entry for each annotation.
+        //
+        // For each annotation retrieved from the [`class`], create an entry with
+        // its corresponding variable.
+        for a in self::domain(detections, table, class, &mut next)? {
+            entries.push((v.clone(), a));
+        }
+
+        bindings.push(entries);
+    }
+
+    // For each binding, create a table.
+    //
+    // In this case, we must create all possible combinations of tables in order
+    // to effectively find a possible satisfying formula.
+    Ok(bindings
+        .into_iter()
+        .multi_cartesian_product()
+        .filter(|entries| {
+            // Skip combinations that reuse an annotation.
+            //
+            // If distinct bindings are required, then each variable must bind
+            // to a different annotation.
+            !b.distinct || self::distinct(entries)
+        })
+        .map(move |entries| {
+            // Create a lookup table.
+            //
+            // This table maps a variable to an annotation, accordingly.
+            let mut lookup: HashMap<String, Annotation> = HashMap::new();
+
+            if let Some(table) = table {
+                // Extend the lookup table.
+                //
+                // The lookup table needs to check for parent lookup tables
+                // declared beforehand and include them accordingly.
+                //
+                // p.s., To resolve name clashes, we use the the most recent name
+                // (i.e., the youngest lookup table).
+                for (v, annotation) in table.iter() {
+                    lookup.insert(v.clone(), annotation.clone());
+                }
+            }
+
+            // Insert the most recent entries.
+            //
+            // This ensures that the most recent definitions are used,
+            // accordingly.
+            for (v, (_, annotation)) in entries.into_iter() {
+                lookup.insert(v, annotation);
+            }
+
+            lookup
+        }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use indexmap::IndexMap;
+
+    use crate::compiler::ir::ast::{OperandKind, SpatialFormula};
+    use crate::compiler::ir::ops::{
+        Bindings, Operator, S4OperatorKind, SpatialOperatorKind, Vacuity,
+    };
+    use crate::compiler::Compiler;
+    use crate::datastream::frame::sample::detections::bbox::region::{aa, Point};
+    use crate::datastream::frame::sample::detections::bbox::BoundingBox;
+    use crate::datastream::frame::sample::detections::label::Label;
+    use crate::datastream::frame::sample::detections::{Annotation, Motion};
+
+    use super::Monitor;
+
+    fn annotation(class: &str, x: f64) -> Annotation {
+        Annotation::new(
+            Label::from(class),
+            1.0,
+            BoundingBox::AxisAligned(aa::Region::new(Point::new(x, 0.0), 1.0, 1.0)),
+        )
+    }
+
+    #[test]
+    fn witnesses() {
+        let mut detections = IndexMap::new();
+        detections.insert(
+            Label::from("car"),
+            vec![annotation("car", 0.0), annotation("car", 100.0)],
+        );
+        detections.insert(
+            Label::from("pedestrian"),
+            vec![annotation("pedestrian", 2.0)],
+        );
+        detections.insert(Label::from("truck"), vec![annotation("truck", 50.0)]);
+
+        let ast = Compiler::new()
+            .compile("[E(v := [:car:], p := [:pedestrian:]) @dist(v, p) > 5.0]")
+            .unwrap();
+        let formula = &ast.fmap()[0].formula;
+
+        assert_eq!(
+            Monitor::witnesses(&detections, None, formula, formula.root()).unwrap(),
+            [annotation("car", 100.0), annotation("pedestrian", 2.0)]
+        );
+    }
+
+    #[test]
+    fn distinct() {
+        let mut detections = IndexMap::new();
+        detections.insert(
+            Label::from("car"),
+            vec![annotation("car", 0.0), annotation("car", 0.0)],
+        );
+
+        let evaluate = |source: &str| {
+            let ast = Compiler::new().compile(source).unwrap();
+            let formula = &ast.fmap()[0].formula;
+
+            Monitor::evaluate(&detections, None, formula, formula.root()).unwrap()
+        };
+
+        // The identical detections are still two different cars.
+        assert!(evaluate(
+            "[E#(x := [:car:], y := [:car:]) @dist(x, y) < 1.0]"
+        ));
+        assert!(evaluate(
+            "[E#(x := [:car:], y := [:car|truck:]) @dist(x, y) < 1.0]"
+        ));
+        assert!(!evaluate(
+            "[E#(x := [:car:], y := [:car:], z := [:car:]) @dist(x, y) < 1.0]"
+        ));
+    }
+
+    #[test]
+    fn distinct_compound() {
+        let car = || SpatialFormula::from(OperandKind::Symbol(Label::from("car")));
+        let truck = || SpatialFormula::from(OperandKind::Symbol(Label::from("truck")));
+
+        // Bind `x` to a car and `y` to a car or a truck.
+        //
+        // The class of `y` is not a bare class; therefore, its annotations
+        // are evaluated rather than looked up.
+        let mut table = HashMap::new();
+        table.insert(String::from("x"), car());
+        table.insert(
+            String::from("y"),
+            SpatialFormula::binary(
+                Operator::SpatialOperator(SpatialOperatorKind::S4Operator(S4OperatorKind::Union)),
+                car(),
+                truck(),
+            ),
+        );
+
+        let bindings = Bindings::new(table, true);
+
+        let mut detections = IndexMap::new();
+        detections.insert(Label::from("car"), vec![annotation("car", 0.0)]);
+        detections.insert(Label::from("truck"), vec![annotation("truck", 50.0)]);
+
+        // The single car cannot be bound to both variables.
+        let valuations = super::valuations(&detections, None, &bindings)
+            .unwrap()
+            .collect::<Vec<_>>();
+
+        assert_eq!(valuations.len(), 1);
+        assert_eq!(valuations[0]["y"], annotation("truck", 50.0));
+
+        // The identical detections are still two different cars.
+        let mut detections = IndexMap::new();
+        detections.insert(
+            Label::from("car"),
+            vec![annotation("car", 0.0), annotation("car", 0.0)],
+        );
+
+        assert_eq!(
+            super::valuations(&detections, None, &bindings)
+                .unwrap()
+                .count(),
+            2
+        );
+    }
+
+    #[test]
+    fn score() {
+        let mut detections = IndexMap::new();
+        detections.insert(
+            Label::from("car"),
+            vec![
+                Annotation::new(
+                    Label::from("car"),
+                    0.3,
+                    BoundingBox::AxisAligned(aa::Region::new(Point::new(0.0, 0.0), 1.0, 1.0)),
+                ),
+                annotation("car", 100.0),
+            ],
+        );
+
+        let evaluate = |source: &str| {
+            let ast = Compiler::new().compile(source).unwrap();
+            let formula = &ast.fmap()[0].formula;
+
+            Monitor::evaluate(&detections, None, formula, formula.root()).unwrap()
+        };
+
+        assert!(evaluate("[E(v := [:car:]) @score(v) < 0.5 & @x(v) < 50.0]"));
+        assert!(!evaluate(
+            "[E(v := [:car:]) @score(v) < 0.5 & @x(v) > 50.0]"
+        ));
+        assert!(!evaluate("[A(v := [:car:]) @score(v) > 0.5]"));
+    }
+
+    #[test]
+    fn count() {
+        let mut detections = IndexMap::new();
+        detections.insert(
+            Label::from("pedestrian"),
+            vec![
+                annotation("pedestrian", 0.0),
+                annotation("pedestrian", 0.5),
+                annotation("pedestrian", 100.0),
+            ],
+        );
+        detections.insert(Label::from("car"), vec![annotation("car", 0.0)]);
+
+        let evaluate = |source: &str| {
+            let ast = Compiler::new().compile(source).unwrap();
+            let formula = &ast.fmap()[0].formula;
+
+            Monitor::evaluate(&detections, None, formula, formula.root()).unwrap()
+        };
+
+        assert!(evaluate("[#[:pedestrian:] >= 3]"));
+        assert!(!evaluate("[#[:pedestrian:] > 3]"));
+        assert!(evaluate("[@count([:pedestrian:] | [:car:]) > 3]"));
+
+        // The car is counted once, although it intersects both pedestrians.
+        assert!(evaluate("[#([:pedestrian:] & [:car:]) <= 3]"));
+        assert!(evaluate("[#([:pedestrian:] & [:car:]) > 2]"));
+        assert!(evaluate("[#[:truck:] < 1]"));
+    }
+
+    #[test]
+    fn motion() {
+        let mut approaching = annotation("car", 0.0);
+        approaching.motion = Some(Motion {
+            dx: 0.0,
+            dy: 6.0,
+            dt: Some(0.5),
+        });
+
+        let mut detections = IndexMap::new();
+        detections.insert(
+            Label::from("car"),
+            vec![annotation("car", 100.0), approaching],
+        );
+
+        let evaluate = |source: &str| {
+            let ast = Compiler::new().compile(source).unwrap();
+            let formula = &ast.fmap()[0].formula;
+
+            Monitor::evaluate(&detections, None, formula, formula.root()).unwrap()
+        };
+
+        assert!(evaluate(
+            "[E(v := [:car:]) @speed(v) > 10.0 & @dy(v) > 0.0]"
+        ));
+        assert!(!evaluate("[E(v := [:car:]) @speed(v) > 20.0]"));
+
+        // The car without any motion is not compared.
+        assert!(!evaluate("[E(v := [:car:]) @dx(v) > 0.0 | @dx(v) < 0.0]"));
+    }
+
+    #[test]
+    fn relation() {
+        let mut detections = IndexMap::new();
+        detections.insert(
+            Label::from("car"),
+            vec![annotation("car", 0.0), annotation("car", 100.0)],
+        );
+        detections.insert(
+            Label::from("pedestrian"),
+            vec![annotation("pedestrian", 2.0)],
+        );
+
+        let formula = |source: &str| {
+            let ast = Compiler::new().compile(source).unwrap();
+            ast.fmap()[0].formula.clone()
+        };
+
+        let evaluate = |source: &str| {
+            let formula = formula(source);
+            Monitor::evaluate(&detections, None, &formula, formula.root()).unwrap()
+        };
+
+        let robustness = |source: &str| {
+            let formula = formula(source);
+            Monitor::robustness(&detections, None, &formula, formula.root()).unwrap()
+        };
+
+        assert!(evaluate("[@leftof([:car:], [:pedestrian:])]"));
+        assert!(evaluate(
+            "[E(v := [:car:], p := [:pedestrian:]) @rightof(v, p) & @dist(v, p) > 50.0]"
+        ));
+        assert!(!evaluate("[@above([:car:], [:pedestrian:])]"));
+        assert!(!evaluate("[@below([:car:], [:pedestrian:])]"));
+
+        // The boxes are 1.0 apart, or overlap by their height.
+        assert_eq!(robustness("[@leftof([:car:], [:pedestrian:])]"), 1.0);
+        assert_eq!(robustness("[@below([:car:], [:pedestrian:])]"), -1.0);
+    }
+
+    #[test]
+    fn robustness() {
+        let mut detections = IndexMap::new();
+        detections.insert(
+            Label::from("car"),
+            vec![annotation("car", 0.0), annotation("car", 10.0)],
+        );
+        detections.insert(
+            Label::from("pedestrian"),
+            vec![annotation("pedestrian", 2.0)],
+        );
+
+        let robustness = |source: &str| {
+            let ast = Compiler::new().compile(source).unwrap();
+            let formula = &ast.fmap()[0].formula;
+
+            Monitor::robustness(&detections, None, formula, formula.root()).unwrap()
+        };
+
+        assert_eq!(
+            robustness("[E(v := [:car:], p := [:pedestrian:]) @dist(v, p) < 5.0]"),
+            3.0
+        );
+        assert_eq!(
+            robustness("[A(v := [:car:]) E(p := [:pedestrian:]) @dist(v, p) < 5.0]"),
+            -3.0
+        );
+        assert_eq!(
+            robustness("[!(A(v := [:car:]) E(p := [:pedestrian:]) @dist(v, p) < 5.0)]"),
+            3.0
+        );
+        assert_eq!(
+            robustness("[NE([:car:]) & @dist([:car:], [:pedestrian:]) >= 1.0]"),
+            7.0
+        );
+        assert_eq!(
+            robustness("[NE([:truck:]) | @dist([:car:], [:pedestrian:]) < 1.0]"),
+            -1.0
+        );
+        assert_eq!(
+            robustness("[NE([:truck:]) & @x([:car:]) < 0.0]"),
+            f64::NEG_INFINITY
+        );
+    }
+
+    #[test]
+    fn complement() {
+        let mut detections = IndexMap::new();
+        detections.insert(
+            Label::from("car"),
+            vec![annotation("car", 0.0), annotation("car", 100.0)],
+        );
+
+        let evaluate = |detections: &IndexMap<Label, Vec<Annotation>>, source: &str| {
+            let ast = Compiler::new().compile(source).unwrap();
+            let formula = &ast.fmap()[0].formula;
+
+            Monitor::evaluate(detections, None, formula, formula.root()).unwrap()
+        };
+
+        assert!(!evaluate(&detections, "[NE(![:car:])]"));
+        assert!(!evaluate(&detections, "[E(v := [:car:]) NE(v & ![:car:])]"));
+
+        detections.insert(
+            Label::from("pedestrian"),
+            vec![annotation("pedestrian", 100.5)],
+        );
+
+        assert!(evaluate(&detections, "[NE(!([:car:] & [:truck:]))]"));
+        assert!(evaluate(&detections, "[E(v := [:car:]) NE(v & ![:car:])]"));
+        assert!(!evaluate(
+            &detections,
+            "[NE([:car:] & !([:car:] | [:pedestrian:]))]"
+        ));
+    }
+
+    #[test]
+    fn unknown_variable() {
+        let mut detections = IndexMap::new();
+        detections.insert(Label::from("car"), vec![annotation("car", 0.0)]);
+
+        let ast = Compiler::new().compile("[NE(x & [:car:])]").unwrap();
+        let formula = &ast.fmap()[0].formula;
+
+        assert_eq!(
+            Monitor::evaluate(&detections, None, formula, formula.root())
+                .unwrap_err()
+                .to_string(),
+            "monitor: s4: unknown variable `x`"
+        );
+    }
+
+    #[test]
+    fn vacuity() {
+        let mut detections = IndexMap::new();
+        detections.insert(Label::from("car"), vec![annotation("car", 0.0)]);
+
+        let monitor = |source: &str, vacuity: Vacuity| {
+            let ast = Compiler::new().vacuity(vacuity).compile(source).unwrap();
+            let formula = &ast.fmap()[0].formula;
+
+            (
+                Monitor::evaluate(&detections, None, formula, formula.root()).unwrap(),
+                Monitor::robustness(&detections, None, formula, formula.root()).unwrap(),
+            )
+        };
+
+        // The domain is empty as there are no pedestrians.
+        let source = "[A(p := [:pedestrian:]) @x(p) > 5.0]";
+        assert_eq!(monitor(source, Vacuity::Strict), (false, f64::NEG_INFINITY));
+        assert_eq!(monitor(source, Vacuity::Classical), (true, f64::INFINITY));
+
+        // Otherwise, the modes agree.
+        let source = "[A(v := [:car:]) @x(v) > 5.0]";
+        assert!(!monitor(source, Vacuity::Strict).0);
+        assert!(!monitor(source, Vacuity::Classical).0);
     }
 }
@@ -0,0 +1,1137 @@
+//! A gRPC service of online searches.
+//!
+//! A perception stack that runs STREM as a long-lived sidecar pushes its frames
+//! as they are produced, and expects each match back as soon as it is found.
+//! The [`Server`] exposes a single bidirectional streaming method (see
+//! [`proto`]): the client streams requests of frames (as STREM documents), and
+//! the server streams back each [`Match`](proto::Match) found by the online
+//! matcher, such that a search lasts as long as its stream.
+//!
+//! gRPC is served over unencrypted HTTP/2 with prior knowledge (i.e., `h2c`),
+//! as a sidecar is reached locally. Each stream is searched on its own thread,
+//! and its frames are only acknowledged (i.e., by flow control) once read by
+//! the search, such that a client faster than the search is slowed to its pace.
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::io::{BufReader, Read};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Condvar, Mutex};
+use std::thread;
+
+use serde_json::de::IoRead;
+use serde_json::StreamDeserializer;
+
+use self::hpack::Decoder;
+use self::http2::{Frame, Http2Error};
+use self::proto::{Match, SearchRequest};
+
+use crate::compiler::Compiler;
+use crate::config::ontology::Ontology;
+use crate::config::Configuration;
+use crate::controller::daemon::{Query, Session};
+use crate::datastream::io;
+use crate::datastream::io::importer::Importer;
+
+pub mod hpack;
+pub mod http2;
+pub mod proto;
+
+/// The receive window of the connection and of each stream.
+///
+/// This is larger than the default (i.e., 64 KiB) such that a document of many
+/// frames is not split into many round trips.
+const WINDOW: u32 = 1 << 20;
+
+/// The largest message accepted (i.e., 64 MiB).
+const MAX_MESSAGE: usize = 64 * 1024 * 1024;
+
+/// The maximum size of the dynamic table of the headers received.
+const HEADER_TABLE_SIZE: usize = 4096;
+
+/// The largest header block accepted (i.e., 64 KiB), across its frames.
+///
+/// A header block may be continued over any number of frames; therefore, it is
+/// bounded such that a client cannot exhaust the memory of the server.
+const MAX_HEADER_BLOCK: usize = 64 * 1024;
+
+/// A status of a gRPC call.
+#[derive(Debug)]
+struct Status {
+    code: u32,
+    message: String,
+}
+
+impl Status {
+    const OK: u32 = 0;
+    const CANCELLED: u32 = 1;
+    const INVALID_ARGUMENT: u32 = 3;
+    const RESOURCE_EXHAUSTED: u32 = 8;
+    const UNIMPLEMENTED: u32 = 12;
+    const INTERNAL: u32 = 13;
+
+    fn new(code: u32, message: impl fmt::Display) -> Self {
+        Status {
+            code,
+            message: message.to_string(),
+        }
+    }
+}
+
+/// An event of a stream, forwarded to its search.
+enum Event {
+    /// The data of a DATA frame, and the size of the frame to acknowledge.
+    Data(Vec<u8>, usize),
+
+    /// The end of the requests of the client.
+    End,
+}
+
+/// The send windows of a connection.
+struct Flow {
+    connection: i64,
+
+    /// The window of each open stream.
+    streams: HashMap<u32, i64>,
+
+    /// The initial window of a stream, as set by the peer.
+    initial: i64,
+
+    /// The largest frame payload the peer accepts.
+    size: usize,
+
+    /// Whether the connection has ended.
+    closed: bool,
+}
+
+/// The sending half of a connection, shared by its streams.
+struct Sender {
+    stream: Mutex<TcpStream>,
+    flow: Mutex<Flow>,
+
+    /// Signaled when a send window grows or the connection ends.
+    ready: Condvar,
+}
+
+impl Sender {
+    fn new(stream: TcpStream) -> Self {
+        Sender {
+            stream: Mutex::new(stream),
+            flow: Mutex::new(Flow {
+                connection: 65535,
+                streams: HashMap::new(),
+                initial: 65535,
+                size: http2::MAX_FRAME_SIZE,
+                closed: false,
+            }),
+            ready: Condvar::new(),
+        }
+    }
+
+    /// Send a [`Frame`].
+    fn send(&self, frame: Frame) -> Result<(), Box<dyn Error>> {
+        frame.write(&mut *self.stream.lock().unwrap())
+    }
+
+    /// Send the headers of a stream in a single frame.
+    fn headers(&self, id: u32, headers: &[(&str, &str)], end: bool) -> Result<(), Box<dyn Error>> {
+        let flags = match end {
+            true => http2::END_HEADERS | http2::END_STREAM,
+            false => http2::END_HEADERS,
+        };
+
+        self.send(Frame::new(
+            http2::HEADERS,
+            flags,
+            id,
+            hpack::encode(headers),
+        ))
+    }
+
+    /// Send the data of a stream, waiting for room in the send windows.
+    fn data(&self, id: u32, mut data: &[u8]) -> Result<(), Box<dyn Error>> {
+        while !data.is_empty() {
+            let n = {
+                let mut flow = self.flow.lock().unwrap();
+
+                loop {
+                    if flow.closed {
+                        return Err(Box::new(ServerError::from("connection closed")));
+                    }
+
+                    let Some(window) = flow.streams.get(&id).copied() else {
+                        return Err(Box::new(ServerError::from("stream reset")));
+                    };
+
+                    let n = data
+                        .len()
+                        .min(flow.size)
+                        .min(window.min(flow.connection).max(0) as usize);
+
+                    if n > 0 {
+                        flow.connection -= n as i64;
+                        flow.streams.insert(id, window - n as i64);
+                        break n;
+                    }
+
+                    flow = self.ready.wait(flow).unwrap();
+                }
+            };
+
+            self.send(Frame::new(http2::DATA, 0, id, data[..n].to_vec()))?;
+            data = &data[n..];
+        }
+
+        Ok(())
+    }
+
+    /// Acknowledge `n` bytes received on a stream (and the connection).
+    fn release(&self, id: u32, n: usize) -> Result<(), Box<dyn Error>> {
+        if n == 0 {
+            return Ok(());
+        }
+
+        for id in [0, id].into_iter().collect::<HashSet<u32>>() {
+            self.send(Frame::new(
+                http2::WINDOW_UPDATE,
+                0,
+                id,
+                (n as u32).to_be_bytes().to_vec(),
+            ))?;
+        }
+
+        Ok(())
+    }
+
+    /// Grow the send window of a stream (or the connection, if zero).
+    ///
+    /// A window that grows beyond [`http2::MAX_WINDOW`] is an error.
+    fn grow(&self, id: u32, increment: u32) -> Result<(), Http2Error> {
+        let mut flow = self.flow.lock().unwrap();
+
+        let window = match id {
+            0 => Some(&mut flow.connection),
+            _ => flow.streams.get_mut(&id),
+        };
+
+        if let Some(window) = window {
+            *window += increment as i64;
+
+            if *window > http2::MAX_WINDOW {
+                return Err(Http2Error::new(
+                    http2::FLOW_CONTROL_ERROR,
+                    "window exceeds the maximum",
+                ));
+            }
+        }
+
+        self.ready.notify_all();
+
+        Ok(())
+    }
+
+    /// Apply the settings of the peer.
+    fn settings(&self, payload: &[u8]) -> Result<(), Http2Error> {
+        let mut flow = self.flow.lock().unwrap();
+
+        for setting in payload.chunks_exact(6) {
+            let value = u32::from_be_bytes([setting[2], setting[3], setting[4], setting[5]]);
+
+            match u16::from_be_bytes([setting[0], setting[1]]) {
+                http2::SETTINGS_INITIAL_WINDOW_SIZE => {
+                    if value as i64 > http2::MAX_WINDOW {
+                        return Err(Http2Error::new(
+                            http2::FLOW_CONTROL_ERROR,
+                            "initial window exceeds the maximum",
+                        ));
+                    }
+
+                    // Adjust the window of each open stream by the change.
+                    let delta = value as i64 - flow.initial;
+                    flow.initial = value as i64;
+                    flow.streams.values_mut().for_each(|w| *w += delta);
+
+                    if flow.streams.values().any(|w| *w > http2::MAX_WINDOW) {
+                        return Err(Http2Error::new(
+                            http2::FLOW_CONTROL_ERROR,
+                            "window exceeds the maximum",
+                        ));
+                    }
+                }
+                http2::SETTINGS_MAX_FRAME_SIZE => {
+                    if !(http2::MAX_FRAME_SIZE..=http2::MAX_FRAME_SIZE_LIMIT)
+                        .contains(&(value as usize))
+                    {
+                        return Err(Http2Error::new(
+                            http2::PROTOCOL_ERROR,
+                            format!("invalid maximum frame size of {} bytes", value),
+                        ));
+                    }
+
+                    flow.size = value as usize;
+                }
+                _ => {}
+            }
+        }
+
+        self.ready.notify_all();
+
+        Ok(())
+    }
+
+    /// Open a stream to send on.
+    fn open(&self, id: u32) {
+        let mut flow = self.flow.lock().unwrap();
+        let initial = flow.initial;
+
+        flow.streams.insert(id, initial);
+    }
+
+    /// Close a stream, such that nothing more is sent on it.
+    ///
+    /// This returns whether the stream was open.
+    fn close(&self, id: u32) -> bool {
+        let open = self.flow.lock().unwrap().streams.remove(&id).is_some();
+        self.ready.notify_all();
+
+        open
+    }
+
+    /// End the connection, such that nothing more is sent on it.
+    fn shutdown(&self) {
+        self.flow.lock().unwrap().closed = true;
+        self.ready.notify_all();
+    }
+}
+
+/// The gRPC messages of a stream, in order.
+struct Messages<'a> {
+    id: u32,
+    sender: &'a Sender,
+    events: Receiver<Event>,
+
+    /// The data received but not yet read as messages.
+    buffer: Vec<u8>,
+
+    /// Whether the client has ended its requests.
+    ended: bool,
+}
+
+impl Messages<'_> {
+    /// Wait for the next message, if any.
+    ///
+    /// Each message is prefixed by whether it is compressed and its length.
+    /// The data of a stream is acknowledged once buffered.
+    fn next(&mut self) -> Result<Option<Vec<u8>>, Status> {
+        loop {
+            if let Some(header) = self.buffer.get(..5) {
+                let length = u32::from_be_bytes([header[1], header[2], header[3], header[4]]);
+                let length = length as usize;
+
+                if length > MAX_MESSAGE {
+                    return Err(Status::new(
+                        Status::RESOURCE_EXHAUSTED,
+                        format!("message of {} bytes exceeds the maximum", length),
+                    ));
+                }
+
+                if self.buffer.len() >= 5 + length {
+                    if header[0] != 0 {
+                        return Err(Status::new(
+                            Status::UNIMPLEMENTED,
+                            "compressed messages are not supported",
+                        ));
+                    }
+
+                    let message = self.buffer[5..(5 + length)].to_vec();
+                    self.buffer.drain(..(5 + length));
+
+                    return Ok(Some(message));
+                }
+            }
+
+            if self.ended {
+                return match self.buffer.is_empty() {
+                    true => Ok(None),
+                    false => Err(Status::new(Status::INVALID_ARGUMENT, "incomplete message")),
+                };
+            }
+
+            match self.events.recv() {
+                Ok(Event::Data(data, size)) => {
+                    self.buffer.extend(data);
+                    self.sender
+                        .release(self.id, size)
+                        .map_err(|e| Status::new(Status::CANCELLED, e))?;
+                }
+                Ok(Event::End) => self.ended = true,
+                Err(_) => return Err(Status::new(Status::CANCELLED, "stream reset")),
+            }
+        }
+    }
+}
+
+/// A gRPC server of online searches.
+pub struct Server {
+    listener: TcpListener,
+
+    /// The maximum number of frames kept for an unbounded query.
+    window: Option<usize>,
+
+    /// The [`Ontology`] applied to each search, if any.
+    ontology: Option<Ontology>,
+
+    /// The [`Compiler`] of the queries of each search.
+    compiler: Compiler,
+}
+
+impl Server {
+    /// Create a new [`Server`] listening on `address`.
+    ///
+    /// The queries of each search are compiled by the `compiler` (see
+    /// [`Configuration::compiler`]).
+    pub fn bind(
+        address: impl ToSocketAddrs + fmt::Display,
+        window: Option<usize>,
+        ontology: Option<Ontology>,
+        compiler: Compiler,
+    ) -> Result<Self, Box<dyn Error>> {
+        let listener = TcpListener::bind(&address)
+            .map_err(|e| ServerError::from(format!("{}: failed to listen ({})", address, e)))?;
+
+        Ok(Server {
+            listener,
+            window,
+            ontology,
+            compiler,
+        })
+    }
+
+    /// The address the [`Server`] listens on.
+    pub fn address(&self) -> Result<String, Box<dyn Error>> {
+        Ok(self.listener.local_addr()?.to_string())
+    }
+
+    /// Serve connections until the process stops.
+    ///
+    /// Each connection is served on its own thread, and a connection that
+    /// fails is reported and closed while the others continue.
+    pub fn run(&self) -> Result<(), Box<dyn Error>> {
+        eprintln!("server: listening on grpc://{}", self.address()?);
+
+        thread::scope(|scope| {
+            for stream in self.listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(e) => {
+                        eprintln!("server: warning: failed to accept connection ({})", e);
+                        continue;
+                    }
+                };
+
+                scope.spawn(move || {
+                    if let Err(e) = self.serve(stream) {
+                        eprintln!("server: warning: {}", e);
+                    }
+                });
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Serve a single HTTP/2 connection.
+    fn serve(&self, stream: TcpStream) -> Result<(), Box<dyn Error>> {
+        let mut reader = BufReader::new(stream.try_clone()?);
+
+        let mut preface = [0; http2::PREFACE.len()];
+        reader.read_exact(&mut preface)?;
+
+        if preface != http2::PREFACE {
+            return Err(Box::new(ServerError::from(
+                "expected an HTTP/2 connection with prior knowledge",
+            )));
+        }
+
+        let sender = Sender::new(stream);
+
+        let mut settings = Vec::new();
+        settings.extend(http2::SETTINGS_INITIAL_WINDOW_SIZE.to_be_bytes());
+        settings.extend(WINDOW.to_be_bytes());
+
+        sender.send(Frame::new(http2::SETTINGS, 0, 0, settings))?;
+        sender.release(0, (WINDOW - 65535) as usize)?;
+
+        let result = thread::scope(|scope| {
+            let result = self.dispatch(&mut reader, &sender, scope);
+
+            // End the searches of the connection.
+            //
+            // A search waiting for room to send in is woken, such that it
+            // stops rather than waits for a window that never grows.
+            sender.shutdown();
+
+            result
+        });
+
+        if let Err(e) = &result {
+            let code = e
+                .downcast_ref::<Http2Error>()
+                .map_or(http2::PROTOCOL_ERROR, |e| e.code);
+
+            let mut payload = 0u32.to_be_bytes().to_vec();
+            payload.extend(code.to_be_bytes());
+
+            let _ = sender.send(Frame::new(http2::GOAWAY, 0, 0, payload));
+        }
+
+        result
+    }
+
+    /// Read the frames of a connection, and dispatch those of each stream to
+    /// its search.
+    fn dispatch<'s>(
+        &'s self,
+        reader: &mut impl Read,
+        sender: &'s Sender,
+        scope: &'s thread::Scope<'s, '_>,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut decoder = Decoder::new(HEADER_TABLE_SIZE);
+
+        // The searches of the open streams.
+        let mut streams: HashMap<u32, mpsc::Sender<Event>> = HashMap::new();
+
+        // The highest identifier of a stream opened by the client.
+        let mut last = 0;
+
+        // The header block being continued, if any.
+        let mut block: Option<(u32, u8, Vec<u8>)> = None;
+
+        while let Some(frame) = Frame::read(reader, http2::MAX_FRAME_SIZE)? {
+            if let Some((id, _, _)) = &block {
+                if frame.kind != http2::CONTINUATION || frame.stream != *id {
+                    return Err(Box::new(Http2Error::new(
+                        http2::PROTOCOL_ERROR,
+                        "expected continuation of headers",
+                    )));
+                }
+            }
+
+            match frame.kind {
+                http2::HEADERS | http2::CONTINUATION => {
+                    let (id, flags, mut fragment) = match block.take() {
+                        Some(block) => block,
+                        None if frame.kind == http2::HEADERS => {
+                            (frame.stream, frame.flags, Vec::new())
+                        }
+                        None => {
+                            return Err(Box::new(Http2Error::new(
+                                http2::PROTOCOL_ERROR,
+                                "unexpected continuation",
+                            )))
+                        }
+                    };
+
+                    match frame.kind {
+                        http2::HEADERS => fragment.extend(frame.contents()?),
+                        _ => fragment.extend(&frame.payload),
+                    }
+
+                    if fragment.len() > MAX_HEADER_BLOCK {
+                        return Err(Box::new(Http2Error::new(
+                            http2::ENHANCE_YOUR_CALM,
+                            "header block exceeds the maximum",
+                        )));
+                    }
+
+                    if frame.flags & http2::END_HEADERS == 0 {
+                        block = Some((id, flags, fragment));
+                        continue;
+                    }
+
+                    let headers = decoder.decode(&fragment)?;
+                    let end = flags & http2::END_STREAM != 0;
+
+                    // The trailers of an open stream.
+                    if id <= last {
+                        if let (true, Some(events)) = (end, streams.remove(&id)) {
+                            let _ = events.send(Event::End);
+                        }
+
+                        continue;
+                    }
+
+                    if id % 2 == 0 {
+                        return Err(Box::new(Http2Error::new(
+                            http2::PROTOCOL_ERROR,
+                            "stream identifier must be odd",
+                        )));
+                    }
+
+                    last = id;
+
+                    let header = |name: &str| {
+                        headers
+                            .iter()
+                            .find(|(n, _)| n == name)
+                            .map(|(_, v)| v.as_str())
+                    };
+
+                    if header(":method") != Some("POST")
+                        || header(":path") != Some(proto::SEARCH)
+                        || !header("content-type")
+                            .is_some_and(|t| t.starts_with("application/grpc"))
+                    {
+                        let message =
+                            format!("unknown method `{}`", header(":path").unwrap_or_default());
+
+                        sender.headers(
+                            id,
+                            &[
+                                (":status", "200"),
+                                ("content-type", "application/grpc"),
+                                ("grpc-status", &Status::UNIMPLEMENTED.to_string()),
+                                ("grpc-message", &self::percent(&message)),
+                            ],
+                            true,
+                        )?;
+
+                        continue;
+                    }
+
+                    let (events, receiver) = mpsc::channel();
+
+                    if end {
+                        let _ = events.send(Event::End);
+                    } else {
+                        streams.insert(id, events);
+                    }
+
+                    sender.open(id);
+
+                    scope.spawn(move || {
+                        let messages = Messages {
+                            id,
+                            sender,
+                            events: receiver,
+                            buffer: Vec::new(),
+                            ended: false,
+                        };
+
+                        let status = match self.search(messages, sender) {
+                            Ok(()) => Status::new(Status::OK, ""),
+                            Err(status) => status,
+                        };
+
+                        // Send the status, unless the stream was reset.
+                        if status.code != Status::CANCELLED {
+                            let code = status.code.to_string();
+                            let message = self::percent(&status.message);
+
+                            let trailers = match message.is_empty() {
+                                true => vec![("grpc-status", code.as_str())],
+                                false => vec![
+                                    ("grpc-status", code.as_str()),
+                                    ("grpc-message", message.as_str()),
+                                ],
+                            };
+
+                            let _ = sender.headers(id, &trailers, true);
+                        }
+
+                        sender.close(id);
+                    });
+                }
+                http2::DATA => {
+                    let size = frame.payload.len();
+                    let end = frame.flags & http2::END_STREAM != 0;
+
+                    // Acknowledge the data of a closed stream right away, as it
+                    // is not read by any search.
+                    let sent = match streams.get(&frame.stream) {
+                        Some(events) => events
+                            .send(Event::Data(frame.contents()?.to_vec(), size))
+                            .is_ok(),
+                        None => false,
+                    };
+
+                    if !sent {
+                        sender.release(0, size)?;
+                    }
+
+                    if end {
+                        if let Some(events) = streams.remove(&frame.stream) {
+                            let _ = events.send(Event::End);
+                        }
+                    }
+                }
+                http2::RST_STREAM => {
+                    streams.remove(&frame.stream);
+                    sender.close(frame.stream);
+                }
+                http2::SETTINGS if frame.flags & http2::ACK == 0 => {
+                    if frame.payload.len() % 6 != 0 {
+                        return Err(Box::new(Http2Error::new(
+                            http2::FRAME_SIZE_ERROR,
+                            "malformed settings",
+                        )));
+                    }
+
+                    sender.settings(&frame.payload)?;
+                    sender.send(Frame::new(http2::SETTINGS, http2::ACK, 0, Vec::new()))?;
+                }
+                http2::PING if frame.flags & http2::ACK == 0 => {
+                    sender.send(Frame::new(http2::PING, http2::ACK, 0, frame.payload))?;
+                }
+                http2::WINDOW_UPDATE => {
+                    let increment = frame
+                        .payload
+                        .get(..4)
+                        .map(|n| u32::from_be_bytes([n[0], n[1], n[2], n[3]]) & 0x7fffffff)
+                        .ok_or(Http2Error::new(
+                            http2::FRAME_SIZE_ERROR,
+                            "malformed window update",
+                        ))?;
+
+                    if increment == 0 && frame.stream == 0 {
+                        return Err(Box::new(Http2Error::new(
+                            http2::FLOW_CONTROL_ERROR,
+                            "window update of zero",
+                        )));
+                    }
+
+                    // A stream whose window overflows is reset, while the
+                    // connection itself ends.
+                    if let Err(e) = sender.grow(frame.stream, increment) {
+                        if frame.stream == 0 {
+                            return Err(Box::new(e));
+                        }
+
+                        streams.remove(&frame.stream);
+                        sender.close(frame.stream);
+                        sender.send(Frame::new(
+                            http2::RST_STREAM,
+                            0,
+                            frame.stream,
+                            e.code.to_be_bytes().to_vec(),
+                        ))?;
+                    }
+                }
+                http2::PUSH_PROMISE => {
+                    return Err(Box::new(Http2Error::new(
+                        http2::PROTOCOL_ERROR,
+                        "unexpected push promise",
+                    )))
+                }
+                http2::GOAWAY => break,
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Search the frames of the messages of a stream.
+    ///
+    /// The queries are compiled from the first request, and each match is
+    /// sent as soon as it is found.
+    fn search(&self, mut messages: Messages, sender: &Sender) -> Result<(), Status> {
+        let id = messages.id;
+
+        sender
+            .headers(
+                id,
+                &[(":status", "200"), ("content-type", "application/grpc")],
+                false,
+            )
+            .map_err(|e| Status::new(Status::CANCELLED, e))?;
+
+        let Some(message) = messages.next()? else {
+            return Ok(());
+        };
+
+        let request = self::decode(&message)?;
+
+        if request.queries.is_empty() {
+            return Err(Status::new(
+                Status::INVALID_ARGUMENT,
+                "the first request must set the queries",
+            ));
+        }
+
+        let mut ids = HashSet::new();
+        let mut queries = Vec::new();
+
+        for query in request.queries.iter() {
+            if !ids.insert(&query.id) {
+                return Err(Status::new(
+                    Status::INVALID_ARGUMENT,
+                    format!("duplicate query `{}`", query.id),
+                ));
+            }
+
+            queries.push(
+                Query::compile(&query.id, &query.pattern, &self.compiler, self.window).map_err(
+                    |e| {
+                        Status::new(
+                            Status::INVALID_ARGUMENT,
+                            format!("query `{}`: {}", query.id, e),
+                        )
+                    },
+                )?,
+            );
+        }
+
+        let pattern = String::new();
+        let mut config = Configuration::new(&pattern);
+        config.online = true;
+        config.window = self.window;
+        config.ontology = self.ontology.clone();
+
+        let mut importer = Importer::new(&config);
+        let mut session =
+            Session::new(&queries).map_err(|e| Status::new(Status::INVALID_ARGUMENT, e))?;
+
+        let mut request = Some(request);
+
+        loop {
+            let request = match request.take() {
+                Some(request) => request,
+                None => match messages.next()? {
+                    Some(message) => self::decode(&message)?,
+                    None => return Ok(()),
+                },
+            };
+
+            let stream = StreamDeserializer::<_, io::DataStream<'static>>::new(IoRead::new(
+                request.datastream.as_slice(),
+            ));
+
+            for data in stream {
+                let data = data.map_err(|e| Status::new(Status::INVALID_ARGUMENT, e))?;
+
+                let Some(frames) = importer
+                    .import(data)
+                    .map_err(|e| Status::new(Status::INVALID_ARGUMENT, e))?
+                else {
+                    continue;
+                };
+
+                for frame in frames {
+                    let events = session
+                        .push(frame)
+                        .map_err(|e| Status::new(Status::INTERNAL, e))?;
+
+                    for event in events {
+                        let message = Match {
+                            query: event.query.to_string(),
+                            start: event.range.start as u64,
+                            end: event.range.end as u64,
+                        }
+                        .encode();
+
+                        let mut data = vec![0];
+                        data.extend((message.len() as u32).to_be_bytes());
+                        data.extend(message);
+
+                        sender
+                            .data(id, &data)
+                            .map_err(|e| Status::new(Status::CANCELLED, e))?;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Decode a [`SearchRequest`].
+fn decode(message: &[u8]) -> Result<SearchRequest, Status> {
+    SearchRequest::decode(message).map_err(|e| Status::new(Status::INVALID_ARGUMENT, e))
+}
+
+/// Percent-encode a status message, as required of the `grpc-message` header.
+fn percent(message: &str) -> String {
+    message
+        .bytes()
+        .map(|b| match b {
+            b' '..=b'~' if b != b'%' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+struct ServerError {
+    msg: String,
+}
+
+impl From<&str> for ServerError {
+    fn from(msg: &str) -> Self {
+        ServerError {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for ServerError {
+    fn from(msg: String) -> Self {
+        ServerError { msg }
+    }
+}
+
+impl fmt::Display for ServerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "server: {}", self.msg)
+    }
+}
+
+impl Error for ServerError {}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::net::TcpStream;
+
+    use super::*;
+
+    /// Connect to a new [`Server`], and send the preface and `settings`.
+    fn connect(settings: &[(u16, u32)]) -> TcpStream {
+        let server = Server::bind("127.0.0.1:0", None, None, Compiler::new()).unwrap();
+        let address = server.address().unwrap();
+
+        thread::spawn(move || server.run().is_ok());
+
+        let mut stream = TcpStream::connect(address).unwrap();
+        stream.write_all(http2::PREFACE).unwrap();
+
+        let payload = settings
+            .iter()
+            .flat_map(|(id, value)| [&id.to_be_bytes()[..], &value.to_be_bytes()].concat())
+            .collect();
+
+        Frame::new(http2::SETTINGS, 0, 0, payload)
+            .write(&mut stream)
+            .unwrap();
+
+        stream
+    }
+
+    /// Start a search of two frames of a car on stream 1, returning the number
+    /// of bytes of its data.
+    fn request(stream: &mut TcpStream) -> usize {
+        let headers = hpack::encode(&[
+            (":method", "POST"),
+            (":scheme", "http"),
+            (":path", proto::SEARCH),
+            (":authority", "localhost"),
+            ("content-type", "application/grpc"),
+        ]);
+
+        Frame::new(http2::HEADERS, http2::END_HEADERS, 1, headers)
+            .write(stream)
+            .unwrap();
+
+        let requests = [
+            SearchRequest {
+                queries: vec![proto::Query {
+                    id: "cars".to_string(),
+                    pattern: "[[:car:]]{2,}".to_string(),
+                }],
+                datastream: Vec::new(),
+            },
+            SearchRequest {
+                queries: Vec::new(),
+                datastream: br#"{"version":"0.2.0","frames":[{"index":0,"samples":[{"type":"@stremf/sample/detection","channel":"cam","image":{"path":"0.png","dimensions":{"width":10,"height":10}},"annotations":[{"class":"car","score":1.0,"bbox":{"type":"@stremf/bbox/aabb","region":{"center":{"x":5.0,"y":5.0},"dimensions":{"w":2.0,"h":2.0}}}}]}]},{"index":1,"samples":[{"type":"@stremf/sample/detection","channel":"cam","image":{"path":"1.png","dimensions":{"width":10,"height":10}},"annotations":[{"class":"car","score":1.0,"bbox":{"type":"@stremf/bbox/aabb","region":{"center":{"x":5.0,"y":5.0},"dimensions":{"w":2.0,"h":2.0}}}}]}]}]}"#.to_vec(),
+            },
+        ];
+
+        let mut data = Vec::new();
+
+        for request in requests.iter() {
+            let message = request.encode();
+
+            data.push(0);
+            data.extend((message.len() as u32).to_be_bytes());
+            data.extend(message);
+        }
+
+        let size = data.len();
+
+        Frame::new(http2::DATA, http2::END_STREAM, 1, data)
+            .write(stream)
+            .unwrap();
+
+        size
+    }
+
+    /// Read the frames of the server until it ends the connection, returning
+    /// the code of the error sent.
+    fn goaway(stream: &mut TcpStream) -> u32 {
+        loop {
+            let frame = Frame::read(stream, http2::MAX_FRAME_SIZE).unwrap().unwrap();
+
+            if frame.kind == http2::GOAWAY {
+                return u32::from_be_bytes(frame.payload[4..8].try_into().unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn search() {
+        let mut stream = connect(&[]);
+        self::request(&mut stream);
+
+        let mut decoder = Decoder::new(HEADER_TABLE_SIZE);
+        let mut data = Vec::new();
+
+        let trailers = loop {
+            let frame = Frame::read(&mut stream, http2::MAX_FRAME_SIZE)
+                .unwrap()
+                .unwrap();
+
+            match frame.kind {
+                http2::DATA => data.extend(frame.payload),
+                http2::HEADERS => {
+                    let headers = decoder.decode(&frame.payload).unwrap();
+
+                    if frame.flags & http2::END_STREAM != 0 {
+                        break headers;
+                    }
+                }
+                _ => {}
+            }
+        };
+
+        assert_eq!(trailers, vec![("grpc-status".to_string(), "0".to_string())]);
+
+        assert_eq!(
+            Match::decode(&data[5..]).unwrap(),
+            Match {
+                query: "cars".to_string(),
+                start: 0,
+                end: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn flow() {
+        // A send window of a stream smaller than the match.
+        let mut stream = connect(&[(http2::SETTINGS_INITIAL_WINDOW_SIZE, 8)]);
+        let size = self::request(&mut stream);
+
+        let mut data = Vec::new();
+        let mut released = HashMap::new();
+
+        // Read the next frame, recording its data and the windows released.
+        let mut next = |stream: &mut TcpStream, data: &mut Vec<u8>| {
+            let frame = Frame::read(stream, http2::MAX_FRAME_SIZE).unwrap().unwrap();
+
+            match frame.kind {
+                http2::DATA => data.extend(&frame.payload),
+                http2::WINDOW_UPDATE => {
+                    let increment = u32::from_be_bytes(frame.payload[..4].try_into().unwrap());
+                    *released.entry(frame.stream).or_insert(0) += increment as usize;
+                }
+                _ => {}
+            }
+
+            frame
+        };
+
+        // Only the window is sent until it grows.
+        while data.len() < 8 {
+            next(&mut stream, &mut data);
+        }
+
+        assert_eq!(data.len(), 8);
+
+        Frame::new(http2::WINDOW_UPDATE, 0, 1, 64u32.to_be_bytes().to_vec())
+            .write(&mut stream)
+            .unwrap();
+
+        // The rest is sent until the trailers.
+        loop {
+            let frame = next(&mut stream, &mut data);
+
+            if frame.kind == http2::HEADERS && frame.flags & http2::END_STREAM != 0 {
+                break;
+            }
+        }
+
+        assert!(data.len() > 8);
+        assert_eq!(
+            Match::decode(&data[5..]).unwrap(),
+            Match {
+                query: "cars".to_string(),
+                start: 0,
+                end: 2,
+            }
+        );
+
+        // The data received is acknowledged on the stream and the connection,
+        // along with the window of the connection above the default.
+        assert_eq!(released[&1], size);
+        assert_eq!(released[&0], size + (WINDOW - 65535) as usize);
+
+        // A window may not grow beyond the maximum.
+        let mut stream = connect(&[]);
+
+        Frame::new(
+            http2::WINDOW_UPDATE,
+            0,
+            0,
+            0x7fffffffu32.to_be_bytes().to_vec(),
+        )
+        .write(&mut stream)
+        .unwrap();
+
+        assert_eq!(goaway(&mut stream), http2::FLOW_CONTROL_ERROR);
+
+        let mut stream = connect(&[(http2::SETTINGS_INITIAL_WINDOW_SIZE, 1 << 31)]);
+        assert_eq!(goaway(&mut stream), http2::FLOW_CONTROL_ERROR);
+    }
+
+    #[test]
+    fn settings() {
+        // The maximum frame size may only be within 16 KiB and 16 MiB.
+        for size in [http2::MAX_FRAME_SIZE - 1, http2::MAX_FRAME_SIZE_LIMIT + 1] {
+            let mut stream = connect(&[(http2::SETTINGS_MAX_FRAME_SIZE, size as u32)]);
+            assert_eq!(goaway(&mut stream), http2::PROTOCOL_ERROR);
+        }
+
+        let mut stream = connect(&[(http2::SETTINGS_MAX_FRAME_SIZE, 1 << 16)]);
+
+        loop {
+            let frame = Frame::read(&mut stream, http2::MAX_FRAME_SIZE)
+                .unwrap()
+                .unwrap();
+
+            assert_ne!(frame.kind, http2::GOAWAY);
+
+            if frame.kind == http2::SETTINGS && frame.flags & http2::ACK != 0 {
+                break;
+            }
+        }
+    }
+
+    #[test]
+    fn continuation() {
+        let mut stream = connect(&[]);
+
+        // A header block continued beyond the maximum.
+        Frame::new(http2::HEADERS, 0, 1, vec![0; 100])
+            .write(&mut stream)
+            .unwrap();
+
+        for _ in 0..(MAX_HEADER_BLOCK / http2::MAX_FRAME_SIZE) {
+            Frame::new(http2::CONTINUATION, 0, 1, vec![0; http2::MAX_FRAME_SIZE])
+                .write(&mut stream)
+                .unwrap();
+        }
+
+        assert_eq!(goaway(&mut stream), http2::ENHANCE_YOUR_CALM);
+    }
+}
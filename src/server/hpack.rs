@@ -0,0 +1,617 @@
+//! HPACK header compression (RFC 7541).
+//!
+//! The headers of HTTP/2 are compressed against a static table of common
+//! headers, a dynamic table of the headers seen so far on the connection, and a
+//! static Huffman code. A [`Decoder`] keeps the dynamic table of a connection;
+//! headers are only encoded as literals (without indexing or Huffman coding),
+//! which any peer can decode without keeping state for them.
+
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::sync::OnceLock;
+
+/// The static table of common headers.
+const STATIC: [(&str, &str); 61] = [
+    (":authority", ""),
+    (":method", "GET"),
+    (":method", "POST"),
+    (":path", "/"),
+    (":path", "/index.html"),
+    (":scheme", "http"),
+    (":scheme", "https"),
+    (":status", "200"),
+    (":status", "204"),
+    (":status", "206"),
+    (":status", "304"),
+    (":status", "400"),
+    (":status", "404"),
+    (":status", "500"),
+    ("accept-charset", ""),
+    ("accept-encoding", "gzip, deflate"),
+    ("accept-language", ""),
+    ("accept-ranges", ""),
+    ("accept", ""),
+    ("access-control-allow-origin", ""),
+    ("age", ""),
+    ("allow", ""),
+    ("authorization", ""),
+    ("cache-control", ""),
+    ("content-disposition", ""),
+    ("content-encoding", ""),
+    ("content-language", ""),
+    ("content-length", ""),
+    ("content-location", ""),
+    ("content-range", ""),
+    ("content-type", ""),
+    ("cookie", ""),
+    ("date", ""),
+    ("etag", ""),
+    ("expect", ""),
+    ("expires", ""),
+    ("from", ""),
+    ("host", ""),
+    ("if-match", ""),
+    ("if-modified-since", ""),
+    ("if-none-match", ""),
+    ("if-range", ""),
+    ("if-unmodified-since", ""),
+    ("last-modified", ""),
+    ("link", ""),
+    ("location", ""),
+    ("max-forwards", ""),
+    ("proxy-authenticate", ""),
+    ("proxy-authorization", ""),
+    ("range", ""),
+    ("referer", ""),
+    ("refresh", ""),
+    ("retry-after", ""),
+    ("server", ""),
+    ("set-cookie", ""),
+    ("strict-transport-security", ""),
+    ("transfer-encoding", ""),
+    ("user-agent", ""),
+    ("vary", ""),
+    ("via", ""),
+    ("www-authenticate", ""),
+];
+
+/// The length (in bits) of the Huffman code of each symbol.
+///
+/// The code is canonical (i.e., the codes of each length are consecutive and
+/// ordered by symbol); therefore, the codes follow from their lengths alone.
+/// The last symbol is the end of a string, which is never encoded.
+const LENGTHS: [u8; 257] = [
+    13, 23, 28, 28, 28, 28, 28, 28, 28, 24, 30, 28, 28, 30, 28, 28, 28, 28, 28, 28, 28, 28, 30, 28,
+    28, 28, 28, 28, 28, 28, 28, 28, 6, 10, 10, 12, 13, 6, 8, 11, 10, 10, 8, 11, 8, 6, 6, 6, 5, 5,
+    5, 6, 6, 6, 6, 6, 6, 6, 7, 8, 15, 6, 12, 10, 13, 6, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7, 7,
+    7, 7, 7, 7, 7, 7, 7, 7, 8, 7, 8, 13, 19, 13, 14, 6, 15, 5, 6, 5, 6, 5, 6, 6, 6, 5, 7, 7, 6, 6,
+    6, 5, 6, 7, 6, 5, 5, 6, 7, 7, 7, 7, 7, 15, 11, 14, 13, 28, 20, 22, 20, 20, 22, 22, 22, 23, 22,
+    23, 23, 23, 23, 23, 24, 23, 24, 24, 22, 23, 24, 23, 23, 23, 23, 21, 22, 23, 22, 23, 23, 24, 22,
+    21, 20, 22, 22, 23, 23, 21, 23, 22, 22, 24, 21, 22, 23, 23, 21, 21, 22, 21, 23, 22, 23, 23, 20,
+    22, 22, 22, 23, 22, 22, 23, 26, 26, 20, 19, 22, 23, 22, 25, 26, 26, 26, 27, 27, 26, 24, 25, 19,
+    21, 26, 27, 27, 26, 27, 24, 21, 21, 26, 26, 28, 27, 27, 27, 20, 24, 20, 21, 22, 21, 21, 23, 22,
+    22, 25, 25, 24, 24, 26, 23, 26, 27, 26, 26, 27, 27, 27, 27, 27, 28, 27, 27, 27, 27, 27, 26, 30,
+];
+
+/// The symbol of the end of a string.
+const EOS: u16 = 256;
+
+/// The size of an entry of the dynamic table besides its name and value.
+const OVERHEAD: usize = 32;
+
+/// The canonical Huffman code, arranged for decoding.
+struct Huffman {
+    /// The first code of each length.
+    first: [u32; 31],
+
+    /// The number of codes of each length.
+    count: [u32; 31],
+
+    /// The position of the first symbol of each length within `symbols`.
+    offset: [usize; 31],
+
+    /// The symbols, ordered by the length of their code and then by value.
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    /// The Huffman code of HPACK.
+    fn get() -> &'static Self {
+        static HUFFMAN: OnceLock<Huffman> = OnceLock::new();
+
+        HUFFMAN.get_or_init(|| {
+            let mut symbols = (0..=EOS).collect::<Vec<u16>>();
+            symbols.sort_by_key(|s| (LENGTHS[*s as usize], *s));
+
+            let mut count = [0; 31];
+            LENGTHS.iter().for_each(|l| count[*l as usize] += 1);
+
+            let mut first = [0; 31];
+            let mut offset = [0; 31];
+            let mut code = 0;
+
+            for length in 1..31 {
+                first[length] = code;
+                offset[length] = offset[length - 1] + count[length - 1] as usize;
+                code = (code + count[length]) << 1;
+            }
+
+            Huffman {
+                first,
+                count,
+                offset,
+                symbols,
+            }
+        })
+    }
+
+    /// Decode a Huffman-coded string.
+    ///
+    /// The string is padded to a whole byte with the most significant bits of
+    /// the end of a string (i.e., all ones), and at most seven bits of it.
+    fn decode(&self, data: &[u8]) -> Result<Vec<u8>, HpackError> {
+        let mut decoded = Vec::with_capacity(data.len() * 8 / 5);
+        let (mut code, mut length) = (0u32, 0usize);
+
+        for byte in data.iter() {
+            for i in (0..8).rev() {
+                code = (code << 1) | ((*byte >> i) & 1) as u32;
+                length += 1;
+
+                if length > 30 {
+                    return Err(HpackError::from("invalid huffman code"));
+                }
+
+                if code >= self.first[length] && code - self.first[length] < self.count[length] {
+                    let symbol =
+                        self.symbols[self.offset[length] + (code - self.first[length]) as usize];
+
+                    if symbol == EOS {
+                        return Err(HpackError::from("unexpected end of string"));
+                    }
+
+                    decoded.push(symbol as u8);
+                    (code, length) = (0, 0);
+                }
+            }
+        }
+
+        if length > 7 || code != (1 << length) - 1 {
+            return Err(HpackError::from("invalid huffman padding"));
+        }
+
+        Ok(decoded)
+    }
+}
+
+/// A decoder of the header blocks of a connection.
+pub struct Decoder {
+    /// The dynamic table, from the most to the least recent entry.
+    table: VecDeque<(String, String)>,
+
+    /// The size of the dynamic table.
+    size: usize,
+
+    /// The maximum size of the dynamic table, as last updated by the peer.
+    capacity: usize,
+
+    /// The maximum size of the dynamic table the peer may update to.
+    maximum: usize,
+}
+
+impl Decoder {
+    /// Create a new [`Decoder`] with a dynamic table of at most `maximum`.
+    pub fn new(maximum: usize) -> Self {
+        Decoder {
+            table: VecDeque::new(),
+            size: 0,
+            capacity: maximum,
+            maximum,
+        }
+    }
+
+    /// Decode a header block into its (name, value) pairs, in order.
+    pub fn decode(&mut self, block: &[u8]) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+        let mut headers = Vec::new();
+        let mut block = block;
+
+        while let Some(byte) = block.first().copied() {
+            if byte & 0x80 != 0 {
+                // An indexed header.
+                let index = self::integer(&mut block, 7)?;
+                headers.push(self.entry(index)?);
+            } else if byte & 0xe0 == 0x20 {
+                // An update of the size of the dynamic table.
+                let capacity = self::integer(&mut block, 5)?;
+
+                if capacity > self.maximum {
+                    return Err(Box::new(HpackError::from("table size exceeds maximum")));
+                }
+
+                self.capacity = capacity;
+                self.evict(0);
+            } else {
+                // A literal header, indexed if its prefix is `01`.
+                let (prefix, indexed) = match byte & 0xc0 == 0x40 {
+                    true => (6, true),
+                    false => (4, false),
+                };
+
+                let name = match self::integer(&mut block, prefix)? {
+                    0 => self::string(&mut block)?,
+                    index => self.entry(index)?.0,
+                };
+                let value = self::string(&mut block)?;
+
+                if indexed {
+                    self.insert(name.clone(), value.clone());
+                }
+
+                headers.push((name, value));
+            }
+        }
+
+        Ok(headers)
+    }
+
+    /// The entry at `index` of the static and dynamic tables.
+    fn entry(&self, index: usize) -> Result<(String, String), HpackError> {
+        match index {
+            1..=61 => {
+                let (name, value) = STATIC[index - 1];
+                Ok((name.to_string(), value.to_string()))
+            }
+            _ => self
+                .table
+                .get(index.wrapping_sub(62))
+                .cloned()
+                .ok_or(HpackError::from(format!("invalid index `{}`", index))),
+        }
+    }
+
+    /// Add an entry to the dynamic table, evicting the least recent entries
+    /// to make room.
+    fn insert(&mut self, name: String, value: String) {
+        let size = name.len() + value.len() + OVERHEAD;
+        self.evict(size);
+
+        // An entry larger than the table empties it and is not added.
+        if size <= self.capacity {
+            self.size += size;
+            self.table.push_front((name, value));
+        }
+    }
+
+    /// Evict the least recent entries until `size` more fits the table.
+    fn evict(&mut self, size: usize) {
+        while self.size + size > self.capacity {
+            match self.table.pop_back() {
+                Some((name, value)) => self.size -= name.len() + value.len() + OVERHEAD,
+                None => break,
+            }
+        }
+    }
+}
+
+/// Encode headers as a header block of literals.
+pub fn encode(headers: &[(&str, &str)]) -> Vec<u8> {
+    let mut block = Vec::new();
+
+    for (name, value) in headers.iter() {
+        // A literal header without indexing of a new name.
+        block.push(0x00);
+
+        for s in [name, value] {
+            self::prefix(&mut block, s.len(), 7, 0x00);
+            block.extend(s.as_bytes());
+        }
+    }
+
+    block
+}
+
+/// Encode an integer with a prefix of `bits` within the first byte.
+fn prefix(block: &mut Vec<u8>, mut n: usize, bits: u32, flags: u8) {
+    let mask = (1 << bits) - 1;
+
+    if n < mask {
+        block.push(flags | n as u8);
+        return;
+    }
+
+    block.push(flags | mask as u8);
+    n -= mask;
+
+    while n >= 0x80 {
+        block.push((n % 0x80) as u8 | 0x80);
+        n /= 0x80;
+    }
+
+    block.push(n as u8);
+}
+
+/// Decode an integer with a prefix of `bits` within the first byte.
+fn integer(block: &mut &[u8], bits: u32) -> Result<usize, HpackError> {
+    let mask = (1 << bits) - 1;
+
+    let (first, rest) = block
+        .split_first()
+        .ok_or(HpackError::from("unexpected end of block"))?;
+    *block = rest;
+
+    let mut n = (*first as usize) & mask;
+
+    if n < mask {
+        return Ok(n);
+    }
+
+    let mut shift = 0;
+
+    loop {
+        let (byte, rest) = block
+            .split_first()
+            .ok_or(HpackError::from("unexpected end of block"))?;
+        *block = rest;
+
+        if shift > 28 {
+            return Err(HpackError::from("integer overflow"));
+        }
+
+        n += ((*byte & 0x7f) as usize) << shift;
+        shift += 7;
+
+        if byte & 0x80 == 0 {
+            return Ok(n);
+        }
+    }
+}
+
+/// Decode a string, Huffman-coded if its first bit is set.
+fn string(block: &mut &[u8]) -> Result<String, HpackError> {
+    let huffman = block.first().is_some_and(|b| b & 0x80 != 0);
+    let length = self::integer(block, 7)?;
+
+    if block.len() < length {
+        return Err(HpackError::from("unexpected end of block"));
+    }
+
+    let (data, rest) = block.split_at(length);
+    *block = rest;
+
+    let data = match huffman {
+        true => Huffman::get().decode(data)?,
+        false => data.to_vec(),
+    };
+
+    String::from_utf8(data).or(Err(HpackError::from("header is not valid utf-8")))
+}
+
+#[derive(Debug, Clone)]
+struct HpackError {
+    msg: String,
+}
+
+impl From<&str> for HpackError {
+    fn from(msg: &str) -> Self {
+        HpackError {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for HpackError {
+    fn from(msg: String) -> Self {
+        HpackError { msg }
+    }
+}
+
+impl fmt::Display for HpackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "hpack: {}", self.msg)
+    }
+}
+
+impl Error for HpackError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..(i + 2)], 16).unwrap())
+            .collect()
+    }
+
+    /// The (name, value) pairs of `headers`.
+    fn headers(headers: &[(&str, &str)]) -> Vec<(String, String)> {
+        headers
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn integers() {
+        // The integers of RFC 7541 (Appendix C.1).
+        for (n, bits, bytes) in [
+            (10, 5, &[0x0a][..]),
+            (1337, 5, &[0x1f, 0x9a, 0x0a]),
+            (42, 8, &[0x2a]),
+        ] {
+            let mut block = Vec::new();
+            prefix(&mut block, n, bits, 0x00);
+            assert_eq!(block, bytes);
+
+            let mut block = bytes;
+            assert_eq!(integer(&mut block, bits).unwrap(), n);
+            assert!(block.is_empty());
+        }
+    }
+
+    #[test]
+    fn decode_literals() {
+        // The literals of RFC 7541 (Appendix C.2).
+        let mut decoder = Decoder::new(4096);
+        let block = hex("400a637573746f6d2d6b65790d637573746f6d2d686561646572");
+        assert_eq!(
+            decoder.decode(&block).unwrap(),
+            headers(&[("custom-key", "custom-header")])
+        );
+        assert_eq!(decoder.size, 55);
+
+        // Without indexing, and never indexed.
+        for (block, name, value) in [
+            ("040c2f73616d706c652f70617468", ":path", "/sample/path"),
+            ("100870617373776f726406736563726574", "password", "secret"),
+        ] {
+            let mut decoder = Decoder::new(4096);
+            assert_eq!(
+                decoder.decode(&hex(block)).unwrap(),
+                headers(&[(name, value)])
+            );
+            assert!(decoder.table.is_empty());
+        }
+
+        let mut decoder = Decoder::new(4096);
+        assert_eq!(
+            decoder.decode(&hex("82")).unwrap(),
+            headers(&[(":method", "GET")])
+        );
+        assert!(decoder.table.is_empty());
+    }
+
+    #[test]
+    fn decode_requests_without_huffman() {
+        // The requests of RFC 7541 (Appendix C.3), without Huffman coding.
+        let mut decoder = Decoder::new(4096);
+
+        let block = hex("828684410f7777772e6578616d706c652e636f6d");
+        assert_eq!(
+            decoder.decode(&block).unwrap(),
+            headers(&[
+                (":method", "GET"),
+                (":scheme", "http"),
+                (":path", "/"),
+                (":authority", "www.example.com"),
+            ])
+        );
+        assert_eq!(decoder.size, 57);
+
+        let block = hex("828684be58086e6f2d6361636865");
+        assert_eq!(
+            decoder.decode(&block).unwrap(),
+            headers(&[
+                (":method", "GET"),
+                (":scheme", "http"),
+                (":path", "/"),
+                (":authority", "www.example.com"),
+                ("cache-control", "no-cache"),
+            ])
+        );
+        assert_eq!(decoder.size, 110);
+
+        let block = hex("828785bf400a637573746f6d2d6b65790c637573746f6d2d76616c7565");
+        assert_eq!(
+            decoder.decode(&block).unwrap(),
+            headers(&[
+                (":method", "GET"),
+                (":scheme", "https"),
+                (":path", "/index.html"),
+                (":authority", "www.example.com"),
+                ("custom-key", "custom-value"),
+            ])
+        );
+        assert_eq!(decoder.size, 164);
+    }
+
+    #[test]
+    fn decode_responses() {
+        // The responses of RFC 7541 (Appendix C.5 and C.6), without and with
+        // Huffman coding, which evict entries of a table of 256 bytes.
+        let first = headers(&[
+            (":status", "302"),
+            ("cache-control", "private"),
+            ("date", "Mon, 21 Oct 2013 20:13:21 GMT"),
+            ("location", "https://www.example.com"),
+        ]);
+
+        let mut second = first.clone();
+        second[0].1 = String::from("307");
+
+        let third = headers(&[
+            (":status", "200"),
+            ("cache-control", "private"),
+            ("date", "Mon, 21 Oct 2013 20:13:22 GMT"),
+            ("location", "https://www.example.com"),
+            ("content-encoding", "gzip"),
+            (
+                "set-cookie",
+                "foo=ASDJKHQKBZXOQWEOPIUAXQWEOIU; max-age=3600; version=1",
+            ),
+        ]);
+
+        for blocks in [
+            [
+                "4803333032580770726976617465611d4d6f6e2c203231204f637420323031332032303a31333a323120474d546e1768747470733a2f2f7777772e6578616d706c652e636f6d",
+                "4803333037c1c0bf",
+                "88c1611d4d6f6e2c203231204f637420323031332032303a31333a323220474d54c05a04677a69707738666f6f3d4153444a4b48514b425a584f5157454f50495541585157454f49553b206d61782d6167653d333630303b2076657273696f6e3d31",
+            ],
+            [
+                "488264025885aec3771a4b6196d07abe941054d444a8200595040b8166e082a62d1bff6e919d29ad171863c78f0b97c8e9ae82ae43d3",
+                "4883640effc1c0bf",
+                "88c16196d07abe941054d444a8200595040b8166e084a62d1bffc05a839bd9ab77ad94e7821dd7f2e6c7b335dfdfcd5b3960d5af27087f3672c1ab270fb5291f9587316065c003ed4ee5b1063d5007",
+            ],
+        ] {
+            let mut decoder = Decoder::new(256);
+
+            for (block, expected) in blocks.iter().zip([&first, &second, &third]) {
+                assert_eq!(&decoder.decode(&hex(block)).unwrap(), expected);
+            }
+
+            assert_eq!(decoder.size, 215);
+            assert_eq!(
+                decoder.table,
+                headers(&[
+                    (
+                        "set-cookie",
+                        "foo=ASDJKHQKBZXOQWEOPIUAXQWEOIU; max-age=3600; version=1",
+                    ),
+                    ("content-encoding", "gzip"),
+                    ("date", "Mon, 21 Oct 2013 20:13:22 GMT"),
+                ])
+            );
+        }
+    }
+
+    #[test]
+    fn decode_requests() {
+        // The requests of RFC 7541 (Appendix C.4), with Huffman coding.
+        let mut decoder = Decoder::new(4096);
+
+        let headers = decoder
+            .decode(&hex("828684418cf1e3c2e5f23a6ba0ab90f4ff"))
+            .unwrap();
+        assert_eq!(headers[3], (":authority".into(), "www.example.com".into()));
+
+        let headers = decoder.decode(&hex("828684be5886a8eb10649cbf")).unwrap();
+        assert_eq!(headers[3], (":authority".into(), "www.example.com".into()));
+        assert_eq!(headers[4], ("cache-control".into(), "no-cache".into()));
+
+        let headers = decoder
+            .decode(&hex("828785bf408825a849e95ba97d7f8925a849e95bb8e8b4bf"))
+            .unwrap();
+        assert_eq!(headers[1], (":scheme".into(), "https".into()));
+        assert_eq!(headers[3], (":authority".into(), "www.example.com".into()));
+        assert_eq!(headers[4], ("custom-key".into(), "custom-value".into()));
+        assert_eq!(decoder.size, 164);
+
+        let block = encode(&[("grpc-status", "0")]);
+        assert_eq!(
+            Decoder::new(4096).decode(&block).unwrap(),
+            vec![("grpc-status".into(), "0".into())]
+        );
+    }
+}
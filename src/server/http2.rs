@@ -0,0 +1,181 @@
+//! HTTP/2 framing (RFC 9113).
+//!
+//! An HTTP/2 connection is a sequence of frames, each of a type, flags, and
+//! the stream it belongs to. Only the framing is handled here; the state of
+//! the connection and its streams is kept by the [`Server`](super::Server).
+
+use std::error::Error;
+use std::fmt;
+use std::io::{ErrorKind, Read, Write};
+
+/// The preface a client starts each connection with.
+pub const PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+
+/// The largest frame payload allowed unless changed by the settings.
+///
+/// This is also the least a peer may change it to.
+pub const MAX_FRAME_SIZE: usize = 16384;
+
+/// The largest frame payload a peer may change the settings to.
+pub const MAX_FRAME_SIZE_LIMIT: usize = (1 << 24) - 1;
+
+/// The largest flow-control window.
+pub const MAX_WINDOW: i64 = (1 << 31) - 1;
+
+// The type of each frame.
+pub const DATA: u8 = 0x0;
+pub const HEADERS: u8 = 0x1;
+pub const RST_STREAM: u8 = 0x3;
+pub const SETTINGS: u8 = 0x4;
+pub const PUSH_PROMISE: u8 = 0x5;
+pub const PING: u8 = 0x6;
+pub const GOAWAY: u8 = 0x7;
+pub const WINDOW_UPDATE: u8 = 0x8;
+pub const CONTINUATION: u8 = 0x9;
+
+// The flags of a frame.
+pub const END_STREAM: u8 = 0x1;
+pub const ACK: u8 = 0x1;
+pub const END_HEADERS: u8 = 0x4;
+pub const PADDED: u8 = 0x8;
+pub const PRIORITY: u8 = 0x20;
+
+// The identifier of each setting.
+pub const SETTINGS_HEADER_TABLE_SIZE: u16 = 0x1;
+pub const SETTINGS_INITIAL_WINDOW_SIZE: u16 = 0x4;
+pub const SETTINGS_MAX_FRAME_SIZE: u16 = 0x5;
+
+// The code of each error.
+pub const NO_ERROR: u32 = 0x0;
+pub const PROTOCOL_ERROR: u32 = 0x1;
+pub const FLOW_CONTROL_ERROR: u32 = 0x3;
+pub const FRAME_SIZE_ERROR: u32 = 0x6;
+pub const ENHANCE_YOUR_CALM: u32 = 0xb;
+
+/// A frame of a connection.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    pub kind: u8,
+    pub flags: u8,
+
+    /// The identifier of the stream, or zero for the connection itself.
+    pub stream: u32,
+
+    pub payload: Vec<u8>,
+}
+
+impl Frame {
+    /// Create a new [`Frame`].
+    pub fn new(kind: u8, flags: u8, stream: u32, payload: Vec<u8>) -> Self {
+        Frame {
+            kind,
+            flags,
+            stream,
+            payload,
+        }
+    }
+
+    /// Read the next [`Frame`] of `reader`, if any.
+    ///
+    /// A frame with a payload larger than `limit` is rejected.
+    pub fn read(reader: &mut impl Read, limit: usize) -> Result<Option<Self>, Box<dyn Error>> {
+        let mut header = [0; 9];
+
+        match reader.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(Box::new(e)),
+        }
+
+        let length = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+
+        if length > limit {
+            return Err(Box::new(Http2Error::new(
+                FRAME_SIZE_ERROR,
+                format!("frame of {} bytes exceeds the maximum", length),
+            )));
+        }
+
+        let mut payload = vec![0; length];
+        reader.read_exact(&mut payload)?;
+
+        Ok(Some(Frame {
+            kind: header[3],
+            flags: header[4],
+            stream: u32::from_be_bytes([header[5], header[6], header[7], header[8]]) & 0x7fffffff,
+            payload,
+        }))
+    }
+
+    /// Write the [`Frame`] to `writer`.
+    pub fn write(&self, writer: &mut impl Write) -> Result<(), Box<dyn Error>> {
+        let length = (self.payload.len() as u32).to_be_bytes();
+
+        let mut bytes = Vec::with_capacity(9 + self.payload.len());
+        bytes.extend(&length[1..]);
+        bytes.extend([self.kind, self.flags]);
+        bytes.extend(self.stream.to_be_bytes());
+        bytes.extend(&self.payload);
+
+        writer.write_all(&bytes)?;
+
+        Ok(())
+    }
+
+    /// The contents of a DATA or HEADERS frame, without its padding or
+    /// priority (of a HEADERS frame).
+    pub fn contents(&self) -> Result<&[u8], Box<dyn Error>> {
+        let mut contents = self.payload.as_slice();
+        let mut padding = 0;
+
+        if self.flags & PADDED != 0 {
+            let (length, rest) = contents
+                .split_first()
+                .ok_or(Http2Error::new(PROTOCOL_ERROR, "malformed padding"))?;
+
+            padding = *length as usize;
+            contents = rest;
+        }
+
+        if self.kind == HEADERS && self.flags & PRIORITY != 0 {
+            contents = contents
+                .get(5..)
+                .ok_or(Http2Error::new(PROTOCOL_ERROR, "malformed priority"))?;
+        }
+
+        match contents.len().checked_sub(padding) {
+            Some(length) => Ok(&contents[..length]),
+            None => Err(Box::new(Http2Error::new(
+                PROTOCOL_ERROR,
+                "malformed padding",
+            ))),
+        }
+    }
+}
+
+/// A violation of the protocol that ends the connection.
+#[derive(Debug, Clone)]
+pub struct Http2Error {
+    /// The code of the error sent to the peer.
+    pub code: u32,
+
+    msg: String,
+}
+
+impl Http2Error {
+    /// Create a new [`Http2Error`] of `code`.
+    pub fn new(code: u32, msg: impl Into<String>) -> Self {
+        Http2Error {
+            code,
+            msg: msg.into(),
+        }
+    }
+}
+
+impl fmt::Display for Http2Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "http2: {}", self.msg)
+    }
+}
+
+impl Error for Http2Error {}
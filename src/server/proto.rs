@@ -0,0 +1,318 @@
+//! The Protocol Buffers messages of the gRPC service.
+//!
+//! The messages are few and small; therefore, they are encoded and decoded by
+//! hand following the wire format rather than generated from their definition,
+//! which is as follows:
+//!
+//! ```proto
+//! syntax = "proto3";
+//!
+//! package strem;
+//!
+//! service Monitor {
+//!   rpc Search(stream SearchRequest) returns (stream Match);
+//! }
+//!
+//! message Query {
+//!   string id = 1;
+//!   string pattern = 2;
+//! }
+//!
+//! message SearchRequest {
+//!   repeated Query queries = 1;
+//!   bytes datastream = 2;
+//! }
+//!
+//! message Match {
+//!   string query = 1;
+//!   uint64 start = 2;
+//!   uint64 end = 3;
+//! }
+//! ```
+
+use std::error::Error;
+use std::fmt;
+
+/// The path of the method that searches a stream of frames.
+pub const SEARCH: &str = "/strem.Monitor/Search";
+
+/// A persistent query of a search.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Query {
+    pub id: String,
+    pub pattern: String,
+}
+
+impl Query {
+    fn decode(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let mut query = Query::default();
+
+        for field in Fields(bytes) {
+            match field? {
+                (1, Value::Bytes(b)) => query.id = self::string(b)?,
+                (2, Value::Bytes(b)) => query.pattern = self::string(b)?,
+                _ => {}
+            }
+        }
+
+        Ok(query)
+    }
+}
+
+/// A request of a search.
+///
+/// The queries are set by the first request of a search, and each request may
+/// carry the next frames as one or more STREM documents (i.e., JSON).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchRequest {
+    pub queries: Vec<Query>,
+    pub datastream: Vec<u8>,
+}
+
+impl SearchRequest {
+    /// Decode a [`SearchRequest`] of the wire format.
+    pub fn decode(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let mut request = SearchRequest::default();
+
+        for field in Fields(bytes) {
+            match field? {
+                (1, Value::Bytes(b)) => request.queries.push(Query::decode(b)?),
+                (2, Value::Bytes(b)) => request.datastream.extend(b),
+                _ => {}
+            }
+        }
+
+        Ok(request)
+    }
+
+    /// Encode the [`SearchRequest`] in the wire format.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+
+        for query in self.queries.iter() {
+            let mut nested = Vec::new();
+            self::bytes(&mut nested, 1, query.id.as_bytes());
+            self::bytes(&mut nested, 2, query.pattern.as_bytes());
+
+            self::bytes(&mut bytes, 1, &nested);
+        }
+
+        self::bytes(&mut bytes, 2, &self.datastream);
+
+        bytes
+    }
+}
+
+/// A match of a query, as the indices of its frames (i.e., `[start, end)`).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Match {
+    pub query: String,
+    pub start: u64,
+    pub end: u64,
+}
+
+impl Match {
+    /// Decode a [`Match`] of the wire format.
+    pub fn decode(bytes: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let mut m = Match::default();
+
+        for field in Fields(bytes) {
+            match field? {
+                (1, Value::Bytes(b)) => m.query = self::string(b)?,
+                (2, Value::Varint(n)) => m.start = n,
+                (3, Value::Varint(n)) => m.end = n,
+                _ => {}
+            }
+        }
+
+        Ok(m)
+    }
+
+    /// Encode the [`Match`] in the wire format.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+
+        self::bytes(&mut bytes, 1, self.query.as_bytes());
+        self::varint(&mut bytes, 2, self.start);
+        self::varint(&mut bytes, 3, self.end);
+
+        bytes
+    }
+}
+
+/// The value of a field.
+enum Value<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+
+    /// A fixed-size value, which no message has.
+    Fixed,
+}
+
+/// The fields of an encoded message, in order.
+struct Fields<'a>(&'a [u8]);
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = Result<(u64, Value<'a>), ProtoError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0.is_empty() {
+            return None;
+        }
+
+        let field = (|| {
+            let key = self::decode(&mut self.0)?;
+
+            let value = match key & 0x7 {
+                0 => Value::Varint(self::decode(&mut self.0)?),
+                2 => {
+                    let length = self::decode(&mut self.0)? as usize;
+
+                    if self.0.len() < length {
+                        return Err(ProtoError::from("unexpected end of message"));
+                    }
+
+                    let (value, rest) = self.0.split_at(length);
+                    self.0 = rest;
+
+                    Value::Bytes(value)
+                }
+                kind @ (1 | 5) => {
+                    let length = if kind == 1 { 8 } else { 4 };
+
+                    self.0 = self
+                        .0
+                        .get(length..)
+                        .ok_or(ProtoError::from("unexpected end of message"))?;
+
+                    Value::Fixed
+                }
+                kind => {
+                    return Err(ProtoError::from(format!(
+                        "unsupported wire type `{}`",
+                        kind
+                    )))
+                }
+            };
+
+            Ok((key >> 3, value))
+        })();
+
+        // Stop after an error, as the remainder cannot be read.
+        if field.is_err() {
+            self.0 = &[];
+        }
+
+        Some(field)
+    }
+}
+
+/// Decode a variable-length integer.
+fn decode(bytes: &mut &[u8]) -> Result<u64, ProtoError> {
+    let mut n = 0;
+
+    for shift in (0..64).step_by(7) {
+        let (byte, rest) = bytes
+            .split_first()
+            .ok_or(ProtoError::from("unexpected end of message"))?;
+        *bytes = rest;
+
+        n |= ((byte & 0x7f) as u64) << shift;
+
+        if byte & 0x80 == 0 {
+            return Ok(n);
+        }
+    }
+
+    Err(ProtoError::from("integer overflow"))
+}
+
+/// Encode a variable-length integer.
+fn encode(bytes: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        bytes.push((n as u8 & 0x7f) | 0x80);
+        n >>= 7;
+    }
+
+    bytes.push(n as u8);
+}
+
+/// Encode an integer field, unless of the default value.
+fn varint(bytes: &mut Vec<u8>, field: u64, n: u64) {
+    if n != 0 {
+        self::encode(bytes, field << 3);
+        self::encode(bytes, n);
+    }
+}
+
+/// Encode a length-delimited field, unless empty.
+fn bytes(bytes: &mut Vec<u8>, field: u64, value: &[u8]) {
+    if !value.is_empty() {
+        self::encode(bytes, (field << 3) | 2);
+        self::encode(bytes, value.len() as u64);
+        bytes.extend(value);
+    }
+}
+
+fn string(bytes: &[u8]) -> Result<String, ProtoError> {
+    String::from_utf8(bytes.to_vec()).or(Err(ProtoError::from("string is not valid utf-8")))
+}
+
+#[derive(Debug, Clone)]
+struct ProtoError {
+    msg: String,
+}
+
+impl From<&str> for ProtoError {
+    fn from(msg: &str) -> Self {
+        ProtoError {
+            msg: msg.to_string(),
+        }
+    }
+}
+
+impl From<String> for ProtoError {
+    fn from(msg: String) -> Self {
+        ProtoError { msg }
+    }
+}
+
+impl fmt::Display for ProtoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "proto: {}", self.msg)
+    }
+}
+
+impl Error for ProtoError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrip() {
+        let request = SearchRequest {
+            queries: vec![Query {
+                id: "cars".to_string(),
+                pattern: "[[:car:]]{2,}".to_string(),
+            }],
+            datastream: br#"{"version":"0.2.0","frames":[]}"#.to_vec(),
+        };
+
+        assert_eq!(SearchRequest::decode(&request.encode()).unwrap(), request);
+
+        let m = Match {
+            query: "cars".to_string(),
+            start: 300,
+            end: 302,
+        };
+
+        assert_eq!(
+            m.encode()[..8],
+            [0x0a, 0x04, b'c', b'a', b'r', b's', 0x10, 0xac]
+        );
+        assert_eq!(Match::decode(&m.encode()).unwrap(), m);
+
+        assert!(SearchRequest::decode(&[0x0a, 0x05, 0x0a]).is_err());
+    }
+}
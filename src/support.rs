@@ -0,0 +1,48 @@
+//! Builders of the data shared by the tests of the crate.
+
+use crate::datastream::frame::sample::detections::bbox::region::{aa, Point};
+use crate::datastream::frame::sample::detections::bbox::BoundingBox;
+use crate::datastream::frame::sample::detections::label::Label;
+use crate::datastream::frame::sample::detections::{Annotation, DetectionRecord};
+use crate::datastream::frame::sample::Sample;
+use crate::datastream::frame::Frame;
+
+/// An object of a frame as its label, instance identifier, and position along
+/// the x-axis.
+pub type Object<'a> = (&'a str, Option<&'a str>, f64);
+
+/// Create a frame of the objects detected by a single camera.
+///
+/// Each object is a 2x2 box centered on its position with a score of 1.0.
+pub fn frame(index: usize, objects: &[Object]) -> Frame {
+    let mut record = DetectionRecord::new(String::from("cam"), None);
+
+    for (label, id, x) in objects {
+        let mut annotation = Annotation::new(
+            Label::from(*label),
+            1.0,
+            BoundingBox::AxisAligned(aa::Region::new(Point::new(*x, 0.0), 2.0, 2.0)),
+        );
+        annotation.instance_id = id.map(String::from);
+
+        record
+            .annotations
+            .entry(Label::from(*label))
+            .or_default()
+            .push(annotation);
+    }
+
+    let mut frame = Frame::new(index);
+    frame.samples.push(Sample::ObjectDetection(record));
+
+    frame
+}
+
+/// Create a frame for each label, with a single object of that label.
+pub fn frames(labels: &[&str]) -> Vec<Frame> {
+    labels
+        .iter()
+        .enumerate()
+        .map(|(i, label)| self::frame(i, &[(label, None, 0.0)]))
+        .collect()
+}
@@ -3,7 +3,16 @@
 //! This Intermediate Representation (IR) of the SpRE is a necessary step to
 //! perform matching with the underlying library.
 
-use crate::compiler::ir::{ast::SpatialFormula, Node};
+use crate::compiler::ir::ast::{Classes, OperandKind, SpatialFormula};
+use crate::compiler::ir::ops::{Operator, S4mOperatorKind, SpatialOperatorKind};
+use crate::compiler::ir::{Node, Tree};
+use crate::datastream::frame::sample::detections::label::Label;
+
+/// A unique identifier of a spatial formula.
+///
+/// The matcher is responsible for encoding this identifier into the input
+/// accepted by the underlying automaton.
+pub type Symbol = usize;
 
 /// A symbolically-linked spatial formula.
 ///
@@ -11,12 +20,12 @@ use crate::compiler::ir::{ast::SpatialFormula, Node};
 /// performing matching.
 #[derive(Debug)]
 pub struct SymbolicFormula {
-    pub symbol: char,
+    pub symbol: Symbol,
     pub formula: SpatialFormula,
 }
 
 impl SymbolicFormula {
-    pub fn new(symbol: char, formula: SpatialFormula) -> Self {
+    pub fn new(symbol: Symbol, formula: SpatialFormula) -> Self {
         Self { symbol, formula }
     }
 }
@@ -27,38 +36,94 @@ impl SymbolicFormula {
 /// alternation, concatenation, etc); and each operand is a [`SymbolicFormula`].
 #[derive(Debug)]
 pub struct SymbolicAbstractSyntaxTree {
-    pub root: Option<Node<SymbolicFormula>>,
+    pub root: Option<Tree<SymbolicFormula>>,
 }
 
 impl SymbolicAbstractSyntaxTree {
-    pub fn new(root: Option<Node<SymbolicFormula>>) -> Self {
+    pub fn new(root: Option<Tree<SymbolicFormula>>) -> Self {
         Self { root }
     }
 
     /// From the symbolic-AST, return the set of spatial formulas.
+    ///
+    /// The formulas are listed in the order they appear in the pattern.
     pub fn fmap(&self) -> Vec<&SymbolicFormula> {
         if let Some(root) = &self.root {
-            return SymbolicAbstractSyntaxTree::fmapit(root);
+            return root.operands().collect();
         }
 
         Vec::new()
     }
 
-    /// The recursive helper function to generate the list of spatial formulas.
+    /// From the symbolic-AST, return the set of class labels.
+    ///
+    /// The labels are listed in the order they first appear in the pattern.
+    pub fn labels(&self) -> Vec<Label> {
+        let mut labels = Vec::new();
+
+        for formula in self.fmap() {
+            for operand in formula.formula.operands() {
+                if let OperandKind::Symbol(label) = operand {
+                    if !labels.contains(label) {
+                        labels.push(label.clone());
+                    }
+                }
+            }
+        }
+
+        labels
+    }
+
+    /// From the symbolic-AST, return the sets of class labels.
+    ///
+    /// The sets are listed in the order they first appear in the pattern.
+    pub fn classes(&self) -> Vec<&Classes> {
+        let mut classes: Vec<&Classes> = Vec::new();
+
+        for formula in self.fmap() {
+            for operand in formula.formula.operands() {
+                if let OperandKind::Classes(c) = operand {
+                    if !classes.iter().any(|x| x.source == c.source) {
+                        classes.push(c);
+                    }
+                }
+            }
+        }
+
+        classes
+    }
+
+    /// From the symbolic-AST, return the names of the functions called.
     ///
-    /// The result is a list of [`SymbolicFormula`] found on the symbolic-AST.
-    /// Since the AST operands are [`SymbolicFormula`], for each operand, the
-    /// tree is returned.
-    fn fmapit(node: &Node<SymbolicFormula>) -> Vec<&SymbolicFormula> {
-        match node {
-            Node::Operand(sformula) => vec![sformula],
-            Node::UnaryExpr { child, .. } => SymbolicAbstractSyntaxTree::fmapit(child),
-            Node::BinaryExpr { lhs, rhs, .. } => {
-                let mut formulas = SymbolicAbstractSyntaxTree::fmapit(lhs);
-                formulas.extend(SymbolicAbstractSyntaxTree::fmapit(rhs));
-
-                formulas
+    /// The names are listed in the order they first appear in the pattern.
+    pub fn functions(&self) -> Vec<&str> {
+        let mut functions: Vec<&str> = Vec::new();
+
+        for formula in self.fmap() {
+            for node in formula.formula.nodes() {
+                if let Node::UnaryExpr { op, .. } | Node::BinaryExpr { op, .. } = node {
+                    if let Operator::SpatialOperator(SpatialOperatorKind::S4mOperator(
+                        S4mOperatorKind::Function(name),
+                    )) = op
+                    {
+                        if !functions.contains(&name.as_str()) {
+                            functions.push(name);
+                        }
+                    }
+                }
             }
         }
+
+        functions
+    }
+
+    /// Check if the pattern uses the motion of a tracked object.
+    ///
+    /// The motion (e.g., `@speed`) is only found as the frames are imported;
+    /// therefore, the objects are only tracked if it is used.
+    pub fn motion(&self) -> bool {
+        self.functions()
+            .iter()
+            .any(|name| matches!(*name, "dx" | "dy" | "speed"))
     }
 }